@@ -0,0 +1,166 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+//! Train a Connect Four policy and value network by self-play, play against it, and benchmark
+//! it against vanilla UCT.
+
+use std::sync::Arc;
+
+use alphazero_connect_four::benchmark::{compare_with_uct, uct_config};
+use alphazero_connect_four::pipeline::{run_pipeline, PipelineConfig};
+use alphazero_connect_four::puct::SearchConfig;
+use alphazero_connect_four::PolicyValueNetwork;
+use mcts_connect_four::{get_best_puct_move, shared_rng, Action, MctsPlayer, Player, State};
+
+const WIDTH: usize = 7;
+const HEIGHT: usize = 6;
+const DEFAULT_MODEL: &str = "alphazero-connect-four-model";
+
+// Usage:
+//
+//   alphazero-connect-four train [iterations] [model directory]
+//   alphazero-connect-four play [--nn-guided [model directory]]
+//   alphazero-connect-four bench [model directory] [games]
+//
+// train runs the self-play pipeline, 10 iterations by default, and writes the best network to
+// policy.json and value.json in the model directory, ./alphazero-connect-four-model by default.
+// play is a game against the computer, which uses vanilla UCT unless --nn-guided is given, in
+// which case it uses PUCT guided by the trained network. bench plays the NN-guided search
+// against vanilla UCT and prints the results.
+fn main() {
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        None | Some("train") => train(args),
+        Some("play") => play(args),
+        Some("bench") => bench(args),
+        Some(other) => panic!("unknown command {other:?}, expected train, play or bench"),
+    }
+}
+
+fn train(mut args: impl Iterator<Item = String>) {
+    let iterations: usize = args
+        .next()
+        .map_or(10, |arg| arg.parse().expect("invalid iterations"));
+    let output = args.next().unwrap_or_else(|| DEFAULT_MODEL.to_string());
+    let config = PipelineConfig {
+        width: WIDTH,
+        height: HEIGHT,
+        iterations,
+        ..PipelineConfig::default()
+    };
+
+    println!(
+        "{:>9} {:>9} {:>8} {:>11} {:>10} {:>11} {:>8}",
+        "iteration", "examples", "buffer", "policy loss", "value loss", "arena W-L-D", "accepted"
+    );
+    let best = run_pipeline(&config, |report| {
+        println!(
+            "{:>9} {:>9} {:>8} {:>11.4} {:>10.4} {:>11} {:>8}",
+            report.iteration,
+            report.new_examples,
+            report.buffer_size,
+            report.losses.policy,
+            report.losses.value,
+            format!(
+                "{}-{}-{}",
+                report.arena.wins, report.arena.losses, report.arena.draws
+            ),
+            report.accepted
+        );
+    });
+
+    std::fs::create_dir_all(&output).expect("failed to create the output directory");
+    best.save(&output).expect("failed to save the network");
+    println!("saved the best network to {}", output);
+}
+
+fn load(directory: &str) -> PolicyValueNetwork {
+    PolicyValueNetwork::load(directory, WIDTH, HEIGHT)
+        .unwrap_or_else(|e| panic!("failed to load the network from {directory}: {e}"))
+}
+
+fn play(mut args: impl Iterator<Item = String>) {
+    let network = match args.next().as_deref() {
+        Some("--nn-guided") => Some(load(
+            &args.next().unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+        )),
+        Some(other) => panic!("unknown flag {other:?}"),
+        None => None,
+    };
+    let rng = shared_rng(42);
+    let mut uct = MctsPlayer::new(uct_config(300), Arc::clone(&rng));
+    let puct = SearchConfig::default();
+    let human = Player::Player2;
+    let mut state = State::new(WIDTH, HEIGHT, Player::Player1, Player::Player1);
+    while connect_four_logic::is_terminal_position(&state.board)
+        == connect_four_logic::TerminalPosition::IsNotTerminal
+    {
+        println!("{}", &state);
+        let mv = if state.turn == human {
+            // i or p (insert or pop) and a column, space delimited.
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input).unwrap();
+            let mut input = input.split_whitespace();
+            let move_type = match input.next() {
+                Some("i") => connect_four_logic::MoveType::Insert,
+                Some("p") => connect_four_logic::MoveType::Pop,
+                _ => panic!("invalid action"),
+            };
+            let column = input.next().unwrap().parse::<usize>().unwrap();
+            connect_four_logic::Move { move_type, column }
+        } else {
+            state.who_am_i = state.turn;
+            match &network {
+                Some(network) => {
+                    get_best_puct_move(&state, network, &puct, Arc::clone(&rng)).actual_move
+                }
+                None => uct.best_move(&state).actual_move,
+            }
+        };
+        state.apply_move(&Action(mv));
+        uct.apply_move(mv);
+    }
+    println!("{}", &state.board);
+    match connect_four_logic::is_terminal_position(&state.board) {
+        connect_four_logic::TerminalPosition::IsTerminalWin(winner) => {
+            println!("winner: {:?}", winner)
+        }
+        _ => println!("draw"),
+    }
+}
+
+fn bench(mut args: impl Iterator<Item = String>) {
+    let network = load(&args.next().unwrap_or_else(|| DEFAULT_MODEL.to_string()));
+    let games: usize = args
+        .next()
+        .map_or(10, |arg| arg.parse().expect("invalid games"));
+    let puct = SearchConfig::default();
+    let uct = uct_config(300);
+    println!(
+        "NN-guided PUCT ({} simulations) vs UCT ({} iterations, {} playouts each), {} games",
+        puct.simulations, uct.iterations, uct.playouts_per_simulation, games
+    );
+    let start = std::time::Instant::now();
+    let result = compare_with_uct(&network, &puct, &uct, WIDTH, HEIGHT, games, 100, 42);
+    println!(
+        "wins {} losses {} draws {} score {:.2} in {:.1?}",
+        result.wins,
+        result.losses,
+        result.draws,
+        result.score(),
+        start.elapsed()
+    );
+}
@@ -20,8 +20,55 @@
 //!
 //! For more information see chapter 2 "Intelligent Agents" exercises 11 and 12.
 
-use vacuum_cleaner::vacuum_world::{ReflexVacuumAgent, VacuumWorldEnvironment};
-use vacuum_cleaner::Simulation;
+use std::time::Duration;
+
+use vacuum_cleaner::render::AsciiRenderer;
+use vacuum_cleaner::vacuum_world::{
+    ReflexVacuumAgent, SweepingVacuumAgent, VaccuumWorldLocalPercept, VacuumWorldAction,
+    VacuumWorldEnvironment,
+};
+use vacuum_cleaner::{Agent, Simulation};
+
+/// How to show the simulation while it runs.
+enum RenderMode {
+    /// Only print the final score.
+    None,
+
+    /// Print the world after every time step.
+    Steps,
+
+    /// Redraw the world in place after every time step, pausing for the delay.
+    Animate(Duration),
+}
+
+fn run<_Agent>(
+    agent: _Agent,
+    height: i32,
+    width: i32,
+    time_steps: i32,
+    render_mode: &RenderMode,
+) -> i32
+where
+    _Agent: Agent<Action = VacuumWorldAction, Percept = VaccuumWorldLocalPercept>,
+{
+    let environment = VacuumWorldEnvironment::new(height, width);
+    let mut simulation = Simulation::new(environment, agent, time_steps);
+    let mut renderer = match render_mode {
+        RenderMode::None => None,
+        RenderMode::Steps => Some(AsciiRenderer::stdout()),
+        RenderMode::Animate(delay) => Some(AsciiRenderer::stdout().animated(*delay)),
+    };
+    match renderer.as_mut() {
+        None => simulation.run(),
+        Some(renderer) => {
+            renderer
+                .render_initial(simulation.environment())
+                .expect("failed to write to stdout");
+            simulation.run_with_observer(renderer);
+        }
+    }
+    simulation.score()
+}
 
 // Chapter 2 Intelligent Agents Exercises 11 and 12.
 //
@@ -32,16 +79,54 @@ use vacuum_cleaner::Simulation;
 // actuators, and environment characteristics (size, shape, dirt placement, etc.) can be changed
 // easily. (Note: for some choices of programming language and operating system there are already
 // implementations in the online code repository.)
+//
+// Usage: vacuum-agents [width] [height] [--render | --animate <delay_ms>]. The default is the
+// two-square world from Figure 2.2, which uses the reflex agent. Any other size uses the sweeping
+// agent because the reflex agent only understands two squares. --render prints the world after
+// every time step, and --animate redraws it in place with a delay between time steps.
 fn main() {
-    let agent = ReflexVacuumAgent::new();
-
-    let width = 2;
-    let height = 1;
-    let environment = VacuumWorldEnvironment::new(height, width);
-
+    let mut positional = vec![];
+    let mut render_mode = RenderMode::None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--render" => render_mode = RenderMode::Steps,
+            "--animate" => {
+                let delay_ms: u64 = args
+                    .next()
+                    .expect("--animate needs a delay in milliseconds")
+                    .parse()
+                    .expect("invalid delay");
+                render_mode = RenderMode::Animate(Duration::from_millis(delay_ms));
+            }
+            _ => positional.push(arg),
+        }
+    }
+    let mut positional = positional.into_iter();
+    let width: i32 = positional
+        .next()
+        .map_or(2, |arg| arg.parse().expect("invalid width"));
+    let height: i32 = positional
+        .next()
+        .map_or(1, |arg| arg.parse().expect("invalid height"));
     let time_steps = 1000;
-    let mut simulation = Simulation::new(environment, agent, time_steps);
 
-    simulation.run();
-    println!("score: {}", simulation.score());
+    let score = if width == 2 && height == 1 {
+        run(
+            ReflexVacuumAgent::new(),
+            height,
+            width,
+            time_steps,
+            &render_mode,
+        )
+    } else {
+        run(
+            SweepingVacuumAgent::new(height, width),
+            height,
+            width,
+            time_steps,
+            &render_mode,
+        )
+    };
+    println!("score: {}", score);
 }
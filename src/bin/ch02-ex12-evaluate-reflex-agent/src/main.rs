@@ -18,11 +18,57 @@
 
 //! Chapter 2, Exercise 12, Evaluate Reflex Agent.
 
+use vacuum_cleaner::evaluation::{evaluate_all_configs, Evaluation};
+use vacuum_cleaner::vacuum_world::{ReflexVacuumAgent, SquareState, SweepingVacuumAgent};
+
 // Exercise 12:
 //
 // Implement a simple reflex agent for the vacuum environment in Exercise 2.10. Run the environment
 // with this agent for all possible initial dirt configurations and agent locations. Record the
 // performance score for each configuration and the overall average score.
+//
+// Usage: ch02-ex12-evaluate-reflex-agent [width] [height]. The default is the two-square world,
+// which uses the reflex agent. Any other size uses the sweeping agent. There are
+// 2^(width * height) dirt configurations so keep the world small.
 fn main() {
-    println!("Hello, world!");
+    let mut args = std::env::args().skip(1);
+    let width: i32 = args
+        .next()
+        .map_or(2, |arg| arg.parse().expect("invalid width"));
+    let height: i32 = args
+        .next()
+        .map_or(1, |arg| arg.parse().expect("invalid height"));
+    let time_steps = 1000;
+    let evaluation: Evaluation = if width == 2 && height == 1 {
+        evaluate_all_configs(height, width, time_steps, ReflexVacuumAgent::new)
+    } else {
+        evaluate_all_configs(height, width, time_steps, || {
+            SweepingVacuumAgent::new(height, width)
+        })
+    };
+
+    let mut header = String::new();
+    for location in &evaluation.locations {
+        header.push_str(&format!(
+            "{:<8}",
+            format!("({},{})", location.x, location.y)
+        ));
+    }
+    println!("{}{:<8}{:>8}", header, "agent", "score");
+    for configuration in &evaluation.scores {
+        let mut row = String::new();
+        for square in &configuration.squares {
+            let square = match square {
+                SquareState::Clean => "Clean",
+                SquareState::Dirty => "Dirty",
+            };
+            row.push_str(&format!("{:<8}", square));
+        }
+        let agent = format!(
+            "({},{})",
+            configuration.agent_location.x, configuration.agent_location.y
+        );
+        println!("{}{:<8}{:>8}", row, agent, configuration.score);
+    }
+    println!("average score: {:.2}", evaluation.average_score());
 }
@@ -0,0 +1,80 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+#![warn(missing_docs)]
+
+//! Chapter 3, compare search algorithms on the 8-puzzle.
+
+use std::collections::BTreeMap;
+
+use search::bidirectional::bidirectional_breadth_first_search;
+use search::informed::astar_search;
+use search::sliding_puzzle::{Board, PuzzleHeuristic, SlidingPuzzle};
+use search::uninformed::breadth_first_search;
+
+// Reproduces the shape of Figure 3.26: the average number of nodes expanded by breadth-first,
+// bidirectional breadth-first, and A* search with the misplaced-tiles (h1) and Manhattan distance
+// (h2) heuristics, on random 8-puzzles grouped by the length of their optimal solution.
+//
+// Usage: ch03-compare-search [puzzles]. The default is 200 puzzles.
+fn main() {
+    let puzzles: u64 = std::env::args()
+        .nth(1)
+        .map_or(200, |arg| arg.parse().expect("invalid number of puzzles"));
+
+    // Expanded node counts for each algorithm, keyed by solution length.
+    let mut rows: BTreeMap<usize, Vec<[usize; 4]>> = BTreeMap::new();
+    for seed in 0..puzzles {
+        let board = Board::scrambled(3, 8 + (seed % 12) as usize, seed);
+        let manhattan = SlidingPuzzle::new(board);
+        let misplaced = manhattan
+            .clone()
+            .with_heuristic(PuzzleHeuristic::MisplacedTiles);
+
+        let astar_h2 = astar_search(&manhattan);
+        let length = astar_h2.solution().unwrap().len();
+        if length == 0 {
+            continue;
+        }
+        rows.entry(length).or_default().push([
+            breadth_first_search(&manhattan).stats.nodes_expanded,
+            bidirectional_breadth_first_search(&manhattan)
+                .stats
+                .nodes_expanded,
+            astar_search(&misplaced).stats.nodes_expanded,
+            astar_h2.stats.nodes_expanded,
+        ]);
+    }
+
+    println!(
+        "{:>6}{:>8}{:>10}{:>10}{:>10}{:>10}",
+        "depth", "puzzles", "BFS", "BiBFS", "A*(h1)", "A*(h2)"
+    );
+    for (length, counts) in rows {
+        let average = |i: usize| {
+            counts.iter().map(|count| count[i]).sum::<usize>() as f64 / counts.len() as f64
+        };
+        println!(
+            "{:>6}{:>8}{:>10.0}{:>10.0}{:>10.0}{:>10.0}",
+            length,
+            counts.len(),
+            average(0),
+            average(1),
+            average(2),
+            average(3)
+        );
+    }
+}
@@ -0,0 +1,107 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+#![warn(missing_docs)]
+
+//! Chapter 4, compare local search algorithms on n-queens and the traveling salesperson problem.
+
+use search::local::{
+    compare_local_search, ExponentialSchedule, LocalSearchAlgorithm, LocalSearchSummary,
+};
+use search::n_queens::NQueensLocal;
+use search::tsp::TravelingSalesperson;
+
+// Runs hill climbing, random-restart hill climbing, simulated annealing, and local and stochastic
+// beam search on the same problems, and prints how often each one found a solution, the mean and
+// best objective values, and the mean number of steps.
+//
+// Usage: ch04-compare-local-search [trials]. The default is 20 trials.
+fn main() {
+    let trials: usize = std::env::args()
+        .nth(1)
+        .map_or(20, |arg| arg.parse().expect("invalid number of trials"));
+
+    let queens = [
+        LocalSearchAlgorithm::HillClimbing,
+        LocalSearchAlgorithm::RandomRestartHillClimbing { max_restarts: 100 },
+        LocalSearchAlgorithm::SimulatedAnnealing(ExponentialSchedule {
+            initial: 2.0,
+            decay: 0.001,
+            limit: 10_000,
+        }),
+        LocalSearchAlgorithm::LocalBeam {
+            k: 10,
+            max_steps: 100,
+        },
+        LocalSearchAlgorithm::StochasticBeam {
+            k: 10,
+            temperature: 0.5,
+            max_steps: 100,
+        },
+    ];
+    println!("8-queens (value is minus the number of attacking pairs)");
+    print_summaries(&compare_local_search(
+        &NQueensLocal { n: 8 },
+        &queens,
+        trials,
+        0,
+    ));
+
+    // The optimal tour is unknown, so no trial counts as solved.
+    let tours = [
+        LocalSearchAlgorithm::HillClimbing,
+        LocalSearchAlgorithm::RandomRestartHillClimbing { max_restarts: 10 },
+        LocalSearchAlgorithm::SimulatedAnnealing(ExponentialSchedule {
+            initial: 0.5,
+            decay: 0.0005,
+            limit: 20_000,
+        }),
+        LocalSearchAlgorithm::LocalBeam {
+            k: 5,
+            max_steps: 1000,
+        },
+        LocalSearchAlgorithm::StochasticBeam {
+            k: 5,
+            temperature: 0.01,
+            max_steps: 200,
+        },
+    ];
+    println!();
+    println!("Traveling salesperson, 20 random cities (value is minus the tour length)");
+    print_summaries(&compare_local_search(
+        &TravelingSalesperson::random(20, 0),
+        &tours,
+        trials,
+        0,
+    ));
+}
+
+fn print_summaries(summaries: &[LocalSearchSummary]) {
+    println!(
+        "{:<26}{:>8}{:>12}{:>12}{:>12}",
+        "algorithm", "solved", "mean value", "best value", "mean steps"
+    );
+    for summary in summaries {
+        println!(
+            "{:<26}{:>8}{:>12.3}{:>12.3}{:>12.1}",
+            summary.name,
+            format!("{}/{}", summary.solved, summary.trials),
+            summary.mean_value,
+            summary.best_value,
+            summary.mean_steps
+        );
+    }
+}
@@ -0,0 +1,60 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+#![warn(missing_docs)]
+
+//! Chapter 6, solve a Sudoku puzzle as a CSP.
+
+use std::time::Instant;
+
+use csp::backtracking::{Inference, SolverConfig};
+use csp::sudoku::Sudoku;
+
+/// A puzzle that needs backtracking search as well as AC-3.
+const DEFAULT_PUZZLE: &str =
+    "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......";
+
+// Usage: ch06-sudoku [puzzle]. The puzzle is 81 cells row by row, using 1 to 9 for digits and 0
+// or . for empty cells. The solver runs AC-3 first, then backtracking search with the MRV, degree
+// and least-constraining-value heuristics, maintaining arc consistency.
+fn main() {
+    let puzzle: Sudoku = std::env::args()
+        .nth(1)
+        .as_deref()
+        .unwrap_or(DEFAULT_PUZZLE)
+        .parse()
+        .unwrap_or_else(|error| panic!("invalid puzzle: {}", error));
+    println!("{}", puzzle);
+
+    let start = Instant::now();
+    let (solution, stats) = puzzle.solve(SolverConfig {
+        inference: Inference::MaintainArcConsistency,
+        preprocess_with_ac3: true,
+        ..SolverConfig::default()
+    });
+    let elapsed = start.elapsed();
+
+    match solution {
+        Some(solution) => println!("{}", solution),
+        None => println!("no solution\n"),
+    }
+    println!(
+        "{} assignments, {} backtracks, {:.1} ms",
+        stats.assignments,
+        stats.backtracks,
+        elapsed.as_secs_f64() * 1000.0
+    );
+}
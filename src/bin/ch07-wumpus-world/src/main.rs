@@ -0,0 +1,52 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+#![warn(missing_docs)]
+
+//! Chapter 7, Wumpus World.
+
+use vacuum_cleaner::Simulation;
+use wumpus_world::agent::CautiousWumpusAgent;
+use wumpus_world::{GameOver, WumpusWorldEnvironment};
+
+// Run the cautious agent in random 4x4 caves, as described in section 7.2, and report how often
+// it comes back with the gold and its average score.
+//
+// Usage: ch07-wumpus-world [caves]. The default is 1000 caves.
+fn main() {
+    let caves: u64 = std::env::args()
+        .nth(1)
+        .map_or(1000, |arg| arg.parse().expect("invalid number of caves"));
+    let time_steps = 1000;
+
+    let mut total_score = 0;
+    let mut golds = 0;
+    for seed in 0..caves {
+        let mut simulation = Simulation::new(
+            WumpusWorldEnvironment::new_random(4, 0.2, seed),
+            CautiousWumpusAgent::new(),
+            time_steps,
+        );
+        let outcome = simulation.run_until(&mut GameOver);
+        total_score += outcome.score;
+        if simulation.environment().has_gold() {
+            golds += 1;
+        }
+    }
+    println!("caves: {}", caves);
+    println!("gold retrieved: {}", golds);
+    println!("average score: {:.2}", total_score as f64 / caves as f64);
+}
@@ -14,29 +14,91 @@
  * with this program. If not, see <https://www.gnu.org/licenses/>
  */
 
-use mcts_connect_four::{MctsConfig, Player, State};
-use rand::SeedableRng;
-use std::cell::RefCell;
-use std::rc::Rc;
+use graphics::{layout_mcts_tree, save_png, MctsStyle, RasterStyle, ReingoldTilfordLayout};
+use mcts_connect_four::{shared_rng, BestMctsMove, MctsConfig, MctsPlayer, Player, State};
+use std::path::{Path, PathBuf};
 
+/// Write the final search tree behind the CPU's `move_number`th move to `dir` as a PNG. Only
+/// the top few plies are drawn; deeper nodes are folded into summary nodes.
+fn dump_tree_png(dir: &Path, move_number: usize, best_move: &BestMctsMove) {
+    let Some(tree) = best_move
+        .debug_trees
+        .as_ref()
+        .and_then(|trees| trees.last())
+    else {
+        return;
+    };
+    let result = layout_mcts_tree(
+        tree,
+        &MctsStyle::default().with_max_depth(2),
+        &ReingoldTilfordLayout::new(0.25, 0.5, 1.0),
+    );
+    let path = dir.join(format!("move-{:03}.png", move_number));
+    if let Err(err) = save_png(&result, &RasterStyle::default(), |data| data.color, &path) {
+        println!("failed to write {}: {}", path.display(), err);
+    }
+}
+
+/// Print how far the search behind `best_move` got and the line of play it expects.
+fn print_search_info(best_move: &BestMctsMove) {
+    let Some(info) = &best_move.search_info else {
+        return;
+    };
+    let variation: Vec<String> = info
+        .principal_variation
+        .iter()
+        .map(|action| action.0.to_string())
+        .collect();
+    println!(
+        "searched {} iterations ({:.0}/s), {} nodes, depth {}, expecting {}",
+        info.iterations,
+        info.iterations_per_second.unwrap_or_default(),
+        info.nodes,
+        info.max_depth,
+        variation.join(" "),
+    );
+    match info.proven_reward {
+        Some(1.0) => println!("proven win"),
+        Some(0.0) => println!("proven loss"),
+        Some(_) => println!("proven draw"),
+        None => {}
+    }
+}
+
+// Usage: mcts-connect-four-bin [checkpoint]. Given a checkpoint path, the CPU's search tree is
+// saved there after every move, and if the file already exists the interrupted game it holds is
+// resumed. The file is removed once the game is over.
 fn main() {
     println!("starting");
+    let checkpoint = std::env::args().nth(1).map(PathBuf::from);
     let mcts_config = MctsConfig::default();
 
     // ensure config.tree_dump_dir exists and is empty directory
-    let _ = std::fs::remove_dir_all(&mcts_config.get_tree_dump_dir().unwrap());
-    std::fs::create_dir(&mcts_config.get_tree_dump_dir().unwrap()).unwrap();
-
-    let rng = Rc::new(RefCell::new(rand_pcg::Pcg64::seed_from_u64(42)));
+    let _ = std::fs::remove_dir_all(mcts_config.get_tree_dump_dir().unwrap());
+    std::fs::create_dir(mcts_config.get_tree_dump_dir().unwrap()).unwrap();
 
+    let rng = shared_rng(42);
     let human_player = Player::Player2;
     let cpu_player = Player::Player1;
-    let mut state = State::new(
-        7,               /*width*/
-        6,               /*height*/
-        Player::Player1, /*turn*/
-        cpu_player,      /*who_am_i*/
-    );
+    let mut move_number = 0;
+    let (mut cpu, mut state) = match checkpoint.as_ref().filter(|path| path.exists()) {
+        Some(path) => {
+            println!("resuming from {}", path.display());
+            let cpu = MctsPlayer::resume(mcts_config, rng, path)
+                .unwrap_or_else(|error| panic!("failed to resume: {}", error));
+            let state = cpu.root_state().unwrap().clone();
+            (cpu, state)
+        }
+        None => (
+            MctsPlayer::new(mcts_config, rng),
+            State::new(
+                7,               /*width*/
+                6,               /*height*/
+                Player::Player1, /*turn*/
+                cpu_player,      /*who_am_i*/
+            ),
+        ),
+    };
 
     while connect_four_logic::is_terminal_position(&state.board)
         == connect_four_logic::TerminalPosition::IsNotTerminal
@@ -62,8 +124,15 @@ fn main() {
                 _ => panic!("invalid action"),
             }
         } else {
-            mcts_connect_four::get_best_mcts_move(&state, &mcts_config, Rc::clone(&rng)).actual_move
+            let best_move = cpu.best_move(&state);
+            print_search_info(&best_move);
+            if let Some(dir) = cpu.config().get_tree_dump_dir() {
+                dump_tree_png(&dir, move_number, &best_move);
+            }
+            best_move.actual_move
         };
+        move_number += 1;
+        cpu.apply_move(action);
 
         let player = match state.turn {
             Player::Player1 => connect_four_logic::Player::Player1,
@@ -84,6 +153,16 @@ fn main() {
             Player::Player1 => Player::Player2,
             Player::Player2 => Player::Player1,
         };
+
+        if let Some(path) = &checkpoint {
+            if let Err(error) = cpu.save_checkpoint(path) {
+                println!("failed to save checkpoint {}: {}", path.display(), error);
+            }
+        }
+    }
+
+    if let Some(path) = &checkpoint {
+        let _ = std::fs::remove_file(path);
     }
 
     println!("{}", &state.board);
@@ -103,11 +182,11 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Arc;
 
     // player1 has two tokens in column 3 and column 4. it is player2's turn. check that mcts
     // returns a move to block on either column 2 or column 5, or else player1 will win.
-    #[test]
-    fn test_avoid_losing() {
+    fn assert_avoids_losing(transpositions: monte_carlo_tree_search::Transpositions) {
         let mcts_config = MctsConfig {
             iterations: 50,
             playouts_per_simulation: 30,
@@ -115,9 +194,11 @@ mod tests {
             exploration_constant: std::f64::consts::SQRT_2,
             tree_dump_dir: None,
             debug_track_trees: monte_carlo_tree_search::DebugTrackTrees::None,
+            transpositions,
+            reward_perspective: monte_carlo_tree_search::RewardPerspective::Alternating,
         };
 
-        let rng = Rc::new(RefCell::new(rand_pcg::Pcg64::seed_from_u64(42)));
+        let rng = shared_rng(42);
         let mut state = State::new(
             7,               /*width*/
             6,               /*height*/
@@ -133,7 +214,7 @@ mod tests {
             .unwrap();
 
         let best_move =
-            mcts_connect_four::get_best_mcts_move(&state, &mcts_config, Rc::clone(&rng));
+            mcts_connect_four::get_best_mcts_move(&state, &mcts_config, Arc::clone(&rng));
 
         match best_move.actual_move.move_type {
             connect_four_logic::MoveType::Insert => {
@@ -142,4 +223,14 @@ mod tests {
             _ => panic!("expected insert"),
         }
     }
+
+    #[test]
+    fn test_avoid_losing() {
+        assert_avoids_losing(monte_carlo_tree_search::Transpositions::Separate);
+    }
+
+    #[test]
+    fn test_avoid_losing_with_transpositions() {
+        assert_avoids_losing(monte_carlo_tree_search::Transpositions::Share);
+    }
 }
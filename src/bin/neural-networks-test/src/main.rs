@@ -14,9 +14,12 @@
  * with this program. If not, see <https://www.gnu.org/licenses/>
  */
 
-use nn::grad_example;
+use nn::{grad_example, select_device};
 
 fn main() {
     println!("Hello, world!");
-    grad_example();
+    let device = select_device();
+    println!("using device {:?}", device);
+    let (value, dy_over_dx) = grad_example(device);
+    println!("y = {}, dy/dx = {}", value, dy_over_dx);
 }
@@ -0,0 +1,158 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+//! One dependency for all of the workspace's algorithms.
+//!
+//! Each area of the book is a feature that re-exports the crates implementing it, so
+//! `aima::search` is the `search` crate and `aima::games::connect_four` the
+//! `connect-four-logic` crate. Every feature is on by default; to pick only some, turn off
+//! default features:
+//!
+//! ```toml
+//! aima = { path = "src/lib/aima", default-features = false, features = ["search", "csp"] }
+//! ```
+//!
+//! | Feature | Modules |
+//! |---------------|---------------------------------------------------------------|
+//! | `search` | [`search`] |
+//! | `games` | [`games::connect_four`], [`games::tic_tac_toe`] |
+//! | `mcts` | [`mcts`] |
+//! | `csp` | [`csp`] |
+//! | `logic` | [`logic`], [`planning`] |
+//! | `learning` | [`learning::neural_networks`], [`learning::reinforcement`] |
+//! | `agents` | [`agents::vacuum_world`], [`agents::wumpus_world`] |
+//! | `probability` | [`probability::bayes_net`], [`probability::mdp`] |
+//! | `graphics` | [`graphics`] |
+//!
+//! `torch` and `onnx` turn on the matching features of the neural network crate.
+//!
+//! [`prelude`] brings the main trait and type of each enabled area into scope.
+
+#[cfg(feature = "csp")]
+pub use csp;
+#[cfg(feature = "graphics")]
+pub use graphics;
+#[cfg(feature = "logic")]
+pub use logic;
+#[cfg(feature = "mcts")]
+pub use monte_carlo_tree_search as mcts;
+#[cfg(feature = "logic")]
+pub use planning;
+#[cfg(feature = "search")]
+pub use search;
+
+/// Rules of the two-player games used by adversarial search.
+#[cfg(feature = "games")]
+pub mod games {
+    pub use connect_four_logic as connect_four;
+    pub use tic_tac_toe_logic as tic_tac_toe;
+}
+
+/// Supervised learners, neural networks and reinforcement learning.
+#[cfg(feature = "learning")]
+pub mod learning {
+    pub use nn as neural_networks;
+    pub use reinforcement_learning as reinforcement;
+}
+
+/// Agents and the environments they act in.
+#[cfg(feature = "agents")]
+pub mod agents {
+    pub use vacuum_cleaner as vacuum_world;
+    pub use wumpus_world;
+}
+
+/// Probabilistic reasoning and sequential decisions.
+#[cfg(feature = "probability")]
+pub mod probability {
+    pub use bayes_net;
+    pub use mdp;
+}
+
+/// The main traits and types of every enabled feature, for `use aima::prelude::*`.
+///
+/// Names shared between crates, such as each game's `Player` and `Board`, are left out; use
+/// them through their modules. The Monte Carlo tree search traits are renamed to
+/// [`MctsState`](prelude::MctsState) and [`MctsAction`](prelude::MctsAction) for the same
+/// reason.
+pub mod prelude {
+    #[cfg(feature = "agents")]
+    pub use vacuum_cleaner::{Agent, Environment, PerformanceMeasure, Simulation};
+
+    #[cfg(feature = "csp")]
+    pub use csp::{backtracking::backtracking_search, Constraint, Csp};
+
+    #[cfg(feature = "graphics")]
+    pub use graphics::{Layout, LayoutResult, ReingoldTilfordLayout, Tree};
+
+    #[cfg(feature = "learning")]
+    pub use nn::learner::SupervisedLearner;
+    #[cfg(feature = "learning")]
+    pub use reinforcement_learning::EpisodicEnvironment;
+
+    #[cfg(feature = "logic")]
+    pub use logic::{dpll::dpll_satisfiable, parser::parse, Model, Sentence};
+    #[cfg(feature = "logic")]
+    pub use planning::{Domain, PlanningProblem};
+
+    #[cfg(feature = "mcts")]
+    pub use monte_carlo_tree_search::{Action as MctsAction, Mcts, MctsArgs, State as MctsState};
+
+    #[cfg(feature = "probability")]
+    pub use bayes_net::BayesNet;
+    #[cfg(feature = "probability")]
+    pub use mdp::Mdp;
+
+    #[cfg(feature = "search")]
+    pub use search::{Problem, SearchResult, Solution};
+}
+
+#[cfg(test)]
+mod tests {
+    #[allow(unused_imports)]
+    use super::prelude::*;
+
+    #[cfg(feature = "search")]
+    #[test]
+    fn test_search() {
+        let problem = crate::search::romania::RomaniaProblem::arad_to_bucharest();
+        let result = crate::search::uninformed::uniform_cost_search(&problem);
+        assert_eq!(result.solution().unwrap().path_cost, 418.0);
+    }
+
+    #[cfg(feature = "csp")]
+    #[test]
+    fn test_csp() {
+        let australia = crate::csp::map_coloring::australia();
+        let result = backtracking_search(&australia, Default::default());
+        assert!(result.solution.is_some());
+    }
+
+    #[cfg(feature = "logic")]
+    #[test]
+    fn test_logic() {
+        let sentence = parse("(A | B) & ~A").unwrap();
+        let model = dpll_satisfiable(&sentence).unwrap();
+        assert_eq!(model.get("B"), Some(&true));
+    }
+
+    #[cfg(feature = "games")]
+    #[test]
+    fn test_games() {
+        let board = crate::games::tic_tac_toe::Board::default();
+        assert_eq!(crate::games::tic_tac_toe::get_legal_moves(&board).len(), 9);
+    }
+}
@@ -0,0 +1,255 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Decision networks: Bayesian networks extended with decision and utility nodes.
+//
+// See:
+// -  Chapter 16: Making Simple Decisions, section 16.5
+
+use crate::elimination::elimination_ask;
+use crate::{BayesNet, BayesNetError, Evidence, VariableId};
+
+/// A decision network, section 16.5, also called an influence diagram. Chance nodes are
+/// ordinary Bayes net variables. Decision nodes are variables too, but their values are chosen
+/// rather than sampled, so they have no parents and a CPT that is never used. There is one
+/// utility node, a table over its parents.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DecisionNetwork {
+    net: BayesNet,
+    decisions: Vec<VariableId>,
+    utility_parents: Vec<VariableId>,
+
+    /// One entry per assignment of the utility node's parents, with the first parent's value
+    /// changing slowest.
+    utility: Vec<f64>,
+}
+
+/// The expected utility of every combination of decisions, and the best one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecisionResult {
+    /// Value of each decision node, in the order they were added, for the best combination.
+    pub best: Vec<usize>,
+    pub expected_utility: f64,
+    pub all: Vec<(Vec<usize>, f64)>,
+}
+
+impl DecisionNetwork {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn net(&self) -> &BayesNet {
+        &self.net
+    }
+
+    pub fn decisions(&self) -> &[VariableId] {
+        &self.decisions
+    }
+
+    pub fn add_chance(
+        &mut self,
+        name: &str,
+        values: &[&str],
+        parents: &[VariableId],
+        cpt: Vec<f64>,
+    ) -> Result<VariableId, BayesNetError> {
+        self.net.add_variable(name, values, parents, cpt)
+    }
+
+    pub fn add_boolean_chance(
+        &mut self,
+        name: &str,
+        parents: &[VariableId],
+        probabilities_true: &[f64],
+    ) -> Result<VariableId, BayesNetError> {
+        self.net.add_boolean(name, parents, probabilities_true)
+    }
+
+    /// A decision with the given choices.
+    pub fn add_decision(
+        &mut self,
+        name: &str,
+        values: &[&str],
+    ) -> Result<VariableId, BayesNetError> {
+        let uniform = vec![1.0 / values.len() as f64; values.len()];
+        let variable = self.net.add_variable(name, values, &[], uniform)?;
+        self.decisions.push(variable);
+        Ok(variable)
+    }
+
+    /// Set the utility node's parents and its table, which has one entry per assignment of the
+    /// parents with the first parent's value changing slowest.
+    pub fn set_utility(
+        &mut self,
+        parents: &[VariableId],
+        table: Vec<f64>,
+    ) -> Result<(), BayesNetError> {
+        if let Some(&parent) = parents.iter().find(|&&parent| parent >= self.net.len()) {
+            return Err(BayesNetError::UnknownParent {
+                variable: "Utility".to_string(),
+                parent,
+            });
+        }
+        let expected: usize = parents.iter().map(|&p| self.net.cardinality(p)).product();
+        if table.len() != expected {
+            return Err(BayesNetError::WrongCptSize {
+                variable: "Utility".to_string(),
+                expected,
+                found: table.len(),
+            });
+        }
+        self.utility_parents = parents.to_vec();
+        self.utility = table;
+        Ok(())
+    }
+
+    /// Expected utility given evidence, which should include a value for every decision node:
+    /// Σ P(parents of U | evidence) U(parents of U). The joint distribution of the utility node's
+    /// parents comes from the chain rule, one variable elimination query per parent.
+    pub fn expected_utility(&self, evidence: &Evidence) -> f64 {
+        let mut evidence = evidence.clone();
+        self.expected_utility_from(0, &mut evidence)
+    }
+
+    fn expected_utility_from(&self, parent: usize, evidence: &mut Evidence) -> f64 {
+        if parent == self.utility_parents.len() {
+            let index = self.utility_parents.iter().fold(0, |index, &p| {
+                index * self.net.cardinality(p) + evidence[&p]
+            });
+            return self.utility[index];
+        }
+        let variable = self.utility_parents[parent];
+        if evidence.contains_key(&variable) {
+            return self.expected_utility_from(parent + 1, evidence);
+        }
+        let distribution = elimination_ask(&self.net, variable, evidence);
+        let mut total = 0.0;
+        for (value, p) in distribution.into_iter().enumerate() {
+            if p == 0.0 {
+                continue;
+            }
+            evidence.insert(variable, value);
+            total += p * self.expected_utility_from(parent + 1, evidence);
+        }
+        evidence.remove(&variable);
+        total
+    }
+
+    /// Evaluate the decision network, section 16.5.2: for every combination of decisions,
+    /// compute the expected utility given the evidence, and pick the highest. Ties go to the
+    /// earliest combination.
+    pub fn best_decision(&self, evidence: &Evidence) -> DecisionResult {
+        let mut combinations: Vec<Vec<usize>> = vec![vec![]];
+        for &decision in &self.decisions {
+            combinations = combinations
+                .into_iter()
+                .flat_map(|prefix| {
+                    (0..self.net.cardinality(decision)).map(move |value| {
+                        let mut next = prefix.clone();
+                        next.push(value);
+                        next
+                    })
+                })
+                .collect();
+        }
+        let all: Vec<(Vec<usize>, f64)> = combinations
+            .into_iter()
+            .map(|choice| {
+                let mut with_decisions = evidence.clone();
+                with_decisions.extend(self.decisions.iter().copied().zip(choice.iter().copied()));
+                let utility = self.expected_utility(&with_decisions);
+                (choice, utility)
+            })
+            .collect();
+        let (best, expected_utility) = all
+            .iter()
+            .fold(
+                None,
+                |best: Option<&(Vec<usize>, f64)>, candidate| match best {
+                    Some(b) if b.1 >= candidate.1 => Some(b),
+                    _ => Some(candidate),
+                },
+            )
+            .cloned()
+            .unwrap_or_default();
+        DecisionResult {
+            best,
+            expected_utility,
+            all,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examples::{airport_siting, umbrella_decision};
+
+    #[test]
+    fn test_airport_siting() {
+        let network = airport_siting();
+        let result = network.best_decision(&Evidence::new());
+        let site = network.decisions()[0];
+        assert_eq!(
+            network.net().node(site).values[result.best[0]],
+            "Northfield"
+        );
+        // 100 - 40 × 0.3 - 30 × 0.2 - 20 × 0.36 for Northfield.
+        assert!((result.expected_utility - 74.8).abs() < 1e-9);
+        assert!((result.all[1].1 - 64.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_evidence_changes_expected_utility() {
+        let network = airport_siting();
+        let evidence = network.net().evidence(&[("AirTraffic", "high")]).unwrap();
+        let result = network.best_decision(&evidence);
+        assert!((result.all[0].1 - 70.0).abs() < 1e-9);
+        assert!((result.all[1].1 - 60.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_forecast_flips_the_umbrella_decision() {
+        let network = umbrella_decision();
+        let net = network.net();
+        let take = net.variable("Umbrella").unwrap();
+        let best = |observations: &[(&str, &str)]| {
+            let result = network.best_decision(&net.evidence(observations).unwrap());
+            net.node(take).values[result.best[0]].clone()
+        };
+        assert_eq!(best(&[]), "leave");
+        assert_eq!(best(&[("Forecast", "rainy")]), "take");
+        assert_eq!(best(&[("Forecast", "sunny")]), "leave");
+    }
+
+    #[test]
+    fn test_utility_errors() {
+        let mut network = DecisionNetwork::new();
+        let decision = network.add_decision("D", &["a", "b"]).unwrap();
+        assert_eq!(
+            network.set_utility(&[decision], vec![1.0]),
+            Err(BayesNetError::WrongCptSize {
+                variable: "Utility".to_string(),
+                expected: 2,
+                found: 1
+            })
+        );
+        assert!(matches!(
+            network.set_utility(&[3], vec![1.0, 2.0]),
+            Err(BayesNetError::UnknownParent { parent: 3, .. })
+        ));
+    }
+}
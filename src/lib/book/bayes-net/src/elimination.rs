@@ -0,0 +1,290 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Exact inference by variable elimination, Figure 13.13.
+
+use std::collections::HashSet;
+
+use crate::{normalize, point_distribution, BayesNet, Evidence, VariableId};
+
+/// A function from assignments of some variables to numbers, such as a CPT with the evidence
+/// variables fixed. Values are stored with the first variable changing slowest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Factor {
+    variables: Vec<VariableId>,
+    cardinalities: Vec<usize>,
+    values: Vec<f64>,
+}
+
+impl Factor {
+    /// The factor for P(variable | parents) with the evidence variables fixed to their values.
+    pub fn from_node(net: &BayesNet, variable: VariableId, evidence: &Evidence) -> Self {
+        let node = net.node(variable);
+        let variables: Vec<VariableId> = node
+            .parents
+            .iter()
+            .copied()
+            .chain(std::iter::once(variable))
+            .filter(|v| !evidence.contains_key(v))
+            .collect();
+        let cardinalities: Vec<usize> = variables.iter().map(|&v| net.cardinality(v)).collect();
+        let size = cardinalities.iter().product();
+        let mut values_of: Vec<Option<usize>> =
+            (0..net.len()).map(|v| evidence.get(&v).copied()).collect();
+        let values = (0..size)
+            .map(|index| {
+                for (v, value) in variables.iter().zip(assignment(index, &cardinalities)) {
+                    values_of[*v] = Some(value);
+                }
+                let value = values_of[variable].expect("variable has a value");
+                net.conditional(variable, &values_of)[value]
+            })
+            .collect();
+        Self {
+            variables,
+            cardinalities,
+            values,
+        }
+    }
+
+    pub fn variables(&self) -> &[VariableId] {
+        &self.variables
+    }
+
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    fn index(&self, values_of: &[usize]) -> usize {
+        self.variables
+            .iter()
+            .zip(&self.cardinalities)
+            .fold(0, |index, (&v, &cardinality)| {
+                index * cardinality + values_of[v]
+            })
+    }
+
+    /// The pointwise product, a factor over the union of both factors' variables.
+    pub fn product(&self, other: &Factor) -> Factor {
+        let mut variables = self.variables.clone();
+        let mut cardinalities = self.cardinalities.clone();
+        for (&v, &cardinality) in other.variables.iter().zip(&other.cardinalities) {
+            if !variables.contains(&v) {
+                variables.push(v);
+                cardinalities.push(cardinality);
+            }
+        }
+        let size = cardinalities.iter().product();
+        let width = variables.iter().max().map_or(0, |&v| v + 1);
+        let mut values_of = vec![0; width];
+        let values = (0..size)
+            .map(|index| {
+                for (v, value) in variables.iter().zip(assignment(index, &cardinalities)) {
+                    values_of[*v] = value;
+                }
+                self.values[self.index(&values_of)] * other.values[other.index(&values_of)]
+            })
+            .collect();
+        Factor {
+            variables,
+            cardinalities,
+            values,
+        }
+    }
+
+    /// Sum over the values of variable, giving a factor without it.
+    pub fn sum_out(&self, variable: VariableId) -> Factor {
+        let Some(position) = self.variables.iter().position(|&v| v == variable) else {
+            return self.clone();
+        };
+        let mut variables = self.variables.clone();
+        let mut cardinalities = self.cardinalities.clone();
+        variables.remove(position);
+        cardinalities.remove(position);
+        let inner: usize = self.cardinalities[position + 1..].iter().product();
+        let cardinality = self.cardinalities[position];
+        let mut values = vec![0.0; cardinalities.iter().product()];
+        for (index, value) in self.values.iter().enumerate() {
+            let outer = index / (inner * cardinality);
+            values[outer * inner + index % inner] += value;
+        }
+        Factor {
+            variables,
+            cardinalities,
+            values,
+        }
+    }
+}
+
+/// The values of each variable at index in a table with the first variable changing slowest.
+fn assignment(mut index: usize, cardinalities: &[usize]) -> Vec<usize> {
+    let mut values = vec![0; cardinalities.len()];
+    for (value, &cardinality) in values.iter_mut().zip(cardinalities).rev() {
+        *value = index % cardinality;
+        index /= cardinality;
+    }
+    values
+}
+
+/// The query and evidence variables and all their ancestors. Every other variable sums out to a
+/// factor of 1, so it is irrelevant to the query.
+fn relevant_variables(
+    net: &BayesNet,
+    query: VariableId,
+    evidence: &Evidence,
+) -> HashSet<VariableId> {
+    let mut relevant = HashSet::new();
+    let mut stack: Vec<VariableId> = evidence.keys().copied().chain([query]).collect();
+    while let Some(variable) = stack.pop() {
+        if relevant.insert(variable) {
+            stack.extend(net.node(variable).parents.iter().copied());
+        }
+    }
+    relevant
+}
+
+/// P(query | evidence) by variable elimination: work from the last variable to the first,
+/// multiplying each relevant variable's factor in, and summing out each hidden variable as soon
+/// as it has been reached. Avoids the repeated subexpressions of enumeration.
+pub fn elimination_ask(net: &BayesNet, query: VariableId, evidence: &Evidence) -> Vec<f64> {
+    if let Some(&value) = evidence.get(&query) {
+        return point_distribution(net.cardinality(query), value);
+    }
+    let relevant = relevant_variables(net, query, evidence);
+    let mut factors: Vec<Factor> = vec![];
+    for variable in (0..net.len()).rev() {
+        if !relevant.contains(&variable) {
+            continue;
+        }
+        factors.push(Factor::from_node(net, variable, evidence));
+        if variable != query && !evidence.contains_key(&variable) {
+            let (with, without): (Vec<Factor>, Vec<Factor>) = factors
+                .into_iter()
+                .partition(|factor| factor.variables.contains(&variable));
+            factors = without;
+            if let Some(product) = with.into_iter().reduce(|a, b| a.product(&b)) {
+                factors.push(product.sum_out(variable));
+            }
+        }
+    }
+    let product = factors
+        .into_iter()
+        .reduce(|a, b| a.product(&b))
+        .expect("the query has a factor");
+    let mut distribution: Vec<f64> = (0..net.cardinality(query))
+        .map(|value| {
+            let mut values_of = vec![0; net.len()];
+            values_of[query] = value;
+            // Only the query variable is left, plus constant factors over no variables.
+            product.values[product.index(&values_of)]
+        })
+        .collect();
+    normalize(&mut distribution);
+    distribution
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enumeration::enumeration_ask;
+    use crate::examples::burglary;
+
+    #[test]
+    fn test_assignment() {
+        assert_eq!(assignment(5, &[2, 3]), vec![1, 2]);
+        assert_eq!(assignment(0, &[]), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_factor_with_evidence() {
+        // f(A) = P(j | A), Figure 13.12.
+        let net = burglary();
+        let evidence = net.evidence(&[("JohnCalls", "true")]).unwrap();
+        let factor = Factor::from_node(&net, net.variable("JohnCalls").unwrap(), &evidence);
+        assert_eq!(factor.variables(), &[net.variable("Alarm").unwrap()]);
+        assert_eq!(factor.values(), &[0.90, 0.05]);
+    }
+
+    #[test]
+    fn test_product_and_sum_out() {
+        let f = Factor {
+            variables: vec![0, 1],
+            cardinalities: vec![2, 2],
+            values: vec![0.3, 0.7, 0.9, 0.1],
+        };
+        let g = Factor {
+            variables: vec![1, 2],
+            cardinalities: vec![2, 2],
+            values: vec![0.2, 0.8, 0.6, 0.4],
+        };
+        // Figure 13.14.
+        let h = f.product(&g);
+        assert_eq!(h.variables(), &[0, 1, 2]);
+        let expected = [0.06, 0.24, 0.42, 0.28, 0.18, 0.72, 0.06, 0.04];
+        for (value, expected) in h.values().iter().zip(expected) {
+            assert!((value - expected).abs() < 1e-12);
+        }
+        let summed = h.sum_out(0);
+        assert_eq!(summed.variables(), &[1, 2]);
+        let expected = [0.24, 0.96, 0.48, 0.32];
+        for (value, expected) in summed.values().iter().zip(expected) {
+            assert!((value - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_burglary_given_both_call() {
+        let net = burglary();
+        let evidence = net
+            .evidence(&[("JohnCalls", "true"), ("MaryCalls", "true")])
+            .unwrap();
+        let distribution = elimination_ask(&net, net.variable("Burglary").unwrap(), &evidence);
+        assert!(
+            (distribution[0] - 0.284).abs() < 0.001,
+            "{:?}",
+            distribution
+        );
+    }
+
+    #[test]
+    fn test_irrelevant_variables_are_pruned() {
+        let net = burglary();
+        let evidence = net.evidence(&[("Burglary", "true")]).unwrap();
+        let relevant = relevant_variables(&net, net.variable("JohnCalls").unwrap(), &evidence);
+        assert!(!relevant.contains(&net.variable("MaryCalls").unwrap()));
+    }
+
+    #[test]
+    fn test_agrees_with_enumeration() {
+        let net = burglary();
+        let observations: [&[(&str, &str)]; 4] = [
+            &[],
+            &[("JohnCalls", "true")],
+            &[("MaryCalls", "false"), ("Earthquake", "true")],
+            &[("Alarm", "true")],
+        ];
+        for observed in observations {
+            let evidence = net.evidence(observed).unwrap();
+            for query in 0..net.len() {
+                let expected = enumeration_ask(&net, query, &evidence);
+                let actual = elimination_ask(&net, query, &evidence);
+                for (a, e) in actual.iter().zip(&expected) {
+                    assert!((a - e).abs() < 1e-12, "{} {:?}", query, observed);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,103 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Exact inference by enumeration, Figure 13.11.
+
+use crate::{normalize, point_distribution, BayesNet, Evidence, VariableId};
+
+/// P(query | evidence) by summing the full joint distribution over the hidden variables, one
+/// variable at a time in topological order. Takes O(d^n) time for n variables with d values.
+pub fn enumeration_ask(net: &BayesNet, query: VariableId, evidence: &Evidence) -> Vec<f64> {
+    if let Some(&value) = evidence.get(&query) {
+        return point_distribution(net.cardinality(query), value);
+    }
+    let mut values: Vec<Option<usize>> = (0..net.len())
+        .map(|variable| evidence.get(&variable).copied())
+        .collect();
+    let mut distribution: Vec<f64> = (0..net.cardinality(query))
+        .map(|value| {
+            values[query] = Some(value);
+            enumerate_all(net, 0, &mut values)
+        })
+        .collect();
+    normalize(&mut distribution);
+    distribution
+}
+
+/// Sum over the values of variables from variable onwards of the product of their conditional
+/// probabilities, with the values already fixed in values.
+fn enumerate_all(net: &BayesNet, variable: VariableId, values: &mut [Option<usize>]) -> f64 {
+    if variable == net.len() {
+        return 1.0;
+    }
+    match values[variable] {
+        Some(value) => {
+            net.conditional(variable, values)[value] * enumerate_all(net, variable + 1, values)
+        }
+        None => {
+            let mut total = 0.0;
+            for value in 0..net.cardinality(variable) {
+                let p = net.conditional(variable, values)[value];
+                values[variable] = Some(value);
+                total += p * enumerate_all(net, variable + 1, values);
+            }
+            values[variable] = None;
+            total
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examples::burglary;
+
+    #[test]
+    fn test_burglary_given_both_call() {
+        // P(Burglary | JohnCalls = true, MaryCalls = true) ≈ ⟨0.284, 0.716⟩, page 440.
+        let net = burglary();
+        let evidence = net
+            .evidence(&[("JohnCalls", "true"), ("MaryCalls", "true")])
+            .unwrap();
+        let distribution = enumeration_ask(&net, net.variable("Burglary").unwrap(), &evidence);
+        assert!(
+            (distribution[0] - 0.284).abs() < 0.001,
+            "{:?}",
+            distribution
+        );
+        assert!(
+            (distribution[1] - 0.716).abs() < 0.001,
+            "{:?}",
+            distribution
+        );
+    }
+
+    #[test]
+    fn test_no_evidence_gives_prior() {
+        let net = burglary();
+        let distribution =
+            enumeration_ask(&net, net.variable("Earthquake").unwrap(), &Evidence::new());
+        assert!((distribution[0] - 0.002).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_query_is_evidence() {
+        let net = burglary();
+        let alarm = net.variable("Alarm").unwrap();
+        let evidence = net.evidence(&[("Alarm", "false")]).unwrap();
+        assert_eq!(enumeration_ask(&net, alarm, &evidence), vec![0.0, 1.0]);
+    }
+}
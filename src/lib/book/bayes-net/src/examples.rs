@@ -0,0 +1,124 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Example networks.
+
+use crate::decision::DecisionNetwork;
+use crate::BayesNet;
+
+/// The burglary network of Figure 13.2. Variables are Burglary, Earthquake, Alarm, JohnCalls and
+/// MaryCalls, in that order.
+pub fn burglary() -> BayesNet {
+    let mut net = BayesNet::new();
+    let burglary = net.add_boolean("Burglary", &[], &[0.001]).unwrap();
+    let earthquake = net.add_boolean("Earthquake", &[], &[0.002]).unwrap();
+    let alarm = net
+        .add_boolean("Alarm", &[burglary, earthquake], &[0.95, 0.94, 0.29, 0.001])
+        .unwrap();
+    net.add_boolean("JohnCalls", &[alarm], &[0.90, 0.05])
+        .unwrap();
+    net.add_boolean("MaryCalls", &[alarm], &[0.70, 0.01])
+        .unwrap();
+    net
+}
+
+/// The sprinkler network of Figure 13.15. Variables are Cloudy, Sprinkler, Rain and WetGrass, in
+/// that order.
+pub fn sprinkler() -> BayesNet {
+    let mut net = BayesNet::new();
+    let cloudy = net.add_boolean("Cloudy", &[], &[0.5]).unwrap();
+    let sprinkler = net
+        .add_boolean("Sprinkler", &[cloudy], &[0.10, 0.50])
+        .unwrap();
+    let rain = net.add_boolean("Rain", &[cloudy], &[0.80, 0.20]).unwrap();
+    net.add_boolean("WetGrass", &[sprinkler, rain], &[0.99, 0.90, 0.90, 0.00])
+        .unwrap();
+    net
+}
+
+/// A small airport-siting problem in the style of Figure 16.6. The utility starts at 100 and
+/// loses 40 for litigation, 30 for expensive construction and 20 for noise complaints.
+/// Southfield is cheaper to fight for in court but costlier to build and closer to town.
+pub fn airport_siting() -> DecisionNetwork {
+    let mut network = DecisionNetwork::new();
+    let traffic = network
+        .add_chance("AirTraffic", &["low", "high"], &[], vec![0.6, 0.4])
+        .unwrap();
+    let site = network
+        .add_decision("AirportSite", &["Northfield", "Southfield"])
+        .unwrap();
+    let litigation = network
+        .add_boolean_chance("Litigation", &[site], &[0.3, 0.1])
+        .unwrap();
+    let construction = network
+        .add_chance(
+            "Construction",
+            &["cheap", "expensive"],
+            &[site],
+            vec![0.8, 0.2, 0.4, 0.6],
+        )
+        .unwrap();
+    let noise = network
+        .add_boolean_chance("Noise", &[site, traffic], &[0.2, 0.6, 0.5, 0.9])
+        .unwrap();
+    let mut table = vec![];
+    for litigated in [true, false] {
+        for expensive in [false, true] {
+            for noisy in [true, false] {
+                let mut utility = 100.0;
+                if litigated {
+                    utility -= 40.0;
+                }
+                if expensive {
+                    utility -= 30.0;
+                }
+                if noisy {
+                    utility -= 20.0;
+                }
+                table.push(utility);
+            }
+        }
+    }
+    network
+        .set_utility(&[litigation, construction, noise], table)
+        .unwrap();
+    network
+}
+
+/// Whether to take an umbrella given a weather forecast. Rain is 30% likely, and the forecast
+/// is right 70% of the time when it rains and 80% when it does not.
+pub fn umbrella_decision() -> DecisionNetwork {
+    let mut network = DecisionNetwork::new();
+    let weather = network
+        .add_chance("Weather", &["rain", "sun"], &[], vec![0.3, 0.7])
+        .unwrap();
+    network
+        .add_chance(
+            "Forecast",
+            &["rainy", "sunny"],
+            &[weather],
+            vec![0.7, 0.3, 0.2, 0.8],
+        )
+        .unwrap();
+    let umbrella = network
+        .add_decision("Umbrella", &["take", "leave"])
+        .unwrap();
+    // U(rain, take) = 70, U(rain, leave) = 0, U(sun, take) = 20, U(sun, leave) = 100.
+    network
+        .set_utility(&[weather, umbrella], vec![70.0, 0.0, 20.0, 100.0])
+        .unwrap();
+    network
+}
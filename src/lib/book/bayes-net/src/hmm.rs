@@ -0,0 +1,364 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Hidden Markov models: filtering, smoothing and finding the most likely sequence.
+//
+// See:
+// -  Chapter 14: Probabilistic Reasoning over Time, sections 14.2 and 14.3
+
+use std::collections::VecDeque;
+
+use thiserror::Error;
+
+use crate::normalize;
+
+const TOLERANCE: f64 = 1e-9;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum HmmError {
+    #[error("the {0} has the wrong number of rows or columns")]
+    WrongShape(&'static str),
+
+    #[error("row {row} of the {matrix} does not sum to 1")]
+    RowNotNormalized { matrix: &'static str, row: usize },
+}
+
+/// A hidden Markov model, section 14.3: one discrete state variable and one discrete evidence
+/// variable per time step, described by matrices.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HiddenMarkovModel {
+    /// P(X0).
+    prior: Vec<f64>,
+
+    /// transition[i][j] = P(X_t = j | X_{t-1} = i).
+    transition: Vec<Vec<f64>>,
+
+    /// sensor[i][e] = P(E_t = e | X_t = i).
+    sensor: Vec<Vec<f64>>,
+}
+
+fn check_rows(matrix: &[Vec<f64>], name: &'static str, columns: usize) -> Result<(), HmmError> {
+    for (row, probabilities) in matrix.iter().enumerate() {
+        if probabilities.len() != columns {
+            return Err(HmmError::WrongShape(name));
+        }
+        if (probabilities.iter().sum::<f64>() - 1.0).abs() > TOLERANCE {
+            return Err(HmmError::RowNotNormalized { matrix: name, row });
+        }
+    }
+    Ok(())
+}
+
+impl HiddenMarkovModel {
+    pub fn new(
+        prior: Vec<f64>,
+        transition: Vec<Vec<f64>>,
+        sensor: Vec<Vec<f64>>,
+    ) -> Result<Self, HmmError> {
+        let states = prior.len();
+        check_rows(std::slice::from_ref(&prior), "prior", states)?;
+        if transition.len() != states {
+            return Err(HmmError::WrongShape("transition model"));
+        }
+        check_rows(&transition, "transition model", states)?;
+        if sensor.len() != states || sensor.is_empty() {
+            return Err(HmmError::WrongShape("sensor model"));
+        }
+        check_rows(&sensor, "sensor model", sensor[0].len())?;
+        Ok(Self {
+            prior,
+            transition,
+            sensor,
+        })
+    }
+
+    /// The umbrella world of Figure 14.2. State 0 is rain and state 1 is no rain; observation 0
+    /// is the umbrella and observation 1 is no umbrella.
+    pub fn umbrella() -> Self {
+        Self::new(
+            vec![0.5, 0.5],
+            vec![vec![0.7, 0.3], vec![0.3, 0.7]],
+            vec![vec![0.9, 0.1], vec![0.2, 0.8]],
+        )
+        .expect("valid model")
+    }
+
+    pub fn states(&self) -> usize {
+        self.prior.len()
+    }
+
+    pub fn prior(&self) -> &[f64] {
+        &self.prior
+    }
+
+    /// One filtering step, equation (14.12): predict the next state from the filtered
+    /// distribution, then update the prediction with the evidence.
+    pub fn forward(&self, filtered: &[f64], evidence: usize) -> Vec<f64> {
+        let mut next: Vec<f64> = (0..self.states())
+            .map(|j| {
+                let predicted: f64 = (0..self.states())
+                    .map(|i| self.transition[i][j] * filtered[i])
+                    .sum();
+                self.sensor[j][evidence] * predicted
+            })
+            .collect();
+        normalize(&mut next);
+        next
+    }
+
+    /// One backward step, equation (14.14): P(e_{k+1:t} | X_k) from P(e_{k+2:t} | X_{k+1}) and
+    /// the evidence at k + 1.
+    pub fn backward(&self, message: &[f64], evidence: usize) -> Vec<f64> {
+        (0..self.states())
+            .map(|i| {
+                (0..self.states())
+                    .map(|j| self.sensor[j][evidence] * message[j] * self.transition[i][j])
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// P(X_k | e_{1:k}) for every k from 1 to t.
+    pub fn filter(&self, evidence: &[usize]) -> Vec<Vec<f64>> {
+        let mut filtered = self.prior.clone();
+        evidence
+            .iter()
+            .map(|&e| {
+                filtered = self.forward(&filtered, e);
+                filtered.clone()
+            })
+            .collect()
+    }
+
+    /// The forward-backward algorithm, Figure 14.4: P(X_k | e_{1:t}) for every k from 1 to t.
+    pub fn forward_backward(&self, evidence: &[usize]) -> Vec<Vec<f64>> {
+        let filtered = self.filter(evidence);
+        let mut smoothed = vec![vec![]; evidence.len()];
+        let mut backward = vec![1.0; self.states()];
+        for k in (0..evidence.len()).rev() {
+            let mut estimate: Vec<f64> = filtered[k]
+                .iter()
+                .zip(&backward)
+                .map(|(f, b)| f * b)
+                .collect();
+            normalize(&mut estimate);
+            smoothed[k] = estimate;
+            backward = self.backward(&backward, evidence[k]);
+        }
+        smoothed
+    }
+
+    /// The Viterbi algorithm, equation (14.13): the most likely sequence of states given the
+    /// evidence, and its probability P(x_{1:t}, e_{1:t}).
+    pub fn viterbi(&self, evidence: &[usize]) -> (Vec<usize>, f64) {
+        let Some((&first, rest)) = evidence.split_first() else {
+            return (vec![], 1.0);
+        };
+        let states = self.states();
+        let mut message: Vec<f64> = (0..states)
+            .map(|j| {
+                let predicted: f64 = (0..states)
+                    .map(|i| self.transition[i][j] * self.prior[i])
+                    .sum();
+                self.sensor[j][first] * predicted
+            })
+            .collect();
+        let mut back_pointers: Vec<Vec<usize>> = vec![];
+        for &e in rest {
+            let mut pointers = vec![0; states];
+            let next: Vec<f64> = (0..states)
+                .map(|j| {
+                    let (best, p) = (0..states)
+                        .map(|i| (i, self.transition[i][j] * message[i]))
+                        .fold((0, f64::NEG_INFINITY), |best, candidate| {
+                            if candidate.1 > best.1 {
+                                candidate
+                            } else {
+                                best
+                            }
+                        });
+                    pointers[j] = best;
+                    self.sensor[j][e] * p
+                })
+                .collect();
+            back_pointers.push(pointers);
+            message = next;
+        }
+        let (mut state, probability) =
+            message
+                .iter()
+                .copied()
+                .enumerate()
+                .fold((0, f64::NEG_INFINITY), |best, candidate| {
+                    if candidate.1 > best.1 {
+                        candidate
+                    } else {
+                        best
+                    }
+                });
+        let mut sequence = vec![state];
+        for pointers in back_pointers.iter().rev() {
+            state = pointers[state];
+            sequence.push(state);
+        }
+        sequence.reverse();
+        (sequence, probability)
+    }
+}
+
+/// Online fixed-lag smoothing, section 14.3.1: after the evidence at time t arrives, estimate
+/// P(X_{t-lag} | e_{1:t}).
+///
+/// Unlike Figure 14.6, which updates a backward matrix in constant time using the inverse of the
+/// sensor matrix, this keeps the last lag observations and runs the backward pass over them. That
+/// costs O(lag) per step but works even when the sensor matrix is singular.
+#[derive(Debug, Clone)]
+pub struct FixedLagSmoother<'a> {
+    hmm: &'a HiddenMarkovModel,
+    lag: usize,
+
+    /// P(X_{t-lag-1} | e_{1:t-lag-1}), or the prior before any estimate has been made.
+    filtered: Vec<f64>,
+
+    /// Evidence from time t - lag to t.
+    window: VecDeque<usize>,
+}
+
+impl<'a> FixedLagSmoother<'a> {
+    pub fn new(hmm: &'a HiddenMarkovModel, lag: usize) -> Self {
+        Self {
+            hmm,
+            lag,
+            filtered: hmm.prior.clone(),
+            window: VecDeque::new(),
+        }
+    }
+
+    /// Add the evidence for the next time step. Returns the smoothed estimate for lag steps ago,
+    /// or None for the first lag steps.
+    pub fn observe(&mut self, evidence: usize) -> Option<Vec<f64>> {
+        self.window.push_back(evidence);
+        if self.window.len() <= self.lag {
+            return None;
+        }
+        let oldest = self.window.pop_front().expect("window is not empty");
+        self.filtered = self.hmm.forward(&self.filtered, oldest);
+        let mut backward = vec![1.0; self.hmm.states()];
+        for &e in self.window.iter().rev() {
+            backward = self.hmm.backward(&backward, e);
+        }
+        let mut estimate: Vec<f64> = self
+            .filtered
+            .iter()
+            .zip(&backward)
+            .map(|(f, b)| f * b)
+            .collect();
+        normalize(&mut estimate);
+        Some(estimate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UMBRELLA: usize = 0;
+    const NO_UMBRELLA: usize = 1;
+    const RAIN: usize = 0;
+    const NO_RAIN: usize = 1;
+
+    #[test]
+    fn test_filtering() {
+        // Page 468: P(R1 | u1) = 0.818 and P(R2 | u1, u2) = 0.883.
+        let hmm = HiddenMarkovModel::umbrella();
+        let filtered = hmm.filter(&[UMBRELLA, UMBRELLA]);
+        assert!((filtered[0][RAIN] - 0.818).abs() < 0.001);
+        assert!((filtered[1][RAIN] - 0.883).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_smoothing() {
+        // Page 470: P(R1 | u1, u2) = 0.883.
+        let hmm = HiddenMarkovModel::umbrella();
+        let smoothed = hmm.forward_backward(&[UMBRELLA, UMBRELLA]);
+        assert!((smoothed[0][RAIN] - 0.883).abs() < 0.001);
+        // The last smoothed estimate is the filtered one.
+        assert!((smoothed[1][RAIN] - 0.883).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_viterbi() {
+        // Figure 14.5: the umbrella appears on every day except the third.
+        let hmm = HiddenMarkovModel::umbrella();
+        let evidence = [UMBRELLA, UMBRELLA, NO_UMBRELLA, UMBRELLA, UMBRELLA];
+        let (sequence, probability) = hmm.viterbi(&evidence);
+        assert_eq!(sequence, vec![RAIN, RAIN, NO_RAIN, RAIN, RAIN]);
+        // The figure starts from the normalized P(R1 | u1), so its final message for rain,
+        // 0.0210, is P(x_{1:5}, e_{1:5}) divided by P(u1) = 0.55.
+        assert!(
+            (probability / 0.55 - 0.0210).abs() < 0.0001,
+            "{}",
+            probability
+        );
+        assert_eq!(hmm.viterbi(&[]), (vec![], 1.0));
+    }
+
+    #[test]
+    fn test_fixed_lag_smoothing_matches_forward_backward() {
+        let hmm = HiddenMarkovModel::umbrella();
+        let evidence = [
+            UMBRELLA,
+            NO_UMBRELLA,
+            UMBRELLA,
+            UMBRELLA,
+            NO_UMBRELLA,
+            UMBRELLA,
+        ];
+        let lag = 2;
+        let mut smoother = FixedLagSmoother::new(&hmm, lag);
+        for t in 0..evidence.len() {
+            let estimate = smoother.observe(evidence[t]);
+            if t < lag {
+                assert_eq!(estimate, None);
+                continue;
+            }
+            let expected = &hmm.forward_backward(&evidence[..=t])[t - lag];
+            let estimate = estimate.unwrap();
+            for (a, e) in estimate.iter().zip(expected) {
+                assert!((a - e).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_models() {
+        assert_eq!(
+            HiddenMarkovModel::new(vec![0.5, 0.5], vec![vec![1.0]], vec![vec![1.0], vec![1.0]]),
+            Err(HmmError::WrongShape("transition model"))
+        );
+        assert_eq!(
+            HiddenMarkovModel::new(
+                vec![0.5, 0.5],
+                vec![vec![0.5, 0.5], vec![0.5, 0.6]],
+                vec![vec![1.0], vec![1.0]]
+            ),
+            Err(HmmError::RowNotNormalized {
+                matrix: "transition model",
+                row: 1
+            })
+        );
+    }
+}
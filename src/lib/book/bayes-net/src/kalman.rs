@@ -0,0 +1,291 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Kalman filtering: exact filtering when the transition and sensor models are linear with
+// Gaussian noise.
+//
+// See:
+// -  Chapter 14: Probabilistic Reasoning over Time, section 14.4
+
+use nalgebra::{DMatrix, DVector};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum KalmanError {
+    #[error("the {0} has the wrong number of rows or columns")]
+    WrongShape(&'static str),
+
+    #[error("the innovation covariance is singular")]
+    SingularCovariance,
+}
+
+/// A Kalman filter for the linear-Gaussian model of equation (14.19):
+///
+/// P(x_{t+1} | x_t) = N(F x_t, Σx)
+/// P(z_t | x_t) = N(H x_t, Σz)
+///
+/// The belief state is always a Gaussian, stored as its mean and covariance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KalmanFilter {
+    /// F.
+    transition: DMatrix<f64>,
+
+    /// Σx.
+    transition_noise: DMatrix<f64>,
+
+    /// H.
+    sensor: DMatrix<f64>,
+
+    /// Σz.
+    sensor_noise: DMatrix<f64>,
+    mean: DVector<f64>,
+    covariance: DMatrix<f64>,
+}
+
+impl KalmanFilter {
+    pub fn new(
+        transition: DMatrix<f64>,
+        transition_noise: DMatrix<f64>,
+        sensor: DMatrix<f64>,
+        sensor_noise: DMatrix<f64>,
+        mean: DVector<f64>,
+        covariance: DMatrix<f64>,
+    ) -> Result<Self, KalmanError> {
+        let n = mean.len();
+        let m = sensor.nrows();
+        if transition.shape() != (n, n) {
+            return Err(KalmanError::WrongShape("transition model"));
+        }
+        if transition_noise.shape() != (n, n) {
+            return Err(KalmanError::WrongShape("transition noise"));
+        }
+        if sensor.ncols() != n {
+            return Err(KalmanError::WrongShape("sensor model"));
+        }
+        if sensor_noise.shape() != (m, m) {
+            return Err(KalmanError::WrongShape("sensor noise"));
+        }
+        if covariance.shape() != (n, n) {
+            return Err(KalmanError::WrongShape("covariance"));
+        }
+        Ok(Self {
+            transition,
+            transition_noise,
+            sensor,
+            sensor_noise,
+            mean,
+            covariance,
+        })
+    }
+
+    /// A one-dimensional random walk observed with noise, the example of section 14.4.2.
+    pub fn random_walk_1d(
+        mean: f64,
+        variance: f64,
+        transition_variance: f64,
+        sensor_variance: f64,
+    ) -> Self {
+        Self::new(
+            DMatrix::identity(1, 1),
+            DMatrix::from_element(1, 1, transition_variance),
+            DMatrix::identity(1, 1),
+            DMatrix::from_element(1, 1, sensor_variance),
+            DVector::from_element(1, mean),
+            DMatrix::from_element(1, 1, variance),
+        )
+        .expect("valid shapes")
+    }
+
+    /// An object moving with roughly constant velocity in the plane, like the bird of Figure
+    /// 14.11. The state is (x, y, ẋ, ẏ) and only the position is observed. Each time step is dt
+    /// long, the velocity drifts with variance velocity_variance per step, and each observed
+    /// coordinate has variance sensor_variance.
+    pub fn constant_velocity_2d(
+        dt: f64,
+        velocity_variance: f64,
+        sensor_variance: f64,
+        initial_position: (f64, f64),
+    ) -> Self {
+        #[rustfmt::skip]
+        let transition = DMatrix::from_row_slice(4, 4, &[
+            1.0, 0.0, dt, 0.0,
+            0.0, 1.0, 0.0, dt,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        ]);
+        let transition_noise = DMatrix::from_diagonal(&DVector::from_vec(vec![
+            0.0,
+            0.0,
+            velocity_variance,
+            velocity_variance,
+        ]));
+        #[rustfmt::skip]
+        let sensor = DMatrix::from_row_slice(2, 4, &[
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+        ]);
+        let sensor_noise = DMatrix::identity(2, 2) * sensor_variance;
+        let mean = DVector::from_vec(vec![initial_position.0, initial_position.1, 0.0, 0.0]);
+        // The position is known about as well as one observation; the velocity is unknown.
+        let covariance = DMatrix::from_diagonal(&DVector::from_vec(vec![
+            sensor_variance,
+            sensor_variance,
+            100.0,
+            100.0,
+        ]));
+        Self::new(
+            transition,
+            transition_noise,
+            sensor,
+            sensor_noise,
+            mean,
+            covariance,
+        )
+        .expect("valid shapes")
+    }
+
+    pub fn mean(&self) -> &DVector<f64> {
+        &self.mean
+    }
+
+    pub fn covariance(&self) -> &DMatrix<f64> {
+        &self.covariance
+    }
+
+    /// Prediction: the belief one step later, before seeing the evidence. The mean moves by F
+    /// and the covariance grows: F Σ Fᵀ + Σx.
+    pub fn predict(&mut self) {
+        self.mean = &self.transition * &self.mean;
+        self.covariance = &self.transition * &self.covariance * self.transition.transpose()
+            + &self.transition_noise;
+    }
+
+    /// Update the predicted belief with an observation, equation (14.19), using the Kalman gain
+    /// K = Σ Hᵀ (H Σ Hᵀ + Σz)⁻¹.
+    pub fn update(&mut self, observation: &DVector<f64>) -> Result<(), KalmanError> {
+        if observation.len() != self.sensor.nrows() {
+            return Err(KalmanError::WrongShape("observation"));
+        }
+        let innovation_covariance =
+            &self.sensor * &self.covariance * self.sensor.transpose() + &self.sensor_noise;
+        let inverse = innovation_covariance
+            .try_inverse()
+            .ok_or(KalmanError::SingularCovariance)?;
+        let gain = &self.covariance * self.sensor.transpose() * inverse;
+        let innovation = observation - &self.sensor * &self.mean;
+        self.mean += &gain * innovation;
+        let identity = DMatrix::identity(self.mean.len(), self.mean.len());
+        self.covariance = (identity - &gain * &self.sensor) * &self.covariance;
+        Ok(())
+    }
+
+    /// One filtering step: predict, then update with the observation.
+    pub fn step(&mut self, observation: &DVector<f64>) -> Result<(), KalmanError> {
+        self.predict();
+        self.update(observation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_distr::{Distribution, Normal};
+
+    use super::*;
+
+    #[test]
+    fn test_one_dimensional_update_matches_closed_form() {
+        // Equation (14.18).
+        let (mean, variance, sigma_x2, sigma_z2) = (1.0, 2.0, 0.5, 1.5);
+        let z = 4.0;
+        let mut filter = KalmanFilter::random_walk_1d(mean, variance, sigma_x2, sigma_z2);
+        filter.step(&DVector::from_element(1, z)).unwrap();
+        let expected_mean =
+            ((variance + sigma_x2) * z + sigma_z2 * mean) / (variance + sigma_x2 + sigma_z2);
+        let expected_variance = (variance + sigma_x2) * sigma_z2 / (variance + sigma_x2 + sigma_z2);
+        assert!((filter.mean()[0] - expected_mean).abs() < 1e-12);
+        assert!((filter.covariance()[(0, 0)] - expected_variance).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_variance_converges() {
+        // The variance settles to a fixed point that does not depend on the observations.
+        let mut filter = KalmanFilter::random_walk_1d(0.0, 10.0, 2.0, 1.0);
+        for _ in 0..50 {
+            filter.step(&DVector::from_element(1, 0.0)).unwrap();
+        }
+        let v = filter.covariance()[(0, 0)];
+        let next = (v + 2.0) * 1.0 / (v + 2.0 + 1.0);
+        assert!((v - next).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_tracking_in_the_plane() {
+        let mut rng = rand_pcg::Pcg64::seed_from_u64(0);
+        let noise = Normal::new(0.0, 2.0).unwrap();
+        let velocity = (1.0, -0.5);
+        let mut filter = KalmanFilter::constant_velocity_2d(1.0, 0.001, 4.0, (0.0, 0.0));
+        let (mut observation_error, mut filter_error) = (0.0, 0.0);
+        for t in 1..=100 {
+            let position = (velocity.0 * t as f64, velocity.1 * t as f64);
+            let observed = DVector::from_vec(vec![
+                position.0 + noise.sample(&mut rng),
+                position.1 + noise.sample(&mut rng),
+            ]);
+            filter.step(&observed).unwrap();
+            if t > 20 {
+                let mean = filter.mean();
+                observation_error +=
+                    (observed[0] - position.0).powi(2) + (observed[1] - position.1).powi(2);
+                filter_error += (mean[0] - position.0).powi(2) + (mean[1] - position.1).powi(2);
+            }
+        }
+        assert!(
+            filter_error < observation_error / 4.0,
+            "{} {}",
+            filter_error,
+            observation_error
+        );
+        let mean = filter.mean();
+        assert!((mean[2] - velocity.0).abs() < 0.1, "{}", mean);
+        assert!((mean[3] - velocity.1).abs() < 0.1, "{}", mean);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut filter = KalmanFilter::random_walk_1d(0.0, 1.0, 1.0, 1.0);
+        assert_eq!(
+            filter.update(&DVector::from_vec(vec![1.0, 2.0])),
+            Err(KalmanError::WrongShape("observation"))
+        );
+        let mut singular = KalmanFilter::random_walk_1d(0.0, 0.0, 0.0, 0.0);
+        assert_eq!(
+            singular.step(&DVector::from_element(1, 1.0)),
+            Err(KalmanError::SingularCovariance)
+        );
+        assert_eq!(
+            KalmanFilter::new(
+                DMatrix::identity(2, 2),
+                DMatrix::identity(1, 1),
+                DMatrix::identity(1, 1),
+                DMatrix::identity(1, 1),
+                DVector::zeros(1),
+                DMatrix::identity(1, 1),
+            ),
+            Err(KalmanError::WrongShape("transition model"))
+        );
+    }
+}
@@ -0,0 +1,318 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Bayesian networks of discrete random variables.
+//
+// See:
+// -  Chapter 13: Probabilistic Reasoning, sections 13.1 to 13.3
+
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+pub mod decision;
+pub mod elimination;
+pub mod enumeration;
+pub mod examples;
+pub mod hmm;
+pub mod kalman;
+pub mod particle_filter;
+pub mod sampling;
+
+/// Index of a variable in a BayesNet, in the order the variables were added.
+pub type VariableId = usize;
+
+/// Observed values of some variables, as indices into each variable's values.
+pub type Evidence = HashMap<VariableId, usize>;
+
+/// How far the probabilities in a CPT row may sum to from 1.
+const TOLERANCE: f64 = 1e-9;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BayesNetError {
+    #[error("variable {0} is already in the network")]
+    DuplicateVariable(String),
+
+    #[error("unknown variable {0}")]
+    UnknownVariable(String),
+
+    #[error("variable {variable} has no value {value}")]
+    UnknownValue { variable: String, value: String },
+
+    #[error("parent {parent} of {variable} is not in the network yet")]
+    UnknownParent {
+        variable: String,
+        parent: VariableId,
+    },
+
+    #[error("variable {variable} needs {expected} CPT entries, found {found}")]
+    WrongCptSize {
+        variable: String,
+        expected: usize,
+        found: usize,
+    },
+
+    #[error("row {row} of the CPT of {variable} does not sum to 1")]
+    RowNotNormalized { variable: String, row: usize },
+}
+
+/// A variable and its conditional probability table, P(X | Parents(X)).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub name: String,
+    pub values: Vec<String>,
+    pub parents: Vec<VariableId>,
+
+    /// One row per assignment of the parents, each with one probability per value. Rows are
+    /// ordered with the first parent's value changing slowest, like the tables in the book.
+    pub cpt: Vec<f64>,
+}
+
+/// A Bayesian network, section 13.2: a directed acyclic graph with a conditional probability
+/// table for each variable. Variables are added after their parents, so the order of addition
+/// is a topological order and the graph can never have a cycle.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BayesNet {
+    nodes: Vec<Node>,
+}
+
+/// Values of boolean variables. true comes first, like in the book's tables.
+pub const BOOLEAN: [&str; 2] = ["true", "false"];
+
+impl BayesNet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a variable with the given parents. cpt has one row per assignment of the parents,
+    /// with the first parent's value changing slowest, and each row has one probability per
+    /// value and sums to 1.
+    pub fn add_variable(
+        &mut self,
+        name: &str,
+        values: &[&str],
+        parents: &[VariableId],
+        cpt: Vec<f64>,
+    ) -> Result<VariableId, BayesNetError> {
+        if self.variable(name).is_ok() {
+            return Err(BayesNetError::DuplicateVariable(name.to_string()));
+        }
+        if let Some(&parent) = parents.iter().find(|&&parent| parent >= self.nodes.len()) {
+            return Err(BayesNetError::UnknownParent {
+                variable: name.to_string(),
+                parent,
+            });
+        }
+        let rows: usize = parents
+            .iter()
+            .map(|&parent| self.cardinality(parent))
+            .product();
+        let expected = rows * values.len();
+        if cpt.len() != expected {
+            return Err(BayesNetError::WrongCptSize {
+                variable: name.to_string(),
+                expected,
+                found: cpt.len(),
+            });
+        }
+        for (row, probabilities) in cpt.chunks(values.len()).enumerate() {
+            let total: f64 = probabilities.iter().sum();
+            if (total - 1.0).abs() > TOLERANCE || probabilities.iter().any(|&p| p < 0.0) {
+                return Err(BayesNetError::RowNotNormalized {
+                    variable: name.to_string(),
+                    row,
+                });
+            }
+        }
+        self.nodes.push(Node {
+            name: name.to_string(),
+            values: values.iter().map(|value| value.to_string()).collect(),
+            parents: parents.to_vec(),
+            cpt,
+        });
+        Ok(self.nodes.len() - 1)
+    }
+
+    /// Add a boolean variable given P(name = true) for each assignment of the parents, in the
+    /// same order as the rows of a CPT.
+    pub fn add_boolean(
+        &mut self,
+        name: &str,
+        parents: &[VariableId],
+        probabilities_true: &[f64],
+    ) -> Result<VariableId, BayesNetError> {
+        let cpt = probabilities_true
+            .iter()
+            .flat_map(|&p| [p, 1.0 - p])
+            .collect();
+        self.add_variable(name, &BOOLEAN, parents, cpt)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn node(&self, variable: VariableId) -> &Node {
+        &self.nodes[variable]
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    pub fn variable(&self, name: &str) -> Result<VariableId, BayesNetError> {
+        self.nodes
+            .iter()
+            .position(|node| node.name == name)
+            .ok_or_else(|| BayesNetError::UnknownVariable(name.to_string()))
+    }
+
+    pub fn value(&self, variable: VariableId, value: &str) -> Result<usize, BayesNetError> {
+        let node = &self.nodes[variable];
+        node.values
+            .iter()
+            .position(|v| v == value)
+            .ok_or_else(|| BayesNetError::UnknownValue {
+                variable: node.name.clone(),
+                value: value.to_string(),
+            })
+    }
+
+    /// Number of values of variable.
+    pub fn cardinality(&self, variable: VariableId) -> usize {
+        self.nodes[variable].values.len()
+    }
+
+    /// Evidence from (variable name, value name) pairs.
+    pub fn evidence(&self, observations: &[(&str, &str)]) -> Result<Evidence, BayesNetError> {
+        observations
+            .iter()
+            .map(|(name, value)| {
+                let variable = self.variable(name)?;
+                Ok((variable, self.value(variable, value)?))
+            })
+            .collect()
+    }
+
+    /// Variables that have variable as a parent.
+    pub fn children(&self, variable: VariableId) -> Vec<VariableId> {
+        (variable + 1..self.nodes.len())
+            .filter(|&child| self.nodes[child].parents.contains(&variable))
+            .collect()
+    }
+
+    /// P(variable | parents) for every value of variable, given values of at least its parents.
+    pub fn conditional(&self, variable: VariableId, values: &[Option<usize>]) -> &[f64] {
+        let node = &self.nodes[variable];
+        let row = node.parents.iter().fold(0, |row, &parent| {
+            let value = values[parent].expect("parents have values");
+            row * self.cardinality(parent) + value
+        });
+        let cardinality = node.values.len();
+        &node.cpt[row * cardinality..(row + 1) * cardinality]
+    }
+
+    /// P(x1, ..., xn) for a full assignment of values, equation (13.2): the product of every
+    /// variable's conditional probability given its parents.
+    pub fn joint_probability(&self, values: &[usize]) -> f64 {
+        let values: Vec<Option<usize>> = values.iter().copied().map(Some).collect();
+        (0..self.nodes.len())
+            .map(|variable| {
+                self.conditional(variable, &values)[values[variable].expect("full assignment")]
+            })
+            .product()
+    }
+}
+
+/// Scale a distribution so that it sums to 1.
+pub fn normalize(distribution: &mut [f64]) {
+    let total: f64 = distribution.iter().sum();
+    if total > 0.0 {
+        for p in distribution.iter_mut() {
+            *p /= total;
+        }
+    }
+}
+
+/// The distribution that puts all its probability on value.
+pub(crate) fn point_distribution(cardinality: usize, value: usize) -> Vec<f64> {
+    let mut distribution = vec![0.0; cardinality];
+    distribution[value] = 1.0;
+    distribution
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examples::burglary;
+
+    #[test]
+    fn test_joint_probability() {
+        // P(j, m, a, ¬b, ¬e) from page 430.
+        let net = burglary();
+        let values: Vec<usize> = ["false", "false", "true", "true", "true"]
+            .iter()
+            .enumerate()
+            .map(|(variable, value)| net.value(variable, value).unwrap())
+            .collect();
+        let p = net.joint_probability(&values);
+        assert!((p - 0.000628).abs() < 1e-6, "{}", p);
+    }
+
+    #[test]
+    fn test_conditional() {
+        let net = burglary();
+        let values = vec![Some(0), Some(1), None, None, None];
+        assert_eq!(net.conditional(2, &values), &[0.94, 1.0 - 0.94]);
+    }
+
+    #[test]
+    fn test_construction_errors() {
+        let mut net = BayesNet::new();
+        let a = net.add_boolean("A", &[], &[0.3]).unwrap();
+        assert_eq!(
+            net.add_boolean("A", &[], &[0.3]),
+            Err(BayesNetError::DuplicateVariable("A".to_string()))
+        );
+        assert_eq!(
+            net.add_boolean("B", &[a], &[0.3]),
+            Err(BayesNetError::WrongCptSize {
+                variable: "B".to_string(),
+                expected: 4,
+                found: 2
+            })
+        );
+        assert_eq!(
+            net.add_variable("C", &["x", "y"], &[], vec![0.5, 0.6]),
+            Err(BayesNetError::RowNotNormalized {
+                variable: "C".to_string(),
+                row: 0
+            })
+        );
+        assert!(matches!(
+            net.add_boolean("D", &[7], &[0.1, 0.2]),
+            Err(BayesNetError::UnknownParent { parent: 7, .. })
+        ));
+        assert!(matches!(
+            net.evidence(&[("A", "maybe")]),
+            Err(BayesNetError::UnknownValue { .. })
+        ));
+    }
+}
@@ -0,0 +1,290 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Particle filtering: approximate filtering for any transition and sensor model, by keeping a
+// population of sampled states.
+//
+// See:
+// -  Chapter 14: Probabilistic Reasoning over Time, section 14.5.3
+
+use rand::{Rng, SeedableRng};
+
+/// What happened in one update of a ParticleFilter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleFilterStep {
+    /// Effective sample size after weighting by the evidence and before any resampling.
+    pub effective_sample_size: f64,
+    pub resampled: bool,
+}
+
+/// A bootstrap particle filter, Figure 14.17. Each update propagates every particle through the
+/// transition model given a control input such as the robot's action, weights it by the
+/// likelihood of the evidence under the sensor model, and resamples when the weights have become
+/// too uneven.
+///
+/// The transition model is a closure from a state, a control input and a random number generator
+/// to a sampled next state. The sensor model is a closure from evidence and a state to
+/// P(evidence | state).
+pub struct ParticleFilter<_State, _Transition, _Sensor> {
+    particles: Vec<_State>,
+
+    /// Normalized weights, one per particle.
+    weights: Vec<f64>,
+    transition: _Transition,
+    sensor: _Sensor,
+
+    /// Resample when the effective sample size drops below this fraction of the particles.
+    resample_threshold: f64,
+    rng: rand_pcg::Pcg64,
+}
+
+impl<_State, _Transition, _Sensor> ParticleFilter<_State, _Transition, _Sensor>
+where
+    _State: Clone,
+{
+    /// A filter starting from particles sampled from the prior. It resamples after every
+    /// update, like Figure 14.17.
+    pub fn new(
+        particles: Vec<_State>,
+        transition: _Transition,
+        sensor: _Sensor,
+        seed: u64,
+    ) -> Self {
+        let n = particles.len();
+        Self {
+            particles,
+            weights: vec![1.0 / n as f64; n],
+            transition,
+            sensor,
+            resample_threshold: 1.0,
+            rng: rand_pcg::Pcg64::seed_from_u64(seed),
+        }
+    }
+
+    /// Only resample when the effective sample size drops below threshold times the number of
+    /// particles. 0.5 is common; 1.0 resamples every time.
+    pub fn with_resample_threshold(mut self, threshold: f64) -> Self {
+        self.resample_threshold = threshold;
+        self
+    }
+
+    pub fn particles(&self) -> &[_State] {
+        &self.particles
+    }
+
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    /// 1 / Σ w², between 1 when one particle has all the weight and the number of particles when
+    /// the weights are equal.
+    pub fn effective_sample_size(&self) -> f64 {
+        1.0 / self.weights.iter().map(|w| w * w).sum::<f64>()
+    }
+
+    /// Advance one time step with control and then condition on evidence. If no particle is
+    /// consistent with the evidence, the weights are reset to be equal, so the filter carries on
+    /// with its predicted particles instead of failing.
+    pub fn update<_Control, _Evidence>(
+        &mut self,
+        control: &_Control,
+        evidence: &_Evidence,
+    ) -> ParticleFilterStep
+    where
+        _Transition: Fn(&_State, &_Control, &mut rand_pcg::Pcg64) -> _State,
+        _Sensor: Fn(&_Evidence, &_State) -> f64,
+    {
+        for particle in self.particles.iter_mut() {
+            *particle = (self.transition)(particle, control, &mut self.rng);
+        }
+        for (weight, particle) in self.weights.iter_mut().zip(&self.particles) {
+            *weight *= (self.sensor)(evidence, particle);
+        }
+        let total: f64 = self.weights.iter().sum();
+        let n = self.particles.len() as f64;
+        if total > 0.0 {
+            for weight in self.weights.iter_mut() {
+                *weight /= total;
+            }
+        } else {
+            self.weights.fill(1.0 / n);
+        }
+        let effective_sample_size = if total > 0.0 {
+            self.effective_sample_size()
+        } else {
+            0.0
+        };
+        let resampled = effective_sample_size < self.resample_threshold * n;
+        if resampled {
+            let indices = systematic_resample(&self.weights, &mut self.rng);
+            self.particles = indices.iter().map(|&i| self.particles[i].clone()).collect();
+            self.weights.fill(1.0 / n);
+        }
+        ParticleFilterStep {
+            effective_sample_size,
+            resampled,
+        }
+    }
+
+    /// Weighted mean of f over the particles.
+    pub fn estimate(&self, f: impl Fn(&_State) -> f64) -> f64 {
+        self.particles
+            .iter()
+            .zip(&self.weights)
+            .map(|(particle, weight)| weight * f(particle))
+            .sum()
+    }
+
+    /// Total weight of the particles for which predicate holds.
+    pub fn probability(&self, predicate: impl Fn(&_State) -> bool) -> f64 {
+        self.estimate(|particle| if predicate(particle) { 1.0 } else { 0.0 })
+    }
+}
+
+/// Systematic resampling: one random offset, then evenly spaced points through the cumulative
+/// weights. Returns the index of the particle chosen for each new slot. Has lower variance than
+/// drawing every particle independently, and a particle with weight w is copied either ⌊nw⌋ or
+/// ⌈nw⌉ times.
+pub fn systematic_resample<_Rng: Rng>(weights: &[f64], rng: &mut _Rng) -> Vec<usize> {
+    let n = weights.len();
+    let offset: f64 = rng.gen();
+    let mut indices = Vec::with_capacity(n);
+    let mut cumulative = 0.0;
+    let mut i = 0;
+    for slot in 0..n {
+        let point = (slot as f64 + offset) / n as f64;
+        while i < n - 1 && cumulative + weights[i] < point {
+            cumulative += weights[i];
+            i += 1;
+        }
+        indices.push(i);
+    }
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use mdp::grid_world::{Cell, Direction, GridWorld};
+    use mdp::Mdp;
+    use rand::seq::SliceRandom;
+
+    use super::*;
+
+    #[test]
+    fn test_systematic_resample() {
+        let mut rng = rand_pcg::Pcg64::seed_from_u64(0);
+        for _ in 0..100 {
+            let mut counts = [0; 4];
+            for i in systematic_resample(&[0.5, 0.25, 0.25, 0.0], &mut rng) {
+                counts[i] += 1;
+            }
+            assert_eq!(counts, [2, 1, 1, 0]);
+        }
+        let indices = systematic_resample(&[0.0, 1.0, 0.0], &mut rng);
+        assert_eq!(indices, vec![1, 1, 1]);
+    }
+
+    #[test]
+    fn test_effective_sample_size_and_threshold() {
+        // A random walk on the integers, with a sensor that strongly prefers 0.
+        let transition = |x: &i32, _: &(), rng: &mut rand_pcg::Pcg64| x + rng.gen_range(-1..=1);
+        let sensor = |_: &(), x: &i32| if *x == 0 { 1.0 } else { 0.01 };
+        let mut filter =
+            ParticleFilter::new(vec![0; 100], transition, sensor, 0).with_resample_threshold(0.5);
+        assert!((filter.effective_sample_size() - 100.0).abs() < 1e-9);
+        let step = filter.update(&(), &());
+        assert!(step.effective_sample_size < 50.0, "{:?}", step);
+        assert!(step.resampled);
+        assert!((filter.effective_sample_size() - 100.0).abs() < 1e-9);
+        assert!(filter.probability(|x| *x == 0) > 0.9);
+    }
+
+    #[test]
+    fn test_inconsistent_evidence_resets_weights() {
+        let transition = |x: &i32, _: &(), _: &mut rand_pcg::Pcg64| *x;
+        let sensor = |_: &(), _: &i32| 0.0;
+        let mut filter = ParticleFilter::new(vec![1, 2], transition, sensor, 0);
+        let step = filter.update(&(), &());
+        assert_eq!(step.effective_sample_size, 0.0);
+        assert_eq!(filter.weights(), &[0.5, 0.5]);
+    }
+
+    /// Which of the four directions are blocked by a wall or the edge.
+    fn walls(world: &GridWorld, cell: Cell) -> [bool; 4] {
+        Direction::ALL.map(|direction| world.step(cell, direction) == cell)
+    }
+
+    /// Robot localization in the 4x3 world, in the style of section 14.3.2: the robot knows which
+    /// way it tries to move, and has four wall sensors that are each wrong with probability 0.1.
+    #[test]
+    fn test_grid_world_localization() {
+        let world = GridWorld::four_by_three();
+        let error_rate = 0.1;
+        let move_robot = |cell: &Cell, direction: &Direction, rng: &mut rand_pcg::Pcg64| {
+            let transitions = world.transitions(cell, direction);
+            let mut threshold: f64 = rng.gen();
+            for (next, p) in &transitions {
+                if threshold < *p {
+                    return *next;
+                }
+                threshold -= p;
+            }
+            transitions.last().unwrap().0
+        };
+        let sensor = |evidence: &[bool; 4], cell: &Cell| {
+            walls(&world, *cell)
+                .iter()
+                .zip(evidence)
+                .map(|(actual, seen)| {
+                    if actual == seen {
+                        1.0 - error_rate
+                    } else {
+                        error_rate
+                    }
+                })
+                .product::<f64>()
+        };
+
+        let mut localized = 0;
+        for seed in 0..10 {
+            let mut rng = rand_pcg::Pcg64::seed_from_u64(seed);
+            let cells = world.states();
+            let particles: Vec<Cell> = (0..1_000)
+                .map(|_| *cells.choose(&mut rng).unwrap())
+                .collect();
+            let mut filter = ParticleFilter::new(particles, move_robot, sensor, seed);
+            let mut robot = *cells.choose(&mut rng).unwrap();
+            for _ in 0..15 {
+                let direction = *Direction::ALL.choose(&mut rng).unwrap();
+                robot = move_robot(&robot, &direction, &mut rng);
+                let evidence = walls(&world, robot).map(|wall| wall != rng.gen_bool(error_rate));
+                filter.update(&direction, &evidence);
+            }
+            if filter.probability(|cell| *cell == robot) > 0.5 {
+                localized += 1;
+            }
+        }
+        assert!(localized >= 8, "{}", localized);
+    }
+}
@@ -0,0 +1,279 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Approximate inference by sampling, section 13.4.
+
+use rand::{Rng, SeedableRng};
+
+use crate::{normalize, point_distribution, BayesNet, Evidence, VariableId};
+
+/// Index of a value drawn from distribution, which sums to 1.
+fn sample_value<_Rng: Rng>(distribution: &[f64], rng: &mut _Rng) -> usize {
+    let mut threshold: f64 = rng.gen();
+    for (value, &p) in distribution.iter().enumerate() {
+        if threshold < p {
+            return value;
+        }
+        threshold -= p;
+    }
+    // Rounding left a little probability over; it belongs to the last possible value.
+    distribution
+        .iter()
+        .rposition(|&p| p > 0.0)
+        .unwrap_or(distribution.len() - 1)
+}
+
+/// An event sampled from the network's prior, Figure 13.16: sample every variable in
+/// topological order given its already sampled parents.
+pub fn prior_sample<_Rng: Rng>(net: &BayesNet, rng: &mut _Rng) -> Vec<usize> {
+    let mut values: Vec<Option<usize>> = vec![None; net.len()];
+    for variable in 0..net.len() {
+        values[variable] = Some(sample_value(net.conditional(variable, &values), rng));
+    }
+    values.into_iter().flatten().collect()
+}
+
+/// Rejection sampling, Figure 13.17: draw prior samples, throw away those that disagree with the
+/// evidence, and count the query values of the rest. Most samples are wasted when the evidence
+/// is unlikely. If every sample is rejected the result is all zeros.
+pub fn rejection_sampling(
+    net: &BayesNet,
+    query: VariableId,
+    evidence: &Evidence,
+    samples: usize,
+    seed: u64,
+) -> Vec<f64> {
+    let mut rng = rand_pcg::Pcg64::seed_from_u64(seed);
+    let mut counts = vec![0.0; net.cardinality(query)];
+    for _ in 0..samples {
+        let event = prior_sample(net, &mut rng);
+        if evidence
+            .iter()
+            .all(|(&variable, &value)| event[variable] == value)
+        {
+            counts[event[query]] += 1.0;
+        }
+    }
+    normalize(&mut counts);
+    counts
+}
+
+/// An event that agrees with the evidence, and its weight: the likelihood of the evidence given
+/// the sampled values of its parents.
+fn weighted_sample<_Rng: Rng>(
+    net: &BayesNet,
+    evidence: &Evidence,
+    rng: &mut _Rng,
+) -> (Vec<usize>, f64) {
+    let mut weight = 1.0;
+    let mut values: Vec<Option<usize>> = vec![None; net.len()];
+    for variable in 0..net.len() {
+        let distribution = net.conditional(variable, &values);
+        let value = match evidence.get(&variable) {
+            Some(&value) => {
+                weight *= distribution[value];
+                value
+            }
+            None => sample_value(distribution, rng),
+        };
+        values[variable] = Some(value);
+    }
+    (values.into_iter().flatten().collect(), weight)
+}
+
+/// Likelihood weighting, Figure 13.18: fix the evidence variables, sample the rest, and weight
+/// each sample by the likelihood of the evidence. Every sample is used, but when the evidence is
+/// downstream of the query most of the weight can fall on a few samples.
+pub fn likelihood_weighting(
+    net: &BayesNet,
+    query: VariableId,
+    evidence: &Evidence,
+    samples: usize,
+    seed: u64,
+) -> Vec<f64> {
+    let mut rng = rand_pcg::Pcg64::seed_from_u64(seed);
+    let mut weights = vec![0.0; net.cardinality(query)];
+    for _ in 0..samples {
+        let (event, weight) = weighted_sample(net, evidence, &mut rng);
+        weights[event[query]] += weight;
+    }
+    normalize(&mut weights);
+    weights
+}
+
+/// P(variable | its Markov blanket), equation (13.10): proportional to the probability of each
+/// value given its parents times the probability of each child's value given its parents.
+fn markov_blanket_distribution(
+    net: &BayesNet,
+    variable: VariableId,
+    children: &[VariableId],
+    values: &mut [Option<usize>],
+) -> Vec<f64> {
+    let mut distribution: Vec<f64> = (0..net.cardinality(variable))
+        .map(|value| {
+            values[variable] = Some(value);
+            let mut p = net.conditional(variable, values)[value];
+            for &child in children {
+                let child_value = values[child].expect("every variable has a value");
+                p *= net.conditional(child, values)[child_value];
+            }
+            p
+        })
+        .collect();
+    normalize(&mut distribution);
+    distribution
+}
+
+/// Gibbs sampling, Figure 13.20: start from a random state consistent with the evidence, then
+/// repeatedly resample each non-evidence variable given its Markov blanket, counting the query
+/// value after every step. Consecutive states are correlated, and networks with deterministic
+/// CPT entries can stop the chain from reaching every state.
+pub fn gibbs_ask(
+    net: &BayesNet,
+    query: VariableId,
+    evidence: &Evidence,
+    samples: usize,
+    seed: u64,
+) -> Vec<f64> {
+    if let Some(&value) = evidence.get(&query) {
+        return point_distribution(net.cardinality(query), value);
+    }
+    let mut rng = rand_pcg::Pcg64::seed_from_u64(seed);
+    let (initial, _) = weighted_sample(net, evidence, &mut rng);
+    let mut values: Vec<Option<usize>> = initial.into_iter().map(Some).collect();
+    let hidden: Vec<VariableId> = (0..net.len())
+        .filter(|variable| !evidence.contains_key(variable))
+        .collect();
+    let children: Vec<Vec<VariableId>> = (0..net.len()).map(|v| net.children(v)).collect();
+    let mut counts = vec![0.0; net.cardinality(query)];
+    for step in 0..samples {
+        let variable = hidden[step % hidden.len()];
+        let distribution =
+            markov_blanket_distribution(net, variable, &children[variable], &mut values);
+        values[variable] = Some(sample_value(&distribution, &mut rng));
+        counts[values[query].expect("query has a value")] += 1.0;
+    }
+    normalize(&mut counts);
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elimination::elimination_ask;
+    use crate::examples::{burglary, sprinkler};
+
+    type Sampler = fn(&BayesNet, VariableId, &Evidence, usize, u64) -> Vec<f64>;
+
+    const SAMPLERS: [(&str, Sampler); 3] = [
+        ("rejection", rejection_sampling),
+        ("likelihood weighting", likelihood_weighting),
+        ("gibbs", gibbs_ask),
+    ];
+
+    fn assert_close(actual: &[f64], expected: &[f64], tolerance: f64, name: &str) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!(
+                (a - e).abs() < tolerance,
+                "{}: {:?} vs {:?}",
+                name,
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_sample_value() {
+        let mut rng = rand_pcg::Pcg64::seed_from_u64(0);
+        let mut counts = [0; 3];
+        for _ in 0..10_000 {
+            counts[sample_value(&[0.2, 0.0, 0.8], &mut rng)] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert!((1_800..2_200).contains(&counts[0]), "{:?}", counts);
+    }
+
+    #[test]
+    fn test_prior_sample_frequencies() {
+        // P(Rain = true) = 0.5 × 0.8 + 0.5 × 0.2 = 0.5, and WetGrass is true about 65% of the time.
+        let net = sprinkler();
+        let mut rng = rand_pcg::Pcg64::seed_from_u64(0);
+        let samples: Vec<Vec<usize>> = (0..10_000).map(|_| prior_sample(&net, &mut rng)).collect();
+        let wet = samples.iter().filter(|event| event[3] == 0).count() as f64 / 10_000.0;
+        let exact = elimination_ask(&net, 3, &Evidence::new());
+        assert!((wet - exact[0]).abs() < 0.02, "{} vs {}", wet, exact[0]);
+    }
+
+    #[test]
+    fn test_samplers_converge_on_sprinkler() {
+        // P(Rain | Sprinkler = true) = ⟨0.3, 0.7⟩, page 449.
+        let net = sprinkler();
+        let rain = net.variable("Rain").unwrap();
+        let evidence = net.evidence(&[("Sprinkler", "true")]).unwrap();
+        let exact = elimination_ask(&net, rain, &evidence);
+        assert_close(&exact, &[0.3, 0.7], 1e-12, "exact");
+        for (name, sampler) in SAMPLERS {
+            let estimate = sampler(&net, rain, &evidence, 20_000, 0);
+            assert_close(&estimate, &exact, 0.02, name);
+        }
+    }
+
+    #[test]
+    fn test_samplers_converge_with_downstream_evidence() {
+        let net = sprinkler();
+        let cloudy = net.variable("Cloudy").unwrap();
+        let evidence = net
+            .evidence(&[("Sprinkler", "true"), ("WetGrass", "true")])
+            .unwrap();
+        let exact = elimination_ask(&net, cloudy, &evidence);
+        for (name, sampler) in SAMPLERS {
+            let estimate = sampler(&net, cloudy, &evidence, 20_000, 1);
+            assert_close(&estimate, &exact, 0.02, name);
+        }
+    }
+
+    #[test]
+    fn test_likelihood_weighting_on_unlikely_evidence() {
+        // Rejection sampling keeps about 1 sample in 500 here, but likelihood weighting uses all
+        // of them.
+        let net = burglary();
+        let alarm = net.variable("Alarm").unwrap();
+        let evidence = net
+            .evidence(&[("JohnCalls", "true"), ("MaryCalls", "true")])
+            .unwrap();
+        let exact = elimination_ask(&net, alarm, &evidence);
+        let estimate = likelihood_weighting(&net, alarm, &evidence, 50_000, 2);
+        assert_close(&estimate, &exact, 0.03, "likelihood weighting");
+    }
+
+    #[test]
+    fn test_rejection_sampling_with_impossible_evidence() {
+        // WetGrass is never true when neither the sprinkler nor the rain is on.
+        let net = sprinkler();
+        let evidence = net
+            .evidence(&[
+                ("Sprinkler", "false"),
+                ("Rain", "false"),
+                ("WetGrass", "true"),
+            ])
+            .unwrap();
+        assert_eq!(
+            rejection_sampling(&net, 0, &evidence, 1_000, 0),
+            vec![0.0, 0.0]
+        );
+    }
+}
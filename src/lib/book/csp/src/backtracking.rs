@@ -0,0 +1,328 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Backtracking search for CSPs.
+//
+// See:
+// -  Chapter 6: Constraint Satisfaction Problems, section 6.3
+
+use std::collections::VecDeque;
+
+use crate::inference::{ac3, ac3_queue, forward_check};
+use crate::{Csp, Variable};
+
+/// How to choose the next variable to assign, section 6.3.1.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VariableOrdering {
+    /// The order the variables were added.
+    Static,
+
+    /// Minimum remaining values: the variable with the fewest legal values left, which is the
+    /// most likely to cause a failure soon.
+    MinimumRemainingValues,
+
+    /// Minimum remaining values, breaking ties with the degree heuristic: the variable involved
+    /// in the most constraints on other unassigned variables.
+    #[default]
+    MinimumRemainingValuesThenDegree,
+}
+
+/// Order in which to try the values of the chosen variable, section 6.3.1.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ValueOrdering {
+    /// The order of the variable's domain.
+    Static,
+
+    /// Least constraining value: the value that rules out the fewest values in the domains of
+    /// unassigned neighbours.
+    #[default]
+    LeastConstrainingValue,
+}
+
+/// Inference to do after each assignment, section 6.3.2.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Inference {
+    None,
+    #[default]
+    ForwardChecking,
+
+    /// Maintaining arc consistency: AC-3, starting from the arcs into the assigned variable.
+    MaintainArcConsistency,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SolverConfig {
+    pub variable_ordering: VariableOrdering,
+    pub value_ordering: ValueOrdering,
+    pub inference: Inference,
+
+    /// Whether to run AC-3 over the whole problem before searching.
+    pub preprocess_with_ac3: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SolverStats {
+    /// Values assigned to variables, including ones later undone.
+    pub assignments: usize,
+
+    /// Times every value of a variable failed, so the search had to back up.
+    pub backtracks: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolverResult<_Value> {
+    /// The value of each variable, indexed by variable, or None if there is no solution.
+    pub solution: Option<Vec<_Value>>,
+    pub stats: SolverStats,
+}
+
+/// Backtracking search, Figure 6.5. Assigns one variable at a time, in the order chosen by the
+/// config, and backs up as soon as a variable has no value consistent with the assignments so
+/// far.
+pub fn backtracking_search<_Value: Clone + PartialEq>(
+    csp: &Csp<_Value>,
+    config: SolverConfig,
+) -> SolverResult<_Value> {
+    let mut solver = Solver {
+        csp,
+        config,
+        stats: SolverStats::default(),
+        assignment: vec![None; csp.len()],
+    };
+    let mut domains = csp.domains().to_vec();
+    let found =
+        (!config.preprocess_with_ac3 || ac3(csp, &mut domains)) && solver.backtrack(domains);
+    let solution = if found {
+        Some(solver.assignment.into_iter().map(Option::unwrap).collect())
+    } else {
+        None
+    };
+    SolverResult {
+        solution,
+        stats: solver.stats,
+    }
+}
+
+struct Solver<'a, _Value> {
+    csp: &'a Csp<_Value>,
+    config: SolverConfig,
+    stats: SolverStats,
+    assignment: Vec<Option<_Value>>,
+}
+
+impl<_Value: Clone + PartialEq> Solver<'_, _Value> {
+    fn backtrack(&mut self, domains: Vec<Vec<_Value>>) -> bool {
+        let Some(variable) = self.select_unassigned_variable(&domains) else {
+            return true;
+        };
+        for value in self.order_domain_values(variable, &domains) {
+            if !self.csp.is_consistent(variable, &value, &self.assignment) {
+                continue;
+            }
+            self.assignment[variable] = Some(value.clone());
+            self.stats.assignments += 1;
+            let mut inferred = domains.clone();
+            inferred[variable] = vec![value.clone()];
+            if self.infer(&mut inferred, variable, &value) && self.backtrack(inferred) {
+                return true;
+            }
+            self.assignment[variable] = None;
+        }
+        self.stats.backtracks += 1;
+        false
+    }
+
+    fn infer(&self, domains: &mut [Vec<_Value>], variable: Variable, value: &_Value) -> bool {
+        match self.config.inference {
+            Inference::None => true,
+            Inference::ForwardChecking => {
+                forward_check(self.csp, domains, variable, value, &self.assignment)
+            }
+            Inference::MaintainArcConsistency => {
+                let queue: VecDeque<(Variable, Variable)> = self
+                    .csp
+                    .neighbours(variable)
+                    .iter()
+                    .filter(|&&neighbour| self.assignment[neighbour].is_none())
+                    .map(|&neighbour| (neighbour, variable))
+                    .collect();
+                ac3_queue(self.csp, domains, queue)
+            }
+        }
+    }
+
+    /// Number of values left for variable that are consistent with the current assignment.
+    fn remaining_values(&self, variable: Variable, domains: &[Vec<_Value>]) -> usize {
+        domains[variable]
+            .iter()
+            .filter(|value| self.csp.is_consistent(variable, value, &self.assignment))
+            .count()
+    }
+
+    /// Number of unassigned variables that share a constraint with variable.
+    fn degree(&self, variable: Variable) -> usize {
+        self.csp
+            .neighbours(variable)
+            .iter()
+            .filter(|&&neighbour| self.assignment[neighbour].is_none())
+            .count()
+    }
+
+    fn select_unassigned_variable(&self, domains: &[Vec<_Value>]) -> Option<Variable> {
+        let mut unassigned = (0..self.csp.len()).filter(|&v| self.assignment[v].is_none());
+        match self.config.variable_ordering {
+            VariableOrdering::Static => unassigned.next(),
+            VariableOrdering::MinimumRemainingValues => {
+                unassigned.min_by_key(|&v| self.remaining_values(v, domains))
+            }
+            VariableOrdering::MinimumRemainingValuesThenDegree => unassigned.min_by_key(|&v| {
+                (
+                    self.remaining_values(v, domains),
+                    std::cmp::Reverse(self.degree(v)),
+                )
+            }),
+        }
+    }
+
+    fn order_domain_values(&self, variable: Variable, domains: &[Vec<_Value>]) -> Vec<_Value> {
+        let mut values = domains[variable].clone();
+        if self.config.value_ordering == ValueOrdering::LeastConstrainingValue {
+            // sort_by_cached_key is stable, so ties keep the domain order.
+            values.sort_by_cached_key(|value| {
+                self.csp
+                    .neighbours(variable)
+                    .iter()
+                    .filter(|&&neighbour| self.assignment[neighbour].is_none())
+                    .map(|&neighbour| {
+                        domains[neighbour]
+                            .iter()
+                            .filter(|other| {
+                                !self.csp.arc_consistent(variable, value, neighbour, other)
+                            })
+                            .count()
+                    })
+                    .sum::<usize>()
+            });
+        }
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_configs() -> Vec<SolverConfig> {
+        let mut configs = vec![];
+        for variable_ordering in [
+            VariableOrdering::Static,
+            VariableOrdering::MinimumRemainingValues,
+            VariableOrdering::MinimumRemainingValuesThenDegree,
+        ] {
+            for value_ordering in [ValueOrdering::Static, ValueOrdering::LeastConstrainingValue] {
+                for inference in [
+                    Inference::None,
+                    Inference::ForwardChecking,
+                    Inference::MaintainArcConsistency,
+                ] {
+                    for preprocess_with_ac3 in [false, true] {
+                        configs.push(SolverConfig {
+                            variable_ordering,
+                            value_ordering,
+                            inference,
+                            preprocess_with_ac3,
+                        });
+                    }
+                }
+            }
+        }
+        configs
+    }
+
+    /// n-queens with one variable per column, whose value is the row of its queen.
+    fn queens(n: usize) -> Csp<usize> {
+        let mut csp = Csp::new();
+        let columns: Vec<Variable> = (0..n)
+            .map(|column| csp.add_variable(&format!("Q{}", column), (0..n).collect()))
+            .collect();
+        for (i, &a) in columns.iter().enumerate() {
+            for (j, &b) in columns.iter().enumerate().skip(i + 1) {
+                let distance = j - i;
+                csp.add_binary(a, b, move |&row_a, &row_b| {
+                    row_a != row_b && row_a.abs_diff(row_b) != distance
+                });
+            }
+        }
+        csp
+    }
+
+    #[test]
+    fn test_every_config_solves_queens() {
+        let csp = queens(8);
+        for config in all_configs() {
+            let result = backtracking_search(&csp, config);
+            let solution = result.solution.expect("8-queens has solutions");
+            assert!(csp.is_solution(&solution), "{:?}", config);
+        }
+    }
+
+    #[test]
+    fn test_every_config_proves_no_solution() {
+        let csp = queens(3);
+        for config in all_configs() {
+            assert_eq!(backtracking_search(&csp, config).solution, None);
+        }
+    }
+
+    #[test]
+    fn test_heuristics_reduce_work() {
+        let csp = queens(12);
+        let plain = backtracking_search(
+            &csp,
+            SolverConfig {
+                variable_ordering: VariableOrdering::Static,
+                value_ordering: ValueOrdering::Static,
+                inference: Inference::None,
+                preprocess_with_ac3: false,
+            },
+        );
+        let heuristic = backtracking_search(&csp, SolverConfig::default());
+        assert!(
+            heuristic.stats.backtracks < plain.stats.backtracks,
+            "{:?} vs {:?}",
+            heuristic.stats,
+            plain.stats
+        );
+    }
+
+    #[test]
+    fn test_global_constraint() {
+        // Three digits that are all different and sum to 6, where the first is the largest.
+        let mut csp = Csp::new();
+        let digits: Vec<Variable> = (0..3)
+            .map(|i| csp.add_variable(&format!("D{}", i), (0..10).collect()))
+            .collect();
+        csp.add_all_different(&digits);
+        csp.add_global(&digits, |values| values.iter().copied().sum::<i32>() == 6);
+        csp.add_binary(digits[0], digits[1], |a, b| a > b);
+        csp.add_binary(digits[0], digits[2], |a, c| a > c);
+        for config in all_configs() {
+            let solution = backtracking_search(&csp, config).solution.unwrap();
+            assert!(csp.is_solution(&solution), "{:?}", config);
+            assert!(solution[0] >= 3);
+        }
+    }
+}
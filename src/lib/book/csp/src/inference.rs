@@ -0,0 +1,159 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Inference in CSPs: removing values from domains that cannot be part of a solution.
+//
+// See:
+// -  Chapter 6: Constraint Satisfaction Problems, sections 6.2 and 6.3.2
+
+use std::collections::VecDeque;
+
+use crate::{Csp, Variable};
+
+/// AC-3, Figure 6.3: makes every variable arc-consistent with every other, by removing values
+/// from domains that have no supporting value in a neighbour's domain. Only binary constraints
+/// are used. Returns false if a domain became empty, which means there is no solution.
+pub fn ac3<_Value: Clone + PartialEq>(csp: &Csp<_Value>, domains: &mut [Vec<_Value>]) -> bool {
+    let queue = (0..csp.len())
+        .flat_map(|x| csp.neighbours(x).iter().map(move |&y| (x, y)))
+        .collect();
+    ac3_queue(csp, domains, queue)
+}
+
+/// AC-3 starting from the given queue of arcs rather than every arc. This is what maintaining
+/// arc consistency (MAC) uses after an assignment, starting from the arcs into the assigned
+/// variable.
+pub(crate) fn ac3_queue<_Value: Clone + PartialEq>(
+    csp: &Csp<_Value>,
+    domains: &mut [Vec<_Value>],
+    mut queue: VecDeque<(Variable, Variable)>,
+) -> bool {
+    while let Some((x, y)) = queue.pop_front() {
+        if revise(csp, domains, x, y) {
+            if domains[x].is_empty() {
+                return false;
+            }
+            for &z in csp.neighbours(x) {
+                if z != y {
+                    queue.push_back((z, x));
+                }
+            }
+        }
+    }
+    true
+}
+
+/// Remove the values of x that are inconsistent with every value of y. Returns true if any value
+/// was removed.
+fn revise<_Value: Clone + PartialEq>(
+    csp: &Csp<_Value>,
+    domains: &mut [Vec<_Value>],
+    x: Variable,
+    y: Variable,
+) -> bool {
+    let before = domains[x].len();
+    let y_domain = domains[y].clone();
+    domains[x].retain(|x_value| {
+        y_domain
+            .iter()
+            .any(|y_value| csp.arc_consistent(x, x_value, y, y_value))
+    });
+    domains[x].len() < before
+}
+
+/// Forward checking, section 6.3.2: after assigning value to variable, remove the values
+/// inconsistent with it from the domains of every unassigned neighbour. Returns false if a domain
+/// became empty.
+pub fn forward_check<_Value: Clone + PartialEq>(
+    csp: &Csp<_Value>,
+    domains: &mut [Vec<_Value>],
+    variable: Variable,
+    value: &_Value,
+    assignment: &[Option<_Value>],
+) -> bool {
+    for &neighbour in csp.neighbours(variable) {
+        if assignment[neighbour].is_some() {
+            continue;
+        }
+        domains[neighbour].retain(|neighbour_value| {
+            csp.arc_consistent(variable, value, neighbour, neighbour_value)
+        });
+        if domains[neighbour].is_empty() {
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ac3_square() {
+        // Section 6.2.2: Y = X^2 with both domains 0 to 9.
+        let mut csp = Csp::new();
+        let x = csp.add_variable("X", (0..10).collect());
+        let y = csp.add_variable("Y", (0..10).collect());
+        csp.add_binary(x, y, |x, y| *y == x * x);
+        let mut domains = csp.domains().to_vec();
+        assert!(ac3(&csp, &mut domains));
+        assert_eq!(domains[x], vec![0, 1, 2, 3]);
+        assert_eq!(domains[y], vec![0, 1, 4, 9]);
+    }
+
+    #[test]
+    fn test_ac3_detects_inconsistency() {
+        // Three mutually different variables with only two values.
+        let mut csp = Csp::new();
+        let a = csp.add_variable("A", vec![1, 2]);
+        let b = csp.add_variable("B", vec![1]);
+        let c = csp.add_variable("C", vec![1, 2]);
+        csp.add_all_different(&[a, b, c]);
+        let mut domains = csp.domains().to_vec();
+        assert!(!ac3(&csp, &mut domains));
+    }
+
+    #[test]
+    fn test_ac3_propagates() {
+        // A < B < C over 1 to 3 has the single solution 1, 2, 3.
+        let mut csp = Csp::new();
+        let a = csp.add_variable("A", vec![1, 2, 3]);
+        let b = csp.add_variable("B", vec![1, 2, 3]);
+        let c = csp.add_variable("C", vec![1, 2, 3]);
+        csp.add_binary(a, b, |a, b| a < b);
+        csp.add_binary(b, c, |b, c| b < c);
+        let mut domains = csp.domains().to_vec();
+        assert!(ac3(&csp, &mut domains));
+        assert_eq!(domains, vec![vec![1], vec![2], vec![3]]);
+    }
+
+    #[test]
+    fn test_forward_check() {
+        let mut csp = Csp::new();
+        let a = csp.add_variable("A", vec![1, 2, 3]);
+        let b = csp.add_variable("B", vec![1, 2, 3]);
+        let c = csp.add_variable("C", vec![1, 2, 3]);
+        csp.add_binary(a, b, |a, b| a < b);
+        csp.add_not_equal(a, c);
+        let mut domains = csp.domains().to_vec();
+        let assignment = vec![Some(2), None, None];
+        assert!(forward_check(&csp, &mut domains, a, &2, &assignment));
+        assert_eq!(domains[b], vec![3]);
+        assert_eq!(domains[c], vec![1, 3]);
+        assert!(!forward_check(&csp, &mut domains, a, &3, &assignment));
+    }
+}
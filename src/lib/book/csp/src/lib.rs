@@ -0,0 +1,338 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Constraint satisfaction problems
+//
+// See:
+// -  Chapter 6: Constraint Satisfaction Problems
+
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+pub mod backtracking;
+pub mod inference;
+pub mod map_coloring;
+pub mod sudoku;
+
+/// Variables are identified by their index, in the order they were added.
+pub type Variable = usize;
+
+type Relation<_Value> = Rc<dyn Fn(&_Value, &_Value) -> bool>;
+type Predicate<_Value> = Rc<dyn Fn(&[&_Value]) -> bool>;
+
+/// A constraint restricts the combinations of values that some variables, its scope, can take.
+#[derive(Clone)]
+pub enum Constraint<_Value> {
+    /// Satisfied when relation(value of first, value of second) is true.
+    Binary {
+        first: Variable,
+        second: Variable,
+        relation: Relation<_Value>,
+    },
+
+    /// Satisfied when predicate, given the values of scope in order, is true. A global constraint
+    /// is only checked once every variable in its scope is assigned, and does not take part in
+    /// inference.
+    Global {
+        scope: Vec<Variable>,
+        predicate: Predicate<_Value>,
+    },
+}
+
+impl<_Value> Constraint<_Value> {
+    pub fn scope(&self) -> Vec<Variable> {
+        match self {
+            Constraint::Binary { first, second, .. } => vec![*first, *second],
+            Constraint::Global { scope, .. } => scope.clone(),
+        }
+    }
+}
+
+impl<_Value> fmt::Debug for Constraint<_Value> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Constraint::Binary { first, second, .. } => f
+                .debug_struct("Binary")
+                .field("first", first)
+                .field("second", second)
+                .finish_non_exhaustive(),
+            Constraint::Global { scope, .. } => f
+                .debug_struct("Global")
+                .field("scope", scope)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+/// A constraint satisfaction problem, section 6.1: variables, each with a finite domain of
+/// values, and constraints on the values they can take together. A solution assigns a value to
+/// every variable such that every constraint is satisfied.
+#[derive(Debug, Clone)]
+pub struct Csp<_Value> {
+    names: Vec<String>,
+    domains: Vec<Vec<_Value>>,
+    constraints: Vec<Constraint<_Value>>,
+
+    /// For each ordered pair of variables, the binary constraints between them.
+    arcs: HashMap<(Variable, Variable), Vec<usize>>,
+
+    /// For each variable, the variables that share a constraint with it.
+    neighbours: Vec<Vec<Variable>>,
+}
+
+impl<_Value> Default for Csp<_Value> {
+    fn default() -> Self {
+        Self {
+            names: vec![],
+            domains: vec![],
+            constraints: vec![],
+            arcs: HashMap::new(),
+            neighbours: vec![],
+        }
+    }
+}
+
+impl<_Value: Clone + PartialEq> Csp<_Value> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_variable(&mut self, name: &str, domain: Vec<_Value>) -> Variable {
+        self.names.push(name.to_string());
+        self.domains.push(domain);
+        self.neighbours.push(vec![]);
+        self.names.len() - 1
+    }
+
+    pub fn add_binary<_Relation>(&mut self, first: Variable, second: Variable, relation: _Relation)
+    where
+        _Relation: Fn(&_Value, &_Value) -> bool + 'static,
+    {
+        self.check_variable(first);
+        self.check_variable(second);
+        assert_ne!(first, second, "a binary constraint needs two variables");
+        let index = self.constraints.len();
+        self.constraints.push(Constraint::Binary {
+            first,
+            second,
+            relation: Rc::new(relation),
+        });
+        self.arcs.entry((first, second)).or_default().push(index);
+        self.arcs.entry((second, first)).or_default().push(index);
+        self.connect(&[first, second]);
+    }
+
+    pub fn add_not_equal(&mut self, first: Variable, second: Variable) {
+        self.add_binary(first, second, |a, b| a != b);
+    }
+
+    /// Alldiff: every variable in scope takes a different value. Added as a not-equal constraint
+    /// between each pair, so that it takes part in inference.
+    pub fn add_all_different(&mut self, scope: &[Variable]) {
+        for (i, &first) in scope.iter().enumerate() {
+            for &second in &scope[i + 1..] {
+                self.add_not_equal(first, second);
+            }
+        }
+    }
+
+    pub fn add_global<_Predicate>(&mut self, scope: &[Variable], predicate: _Predicate)
+    where
+        _Predicate: Fn(&[&_Value]) -> bool + 'static,
+    {
+        for &variable in scope {
+            self.check_variable(variable);
+        }
+        self.constraints.push(Constraint::Global {
+            scope: scope.to_vec(),
+            predicate: Rc::new(predicate),
+        });
+        self.connect(scope);
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn name(&self, variable: Variable) -> &str {
+        &self.names[variable]
+    }
+
+    pub fn variable(&self, name: &str) -> Option<Variable> {
+        self.names.iter().position(|n| n == name)
+    }
+
+    pub fn domain(&self, variable: Variable) -> &[_Value] {
+        &self.domains[variable]
+    }
+
+    pub fn domains(&self) -> &[Vec<_Value>] {
+        &self.domains
+    }
+
+    pub fn constraints(&self) -> &[Constraint<_Value>] {
+        &self.constraints
+    }
+
+    pub fn neighbours(&self, variable: Variable) -> &[Variable] {
+        &self.neighbours[variable]
+    }
+
+    /// Whether first = first_value and second = second_value satisfies every binary constraint
+    /// between first and second.
+    pub fn arc_consistent(
+        &self,
+        first: Variable,
+        first_value: &_Value,
+        second: Variable,
+        second_value: &_Value,
+    ) -> bool {
+        let Some(constraints) = self.arcs.get(&(first, second)) else {
+            return true;
+        };
+        constraints
+            .iter()
+            .all(|&index| match &self.constraints[index] {
+                Constraint::Binary {
+                    first: a, relation, ..
+                } => {
+                    if *a == first {
+                        relation(first_value, second_value)
+                    } else {
+                        relation(second_value, first_value)
+                    }
+                }
+                Constraint::Global { .. } => unreachable!("arcs only hold binary constraints"),
+            })
+    }
+
+    /// Whether assigning value to variable is consistent with the rest of a partial assignment:
+    /// every binary constraint with an assigned neighbour, and every global constraint whose scope
+    /// would be fully assigned, is satisfied.
+    pub fn is_consistent(
+        &self,
+        variable: Variable,
+        value: &_Value,
+        assignment: &[Option<_Value>],
+    ) -> bool {
+        let binary_ok = self.neighbours[variable].iter().all(|&neighbour| {
+            assignment[neighbour]
+                .as_ref()
+                .is_none_or(|other| self.arc_consistent(variable, value, neighbour, other))
+        });
+        binary_ok
+            && self.constraints.iter().all(|constraint| match constraint {
+                Constraint::Global { scope, predicate } if scope.contains(&variable) => {
+                    let values: Option<Vec<&_Value>> = scope
+                        .iter()
+                        .map(|&v| {
+                            if v == variable {
+                                Some(value)
+                            } else {
+                                assignment[v].as_ref()
+                            }
+                        })
+                        .collect();
+                    values.is_none_or(|values| predicate(&values))
+                }
+                _ => true,
+            })
+    }
+
+    /// Whether a complete assignment satisfies every constraint.
+    pub fn is_solution(&self, assignment: &[_Value]) -> bool {
+        assignment.len() == self.len()
+            && (0..self.len())
+                .all(|variable| self.domains[variable].contains(&assignment[variable]))
+            && self.constraints.iter().all(|constraint| match constraint {
+                Constraint::Binary {
+                    first,
+                    second,
+                    relation,
+                } => relation(&assignment[*first], &assignment[*second]),
+                Constraint::Global { scope, predicate } => {
+                    let values: Vec<&_Value> = scope.iter().map(|&v| &assignment[v]).collect();
+                    predicate(&values)
+                }
+            })
+    }
+
+    fn check_variable(&self, variable: Variable) {
+        assert!(variable < self.len(), "unknown variable {}", variable);
+    }
+
+    fn connect(&mut self, scope: &[Variable]) {
+        for &a in scope {
+            for &b in scope {
+                if a != b && !self.neighbours[a].contains(&b) {
+                    self.neighbours[a].push(b);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_constraint_either_direction() {
+        let mut csp = Csp::new();
+        let x = csp.add_variable("X", vec![1, 2, 3]);
+        let y = csp.add_variable("Y", vec![1, 2, 3]);
+        csp.add_binary(x, y, |x, y| x < y);
+        assert!(csp.arc_consistent(x, &1, y, &2));
+        assert!(!csp.arc_consistent(x, &2, y, &1));
+        assert!(csp.arc_consistent(y, &2, x, &1));
+        assert!(!csp.arc_consistent(y, &1, x, &2));
+        assert_eq!(csp.neighbours(x), &[y]);
+    }
+
+    #[test]
+    fn test_global_constraint_checked_when_complete() {
+        let mut csp = Csp::new();
+        let x = csp.add_variable("X", vec![1, 2, 3]);
+        let y = csp.add_variable("Y", vec![1, 2, 3]);
+        let z = csp.add_variable("Z", vec![1, 2, 3]);
+        csp.add_global(&[x, y, z], |values| {
+            values.iter().copied().sum::<i32>() == 6
+        });
+        let assignment = vec![Some(3), Some(3), None];
+        assert!(csp.is_consistent(y, &3, &[Some(3), None, None]));
+        assert!(!csp.is_consistent(z, &3, &assignment));
+        assert!(csp.is_consistent(z, &1, &[Some(3), Some(2), None]));
+        assert!(csp.is_solution(&[1, 2, 3]));
+        assert!(!csp.is_solution(&[1, 2, 2]));
+        assert_eq!(csp.variable("Z"), Some(z));
+    }
+
+    #[test]
+    fn test_is_solution_checks_domains() {
+        let mut csp = Csp::new();
+        let x = csp.add_variable("X", vec![1, 2]);
+        let y = csp.add_variable("Y", vec![1, 2]);
+        csp.add_not_equal(x, y);
+        assert!(csp.is_solution(&[1, 2]));
+        assert!(!csp.is_solution(&[1, 3]));
+        assert!(!csp.is_solution(&[1, 1]));
+    }
+}
@@ -0,0 +1,152 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Map coloring: color each region so that no two neighbouring regions have the same color.
+//
+// See:
+// -  Chapter 6: Constraint Satisfaction Problems, section 6.1.1 (Figure 6.1)
+
+use std::fmt;
+
+use crate::Csp;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    Red,
+    Green,
+    Blue,
+}
+
+impl Color {
+    pub const ALL: [Color; 3] = [Color::Red, Color::Green, Color::Blue];
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Map coloring problem for the named regions, where each pair in borders must have different
+/// colors.
+pub fn map_coloring<_Value: Clone + PartialEq>(
+    regions: &[&str],
+    borders: &[(&str, &str)],
+    colors: &[_Value],
+) -> Csp<_Value> {
+    let mut csp = Csp::new();
+    for region in regions {
+        csp.add_variable(region, colors.to_vec());
+    }
+    for (a, b) in borders {
+        let a = csp.variable(a).expect("border of an unknown region");
+        let b = csp.variable(b).expect("border of an unknown region");
+        csp.add_not_equal(a, b);
+    }
+    csp
+}
+
+/// The states and territories of Australia, Figure 6.1.
+pub const AUSTRALIA_REGIONS: [&str; 7] = ["WA", "NT", "SA", "Q", "NSW", "V", "T"];
+
+pub const AUSTRALIA_BORDERS: [(&str, &str); 9] = [
+    ("WA", "NT"),
+    ("WA", "SA"),
+    ("NT", "SA"),
+    ("NT", "Q"),
+    ("SA", "Q"),
+    ("SA", "NSW"),
+    ("SA", "V"),
+    ("Q", "NSW"),
+    ("NSW", "V"),
+];
+
+/// Coloring the map of Australia with red, green and blue.
+pub fn australia() -> Csp<Color> {
+    map_coloring(&AUSTRALIA_REGIONS, &AUSTRALIA_BORDERS, &Color::ALL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtracking::{backtracking_search, Inference, SolverConfig, VariableOrdering};
+    use crate::inference::{ac3, forward_check};
+
+    #[test]
+    fn test_australia() {
+        let csp = australia();
+        assert_eq!(csp.len(), 7);
+        assert_eq!(csp.constraints().len(), 9);
+        // Tasmania is an island.
+        assert!(csp.neighbours(csp.variable("T").unwrap()).is_empty());
+
+        let result = backtracking_search(&csp, SolverConfig::default());
+        let solution = result.solution.unwrap();
+        assert!(csp.is_solution(&solution));
+        // With MRV, the degree heuristic and forward checking, the search never backtracks.
+        assert_eq!(result.stats.backtracks, 0);
+        assert_eq!(result.stats.assignments, 7);
+        // The degree heuristic picks South Australia first, and it gets the first color.
+        assert_eq!(solution[csp.variable("SA").unwrap()], Color::Red);
+    }
+
+    #[test]
+    fn test_forward_checking_figure_6_7() {
+        // Figure 6.7: after WA = red and Q = green, forward checking leaves SA with only blue and
+        // NT with only blue.
+        let csp = australia();
+        let var = |name| csp.variable(name).unwrap();
+        let mut domains = csp.domains().to_vec();
+        let mut assignment = vec![None; csp.len()];
+        assignment[var("WA")] = Some(Color::Red);
+        assert!(forward_check(
+            &csp,
+            &mut domains,
+            var("WA"),
+            &Color::Red,
+            &assignment
+        ));
+        assignment[var("Q")] = Some(Color::Green);
+        assert!(forward_check(
+            &csp,
+            &mut domains,
+            var("Q"),
+            &Color::Green,
+            &assignment
+        ));
+        assert_eq!(domains[var("NT")], vec![Color::Blue]);
+        assert_eq!(domains[var("SA")], vec![Color::Blue]);
+        // AC-3 goes further and sees that NT and SA cannot both be blue.
+        domains[var("WA")] = vec![Color::Red];
+        domains[var("Q")] = vec![Color::Green];
+        assert!(!ac3(&csp, &mut domains));
+    }
+
+    #[test]
+    fn test_two_colors_is_unsatisfiable() {
+        let csp = map_coloring(
+            &AUSTRALIA_REGIONS,
+            &AUSTRALIA_BORDERS,
+            &[Color::Red, Color::Green],
+        );
+        let config = SolverConfig {
+            variable_ordering: VariableOrdering::Static,
+            inference: Inference::MaintainArcConsistency,
+            ..SolverConfig::default()
+        };
+        assert_eq!(backtracking_search(&csp, config).solution, None);
+    }
+}
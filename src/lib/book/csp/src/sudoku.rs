@@ -0,0 +1,252 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Sudoku as a CSP.
+//
+// See:
+// -  Chapter 6: Constraint Satisfaction Problems, section 6.2.6 (Figure 6.4)
+
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::backtracking::{backtracking_search, SolverConfig, SolverStats};
+use crate::{Csp, Variable};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SudokuError {
+    #[error("a puzzle has 81 cells, got {0}")]
+    WrongCellCount(usize),
+
+    #[error("invalid cell {0:?}: use 1 to 9 for a digit, and 0 or . for an empty cell")]
+    InvalidCell(char),
+}
+
+/// A 9 by 9 Sudoku grid, stored row by row, with 0 for an empty cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sudoku {
+    cells: [u8; 81],
+}
+
+impl Sudoku {
+    pub fn cells(&self) -> &[u8; 81] {
+        &self.cells
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.cells.iter().all(|&cell| cell != 0)
+    }
+
+    /// The CSP with one variable per cell, named A1 to I9 by row letter and column number as in
+    /// the book. Given cells have a single value in their domain. Every row, column and 3 by 3 box
+    /// is an Alldiff constraint, added as a not-equal constraint between each pair of cells that
+    /// share a unit.
+    pub fn to_csp(&self) -> Csp<u8> {
+        let mut csp = Csp::new();
+        for (index, &cell) in self.cells.iter().enumerate() {
+            let name = format!("{}{}", (b'A' + (index / 9) as u8) as char, index % 9 + 1);
+            let domain = if cell == 0 {
+                (1..=9).collect()
+            } else {
+                vec![cell]
+            };
+            csp.add_variable(&name, domain);
+        }
+        // Pairs of cells in the same row, column or box, each pair only once.
+        for a in 0..81 {
+            for b in a + 1..81 {
+                if peers(a, b) {
+                    csp.add_not_equal(a, b);
+                }
+            }
+        }
+        csp
+    }
+
+    /// Solve the puzzle with backtracking search. Returns None if it has no solution.
+    pub fn solve(&self, config: SolverConfig) -> (Option<Sudoku>, SolverStats) {
+        let result = backtracking_search(&self.to_csp(), config);
+        let solution = result.solution.map(|values| Sudoku {
+            cells: values.try_into().unwrap(),
+        });
+        (solution, result.stats)
+    }
+}
+
+/// Whether two cells share a row, column or box.
+fn peers(a: Variable, b: Variable) -> bool {
+    let (row_a, column_a) = (a / 9, a % 9);
+    let (row_b, column_b) = (b / 9, b % 9);
+    row_a == row_b
+        || column_a == column_b
+        || (row_a / 3 == row_b / 3 && column_a / 3 == column_b / 3)
+}
+
+/// Parse 81 cells, row by row. Whitespace is ignored, so the puzzle can be written on one line or
+/// as a grid.
+impl FromStr for Sudoku {
+    type Err = SudokuError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let cells: Vec<u8> = s
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| match c {
+                '.' | '0' => Ok(0),
+                '1'..='9' => Ok(c as u8 - b'0'),
+                _ => Err(SudokuError::InvalidCell(c)),
+            })
+            .collect::<Result<_, _>>()?;
+        let count = cells.len();
+        Ok(Sudoku {
+            cells: cells
+                .try_into()
+                .map_err(|_| SudokuError::WrongCellCount(count))?,
+        })
+    }
+}
+
+impl fmt::Display for Sudoku {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (row, cells) in self.cells.chunks(9).enumerate() {
+            if row > 0 && row % 3 == 0 {
+                writeln!(f, "------+-------+------")?;
+            }
+            let groups: Vec<String> = cells
+                .chunks(3)
+                .map(|group| {
+                    group
+                        .iter()
+                        .map(|&cell| match cell {
+                            0 => ".".to_string(),
+                            _ => cell.to_string(),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect();
+            writeln!(f, "{}", groups.join(" | "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtracking::{Inference, ValueOrdering, VariableOrdering};
+    use crate::inference::ac3;
+
+    /// Easy enough that AC-3 alone solves it.
+    const EASY: &str =
+        "..3.2.6..9..3.5..1..18.64....81.29..7.......8..67.82....26.95..8..2.3..9..5.1.3..";
+
+    /// Needs search after AC-3.
+    const HARD: &str =
+        "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......";
+
+    fn assert_solves(puzzle: &Sudoku, solution: &Sudoku) {
+        assert!(solution.is_complete());
+        assert!(puzzle.to_csp().is_solution(solution.cells()));
+        for (given, solved) in puzzle.cells().iter().zip(solution.cells()) {
+            assert!(*given == 0 || given == solved);
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let puzzle: Sudoku = EASY.parse().unwrap();
+        assert_eq!(puzzle.cells()[2], 3);
+        assert_eq!(puzzle.cells()[0], 0);
+        assert_eq!("12".parse::<Sudoku>(), Err(SudokuError::WrongCellCount(2)));
+        assert_eq!(
+            "x".repeat(81).parse::<Sudoku>(),
+            Err(SudokuError::InvalidCell('x'))
+        );
+        // Grids with whitespace parse the same as one line.
+        let grid = puzzle.to_string().replace(['|', '-', '+'], "");
+        assert_eq!(grid.parse::<Sudoku>(), Ok(puzzle));
+    }
+
+    #[test]
+    fn test_csp_shape() {
+        let csp = Sudoku::from_str(EASY).unwrap().to_csp();
+        assert_eq!(csp.len(), 81);
+        assert_eq!(csp.name(0), "A1");
+        assert_eq!(csp.name(80), "I9");
+        // Each cell has 20 peers, and each constraint is shared by two cells.
+        assert_eq!(csp.constraints().len(), 81 * 20 / 2);
+        assert!((0..81).all(|cell| csp.neighbours(cell).len() == 20));
+    }
+
+    #[test]
+    fn test_ac3_solves_easy_puzzle() {
+        let csp = Sudoku::from_str(EASY).unwrap().to_csp();
+        let mut domains = csp.domains().to_vec();
+        assert!(ac3(&csp, &mut domains));
+        assert!(domains.iter().all(|domain| domain.len() == 1));
+        let values: Vec<u8> = domains.iter().map(|domain| domain[0]).collect();
+        assert!(csp.is_solution(&values));
+    }
+
+    #[test]
+    fn test_solve_hard_puzzle() {
+        let puzzle: Sudoku = HARD.parse().unwrap();
+        let (solution, stats) = puzzle.solve(SolverConfig {
+            inference: Inference::MaintainArcConsistency,
+            preprocess_with_ac3: true,
+            ..SolverConfig::default()
+        });
+        assert_solves(&puzzle, &solution.unwrap());
+        assert!(stats.assignments > 0);
+    }
+
+    #[test]
+    fn test_solve_with_forward_checking() {
+        let puzzle: Sudoku = EASY.parse().unwrap();
+        let (solution, stats) = puzzle.solve(SolverConfig {
+            variable_ordering: VariableOrdering::MinimumRemainingValues,
+            value_ordering: ValueOrdering::Static,
+            inference: Inference::ForwardChecking,
+            preprocess_with_ac3: false,
+        });
+        assert_solves(&puzzle, &solution.unwrap());
+        assert_eq!(stats.backtracks, 0);
+    }
+
+    #[test]
+    fn test_unsolvable_puzzle() {
+        // Two 1s in the first row.
+        let mut cells = ".".repeat(81);
+        cells.replace_range(0..2, "11");
+        let puzzle: Sudoku = cells.parse().unwrap();
+        let (solution, _) = puzzle.solve(SolverConfig {
+            preprocess_with_ac3: true,
+            ..SolverConfig::default()
+        });
+        assert_eq!(solution, None);
+    }
+
+    #[test]
+    fn test_display() {
+        let puzzle: Sudoku = EASY.parse().unwrap();
+        let lines: Vec<String> = puzzle.to_string().lines().map(String::from).collect();
+        assert_eq!(lines.len(), 11);
+        assert_eq!(lines[0], ". . 3 | . 2 . | 6 . .");
+        assert_eq!(lines[3], "------+-------+------");
+    }
+}
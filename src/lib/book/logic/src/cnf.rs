@@ -0,0 +1,212 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Conjunctive normal form
+//
+// See:
+// -  Chapter 7: Logical Agents, section 7.5.2
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::{Model, Sentence};
+
+/// A proposition symbol or its negation.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Literal {
+    pub symbol: String,
+    pub positive: bool,
+}
+
+impl Literal {
+    pub fn positive(symbol: &str) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            positive: true,
+        }
+    }
+
+    pub fn negative(symbol: &str) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            positive: false,
+        }
+    }
+
+    pub fn negated(&self) -> Self {
+        Self {
+            symbol: self.symbol.clone(),
+            positive: !self.positive,
+        }
+    }
+
+    /// Truth value in model, or None if the symbol is unassigned.
+    pub fn evaluate(&self, model: &Model) -> Option<bool> {
+        model.get(&self.symbol).map(|&value| value == self.positive)
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.positive {
+            write!(f, "{}", self.symbol)
+        } else {
+            write!(f, "~{}", self.symbol)
+        }
+    }
+}
+
+/// A disjunction of literals. The empty clause is false.
+pub type Clause = BTreeSet<Literal>;
+
+/// A conjunction of clauses. The empty set of clauses is true.
+pub type Cnf = BTreeSet<Clause>;
+
+/// Convert a sentence into an equivalent set of clauses, using the steps of section 7.5.2:
+/// eliminate biconditionals and implications, move negation inwards to the literals, and
+/// distribute or over and. Clauses that contain both a literal and its negation are always true,
+/// and are dropped.
+///
+/// Distributing or over and can make the result exponentially larger than the sentence.
+pub fn to_cnf(sentence: &Sentence) -> Cnf {
+    clauses(sentence, true)
+}
+
+/// Clauses of the sentence, or of its negation if positive is false. Handling negation here
+/// moves it inwards without building the negation normal form.
+fn clauses(sentence: &Sentence, positive: bool) -> Cnf {
+    match (sentence, positive) {
+        (Sentence::True, true) | (Sentence::False, false) => Cnf::new(),
+        (Sentence::True, false) | (Sentence::False, true) => Cnf::from([Clause::new()]),
+        (Sentence::Symbol(symbol), _) => Cnf::from([Clause::from([Literal {
+            symbol: symbol.clone(),
+            positive,
+        }])]),
+        (Sentence::Not(s), _) => clauses(s, !positive),
+        // A & B, or ~(A | B) = ~A & ~B.
+        (Sentence::And(a, b), true) | (Sentence::Or(a, b), false) => {
+            let mut result = clauses(a, positive);
+            result.extend(clauses(b, positive));
+            result
+        }
+        // A | B, or ~(A & B) = ~A | ~B.
+        (Sentence::And(a, b), false) | (Sentence::Or(a, b), true) => {
+            distribute(&clauses(a, positive), &clauses(b, positive))
+        }
+        // A => B = ~A | B.
+        (Sentence::Implies(a, b), true) => distribute(&clauses(a, false), &clauses(b, true)),
+        // ~(A => B) = A & ~B.
+        (Sentence::Implies(a, b), false) => {
+            let mut result = clauses(a, true);
+            result.extend(clauses(b, false));
+            result
+        }
+        // A <=> B = (A => B) & (B => A) = (~A | B) & (A | ~B).
+        (Sentence::Iff(a, b), true) => {
+            let mut result = distribute(&clauses(a, false), &clauses(b, true));
+            result.extend(distribute(&clauses(a, true), &clauses(b, false)));
+            result
+        }
+        // ~(A <=> B) = (A | B) & (~A | ~B).
+        (Sentence::Iff(a, b), false) => {
+            let mut result = distribute(&clauses(a, true), &clauses(b, true));
+            result.extend(distribute(&clauses(a, false), &clauses(b, false)));
+            result
+        }
+    }
+}
+
+/// The clauses of (a1 & a2 & ...) | (b1 & b2 & ...), which are every ai | bj.
+fn distribute(a: &Cnf, b: &Cnf) -> Cnf {
+    let mut result = Cnf::new();
+    for a_clause in a {
+        for b_clause in b {
+            let clause: Clause = a_clause.union(b_clause).cloned().collect();
+            if !is_tautology(&clause) {
+                result.insert(clause);
+            }
+        }
+    }
+    result
+}
+
+/// Whether clause contains some literal and its negation.
+pub fn is_tautology(clause: &Clause) -> bool {
+    clause
+        .iter()
+        .any(|literal| literal.positive && clause.contains(&literal.negated()))
+}
+
+/// The clauses as a sentence, for printing and checking.
+pub fn to_sentence(cnf: &Cnf) -> Sentence {
+    Sentence::conjunction(cnf.iter().map(|clause| {
+        Sentence::disjunction(clause.iter().map(|literal| {
+            let symbol = Sentence::symbol(&literal.symbol);
+            if literal.positive {
+                symbol
+            } else {
+                symbol.not()
+            }
+        }))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::parser::parse;
+    use crate::test_sentences::{all_models, arbitrary_sentence};
+
+    #[test]
+    fn test_book_example() {
+        // Section 7.5.2: B11 <=> (P12 | P21) becomes
+        // (~B11 | P12 | P21) & (~P12 | B11) & (~P21 | B11).
+        let cnf = to_cnf(&parse("B11 <=> (P12 | P21)").unwrap());
+        let expected = Cnf::from([
+            Clause::from([
+                Literal::negative("B11"),
+                Literal::positive("P12"),
+                Literal::positive("P21"),
+            ]),
+            Clause::from([Literal::negative("P12"), Literal::positive("B11")]),
+            Clause::from([Literal::negative("P21"), Literal::positive("B11")]),
+        ]);
+        assert_eq!(cnf, expected);
+    }
+
+    #[test]
+    fn test_constants_and_tautologies() {
+        assert_eq!(to_cnf(&Sentence::True), Cnf::new());
+        assert_eq!(to_cnf(&Sentence::False), Cnf::from([Clause::new()]));
+        assert_eq!(to_cnf(&parse("A | ~A").unwrap()), Cnf::new());
+        assert_eq!(to_sentence(&Cnf::new()), Sentence::True);
+    }
+
+    proptest! {
+        #[test]
+        fn test_cnf_is_equivalent(sentence in arbitrary_sentence()) {
+            let cnf = to_sentence(&to_cnf(&sentence));
+            for model in all_models(&sentence.symbols()) {
+                prop_assert_eq!(
+                    cnf.evaluate(&model).unwrap(),
+                    sentence.evaluate(&model).unwrap()
+                );
+            }
+        }
+    }
+}
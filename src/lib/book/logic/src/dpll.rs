@@ -0,0 +1,260 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// The Davis-Putnam-Logemann-Loveland (DPLL) satisfiability algorithm.
+//
+// See:
+// -  Chapter 7: Logical Agents, section 7.6.1 (Figure 7.17)
+
+use crate::cnf::{to_cnf, Clause, Cnf, Literal};
+use crate::{Model, Sentence};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DpllStats {
+    /// Symbols assigned by branching on both values.
+    pub branches: usize,
+
+    /// Symbols assigned because they were the last unassigned literal in a clause.
+    pub unit_propagations: usize,
+
+    /// Symbols assigned because they appear with the same sign in every clause not yet true.
+    pub pure_symbols: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DpllResult {
+    /// A model of every symbol in the clauses in which they are all true, or None if the
+    /// clauses are unsatisfiable.
+    pub model: Option<Model>,
+    pub stats: DpllStats,
+}
+
+/// DPLL, Figure 7.17: a depth-first backtracking search over partial models, which stops
+/// early once every clause is true or some clause is false, and before branching assigns pure
+/// symbols and unit clauses, whose values are forced.
+pub fn dpll(clauses: &Cnf) -> DpllResult {
+    let clauses: Vec<&Clause> = clauses.iter().collect();
+    let symbols: Vec<String> = clauses
+        .iter()
+        .flat_map(|clause| clause.iter().map(|literal| literal.symbol.clone()))
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let mut stats = DpllStats::default();
+    let mut model = Model::new();
+    let model = if search(&clauses, &symbols, &mut model, &mut stats) {
+        // Symbols left unassigned do not matter, so give them any value.
+        for symbol in symbols {
+            model.entry(symbol).or_insert(false);
+        }
+        Some(model)
+    } else {
+        None
+    };
+    DpllResult { model, stats }
+}
+
+/// A model of sentence found with DPLL, or None if it is unsatisfiable.
+pub fn dpll_satisfiable(sentence: &Sentence) -> Option<Model> {
+    let mut model = dpll(&to_cnf(sentence)).model?;
+    // Converting to CNF may drop symbols that do not matter.
+    for symbol in sentence.symbols() {
+        model.entry(symbol).or_insert(false);
+    }
+    Some(model)
+}
+
+/// kb entails alpha if kb & ~alpha is unsatisfiable, section 7.5.
+pub fn dpll_entails(kb: &Sentence, alpha: &Sentence) -> bool {
+    dpll_satisfiable(&kb.clone().and(alpha.clone().not())).is_none()
+}
+
+/// Truth value of a clause in a partial model, or None if it is not yet decided.
+fn clause_value(clause: &Clause, model: &Model) -> Option<bool> {
+    let mut undecided = false;
+    for literal in clause {
+        match literal.evaluate(model) {
+            Some(true) => return Some(true),
+            Some(false) => {}
+            None => undecided = true,
+        }
+    }
+    if undecided {
+        None
+    } else {
+        Some(false)
+    }
+}
+
+fn search(
+    clauses: &[&Clause],
+    symbols: &[String],
+    model: &mut Model,
+    stats: &mut DpllStats,
+) -> bool {
+    let mut open = vec![];
+    for clause in clauses {
+        match clause_value(clause, model) {
+            Some(true) => {}
+            Some(false) => return false,
+            None => open.push(*clause),
+        }
+    }
+    if open.is_empty() {
+        return true;
+    }
+
+    if let Some(literal) = find_pure_symbol(&open, model) {
+        stats.pure_symbols += 1;
+        return assign_and_search(clauses, symbols, model, stats, &literal);
+    }
+    if let Some(literal) = find_unit_clause(&open, model) {
+        stats.unit_propagations += 1;
+        return assign_and_search(clauses, symbols, model, stats, &literal);
+    }
+
+    let symbol = symbols
+        .iter()
+        .find(|symbol| !model.contains_key(*symbol))
+        .expect("an open clause has an unassigned symbol");
+    stats.branches += 1;
+    for positive in [true, false] {
+        let literal = Literal {
+            symbol: symbol.clone(),
+            positive,
+        };
+        if assign_and_search(clauses, symbols, model, stats, &literal) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Make literal true, search, and undo the assignment if the search fails.
+fn assign_and_search(
+    clauses: &[&Clause],
+    symbols: &[String],
+    model: &mut Model,
+    stats: &mut DpllStats,
+    literal: &Literal,
+) -> bool {
+    model.insert(literal.symbol.clone(), literal.positive);
+    if search(clauses, symbols, model, stats) {
+        return true;
+    }
+    model.remove(&literal.symbol);
+    false
+}
+
+/// An unassigned symbol that appears with the same sign in every open clause it is in. Making it
+/// true can never make a clause false.
+fn find_pure_symbol(open: &[&Clause], model: &Model) -> Option<Literal> {
+    let unassigned = || {
+        open.iter()
+            .flat_map(|clause| clause.iter())
+            .filter(|literal| !model.contains_key(&literal.symbol))
+    };
+    unassigned()
+        .find(|literal| !unassigned().any(|other| *other == literal.negated()))
+        .cloned()
+}
+
+/// The only unassigned literal of an open clause whose other literals are all false. It must be
+/// made true.
+fn find_unit_clause(open: &[&Clause], model: &Model) -> Option<Literal> {
+    open.iter().find_map(|clause| {
+        let mut unassigned = clause
+            .iter()
+            .filter(|literal| !model.contains_key(&literal.symbol));
+        match (unassigned.next(), unassigned.next()) {
+            (Some(literal), None) => Some(literal.clone()),
+            _ => None,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::parser::parse;
+    use crate::test_sentences::arbitrary_sentence;
+    use crate::truth_table::{tt_entails, tt_satisfiable};
+
+    #[test]
+    fn test_unit_propagation() {
+        // A, and A => B, and B => C: every symbol is forced, with no branching.
+        let result = dpll(&to_cnf(
+            &parse("A & (A => B) & (B => C) & (C | D) & (~D | ~C)").unwrap(),
+        ));
+        let model = result.model.unwrap();
+        assert!(model["A"] && model["B"] && model["C"] && !model["D"]);
+        assert_eq!(result.stats.branches, 0);
+        assert!(result.stats.unit_propagations > 0);
+    }
+
+    #[test]
+    fn test_pure_symbol() {
+        let result = dpll(&to_cnf(&parse("(A | B) & (A | ~C)").unwrap()));
+        assert!(result.model.unwrap()["A"]);
+        assert_eq!(result.stats.pure_symbols, 1);
+        assert_eq!(result.stats.branches, 0);
+    }
+
+    #[test]
+    fn test_unsatisfiable() {
+        let sentence = parse("(A | B) & (~A | B) & (A | ~B) & (~A | ~B)").unwrap();
+        assert_eq!(dpll_satisfiable(&sentence), None);
+        assert_eq!(dpll(&to_cnf(&Sentence::False)).model, None);
+        assert_eq!(dpll(&Cnf::new()).model, Some(Model::new()));
+    }
+
+    #[test]
+    fn test_wumpus_entailment() {
+        let kb = parse("~P11 & (B11 <=> (P12 | P21)) & (B21 <=> (P11 | P22 | P31)) & ~B11 & B21")
+            .unwrap();
+        assert!(dpll_entails(&kb, &parse("~P12").unwrap()));
+        assert!(dpll_entails(&kb, &parse("P22 | P31").unwrap()));
+        assert!(!dpll_entails(&kb, &parse("P22").unwrap()));
+    }
+
+    #[test]
+    fn test_model_covers_every_symbol() {
+        // B does not matter, and disappears from the CNF.
+        let model = dpll_satisfiable(&parse("A & (B | ~B)").unwrap()).unwrap();
+        assert_eq!(model.len(), 2);
+    }
+
+    proptest! {
+        #[test]
+        fn test_agrees_with_truth_table(sentence in arbitrary_sentence()) {
+            let model = dpll_satisfiable(&sentence);
+            prop_assert_eq!(model.is_some(), tt_satisfiable(&sentence).is_some());
+            if let Some(model) = model {
+                prop_assert_eq!(sentence.evaluate(&model), Some(true));
+            }
+        }
+
+        #[test]
+        fn test_entailment_agrees_with_truth_table(
+            kb in arbitrary_sentence(),
+            alpha in arbitrary_sentence(),
+        ) {
+            prop_assert_eq!(dpll_entails(&kb, &alpha), tt_entails(&kb, &alpha));
+        }
+    }
+}
@@ -0,0 +1,308 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Propositional logic
+//
+// See:
+// -  Chapter 7: Logical Agents, sections 7.4 to 7.6
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+pub mod cnf;
+pub mod dpll;
+pub mod parser;
+pub mod truth_table;
+
+/// A model assigns true or false to proposition symbols.
+pub type Model = HashMap<String, bool>;
+
+/// A sentence of propositional logic, with the syntax of Figure 7.7.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Sentence {
+    True,
+    False,
+    Symbol(String),
+    Not(Box<Sentence>),
+    And(Box<Sentence>, Box<Sentence>),
+    Or(Box<Sentence>, Box<Sentence>),
+    Implies(Box<Sentence>, Box<Sentence>),
+    Iff(Box<Sentence>, Box<Sentence>),
+}
+
+impl Sentence {
+    pub fn symbol(name: &str) -> Sentence {
+        Sentence::Symbol(name.to_string())
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Sentence {
+        Sentence::Not(Box::new(self))
+    }
+
+    pub fn and(self, other: Sentence) -> Sentence {
+        Sentence::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Sentence) -> Sentence {
+        Sentence::Or(Box::new(self), Box::new(other))
+    }
+
+    pub fn implies(self, other: Sentence) -> Sentence {
+        Sentence::Implies(Box::new(self), Box::new(other))
+    }
+
+    pub fn iff(self, other: Sentence) -> Sentence {
+        Sentence::Iff(Box::new(self), Box::new(other))
+    }
+
+    /// The conjunction of all the sentences, or True if there are none.
+    pub fn conjunction<_Sentences: IntoIterator<Item = Sentence>>(
+        sentences: _Sentences,
+    ) -> Sentence {
+        sentences
+            .into_iter()
+            .reduce(Sentence::and)
+            .unwrap_or(Sentence::True)
+    }
+
+    /// The disjunction of all the sentences, or False if there are none.
+    pub fn disjunction<_Sentences: IntoIterator<Item = Sentence>>(
+        sentences: _Sentences,
+    ) -> Sentence {
+        sentences
+            .into_iter()
+            .reduce(Sentence::or)
+            .unwrap_or(Sentence::False)
+    }
+
+    /// Every proposition symbol in the sentence, in sorted order.
+    pub fn symbols(&self) -> BTreeSet<String> {
+        let mut symbols = BTreeSet::new();
+        self.collect_symbols(&mut symbols);
+        symbols
+    }
+
+    fn collect_symbols(&self, symbols: &mut BTreeSet<String>) {
+        match self {
+            Sentence::True | Sentence::False => {}
+            Sentence::Symbol(name) => {
+                symbols.insert(name.clone());
+            }
+            Sentence::Not(s) => s.collect_symbols(symbols),
+            Sentence::And(a, b)
+            | Sentence::Or(a, b)
+            | Sentence::Implies(a, b)
+            | Sentence::Iff(a, b) => {
+                a.collect_symbols(symbols);
+                b.collect_symbols(symbols);
+            }
+        }
+    }
+
+    /// Truth value of the sentence in model, using the truth tables of Figure 7.8. Returns None
+    /// if the model does not assign a symbol the value depends on.
+    pub fn evaluate(&self, model: &Model) -> Option<bool> {
+        match self {
+            Sentence::True => Some(true),
+            Sentence::False => Some(false),
+            Sentence::Symbol(name) => model.get(name).copied(),
+            Sentence::Not(s) => s.evaluate(model).map(|value| !value),
+            // And and Or short-circuit, so a partial model can still decide them.
+            Sentence::And(a, b) => match (a.evaluate(model), b.evaluate(model)) {
+                (Some(false), _) | (_, Some(false)) => Some(false),
+                (Some(true), Some(true)) => Some(true),
+                _ => None,
+            },
+            Sentence::Or(a, b) => match (a.evaluate(model), b.evaluate(model)) {
+                (Some(true), _) | (_, Some(true)) => Some(true),
+                (Some(false), Some(false)) => Some(false),
+                _ => None,
+            },
+            Sentence::Implies(a, b) => match (a.evaluate(model), b.evaluate(model)) {
+                (Some(false), _) | (_, Some(true)) => Some(true),
+                (Some(true), Some(false)) => Some(false),
+                _ => None,
+            },
+            Sentence::Iff(a, b) => Some(a.evaluate(model)? == b.evaluate(model)?),
+        }
+    }
+
+    /// Binding strength for printing, from the operator precedence of section 7.4.1: not binds
+    /// tightest, then and, or, implies and iff.
+    fn precedence(&self) -> u8 {
+        match self {
+            Sentence::True | Sentence::False | Sentence::Symbol(_) | Sentence::Not(_) => 5,
+            Sentence::And(..) => 4,
+            Sentence::Or(..) => 3,
+            Sentence::Implies(..) => 2,
+            Sentence::Iff(..) => 1,
+        }
+    }
+}
+
+/// Prints with the ASCII operators ~, &, |, => and <=>, adding only the parentheses needed for
+/// the result to parse back to the same sentence. And, or and iff group to the left, and
+/// implies to the right.
+impl fmt::Display for Sentence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operand = |f: &mut fmt::Formatter<'_>, s: &Sentence, parenthesize: bool| {
+            if parenthesize {
+                write!(f, "({})", s)
+            } else {
+                write!(f, "{}", s)
+            }
+        };
+        let binary = |f: &mut fmt::Formatter<'_>,
+                      a: &Sentence,
+                      op: &str,
+                      b: &Sentence,
+                      right_associative: bool| {
+            let p = self.precedence();
+            operand(
+                f,
+                a,
+                a.precedence() < p || (right_associative && a.precedence() == p),
+            )?;
+            write!(f, " {} ", op)?;
+            operand(
+                f,
+                b,
+                b.precedence() < p || (!right_associative && b.precedence() == p),
+            )
+        };
+        match self {
+            Sentence::True => write!(f, "True"),
+            Sentence::False => write!(f, "False"),
+            Sentence::Symbol(name) => write!(f, "{}", name),
+            Sentence::Not(s) => {
+                write!(f, "~")?;
+                operand(f, s, s.precedence() < self.precedence())
+            }
+            Sentence::And(a, b) => binary(f, a, "&", b, false),
+            Sentence::Or(a, b) => binary(f, a, "|", b, false),
+            Sentence::Implies(a, b) => binary(f, a, "=>", b, true),
+            Sentence::Iff(a, b) => binary(f, a, "<=>", b, false),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_sentences {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    /// Random sentences over the symbols A to D, for property tests.
+    pub(crate) fn arbitrary_sentence() -> impl Strategy<Value = Sentence> {
+        let leaf = prop_oneof![
+            1 => Just(Sentence::True),
+            1 => Just(Sentence::False),
+            8 => prop::sample::select(vec!["A", "B", "C", "D"]).prop_map(Sentence::symbol),
+        ];
+        leaf.prop_recursive(4, 32, 2, |inner| {
+            prop_oneof![
+                inner.clone().prop_map(Sentence::not),
+                (inner.clone(), inner.clone()).prop_map(|(a, b)| a.and(b)),
+                (inner.clone(), inner.clone()).prop_map(|(a, b)| a.or(b)),
+                (inner.clone(), inner.clone()).prop_map(|(a, b)| a.implies(b)),
+                (inner.clone(), inner).prop_map(|(a, b)| a.iff(b)),
+            ]
+        })
+    }
+
+    /// Every model over the given symbols.
+    pub(crate) fn all_models(symbols: &BTreeSet<String>) -> Vec<Model> {
+        let symbols: Vec<&String> = symbols.iter().collect();
+        (0..1u32 << symbols.len())
+            .map(|bits| {
+                symbols
+                    .iter()
+                    .enumerate()
+                    .map(|(i, symbol)| ((*symbol).clone(), bits & (1 << i) != 0))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate() {
+        let p = Sentence::symbol("P");
+        let q = Sentence::symbol("Q");
+        let model = Model::from([("P".to_string(), true), ("Q".to_string(), false)]);
+        assert_eq!(p.clone().and(q.clone()).evaluate(&model), Some(false));
+        assert_eq!(p.clone().or(q.clone()).evaluate(&model), Some(true));
+        assert_eq!(p.clone().implies(q.clone()).evaluate(&model), Some(false));
+        assert_eq!(q.clone().implies(p.clone()).evaluate(&model), Some(true));
+        assert_eq!(p.clone().iff(q.clone()).evaluate(&model), Some(false));
+        assert_eq!(q.clone().not().evaluate(&model), Some(true));
+
+        // Partial models decide sentences when the missing symbols do not matter.
+        let partial = Model::from([("P".to_string(), false)]);
+        let r = Sentence::symbol("R");
+        assert_eq!(p.clone().and(r.clone()).evaluate(&partial), Some(false));
+        assert_eq!(p.or(r).evaluate(&partial), None);
+    }
+
+    #[test]
+    fn test_symbols() {
+        let sentence = Sentence::symbol("B")
+            .iff(Sentence::symbol("A").or(Sentence::symbol("B").not()))
+            .and(Sentence::True);
+        assert_eq!(
+            sentence.symbols().into_iter().collect::<Vec<_>>(),
+            vec!["A", "B"]
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let [a, b, c] = ["A", "B", "C"].map(Sentence::symbol);
+        assert_eq!(
+            a.clone().and(b.clone().or(c.clone())).to_string(),
+            "A & (B | C)"
+        );
+        assert_eq!(
+            a.clone().and(b.clone()).or(c.clone()).to_string(),
+            "A & B | C"
+        );
+        assert_eq!(
+            a.clone().implies(b.clone().implies(c.clone())).to_string(),
+            "A => B => C"
+        );
+        assert_eq!(
+            a.clone().implies(b.clone()).implies(c.clone()).to_string(),
+            "(A => B) => C"
+        );
+        assert_eq!(a.clone().and(b.clone()).not().to_string(), "~(A & B)");
+        assert_eq!(a.not().not().to_string(), "~~A");
+    }
+
+    #[test]
+    fn test_conjunction_and_disjunction() {
+        assert_eq!(Sentence::conjunction(vec![]), Sentence::True);
+        assert_eq!(Sentence::disjunction(vec![]), Sentence::False);
+        assert_eq!(
+            Sentence::conjunction(["A", "B", "C"].map(Sentence::symbol)).to_string(),
+            "A & B & C"
+        );
+    }
+}
@@ -0,0 +1,302 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Parsing propositional sentences from text.
+
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::Sentence;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ParseError {
+    #[error("unexpected character {character:?} at position {position}")]
+    UnexpectedCharacter { character: char, position: usize },
+
+    #[error("unexpected {found} at position {position}, expected {expected}")]
+    UnexpectedToken {
+        found: String,
+        position: usize,
+        expected: &'static str,
+    },
+
+    #[error("unexpected end of input, expected {expected}")]
+    UnexpectedEnd { expected: &'static str },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Symbol(String),
+    Not,
+    And,
+    Or,
+    Implies,
+    Iff,
+    Open,
+    Close,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Symbol(name) => format!("symbol {}", name),
+            Token::Not => "'~'".to_string(),
+            Token::And => "'&'".to_string(),
+            Token::Or => "'|'".to_string(),
+            Token::Implies => "'=>'".to_string(),
+            Token::Iff => "'<=>'".to_string(),
+            Token::Open => "'('".to_string(),
+            Token::Close => "')'".to_string(),
+        }
+    }
+}
+
+/// Split text into tokens, each with its character position.
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let rest: String = chars[i..chars.len().min(i + 3)].iter().collect();
+        let (token, length) = if c.is_whitespace() {
+            i += 1;
+            continue;
+        } else if c.is_alphanumeric() || c == '_' {
+            let length = chars[i..]
+                .iter()
+                .take_while(|c| c.is_alphanumeric() || **c == '_')
+                .count();
+            (Token::Symbol(chars[i..i + length].iter().collect()), length)
+        } else if rest.starts_with("<=>") {
+            (Token::Iff, 3)
+        } else if rest.starts_with("=>") {
+            (Token::Implies, 2)
+        } else {
+            let token = match c {
+                '~' | '!' | '¬' => Token::Not,
+                '&' | '∧' => Token::And,
+                '|' | '∨' => Token::Or,
+                '⇒' => Token::Implies,
+                '⇔' => Token::Iff,
+                '(' => Token::Open,
+                ')' => Token::Close,
+                _ => {
+                    return Err(ParseError::UnexpectedCharacter {
+                        character: c,
+                        position: i,
+                    })
+                }
+            };
+            (token, 1)
+        };
+        tokens.push((token, i));
+        i += length;
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser, one method per precedence level.
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(token, _)| token)
+    }
+
+    fn accept(&mut self, token: Token) -> bool {
+        if self.peek() == Some(&token) {
+            self.next += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn unexpected(&self, expected: &'static str) -> ParseError {
+        match self.tokens.get(self.next) {
+            Some((token, position)) => ParseError::UnexpectedToken {
+                found: token.describe(),
+                position: *position,
+                expected,
+            },
+            None => ParseError::UnexpectedEnd { expected },
+        }
+    }
+
+    fn iff(&mut self) -> Result<Sentence, ParseError> {
+        let mut sentence = self.implies()?;
+        while self.accept(Token::Iff) {
+            sentence = sentence.iff(self.implies()?);
+        }
+        Ok(sentence)
+    }
+
+    fn implies(&mut self) -> Result<Sentence, ParseError> {
+        let sentence = self.or()?;
+        if self.accept(Token::Implies) {
+            Ok(sentence.implies(self.implies()?))
+        } else {
+            Ok(sentence)
+        }
+    }
+
+    fn or(&mut self) -> Result<Sentence, ParseError> {
+        let mut sentence = self.and()?;
+        while self.accept(Token::Or) {
+            sentence = sentence.or(self.and()?);
+        }
+        Ok(sentence)
+    }
+
+    fn and(&mut self) -> Result<Sentence, ParseError> {
+        let mut sentence = self.unary()?;
+        while self.accept(Token::And) {
+            sentence = sentence.and(self.unary()?);
+        }
+        Ok(sentence)
+    }
+
+    fn unary(&mut self) -> Result<Sentence, ParseError> {
+        if self.accept(Token::Not) {
+            return Ok(self.unary()?.not());
+        }
+        if self.accept(Token::Open) {
+            let sentence = self.iff()?;
+            if !self.accept(Token::Close) {
+                return Err(self.unexpected("')'"));
+            }
+            return Ok(sentence);
+        }
+        match self.peek() {
+            Some(Token::Symbol(name)) => {
+                let sentence = match name.as_str() {
+                    "True" => Sentence::True,
+                    "False" => Sentence::False,
+                    _ => Sentence::Symbol(name.clone()),
+                };
+                self.next += 1;
+                Ok(sentence)
+            }
+            _ => Err(self.unexpected("a symbol, '~' or '('")),
+        }
+    }
+}
+
+/// Parse a sentence. Operators can be written in ASCII, ~ (or !), &, |, => and <=>, or with the
+/// book's symbols ¬, ∧, ∨, ⇒ and ⇔. Symbols are letters, digits and underscores, and True and
+/// False are the constants. Precedence follows section 7.4.1, from tightest to loosest: not, and,
+/// or, implies, iff. And, or and iff group to the left, and implies groups to the right.
+pub fn parse(text: &str) -> Result<Sentence, ParseError> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        next: 0,
+    };
+    let sentence = parser.iff()?;
+    if parser.peek().is_some() {
+        return Err(parser.unexpected("an operator or the end of input"));
+    }
+    Ok(sentence)
+}
+
+impl FromStr for Sentence {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::test_sentences::arbitrary_sentence;
+
+    #[test]
+    fn test_precedence() {
+        let [a, b, c] = ["A", "B", "C"].map(Sentence::symbol);
+        assert_eq!(
+            parse("~A & B | C").unwrap(),
+            a.clone().not().and(b.clone()).or(c.clone())
+        );
+        assert_eq!(
+            parse("A => B => C").unwrap(),
+            a.clone().implies(b.clone().implies(c.clone()))
+        );
+        assert_eq!(
+            parse("A | B <=> C").unwrap(),
+            a.clone().or(b.clone()).iff(c.clone())
+        );
+        assert_eq!(
+            parse("(A | B) & C").unwrap(),
+            a.clone().or(b.clone()).and(c.clone())
+        );
+        assert_eq!(
+            parse("¬A ∧ B ⇒ True").unwrap(),
+            a.not().and(b).implies(Sentence::True)
+        );
+    }
+
+    #[test]
+    fn test_wumpus_sentences() {
+        // R2 from section 7.4.3.
+        let r2 = parse("B11 <=> (P12 | P21)").unwrap();
+        assert_eq!(r2.symbols().len(), 3);
+        assert_eq!(r2.to_string(), "B11 <=> P12 | P21");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            parse("A $ B"),
+            Err(ParseError::UnexpectedCharacter {
+                character: '$',
+                position: 2
+            })
+        );
+        assert_eq!(
+            parse("A &"),
+            Err(ParseError::UnexpectedEnd {
+                expected: "a symbol, '~' or '('"
+            })
+        );
+        assert_eq!(
+            parse("(A"),
+            Err(ParseError::UnexpectedEnd { expected: "')'" })
+        );
+        assert_eq!(
+            parse("A B"),
+            Err(ParseError::UnexpectedToken {
+                found: "symbol B".to_string(),
+                position: 2,
+                expected: "an operator or the end of input"
+            })
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_display_round_trips(sentence in arbitrary_sentence()) {
+            prop_assert_eq!(parse(&sentence.to_string()).unwrap(), sentence);
+        }
+    }
+}
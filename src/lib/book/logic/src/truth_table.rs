@@ -0,0 +1,109 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Model checking by enumerating truth tables.
+//
+// See:
+// -  Chapter 7: Logical Agents, section 7.4.4 (Figure 7.10)
+
+use crate::{Model, Sentence};
+
+/// TT-Entails, Figure 7.10: kb entails alpha if alpha is true in every model in which kb is
+/// true. Checks all 2^n models of the n symbols, so it is only practical for small n.
+pub fn tt_entails(kb: &Sentence, alpha: &Sentence) -> bool {
+    let mut symbols: Vec<String> = kb.symbols().union(&alpha.symbols()).cloned().collect();
+    symbols.reverse();
+    tt_check_all(kb, alpha, &mut symbols, &mut Model::new())
+}
+
+fn tt_check_all(
+    kb: &Sentence,
+    alpha: &Sentence,
+    symbols: &mut Vec<String>,
+    model: &mut Model,
+) -> bool {
+    let Some(symbol) = symbols.pop() else {
+        return match kb.evaluate(model) {
+            Some(true) => alpha.evaluate(model) == Some(true),
+            _ => true,
+        };
+    };
+    let mut result = true;
+    for value in [true, false] {
+        model.insert(symbol.clone(), value);
+        if !tt_check_all(kb, alpha, symbols, model) {
+            result = false;
+            break;
+        }
+    }
+    model.remove(&symbol);
+    symbols.push(symbol);
+    result
+}
+
+/// A model in which sentence is true, found by enumerating the truth table, or None if the
+/// sentence is unsatisfiable.
+pub fn tt_satisfiable(sentence: &Sentence) -> Option<Model> {
+    let symbols: Vec<String> = sentence.symbols().into_iter().collect();
+    (0..1u64 << symbols.len()).find_map(|bits| {
+        let model: Model = symbols
+            .iter()
+            .enumerate()
+            .map(|(i, symbol)| (symbol.clone(), bits & (1 << i) != 0))
+            .collect();
+        (sentence.evaluate(&model) == Some(true)).then_some(model)
+    })
+}
+
+/// Whether sentence is true in every model.
+pub fn tt_valid(sentence: &Sentence) -> bool {
+    tt_entails(&Sentence::True, sentence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    /// The wumpus world knowledge base R1 to R5 from section 7.4.3.
+    fn wumpus_kb() -> Sentence {
+        parse("~P11 & (B11 <=> (P12 | P21)) & (B21 <=> (P11 | P22 | P31)) & ~B11 & B21").unwrap()
+    }
+
+    #[test]
+    fn test_wumpus_entailment() {
+        let kb = wumpus_kb();
+        assert!(tt_entails(&kb, &parse("~P12").unwrap()));
+        assert!(tt_entails(&kb, &parse("~P21").unwrap()));
+        // There is a pit in [2,2] or [3,1], but the agent cannot tell which.
+        assert!(tt_entails(&kb, &parse("P22 | P31").unwrap()));
+        assert!(!tt_entails(&kb, &parse("P22").unwrap()));
+        assert!(!tt_entails(&kb, &parse("~P22").unwrap()));
+    }
+
+    #[test]
+    fn test_validity_and_satisfiability() {
+        assert!(tt_valid(&parse("A | ~A").unwrap()));
+        assert!(tt_valid(&parse("(A => B) <=> (~B => ~A)").unwrap()));
+        assert!(!tt_valid(&parse("A => B").unwrap()));
+        assert_eq!(tt_satisfiable(&parse("A & ~A").unwrap()), None);
+        let model = tt_satisfiable(&parse("A & ~B").unwrap()).unwrap();
+        assert!(model["A"]);
+        assert!(!model["B"]);
+        // An inconsistent knowledge base entails everything.
+        assert!(tt_entails(&Sentence::False, &parse("A").unwrap()));
+    }
+}
@@ -0,0 +1,255 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// The 4x3 grid world, Figure 17.1.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::{Mdp, Policy, Utilities};
+
+/// A square (x, y), with (1, 1) in the bottom left corner.
+pub type Cell = (usize, usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    /// The two directions at right angles to this one.
+    pub fn perpendicular(self) -> [Direction; 2] {
+        match self {
+            Direction::Up | Direction::Down => [Direction::Left, Direction::Right],
+            Direction::Left | Direction::Right => [Direction::Up, Direction::Down],
+        }
+    }
+
+    pub fn arrow(self) -> char {
+        match self {
+            Direction::Up => '↑',
+            Direction::Down => '↓',
+            Direction::Left => '←',
+            Direction::Right => '→',
+        }
+    }
+}
+
+/// A grid world where the agent moves in the intended direction with probability 0.8 and at
+/// right angles to it with probability 0.1 each. Bumping into a wall or the edge leaves the agent
+/// where it is. Every transition gives step_reward, and entering a terminal square also gives that
+/// square's reward and ends the episode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridWorld {
+    pub width: usize,
+    pub height: usize,
+    pub walls: Vec<Cell>,
+    pub terminals: HashMap<Cell, f64>,
+    pub step_reward: f64,
+    pub discount: f64,
+    pub start: Cell,
+}
+
+impl GridWorld {
+    /// The environment of Figure 17.1: a wall at (2, 2), +1 at (4, 3), -1 at (4, 2), a reward of
+    /// -0.04 for every other transition, and no discounting.
+    pub fn four_by_three() -> Self {
+        Self {
+            width: 4,
+            height: 3,
+            walls: vec![(2, 2)],
+            terminals: HashMap::from([((4, 3), 1.0), ((4, 2), -1.0)]),
+            step_reward: -0.04,
+            discount: 1.0,
+            start: (1, 1),
+        }
+    }
+
+    pub fn with_step_reward(mut self, step_reward: f64) -> Self {
+        self.step_reward = step_reward;
+        self
+    }
+
+    pub fn with_discount(mut self, discount: f64) -> Self {
+        self.discount = discount;
+        self
+    }
+
+    /// The square reached by moving from cell in direction, if nothing is in the way.
+    pub fn step(&self, cell: Cell, direction: Direction) -> Cell {
+        let (x, y) = cell;
+        let next = match direction {
+            Direction::Up => (x, y + 1),
+            Direction::Down => (x, y.wrapping_sub(1)),
+            Direction::Left => (x.wrapping_sub(1), y),
+            Direction::Right => (x + 1, y),
+        };
+        if self.is_open(next) {
+            next
+        } else {
+            cell
+        }
+    }
+
+    fn is_open(&self, (x, y): Cell) -> bool {
+        (1..=self.width).contains(&x)
+            && (1..=self.height).contains(&y)
+            && !self.walls.contains(&(x, y))
+    }
+
+    /// One row per line, top row first, with each square's value formatted by cell_fn and walls
+    /// shown as blanks.
+    fn format_grid(&self, cell_fn: impl Fn(Cell) -> String) -> String {
+        let mut result = String::new();
+        for y in (1..=self.height).rev() {
+            let row: Vec<String> = (1..=self.width)
+                .map(|x| {
+                    if self.walls.contains(&(x, y)) {
+                        String::new()
+                    } else {
+                        cell_fn((x, y))
+                    }
+                })
+                .collect();
+            let row: Vec<String> = row.iter().map(|cell| format!("{:>7}", cell)).collect();
+            writeln!(result, "{}", row.join("")).unwrap();
+        }
+        result
+    }
+
+    /// Utilities laid out like Figure 17.3.
+    pub fn format_utilities(&self, utilities: &Utilities<Cell>) -> String {
+        self.format_grid(|cell| match utilities.get(&cell) {
+            Some(utility) => format!("{:.3}", utility),
+            None => "?".to_string(),
+        })
+    }
+
+    /// A policy laid out like Figure 17.2, with the reward shown in terminal squares.
+    pub fn format_policy(&self, policy: &Policy<Cell, Direction>) -> String {
+        self.format_grid(
+            |cell| match (self.terminals.get(&cell), policy.get(&cell)) {
+                (Some(reward), _) => format!("{:+}", reward),
+                (None, Some(direction)) => direction.arrow().to_string(),
+                (None, None) => "?".to_string(),
+            },
+        )
+    }
+}
+
+impl Mdp for GridWorld {
+    type State = Cell;
+    type Action = Direction;
+
+    fn states(&self) -> Vec<Cell> {
+        (1..=self.height)
+            .flat_map(|y| (1..=self.width).map(move |x| (x, y)))
+            .filter(|&cell| self.is_open(cell))
+            .collect()
+    }
+
+    fn actions(&self, state: &Cell) -> Vec<Direction> {
+        if self.terminals.contains_key(state) {
+            vec![]
+        } else {
+            Direction::ALL.to_vec()
+        }
+    }
+
+    fn transitions(&self, state: &Cell, action: &Direction) -> Vec<(Cell, f64)> {
+        let [left, right] = action.perpendicular();
+        let mut result: Vec<(Cell, f64)> = vec![];
+        for (direction, probability) in [(*action, 0.8), (left, 0.1), (right, 0.1)] {
+            let next = self.step(*state, direction);
+            match result.iter_mut().find(|(cell, _)| *cell == next) {
+                Some((_, total)) => *total += probability,
+                None => result.push((next, probability)),
+            }
+        }
+        result
+    }
+
+    fn reward(&self, _state: &Cell, _action: &Direction, next_state: &Cell) -> f64 {
+        self.step_reward + self.terminals.get(next_state).copied().unwrap_or(0.0)
+    }
+
+    fn discount(&self) -> f64 {
+        self.discount
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_states() {
+        let world = GridWorld::four_by_three();
+        let states = world.states();
+        assert_eq!(states.len(), 11);
+        assert!(!states.contains(&(2, 2)));
+    }
+
+    #[test]
+    fn test_transitions() {
+        let world = GridWorld::four_by_three();
+        let mut transitions = world.transitions(&(1, 1), &Direction::Up);
+        transitions.sort_by_key(|(cell, _)| *cell);
+        // Moving left bumps into the edge, so the agent stays put with probability 0.1.
+        assert_eq!(
+            transitions,
+            vec![((1, 1), 0.1), ((1, 2), 0.8), ((2, 1), 0.1)]
+        );
+
+        let transitions = world.transitions(&(1, 2), &Direction::Right);
+        let total: f64 = transitions.iter().map(|(_, p)| p).sum();
+        assert!((total - 1.0).abs() < 1e-12);
+        assert_eq!(transitions[0], ((1, 2), 0.8));
+    }
+
+    #[test]
+    fn test_rewards() {
+        let world = GridWorld::four_by_three();
+        assert_eq!(world.reward(&(3, 3), &Direction::Right, &(4, 3)), 0.96);
+        assert_eq!(world.reward(&(3, 2), &Direction::Right, &(4, 2)), -1.04);
+        assert_eq!(world.reward(&(1, 1), &Direction::Up, &(1, 2)), -0.04);
+    }
+
+    #[test]
+    fn test_format_policy() {
+        let world = GridWorld::four_by_three();
+        let policy: Policy<Cell, Direction> = world
+            .states()
+            .into_iter()
+            .filter(|cell| !world.is_terminal(cell))
+            .map(|cell| (cell, Direction::Up))
+            .collect();
+        assert_eq!(
+            world.format_policy(&policy),
+            "      ↑      ↑      ↑     +1\n      ↑             ↑     -1\n      ↑      ↑      ↑      ↑\n"
+        );
+    }
+}
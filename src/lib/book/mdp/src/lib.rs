@@ -0,0 +1,133 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Markov decision processes: sequential decisions in a fully observable, stochastic environment.
+//
+// See:
+// -  Chapter 17: Making Complex Decisions, sections 17.1 and 17.2
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+pub mod grid_world;
+pub mod policy_iteration;
+pub mod value_iteration;
+
+/// A Markov decision process, section 17.1: states, the actions available in each state, a
+/// transition model P(s' | s, a), a reward function R(s, a, s'), and a discount factor γ.
+pub trait Mdp {
+    type State: Clone + Eq + Hash;
+    type Action: Clone + Eq + Hash;
+
+    /// Every state, including terminal states.
+    fn states(&self) -> Vec<Self::State>;
+
+    /// Actions available in state. Terminal states have none.
+    fn actions(&self, state: &Self::State) -> Vec<Self::Action>;
+
+    /// Each possible next state with its probability. The probabilities sum to 1.
+    fn transitions(&self, state: &Self::State, action: &Self::Action) -> Vec<(Self::State, f64)>;
+
+    fn reward(&self, state: &Self::State, action: &Self::Action, next_state: &Self::State) -> f64;
+
+    /// γ, between 0 and 1. With γ = 1 every policy considered must eventually reach a terminal
+    /// state.
+    fn discount(&self) -> f64;
+
+    fn is_terminal(&self, state: &Self::State) -> bool {
+        self.actions(state).is_empty()
+    }
+}
+
+/// Expected utility of every state.
+pub type Utilities<_State> = HashMap<_State, f64>;
+
+/// The action to take in every non-terminal state.
+pub type Policy<_State, _Action> = HashMap<_State, _Action>;
+
+/// Q(s, a): the expected utility of doing action in state and then acting to get the utilities
+/// in utilities, equation (17.8). States missing from utilities have utility 0.
+pub fn q_value<_Mdp: Mdp>(
+    mdp: &_Mdp,
+    state: &_Mdp::State,
+    action: &_Mdp::Action,
+    utilities: &Utilities<_Mdp::State>,
+) -> f64 {
+    mdp.transitions(state, action)
+        .iter()
+        .map(|(next_state, probability)| {
+            let utility = utilities.get(next_state).copied().unwrap_or(0.0);
+            probability * (mdp.reward(state, action, next_state) + mdp.discount() * utility)
+        })
+        .sum()
+}
+
+/// The action with the highest Q-value in state, with ties going to the earliest action, or None
+/// for terminal states.
+pub fn best_action<_Mdp: Mdp>(
+    mdp: &_Mdp,
+    state: &_Mdp::State,
+    utilities: &Utilities<_Mdp::State>,
+) -> Option<(_Mdp::Action, f64)> {
+    let mut best: Option<(_Mdp::Action, f64)> = None;
+    for action in mdp.actions(state) {
+        let value = q_value(mdp, state, &action, utilities);
+        if best
+            .as_ref()
+            .is_none_or(|(_, best_value)| value > *best_value)
+        {
+            best = Some((action, value));
+        }
+    }
+    best
+}
+
+/// The policy that is greedy with respect to utilities, equation (17.4).
+pub fn extract_policy<_Mdp: Mdp>(
+    mdp: &_Mdp,
+    utilities: &Utilities<_Mdp::State>,
+) -> Policy<_Mdp::State, _Mdp::Action> {
+    mdp.states()
+        .into_iter()
+        .filter_map(|state| {
+            let (action, _) = best_action(mdp, &state, utilities)?;
+            Some((state, action))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid_world::{Direction, GridWorld};
+
+    #[test]
+    fn test_q_value() {
+        let world = GridWorld::four_by_three();
+        let utilities = Utilities::new();
+        // -0.04 for the step, plus 0.8 of reaching (4, 3) for +1.
+        let q = q_value(&world, &(3, 3), &Direction::Right, &utilities);
+        assert!((q - (0.8 - 0.04)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_best_action_in_terminal_state() {
+        let world = GridWorld::four_by_three();
+        assert_eq!(best_action(&world, &(4, 3), &Utilities::new()), None);
+        assert!(world.is_terminal(&(4, 3)));
+        assert!(!world.is_terminal(&(1, 1)));
+    }
+}
@@ -0,0 +1,136 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Policy iteration, Figure 17.9.
+
+use crate::{q_value, Mdp, Policy, Utilities};
+
+#[derive(Debug, Clone)]
+pub struct PolicyIterationResult<_State, _Action> {
+    pub policy: Policy<_State, _Action>,
+    pub utilities: Utilities<_State>,
+
+    /// Number of rounds of policy evaluation and policy improvement.
+    pub iterations: usize,
+}
+
+/// Approximate policy evaluation, section 17.2.2: apply the simplified Bellman update (17.14) for
+/// the fixed policy to every state, sweeps times, starting from utilities.
+pub fn policy_evaluation<_Mdp: Mdp>(
+    mdp: &_Mdp,
+    policy: &Policy<_Mdp::State, _Mdp::Action>,
+    utilities: &mut Utilities<_Mdp::State>,
+    sweeps: usize,
+) {
+    let states = mdp.states();
+    for _ in 0..sweeps {
+        let mut next = utilities.clone();
+        for state in &states {
+            let utility = match policy.get(state) {
+                Some(action) => q_value(mdp, state, action, utilities),
+                None => 0.0,
+            };
+            next.insert(state.clone(), utility);
+        }
+        *utilities = next;
+    }
+}
+
+/// Modified policy iteration, Figure 17.9: alternate between evaluating the current policy with
+/// evaluation_sweeps simplified Bellman updates, and making the policy greedy with respect to the
+/// resulting utilities. An action only replaces the current one if it is strictly better, so the
+/// loop stops once the policy is unchanged.
+///
+/// The initial policy picks the first action in every state. Without discounting that policy may
+/// never reach a terminal state; its utilities then only drift downwards during a bounded number
+/// of sweeps, and the first improvement step replaces it.
+pub fn policy_iteration<_Mdp: Mdp>(
+    mdp: &_Mdp,
+    evaluation_sweeps: usize,
+) -> PolicyIterationResult<_Mdp::State, _Mdp::Action> {
+    let states = mdp.states();
+    let mut policy: Policy<_Mdp::State, _Mdp::Action> = states
+        .iter()
+        .filter_map(|state| Some((state.clone(), mdp.actions(state).into_iter().next()?)))
+        .collect();
+    let mut utilities: Utilities<_Mdp::State> =
+        states.iter().map(|state| (state.clone(), 0.0)).collect();
+    let mut iterations = 0;
+    loop {
+        policy_evaluation(mdp, &policy, &mut utilities, evaluation_sweeps);
+        iterations += 1;
+        let mut unchanged = true;
+        for (state, current) in policy.iter_mut() {
+            let mut best_value = q_value(mdp, state, current, &utilities);
+            for action in mdp.actions(state) {
+                let value = q_value(mdp, state, &action, &utilities);
+                if value > best_value + f64::EPSILON {
+                    best_value = value;
+                    *current = action;
+                    unchanged = false;
+                }
+            }
+        }
+        if unchanged {
+            return PolicyIterationResult {
+                policy,
+                utilities,
+                iterations,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract_policy;
+    use crate::grid_world::GridWorld;
+    use crate::value_iteration::value_iteration;
+
+    #[test]
+    fn test_agrees_with_value_iteration() {
+        for world in [
+            GridWorld::four_by_three(),
+            GridWorld::four_by_three().with_step_reward(-0.4),
+            GridWorld::four_by_three().with_discount(0.9),
+        ] {
+            let expected = extract_policy(&world, &value_iteration(&world, 1e-9).utilities);
+            let result = policy_iteration(&world, 50);
+            assert_eq!(result.policy, expected, "{:?}", world);
+        }
+    }
+
+    #[test]
+    fn test_four_by_three_utilities() {
+        let world = GridWorld::four_by_three();
+        let mut result = policy_iteration(&world, 50);
+        policy_evaluation(&world, &result.policy, &mut result.utilities, 1000);
+        assert!((result.utilities[&(1, 1)] - 0.705).abs() < 0.0005);
+        assert!((result.utilities[&(3, 3)] - 0.918).abs() < 0.0005);
+        assert!(result.iterations < 10);
+    }
+
+    #[test]
+    fn test_policy_evaluation() {
+        // Always trying to go right from (3, 3): the expected reward of one step.
+        let world = GridWorld::four_by_three();
+        let policy = extract_policy(&world, &value_iteration(&world, 1e-9).utilities);
+        let mut utilities = Utilities::new();
+        policy_evaluation(&world, &policy, &mut utilities, 1);
+        assert!((utilities[&(3, 3)] - (0.8 - 0.04)).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,131 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Value iteration, Figure 17.6.
+
+use crate::{best_action, Mdp, Utilities};
+
+#[derive(Debug, Clone)]
+pub struct ValueIterationResult<_State> {
+    pub utilities: Utilities<_State>,
+
+    /// Number of Bellman updates of every state.
+    pub iterations: usize,
+}
+
+/// Value iteration, Figure 17.6: apply the Bellman update (17.10) to every state until the
+/// largest change is at most ε(1 − γ)/γ, which guarantees that the utilities are within ε of the
+/// true ones. Without discounting that bound is 0, so the search stops when the largest change is
+/// at most ε.
+pub fn value_iteration<_Mdp: Mdp>(mdp: &_Mdp, epsilon: f64) -> ValueIterationResult<_Mdp::State> {
+    let gamma = mdp.discount();
+    let threshold = if gamma < 1.0 {
+        epsilon * (1.0 - gamma) / gamma
+    } else {
+        epsilon
+    };
+    let states = mdp.states();
+    let mut utilities: Utilities<_Mdp::State> =
+        states.iter().map(|state| (state.clone(), 0.0)).collect();
+    let mut iterations = 0;
+    loop {
+        let mut delta: f64 = 0.0;
+        let mut next = utilities.clone();
+        for state in &states {
+            let utility = best_action(mdp, state, &utilities).map_or(0.0, |(_, value)| value);
+            delta = delta.max((utility - utilities[state]).abs());
+            next.insert(state.clone(), utility);
+        }
+        utilities = next;
+        iterations += 1;
+        if delta <= threshold {
+            return ValueIterationResult {
+                utilities,
+                iterations,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract_policy;
+    use crate::grid_world::{Direction, GridWorld};
+
+    /// Utilities of the 4x3 world with a step reward of -0.04, Figure 17.3.
+    const FOUR_BY_THREE_UTILITIES: [((usize, usize), f64); 9] = [
+        ((1, 3), 0.812),
+        ((2, 3), 0.868),
+        ((3, 3), 0.918),
+        ((1, 2), 0.762),
+        ((3, 2), 0.660),
+        ((1, 1), 0.705),
+        ((2, 1), 0.655),
+        ((3, 1), 0.611),
+        ((4, 1), 0.388),
+    ];
+
+    #[test]
+    fn test_four_by_three_utilities() {
+        let world = GridWorld::four_by_three();
+        let result = value_iteration(&world, 1e-6);
+        for (cell, expected) in FOUR_BY_THREE_UTILITIES {
+            let utility = result.utilities[&cell];
+            assert!(
+                (utility - expected).abs() < 0.0005,
+                "{:?}: {} != {}",
+                cell,
+                utility,
+                expected
+            );
+        }
+        assert_eq!(result.utilities[&(4, 3)], 0.0);
+    }
+
+    #[test]
+    fn test_four_by_three_policy() {
+        let world = GridWorld::four_by_three();
+        let result = value_iteration(&world, 1e-6);
+        let policy = extract_policy(&world, &result.utilities);
+        // Figure 17.2(a): go the long way round rather than risk falling into (4, 2).
+        assert_eq!(policy[&(1, 1)], Direction::Up);
+        assert_eq!(policy[&(3, 1)], Direction::Left);
+        assert_eq!(policy[&(4, 1)], Direction::Left);
+        assert_eq!(policy[&(3, 2)], Direction::Up);
+        assert_eq!(
+            world.format_policy(&policy),
+            "      →      →      →     +1\n      ↑             ↑     -1\n      ↑      ←      ←      ←\n"
+        );
+    }
+
+    #[test]
+    fn test_step_reward_changes_policy() {
+        // Figure 17.2(b): when life is painful enough the agent heads straight for the nearest
+        // exit, even the -1 one.
+        let world = GridWorld::four_by_three().with_step_reward(-2.0);
+        let policy = extract_policy(&world, &value_iteration(&world, 1e-6).utilities);
+        assert_eq!(policy[&(3, 2)], Direction::Right);
+        assert_eq!(policy[&(4, 1)], Direction::Up);
+    }
+
+    #[test]
+    fn test_discounting_converges_faster() {
+        let undiscounted = value_iteration(&GridWorld::four_by_three(), 1e-3);
+        let discounted = value_iteration(&GridWorld::four_by_three().with_discount(0.5), 1e-3);
+        assert!(discounted.iterations < undiscounted.iterations);
+    }
+}
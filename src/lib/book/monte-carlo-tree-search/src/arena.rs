@@ -0,0 +1,287 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Playing two search configurations against each other, to tell whether a change to the
+// arguments makes the search stronger.
+
+use crate::{Action, Float, Mcts, MctsArgs, State};
+
+/// ArenaGame is a two-player game the arena can play. Players are numbered 0 and 1.
+pub trait ArenaGame<_Action: Action>: State<_Action> {
+    /// The player to move.
+    fn player_to_move(&self) -> usize;
+
+    /// This state as `player` searches it. Games whose playouts are scored for a player kept in
+    /// the state, like `who_am_i` in Connect Four, set that player here.
+    fn searched_by(&self, player: usize) -> Self;
+
+    /// `player`'s reward once the game is over: 1 for a win, 0 for a loss and 0.5 for a draw.
+    fn reward(&self, player: usize) -> Float;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArenaConfig {
+    pub games: usize,
+
+    /// Games still going after this many moves are draws.
+    pub max_moves: usize,
+}
+
+impl Default for ArenaConfig {
+    fn default() -> Self {
+        Self {
+            games: 20,
+            max_moves: 200,
+        }
+    }
+}
+
+/// Results from the challenger's point of view.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ArenaResult {
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+}
+
+impl ArenaResult {
+    pub fn games(&self) -> usize {
+        self.wins + self.losses + self.draws
+    }
+
+    /// Wins plus half the draws, as a fraction of the games played.
+    pub fn score(&self) -> Float {
+        if self.games() == 0 {
+            return 0.0;
+        }
+        (self.wins as Float + 0.5 * self.draws as Float) / self.games() as Float
+    }
+
+    /// How many Elo points stronger the challenger is than the champion, going by the score.
+    /// Infinite if either side won every game, and None if no games were played.
+    pub fn elo_difference(&self) -> Option<Float> {
+        (self.games() > 0).then(|| elo_difference(self.score()))
+    }
+}
+
+/// The Elo rating difference at which the expected score of the stronger player is `score`.
+pub fn elo_difference(score: Float) -> Float {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// Play `config.games` games from `initial_state` between two search configurations,
+/// alternating which one moves first. Every move is a fresh search with the mover's arguments,
+/// from the state as the mover sees it, so the searches don't share trees.
+pub fn arena<_State, _Action>(
+    initial_state: &_State,
+    challenger: &MctsArgs,
+    champion: &MctsArgs,
+    config: &ArenaConfig,
+) -> ArenaResult
+where
+    _State: ArenaGame<_Action>,
+    _Action: Action,
+{
+    let first_player = initial_state.player_to_move();
+    let mut result = ArenaResult::default();
+    for game in 0..config.games {
+        let challenger_player = if game % 2 == 0 {
+            first_player
+        } else {
+            1 - first_player
+        };
+        let mut state = initial_state.clone();
+        let mut moves = 0;
+        while !state.is_terminal() && moves < config.max_moves {
+            let player = state.player_to_move();
+            let args = if player == challenger_player {
+                challenger
+            } else {
+                champion
+            };
+            let mut mcts = Mcts::new(state.searched_by(player), args.clone());
+            mcts.run();
+            let Some(action) = mcts.best_action() else {
+                break;
+            };
+            state = state.get_next_state(&action);
+            moves += 1;
+        }
+        let reward = if state.is_terminal() {
+            state.reward(challenger_player)
+        } else {
+            0.5
+        };
+        if reward > 0.5 {
+            result.wins += 1;
+        } else if reward < 0.5 {
+            result.losses += 1;
+        } else {
+            result.draws += 1;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::{shared_rng, Int, IterationLimitKind, RewardPerspective, Rng, Solver};
+
+    // Nim with one pile: players take one or two stones in turn, and whoever takes the last
+    // stone wins. The player to move loses if the pile is a multiple of three.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+    pub(crate) struct Nim {
+        pub(crate) stones: u8,
+        pub(crate) to_move: usize,
+        pub(crate) searcher: usize,
+    }
+
+    impl Nim {
+        // A pile of `stones` with the first player, who is also the searcher, to move.
+        pub(crate) fn new(stones: u8) -> Self {
+            Nim {
+                stones,
+                to_move: 0,
+                searcher: 0,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub(crate) struct Take(pub(crate) u8);
+
+    impl Action for Take {}
+
+    impl State<Take> for Nim {
+        fn simulate(
+            &self,
+            playouts: Int,
+            max_depth_per_playout: Int,
+            rng: &mut Rng,
+        ) -> Vec<crate::SimulationResult> {
+            crate::run_playouts(
+                self,
+                &crate::RandomPlayout,
+                |state| crate::SimulationResult::Reward(state.reward(state.searcher)),
+                playouts,
+                max_depth_per_playout,
+                rng,
+            )
+            .into_iter()
+            .map(|playout| playout.result)
+            .collect()
+        }
+
+        fn get_actions(&self) -> Vec<Take> {
+            (1..=self.stones.min(2)).map(Take).collect()
+        }
+
+        fn get_next_state(&self, action: &Take) -> Self {
+            Nim {
+                stones: self.stones - action.0,
+                to_move: 1 - self.to_move,
+                searcher: self.searcher,
+            }
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.stones == 0
+        }
+    }
+
+    impl ArenaGame<Take> for Nim {
+        fn player_to_move(&self) -> usize {
+            self.to_move
+        }
+
+        fn searched_by(&self, player: usize) -> Self {
+            Nim {
+                searcher: player,
+                ..*self
+            }
+        }
+
+        // Whoever took the last stone is not the one to move.
+        fn reward(&self, player: usize) -> Float {
+            if player == self.to_move {
+                0.0
+            } else {
+                1.0
+            }
+        }
+    }
+
+    pub(crate) fn args(iterations: Int) -> MctsArgs {
+        MctsArgs {
+            iteration_limit: IterationLimitKind::Iterations(iterations),
+            playouts_per_simulation: 4,
+            max_depth_per_playout: 20,
+            rng: shared_rng(42),
+            reward_perspective: RewardPerspective::Alternating,
+            solver: Solver::Enabled,
+            ..MctsArgs::default()
+        }
+    }
+
+    #[test]
+    fn test_stronger_search_wins() {
+        let initial_state = Nim::new(10);
+        let config = ArenaConfig {
+            games: 20,
+            max_moves: 20,
+        };
+        let result = arena(&initial_state, &args(500), &args(1), &config);
+        assert_eq!(result.games(), 20);
+        assert_eq!(result.draws, 0);
+        // The pile of ten is won for the first player, so the stronger search wins every game
+        // it starts and every game the weaker search throws away.
+        assert!(result.wins >= 10, "{:?}", result);
+        assert!(result.elo_difference().unwrap() > 0.0);
+    }
+
+    #[test]
+    fn test_max_moves_draws() {
+        let initial_state = Nim::new(10);
+        let config = ArenaConfig {
+            games: 2,
+            max_moves: 2,
+        };
+        let result = arena(&initial_state, &args(10), &args(10), &config);
+        assert_eq!(result.draws, 2);
+        assert_eq!(result.elo_difference(), Some(0.0));
+    }
+
+    #[test]
+    fn test_score_and_elo_difference() {
+        let result = ArenaResult {
+            wins: 3,
+            losses: 1,
+            draws: 2,
+        };
+        assert!((result.score() - 4.0 / 6.0).abs() < 1e-12);
+        assert_eq!(ArenaResult::default().score(), 0.0);
+        assert_eq!(ArenaResult::default().elo_difference(), None);
+
+        // A 76% score is about 200 Elo.
+        assert!((elo_difference(0.76) - 200.0).abs() < 1.0);
+        assert!((elo_difference(0.24) + 200.0).abs() < 1.0);
+        assert_eq!(elo_difference(1.0), Float::INFINITY);
+    }
+}
@@ -0,0 +1,269 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Checkpoints save a search tree to disk so that a long search can be interrupted and resumed.
+// They are CBOR, a compact binary encoding of the same serde data model as JSON.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{Action, Mcts, MctsArgs, MctsTree, State};
+
+/// Written at the top of every checkpoint so other CBOR isn't mistaken for one.
+pub const CHECKPOINT_FORMAT_NAME: &str = "aima-rs-mcts-checkpoint";
+
+/// Bump this whenever the layout of the tree changes, and keep reading older versions.
+pub const CHECKPOINT_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum CheckpointError {
+    #[error("i/o error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("encoding error: {0}")]
+    Encode(#[from] ciborium::ser::Error<std::io::Error>),
+
+    #[error("decoding error: {0}")]
+    Decode(#[from] ciborium::de::Error<std::io::Error>),
+
+    #[error("decoding error in tree: {0}")]
+    DecodeTree(#[from] ciborium::value::Error),
+
+    #[error("not a checkpoint, format is {0:?}")]
+    WrongFormat(String),
+
+    #[error("checkpoint version {found} is newer than the supported version {supported}")]
+    UnsupportedVersion { found: u32, supported: u32 },
+}
+
+#[derive(Serialize, Deserialize)]
+struct Checkpoint<_Tree> {
+    format: String,
+    version: u32,
+    tree: _Tree,
+}
+
+impl<_State, _Action> Mcts<_State, _Action>
+where
+    _State: State<_Action>,
+    _Action: Action,
+{
+    /// Save the tree, with its statistics and root state, to `path`. The arguments are not
+    /// saved, since the rng and selection policy can't be.
+    pub fn save_checkpoint(&self, path: impl AsRef<Path>) -> Result<(), CheckpointError> {
+        let checkpoint = Checkpoint {
+            format: CHECKPOINT_FORMAT_NAME.to_string(),
+            version: CHECKPOINT_FORMAT_VERSION,
+            tree: &self.tree,
+        };
+        let mut writer = BufWriter::new(File::create(path)?);
+        ciborium::into_writer(&checkpoint, &mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Resume a search saved by save_checkpoint with `args`, which need not be the ones it was
+    /// started with. If `args` shares transpositions, the table is rebuilt from the tree.
+    pub fn load_checkpoint(path: impl AsRef<Path>, args: MctsArgs) -> Result<Self, CheckpointError>
+    where
+        _State: DeserializeOwned,
+        _Action: DeserializeOwned,
+    {
+        // The tree is only decoded once the header says which layout it has.
+        let checkpoint: Checkpoint<ciborium::Value> =
+            ciborium::from_reader(BufReader::new(File::open(path)?))?;
+        if checkpoint.format != CHECKPOINT_FORMAT_NAME {
+            return Err(CheckpointError::WrongFormat(checkpoint.format));
+        }
+        let tree: MctsTree<_State, _Action> = match checkpoint.version {
+            // Every version so far has the same layout.
+            ..=CHECKPOINT_FORMAT_VERSION => checkpoint.tree.deserialized()?,
+            found => {
+                return Err(CheckpointError::UnsupportedVersion {
+                    found,
+                    supported: CHECKPOINT_FORMAT_VERSION,
+                })
+            }
+        };
+        let tree = tree.with_transpositions(args.transpositions);
+        Ok(Mcts::new_from_tree(tree, args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{shared_rng, Int, IterationLimitKind, Rng, SimulationResult, Transpositions};
+    use rand::seq::SliceRandom;
+    use std::path::PathBuf;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    struct Step(u8);
+
+    impl Action for Step {}
+
+    // A race to 10 in steps of 1 or 2, which is won by landing on 10 exactly. Different orders
+    // of steps reach the same total, so there are transpositions.
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    struct Race(u8);
+
+    impl State<Step> for Race {
+        fn simulate(
+            &self,
+            playouts: Int,
+            max_depth_per_playout: Int,
+            rng: &mut Rng,
+        ) -> Vec<SimulationResult> {
+            (0..playouts)
+                .map(|_| {
+                    let mut state = self.clone();
+                    for _ in 0..max_depth_per_playout {
+                        let Some(step) = state.get_actions().choose(rng).copied() else {
+                            break;
+                        };
+                        state = state.get_next_state(&step);
+                    }
+                    if state.0 == 10 {
+                        SimulationResult::Win
+                    } else {
+                        SimulationResult::NotWin
+                    }
+                })
+                .collect()
+        }
+
+        fn get_actions(&self) -> Vec<Step> {
+            if self.is_terminal() {
+                vec![]
+            } else {
+                vec![Step(1), Step(2)]
+            }
+        }
+
+        fn get_next_state(&self, action: &Step) -> Self {
+            Race(self.0 + action.0)
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.0 >= 10
+        }
+    }
+
+    fn args(transpositions: Transpositions) -> MctsArgs {
+        MctsArgs {
+            iteration_limit: IterationLimitKind::Iterations(50),
+            playouts_per_simulation: 5,
+            max_depth_per_playout: 10,
+            rng: shared_rng(42),
+            transpositions,
+            ..MctsArgs::default()
+        }
+    }
+
+    fn checkpoint_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("aima-mcts-{}-{}.cbor", name, std::process::id()))
+    }
+
+    fn assert_round_trip(transpositions: Transpositions, name: &str) {
+        let mut mcts = Mcts::new(Race(0), args(transpositions));
+        mcts.run();
+        let path = checkpoint_path(name);
+        mcts.save_checkpoint(&path).unwrap();
+        let mut loaded: Mcts<Race, Step> =
+            Mcts::load_checkpoint(&path, args(transpositions)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let tree_json = |mcts: &Mcts<Race, Step>| serde_json::to_string(&mcts.serialize_tree());
+        assert_eq!(tree_json(&loaded).unwrap(), tree_json(&mcts).unwrap());
+        assert_eq!(loaded.node_count(), mcts.node_count());
+        assert_eq!(
+            loaded.tree.transpositions.as_ref().map(|table| table.len()),
+            mcts.tree.transpositions.as_ref().map(|table| table.len())
+        );
+
+        // The resumed search builds on the saved statistics.
+        loaded.run();
+        assert_eq!(loaded.tree.get_root().visits, 2 * 50 * 5);
+    }
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        assert_round_trip(Transpositions::Separate, "separate");
+    }
+
+    #[test]
+    fn test_checkpoint_round_trip_with_transpositions() {
+        assert_round_trip(Transpositions::Share, "share");
+    }
+
+    #[test]
+    fn test_load_checkpoint_rejects_other_files() {
+        let mcts = Mcts::new(Race(0), args(Transpositions::Separate));
+        let load = |format: &str, version: u32| {
+            let path = checkpoint_path(&format!("{}-{}", format, version));
+            let checkpoint = Checkpoint {
+                format: format.to_string(),
+                version,
+                tree: &mcts.tree,
+            };
+            ciborium::into_writer(&checkpoint, File::create(&path).unwrap()).unwrap();
+            let result = Mcts::<Race, Step>::load_checkpoint(&path, args(Transpositions::Separate));
+            std::fs::remove_file(&path).unwrap();
+            result.err()
+        };
+
+        assert!(matches!(
+            load("something-else", 1),
+            Some(CheckpointError::WrongFormat(format)) if format == "something-else"
+        ));
+        assert!(matches!(
+            load(CHECKPOINT_FORMAT_NAME, CHECKPOINT_FORMAT_VERSION + 1),
+            Some(CheckpointError::UnsupportedVersion { .. })
+        ));
+        assert!(load(CHECKPOINT_FORMAT_NAME, CHECKPOINT_FORMAT_VERSION).is_none());
+        assert!(matches!(
+            Mcts::<Race, Step>::load_checkpoint(
+                checkpoint_path("missing"),
+                args(Transpositions::Separate)
+            ),
+            Err(CheckpointError::Io(_))
+        ));
+    }
+
+    // A newer version may lay the tree out in a way this version can't decode at all.
+    #[test]
+    fn test_load_checkpoint_rejects_future_layouts() {
+        let path = checkpoint_path("future");
+        let checkpoint = Checkpoint {
+            format: CHECKPOINT_FORMAT_NAME.to_string(),
+            version: CHECKPOINT_FORMAT_VERSION + 1,
+            tree: vec!["a", "future", "layout"],
+        };
+        ciborium::into_writer(&checkpoint, File::create(&path).unwrap()).unwrap();
+        let result = Mcts::<Race, Step>::load_checkpoint(&path, args(Transpositions::Separate));
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            result.err(),
+            Some(CheckpointError::UnsupportedVersion { found, supported })
+                if found == CHECKPOINT_FORMAT_VERSION + 1 && supported == CHECKPOINT_FORMAT_VERSION
+        ));
+    }
+}
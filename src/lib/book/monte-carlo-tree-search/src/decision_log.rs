@@ -0,0 +1,336 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// The decision log records enough about every search to repeat it exactly, so that a game that
+// went wrong can be replayed move by move under a debugger.
+
+use std::fs::{File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, Write};
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{Action, Int, IterationLimitKind, Mcts, State};
+
+#[derive(Debug, Error)]
+pub enum DecisionLogError {
+    #[error("i/o error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("line {line}: {source}")]
+    Parse {
+        line: usize,
+        source: serde_json::Error,
+    },
+
+    #[error("root state hash {found:#x} doesn't match the logged {logged:#x}")]
+    StateMismatch { logged: u64, found: u64 },
+}
+
+/// DecisionRecord is one line of the decision log, written at the end of a call to run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecisionRecord<_Action> {
+    /// The search's rng was seeded with this, after drawing it from `MctsArgs::rng`.
+    pub seed: u64,
+    pub iterations: Int,
+
+    /// See [`state_hash`]; replaying checks it to catch a search from the wrong state.
+    pub root_state_hash: u64,
+
+    /// The best action once the search was over.
+    pub action: Option<_Action>,
+}
+
+impl<_Action: Serialize> DecisionRecord<_Action> {
+    /// Append this record to the JSON lines file at `path`, creating it if need be.
+    fn append_to(&self, path: &Path) -> io::Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut line = serde_json::to_vec(self)?;
+        line.push(b'\n');
+        file.write_all(&line)
+    }
+}
+
+/// A hash of `state` that is the same from one run of the program to the next and on every
+/// platform, computed by [`StableHasher`] from what the state's Hash impl writes. The Hash impls
+/// of std types may change between Rust releases, so a log is only sure to replay with the
+/// compiler that wrote it.
+pub fn state_hash<_State: Hash>(state: &_State) -> u64 {
+    let mut hasher = StableHasher::default();
+    state.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// StableHasher is 64-bit FNV-1a over the bytes it is given, with integers written as
+/// little-endian bytes and usize and isize widened to 64 bits, so that the hash doesn't depend
+/// on the platform's endianness or pointer width.
+#[derive(Debug, Clone, Copy)]
+pub struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16);
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32);
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64);
+    }
+}
+
+/// The records in the decision log at `path`, oldest first.
+pub fn read_decision_log<_Action: DeserializeOwned>(
+    path: impl AsRef<Path>,
+) -> Result<Vec<DecisionRecord<_Action>>, DecisionLogError> {
+    let mut records = vec![];
+    for (i, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(&line).map_err(|source| DecisionLogError::Parse {
+            line: i + 1,
+            source,
+        })?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+impl<_State, _Action> Mcts<_State, _Action>
+where
+    _State: State<_Action>,
+    _Action: Action,
+{
+    /// Search, appending a record of the search to the decision log at `path`. The search
+    /// uses an rng seeded from `args.rng`, which the record keeps. If the record can't be
+    /// written the search has still run, and the error is returned.
+    pub(crate) fn run_logged<F>(
+        &mut self,
+        path: &Path,
+        every: Int,
+        callback: F,
+    ) -> Result<(), DecisionLogError>
+    where
+        F: FnMut(&Self) -> ControlFlow<()>,
+    {
+        let seed = rand::RngCore::next_u64(&mut *self.args.lock_rng());
+        let root_state_hash = state_hash(&self.tree.root_state);
+        self.with_seed(seed, |mcts| mcts.search(every, callback));
+        let record = DecisionRecord {
+            seed,
+            iterations: self.iterations_run,
+            root_state_hash,
+            action: self.best_action(),
+        };
+        record.append_to(path)?;
+        Ok(())
+    }
+
+    /// Repeat the search `record` describes and return the best action it finds, which is the
+    /// logged one unless something has changed. The search must start from the same tree as
+    /// the logged one: replay a game's records in order, from a new search with the same
+    /// arguments, applying the same moves with advance_root in between.
+    pub fn replay(
+        &mut self,
+        record: &DecisionRecord<_Action>,
+    ) -> Result<Option<_Action>, DecisionLogError> {
+        let found = state_hash(&self.tree.root_state);
+        if found != record.root_state_hash {
+            return Err(DecisionLogError::StateMismatch {
+                logged: record.root_state_hash,
+                found,
+            });
+        }
+        let iteration_limit = std::mem::replace(
+            &mut self.args.iteration_limit,
+            IterationLimitKind::Iterations(record.iterations),
+        );
+        self.with_seed(record.seed, |mcts| {
+            mcts.search(Int::MAX, |_| ControlFlow::Continue(()))
+        });
+        self.args.iteration_limit = iteration_limit;
+        Ok(self.best_action())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::arena::tests::{args, Nim, Take};
+    use crate::MctsArgs;
+
+    fn log_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "aima-mcts-decisions-{}-{}.jsonl",
+            name,
+            std::process::id()
+        ))
+    }
+
+    fn logged_args(path: &Path) -> MctsArgs {
+        MctsArgs {
+            decision_log: Some(path.to_path_buf()),
+            ..args(100)
+        }
+    }
+
+    fn tree_json(mcts: &Mcts<Nim, Take>) -> String {
+        serde_json::to_string(&mcts.serialize_tree()).unwrap()
+    }
+
+    #[test]
+    fn test_replay_repeats_every_search() {
+        let path = log_path("replay");
+        let _ = std::fs::remove_file(&path);
+
+        // Play a few moves, keeping the tree between them.
+        let mut mcts = Mcts::new(Nim::new(10), logged_args(&path));
+        let mut trees = vec![];
+        for _ in 0..3 {
+            mcts.run();
+            trees.push(tree_json(&mcts));
+            mcts.advance_root(mcts.best_action().unwrap());
+        }
+
+        let records: Vec<DecisionRecord<Take>> = read_decision_log(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].root_state_hash, state_hash(&Nim::new(10)));
+
+        // A fresh search with a different rng replays the same searches.
+        let mut replayed = Mcts::new(Nim::new(10), args(100));
+        for (record, tree) in records.iter().zip(&trees) {
+            let action = replayed.replay(record).unwrap();
+            assert_eq!(action, record.action);
+            assert_eq!(replayed.iterations_run(), record.iterations);
+            assert_eq!(&tree_json(&replayed), tree);
+            replayed.advance_root(action.unwrap());
+        }
+    }
+
+    #[test]
+    fn test_replay_from_the_wrong_state() {
+        let path = log_path("wrong-state");
+        let _ = std::fs::remove_file(&path);
+        let mut mcts = Mcts::new(Nim::new(10), logged_args(&path));
+        mcts.run();
+        let records: Vec<DecisionRecord<Take>> = read_decision_log(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut other = Mcts::new(Nim::new(9), args(100));
+        assert!(matches!(
+            other.replay(&records[0]),
+            Err(DecisionLogError::StateMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_unwritable_log() {
+        // A directory can't be appended to.
+        let path = std::env::temp_dir();
+        let mut mcts = Mcts::new(Nim::new(10), logged_args(&path));
+        let result = mcts.run_with_callback(Int::MAX, |_| ControlFlow::Continue(()));
+        assert!(matches!(result, Err(DecisionLogError::Io(_))));
+        // The search still ran.
+        assert!(mcts.best_action().is_some());
+
+        // run keeps the error rather than panicking.
+        let mut mcts = Mcts::new(Nim::new(10), logged_args(&path));
+        mcts.run();
+        assert!(matches!(
+            mcts.decision_log_error(),
+            Some(DecisionLogError::Io(_))
+        ));
+        assert!(mcts.best_action().is_some());
+    }
+
+    #[test]
+    fn test_state_hash_is_fnv_1a() {
+        let mut hasher = StableHasher::default();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+        // Integers hash the same whatever their width on this platform.
+        assert_eq!(state_hash(&7_usize), state_hash(&7_u64));
+        assert_eq!(state_hash(&-7_isize), state_hash(&-7_i64));
+    }
+
+    #[test]
+    fn test_read_bad_log() {
+        let path = log_path("bad");
+        std::fs::write(&path, "{\"seed\": 1}\n").unwrap();
+        let result: Result<Vec<DecisionRecord<Take>>, _> = read_decision_log(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            result,
+            Err(DecisionLogError::Parse { line: 1, .. })
+        ));
+    }
+}
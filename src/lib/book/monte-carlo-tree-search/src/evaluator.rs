@@ -0,0 +1,181 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Evaluators stand in for playouts, for example a policy and value network guiding the search.
+
+use std::sync::Arc;
+
+use crate::{Action, Float, Mcts, Playout, SimulationResult, State};
+
+/// Evaluator replaces random playouts with an evaluation of the state, as AlphaZero does with
+/// its policy and value network. It returns prior probabilities for the state's actions, used
+/// when the state is expanded, and the value of the state: the searching player's expected
+/// reward between 0 and 1, like SimulationResult::Reward.
+///
+/// Actions that aren't legal are ignored and legal actions left out get a prior of 0. The
+/// priors are renormalized over the legal actions, so they don't need to sum to 1. Terminal
+/// states are still scored by State::simulate, since their outcome is known exactly.
+pub trait Evaluator<_State, _Action>: Send + Sync {
+    fn evaluate(&self, state: &_State) -> (Vec<(_Action, Float)>, Float);
+
+    /// Evaluate `state` once, giving the priors of `actions` in the same order, renormalized
+    /// over them, and the value.
+    fn evaluate_actions(&self, state: &_State, actions: &[_Action]) -> (Vec<Float>, Float)
+    where
+        _Action: PartialEq,
+    {
+        let (evaluated, value) = self.evaluate(state);
+        let mut priors: Vec<Float> = actions
+            .iter()
+            .map(|action| {
+                evaluated
+                    .iter()
+                    .find(|(evaluated_action, _)| evaluated_action == action)
+                    .map_or(0.0, |(_, prior)| prior.max(0.0))
+            })
+            .collect();
+        let total: Float = priors.iter().sum();
+        if total > 0.0 {
+            priors.iter_mut().for_each(|prior| *prior /= total);
+        } else {
+            priors.fill(1.0 / actions.len() as Float);
+        }
+        (priors, value)
+    }
+}
+
+impl<_State, _Action, F> Evaluator<_State, _Action> for F
+where
+    F: Fn(&_State) -> (Vec<(_Action, Float)>, Float) + Send + Sync,
+{
+    fn evaluate(&self, state: &_State) -> (Vec<(_Action, Float)>, Float) {
+        self(state)
+    }
+}
+
+/// An evaluator's value of a state as the result of a single playout.
+pub(crate) fn value_playout<_Action>(value: Float) -> Vec<Playout<_Action>> {
+    vec![Playout {
+        result: SimulationResult::Reward(value),
+        actions: vec![],
+    }]
+}
+
+impl<_State, _Action> Mcts<_State, _Action>
+where
+    _State: State<_Action>,
+    _Action: Action,
+{
+    /// Search with `evaluator` in place of playouts. Each iteration evaluates the leaf it
+    /// reaches once, expanding it with the evaluator's priors in place of State::action_priors
+    /// and backing up its value as one playout, whatever `playouts_per_simulation` is. Use a
+    /// selection policy that follows priors, such as Puct.
+    pub fn with_evaluator(mut self, evaluator: impl Evaluator<_State, _Action> + 'static) -> Self {
+        self.evaluator = Some(Arc::new(evaluator));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena::tests::{args, Nim, Take};
+    use crate::{DirichletNoise, Puct, Solver};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Perfect knowledge of Nim: the player to move loses exactly when the stones are a
+    // multiple of three, and should then take one stone and otherwise leave a multiple of
+    // three.
+    fn perfect(state: &Nim) -> (Vec<(Take, Float)>, Float) {
+        let winning = !state.stones.is_multiple_of(3);
+        let take = if winning { state.stones % 3 } else { 1 };
+        let to_move_wins = if winning { 1.0 } else { 0.0 };
+        let value = if state.to_move == state.searcher {
+            to_move_wins
+        } else {
+            1.0 - to_move_wins
+        };
+        (vec![(Take(take), 1.0)], value)
+    }
+
+    #[test]
+    fn test_evaluate_actions() {
+        let state = Nim::new(10);
+        let actions = state.get_actions();
+        let evaluator = |_: &Nim| (vec![(Take(2), 3.0), (Take(3), 5.0)], 0.25);
+        assert_eq!(
+            evaluator.evaluate_actions(&state, &actions),
+            (vec![0.0, 1.0], 0.25)
+        );
+        let evaluator = |_: &Nim| (vec![], 0.5);
+        assert_eq!(
+            evaluator.evaluate_actions(&state, &actions),
+            (vec![0.5, 0.5], 0.5)
+        );
+    }
+
+    #[test]
+    fn test_evaluator_replaces_playouts() {
+        let mut args = args(200);
+        args.selection_policy = Arc::new(Puct);
+        args.solver = Solver::Disabled;
+        let mut mcts = Mcts::new(Nim::new(10), args).with_evaluator(perfect);
+        mcts.run();
+        assert_eq!(mcts.best_action(), Some(Take(1)));
+        // One evaluation per iteration rather than playouts_per_simulation playouts, except
+        // at the end of the game, which is still played out.
+        assert_eq!(mcts.search_info().iterations, 200);
+        assert!(mcts.tree.get_root().visits < 200 * 4);
+
+        // The priors come from the evaluator.
+        let tree = &mcts.tree;
+        let children = tree.get_children_nodekeys(tree.root);
+        assert_eq!(tree.get_node_from_nodekey(children[&Take(1)]).prior, 1.0);
+        assert_eq!(tree.get_node_from_nodekey(children[&Take(2)]).prior, 0.0);
+    }
+
+    #[test]
+    fn test_evaluates_each_leaf_once() {
+        let evaluations = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&evaluations);
+        let evaluator = move |state: &Nim| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            perfect(state)
+        };
+        let mut args = args(100);
+        args.selection_policy = Arc::new(Puct);
+        args.solver = Solver::Disabled;
+        args.root_noise = Some(DirichletNoise::new(0.3, 0.25).unwrap());
+        let mut mcts = Mcts::new(Nim::new(10), args).with_evaluator(evaluator);
+        mcts.run();
+        // The second search draws new root noise from the cached priors.
+        mcts.run();
+        assert!(evaluations.load(Ordering::Relaxed) <= 200);
+    }
+
+    #[test]
+    fn test_evaluator_in_parallel_searches() {
+        let mut args = args(50);
+        args.selection_policy = Arc::new(Puct);
+        let mut mcts = Mcts::new(Nim::new(10), args.clone()).with_evaluator(perfect);
+        mcts.run_parallel(2);
+        assert_eq!(mcts.best_action(), Some(Take(1)));
+
+        let mut mcts = Mcts::new(Nim::new(10), args).with_evaluator(perfect);
+        mcts.run_tree_parallel(2, 1);
+        assert_eq!(mcts.best_action(), Some(Take(1)));
+    }
+}
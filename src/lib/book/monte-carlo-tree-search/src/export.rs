@@ -0,0 +1,221 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Exporters that draw the search tree with Graphviz or Mermaid. Each node shows its wins and
+// visits and is colored by its win rate, from red for 0 to green for 1, and each edge shows its
+// action.
+
+use std::fmt::{self, Display, Write};
+
+use crate::{Action, Float, HashMap, HashSet, Mcts, MctsNodeKey, MctsTree, State, TreeFilter};
+
+/// TreeDisplay pretty-prints the nodes of a tree that a TreeFilter keeps, one per line in
+/// depth-first order, indented by depth and with the most visited children first. Each line
+/// has the action into the node, its wins and its visits. A node shared between transpositions
+/// only has its children printed the first time. Returned by [`Mcts::display_options`].
+pub struct TreeDisplay<'a, _State: State<_Action>, _Action: Action> {
+    tree: &'a MctsTree<_State, _Action>,
+    filter: TreeFilter,
+}
+
+impl<_State, _Action> Display for TreeDisplay<'_, _State, _Action>
+where
+    _State: State<_Action>,
+    _Action: Action,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut seen = HashSet::default();
+        let mut stack: Vec<(MctsNodeKey, Option<_Action>, usize)> = vec![(self.tree.root, None, 0)];
+        while let Some((node_key, action, depth)) = stack.pop() {
+            let node = self.tree.get_node_from_nodekey(node_key);
+            let indent = " ".repeat(depth * 2);
+            match action {
+                Some(action) => {
+                    write!(f, "{}{:?}: {} / {}", indent, action, node.wins, node.visits)?
+                }
+                None => write!(f, "{}root: {} / {}", indent, node.wins, node.visits)?,
+            }
+            if seen.insert(node_key) {
+                let children = self.tree.filtered_children(node_key, depth, self.filter);
+                stack.extend(
+                    children
+                        .into_iter()
+                        .rev()
+                        .map(|(action, child)| (child, Some(action), depth + 1)),
+                );
+            }
+            if !stack.is_empty() {
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<_State, _Action> MctsTree<_State, _Action>
+where
+    _State: State<_Action>,
+    _Action: Action,
+{
+    /// The nodes that `filter` keeps, in depth-first order from the root, and the edges between
+    /// them. A node shared between transpositions appears once, with an edge from each parent.
+    fn filtered_graph(
+        &self,
+        filter: TreeFilter,
+    ) -> (Vec<MctsNodeKey>, Vec<(MctsNodeKey, _Action, MctsNodeKey)>) {
+        let mut nodes = vec![];
+        let mut edges = vec![];
+        let mut seen = HashSet::default();
+        let mut stack = vec![(self.root, 0)];
+        while let Some((node_key, depth)) = stack.pop() {
+            if !seen.insert(node_key) {
+                continue;
+            }
+            nodes.push(node_key);
+            let children = self.filtered_children(node_key, depth, filter);
+            edges.extend(
+                children
+                    .iter()
+                    .map(|(action, child)| (node_key, *action, *child)),
+            );
+            stack.extend(children.iter().rev().map(|(_, child)| (*child, depth + 1)));
+        }
+        (nodes, edges)
+    }
+
+    pub(crate) fn display_options(&self, filter: TreeFilter) -> TreeDisplay<'_, _State, _Action> {
+        TreeDisplay { tree: self, filter }
+    }
+
+    /// The wins and visits of a node, and its win rate if it has been visited.
+    fn node_label(&self, node_key: MctsNodeKey) -> (String, Option<Float>) {
+        let node = self.get_node_from_nodekey(node_key);
+        let label = format!("{} / {}", node.wins, node.visits);
+        if node.visits == 0 {
+            return (label, None);
+        }
+        let win_rate = node.wins / node.visits as Float;
+        (
+            format!("{} ({:.0}%)", label, win_rate * 100.0),
+            Some(win_rate),
+        )
+    }
+}
+
+/// The fill color for a node with `win_rate`, blending red into green. Unvisited nodes are
+/// grey.
+fn win_rate_color(win_rate: Option<Float>) -> String {
+    let Some(win_rate) = win_rate else {
+        return "#dddddd".to_string();
+    };
+    let win_rate = win_rate.clamp(0.0, 1.0);
+    let blend = |red: Float, green: Float| (red + (green - red) * win_rate).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        blend(215.0, 26.0),
+        blend(48.0, 152.0),
+        blend(39.0, 80.0)
+    )
+}
+
+impl<_State, _Action> Mcts<_State, _Action>
+where
+    _State: State<_Action>,
+    _Action: Action,
+{
+    /// The nodes that `filter` keeps, for printing with `{}`. Unlike the whole tree, which can
+    /// have millions of nodes, this can be read after a real search, for example with a
+    /// `max_depth` of 2 and the `top_k` 3 children of each node.
+    pub fn display_options(&self, filter: TreeFilter) -> TreeDisplay<'_, _State, _Action> {
+        self.tree.display_options(filter)
+    }
+
+    /// The nodes that `filter` keeps as a Graphviz digraph, for example to render with
+    /// `dot -Tsvg`. Actions are labelled with their Debug form.
+    pub fn to_dot(&self, filter: TreeFilter) -> String {
+        let (nodes, edges) = self.tree.filtered_graph(filter);
+        let ids: HashMap<MctsNodeKey, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(id, node)| (*node, id))
+            .collect();
+        let escape = |label: String| label.replace('\\', "\\\\").replace('"', "\\\"");
+
+        let mut dot = String::from("digraph mcts {\n    node [shape=box, style=filled];\n");
+        for (id, node_key) in nodes.iter().enumerate() {
+            let (label, win_rate) = self.tree.node_label(*node_key);
+            writeln!(
+                dot,
+                "    n{} [label=\"{}\", fillcolor=\"{}\"];",
+                id,
+                escape(label),
+                win_rate_color(win_rate)
+            )
+            .unwrap();
+        }
+        for (parent, action, child) in edges {
+            writeln!(
+                dot,
+                "    n{} -> n{} [label=\"{}\"];",
+                ids[&parent],
+                ids[&child],
+                escape(format!("{:?}", action))
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// The nodes that `filter` keeps as a Mermaid flowchart, which renders in Markdown on
+    /// GitHub among others. Actions are labelled with their Debug form.
+    pub fn to_mermaid(&self, filter: TreeFilter) -> String {
+        let (nodes, edges) = self.tree.filtered_graph(filter);
+        let ids: HashMap<MctsNodeKey, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(id, node)| (*node, id))
+            .collect();
+        let escape = |label: String| label.replace('"', "#quot;");
+
+        let mut mermaid = String::from("graph TD\n");
+        for (id, node_key) in nodes.iter().enumerate() {
+            let (label, _) = self.tree.node_label(*node_key);
+            writeln!(mermaid, "    n{}[\"{}\"]", id, escape(label)).unwrap();
+        }
+        for (parent, action, child) in edges {
+            writeln!(
+                mermaid,
+                "    n{} -->|\"{}\"| n{}",
+                ids[&parent],
+                escape(format!("{:?}", action)),
+                ids[&child]
+            )
+            .unwrap();
+        }
+        for (id, node_key) in nodes.iter().enumerate() {
+            let (_, win_rate) = self.tree.node_label(*node_key);
+            writeln!(
+                mermaid,
+                "    style n{} fill:{}",
+                id,
+                win_rate_color(win_rate)
+            )
+            .unwrap();
+        }
+        mermaid
+    }
+}
@@ -17,19 +17,63 @@
 use std::cell::RefCell;
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
-use std::ops::{Deref, DerefMut};
+use std::io::{self, Write};
+use std::ops::ControlFlow;
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use std::time::{Duration, Instant};
 
+use rand::distributions::Distribution;
 use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_distr::Dirichlet;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use slotmap::new_key_type;
+use thiserror::Error;
+
+mod arena;
+mod checkpoint;
+mod decision_log;
+mod evaluator;
+mod export;
+mod playout;
+pub use arena::{arena, elo_difference, ArenaConfig, ArenaGame, ArenaResult};
+pub use checkpoint::{CheckpointError, CHECKPOINT_FORMAT_NAME, CHECKPOINT_FORMAT_VERSION};
+pub use decision_log::{
+    read_decision_log, state_hash, DecisionLogError, DecisionRecord, StableHasher,
+};
+pub use evaluator::Evaluator;
+pub use export::TreeDisplay;
+pub use playout::{
+    run_playouts, EpsilonGreedy, HeuristicWeighted, MoveHeuristic, PlayoutPolicy, RandomPlayout,
+};
+mod search;
+pub use search::{mcts_search, SearchOptions, SearchResult};
+mod selection;
+pub use selection::{ChildStats, Puct, Rave, SelectionPolicy, Ucb1Tuned, Uct};
+mod trace;
+pub use trace::{IterationTrace, Iterations};
+mod tuning;
+pub use tuning::{sweep, SweepKind, SweepSpace, TuningParams, TuningReport, TuningResult};
 
 pub type Int = i32;
 pub type Float = f64;
 pub type Rng = rand_pcg::Pcg64;
+
+/// SharedRng is the rng in MctsArgs. It is shared so that several searches, such as both players
+/// in a game, can draw from one seeded stream, and it is behind a Mutex rather than a RefCell so
+/// that an Mcts can be sent to and shared between threads, for example by a web server.
+pub type SharedRng = Arc<Mutex<Rng>>;
+
+/// A SharedRng seeded with `seed`.
+pub fn shared_rng(seed: u64) -> SharedRng {
+    Arc::new(Mutex::new(Rng::seed_from_u64(seed)))
+}
 pub type HashMap<K, V> = rustc_hash::FxHashMap<K, V>;
+pub type HashSet<K> = rustc_hash::FxHashSet<K>;
 
 pub trait Action: Clone + Copy + PartialEq + Eq + Hash + Debug + Serialize {}
 
@@ -46,6 +90,30 @@ where
     fn get_actions(&self) -> Vec<_Action>;
     fn get_next_state(&self, action: &_Action) -> Self;
     fn is_terminal(&self) -> bool;
+
+    /// Prior probabilities of `actions`, in the same order, for selection policies that use
+    /// them such as Puct. Uniform unless overridden.
+    fn action_priors(&self, actions: &[_Action]) -> Vec<Float> {
+        vec![1.0 / actions.len() as Float; actions.len()]
+    }
+
+    /// Like simulate, but also returns the actions taken in each playout, which selection
+    /// policies that use AMAF statistics such as Rave need. The default reports no actions, so
+    /// only the actions on the way down the tree count towards AMAF statistics.
+    fn simulate_playouts(
+        &self,
+        playouts: Int,
+        max_depth_per_playout: Int,
+        rng: &mut Rng,
+    ) -> Vec<Playout<_Action>> {
+        self.simulate(playouts, max_depth_per_playout, rng)
+            .into_iter()
+            .map(|result| Playout {
+                result,
+                actions: vec![],
+            })
+            .collect()
+    }
 }
 
 new_key_type! { struct MctsNodeKey; }
@@ -55,7 +123,44 @@ struct MctsNode<_State: State<_Action>, _Action: Action> {
     parent: Option<MctsNodeKey>,
     children: HashMap<_Action, MctsNodeKey>,
     visits: Int,
-    wins: Int,
+
+    /// Total reward of the playouts through this node, which is the number of wins if every
+    /// playout is either won or not.
+    wins: Float,
+
+    /// Prior probability of the action leading to this node, set when its parent is expanded.
+    prior: Float,
+
+    /// The prior mixed with root noise, which selection uses instead while this node is a child
+    /// of the root. See DirichletNoise.
+    #[serde(skip)]
+    noisy_prior: Option<Float>,
+
+    /// All-moves-as-first statistics: playouts from the parent in which the action leading to
+    /// this node was played at any later point. Only kept for selection policies that use them.
+    amaf_visits: Int,
+    amaf_wins: Float,
+
+    /// Playouts in flight through this node during a tree-parallel search, counted as visits
+    /// that were not won until their real results are backpropagated.
+    #[serde(skip)]
+    virtual_loss: Int,
+
+    /// Playouts in flight through this node during a tree-parallel search, whatever their
+    /// virtual loss. Recycling leaves these nodes alone.
+    #[serde(skip)]
+    in_flight: Int,
+
+    /// The exact reward of this node, from the same perspective as `wins`, once the solver has
+    /// proven it. See Solver.
+    #[serde(default)]
+    proven: Option<Float>,
+
+    /// When this node was last on the path of a backpropagation, by the tree's `visit_clock`.
+    /// Recycling collapses the least recently visited nodes first.
+    #[serde(skip)]
+    last_visited: u64,
+
     phantom_state: std::marker::PhantomData<_State>,
 }
 
@@ -65,43 +170,134 @@ impl<_State: State<_Action>, _Action: Action> MctsNode<_State, _Action> {
             parent,
             children: HashMap::default(),
             visits: 0,
-            wins: 0,
+            wins: 0.0,
+            prior: 0.0,
+            noisy_prior: None,
+            amaf_visits: 0,
+            amaf_wins: 0.0,
+            virtual_loss: 0,
+            in_flight: 0,
+            proven: None,
+            last_visited: 0,
             phantom_state: std::marker::PhantomData,
         }
     }
+
+    /// The node's score under `policy` as a child of a node with `parent_visits` visits, with
+    /// first play urgency standing in for the infinite score of an unvisited child.
+    fn selection_score(
+        &self,
+        policy: &dyn SelectionPolicy,
+        parent_visits: Int,
+        exploration_constant: Float,
+        first_play_urgency: Option<Float>,
+    ) -> Float {
+        let child_stats = ChildStats {
+            visits: self.visits + self.virtual_loss,
+            wins: self.wins,
+            prior: self.noisy_prior.unwrap_or(self.prior),
+            amaf_visits: self.amaf_visits,
+            amaf_wins: self.amaf_wins,
+        };
+        match policy.score(&child_stats, parent_visits, exploration_constant) {
+            Float::INFINITY => first_play_urgency.unwrap_or(Float::INFINITY),
+            score => score,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(deserialize = "_State: Deserialize<'de>, _Action: Deserialize<'de>"))]
 struct MctsTree<_State: State<_Action>, _Action: Action> {
     nodes: slotmap::SlotMap<MctsNodeKey, MctsNode<_State, _Action>>,
     root: MctsNodeKey,
     root_state: _State,
+
+    /// If transpositions are shared, the node for each state that has one. Nodes can then have
+    /// several parents, so the tree is really a DAG, and a node's `parent` is just the first.
+    #[serde(skip)]
+    transpositions: Option<HashMap<_State, MctsNodeKey>>,
+
+    /// Number of backpropagations so far, to stamp nodes with when they were last visited.
+    #[serde(skip)]
+    visit_clock: u64,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct MctsNodeForSerialization<_State: State<_Action>, _Action: Action> {
     action: Option<_Action>,
     visits: Int,
-    wins: Int,
+    wins: Float,
     children: Vec<Box<MctsNodeForSerialization<_State, _Action>>>,
 
     #[serde(skip)]
     phantom_state: std::marker::PhantomData<_State>,
 }
 
+impl<_State: State<_Action>, _Action: Action> MctsNodeForSerialization<_State, _Action> {
+    pub fn new(
+        action: Option<_Action>,
+        visits: Int,
+        wins: Float,
+        children: Vec<MctsNodeForSerialization<_State, _Action>>,
+    ) -> Self {
+        MctsNodeForSerialization {
+            action,
+            visits,
+            wins,
+            children: children.into_iter().map(Box::new).collect(),
+            phantom_state: std::marker::PhantomData,
+        }
+    }
+
+    /// The action that led to this node from its parent; None for the root.
+    pub fn action(&self) -> Option<_Action> {
+        self.action
+    }
+
+    pub fn visits(&self) -> Int {
+        self.visits
+    }
+
+    /// Total reward of the playouts through the node; see SimulationResult::reward.
+    pub fn wins(&self) -> Float {
+        self.wins
+    }
+
+    /// Children, most visited first.
+    pub fn children(&self) -> impl Iterator<Item = &MctsNodeForSerialization<_State, _Action>> {
+        self.children.iter().map(|child| child.as_ref())
+    }
+}
+
 fn create_tree_for_serialization<_State: State<_Action>, _Action: Action>(
     tree: &MctsTree<_State, _Action>,
     node: MctsNodeKey,
     action: Option<_Action>,
 ) -> MctsNodeForSerialization<_State, _Action> {
-    let node = tree.get_node_from_nodekey(node);
+    serialize_node(tree, node, action, &mut HashSet::default())
+}
 
-    let mut children: Vec<Box<MctsNodeForSerialization<_State, _Action>>> = node
-        .children
-        .iter()
-        .map(|(action, child)| Box::new(create_tree_for_serialization(tree, *child, Some(*action))))
-        .collect();
-    children.sort_unstable_by_key(|child| (-child.visits, -child.wins));
+/// With shared transpositions a node can be reached along several paths, or even from itself.
+/// Its children are only serialized where it is first reached; elsewhere it is a leaf.
+fn serialize_node<_State: State<_Action>, _Action: Action>(
+    tree: &MctsTree<_State, _Action>,
+    node_key: MctsNodeKey,
+    action: Option<_Action>,
+    seen: &mut HashSet<MctsNodeKey>,
+) -> MctsNodeForSerialization<_State, _Action> {
+    let node = tree.get_node_from_nodekey(node_key);
+
+    let mut children: Vec<Box<MctsNodeForSerialization<_State, _Action>>> = if seen.insert(node_key)
+    {
+        node.children
+            .iter()
+            .map(|(action, child)| Box::new(serialize_node(tree, *child, Some(*action), seen)))
+            .collect()
+    } else {
+        vec![]
+    };
+    children.sort_unstable_by(|a, b| b.visits.cmp(&a.visits).then(b.wins.total_cmp(&a.wins)));
 
     MctsNodeForSerialization {
         action,
@@ -112,39 +308,25 @@ fn create_tree_for_serialization<_State: State<_Action>, _Action: Action>(
     }
 }
 
-// implement Display for MctsTree. Pretty print the tree. Print all paths in depth-first order.
-// Don't print the state, just print the action that leads to the node, the visits and wins.
+/// TreeFilter limits the nodes written when exporting or displaying a tree. Nodes more than
+/// `max_depth` actions from the root, or with fewer than `min_visits` visits, are left out
+/// together with their descendants, as are all but the `top_k` most visited children of each
+/// node; the root is always written. The default writes the whole tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TreeFilter {
+    pub max_depth: Option<usize>,
+    pub min_visits: Int,
+    pub top_k: Option<usize>,
+}
+
+// Pretty print the whole tree. See TreeDisplay.
 impl<_State, _Action> Display for MctsTree<_State, _Action>
 where
     _State: State<_Action>,
     _Action: Action,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut s = String::new();
-        let root = self.get_root_nodekey();
-        let mut stack: Vec<(MctsNodeKey, Option<_Action>, usize)> = vec![(root, None, 0)];
-        while let Some((node, action, depth)) = stack.pop() {
-            let node = self.get_node_from_nodekey(node);
-            let indent = " ".repeat(depth * 2);
-            if let Some(action) = action {
-                s.push_str(&format!(
-                    "{}{:?}: {} / {}",
-                    indent, action, node.wins, node.visits
-                ));
-            } else {
-                s.push_str(&format!("{}root: {} / {}", indent, node.wins, node.visits));
-            }
-            stack.extend(
-                node.children
-                    .iter()
-                    .map(|(action, child)| (*child, Some(*action), depth + 1)),
-            );
-            if !stack.is_empty() {
-                s.push('\n');
-            }
-        }
-        write!(f, "{}", s).expect("Failed to write to string.");
-        Ok(())
+        self.display_options(TreeFilter::default()).fmt(f)
     }
 }
 
@@ -160,9 +342,34 @@ where
             nodes,
             root,
             root_state,
+            transpositions: None,
+            visit_clock: 0,
         }
     }
 
+    /// Start or drop the transposition table. The state of every node is worked out from the
+    /// root, so this also rebuilds the table of a tree restored from a checkpoint.
+    fn with_transpositions(mut self, transpositions: Transpositions) -> Self {
+        self.transpositions = match transpositions {
+            Transpositions::Separate => None,
+            Transpositions::Share => {
+                let mut table = HashMap::default();
+                let mut stack = vec![(self.root_state.clone(), self.root)];
+                while let Some((state, node_key)) = stack.pop() {
+                    if table.contains_key(&state) {
+                        continue;
+                    }
+                    for (action, child) in self.get_children_nodekeys(node_key) {
+                        stack.push((state.get_next_state(action), *child));
+                    }
+                    table.insert(state, node_key);
+                }
+                Some(table)
+            }
+        };
+        self
+    }
+
     fn get_node_from_nodekey(&self, node: MctsNodeKey) -> &MctsNode<_State, _Action> {
         &self.nodes[node]
     }
@@ -194,31 +401,656 @@ where
         self.nodes[parent].children.insert(action, child);
         child
     }
+
+    /// Add the child reached from `parent`, whose state is `state`, by `action`. If transpositions
+    /// are shared and another path already reached the same state, that node becomes the child.
+    fn add_child_for_state(
+        &mut self,
+        parent: MctsNodeKey,
+        state: &_State,
+        action: _Action,
+    ) -> MctsNodeKey {
+        if self.transpositions.is_none() {
+            return self.add_child(parent, action);
+        }
+        let child_state = state.get_next_state(&action);
+        let existing = self.transpositions.as_ref().unwrap().get(&child_state);
+        match existing {
+            Some(child) => {
+                let child = *child;
+                self.nodes[parent].children.insert(action, child);
+                child
+            }
+            None => {
+                let child = self.add_child(parent, action);
+                self.transpositions
+                    .as_mut()
+                    .unwrap()
+                    .insert(child_state, child);
+                child
+            }
+        }
+    }
+
+    /// Add the statistics of `other`, a tree searched from the same root state, to this tree.
+    /// Nodes are matched by the actions that lead to them from the root, and nodes only `other`
+    /// has are added. A node `other` shares between several paths is only added once.
+    fn merge(&mut self, other: &MctsTree<_State, _Action>) {
+        let mut merged = HashSet::default();
+        let mut stack = vec![(self.root, other.root, self.root_state.clone())];
+        while let Some((node_key, other_key, state)) = stack.pop() {
+            if !merged.insert(other_key) {
+                continue;
+            }
+            let other_node = other.get_node_from_nodekey(other_key);
+            let node = self.get_mut_node_from_nodekey(node_key);
+            node.visits += other_node.visits;
+            node.wins += other_node.wins;
+            node.amaf_visits += other_node.amaf_visits;
+            node.amaf_wins += other_node.amaf_wins;
+            node.proven = node.proven.or(other_node.proven);
+            for (action, other_child) in &other_node.children {
+                let child = match self.nodes[node_key].children.get(action) {
+                    Some(child) => *child,
+                    None => {
+                        let child = self.add_child_for_state(node_key, &state, *action);
+                        self.nodes[child].prior = other.get_node_from_nodekey(*other_child).prior;
+                        child
+                    }
+                };
+                stack.push((child, *other_child, state.get_next_state(action)));
+            }
+        }
+    }
+
+    /// Descend from the root to a leaf. The path rather than parent links is used to back up
+    /// results, because with shared transpositions a node can have several parents. Selection
+    /// also stops early rather than follow a transposition back to a node already on the path.
+    fn select(
+        &self,
+        policy: &dyn SelectionPolicy,
+        exploration_constant: Float,
+        first_play_urgency: Option<Float>,
+    ) -> Descent<_State, _Action> {
+        let mut descent = Descent {
+            path: vec![self.get_root_nodekey()],
+            actions: vec![],
+            state: self.root_state.clone(),
+            evaluation: None,
+        };
+        loop {
+            // A proven node's value is known exactly, so there is nothing to search below it.
+            let node_key = *descent.path.last().unwrap();
+            if self.get_node_from_nodekey(node_key).proven.is_some() {
+                return descent;
+            }
+            let uct_select_result = uct_select(
+                self,
+                *descent.path.last().unwrap(),
+                policy,
+                exploration_constant,
+                first_play_urgency,
+            );
+            if uct_select_result.finished || descent.path.contains(&uct_select_result.node) {
+                return descent;
+            } else {
+                descent.push(uct_select_result.node, uct_select_result.action.unwrap());
+            }
+        }
+    }
+
+    /// Expand the leaf at the end of the descent, adding the child to simulate to it. If
+    /// `node_limit` leaves no room for the leaf's children, the leaf is simulated instead.
+    ///
+    /// With an evaluator the leaf is expanded on its first visit, and evaluated once for both
+    /// its children's priors and its own value, which is kept in the descent to back up in place
+    /// of playouts. No child is added to the descent.
+    fn expand(
+        &mut self,
+        descent: &mut Descent<_State, _Action>,
+        rng: &mut Rng,
+        node_limit: NodeLimit,
+        root_noise: Option<DirichletNoise>,
+        evaluator: Option<&dyn Evaluator<_State, _Action>>,
+    ) {
+        let node_key = *descent.path.last().unwrap();
+        let state = &descent.state;
+
+        // If the node is terminal, we don't need to expand it because the game is over.
+        // However, we still return it because we want to backpropagate the result.
+        if state.is_terminal() {
+            return;
+        }
+
+        // The node is not terminal. If the node has no visits, we don't need to expand it and
+        // instead return it because we want to simulate it. If we don't we will skip it!
+        if evaluator.is_none()
+            && node_key != self.root
+            && self.get_node_from_nodekey(node_key).visits == 0
+        {
+            return;
+        }
+
+        // Selection stopped at a node that is already expanded because its best child is on
+        // the path; simulate from the node itself.
+        if !self.get_children_nodekeys(node_key).is_empty() {
+            return;
+        }
+
+        let actions = state.get_actions();
+        let (priors, evaluation) = match evaluator {
+            Some(evaluator) => {
+                let (priors, value) = evaluator.evaluate_actions(state, &actions);
+                (priors, Some(value))
+            }
+            None => (state.action_priors(&actions), None),
+        };
+        descent.evaluation = evaluation;
+        if !self.make_room(actions.len(), node_limit, &descent.path) {
+            return;
+        }
+
+        let state = &descent.state;
+        for (action, prior) in actions.iter().zip(priors) {
+            let child = self.add_child_for_state(node_key, state, *action);
+            self.nodes[child].prior = prior;
+        }
+        if let (true, Some(noise)) = (node_key == self.root, root_noise) {
+            self.add_root_noise(noise, rng);
+        }
+        if evaluation.is_some() {
+            return;
+        }
+
+        // Choose a random child, other than a transposition of a node already on the path.
+        let children = self.get_children_nodekeys(node_key);
+        let candidates: Vec<&_Action> = actions
+            .iter()
+            .filter(|action| !descent.path.contains(&children[*action]))
+            .collect();
+        let Some(random_action) = candidates.choose(rng) else {
+            return;
+        };
+        descent.push(children[*random_action], **random_action);
+    }
+
+    /// Mix fresh Dirichlet noise into the priors of the root's children, replacing any noise
+    /// mixed in before.
+    fn add_root_noise(&mut self, noise: DirichletNoise, rng: &mut Rng) {
+        let children: Vec<MctsNodeKey> = self
+            .root_state
+            .get_actions()
+            .iter()
+            .filter_map(|action| self.nodes[self.root].children.get(action).copied())
+            .collect();
+        let mut priors: Vec<Float> = children
+            .iter()
+            .map(|child| self.nodes[*child].prior)
+            .collect();
+        noise.mix(&mut priors, rng);
+        for (child, prior) in children.into_iter().zip(priors) {
+            self.nodes[child].noisy_prior = Some(prior);
+        }
+    }
+
+    /// Back up the playouts from the end of the descent, crediting each node with the reward
+    /// from `perspective`, discounted by `discount` for every move from the node to the end of
+    /// the playout. If `amaf` is set, also update the AMAF statistics of the children of each
+    /// node on the path whose action was played later in the descent or the playout; with
+    /// alternating perspectives only the later actions of the same player count.
+    fn back_propagate(
+        &mut self,
+        descent: &Descent<_State, _Action>,
+        playouts: &[Playout<_Action>],
+        amaf: bool,
+        perspective: RewardPerspective,
+        discount: Option<Float>,
+    ) {
+        let leaf_depth = descent.path.len() - 1;
+        let reward_at_depth = |playout: &Playout<_Action>, depth: usize| {
+            let moves = (leaf_depth + playout.length() as usize).saturating_sub(depth);
+            let reward = discounted(playout.result.reward(), discount, moves);
+            perspective.reward_at_depth(reward, depth)
+        };
+
+        self.visit_clock += 1;
+        let visit_clock = self.visit_clock;
+        for (depth, node_key) in descent.path.iter().enumerate() {
+            let node = self.get_mut_node_from_nodekey(*node_key);
+            node.last_visited = visit_clock;
+            for playout in playouts {
+                node.visits += 1;
+                node.wins += reward_at_depth(playout, depth);
+            }
+        }
+        if !amaf {
+            return;
+        }
+
+        // The action at ply `i` of the descent followed by the playout is made by the player to
+        // move at depth `i`. Actions are kept apart by the parity of their ply if players
+        // alternate, and all together otherwise.
+        let parity = |ply: usize| match perspective {
+            RewardPerspective::Root => 0,
+            RewardPerspective::Alternating => ply % 2,
+        };
+        for playout in playouts {
+            let mut played: [HashSet<_Action>; 2] = Default::default();
+            for (ply, action) in playout.actions.iter().enumerate() {
+                played[parity(leaf_depth + ply)].insert(*action);
+            }
+            // Walk up the path, so that the actions played after each node are known.
+            for (depth, node_key) in descent.path.iter().enumerate().rev() {
+                if let Some(action) = descent.actions.get(depth) {
+                    played[parity(depth)].insert(*action);
+                }
+                let played = &played[parity(depth)];
+                let children: Vec<MctsNodeKey> = self
+                    .get_children_nodekeys(*node_key)
+                    .iter()
+                    .filter(|(action, _)| played.contains(*action))
+                    .map(|(_, child)| *child)
+                    .collect();
+                let child_reward = reward_at_depth(playout, depth + 1);
+                for child in children {
+                    let child = self.get_mut_node_from_nodekey(child);
+                    child.amaf_visits += 1;
+                    child.amaf_wins += child_reward;
+                }
+            }
+        }
+    }
+
+    /// If the solver has proven the node at the end of the descent, `playouts` playouts with its
+    /// exact reward to back up in place of simulating.
+    fn proven_playouts(
+        &self,
+        descent: &Descent<_State, _Action>,
+        playouts: Int,
+        perspective: RewardPerspective,
+    ) -> Option<Vec<Playout<_Action>>> {
+        let depth = descent.path.len() - 1;
+        let proven = self.get_node_from_nodekey(descent.path[depth]).proven?;
+        // Crediting a node with its reward at its depth is its own inverse.
+        let result = SimulationResult::Reward(perspective.reward_at_depth(proven, depth));
+        Some(
+            (0..playouts)
+                .map(|_| Playout {
+                    result,
+                    actions: vec![],
+                })
+                .collect(),
+        )
+    }
+
+    /// MCTS-Solver: if the descent ended in a terminal state, prove the leaf with the reward of
+    /// its playouts, which can't vary once the game is over. Then prove each ancestor on the
+    /// path that its children now settle, stopping at the first that they don't.
+    fn prove(
+        &mut self,
+        descent: &Descent<_State, _Action>,
+        playouts: &[Playout<_Action>],
+        perspective: RewardPerspective,
+    ) {
+        let leaf_depth = descent.path.len() - 1;
+        let leaf = descent.path[leaf_depth];
+        if self.get_node_from_nodekey(leaf).proven.is_none() {
+            if !descent.state.is_terminal() || playouts.is_empty() {
+                return;
+            }
+            let reward = playouts
+                .iter()
+                .map(|playout| playout.result.reward())
+                .sum::<Float>()
+                / playouts.len() as Float;
+            self.get_mut_node_from_nodekey(leaf).proven =
+                Some(perspective.reward_at_depth(reward, leaf_depth));
+        }
+        for node_key in descent.path[..leaf_depth].iter().rev() {
+            match self.proven_from_children(*node_key, perspective) {
+                Some(reward) => self.get_mut_node_from_nodekey(*node_key).proven = Some(reward),
+                None => return,
+            }
+        }
+    }
+
+    /// The reward of `node_key` if its children prove it: either the player choosing between
+    /// them has a proven win, a reward of 1, or every child is proven and they choose the best.
+    fn proven_from_children(
+        &self,
+        node_key: MctsNodeKey,
+        perspective: RewardPerspective,
+    ) -> Option<Float> {
+        let children = self.get_children_nodekeys(node_key);
+        let mut best: Option<Float> = None;
+        let mut all_proven = !children.is_empty();
+        for child in children.values() {
+            match self.get_node_from_nodekey(*child).proven {
+                Some(reward) => best = Some(best.map_or(reward, |best| best.max(reward))),
+                None => all_proven = false,
+            }
+        }
+        let best = best?;
+        (all_proven || best == 1.0).then(|| perspective.reward_from_best_child(best))
+    }
+
+    /// Make `new_root`, whose state is `root_state`, the root, dropping the nodes it can't reach.
+    fn reroot(&mut self, new_root: MctsNodeKey, root_state: _State) {
+        // Root noise only applies to the root's children.
+        let old_children: Vec<MctsNodeKey> = self
+            .get_children_nodekeys(self.root)
+            .values()
+            .copied()
+            .collect();
+        for child in old_children {
+            self.nodes[child].noisy_prior = None;
+        }
+        self.drop_unreachable(new_root);
+        self.nodes[new_root].parent = None;
+        self.root = new_root;
+        self.root_state = root_state;
+    }
+
+    /// Drop the nodes that can't be reached from `from`.
+    fn drop_unreachable(&mut self, from: MctsNodeKey) {
+        let mut reachable = HashSet::default();
+        let mut stack = vec![from];
+        while let Some(node_key) = stack.pop() {
+            if reachable.insert(node_key) {
+                stack.extend(self.get_children_nodekeys(node_key).values());
+            }
+        }
+        self.nodes
+            .retain(|node_key, _| reachable.contains(&node_key));
+        for node in self.nodes.values_mut() {
+            if node
+                .parent
+                .is_some_and(|parent| !reachable.contains(&parent))
+            {
+                node.parent = None;
+            }
+        }
+        if let Some(table) = self.transpositions.as_mut() {
+            table.retain(|_, node_key| reachable.contains(node_key));
+        }
+    }
+
+    /// Whether `needed` more nodes fit within `node_limit`, recycling nodes to make room if the
+    /// limit allows it. Nodes in `keep` are never recycled.
+    fn make_room(&mut self, needed: usize, node_limit: NodeLimit, keep: &[MctsNodeKey]) -> bool {
+        match node_limit {
+            NodeLimit::Unlimited => true,
+            NodeLimit::StopExpanding(max_nodes) => self.nodes.len() + needed <= max_nodes,
+            NodeLimit::Recycle(max_nodes) => {
+                if self.nodes.len() + needed > max_nodes {
+                    self.recycle((max_nodes / 4 * 3).saturating_sub(needed), keep);
+                }
+                self.nodes.len() + needed <= max_nodes
+            }
+        }
+    }
+
+    /// Shrink the tree to at most `target` nodes, if it can be done without touching `keep`, by
+    /// collapsing the least recently visited expanded nodes back into leaves. A collapsed node
+    /// keeps its own statistics and is expanded afresh if the search comes back to it. Only nodes
+    /// whose children are all leaves are collapsed, so old subtrees are peeled away from the
+    /// bottom, and nodes that other threads are playing out through are left alone.
+    fn recycle(&mut self, target: usize, keep: &[MctsNodeKey]) {
+        while self.nodes.len() > target {
+            let mut frontier: Vec<MctsNodeKey> = self
+                .nodes
+                .iter()
+                .filter(|(node_key, node)| {
+                    !node.children.is_empty()
+                        && node.in_flight == 0
+                        && !keep.contains(node_key)
+                        && node
+                            .children
+                            .values()
+                            .all(|child| self.nodes[*child].children.is_empty())
+                })
+                .map(|(node_key, _)| node_key)
+                .collect();
+            if frontier.is_empty() {
+                return;
+            }
+            frontier.sort_by_key(|node_key| self.nodes[*node_key].last_visited);
+            let mut excess = self.nodes.len() - target;
+            for node_key in frontier {
+                if excess == 0 {
+                    break;
+                }
+                let children = std::mem::take(&mut self.nodes[node_key].children);
+                excess = excess.saturating_sub(children.len());
+            }
+            // With shared transpositions a collapsed node's children may still be reachable
+            // through other parents, so only the unreachable ones go.
+            self.drop_unreachable(self.root);
+        }
+    }
+
+    /// Add `amount` of virtual loss to every node on the path. A negative amount removes it
+    /// again.
+    fn add_virtual_loss(&mut self, path: &[MctsNodeKey], amount: Int) {
+        for node_key in path {
+            self.get_mut_node_from_nodekey(*node_key).virtual_loss += amount;
+        }
+    }
+
+    /// Mark a playout as in flight through every node on the path, with `virtual_loss`.
+    fn begin_playout(&mut self, path: &[MctsNodeKey], virtual_loss: Int) {
+        self.add_virtual_loss(path, virtual_loss);
+        for node_key in path {
+            self.get_mut_node_from_nodekey(*node_key).in_flight += 1;
+        }
+    }
+
+    /// Undo [`MctsTree::begin_playout`] once the playout's results are in.
+    fn end_playout(&mut self, path: &[MctsNodeKey], virtual_loss: Int) {
+        self.add_virtual_loss(path, -virtual_loss);
+        for node_key in path {
+            self.get_mut_node_from_nodekey(*node_key).in_flight -= 1;
+        }
+    }
+
+    /// The children of `node_key`, which is `depth` actions from the root, that `filter` keeps,
+    /// most visited first.
+    fn filtered_children(
+        &self,
+        node_key: MctsNodeKey,
+        depth: usize,
+        filter: TreeFilter,
+    ) -> Vec<(_Action, MctsNodeKey)> {
+        if filter.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+            return vec![];
+        }
+        let mut children: Vec<(_Action, MctsNodeKey)> = self
+            .get_children_nodekeys(node_key)
+            .iter()
+            .filter(|(_, child)| self.get_node_from_nodekey(**child).visits >= filter.min_visits)
+            .map(|(action, child)| (*action, *child))
+            .collect();
+        children.sort_unstable_by(|(_, a), (_, b)| {
+            let (a, b) = (
+                self.get_node_from_nodekey(*a),
+                self.get_node_from_nodekey(*b),
+            );
+            b.visits.cmp(&a.visits).then(b.wins.total_cmp(&a.wins))
+        });
+        if let Some(top_k) = filter.top_k {
+            children.truncate(top_k);
+        }
+        children
+    }
+
+    /// Write the nodes that `filter` keeps as JSON, in the layout of MctsNodeForSerialization,
+    /// without building the whole tree in memory first. The tree is walked with an explicit
+    /// stack, so deep trees don't overflow the call stack either. As in serialize_node, a node
+    /// shared between transpositions only has its children written the first time.
+    fn write_json(&self, writer: &mut impl Write, filter: TreeFilter) -> io::Result<()> {
+        enum Step<_Action> {
+            Open {
+                node_key: MctsNodeKey,
+                action: Option<_Action>,
+                depth: usize,
+                first: bool,
+            },
+            Close,
+        }
+
+        let mut seen = HashSet::default();
+        let mut stack = vec![Step::Open {
+            node_key: self.root,
+            action: None,
+            depth: 0,
+            first: true,
+        }];
+        while let Some(step) = stack.pop() {
+            let Step::Open {
+                node_key,
+                action,
+                depth,
+                first,
+            } = step
+            else {
+                writer.write_all(b"]}")?;
+                continue;
+            };
+            if !first {
+                writer.write_all(b",")?;
+            }
+            let node = self.get_node_from_nodekey(node_key);
+            writer.write_all(b"{\"action\":")?;
+            serde_json::to_writer(&mut *writer, &action)?;
+            write!(writer, ",\"visits\":{},\"wins\":", node.visits)?;
+            serde_json::to_writer(&mut *writer, &node.wins)?;
+            writer.write_all(b",\"children\":[")?;
+            stack.push(Step::Close);
+            if seen.insert(node_key) {
+                let children = self.filtered_children(node_key, depth, filter);
+                stack.extend(
+                    children
+                        .into_iter()
+                        .enumerate()
+                        .rev()
+                        .map(|(i, (action, child))| Step::Open {
+                            node_key: child,
+                            action: Some(action),
+                            depth: depth + 1,
+                            first: i == 0,
+                        }),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// The child of `node_key` that `policy` picks, and the action leading to it.
+    fn best_child(
+        &self,
+        node_key: MctsNodeKey,
+        policy: BestActionPolicy,
+    ) -> Option<(_Action, MctsNodeKey)> {
+        let mut best = None;
+        let mut best_score = (0, Float::NEG_INFINITY);
+        for (action, child) in self.get_children_nodekeys(node_key) {
+            let child_node = self.get_node_from_nodekey(*child);
+            // Whatever the policy, proven wins come first and proven losses last.
+            let proof_rank = match child_node.proven {
+                Some(1.0) => 2,
+                Some(0.0) => 0,
+                _ => 1,
+            };
+            let score = (proof_rank, policy.score(child_node.visits, child_node.wins));
+            if best.is_none() || score > best_score {
+                best_score = score;
+                best = Some((*action, *child));
+            }
+        }
+        best
+    }
+
+    /// The actions from the root that follow the most visited child at every step, as far as
+    /// there are visited children, without going round a cycle of shared transpositions.
+    fn principal_variation(&self) -> Vec<_Action> {
+        let mut variation = vec![];
+        let mut seen = HashSet::default();
+        let mut node_key = self.root;
+        seen.insert(node_key);
+        while let Some((action, child)) = self.best_child(node_key, BestActionPolicy::MostVisits) {
+            if self.get_node_from_nodekey(child).visits == 0 || !seen.insert(child) {
+                break;
+            }
+            variation.push(action);
+            node_key = child;
+        }
+        variation
+    }
+
+    /// The largest number of actions from the root to any node, counting each node at its
+    /// shortest distance if transpositions make it reachable along several paths.
+    fn max_depth(&self) -> usize {
+        let mut seen = HashSet::default();
+        seen.insert(self.root);
+        let mut level = vec![self.root];
+        let mut depth = 0;
+        loop {
+            let next: Vec<MctsNodeKey> = level
+                .iter()
+                .flat_map(|node_key| self.get_children_nodekeys(*node_key).values())
+                .filter(|child| seen.insert(**child))
+                .copied()
+                .collect();
+            if next.is_empty() {
+                return depth;
+            }
+            level = next;
+            depth += 1;
+        }
+    }
 }
 
-/// uct_score is the UCT score function. It is a combination of exploitation and exploration.
+/// The current time, or None on wasm32-unknown-unknown, where reading the clock panics.
+fn now() -> Option<Instant> {
+    if cfg!(target_arch = "wasm32") {
+        None
+    } else {
+        Some(Instant::now())
+    }
+}
+
+/// ucb1 is the UCB1 upper confidence bound for an arm or child with the given mean reward, which
+/// has been tried visits times out of parent_visits in total. It is shared by UCT here and by
+/// multi-armed bandit policies.
 ///
 /// See Chapter 5 page 163.
 ///
-/// Note that if the current node is not visited, the formula in the book would be divide-by-zero
-/// and give NaN. In this implementation we return +inf instead. This means that all children
-/// nodes are visited at least once.
-fn uct_score(
+/// Note that if the arm has not been tried, the formula in the book would be divide-by-zero and
+/// give NaN. In this implementation we return +inf instead. This means that every arm is tried at
+/// least once.
+pub fn ucb1(mean: Float, visits: Int, parent_visits: Int, exploration_constant: Float) -> Float {
+    if visits == 0 {
+        return Float::INFINITY;
+    }
+    let visits_float = Float::from(visits);
+    let parent_visits_float = Float::from(parent_visits);
+    mean + exploration_constant * (parent_visits_float.ln() / visits_float).sqrt()
+}
+
+/// uct_score is the UCT score function. It is a combination of exploitation, the mean reward of
+/// playouts through the node, which is the fraction that were won if playouts are only won or
+/// not, and exploration. See ucb1.
+pub(crate) fn uct_score(
     node_visits: Int,
-    node_wins: Int,
+    node_wins: Float,
     parent_visits: Int,
     exploration_constant: Float,
 ) -> Float {
     if node_visits == 0 {
         return Float::INFINITY;
     }
-    let node_wins_float = Float::from(node_wins);
-    let node_visits_float = Float::from(node_visits);
-    let parent_visits_float = Float::from(parent_visits);
-    let exploitation_term = node_wins_float / node_visits_float;
-    let exploration_term =
-        exploration_constant * (parent_visits_float.ln() / node_visits_float).sqrt();
-    exploitation_term + exploration_term
+    let mean = node_wins / Float::from(node_visits);
+    ucb1(mean, node_visits, parent_visits, exploration_constant)
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -231,7 +1063,9 @@ struct UctSelectResult<_Action: Action> {
 fn uct_select<_State, _Action>(
     tree: &MctsTree<_State, _Action>,
     node_key: MctsNodeKey,
+    policy: &dyn SelectionPolicy,
     exploration_constant: Float,
+    first_play_urgency: Option<Float>,
 ) -> UctSelectResult<_Action>
 where
     _State: State<_Action>,
@@ -246,22 +1080,29 @@ where
             finished: true,
         };
     }
-    let parent_visits = node.visits;
+    // Virtual loss makes paths other threads are playing out look visited and lost, so that
+    // concurrent selections spread out over the tree.
+    let parent_visits = node.visits + node.virtual_loss;
     let all_scores: Vec<(&_Action, &MctsNodeKey, Float)> = children
         .iter()
         .map(|(action, child)| {
-            let child_node = tree.get_node_from_nodekey(*child);
-            let score = uct_score(
-                child_node.visits,
-                child_node.wins,
+            let score = tree.get_node_from_nodekey(*child).selection_score(
+                policy,
                 parent_visits,
                 exploration_constant,
+                first_play_urgency,
             );
             (action, child, score)
         })
         .collect::<Vec<(&_Action, &MctsNodeKey, Float)>>();
+    // Children the solver has proven lost for the player choosing between them are only chosen
+    // if there is nothing else.
+    let is_proven_loss =
+        |child: &MctsNodeKey| tree.get_node_from_nodekey(*child).proven == Some(0.0);
+    let all_proven_losses = all_scores.iter().all(|(_, child, _)| is_proven_loss(child));
     let action_child_max_score: Option<&(&_Action, &MctsNodeKey, Float)> = all_scores
         .iter()
+        .filter(|(_, child, _)| all_proven_losses || !is_proven_loss(child))
         .max_by(|(_, _, score1), (_, _, score2)| score1.partial_cmp(score2).unwrap());
     if let Some((action, child, _)) = action_child_max_score {
         UctSelectResult {
@@ -274,49 +1115,482 @@ where
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// SimulationResult is the outcome of a playout, from the point of view the search maximizes.
+/// Win and NotWin are rewards of 1 and 0, and Reward is anything in between, such as 0.5 for a
+/// draw. RewardAfter is a reward reached `length` moves into the playout, which discounting
+/// needs; run_playouts reports its results this way.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SimulationResult {
     Win,
     NotWin,
+    Reward(Float),
+    RewardAfter { reward: Float, length: Int },
 }
 
-#[derive(Debug, Clone, Copy)]
-pub enum IterationLimitKind {
-    Iterations(Int),
-    TimeSeconds(Duration),
+impl SimulationResult {
+    pub fn reward(&self) -> Float {
+        match self {
+            SimulationResult::Win => 1.0,
+            SimulationResult::NotWin => 0.0,
+            SimulationResult::Reward(reward) => *reward,
+            SimulationResult::RewardAfter { reward, .. } => *reward,
+        }
+    }
+
+    /// The number of moves in the playout, if known.
+    pub fn length(&self) -> Option<Int> {
+        match self {
+            SimulationResult::RewardAfter { length, .. } => Some(*length),
+            _ => None,
+        }
+    }
+
+    /// This result, reached after `length` moves.
+    pub fn after(&self, length: Int) -> Self {
+        SimulationResult::RewardAfter {
+            reward: self.reward(),
+            length,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum DebugTrackTrees {
-    None,
-    Track,
+/// `reward` discounted by `discount` for each of `moves` moves. Rewards are discounted towards
+/// 0.5, a draw, rather than towards 0, so that with alternating perspectives a distant loss is
+/// as uncertain as a distant win.
+fn discounted(reward: Float, discount: Option<Float>, moves: usize) -> Float {
+    match discount {
+        Some(discount) => 0.5 + discount.powi(moves as i32) * (reward - 0.5),
+        None => reward,
+    }
 }
 
-#[derive(Debug, Clone)]
-pub struct MctsArgs {
-    pub iteration_limit: IterationLimitKind,
-    pub exploration_constant: Float,
-    pub playouts_per_simulation: Int,
-    pub max_depth_per_playout: Int,
-    pub rng: Rc<RefCell<Rng>>,
+/// Playout is the result of one playout together with the actions taken in it, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Playout<_Action> {
+    pub result: SimulationResult,
+    pub actions: Vec<_Action>,
+}
 
-    // Directory inside which to put serialized trees.
-    // If None, don't serialize trees.
-    pub tree_dump_dir: Option<PathBuf>,
+impl<_Action> Playout<_Action> {
+    /// The number of moves in the playout: the result's length if it has one, otherwise the
+    /// number of actions recorded, which is 0 if they weren't.
+    pub fn length(&self) -> Int {
+        self.result.length().unwrap_or(self.actions.len() as Int)
+    }
+}
+
+/// Descent is the way down the tree in one iteration: the nodes from the root, the actions
+/// between them, and the state at the last node.
+struct Descent<_State, _Action> {
+    path: Vec<MctsNodeKey>,
+    actions: Vec<_Action>,
+    state: _State,
+
+    /// The evaluator's value of `state`, once expansion has evaluated it.
+    evaluation: Option<Float>,
+}
+
+impl<_State, _Action> Descent<_State, _Action>
+where
+    _State: State<_Action>,
+    _Action: Action,
+{
+    fn push(&mut self, node_key: MctsNodeKey, action: _Action) {
+        self.evaluation = None;
+        self.state = self.state.get_next_state(&action);
+        self.path.push(node_key);
+        self.actions.push(action);
+    }
+
+    /// Run the playouts from the last state, recording their actions only if `amaf` is set. An
+    /// evaluator scores states that aren't terminal instead, unless expansion already did.
+    fn simulate(
+        &self,
+        amaf: bool,
+        playouts: Int,
+        max_depth_per_playout: Int,
+        evaluator: Option<&dyn Evaluator<_State, _Action>>,
+        rng: &mut Rng,
+    ) -> Vec<Playout<_Action>> {
+        if let Some(value) = self.evaluation {
+            return evaluator::value_playout(value);
+        }
+        if let (Some(evaluator), false) = (evaluator, self.state.is_terminal()) {
+            return evaluator::value_playout(evaluator.evaluate(&self.state).1);
+        }
+        if amaf {
+            return self
+                .state
+                .simulate_playouts(playouts, max_depth_per_playout, rng);
+        }
+        self.state
+            .simulate(playouts, max_depth_per_playout, rng)
+            .into_iter()
+            .map(|result| Playout {
+                result,
+                actions: vec![],
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum IterationLimitKind {
+    Iterations(Int),
+    TimeSeconds(Duration),
+    // Stop at whichever of the two limits is reached first.
+    IterationsOrTime(Int, Duration),
+}
+
+impl IterationLimitKind {
+    /// When a search starting now must stop, if the limit has a time budget. Searches limited
+    /// only by iterations never read the clock, which panics on wasm32-unknown-unknown.
+    fn deadline(&self) -> Option<Instant> {
+        match *self {
+            IterationLimitKind::Iterations(_) => None,
+            IterationLimitKind::TimeSeconds(time)
+            | IterationLimitKind::IterationsOrTime(_, time) => Instant::now().checked_add(time),
+        }
+    }
+
+    /// Whether another iteration may start after `iterations` have started, given the
+    /// search's `deadline`.
+    fn allows(&self, iterations: Int, deadline: Option<Instant>) -> bool {
+        let in_time = || deadline.is_none_or(|deadline| Instant::now() < deadline);
+        match *self {
+            IterationLimitKind::Iterations(limit) => iterations < limit,
+            IterationLimitKind::TimeSeconds(_) => in_time(),
+            IterationLimitKind::IterationsOrTime(limit, _) => iterations < limit && in_time(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugTrackTrees {
+    None,
+    Track,
+}
+
+/// Transpositions controls whether different action sequences that reach the same state share a
+/// node, and so its statistics.
+///
+/// Where states can repeat, as with pop moves in Connect Four, sharing can make a node its own
+/// descendant. Selection never follows such a cycle, and serialized trees cut it off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transpositions {
+    Separate,
+    Share,
+}
+
+/// RewardPerspective says whose reward the statistics of each node hold. Simulation results are
+/// always from the perspective of the player searching, the player to move at the root.
+///
+/// Root credits every node with that reward, which suits single-agent search. In a two-player
+/// game it has the search pick the opponent's replies for the searching player's benefit.
+///
+/// Alternating is negamax-style backpropagation for games where players take turns, like Connect
+/// Four. Each node holds the reward of the player who made the move into it: the searching
+/// player's reward at odd depths and one minus it at even depths, including the root. Selection
+/// then has each player pick their own best move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RewardPerspective {
+    Root,
+    Alternating,
+}
+
+impl RewardPerspective {
+    /// The reward credited to a node `depth` moves below the root, given the searching player's
+    /// `reward`.
+    fn reward_at_depth(self, reward: Float, depth: usize) -> Float {
+        match self {
+            RewardPerspective::Alternating if depth.is_multiple_of(2) => 1.0 - reward,
+            _ => reward,
+        }
+    }
+
+    /// The reward credited to a node given the best reward among its children, which is the
+    /// reward of the player choosing between them.
+    fn reward_from_best_child(self, best: Float) -> Float {
+        match self {
+            RewardPerspective::Root => best,
+            RewardPerspective::Alternating => 1.0 - best,
+        }
+    }
+}
+
+/// DirichletNoise is mixed into the priors of the root's children, as in AlphaZero's
+/// self-play, so that the search tries moves the priors would have it overlook. Each prior p
+/// becomes (1 - epsilon) * p + epsilon * eta, where eta is drawn from a symmetric Dirichlet
+/// distribution with concentration alpha. Noise only matters to selection policies that use
+/// priors, such as Puct. It is drawn afresh at the start of every search, so a root kept with
+/// advance_root gets new noise too. A root with a single child has nothing to share the noise
+/// with and is left alone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirichletNoise {
+    // Concentration of the noise. Small values put most of it on a few moves.
+    alpha: Float,
+
+    // Fraction of each prior replaced by noise, between 0 and 1.
+    epsilon: Float,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Error)]
+pub enum DirichletNoiseError {
+    #[error("alpha must be positive and finite, got {0}")]
+    Alpha(Float),
+
+    #[error("epsilon must be between 0 and 1, got {0}")]
+    Epsilon(Float),
+}
+
+impl DirichletNoise {
+    pub fn new(alpha: Float, epsilon: Float) -> Result<Self, DirichletNoiseError> {
+        if !(alpha > 0.0 && alpha.is_finite()) {
+            return Err(DirichletNoiseError::Alpha(alpha));
+        }
+        if !(0.0..=1.0).contains(&epsilon) {
+            return Err(DirichletNoiseError::Epsilon(epsilon));
+        }
+        Ok(Self { alpha, epsilon })
+    }
+
+    pub fn alpha(&self) -> Float {
+        self.alpha
+    }
+
+    pub fn epsilon(&self) -> Float {
+        self.epsilon
+    }
+
+    /// Mix noise drawn from `rng` into `priors`. With fewer than two priors there is nothing to
+    /// choose between, and they are left alone.
+    pub fn mix(&self, priors: &mut [Float], rng: &mut Rng) {
+        if self.epsilon == 0.0 {
+            return;
+        }
+        // Only fails for fewer than two priors, since alpha was checked by new.
+        let Ok(dirichlet) = Dirichlet::new_with_size(self.alpha, priors.len()) else {
+            return;
+        };
+        let sample = dirichlet.sample(rng);
+        for (prior, eta) in priors.iter_mut().zip(sample) {
+            *prior = (1.0 - self.epsilon) * *prior + self.epsilon * eta;
+        }
+    }
+}
+
+/// NodeLimit bounds the number of nodes in the tree, which otherwise grows by a node or more
+/// every iteration and so without bound on long time budgets.
+///
+/// StopExpanding stops adding nodes once the tree is full, and further iterations simulate from
+/// the leaves they reach. Recycle instead makes room by collapsing the least recently visited
+/// subtrees back into leaves whenever the tree is full, down to three quarters of the limit, so
+/// the tree keeps following the search. The limit should leave room for at least the root's
+/// children. With root parallelization the limit applies to each worker's tree, and the merged
+/// tree is recycled back under it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeLimit {
+    Unlimited,
+    StopExpanding(usize),
+    Recycle(usize),
+}
+
+/// Solver turns on MCTS-Solver (Winands, Björnsson and Saito 2008), which proves the exact
+/// reward of nodes near the end of the game instead of estimating it from playouts.
+///
+/// A terminal node is proven when it is first reached, with the reward of its playouts. A node
+/// is proven once the player choosing between its children has a proven win, or once all its
+/// children are proven and they pick the best, so proofs propagate up the tree. Selection stops
+/// at proven nodes and backs up their exact reward, and avoids children proven lost. Proven wins
+/// are always the best action and proven losses the worst. Once the root is proven the search
+/// stops early, and [`Mcts::proven_reward`] has the result.
+///
+/// Rewards of 1 and 0 are the wins and losses. Other proven rewards, such as draws, settle a
+/// node only once all its siblings are proven too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Solver {
+    Disabled,
+    Enabled,
+}
+
+/// BestActionPolicy chooses the action to play once the search is over, from the statistics of
+/// the root's children.
+///
+/// MostVisits is the robust child, the usual choice. HighestWinRate is the max child, which can
+/// favour a child whose few playouts happened to go well. SecureChild maximizes the lower
+/// confidence bound `wins / visits - confidence / sqrt(visits)`, preferring well-explored
+/// children whose win rate is nearly as high.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BestActionPolicy {
+    MostVisits,
+    HighestWinRate,
+    SecureChild { confidence: Float },
+}
+
+impl BestActionPolicy {
+    /// The score of a child with the given statistics; the best action has the highest score.
+    /// Unvisited children score lowest except by visits.
+    fn score(self, visits: Int, wins: Float) -> Float {
+        if visits == 0 && self != BestActionPolicy::MostVisits {
+            return Float::NEG_INFINITY;
+        }
+        let visits = visits as Float;
+        match self {
+            BestActionPolicy::MostVisits => visits,
+            BestActionPolicy::HighestWinRate => wins / visits,
+            BestActionPolicy::SecureChild { confidence } => {
+                wins / visits - confidence / visits.sqrt()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MctsArgs {
+    pub iteration_limit: IterationLimitKind,
+    pub exploration_constant: Float,
+
+    // Scores children during selection. Uct is the formula in the book.
+    pub selection_policy: Arc<dyn SelectionPolicy>,
+
+    // First play urgency: the score of children the selection policy would otherwise score as
+    // infinite because they haven't been visited. None keeps infinity, so every child is tried
+    // once before any is tried twice; a finite value lets well-performing siblings be revisited
+    // first, which saves iterations in wide trees.
+    pub first_play_urgency: Option<Float>,
+    pub playouts_per_simulation: Int,
+    pub max_depth_per_playout: Int,
+    pub rng: SharedRng,
+
+    // Directory inside which to put serialized trees.
+    // If None, don't serialize trees.
+    pub tree_dump_dir: Option<PathBuf>,
 
     // If true, track trees and keep them in memory. This is useful for debugging.
     pub debug_track_trees: DebugTrackTrees,
+
+    // Whether transposed states share a node. See Transpositions.
+    pub transpositions: Transpositions,
+
+    // Whose reward each node's statistics hold. See RewardPerspective.
+    pub reward_perspective: RewardPerspective,
+
+    // How best_action chooses between the root's children. See BestActionPolicy.
+    pub best_action_policy: BestActionPolicy,
+
+    // Whether proven wins and losses propagate up the tree. See Solver.
+    pub solver: Solver,
+
+    // The most nodes the tree may hold, and what happens when it is full. See NodeLimit.
+    pub node_limit: NodeLimit,
+
+    // If set, every call to run appends a line to this JSON lines file from which the search
+    // can be replayed. See DecisionRecord and Mcts::replay.
+    pub decision_log: Option<PathBuf>,
+
+    // Discount factor, between 0 and 1, applied to rewards for every move between a node and
+    // the end of the playout, so that wins found soon count for more than distant, uncertain
+    // ones. None doesn't discount. Playouts need to report their length, as run_playouts does.
+    pub discount: Option<Float>,
+
+    // Noise mixed into the priors at the root. See DirichletNoise.
+    pub root_noise: Option<DirichletNoise>,
+}
+
+impl MctsArgs {
+    /// Lock the rng. A search that panicked while drawing from it leaves it usable, since any
+    /// state of the rng is a valid one.
+    pub(crate) fn lock_rng(&self) -> MutexGuard<'_, Rng> {
+        self.rng.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A search of 1000 iterations with Uct, each simulating 10 playouts of at most 100 moves, with
+/// an rng seeded with 0 and every optional feature turned off. Override the fields a search
+/// needs with struct update syntax.
+impl Default for MctsArgs {
+    fn default() -> Self {
+        Self {
+            iteration_limit: IterationLimitKind::Iterations(1000),
+            exploration_constant: std::f64::consts::SQRT_2,
+            selection_policy: Arc::new(Uct),
+            first_play_urgency: None,
+            playouts_per_simulation: 10,
+            max_depth_per_playout: 100,
+            rng: shared_rng(0),
+            tree_dump_dir: None,
+            debug_track_trees: DebugTrackTrees::None,
+            transpositions: Transpositions::Separate,
+            reward_perspective: RewardPerspective::Root,
+            best_action_policy: BestActionPolicy::MostVisits,
+            solver: Solver::Disabled,
+            node_limit: NodeLimit::Unlimited,
+            decision_log: None,
+            discount: None,
+            root_noise: None,
+        }
+    }
+}
+
+/// ActionStat is what the search found out about one action at the root, for showing how good
+/// every move looks rather than only the best one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionStat<_Action> {
+    pub action: _Action,
+    pub visits: Int,
+    pub wins: Float,
+
+    // Mean reward of the action for the player to move at the root, or None if it was never
+    // visited.
+    pub win_rate: Option<Float>,
+
+    // The score the selection policy gives the action, which is the UCT value for Uct:
+    // what the search would try next, rather than how good the action is.
+    pub uct: Float,
+}
+
+/// SearchInfo describes the tree and the most recent search, for displaying diagnostics.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchInfo<_Action> {
+    pub iterations: Int,
+    pub nodes: usize,
+
+    // The largest number of actions from the root to a node.
+    pub max_depth: usize,
+
+    // The line of play the search expects, following the most visited child from the root.
+    pub principal_variation: Vec<_Action>,
+
+    // The searching player's exact reward if the solver has proven the root.
+    pub proven_reward: Option<Float>,
+
+    // None if nothing has been searched yet, or on wasm32-unknown-unknown where there is no
+    // clock; time the search there with the JS clock instead.
+    pub elapsed_seconds: Option<Float>,
+    pub iterations_per_second: Option<Float>,
 }
 
 /// Mcts is the main Monte Carlo Tree Search algorithm.
 /// See section 5.4 Monte Carlo Tree Search page 162 and 163.
 pub struct Mcts<_State: State<_Action>, _Action: Action> {
-    tree: Rc<RefCell<MctsTree<_State, _Action>>>,
+    tree: MctsTree<_State, _Action>,
     args: MctsArgs,
 
     /// If debug_track_trees is true, this is the list of trees that we have tracked. Each
     /// element is a tree at a different iteration.
     debug_trees: Option<Vec<MctsNodeForSerialization<_State, _Action>>>,
+
+    /// Number of iterations performed by the most recent call to run.
+    iterations_run: Int,
+
+    /// How long the most recent call to run took, where the clock can be read.
+    elapsed: Option<Duration>,
+
+    /// Why the most recent call to run couldn't append to args.decision_log, if it couldn't.
+    decision_log_error: Option<DecisionLogError>,
+
+    /// Scores states in place of playouts if set. See with_evaluator.
+    evaluator: Option<Arc<dyn Evaluator<_State, _Action>>>,
 }
 
 impl<_State, _Action> Mcts<_State, _Action>
@@ -325,7 +1599,23 @@ where
     _Action: Action,
 {
     pub fn new(root_state: _State, args: MctsArgs) -> Self {
-        Mcts::new_from_tree(MctsTree::new(root_state), args)
+        let tree = MctsTree::new(root_state).with_transpositions(args.transpositions);
+        Mcts::new_from_tree(tree, args)
+    }
+
+    /// Like new, but with the search drawing from `rng`, the `Rc<RefCell<Rng>>` that
+    /// MctsArgs::rng was before it became a SharedRng, in place of `args.rng`. The search
+    /// starts from a copy of `rng` as it is now and doesn't advance it, so searches made this
+    /// way from the same `rng` no longer share one stream; give them one SharedRng for that.
+    pub fn new_with_rng(root_state: _State, args: MctsArgs, rng: Rc<RefCell<Rng>>) -> Self {
+        let rng = rng.borrow().clone();
+        Mcts::new(
+            root_state,
+            MctsArgs {
+                rng: Arc::new(Mutex::new(rng)),
+                ..args
+            },
+        )
     }
 
     // useful for tests
@@ -335,164 +1625,458 @@ where
             DebugTrackTrees::Track => Some(vec![]),
         };
         Self {
-            tree: Rc::new(RefCell::new(tree)),
+            tree,
             args,
             debug_trees,
+            iterations_run: 0,
+            elapsed: None,
+            decision_log_error: None,
+            evaluator: None,
         }
     }
 
     fn serialize_tree(&self) -> MctsNodeForSerialization<_State, _Action> {
-        let tree = Rc::clone(&self.tree);
-        let tree = tree.borrow();
-        let tree = tree.deref();
-        create_tree_for_serialization(tree, tree.get_root_nodekey(), None /*action*/)
+        create_tree_for_serialization(
+            &self.tree,
+            self.tree.get_root_nodekey(),
+            None, /*action*/
+        )
+    }
+
+    /// Write the tree to `writer` as the JSON of the MctsNodeForSerialization that
+    /// serialize_tree would build, but one node at a time, leaving out the nodes `filter`
+    /// excludes. Unlike serialize_tree this works for trees with millions of nodes.
+    pub fn write_tree(&self, mut writer: impl Write, filter: TreeFilter) -> io::Result<()> {
+        self.tree.write_json(&mut writer, filter)?;
+        writer.flush()
     }
 
-    fn maybe_dump_tree(&mut self, iteration: Int) {
+    fn maybe_dump_tree(&mut self, _iteration: Int) {
         if self.args.debug_track_trees == DebugTrackTrees::Track {
             let tree = self.serialize_tree();
             self.debug_trees.as_mut().unwrap().push(tree);
         }
     }
 
+    /// Search until the iteration limit. If `args.decision_log` is set and the record of the
+    /// search can't be written to it, the search result is kept and the error is available
+    /// from [`Mcts::decision_log_error`]; [`Mcts::run_with_callback`] returns it instead.
     pub fn run(&mut self) {
-        match self.args.iteration_limit {
-            IterationLimitKind::Iterations(iterations) => {
-                for i in 0..iterations {
-                    self.iteration(i);
-                }
+        self.decision_log_error = self
+            .run_with_callback(Int::MAX, |_| ControlFlow::Continue(()))
+            .err();
+    }
+
+    /// Search like [`Mcts::run`], but also call `callback` after every `every` iterations. The
+    /// search stops early if it returns `ControlFlow::Break`, for example once one move at the
+    /// root has so many more visits than the others that it can't be overtaken. By the time
+    /// the callback runs, [`Mcts::iterations_run`] counts the iterations so far.
+    ///
+    /// The only error is failing to append to `args.decision_log`, which happens after the
+    /// search has finished.
+    pub fn run_with_callback<F>(&mut self, every: Int, callback: F) -> Result<(), DecisionLogError>
+    where
+        F: FnMut(&Self) -> ControlFlow<()>,
+    {
+        match self.args.decision_log.clone() {
+            Some(path) => self.run_logged(&path, every, callback),
+            None => {
+                self.search(every, callback);
+                Ok(())
             }
-            IterationLimitKind::TimeSeconds(time) => {
-                let start = Instant::now();
-                let mut i = 0;
-                while start.elapsed() < time {
-                    self.iteration(i);
-                    i += 1;
+        }
+    }
+
+    /// Search with `args.rng` temporarily replaced by one seeded with `seed`, which is put back
+    /// afterwards.
+    fn with_seed<T>(&mut self, seed: u64, search: impl FnOnce(&mut Self) -> T) -> T {
+        let saved = std::mem::replace(&mut *self.args.lock_rng(), Rng::seed_from_u64(seed));
+        let result = search(self);
+        *self.args.lock_rng() = saved;
+        result
+    }
+
+    fn search<F>(&mut self, every: Int, mut callback: F)
+    where
+        F: FnMut(&Self) -> ControlFlow<()>,
+    {
+        assert!(every > 0, "callback interval must be positive");
+        let start = now();
+        self.refresh_root_noise();
+        let deadline = self.args.iteration_limit.deadline();
+        let mut i = 0;
+        while self.args.iteration_limit.allows(i, deadline) && self.proven_reward().is_none() {
+            self.iteration(i);
+            i += 1;
+            if i % every == 0 {
+                self.iterations_run = i;
+                self.elapsed = start.map(|start| start.elapsed());
+                if callback(self).is_break() {
+                    break;
                 }
             }
         }
+        self.iterations_run = i;
+        self.elapsed = start.map(|start| start.elapsed());
+    }
+
+    /// Search with root parallelization: each of `num_threads` workers builds its own tree from
+    /// the root state with the full iteration limit, and the trees are then merged into this
+    /// one, adding up the statistics of nodes reached by the same actions. `num_threads` of 0
+    /// uses one worker per CPU.
+    ///
+    /// Each worker's rng is seeded from `args.rng`, so results don't depend on thread
+    /// scheduling. Afterwards [`Mcts::iterations_run`] is the total over all workers, and if
+    /// trees are tracked only the merged tree is recorded.
+    pub fn run_parallel(&mut self, num_threads: usize)
+    where
+        _State: Send,
+        _Action: Send,
+    {
+        let start = now();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .expect("failed to start worker threads");
+        let workers: Vec<(u64, _State)> = (0..pool.current_num_threads())
+            .map(|_| {
+                let seed = rand::RngCore::next_u64(&mut *self.args.lock_rng());
+                (seed, self.tree.root_state.clone())
+            })
+            .collect();
+        let (args, evaluator) = (&self.args, &self.evaluator);
+        let results: Vec<(MctsTree<_State, _Action>, Int)> = pool.install(|| {
+            workers
+                .into_par_iter()
+                .map(|(seed, root_state)| {
+                    // Each worker has an rng of its own rather than sharing the lock on
+                    // args.rng.
+                    let mut worker = Mcts::new(
+                        root_state,
+                        MctsArgs {
+                            rng: shared_rng(seed),
+                            tree_dump_dir: None,
+                            debug_track_trees: DebugTrackTrees::None,
+                            decision_log: None,
+                            ..args.clone()
+                        },
+                    );
+                    worker.evaluator = evaluator.clone();
+                    worker.run();
+                    (worker.tree, worker.iterations_run)
+                })
+                .collect()
+        });
+
+        self.iterations_run = 0;
+        for (tree, iterations) in &results {
+            self.tree.merge(tree);
+            self.iterations_run += iterations;
+        }
+        if let NodeLimit::Recycle(max_nodes) = self.args.node_limit {
+            self.tree.recycle(max_nodes, &[]);
+        }
+        self.elapsed = start.map(|start| start.elapsed());
+        self.maybe_dump_tree(self.iterations_run);
+    }
+
+    /// Search with tree parallelization: `num_threads` workers descend and grow this one tree
+    /// concurrently, sharing the iteration limit between them. `num_threads` of 0 uses one worker
+    /// per CPU.
+    ///
+    /// While a worker plays out from a node, the node and its ancestors carry `virtual_loss`
+    /// extra visits that count as lost, which steers the other workers away from the same path.
+    /// The virtual loss is replaced by the real results when they are backpropagated.
+    ///
+    /// The tree is locked while a worker selects, expands and backpropagates, but not during its
+    /// playouts, which is where nearly all of the time goes. Each worker's rng is seeded from
+    /// `args.rng`, but unlike [`Mcts::run_parallel`] the resulting tree depends on the order in
+    /// which workers take the lock.
+    pub fn run_tree_parallel(&mut self, num_threads: usize, virtual_loss: Int)
+    where
+        _State: Send,
+        _Action: Send,
+    {
+        let start = now();
+        self.refresh_root_noise();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .expect("failed to start worker threads");
+        let seeds: Vec<u64> = (0..pool.current_num_threads())
+            .map(|_| rand::RngCore::next_u64(&mut *self.args.lock_rng()))
+            .collect();
+        let (iteration_limit, exploration_constant, playouts, max_depth) = (
+            self.args.iteration_limit,
+            self.args.exploration_constant,
+            self.args.playouts_per_simulation,
+            self.args.max_depth_per_playout,
+        );
+        let selection_policy = Arc::clone(&self.args.selection_policy);
+        let amaf = selection_policy.uses_amaf();
+        let (first_play_urgency, reward_perspective, solver, node_limit, discount, root_noise) = (
+            self.args.first_play_urgency,
+            self.args.reward_perspective,
+            self.args.solver,
+            self.args.node_limit,
+            self.args.discount,
+            self.args.root_noise,
+        );
+
+        let evaluator = self.evaluator.as_deref();
+        let tree = Mutex::new(&mut self.tree);
+        let started = AtomicI32::new(0);
+        let finished = AtomicI32::new(0);
+        let deadline = iteration_limit.deadline();
+        let claim_iteration =
+            || iteration_limit.allows(started.fetch_add(1, Ordering::Relaxed), deadline);
+
+        pool.broadcast(|context| {
+            let mut rng = Rng::seed_from_u64(seeds[context.index()]);
+            while claim_iteration() {
+                let (descent, proven) = {
+                    let mut tree = tree.lock().unwrap();
+                    let mut descent = tree.select(
+                        selection_policy.as_ref(),
+                        exploration_constant,
+                        first_play_urgency,
+                    );
+                    tree.expand(&mut descent, &mut rng, node_limit, root_noise, evaluator);
+                    tree.begin_playout(&descent.path, virtual_loss);
+                    let proven = tree.proven_playouts(&descent, playouts, reward_perspective);
+                    (descent, proven)
+                };
+
+                let result = proven.unwrap_or_else(|| {
+                    descent.simulate(amaf, playouts, max_depth, evaluator, &mut rng)
+                });
+
+                let mut tree = tree.lock().unwrap();
+                tree.end_playout(&descent.path, virtual_loss);
+                tree.back_propagate(&descent, &result, amaf, reward_perspective, discount);
+                if solver == Solver::Enabled {
+                    tree.prove(&descent, &result, reward_perspective);
+                }
+                finished.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+
+        self.iterations_run = finished.into_inner();
+        self.elapsed = start.map(|start| start.elapsed());
+        self.maybe_dump_tree(self.iterations_run);
     }
 
-    fn iteration(&mut self, iteration: Int) {
-        let (node_key, state) = self.select();
+    /// Draw new root noise, if there is any, for a search starting from an expanded root. An
+    /// unexpanded root gets its noise when it is expanded.
+    fn refresh_root_noise(&mut self) {
+        if let Some(noise) = self.args.root_noise {
+            self.tree.add_root_noise(noise, &mut self.args.lock_rng());
+        }
+    }
 
-        let (node_key, state) = self.expand(node_key, state);
+    /// One select, expand, simulate and back up cycle, described by the returned trace.
+    fn iteration(&mut self, iteration: Int) -> IterationTrace<_Action> {
+        let mut descent = self.tree.select(
+            self.args.selection_policy.as_ref(),
+            self.args.exploration_constant,
+            self.args.first_play_urgency,
+        );
+        let selected = descent.actions.len();
+
+        self.tree.expand(
+            &mut descent,
+            &mut self.args.lock_rng(),
+            self.args.node_limit,
+            self.args.root_noise,
+            self.evaluator.as_deref(),
+        );
 
-        let result = {
-            let rng = Rc::clone(&self.args.rng);
-            let mut rng = rng.borrow_mut();
-            state.simulate(
+        let amaf = self.args.selection_policy.uses_amaf();
+        let proven = self.tree.proven_playouts(
+            &descent,
+            self.args.playouts_per_simulation,
+            self.args.reward_perspective,
+        );
+        let from_proof = proven.is_some();
+        let result = proven.unwrap_or_else(|| {
+            let mut rng = self.args.lock_rng();
+            descent.simulate(
+                amaf,
                 self.args.playouts_per_simulation,
                 self.args.max_depth_per_playout,
+                self.evaluator.as_deref(),
                 &mut rng,
             )
-        };
-
-        self.back_propagate(node_key, result);
+        });
+
+        self.tree.back_propagate(
+            &descent,
+            &result,
+            amaf,
+            self.args.reward_perspective,
+            self.args.discount,
+        );
+        if self.args.solver == Solver::Enabled {
+            self.tree
+                .prove(&descent, &result, self.args.reward_perspective);
+        }
 
         self.maybe_dump_tree(iteration);
-    }
 
-    fn select(&self) -> (MctsNodeKey, _State) {
-        let tree = Rc::clone(&self.tree);
-        let tree = tree.borrow();
-        let mut state = tree.root_state.clone();
-        let mut node_key = tree.get_root_nodekey();
-        loop {
-            let uct_select_result = uct_select(&tree, node_key, self.args.exploration_constant);
-            if uct_select_result.finished {
-                return (uct_select_result.node, state);
-            } else {
-                state = state.get_next_state(&uct_select_result.action.unwrap());
-                node_key = uct_select_result.node;
-            }
+        let expanded = descent.actions.get(selected).copied();
+        descent.actions.truncate(selected);
+        IterationTrace {
+            iteration,
+            path: descent.actions,
+            expanded,
+            proven: from_proof,
+            playouts: result,
         }
     }
 
-    fn expand(&mut self, node_key: MctsNodeKey, state: _State) -> (MctsNodeKey, _State) {
-        // If the node is terminal, we don't need to expand it because the game is over.
-        // However, we still return it because we want to backpropagate the result.
-        if state.is_terminal() {
-            return (node_key, state);
+    /// Move the root to the state reached by `action`, for example after the best action has
+    /// been played and again after the opponent's reply. The statistics below it are kept, so
+    /// the next call to run builds on them, and nodes that can no longer be reached are dropped.
+    /// If the search never reached that state the tree starts afresh from it. Tracked trees
+    /// belong to the old root, so they are cleared.
+    pub fn advance_root(&mut self, action: _Action) {
+        let root_state = self.tree.root_state.get_next_state(&action);
+        let existing = self
+            .tree
+            .get_children_nodekeys(self.tree.root)
+            .get(&action)
+            .or_else(|| {
+                self.tree
+                    .transpositions
+                    .as_ref()
+                    .and_then(|table| table.get(&root_state))
+            })
+            .copied();
+        match existing {
+            Some(new_root) => self.tree.reroot(new_root, root_state),
+            None => {
+                self.tree = MctsTree::new(root_state).with_transpositions(self.args.transpositions)
+            }
         }
-
-        // The node is not terminal. If the node has no visits, we don't need to expand it and
-        // instead return it because we want to simulate it. If we don't we will skip it!
-        if node_key != self.tree.borrow().root
-            && self.tree.borrow().get_node_from_nodekey(node_key).visits == 0
-        {
-            return (node_key, state);
+        if let Some(debug_trees) = self.debug_trees.as_mut() {
+            debug_trees.clear();
         }
+    }
 
-        let actions = state.get_actions();
+    /// The state at the root of the tree, which the next call to run searches from.
+    pub fn root_state(&self) -> &_State {
+        &self.tree.root_state
+    }
 
-        {
-            let tree = Rc::clone(&self.tree);
-            let mut tree = tree.borrow_mut();
-            for action in &actions {
-                tree.add_child(node_key, *action);
-            }
-        }
-
-        // Choose a random child
-        let (random_child, action) = {
-            let tree = Rc::clone(&self.tree);
-            let tree = tree.borrow();
-            let random_action = actions
-                .choose(&mut self.args.rng.borrow_mut().deref_mut())
-                .unwrap();
-            (
-                *tree
-                    .get_children_nodekeys(node_key)
-                    .get(random_action)
-                    .unwrap(),
-                random_action,
-            )
-        };
+    /// The action to play from the root, chosen by `args.best_action_policy`.
+    pub fn best_action(&self) -> Option<_Action> {
+        self.tree
+            .best_child(self.tree.get_root_nodekey(), self.args.best_action_policy)
+            .map(|(action, _)| action)
+    }
 
-        (random_child, state.get_next_state(action))
+    /// The searching player's exact reward from the root if the solver has proven it, such as 1
+    /// for a forced win and 0 for a forced loss. See Solver.
+    pub fn proven_reward(&self) -> Option<Float> {
+        let proven = self.tree.get_root().proven?;
+        Some(self.args.reward_perspective.reward_at_depth(proven, 0))
     }
 
-    fn back_propagate(&mut self, node_key: MctsNodeKey, results: Vec<SimulationResult>) {
-        let mut node_key = node_key;
-        let tree = Rc::clone(&self.tree);
-        let mut tree = tree.borrow_mut();
-        loop {
-            let mut node = tree.get_mut_node_from_nodekey(node_key);
-            for result in &results {
-                node.visits += 1;
-                match result {
-                    SimulationResult::Win => node.wins += 1,
-                    SimulationResult::NotWin => {}
-                }
-            }
-            match node.parent {
-                None => break,
-                Some(parent_node_key) => {
-                    node_key = parent_node_key;
-                }
-            }
+    /// Describe the tree and the most recent call to run, for example to show how deep the
+    /// search got and which line of play it expects.
+    pub fn search_info(&self) -> SearchInfo<_Action> {
+        let elapsed_seconds = self.elapsed.map(|elapsed| elapsed.as_secs_f64());
+        SearchInfo {
+            iterations: self.iterations_run,
+            nodes: self.node_count(),
+            max_depth: self.tree.max_depth(),
+            principal_variation: self.tree.principal_variation(),
+            proven_reward: self.proven_reward(),
+            elapsed_seconds,
+            iterations_per_second: elapsed_seconds
+                .filter(|seconds| *seconds > 0.0)
+                .map(|seconds| self.iterations_run as Float / seconds),
         }
     }
 
-    pub fn best_action(&self) -> Option<_Action> {
-        let tree = self.tree.borrow();
-        let root_nodekey = tree.get_root_nodekey();
-        let mut best_action = None;
-        let mut best_score = Int::MIN;
-        for (action, child) in tree.get_children_nodekeys(root_nodekey) {
-            let child_node = tree.get_node_from_nodekey(*child);
-            if child_node.visits > best_score {
-                best_score = child_node.visits;
-                best_action = Some(*action);
-            }
-        }
-        best_action
+    /// Statistics of each child of the root, most visited first.
+    pub fn root_action_stats(&self) -> Vec<ActionStat<_Action>> {
+        let tree = &self.tree;
+        let root = tree.get_root();
+        let mut stats: Vec<ActionStat<_Action>> = tree
+            .get_children_nodekeys(tree.root)
+            .iter()
+            .map(|(action, child)| {
+                let child = tree.get_node_from_nodekey(*child);
+                ActionStat {
+                    action: *action,
+                    visits: child.visits,
+                    wins: child.wins,
+                    win_rate: (child.visits > 0).then(|| child.wins / Float::from(child.visits)),
+                    uct: child.selection_score(
+                        self.args.selection_policy.as_ref(),
+                        root.visits + root.virtual_loss,
+                        self.args.exploration_constant,
+                        self.args.first_play_urgency,
+                    ),
+                }
+            })
+            .collect();
+        stats.sort_by(|a, b| b.visits.cmp(&a.visits).then(b.wins.total_cmp(&a.wins)));
+        stats
+    }
+
+    /// Visits of each child of the root, by action.
+    pub fn root_action_visits(&self) -> Vec<(_Action, Int)> {
+        let tree = &self.tree;
+        tree.get_children_nodekeys(tree.get_root_nodekey())
+            .iter()
+            .map(|(action, child)| (*action, tree.get_node_from_nodekey(*child).visits))
+            .collect()
     }
 
     pub fn debug_trees(&self) -> Option<Vec<MctsNodeForSerialization<_State, _Action>>> {
         self.debug_trees.as_ref().cloned()
     }
+
+    /// Why the most recent call to run couldn't append to `args.decision_log`, if it couldn't.
+    pub fn decision_log_error(&self) -> Option<&DecisionLogError> {
+        self.decision_log_error.as_ref()
+    }
+
+    /// Number of iterations performed by the most recent call to run.
+    pub fn iterations_run(&self) -> Int {
+        self.iterations_run
+    }
+
+    /// Number of nodes in the tree, including the root.
+    pub fn node_count(&self) -> usize {
+        self.tree.nodes.len()
+    }
+
+    /// Approximate number of heap bytes used by the tree. This counts the node slots and each
+    /// node's children map, but not allocator overhead or anything owned by the root state, so it
+    /// is a lower bound that is good enough for choosing search budgets.
+    pub fn approximate_tree_bytes(&self) -> usize {
+        let tree = &self.tree;
+        // slotmap stores a u32 version next to each value.
+        let slot_bytes = std::mem::size_of::<MctsNode<_State, _Action>>() + 4;
+        // hashbrown stores one control byte per bucket next to each (key, value) pair.
+        let child_entry_bytes =
+            std::mem::size_of::<_Action>() + std::mem::size_of::<MctsNodeKey>() + 1;
+        let children_bytes: usize = tree
+            .nodes
+            .values()
+            .map(|node| node.children.capacity() * child_entry_bytes)
+            .sum();
+        // The transposition table holds a clone of each node's state, not counting anything the
+        // state itself points to.
+        let transposition_bytes = tree.transpositions.as_ref().map_or(0, |table| {
+            table.capacity()
+                * (std::mem::size_of::<_State>() + std::mem::size_of::<MctsNodeKey>() + 1)
+        });
+        tree.nodes.capacity() * slot_bytes + children_bytes + transposition_bytes
+    }
 }
 
 #[cfg(test)]
@@ -500,9 +2084,9 @@ mod tests {
     use std::fmt::Formatter;
 
     use approx::assert_abs_diff_eq;
-    use rand::SeedableRng;
 
     use super::*;
+    use crate::arena::tests::{args, Nim, Take};
 
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
     enum MyAction {
@@ -600,17 +2184,15 @@ mod tests {
     type MyMctsTree = MctsTree<MyState, MyAction>;
 
     #[allow(dead_code)]
-    fn new_my_mcts(rng: Rc<RefCell<Rng>>) -> MyMcts {
+    fn new_my_mcts(rng: SharedRng) -> MyMcts {
         Mcts::new(
             MyState { data: 0 },
             MctsArgs {
-                iteration_limit: IterationLimitKind::Iterations(1000),
                 exploration_constant: 1.0,
                 playouts_per_simulation: 100,
                 max_depth_per_playout: 10,
                 rng,
-                tree_dump_dir: None,
-                debug_track_trees: DebugTrackTrees::None,
+                ..MctsArgs::default()
             },
         )
     }
@@ -632,68 +2214,68 @@ mod tests {
         let root_state = MyState { data: 0 };
         let mut tree = MyMctsTree::new(root_state);
         let root_node = tree.get_mut_root();
-        root_node.wins = 37;
+        root_node.wins = 37.0;
         root_node.visits = 100;
 
         let first_child_nodekey = tree.add_child(tree.get_root_nodekey(), MyAction::Up);
         let first_child = tree.get_mut_node_from_nodekey(first_child_nodekey);
-        first_child.wins = 60;
+        first_child.wins = 60.0;
         first_child.visits = 79;
 
         let first_grandchild_nodekey = tree.add_child(first_child_nodekey, MyAction::Up);
         let first_grandchild = tree.get_mut_node_from_nodekey(first_grandchild_nodekey);
-        first_grandchild.wins = 3;
+        first_grandchild.wins = 3.0;
         first_grandchild.visits = 26;
 
         let second_grandchild_nodekey = tree.add_child(first_child_nodekey, MyAction::Right);
         let second_grandchild = tree.get_mut_node_from_nodekey(second_grandchild_nodekey);
-        second_grandchild.wins = 16;
+        second_grandchild.wins = 16.0;
         second_grandchild.visits = 53;
 
         let first_great_grandchild_nodekey =
             tree.add_child(second_grandchild_nodekey, MyAction::Up);
         let first_great_grandchild = tree.get_mut_node_from_nodekey(first_great_grandchild_nodekey);
-        first_great_grandchild.wins = 27;
+        first_great_grandchild.wins = 27.0;
         first_great_grandchild.visits = 35;
 
         let second_great_grandchild_nodekey =
             tree.add_child(second_grandchild_nodekey, MyAction::Right);
         let second_great_grandchild =
             tree.get_mut_node_from_nodekey(second_great_grandchild_nodekey);
-        second_great_grandchild.wins = 10;
+        second_great_grandchild.wins = 10.0;
         second_great_grandchild.visits = 18;
 
         let second_child_nodekey = tree.add_child(tree.get_root_nodekey(), MyAction::Right);
         let second_child = tree.get_mut_node_from_nodekey(second_child_nodekey);
-        second_child.wins = 1;
+        second_child.wins = 1.0;
         second_child.visits = 10;
 
         let first_grandchild_nodekey = tree.add_child(second_child_nodekey, MyAction::Up);
         let first_grandchild = tree.get_mut_node_from_nodekey(first_grandchild_nodekey);
-        first_grandchild.wins = 6;
+        first_grandchild.wins = 6.0;
         first_grandchild.visits = 6;
 
         let first_great_grandchild_nodekey =
             tree.add_child(first_grandchild_nodekey, MyAction::Right);
         let first_great_grandchild = tree.get_mut_node_from_nodekey(first_great_grandchild_nodekey);
-        first_great_grandchild.wins = 0;
+        first_great_grandchild.wins = 0.0;
         first_great_grandchild.visits = 3;
 
         let second_great_grandchild_nodekey =
             tree.add_child(first_grandchild_nodekey, MyAction::Right);
         let second_great_grandchild =
             tree.get_mut_node_from_nodekey(second_great_grandchild_nodekey);
-        second_great_grandchild.wins = 0;
+        second_great_grandchild.wins = 0.0;
         second_great_grandchild.visits = 3;
 
         let second_grandchild_nodekey = tree.add_child(second_child_nodekey, MyAction::Right);
         let second_grandchild = tree.get_mut_node_from_nodekey(second_grandchild_nodekey);
-        second_grandchild.wins = 3;
+        second_grandchild.wins = 3.0;
         second_grandchild.visits = 4;
 
         let third_child_nodekey = tree.add_child(tree.get_root_nodekey(), MyAction::Down);
         let third_child = tree.get_mut_node_from_nodekey(third_child_nodekey);
-        third_child.wins = 2;
+        third_child.wins = 2.0;
         third_child.visits = 11;
 
         tree
@@ -705,38 +2287,44 @@ mod tests {
         let tree = MyMctsTree::new(root_state);
         let root_node = tree.get_root();
         assert_eq!(root_node.visits, 0);
-        assert_eq!(root_node.wins, 0);
+        assert_eq!(root_node.wins, 0.0);
         assert!(root_node.children.is_empty());
     }
 
     #[test]
     fn test_uct_score_first_child() {
-        let score = uct_score(79, 60, 100, 1.4);
+        let score = uct_score(79, 60.0, 100, 1.4);
         assert_abs_diff_eq!(score, 1.098, epsilon = 0.001);
     }
 
     #[test]
     fn test_uct_score_second_child() {
-        let score = uct_score(10, 1, 100, 1.4);
+        let score = uct_score(10, 1.0, 100, 1.4);
         assert_abs_diff_eq!(score, 1.050, epsilon = 0.001);
     }
 
     #[test]
     fn test_uct_score_third_child() {
-        let score = uct_score(11, 2, 100, 1.4);
+        let score = uct_score(11, 2.0, 100, 1.4);
         assert_abs_diff_eq!(score, 1.088, epsilon = 0.001);
     }
 
+    #[test]
+    fn test_ucb1_untried_arm_is_infinite() {
+        assert_eq!(ucb1(0.0, 0, 100, 1.4), Float::INFINITY);
+        assert_abs_diff_eq!(ucb1(0.5, 100, 100, 0.0), 0.5);
+    }
+
     // Test a small pre-built tree from chapter 5 page 162.
     //
     // As per p163, if C = 1.4, then the first child is selected, which is 60/79.
     #[test]
     fn test_mcts_tree_small_tree_c_14_first_child_selected() {
         let tree = build_test_tree();
-        let uct_select_result = uct_select(&tree, tree.get_root_nodekey(), 1.4);
+        let uct_select_result = uct_select(&tree, tree.get_root_nodekey(), &Uct, 1.4, None);
         let selected_child = tree.get_node_from_nodekey(uct_select_result.node);
         assert_eq!(selected_child.visits, 79);
-        assert_eq!(selected_child.wins, 60);
+        assert_eq!(selected_child.wins, 60.0);
     }
 
     // Test a small pre-built tree from chapter 5 page 162, just first level.
@@ -745,33 +2333,73 @@ mod tests {
     #[test]
     fn test_mcts_tree_small_tree_c_15_third_child_selected() {
         let tree = build_test_tree();
-        let uct_select_result = uct_select(&tree, tree.get_root_nodekey(), 1.5);
+        let uct_select_result = uct_select(&tree, tree.get_root_nodekey(), &Uct, 1.5, None);
         let selected_child = tree.get_node_from_nodekey(uct_select_result.node);
         assert_eq!(selected_child.visits, 11);
-        assert_eq!(selected_child.wins, 2);
+        assert_eq!(selected_child.wins, 2.0);
+    }
+
+    // Virtual loss on the path through the first child makes the third child the best choice at
+    // C = 1.4, and removing it again restores the original choice.
+    #[test]
+    fn test_virtual_loss_steers_selection() {
+        let mut tree = build_test_tree();
+        let root = tree.get_root_nodekey();
+        let first_child = tree.get_children_nodekeys(root)[&MyAction::Up];
+        let first_grandchild = tree.get_children_nodekeys(first_child)[&MyAction::Up];
+
+        tree.add_virtual_loss(&[root, first_child, first_grandchild], 20);
+        assert_eq!(tree.get_root().virtual_loss, 20);
+        let uct_select_result = uct_select(&tree, root, &Uct, 1.4, None);
+        assert_eq!(uct_select_result.action, Some(MyAction::Down));
+
+        tree.add_virtual_loss(&[root, first_child, first_grandchild], -20);
+        assert_eq!(tree.get_root().virtual_loss, 0);
+        let uct_select_result = uct_select(&tree, root, &Uct, 1.4, None);
+        assert_eq!(uct_select_result.action, Some(MyAction::Up));
+    }
+
+    // An unvisited child is only preferred over a visited sibling whose UCT score,
+    // 0.9 + 1.4 * sqrt(ln 10 / 10) = 1.57, is below the first play urgency.
+    #[test]
+    fn test_first_play_urgency() {
+        let mut tree = MyMctsTree::new(MyState { data: 0 });
+        let root = tree.get_root_nodekey();
+        let up = tree.add_child(root, MyAction::Up);
+        tree.add_child(root, MyAction::Down);
+        tree.get_mut_node_from_nodekey(root).visits = 10;
+        let up = tree.get_mut_node_from_nodekey(up);
+        up.visits = 10;
+        up.wins = 9.0;
+
+        let selected = |first_play_urgency| {
+            uct_select(&tree, root, &Uct, 1.4, first_play_urgency)
+                .action
+                .unwrap()
+        };
+        assert_eq!(selected(None), MyAction::Down);
+        assert_eq!(selected(Some(0.5)), MyAction::Up);
+        assert_eq!(selected(Some(2.0)), MyAction::Down);
     }
 
     #[test]
     fn test_mcts_iterations() {
-        let rng = Rc::new(RefCell::new(rand_pcg::Pcg64::seed_from_u64(42)));
+        let rng = shared_rng(42);
         let playouts_per_simulation = 10;
         let max_depth_per_playout = 10;
         let mut mcts = MyMcts::new(
             MyState { data: 0 },
             MctsArgs {
                 iteration_limit: IterationLimitKind::Iterations(10),
-                exploration_constant: std::f64::consts::SQRT_2,
                 playouts_per_simulation,
                 max_depth_per_playout,
                 rng,
-                tree_dump_dir: None,
-                debug_track_trees: DebugTrackTrees::None,
+                ..MctsArgs::default()
             },
         );
         mcts.run();
 
-        let tree = Rc::clone(&mcts.tree);
-        let tree = tree.borrow();
+        let tree = &mcts.tree;
         println!("MCTS tree: {}", tree);
 
         // Verify that the root node has been visited the expected number of times.
@@ -791,4 +2419,1155 @@ mod tests {
         let serialized_tree = serde_json::to_string_pretty(&serialized_tree).unwrap();
         println!("serialized tree: {}", serialized_tree);
     }
+
+    #[test]
+    fn test_mcts_search_stats() {
+        let rng = shared_rng(42);
+        let mut mcts = MyMcts::new(
+            MyState { data: 0 },
+            MctsArgs {
+                iteration_limit: IterationLimitKind::Iterations(10),
+                max_depth_per_playout: 10,
+                rng,
+                ..MctsArgs::default()
+            },
+        );
+        assert_eq!(mcts.iterations_run(), 0);
+        assert_eq!(mcts.node_count(), 1);
+
+        mcts.run();
+
+        assert_eq!(mcts.iterations_run(), 10);
+        assert!(mcts.node_count() > 1);
+        assert!(
+            mcts.approximate_tree_bytes()
+                >= mcts.node_count() * std::mem::size_of::<MctsNode<MyState, MyAction>>()
+        );
+    }
+
+    // Merging a tree into a copy of itself doubles every node's statistics without adding
+    // nodes.
+    #[test]
+    fn test_merge_same_tree() {
+        let mut tree = build_test_tree();
+        tree.merge(&build_test_tree());
+        assert_eq!(tree.nodes.len(), build_test_tree().nodes.len());
+        let root = tree.get_root();
+        assert_eq!((root.visits, root.wins), (200, 74.0));
+        let up = tree.get_children_nodekeys(tree.get_root_nodekey())[&MyAction::Up];
+        let up = tree.get_node_from_nodekey(up);
+        assert_eq!((up.visits, up.wins), (158, 120.0));
+    }
+
+    // Nodes that only the other tree has are added under the same actions.
+    #[test]
+    fn test_merge_adds_missing_nodes() {
+        let mut tree = MyMctsTree::new(MyState { data: 0 });
+        tree.get_mut_root().visits = 5;
+        let other = build_test_tree();
+        tree.merge(&other);
+        assert_eq!(tree.get_root().visits, 105);
+        let serialized = create_tree_for_serialization(&tree, tree.get_root_nodekey(), None);
+        let expected = create_tree_for_serialization(&other, other.get_root_nodekey(), None);
+        // The test tree adds two children under the same action, so one of its nodes can't be
+        // reached from the root and isn't merged.
+        assert_eq!(tree.nodes.len(), other.nodes.len() - 1);
+        let children = |node: &MctsNodeForSerialization<MyState, MyAction>| {
+            node.children()
+                .map(|child| (child.action(), child.visits(), child.wins()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(children(&serialized), children(&expected));
+    }
+
+    fn new_parallel_mcts(seed: u64) -> MyMcts {
+        Mcts::new(
+            MyState { data: 0 },
+            MctsArgs {
+                iteration_limit: IterationLimitKind::Iterations(10),
+                max_depth_per_playout: 10,
+                rng: shared_rng(seed),
+                debug_track_trees: DebugTrackTrees::Track,
+                ..MctsArgs::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_run_parallel() {
+        let mut mcts = new_parallel_mcts(42);
+        mcts.run_parallel(4);
+        assert_eq!(mcts.iterations_run(), 40);
+        // Every iteration of every worker backs up its playouts through the root.
+        assert_eq!(mcts.tree.get_root().visits, 4 * 10 * 10);
+        assert_eq!(mcts.best_action(), Some(MyAction::Up));
+        // Only the merged tree is tracked.
+        assert_eq!(mcts.debug_trees().unwrap().len(), 1);
+    }
+
+    // Worker seeds come from the search's rng, so the merged tree is the same however the
+    // workers are scheduled.
+    #[test]
+    fn test_run_parallel_is_deterministic() {
+        let tree_json = |seed| {
+            let mut mcts = new_parallel_mcts(seed);
+            mcts.run_parallel(3);
+            serde_json::to_string(&mcts.serialize_tree()).unwrap()
+        };
+        assert_eq!(tree_json(7), tree_json(7));
+    }
+
+    #[test]
+    fn test_run_tree_parallel() {
+        let mut mcts = new_parallel_mcts(42);
+        mcts.run_tree_parallel(4, 3);
+        // The workers share the iteration limit rather than each running it in full.
+        assert_eq!(mcts.iterations_run(), 10);
+        assert_eq!(mcts.tree.get_root().visits, 10 * 10);
+        assert!(mcts
+            .tree
+            .nodes
+            .values()
+            .all(|node| node.virtual_loss == 0 && node.in_flight == 0));
+        assert_eq!(mcts.debug_trees().unwrap().len(), 1);
+    }
+
+    // A search can be handed to another thread, as a server handling requests on a thread pool
+    // would, and searches sharing one rng can run on different threads.
+    #[test]
+    fn test_mcts_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<MyMcts>();
+
+        let rng = shared_rng(42);
+        let searches: Vec<_> = (0..2)
+            .map(|_| {
+                let mut mcts = new_my_mcts(Arc::clone(&rng));
+                std::thread::spawn(move || {
+                    mcts.run();
+                    mcts
+                })
+            })
+            .collect();
+        for search in searches {
+            let mcts = search.join().unwrap();
+            assert_eq!(mcts.iterations_run(), 1000);
+            assert!(mcts.best_action().is_some());
+        }
+    }
+
+    #[test]
+    fn test_new_with_rng() {
+        let args = || MctsArgs {
+            iteration_limit: IterationLimitKind::Iterations(50),
+            max_depth_per_playout: 10,
+            ..MctsArgs::default()
+        };
+        let tree_json = |mcts: &mut MyMcts| {
+            mcts.run();
+            serde_json::to_string(&mcts.serialize_tree()).unwrap()
+        };
+        let rng = Rc::new(RefCell::new(Rng::seed_from_u64(7)));
+        let mut from_rc = Mcts::new_with_rng(MyState { data: 0 }, args(), Rc::clone(&rng));
+        let mut from_shared = Mcts::new(
+            MyState { data: 0 },
+            MctsArgs {
+                rng: shared_rng(7),
+                ..args()
+            },
+        );
+        assert_eq!(tree_json(&mut from_rc), tree_json(&mut from_shared));
+        // The search used a copy.
+        assert_eq!(*rng.borrow(), Rng::seed_from_u64(7));
+    }
+
+    fn new_transposition_mcts(transpositions: Transpositions) -> MyMcts {
+        Mcts::new(
+            MyState { data: 0 },
+            MctsArgs {
+                iteration_limit: IterationLimitKind::Iterations(100),
+                max_depth_per_playout: 10,
+                rng: shared_rng(42),
+                transpositions,
+                ..MctsArgs::default()
+            },
+        )
+    }
+
+    // Down, Left and Right all reach the same state, so with shared transpositions they lead to
+    // the same node, which gets the statistics of all three.
+    #[test]
+    fn test_transpositions_share_nodes() {
+        let mut mcts = new_transposition_mcts(Transpositions::Share);
+        mcts.run();
+        let tree = &mcts.tree;
+        let children = tree.get_children_nodekeys(tree.get_root_nodekey());
+        assert_eq!(children[&MyAction::Down], children[&MyAction::Left]);
+        assert_eq!(children[&MyAction::Down], children[&MyAction::Right]);
+        assert_ne!(children[&MyAction::Down], children[&MyAction::Up]);
+        assert_eq!(tree.get_root().visits, 100 * 10);
+        assert_eq!(mcts.best_action(), Some(MyAction::Up));
+
+        let mut separate = new_transposition_mcts(Transpositions::Separate);
+        separate.run();
+        assert!(mcts.node_count() < separate.node_count());
+    }
+
+    #[test]
+    fn test_run_parallel_with_transpositions() {
+        let mut mcts = new_transposition_mcts(Transpositions::Share);
+        mcts.run_parallel(2);
+        let tree = &mcts.tree;
+        assert_eq!(tree.get_root().visits, 2 * 100 * 10);
+        let children = tree.get_children_nodekeys(tree.get_root_nodekey());
+        assert_eq!(children[&MyAction::Down], children[&MyAction::Left]);
+    }
+
+    // A state on a ring of four positions, where Up and Down step around the ring. Every state
+    // can be reached from itself.
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    struct RingState {
+        position: u8,
+    }
+
+    impl State<MyAction> for RingState {
+        fn simulate(
+            &self,
+            playouts: Int,
+            _max_depth_per_playout: Int,
+            _rng: &mut Rng,
+        ) -> Vec<SimulationResult> {
+            let result = if self.position == 2 {
+                SimulationResult::Win
+            } else {
+                SimulationResult::NotWin
+            };
+            vec![result; playouts as usize]
+        }
+
+        fn get_actions(&self) -> Vec<MyAction> {
+            vec![MyAction::Up, MyAction::Down]
+        }
+
+        fn get_next_state(&self, action: &MyAction) -> Self {
+            let step = match action {
+                MyAction::Up => 1,
+                _ => 3,
+            };
+            RingState {
+                position: (self.position + step) % 4,
+            }
+        }
+
+        fn is_terminal(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test_transpositions_with_cycles() {
+        let mut mcts = Mcts::new(
+            RingState { position: 0 },
+            MctsArgs {
+                iteration_limit: IterationLimitKind::Iterations(200),
+                max_depth_per_playout: 10,
+                rng: shared_rng(42),
+                debug_track_trees: DebugTrackTrees::Track,
+                transpositions: Transpositions::Share,
+                ..MctsArgs::default()
+            },
+        );
+        mcts.run();
+        assert_eq!(mcts.node_count(), 4);
+        assert_eq!(mcts.tree.get_root().visits, 200 * 10);
+        // Each node's children are printed at most once.
+        assert!(mcts.tree.to_string().lines().count() <= 1 + 2 * 4);
+        assert_eq!(mcts.debug_trees().unwrap().len(), 200);
+    }
+
+    fn subtree_size(tree: &MyMctsTree, node_key: MctsNodeKey) -> usize {
+        1 + tree
+            .get_children_nodekeys(node_key)
+            .values()
+            .map(|child| subtree_size(tree, *child))
+            .sum::<usize>()
+    }
+
+    #[test]
+    fn test_advance_root_keeps_subtree() {
+        let mut mcts = new_parallel_mcts(42);
+        mcts.run();
+        let up = mcts.tree.get_children_nodekeys(mcts.tree.root)[&MyAction::Up];
+        let up_visits = mcts.tree.get_node_from_nodekey(up).visits;
+        let up_size = subtree_size(&mcts.tree, up);
+
+        mcts.advance_root(MyAction::Up);
+        assert_eq!(mcts.root_state(), &MyState { data: 100 });
+        assert_eq!(mcts.tree.root, up);
+        assert_eq!(mcts.tree.get_root().parent, None);
+        assert_eq!(mcts.tree.get_root().visits, up_visits);
+        assert_eq!(mcts.node_count(), up_size);
+        assert_eq!(mcts.debug_trees().unwrap().len(), 0);
+
+        mcts.run();
+        assert_eq!(mcts.tree.get_root().visits, up_visits + 10 * 10);
+        assert_eq!(mcts.best_action(), Some(MyAction::Up));
+    }
+
+    #[test]
+    fn test_advance_root_to_unexplored_state() {
+        let mut mcts = new_parallel_mcts(42);
+        mcts.run();
+        mcts.advance_root(MyAction::Up);
+        mcts.advance_root(MyAction::Up);
+        mcts.advance_root(MyAction::Left);
+        assert_eq!(mcts.root_state(), &MyState { data: 201 });
+        assert_eq!(mcts.node_count(), 1);
+        assert_eq!(mcts.tree.get_root().visits, 0);
+    }
+
+    #[test]
+    fn test_advance_root_with_transpositions() {
+        let mut mcts = new_transposition_mcts(Transpositions::Share);
+        mcts.run();
+        mcts.advance_root(MyAction::Down);
+        let table = mcts.tree.transpositions.as_ref().unwrap();
+        assert_eq!(table.len(), mcts.node_count());
+        assert_eq!(table[&MyState { data: 1 }], mcts.tree.root);
+        assert!(!table.contains_key(&MyState { data: 0 }));
+    }
+
+    #[test]
+    fn test_search_with_each_selection_policy() {
+        let policies: Vec<Arc<dyn SelectionPolicy>> =
+            vec![Arc::new(Uct), Arc::new(Ucb1Tuned), Arc::new(Puct)];
+        for selection_policy in policies {
+            let mut mcts = Mcts::new(
+                MyState { data: 0 },
+                MctsArgs {
+                    selection_policy,
+                    ..new_transposition_mcts(Transpositions::Separate).args
+                },
+            );
+            mcts.run();
+            assert_eq!(mcts.tree.get_root().visits, 100 * 10);
+            assert!(mcts.best_action().is_some());
+        }
+    }
+
+    // With a large exploration constant Puct follows the priors, whatever the statistics.
+    #[test]
+    fn test_puct_selection_follows_priors() {
+        let mut tree = build_test_tree();
+        let root = tree.get_root_nodekey();
+        let down = tree.get_children_nodekeys(root)[&MyAction::Down];
+        tree.get_mut_node_from_nodekey(down).prior = 1.0;
+        let uct_select_result = uct_select(&tree, root, &Puct, 10.0, None);
+        assert_eq!(uct_select_result.action, Some(MyAction::Down));
+        let uct_select_result = uct_select(&tree, root, &Uct, 1.4, None);
+        assert_eq!(uct_select_result.action, Some(MyAction::Up));
+    }
+
+    #[test]
+    fn test_expansion_sets_priors() {
+        let mut mcts = new_transposition_mcts(Transpositions::Separate);
+        mcts.run();
+        let tree = &mcts.tree;
+        for child in tree.get_children_nodekeys(tree.root).values() {
+            assert_abs_diff_eq!(tree.get_node_from_nodekey(*child).prior, 0.25);
+        }
+    }
+
+    // The priors selection sees at the root, with noise mixed in.
+    fn root_priors(mcts: &MyMcts) -> Vec<Float> {
+        let tree = &mcts.tree;
+        MyState { data: 0 }
+            .get_actions()
+            .iter()
+            .map(|action| {
+                let child =
+                    tree.get_node_from_nodekey(tree.get_children_nodekeys(tree.root)[action]);
+                child.noisy_prior.unwrap_or(child.prior)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_root_noise() {
+        let new_mcts = |seed| {
+            let mut mcts = new_transposition_mcts(Transpositions::Separate);
+            mcts.args.rng = shared_rng(seed);
+            mcts.args.root_noise = Some(DirichletNoise::new(0.3, 0.25).unwrap());
+            mcts
+        };
+        let mut mcts = new_mcts(1);
+        mcts.run();
+        let priors = root_priors(&mcts);
+        assert_abs_diff_eq!(priors.iter().sum::<Float>(), 1.0, epsilon = 1e-9);
+        assert!(priors.iter().any(|prior| (prior - 0.25).abs() > 1e-3));
+        // At least three quarters of each prior is kept.
+        assert!(priors.iter().all(|prior| *prior >= 0.75 * 0.25));
+
+        // Only the root's children get noise, and their own priors are kept to mix the next
+        // noise into.
+        let tree = &mcts.tree;
+        for child in tree.get_children_nodekeys(tree.root).values() {
+            assert_abs_diff_eq!(tree.get_node_from_nodekey(*child).prior, 0.25);
+            for grandchild in tree.get_children_nodekeys(*child).values() {
+                assert_eq!(tree.get_node_from_nodekey(*grandchild).noisy_prior, None);
+            }
+        }
+
+        // The same seed draws the same noise, and the next search draws new noise.
+        let mut again = new_mcts(1);
+        again.run();
+        assert_eq!(root_priors(&again), priors);
+        again.run();
+        assert_ne!(root_priors(&again), priors);
+        assert_abs_diff_eq!(
+            root_priors(&again).iter().sum::<Float>(),
+            1.0,
+            epsilon = 1e-9
+        );
+
+        // The old root's children lose their noise when the root moves on.
+        again.advance_root(MyAction::Up);
+        assert!(again
+            .tree
+            .nodes
+            .values()
+            .all(|node| node.noisy_prior.is_none()));
+    }
+
+    #[test]
+    fn test_dirichlet_noise_is_checked() {
+        assert!(DirichletNoise::new(0.3, 0.25).is_ok());
+        assert!(DirichletNoise::new(0.3, 0.0).is_ok());
+        assert_eq!(
+            DirichletNoise::new(0.0, 0.25),
+            Err(DirichletNoiseError::Alpha(0.0))
+        );
+        assert!(DirichletNoise::new(Float::NAN, 0.25).is_err());
+        assert_eq!(
+            DirichletNoise::new(0.3, 1.5),
+            Err(DirichletNoiseError::Epsilon(1.5))
+        );
+
+        // A single prior has nothing to share noise with, and is left alone.
+        let noise = DirichletNoise::new(0.3, 0.25).unwrap();
+        let mut priors = vec![1.0];
+        noise.mix(&mut priors, &mut Rng::seed_from_u64(0));
+        assert_eq!(priors, vec![1.0]);
+    }
+
+    #[test]
+    fn test_back_propagate_amaf() {
+        let mut tree = MyMctsTree::new(MyState { data: 0 });
+        let root = tree.get_root_nodekey();
+        let up = tree.add_child(root, MyAction::Up);
+        let down = tree.add_child(root, MyAction::Down);
+        let left = tree.add_child(root, MyAction::Left);
+        let up_down = tree.add_child(up, MyAction::Down);
+        let up_left = tree.add_child(up, MyAction::Left);
+        let descent = Descent {
+            path: vec![root, up],
+            actions: vec![MyAction::Up],
+            state: MyState { data: 100 },
+            evaluation: None,
+        };
+        let playouts = vec![
+            Playout {
+                result: SimulationResult::Win,
+                actions: vec![MyAction::Down, MyAction::Right],
+            },
+            Playout {
+                result: SimulationResult::NotWin,
+                actions: vec![MyAction::Left],
+            },
+        ];
+        tree.back_propagate(&descent, &playouts, true, RewardPerspective::Root, None);
+
+        let amaf = |node_key| {
+            let node = tree.get_node_from_nodekey(node_key);
+            (node.amaf_visits, node.amaf_wins)
+        };
+        // Up was played from the root in both playouts, Down and Left in one each.
+        assert_eq!(amaf(up), (2, 1.0));
+        assert_eq!(amaf(down), (1, 1.0));
+        assert_eq!(amaf(left), (1, 0.0));
+        assert_eq!(amaf(up_down), (1, 1.0));
+        assert_eq!(amaf(up_left), (1, 0.0));
+        assert_eq!(tree.get_node_from_nodekey(up).visits, 2);
+    }
+
+    #[test]
+    fn test_amaf_statistics_only_kept_for_rave() {
+        let amaf_visits = |selection_policy: Arc<dyn SelectionPolicy>| {
+            let mut mcts = Mcts::new(
+                MyState { data: 0 },
+                MctsArgs {
+                    selection_policy,
+                    ..new_transposition_mcts(Transpositions::Separate).args
+                },
+            );
+            mcts.run();
+            assert_eq!(mcts.tree.get_root().visits, 100 * 10);
+            mcts.tree
+                .nodes
+                .values()
+                .map(|node| node.amaf_visits)
+                .sum::<Int>()
+        };
+        assert!(amaf_visits(Arc::new(Rave::default())) > 0);
+        assert_eq!(amaf_visits(Arc::new(Uct)), 0);
+    }
+
+    // Draws and other graded outcomes add their reward rather than a whole win.
+    #[test]
+    fn test_back_propagate_rewards() {
+        let mut tree = MyMctsTree::new(MyState { data: 0 });
+        let root = tree.get_root_nodekey();
+        let up = tree.add_child(root, MyAction::Up);
+        let descent = Descent {
+            path: vec![root, up],
+            actions: vec![MyAction::Up],
+            state: MyState { data: 100 },
+            evaluation: None,
+        };
+        let playouts: Vec<Playout<MyAction>> = [
+            SimulationResult::Win,
+            SimulationResult::Reward(0.5),
+            SimulationResult::NotWin,
+            SimulationResult::Reward(0.25),
+        ]
+        .into_iter()
+        .map(|result| Playout {
+            result,
+            actions: vec![],
+        })
+        .collect();
+        tree.back_propagate(&descent, &playouts, false, RewardPerspective::Root, None);
+        let up = tree.get_node_from_nodekey(up);
+        assert_eq!((up.visits, up.wins), (4, 1.75));
+        assert_eq!(tree.get_root().wins, 1.75);
+    }
+
+    // Discounting shrinks rewards towards a draw for every move between a node and the end of
+    // the playout, whether the playout reports its length or only its actions.
+    #[test]
+    fn test_back_propagate_discounted() {
+        let mut tree = MyMctsTree::new(MyState { data: 0 });
+        let root = tree.get_root_nodekey();
+        let up = tree.add_child(root, MyAction::Up);
+        let descent = Descent {
+            path: vec![root, up],
+            actions: vec![MyAction::Up],
+            state: MyState { data: 100 },
+            evaluation: None,
+        };
+        let playouts = vec![
+            Playout {
+                result: SimulationResult::Win.after(2),
+                actions: vec![],
+            },
+            Playout {
+                result: SimulationResult::NotWin,
+                actions: vec![MyAction::Down, MyAction::Down],
+            },
+        ];
+        tree.back_propagate(
+            &descent,
+            &playouts,
+            false,
+            RewardPerspective::Root,
+            Some(0.5),
+        );
+        // Two moves from the end of the playouts: 0.5 + 0.25 * 0.5 and 0.5 - 0.25 * 0.5.
+        let up = tree.get_node_from_nodekey(up);
+        assert_abs_diff_eq!(up.wins, 0.625 + 0.375);
+        // Three moves: 0.5 + 0.125 * 0.5 and 0.5 - 0.125 * 0.5.
+        assert_abs_diff_eq!(tree.get_root().wins, 0.5625 + 0.4375);
+
+        let mut tree = MyMctsTree::new(MyState { data: 0 });
+        let root = tree.get_root_nodekey();
+        let up = tree.add_child(root, MyAction::Up);
+        tree.back_propagate(
+            &descent,
+            &playouts[..1],
+            false,
+            RewardPerspective::Alternating,
+            Some(0.5),
+        );
+        assert_abs_diff_eq!(tree.get_node_from_nodekey(up).wins, 0.625);
+        assert_abs_diff_eq!(tree.get_root().wins, 1.0 - 0.5625);
+    }
+
+    #[test]
+    fn test_simulation_result_length() {
+        assert_eq!(SimulationResult::Win.length(), None);
+        let result = SimulationResult::Reward(0.5).after(3);
+        assert_eq!(result.reward(), 0.5);
+        assert_eq!(result.length(), Some(3));
+        let playout = Playout {
+            result,
+            actions: vec![MyAction::Up],
+        };
+        assert_eq!(playout.length(), 3);
+    }
+
+    // With alternating perspectives, nodes reached by the opponent's moves hold the opponent's
+    // reward, and AMAF only credits the later moves of the player choosing between children.
+    #[test]
+    fn test_back_propagate_alternating() {
+        let mut tree = MyMctsTree::new(MyState { data: 0 });
+        let root = tree.get_root_nodekey();
+        let up = tree.add_child(root, MyAction::Up);
+        let down = tree.add_child(root, MyAction::Down);
+        let left = tree.add_child(root, MyAction::Left);
+        let up_down = tree.add_child(up, MyAction::Down);
+        let up_right = tree.add_child(up, MyAction::Right);
+        let descent = Descent {
+            path: vec![root, up, up_down],
+            actions: vec![MyAction::Up, MyAction::Down],
+            state: MyState { data: 100 },
+            evaluation: None,
+        };
+        let playouts = vec![
+            Playout {
+                result: SimulationResult::Win,
+                actions: vec![MyAction::Left, MyAction::Right],
+            },
+            Playout {
+                result: SimulationResult::Reward(0.25),
+                actions: vec![MyAction::Left],
+            },
+        ];
+        tree.back_propagate(
+            &descent,
+            &playouts,
+            true,
+            RewardPerspective::Alternating,
+            None,
+        );
+
+        let stats = |node_key| {
+            let node = tree.get_node_from_nodekey(node_key);
+            (node.visits, node.wins, node.amaf_visits, node.amaf_wins)
+        };
+        assert_eq!(stats(root), (2, 0.75, 0, 0.0));
+        assert_eq!(stats(up), (2, 1.25, 2, 1.25));
+        assert_eq!(stats(up_down), (2, 0.75, 2, 0.75));
+        // Left was played by the searching player, Down only by the opponent.
+        assert_eq!(stats(left), (0, 0.0, 2, 1.25));
+        assert_eq!(stats(down), (0, 0.0, 0, 0.0));
+        assert_eq!(stats(up_right), (0, 0.0, 1, 0.0));
+    }
+
+    fn new_limited_mcts(iteration_limit: IterationLimitKind) -> MyMcts {
+        Mcts::new(
+            MyState { data: 0 },
+            MctsArgs {
+                iteration_limit,
+                ..new_transposition_mcts(Transpositions::Separate).args
+            },
+        )
+    }
+
+    #[test]
+    fn test_iterations_or_time_stops_at_iterations() {
+        let mut mcts = new_limited_mcts(IterationLimitKind::IterationsOrTime(
+            25,
+            Duration::from_secs(3600),
+        ));
+        mcts.run();
+        assert_eq!(mcts.iterations_run(), 25);
+        assert_eq!(mcts.tree.get_root().visits, 25 * 10);
+    }
+
+    #[test]
+    fn test_iterations_or_time_stops_at_time() {
+        let mut mcts = new_limited_mcts(IterationLimitKind::IterationsOrTime(
+            Int::MAX,
+            Duration::from_millis(20),
+        ));
+        mcts.run();
+        assert!(mcts.iterations_run() > 0);
+        assert!(mcts.iterations_run() < Int::MAX);
+    }
+
+    #[test]
+    fn test_run_with_callback() {
+        let mut mcts = new_limited_mcts(IterationLimitKind::Iterations(100));
+        let mut seen = vec![];
+        mcts.run_with_callback(30, |mcts| {
+            seen.push(mcts.iterations_run());
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+        assert_eq!(seen, vec![30, 60, 90]);
+        assert_eq!(mcts.iterations_run(), 100);
+    }
+
+    // Stop as soon as the most visited move at the root has more than half of the visits.
+    #[test]
+    fn test_run_with_callback_stops_early() {
+        let mut mcts = new_limited_mcts(IterationLimitKind::Iterations(1000));
+        mcts.run_with_callback(10, |mcts| {
+            let visits = mcts.root_action_visits();
+            let total: Int = visits.iter().map(|(_, visits)| visits).sum();
+            let most = visits.iter().map(|(_, visits)| *visits).max().unwrap_or(0);
+            if 2 * most > total {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .unwrap();
+        assert!(mcts.iterations_run() < 1000);
+        assert_eq!(mcts.iterations_run() % 10, 0);
+        assert_eq!(mcts.tree.get_root().visits, mcts.iterations_run() * 10);
+    }
+
+    #[test]
+    fn test_search_info() {
+        let mut mcts = new_limited_mcts(IterationLimitKind::Iterations(50));
+        let info = mcts.search_info();
+        assert_eq!((info.iterations, info.nodes, info.max_depth), (0, 1, 0));
+        assert!(info.principal_variation.is_empty());
+        assert_eq!(info.elapsed_seconds, None);
+
+        mcts.run();
+
+        let info = mcts.search_info();
+        assert_eq!(info.iterations, 50);
+        assert_eq!(info.nodes, mcts.node_count());
+        assert!(info.max_depth >= info.principal_variation.len());
+        assert_eq!(
+            info.principal_variation.first(),
+            mcts.best_action().as_ref()
+        );
+        assert!(info.elapsed_seconds.is_some());
+        assert!(info.iterations_per_second.unwrap() > 0.0);
+        serde_json::to_string(&info).unwrap();
+    }
+
+    fn write_tree(mcts: &MyMcts, filter: TreeFilter) -> serde_json::Value {
+        let mut json = vec![];
+        mcts.write_tree(&mut json, filter).unwrap();
+        serde_json::from_slice(&json).unwrap()
+    }
+
+    // Without a filter the streamed tree is exactly the serialized one.
+    #[test]
+    fn test_write_tree_matches_serialize_tree() {
+        let mut mcts = new_transposition_mcts(Transpositions::Separate);
+        mcts.run();
+        let mut json = vec![];
+        mcts.write_tree(&mut json, TreeFilter::default()).unwrap();
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            serde_json::to_string(&mcts.serialize_tree()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_write_tree_with_filter() {
+        let mut mcts = new_transposition_mcts(Transpositions::Share);
+        mcts.run();
+        fn depth(node: &serde_json::Value) -> usize {
+            node["children"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|child| 1 + depth(child))
+                .max()
+                .unwrap_or(0)
+        }
+        fn min_visits(node: &serde_json::Value) -> i64 {
+            node["children"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|child| child["visits"].as_i64().unwrap().min(min_visits(child)))
+                .min()
+                .unwrap_or(i64::MAX)
+        }
+
+        let whole = write_tree(&mcts, TreeFilter::default());
+        assert!(depth(&whole) > 2);
+        assert_eq!(whole["visits"], 100 * 10);
+
+        let shallow = write_tree(
+            &mcts,
+            TreeFilter {
+                max_depth: Some(2),
+                ..TreeFilter::default()
+            },
+        );
+        assert_eq!(depth(&shallow), 2);
+
+        let busy = write_tree(
+            &mcts,
+            TreeFilter {
+                min_visits: 50,
+                ..TreeFilter::default()
+            },
+        );
+        assert!(min_visits(&busy) >= 50);
+        assert!(min_visits(&whole) < 50);
+    }
+
+    // The children of the root of the tree from page 162.
+    #[test]
+    fn test_root_action_stats() {
+        let mcts = Mcts::new_from_tree(
+            build_test_tree(),
+            new_transposition_mcts(Transpositions::Separate).args,
+        );
+        let stats = mcts.root_action_stats();
+        let actions: Vec<MyAction> = stats.iter().map(|stat| stat.action).collect();
+        assert_eq!(actions, vec![MyAction::Up, MyAction::Down, MyAction::Right]);
+        assert_eq!((stats[0].visits, stats[0].wins), (79, 60.0));
+        assert_abs_diff_eq!(stats[0].win_rate.unwrap(), 60.0 / 79.0);
+        let c = mcts.args.exploration_constant;
+        for stat in &stats {
+            assert_abs_diff_eq!(stat.uct, uct_score(stat.visits, stat.wins, 100, c));
+        }
+    }
+
+    // The first two levels of the tree from page 162, most visited children first.
+    #[test]
+    fn test_to_dot() {
+        let mcts = Mcts::new_from_tree(
+            build_test_tree(),
+            new_transposition_mcts(Transpositions::Separate).args,
+        );
+        let filter = TreeFilter {
+            max_depth: Some(1),
+            min_visits: 11,
+            ..TreeFilter::default()
+        };
+        assert_eq!(
+            mcts.to_dot(filter),
+            "digraph mcts {
+    node [shape=box, style=filled];
+    n0 [label=\"37 / 100 (37%)\", fillcolor=\"#915636\"];
+    n1 [label=\"60 / 79 (76%)\", fillcolor=\"#477f46\"];
+    n2 [label=\"2 / 11 (18%)\", fillcolor=\"#b5432e\"];
+    n0 -> n1 [label=\"Up\"];
+    n0 -> n2 [label=\"Down\"];
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_to_mermaid() {
+        let mcts = Mcts::new_from_tree(
+            build_test_tree(),
+            new_transposition_mcts(Transpositions::Separate).args,
+        );
+        let filter = TreeFilter {
+            max_depth: Some(1),
+            min_visits: 11,
+            ..TreeFilter::default()
+        };
+        assert_eq!(
+            mcts.to_mermaid(filter),
+            "graph TD
+    n0[\"37 / 100 (37%)\"]
+    n1[\"60 / 79 (76%)\"]
+    n2[\"2 / 11 (18%)\"]
+    n0 -->|\"Up\"| n1
+    n0 -->|\"Down\"| n2
+    style n0 fill:#915636
+    style n1 fill:#477f46
+    style n2 fill:#b5432e
+"
+        );
+    }
+
+    // With shared transpositions every node is drawn once, however many parents it has.
+    #[test]
+    fn test_display_options() {
+        let tree = build_test_tree();
+        let display = |filter| tree.display_options(filter).to_string();
+        assert_eq!(
+            display(TreeFilter {
+                max_depth: Some(1),
+                ..TreeFilter::default()
+            }),
+            "root: 37 / 100\n  Up: 60 / 79\n  Down: 2 / 11\n  Right: 1 / 10"
+        );
+        assert_eq!(
+            display(TreeFilter {
+                max_depth: Some(2),
+                min_visits: 11,
+                top_k: None,
+            }),
+            "root: 37 / 100\n  Up: 60 / 79\n    Right: 16 / 53\n    Up: 3 / 26\n  Down: 2 / 11"
+        );
+        assert_eq!(
+            display(TreeFilter {
+                top_k: Some(1),
+                ..TreeFilter::default()
+            }),
+            "root: 37 / 100\n  Up: 60 / 79\n    Right: 16 / 53\n      Up: 27 / 35"
+        );
+        // The whole tree, as Display prints it.
+        assert_eq!(display(TreeFilter::default()), tree.to_string());
+    }
+
+    #[test]
+    fn test_to_dot_with_transpositions() {
+        let mut mcts = new_transposition_mcts(Transpositions::Share);
+        mcts.run();
+        let dot = mcts.to_dot(TreeFilter::default());
+        let nodes = dot
+            .lines()
+            .filter(|line| line.contains("fillcolor"))
+            .count();
+        assert_eq!(nodes, mcts.node_count());
+        let edges = dot.lines().filter(|line| line.contains(" -> ")).count();
+        let children: usize = mcts
+            .tree
+            .nodes
+            .values()
+            .map(|node| node.children.len())
+            .sum();
+        assert_eq!(edges, children);
+    }
+
+    #[test]
+    fn test_best_action_policies() {
+        let mut tree = MyMctsTree::new(MyState { data: 0 });
+        let root = tree.get_root_nodekey();
+        for (action, visits, wins) in [
+            (MyAction::Up, 100, 55.0),
+            (MyAction::Down, 3, 3.0),
+            (MyAction::Left, 50, 32.0),
+            (MyAction::Right, 0, 0.0),
+        ] {
+            let child = tree.add_child(root, action);
+            let child = tree.get_mut_node_from_nodekey(child);
+            child.visits = visits;
+            child.wins = wins;
+        }
+        let best_action = |best_action_policy| {
+            Mcts::new_from_tree(
+                tree.clone(),
+                MctsArgs {
+                    best_action_policy,
+                    ..new_transposition_mcts(Transpositions::Separate).args
+                },
+            )
+            .best_action()
+        };
+
+        assert_eq!(
+            best_action(BestActionPolicy::MostVisits),
+            Some(MyAction::Up)
+        );
+        assert_eq!(
+            best_action(BestActionPolicy::HighestWinRate),
+            Some(MyAction::Down)
+        );
+        // 0.64 - 1 / sqrt(50) beats 0.55 - 1 / sqrt(100) and 1 - 1 / sqrt(3).
+        assert_eq!(
+            best_action(BestActionPolicy::SecureChild { confidence: 1.0 }),
+            Some(MyAction::Left)
+        );
+    }
+
+    // The principal variation follows the most visited children and stops at unvisited ones,
+    // which still count towards the depth.
+    #[test]
+    fn test_principal_variation_and_max_depth() {
+        let mut tree = MyMctsTree::new(MyState { data: 0 });
+        let root = tree.get_root_nodekey();
+        let up = tree.add_child(root, MyAction::Up);
+        let down = tree.add_child(root, MyAction::Down);
+        let up_down = tree.add_child(up, MyAction::Down);
+        let up_left = tree.add_child(up, MyAction::Left);
+        let up_down_left = tree.add_child(up_down, MyAction::Left);
+        for (node_key, visits) in [(up, 5), (down, 2), (up_down, 3), (up_left, 1)] {
+            tree.get_mut_node_from_nodekey(node_key).visits = visits;
+        }
+        assert_eq!(tree.get_node_from_nodekey(up_down_left).visits, 0);
+
+        assert_eq!(
+            tree.principal_variation(),
+            vec![MyAction::Up, MyAction::Down]
+        );
+        assert_eq!(tree.max_depth(), 3);
+    }
+
+    fn new_nim_mcts(stones: u8, perspective: RewardPerspective, solver: Solver) -> Mcts<Nim, Take> {
+        Mcts::new(
+            Nim::new(stones),
+            MctsArgs {
+                reward_perspective: perspective,
+                solver,
+                ..args(1000)
+            },
+        )
+    }
+
+    #[test]
+    fn test_solver_proves_win() {
+        let mut mcts = new_nim_mcts(7, RewardPerspective::Alternating, Solver::Enabled);
+        mcts.run();
+        assert_eq!(mcts.proven_reward(), Some(1.0));
+        assert_eq!(mcts.search_info().proven_reward, Some(1.0));
+        // Taking one stone leaves a multiple of three.
+        assert_eq!(mcts.best_action(), Some(Take(1)));
+        // The search stopped once the root was proven.
+        assert!(mcts.iterations_run() < 1000, "{}", mcts.iterations_run());
+
+        // Running again has nothing left to prove.
+        mcts.run();
+        assert_eq!(mcts.iterations_run(), 0);
+    }
+
+    #[test]
+    fn test_solver_proves_loss() {
+        let mut mcts = new_nim_mcts(6, RewardPerspective::Alternating, Solver::Enabled);
+        mcts.run();
+        assert_eq!(mcts.proven_reward(), Some(0.0));
+    }
+
+    #[test]
+    fn test_solver_with_root_perspective() {
+        // The search picks the opponent's moves too, so every pile is won.
+        let mut mcts = new_nim_mcts(6, RewardPerspective::Root, Solver::Enabled);
+        mcts.run();
+        assert_eq!(mcts.proven_reward(), Some(1.0));
+    }
+
+    #[test]
+    fn test_solver_disabled() {
+        let mut mcts = new_nim_mcts(7, RewardPerspective::Alternating, Solver::Disabled);
+        mcts.run();
+        assert_eq!(mcts.proven_reward(), None);
+        assert_eq!(mcts.iterations_run(), 1000);
+    }
+
+    #[test]
+    fn test_solver_with_tree_parallel() {
+        let mut mcts = new_nim_mcts(7, RewardPerspective::Alternating, Solver::Enabled);
+        mcts.run_tree_parallel(2, 1);
+        assert_eq!(mcts.proven_reward(), Some(1.0));
+        assert_eq!(mcts.best_action(), Some(Take(1)));
+    }
+
+    #[test]
+    fn test_solver_with_run_parallel() {
+        let mut mcts = new_nim_mcts(7, RewardPerspective::Alternating, Solver::Enabled);
+        mcts.run_parallel(2);
+        assert_eq!(mcts.proven_reward(), Some(1.0));
+        assert_eq!(mcts.best_action(), Some(Take(1)));
+    }
+
+    #[test]
+    fn test_solver_proves_children() {
+        // Taking one of four stones wins and taking two loses. Each child holds the reward of
+        // the player who chose it.
+        let mut mcts = new_nim_mcts(4, RewardPerspective::Alternating, Solver::Enabled);
+        mcts.run();
+        let tree = &mcts.tree;
+        let children = tree.get_children_nodekeys(tree.root);
+        assert_eq!(tree.nodes[children[&Take(1)]].proven, Some(1.0));
+        assert_eq!(tree.nodes[children[&Take(2)]].proven, Some(0.0));
+    }
+
+    fn new_node_limited_mcts(node_limit: NodeLimit) -> MyMcts {
+        let mut mcts = new_my_mcts(shared_rng(42));
+        mcts.args.playouts_per_simulation = 10;
+        mcts.args.node_limit = node_limit;
+        mcts
+    }
+
+    #[test]
+    fn test_node_limit_stop_expanding() {
+        let mut mcts = new_node_limited_mcts(NodeLimit::StopExpanding(50));
+        mcts.run();
+        assert!(mcts.node_count() <= 50, "{}", mcts.node_count());
+        assert_eq!(mcts.iterations_run(), 1000);
+        assert_eq!(mcts.tree.get_root().visits, 1000 * 10);
+        assert_eq!(mcts.best_action(), Some(MyAction::Up));
+    }
+
+    #[test]
+    fn test_node_limit_recycle() {
+        let mut unlimited = new_node_limited_mcts(NodeLimit::Unlimited);
+        unlimited.run();
+        assert!(unlimited.node_count() > 50);
+
+        let mut mcts = new_node_limited_mcts(NodeLimit::Recycle(50));
+        mcts.run();
+        assert!(mcts.node_count() <= 50, "{}", mcts.node_count());
+        assert_eq!(mcts.tree.get_root().visits, 1000 * 10);
+        assert_eq!(mcts.best_action(), Some(MyAction::Up));
+    }
+
+    #[test]
+    fn test_node_limit_recycle_with_transpositions() {
+        let mut mcts = new_node_limited_mcts(NodeLimit::Recycle(50));
+        mcts.args.transpositions = Transpositions::Share;
+        mcts.tree = MctsTree::new(MyState { data: 0 }).with_transpositions(Transpositions::Share);
+        mcts.run();
+        assert!(mcts.node_count() <= 50, "{}", mcts.node_count());
+        let table = mcts.tree.transpositions.as_ref().unwrap();
+        assert_eq!(table.len(), mcts.node_count());
+        assert!(table
+            .values()
+            .all(|node_key| mcts.tree.nodes.contains_key(*node_key)));
+    }
+
+    #[test]
+    fn test_node_limit_recycle_with_tree_parallel() {
+        let mut mcts = new_node_limited_mcts(NodeLimit::Recycle(50));
+        mcts.run_tree_parallel(2, 1);
+        assert!(mcts.node_count() <= 50, "{}", mcts.node_count());
+        assert_eq!(mcts.tree.get_root().visits, 1000 * 10);
+    }
+
+    // Without virtual loss the nodes other workers are playing out through must still be kept.
+    #[test]
+    fn test_node_limit_recycle_with_tree_parallel_and_no_virtual_loss() {
+        let mut mcts = new_node_limited_mcts(NodeLimit::Recycle(12));
+        // Long enough playouts that workers overlap.
+        mcts.args.playouts_per_simulation = 1000;
+        mcts.run_tree_parallel(8, 0);
+        assert!(mcts.node_count() <= 12, "{}", mcts.node_count());
+        assert_eq!(mcts.tree.get_root().visits, 1000 * 1000);
+        assert!(mcts.tree.nodes.values().all(|node| node.in_flight == 0));
+    }
+
+    #[test]
+    fn test_recycle_collapses_least_recently_visited() {
+        // Two expanded children of the root, each with two leaves, of which the Down subtree
+        // was visited longer ago.
+        let mut tree = MyMctsTree::new(MyState { data: 0 });
+        let root = tree.get_root_nodekey();
+        let up = tree.add_child(root, MyAction::Up);
+        let down = tree.add_child(root, MyAction::Down);
+        for (node_key, last_visited) in [(up, 5), (down, 1)] {
+            tree.nodes[node_key].last_visited = last_visited;
+            for action in [MyAction::Up, MyAction::Down] {
+                let leaf = tree.add_child(node_key, action);
+                tree.nodes[leaf].last_visited = last_visited;
+            }
+        }
+        assert_eq!(tree.nodes.len(), 7);
+
+        tree.recycle(5, &[]);
+        assert_eq!(tree.nodes.len(), 5);
+        assert!(tree.get_children_nodekeys(down).is_empty());
+        assert_eq!(tree.get_children_nodekeys(up).len(), 2);
+
+        // Nodes to keep aren't collapsed, even if nothing else can be.
+        tree.recycle(3, &[up]);
+        assert_eq!(tree.nodes.len(), 5);
+    }
 }
@@ -0,0 +1,233 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Playout policies choose the moves of the simulation step of MCTS. With run_playouts the
+// library plays the game out, so a game only supplies its moves, through State, and how the
+// state a playout ends in scores.
+
+use rand::seq::SliceRandom;
+use rand::Rng as _;
+
+use crate::{Action, Float, Int, Playout, Rng, SimulationResult, State};
+
+/// PlayoutPolicy chooses each move of a playout.
+pub trait PlayoutPolicy<_State, _Action> {
+    /// One of `actions`, which is not empty, to play from `state`.
+    fn choose(&self, state: &_State, actions: &[_Action], rng: &mut Rng) -> _Action;
+}
+
+/// MoveHeuristic scores moves for heavy playouts; the higher the better. Closures taking the
+/// state and the action are heuristics.
+pub trait MoveHeuristic<_State, _Action> {
+    fn score(&self, state: &_State, action: &_Action) -> Float;
+}
+
+impl<_State, _Action, F> MoveHeuristic<_State, _Action> for F
+where
+    F: Fn(&_State, &_Action) -> Float,
+{
+    fn score(&self, state: &_State, action: &_Action) -> Float {
+        self(state, action)
+    }
+}
+
+/// RandomPlayout plays uniformly random moves, as in the book.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomPlayout;
+
+impl<_State, _Action: Copy> PlayoutPolicy<_State, _Action> for RandomPlayout {
+    fn choose(&self, _state: &_State, actions: &[_Action], rng: &mut Rng) -> _Action {
+        *actions.choose(rng).expect("no actions to choose from")
+    }
+}
+
+/// EpsilonGreedy plays a random move with probability `epsilon`, and otherwise a move the
+/// heuristic scores highest, breaking ties at random. With an epsilon of 0 and a heuristic that
+/// only scores winning moves, playouts take a win whenever there is one and are random
+/// otherwise.
+#[derive(Debug, Clone, Copy)]
+pub struct EpsilonGreedy<_Heuristic> {
+    pub epsilon: Float,
+    pub heuristic: _Heuristic,
+}
+
+impl<_State, _Action, _Heuristic> PlayoutPolicy<_State, _Action> for EpsilonGreedy<_Heuristic>
+where
+    _Action: Copy,
+    _Heuristic: MoveHeuristic<_State, _Action>,
+{
+    fn choose(&self, state: &_State, actions: &[_Action], rng: &mut Rng) -> _Action {
+        if self.epsilon > 0.0 && rng.gen_bool(self.epsilon.min(1.0)) {
+            return RandomPlayout.choose(state, actions, rng);
+        }
+        let scores: Vec<Float> = actions
+            .iter()
+            .map(|action| self.heuristic.score(state, action))
+            .collect();
+        let best = scores.iter().copied().fold(Float::NEG_INFINITY, Float::max);
+        let best_actions: Vec<_Action> = actions
+            .iter()
+            .zip(&scores)
+            .filter(|(_, score)| **score == best)
+            .map(|(action, _)| *action)
+            .collect();
+        if best_actions.is_empty() {
+            // Every score was NaN.
+            return RandomPlayout.choose(state, actions, rng);
+        }
+        RandomPlayout.choose(state, &best_actions, rng)
+    }
+}
+
+/// HeuristicWeighted plays each move with probability proportional to its heuristic score.
+/// Negative scores count as 0, and if no move scores above 0 the choice is uniform.
+#[derive(Debug, Clone, Copy)]
+pub struct HeuristicWeighted<_Heuristic> {
+    pub heuristic: _Heuristic,
+}
+
+impl<_State, _Action, _Heuristic> PlayoutPolicy<_State, _Action> for HeuristicWeighted<_Heuristic>
+where
+    _Action: Copy,
+    _Heuristic: MoveHeuristic<_State, _Action>,
+{
+    fn choose(&self, state: &_State, actions: &[_Action], rng: &mut Rng) -> _Action {
+        actions
+            .choose_weighted(rng, |action| self.heuristic.score(state, action).max(0.0))
+            .copied()
+            .unwrap_or_else(|_| RandomPlayout.choose(state, actions, rng))
+    }
+}
+
+/// Play `playouts` games out from `state`, choosing moves with `policy` until the game is over
+/// or `max_depth_per_playout` moves have been played, and score the state each ends in with
+/// `evaluate`. The moves played are kept, so this can implement State::simulate_playouts, and
+/// the results carry the number of moves for discounting.
+pub fn run_playouts<_State, _Action>(
+    state: &_State,
+    policy: &dyn PlayoutPolicy<_State, _Action>,
+    evaluate: impl Fn(&_State) -> SimulationResult,
+    playouts: Int,
+    max_depth_per_playout: Int,
+    rng: &mut Rng,
+) -> Vec<Playout<_Action>>
+where
+    _State: State<_Action>,
+    _Action: Action,
+{
+    (0..playouts)
+        .map(|_| {
+            let mut state = state.clone();
+            let mut actions = vec![];
+            while (actions.len() as Int) < max_depth_per_playout && !state.is_terminal() {
+                let legal_actions = state.get_actions();
+                if legal_actions.is_empty() {
+                    break;
+                }
+                let action = policy.choose(&state, &legal_actions, rng);
+                state = state.get_next_state(&action);
+                actions.push(action);
+            }
+            Playout {
+                result: evaluate(&state).after(actions.len() as Int),
+                actions,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn rng() -> Rng {
+        Rng::seed_from_u64(42)
+    }
+
+    // Prefer larger numbers.
+    fn value(_state: &(), action: &i32) -> Float {
+        *action as Float
+    }
+
+    #[test]
+    fn test_random_playout_chooses_every_action() {
+        let mut rng = rng();
+        let mut chosen: Vec<i32> = (0..100)
+            .map(|_| RandomPlayout.choose(&(), &[1, 2, 3], &mut rng))
+            .collect();
+        chosen.sort_unstable();
+        chosen.dedup();
+        assert_eq!(chosen, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_epsilon_greedy() {
+        let mut rng = rng();
+        let greedy = EpsilonGreedy {
+            epsilon: 0.0,
+            heuristic: value,
+        };
+        assert!((0..100).all(|_| greedy.choose(&(), &[1, 3, 2], &mut rng) == 3));
+
+        let exploring = EpsilonGreedy {
+            epsilon: 0.5,
+            heuristic: value,
+        };
+        let best = (0..1000)
+            .filter(|_| exploring.choose(&(), &[1, 3, 2], &mut rng) == 3)
+            .count();
+        // Half the choices are greedy, and a third of the random half picks 3 too.
+        assert!((600..730).contains(&best), "{}", best);
+    }
+
+    #[test]
+    fn test_epsilon_greedy_breaks_ties_at_random() {
+        let mut rng = rng();
+        let greedy = EpsilonGreedy {
+            epsilon: 0.0,
+            heuristic: |_: &(), action: &i32| if *action > 1 { 1.0 } else { 0.0 },
+        };
+        let mut chosen: Vec<i32> = (0..100)
+            .map(|_| greedy.choose(&(), &[1, 2, 3], &mut rng))
+            .collect();
+        chosen.sort_unstable();
+        chosen.dedup();
+        assert_eq!(chosen, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_heuristic_weighted() {
+        let mut rng = rng();
+        let weighted = HeuristicWeighted { heuristic: value };
+        let threes = (0..1000)
+            .filter(|_| weighted.choose(&(), &[0, 1, 3], &mut rng) == 3)
+            .count();
+        // 0 is never chosen, and 3 three times as often as 1.
+        assert!((700..800).contains(&threes), "{}", threes);
+        assert!((0..100).all(|_| weighted.choose(&(), &[0, 1, 3], &mut rng) != 0));
+
+        let unweighted = HeuristicWeighted {
+            heuristic: |_: &(), _: &i32| 0.0,
+        };
+        let mut chosen: Vec<i32> = (0..100)
+            .map(|_| unweighted.choose(&(), &[1, 2, 3], &mut rng))
+            .collect();
+        chosen.sort_unstable();
+        chosen.dedup();
+        assert_eq!(chosen, vec![1, 2, 3]);
+    }
+}
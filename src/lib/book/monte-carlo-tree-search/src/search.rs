@@ -0,0 +1,255 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// A single call to search a state, for when setting up MctsArgs and an Mcts by hand is more
+// than is needed.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::{
+    shared_rng, Action, BestActionPolicy, Float, Int, IterationLimitKind, Mcts, MctsArgs,
+    RewardPerspective, SearchInfo, SelectionPolicy, Solver, State, Transpositions, Uct,
+};
+
+/// SearchOptions are the arguments to mcts_search. Start from `SearchOptions::builder()`, which
+/// has the defaults, and change what you need:
+///
+/// ```
+/// use monte_carlo_tree_search::SearchOptions;
+///
+/// let options = SearchOptions::builder().iterations(1000).seed(42);
+/// ```
+///
+/// By default the search runs 1000 iterations of UCT with an exploration constant of sqrt(2)
+/// and one playout per simulation, with rewards alternating between players as in a
+/// two-player game.
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    iteration_limit: IterationLimitKind,
+    seed: u64,
+    exploration_constant: Float,
+    selection_policy: Arc<dyn SelectionPolicy>,
+    playouts_per_simulation: Int,
+    max_depth_per_playout: Int,
+    transpositions: Transpositions,
+    reward_perspective: RewardPerspective,
+    best_action_policy: BestActionPolicy,
+    solver: Solver,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            iteration_limit: IterationLimitKind::Iterations(1000),
+            seed: 0,
+            exploration_constant: std::f64::consts::SQRT_2,
+            selection_policy: Arc::new(Uct),
+            playouts_per_simulation: 1,
+            max_depth_per_playout: 1000,
+            transpositions: Transpositions::Separate,
+            reward_perspective: RewardPerspective::Alternating,
+            best_action_policy: BestActionPolicy::MostVisits,
+            solver: Solver::Disabled,
+        }
+    }
+}
+
+impl SearchOptions {
+    pub fn builder() -> Self {
+        Self::default()
+    }
+
+    pub fn iterations(mut self, iterations: Int) -> Self {
+        self.iteration_limit = IterationLimitKind::Iterations(iterations);
+        self
+    }
+
+    /// Search for `time` instead of a number of iterations.
+    pub fn time_limit(mut self, time: Duration) -> Self {
+        self.iteration_limit = IterationLimitKind::TimeSeconds(time);
+        self
+    }
+
+    pub fn iteration_limit(mut self, iteration_limit: IterationLimitKind) -> Self {
+        self.iteration_limit = iteration_limit;
+        self
+    }
+
+    /// Seed for the search's rng. The same seed and options give the same search.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn exploration_constant(mut self, exploration_constant: Float) -> Self {
+        self.exploration_constant = exploration_constant;
+        self
+    }
+
+    pub fn selection_policy(mut self, selection_policy: impl SelectionPolicy + 'static) -> Self {
+        self.selection_policy = Arc::new(selection_policy);
+        self
+    }
+
+    pub fn playouts_per_simulation(mut self, playouts_per_simulation: Int) -> Self {
+        self.playouts_per_simulation = playouts_per_simulation;
+        self
+    }
+
+    pub fn max_depth_per_playout(mut self, max_depth_per_playout: Int) -> Self {
+        self.max_depth_per_playout = max_depth_per_playout;
+        self
+    }
+
+    pub fn transpositions(mut self, transpositions: Transpositions) -> Self {
+        self.transpositions = transpositions;
+        self
+    }
+
+    pub fn reward_perspective(mut self, reward_perspective: RewardPerspective) -> Self {
+        self.reward_perspective = reward_perspective;
+        self
+    }
+
+    pub fn best_action_policy(mut self, best_action_policy: BestActionPolicy) -> Self {
+        self.best_action_policy = best_action_policy;
+        self
+    }
+
+    pub fn solver(mut self, solver: Solver) -> Self {
+        self.solver = solver;
+        self
+    }
+
+    /// The MctsArgs these options stand for, to build an Mcts with when the search needs more
+    /// control than mcts_search gives.
+    pub fn to_args(&self) -> MctsArgs {
+        MctsArgs {
+            iteration_limit: self.iteration_limit,
+            exploration_constant: self.exploration_constant,
+            selection_policy: Arc::clone(&self.selection_policy),
+            playouts_per_simulation: self.playouts_per_simulation,
+            max_depth_per_playout: self.max_depth_per_playout,
+            rng: shared_rng(self.seed),
+            transpositions: self.transpositions,
+            reward_perspective: self.reward_perspective,
+            best_action_policy: self.best_action_policy,
+            solver: self.solver,
+            ..MctsArgs::default()
+        }
+    }
+}
+
+/// SearchResult is what mcts_search found.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchResult<_Action> {
+    // None if the root has no actions.
+    pub best_action: Option<_Action>,
+    pub info: SearchInfo<_Action>,
+
+    // Visits of each action from the root, most visited first.
+    pub root_action_visits: Vec<(_Action, Int)>,
+}
+
+/// Search from `root` with `options` and return the best action along with statistics about
+/// the search.
+pub fn mcts_search<_State, _Action>(root: _State, options: SearchOptions) -> SearchResult<_Action>
+where
+    _State: State<_Action>,
+    _Action: Action,
+{
+    let mut mcts = Mcts::new(root, options.to_args());
+    mcts.run();
+    let mut root_action_visits = mcts.root_action_visits();
+    root_action_visits.sort_by_key(|(_, visits)| std::cmp::Reverse(*visits));
+    SearchResult {
+        best_action: mcts.best_action(),
+        info: mcts.search_info(),
+        root_action_visits,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena::tests::{Nim, Take};
+
+    #[test]
+    fn test_mcts_search() {
+        let result = mcts_search(
+            Nim::new(10),
+            SearchOptions::builder()
+                .iterations(500)
+                .seed(42)
+                .playouts_per_simulation(4),
+        );
+        // Taking one stone leaves a multiple of three.
+        assert_eq!(result.best_action, Some(Take(1)));
+        assert_eq!(result.info.iterations, 500);
+        assert_eq!(result.root_action_visits.len(), 2);
+        assert_eq!(result.root_action_visits[0].0, Take(1));
+        assert!(result.root_action_visits[0].1 >= result.root_action_visits[1].1);
+    }
+
+    #[test]
+    fn test_same_seed_same_search() {
+        let search = |seed| {
+            mcts_search(
+                Nim::new(20),
+                SearchOptions::builder().iterations(200).seed(seed),
+            )
+            .root_action_visits
+        };
+        assert_eq!(search(7), search(7));
+    }
+
+    #[test]
+    fn test_mcts_search_with_solver() {
+        let result = mcts_search(
+            Nim::new(7),
+            SearchOptions::builder()
+                .iterations(1000)
+                .solver(Solver::Enabled),
+        );
+        assert_eq!(result.info.proven_reward, Some(1.0));
+        assert!(result.info.iterations < 1000);
+    }
+
+    #[test]
+    fn test_mcts_search_with_time_limit() {
+        let result = mcts_search(
+            Nim::new(20),
+            SearchOptions::builder().time_limit(Duration::from_millis(20)),
+        );
+        assert!(result.info.iterations > 0);
+        assert!(result.best_action.is_some());
+    }
+
+    #[test]
+    fn test_to_args() {
+        let args = SearchOptions::builder()
+            .exploration_constant(0.5)
+            .max_depth_per_playout(7)
+            .reward_perspective(RewardPerspective::Root)
+            .to_args();
+        assert_eq!(args.exploration_constant, 0.5);
+        assert_eq!(args.max_depth_per_playout, 7);
+        assert_eq!(args.reward_perspective, RewardPerspective::Root);
+    }
+}
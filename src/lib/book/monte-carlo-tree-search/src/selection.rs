@@ -0,0 +1,253 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Selection policies score the children of a node during the selection step of MCTS, and the
+// search descends into the child with the highest score.
+
+use std::fmt::Debug;
+
+use crate::{uct_score, Float, Int};
+
+/// ChildStats is what a selection policy knows about a child.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChildStats {
+    /// Playouts through the child, including virtual loss during a tree-parallel search.
+    pub visits: Int,
+    /// Total reward of the playouts through the child.
+    pub wins: Float,
+
+    /// Prior probability of the action leading to the child, from State::action_priors.
+    pub prior: Float,
+
+    /// All-moves-as-first statistics, only kept if the policy uses them.
+    pub amaf_visits: Int,
+    pub amaf_wins: Float,
+}
+
+impl ChildStats {
+    /// Mean reward of the playouts through the child, or 0 if there were none.
+    pub fn mean(&self) -> Float {
+        if self.visits == 0 {
+            return 0.0;
+        }
+        self.wins / Float::from(self.visits)
+    }
+
+    /// Like mean, but for the AMAF statistics.
+    pub fn amaf_mean(&self) -> Float {
+        if self.amaf_visits == 0 {
+            return 0.0;
+        }
+        self.amaf_wins / Float::from(self.amaf_visits)
+    }
+}
+
+pub trait SelectionPolicy: Debug + Send + Sync {
+    /// Score a child of a node with `parent_visits` visits. `exploration_constant` comes from
+    /// MctsArgs and weighs exploration against exploitation.
+    fn score(&self, child: &ChildStats, parent_visits: Int, exploration_constant: Float) -> Float;
+
+    /// Whether the policy uses AMAF statistics. Keeping them means recording every playout's
+    /// actions, so they are only kept if this is true.
+    fn uses_amaf(&self) -> bool {
+        false
+    }
+}
+
+/// Uct is UCB1 applied to trees, as in the book. See section 5.4 page 163.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Uct;
+
+impl SelectionPolicy for Uct {
+    fn score(&self, child: &ChildStats, parent_visits: Int, exploration_constant: Float) -> Float {
+        uct_score(
+            child.visits,
+            child.wins,
+            parent_visits,
+            exploration_constant,
+        )
+    }
+}
+
+/// Ucb1Tuned replaces UCB1's fixed exploration term with one scaled by an upper bound on the
+/// variance of the child's rewards, capped at 1/4, the largest variance a reward in [0, 1] can
+/// have. It has no exploration constant, so the one in MctsArgs is ignored.
+///
+/// See Auer, Cesa-Bianchi and Fischer, "Finite-time Analysis of the Multiarmed Bandit Problem"
+/// (2002).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ucb1Tuned;
+
+impl SelectionPolicy for Ucb1Tuned {
+    fn score(&self, child: &ChildStats, parent_visits: Int, _exploration_constant: Float) -> Float {
+        if child.visits == 0 {
+            return Float::INFINITY;
+        }
+        let mean = child.mean();
+        let log_over_visits = Float::from(parent_visits).ln() / Float::from(child.visits);
+        // Rewards are in [0, 1], so their variance is at most mean * (1 - mean), with equality
+        // when every playout is either won or not.
+        let variance_bound = mean * (1.0 - mean) + (2.0 * log_over_visits).sqrt();
+        mean + (log_over_visits * variance_bound.min(0.25)).sqrt()
+    }
+}
+
+/// Puct is the predictor + UCT formula from AlphaZero, which explores children in proportion to
+/// their prior probability, decaying as they are visited. Unvisited children score their prior
+/// alone, so with priors that aren't uniform they aren't all tried first as with Uct.
+///
+/// See Silver et al., "Mastering Chess and Shogi by Self-Play with a General Reinforcement
+/// Learning Algorithm" (2017).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Puct;
+
+impl SelectionPolicy for Puct {
+    fn score(&self, child: &ChildStats, parent_visits: Int, exploration_constant: Float) -> Float {
+        child.mean()
+            + exploration_constant * child.prior * Float::from(parent_visits).sqrt()
+                / Float::from(1 + child.visits)
+    }
+}
+
+/// Rave is UCT with Rapid Action Value Estimation. It treats an action as though it were played
+/// first whenever it was played later in a playout, which gives estimates for many actions from
+/// each playout, and blends these all-moves-as-first (AMAF) estimates with the usual mean. The
+/// weight of the AMAF estimate is beta = sqrt(k / (3n + k)) for a child with n visits, where k,
+/// `equivalence`, is the number of visits at which both estimates weigh the same.
+///
+/// Unvisited children are scored by their AMAF estimate as if they had been visited once, so
+/// they needn't all be tried first. With RewardPerspective::Alternating, an action counts for
+/// AMAF only when it was played later by the same player as the child's move, since the other
+/// player's moves say nothing about this player's choices. With RewardPerspective::Root, all
+/// players' actions are pooled and an action counts whoever played it.
+///
+/// See Gelly and Silver, "Monte-Carlo tree search and rapid action value estimation in computer
+/// Go" (2011).
+#[derive(Debug, Clone, Copy)]
+pub struct Rave {
+    pub equivalence: Float,
+}
+
+impl Default for Rave {
+    fn default() -> Self {
+        Self {
+            equivalence: 1000.0,
+        }
+    }
+}
+
+impl SelectionPolicy for Rave {
+    fn score(&self, child: &ChildStats, parent_visits: Int, exploration_constant: Float) -> Float {
+        if child.visits == 0 && child.amaf_visits == 0 {
+            return Float::INFINITY;
+        }
+        let visits = Float::from(child.visits);
+        let beta = (self.equivalence / (3.0 * visits + self.equivalence)).sqrt();
+        let value = (1.0 - beta) * child.mean() + beta * child.amaf_mean();
+        value + exploration_constant * (Float::from(parent_visits).ln() / visits.max(1.0)).sqrt()
+    }
+
+    fn uses_amaf(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+    use crate::ucb1;
+
+    fn stats(visits: Int, wins: Float, prior: Float) -> ChildStats {
+        ChildStats {
+            visits,
+            wins,
+            prior,
+            amaf_visits: 0,
+            amaf_wins: 0.0,
+        }
+    }
+
+    fn amaf_stats(visits: Int, wins: Float, amaf_visits: Int, amaf_wins: Float) -> ChildStats {
+        ChildStats {
+            amaf_visits,
+            amaf_wins,
+            ..stats(visits, wins, 0.0)
+        }
+    }
+
+    #[test]
+    fn test_uct_matches_ucb1() {
+        let child = stats(79, 60.0, 0.0);
+        assert_abs_diff_eq!(
+            Uct.score(&child, 100, 1.4),
+            ucb1(child.mean(), 79, 100, 1.4)
+        );
+    }
+
+    #[test]
+    fn test_unvisited_children_are_tried_first() {
+        assert_eq!(Uct.score(&stats(0, 0.0, 0.5), 10, 1.4), Float::INFINITY);
+        assert_eq!(
+            Ucb1Tuned.score(&stats(0, 0.0, 0.5), 10, 1.4),
+            Float::INFINITY
+        );
+        assert!(Puct.score(&stats(0, 0.0, 0.5), 10, 1.4).is_finite());
+    }
+
+    // A child that is always won has no variance, so UCB1-Tuned explores it less than UCB1 with
+    // the constant of 2 from the original paper would.
+    #[test]
+    fn test_ucb1_tuned_explores_less_with_low_variance() {
+        let child = stats(50, 50.0, 0.0);
+        let tuned = Ucb1Tuned.score(&child, 1000, 0.0);
+        assert!(tuned > 1.0);
+        assert!(tuned < ucb1(1.0, 50, 1000, 2.0_f64.sqrt()));
+    }
+
+    #[test]
+    fn test_puct_prefers_higher_priors() {
+        assert!(
+            Puct.score(&stats(0, 0.0, 0.7), 10, 1.0) > Puct.score(&stats(0, 0.0, 0.1), 10, 1.0)
+        );
+        assert_abs_diff_eq!(
+            Puct.score(&stats(3, 3.0, 0.5), 16, 2.0),
+            1.0 + 2.0 * 0.5 * 4.0 / 4.0
+        );
+    }
+
+    #[test]
+    fn test_rave_blends_towards_mean_with_visits() {
+        let rave = Rave { equivalence: 100.0 };
+        // Unvisited, only the AMAF estimate counts.
+        assert_abs_diff_eq!(rave.score(&amaf_stats(0, 0.0, 40, 30.0), 1, 0.0), 0.75);
+        // At the equivalence parameter beta is 1/2.
+        assert_abs_diff_eq!(
+            rave.score(&amaf_stats(100, 25.0, 400, 300.0), 1, 0.0),
+            0.5 * 0.25 + 0.5 * 0.75
+        );
+        // With many visits the mean dominates.
+        let score = rave.score(&amaf_stats(1_000_000, 250_000.0, 400, 300.0), 1, 0.0);
+        assert_abs_diff_eq!(score, 0.25, epsilon = 0.01);
+        assert_eq!(
+            rave.score(&amaf_stats(0, 0.0, 0, 0.0), 1, 0.0),
+            Float::INFINITY
+        );
+        assert!(rave.uses_amaf());
+        assert!(!Uct.uses_amaf());
+    }
+}
@@ -0,0 +1,177 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Stepping through a search one iteration at a time, for front-ends that animate it.
+
+use std::time::{Duration, Instant};
+
+use crate::{now, Action, Int, Mcts, Playout, State};
+
+/// IterationTrace describes one iteration of the search: the way selection went down the tree,
+/// the node expansion added, and the playouts backed up from there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IterationTrace<_Action> {
+    // Which iteration this was, counting from 0 at the start of the search.
+    pub iteration: Int,
+
+    // The actions from the root to the node selection stopped at.
+    pub path: Vec<_Action>,
+
+    // The action to the child that expansion added below the selected node and simulated from,
+    // if it added one. None for terminal nodes, nodes not yet visited, and a full tree.
+    pub expanded: Option<_Action>,
+
+    // The playouts backed up through the path. Their actions are only recorded for selection
+    // policies that use them, such as Rave.
+    pub playouts: Vec<Playout<_Action>>,
+
+    // Whether the playouts stand in for the solver's proven reward rather than being played.
+    pub proven: bool,
+}
+
+/// Iterations searches one iteration every time `next` is called, under the same limits as
+/// [`Mcts::run`]: it ends once the iteration limit is reached or the solver has proven the
+/// root. Returned by [`Mcts::iterations`].
+pub struct Iterations<'a, _State: State<_Action>, _Action: Action> {
+    mcts: &'a mut Mcts<_State, _Action>,
+    iteration: Int,
+    start: Option<Instant>,
+    deadline: Option<Instant>,
+}
+
+impl<_State, _Action> Mcts<_State, _Action>
+where
+    _State: State<_Action>,
+    _Action: Action,
+{
+    /// Search one iteration at a time, each described by an IterationTrace. Iterating to the
+    /// end searches exactly as [`Mcts::run`] does, except that nothing is written to the
+    /// decision log.
+    pub fn iterations(&mut self) -> Iterations<'_, _State, _Action> {
+        self.iterations_run = 0;
+        self.elapsed = None;
+        self.refresh_root_noise();
+        let deadline = self.args.iteration_limit.deadline();
+        Iterations {
+            mcts: self,
+            iteration: 0,
+            start: now(),
+            deadline,
+        }
+    }
+}
+
+impl<_State, _Action> Iterations<'_, _State, _Action>
+where
+    _State: State<_Action>,
+    _Action: Action,
+{
+    /// The search being stepped through, to draw the tree between iterations.
+    pub fn mcts(&self) -> &Mcts<_State, _Action> {
+        self.mcts
+    }
+
+    fn elapsed(&self) -> Option<Duration> {
+        self.start.map(|start| start.elapsed())
+    }
+}
+
+impl<_State, _Action> Iterator for Iterations<'_, _State, _Action>
+where
+    _State: State<_Action>,
+    _Action: Action,
+{
+    type Item = IterationTrace<_Action>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let limit = self.mcts.args.iteration_limit;
+        if !limit.allows(self.iteration, self.deadline) || self.mcts.proven_reward().is_some() {
+            return None;
+        }
+        let trace = self.mcts.iteration(self.iteration);
+        self.iteration += 1;
+        self.mcts.iterations_run = self.iteration;
+        self.mcts.elapsed = self.elapsed();
+        Some(trace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena::tests::{args, Nim, Take};
+    use crate::Solver;
+
+    fn new_mcts(stones: u8) -> Mcts<Nim, Take> {
+        let mut args = args(50);
+        args.solver = Solver::Disabled;
+        Mcts::new(Nim::new(stones), args)
+    }
+
+    #[test]
+    fn test_iterations() {
+        let mut mcts = new_mcts(20);
+        let traces: Vec<_> = mcts.iterations().collect();
+        assert_eq!(traces.len(), 50);
+        assert_eq!(mcts.iterations_run(), 50);
+
+        // The first iteration selects the root and expands one of its children.
+        assert_eq!(traces[0].iteration, 0);
+        assert!(traces[0].path.is_empty());
+        assert!(traces[0].expanded.is_some());
+        assert_eq!(traces[0].playouts.len(), 4);
+        assert!(!traces[0].proven);
+
+        // Each iteration adds at most one level to the tree for selection to go down.
+        for (i, trace) in traces.iter().enumerate() {
+            assert_eq!(trace.iteration, i as Int);
+            assert!(trace.path.len() <= i);
+        }
+    }
+
+    // Stepping to the end builds the same tree as run.
+    #[test]
+    fn test_iterations_match_run() {
+        let mut stepped = new_mcts(20);
+        stepped.iterations().for_each(drop);
+        let mut run = new_mcts(20);
+        run.run();
+        assert_eq!(stepped.root_action_visits(), run.root_action_visits());
+        assert_eq!(
+            stepped.search_info().principal_variation,
+            run.search_info().principal_variation
+        );
+    }
+
+    #[test]
+    fn test_iterations_can_stop_early() {
+        let mut mcts = new_mcts(20);
+        let mut iterations = mcts.iterations();
+        assert!(iterations.next().is_some());
+        assert!(iterations.next().is_some());
+        assert_eq!(iterations.mcts().iterations_run(), 2);
+        assert_eq!(iterations.mcts().search_info().iterations, 2);
+    }
+
+    #[test]
+    fn test_iterations_stop_once_proven() {
+        let mut mcts = Mcts::new(Nim::new(4), args(1000));
+        let traces: Vec<_> = mcts.iterations().collect();
+        assert!(traces.len() < 1000);
+        assert_eq!(mcts.iterations_run(), traces.len() as Int);
+        assert_eq!(mcts.proven_reward(), Some(1.0));
+    }
+}
@@ -0,0 +1,271 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Sweeping search parameters, scoring each setting by how it does in the arena against a
+// baseline configuration.
+
+use rand::seq::SliceRandom;
+use serde::Serialize;
+
+use crate::arena::{arena, ArenaConfig, ArenaGame, ArenaResult};
+use crate::{Action, Float, Int, MctsArgs};
+
+/// TuningParams are the parameters a sweep varies.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TuningParams {
+    pub exploration_constant: Float,
+    pub playouts_per_simulation: Int,
+    pub max_depth_per_playout: Int,
+}
+
+impl TuningParams {
+    fn of(args: &MctsArgs) -> Self {
+        Self {
+            exploration_constant: args.exploration_constant,
+            playouts_per_simulation: args.playouts_per_simulation,
+            max_depth_per_playout: args.max_depth_per_playout,
+        }
+    }
+
+    fn apply(&self, args: &MctsArgs) -> MctsArgs {
+        MctsArgs {
+            exploration_constant: self.exploration_constant,
+            playouts_per_simulation: self.playouts_per_simulation,
+            max_depth_per_playout: self.max_depth_per_playout,
+            ..args.clone()
+        }
+    }
+}
+
+/// SweepSpace lists the values to try for each parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepSpace {
+    pub exploration_constants: Vec<Float>,
+    pub playouts_per_simulation: Vec<Int>,
+    pub max_depth_per_playout: Vec<Int>,
+}
+
+impl SweepSpace {
+    /// Every combination of the values, varying the last parameter fastest.
+    fn grid(&self) -> Vec<TuningParams> {
+        let mut grid = vec![];
+        for &exploration_constant in &self.exploration_constants {
+            for &playouts_per_simulation in &self.playouts_per_simulation {
+                for &max_depth_per_playout in &self.max_depth_per_playout {
+                    grid.push(TuningParams {
+                        exploration_constant,
+                        playouts_per_simulation,
+                        max_depth_per_playout,
+                    });
+                }
+            }
+        }
+        grid
+    }
+}
+
+/// SweepKind chooses which settings of a SweepSpace are tried. Grid tries every combination.
+/// Random tries `samples` combinations picked at random without repeats, which covers large
+/// spaces more cheaply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SweepKind {
+    Grid,
+    Random { samples: usize },
+}
+
+/// TuningResult is how one setting did in the arena against the baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TuningResult {
+    pub params: TuningParams,
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+    pub score: Float,
+
+    // None if no games were played, and infinite, which serializes as null, if either side
+    // won every game.
+    pub elo_difference: Option<Float>,
+}
+
+impl TuningResult {
+    fn new(params: TuningParams, result: ArenaResult) -> Self {
+        Self {
+            params,
+            wins: result.wins,
+            losses: result.losses,
+            draws: result.draws,
+            score: result.score(),
+            elo_difference: result.elo_difference(),
+        }
+    }
+}
+
+/// TuningReport has the result of every setting tried, in the order they were tried, and the
+/// best of them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TuningReport {
+    pub baseline: TuningParams,
+    pub results: Vec<TuningResult>,
+
+    // The setting with the highest score, the earliest tried if several tie. None if the
+    // sweep tried nothing.
+    pub best: Option<TuningResult>,
+}
+
+/// Try settings from `space` by playing each against `baseline` in the arena from
+/// `initial_state`. Each setting is `baseline` with the swept parameters replaced, and the
+/// other arguments, including the iteration limit, are the baseline's. Random sweeps draw
+/// their samples from `baseline.rng`.
+pub fn sweep<_State, _Action>(
+    initial_state: &_State,
+    baseline: &MctsArgs,
+    space: &SweepSpace,
+    kind: SweepKind,
+    arena_config: &ArenaConfig,
+) -> TuningReport
+where
+    _State: ArenaGame<_Action>,
+    _Action: Action,
+{
+    let mut settings = space.grid();
+    if let SweepKind::Random { samples } = kind {
+        let mut rng = baseline.lock_rng();
+        settings.shuffle(&mut *rng);
+        settings.truncate(samples);
+    }
+
+    let results: Vec<TuningResult> = settings
+        .into_iter()
+        .map(|params| {
+            let challenger = params.apply(baseline);
+            let result = arena(initial_state, &challenger, baseline, arena_config);
+            TuningResult::new(params, result)
+        })
+        .collect();
+    let best = results.iter().copied().reduce(|best, result| {
+        if result.score > best.score {
+            result
+        } else {
+            best
+        }
+    });
+    TuningReport {
+        baseline: TuningParams::of(baseline),
+        results,
+        best,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena::tests::{args, Nim};
+
+    fn space() -> SweepSpace {
+        SweepSpace {
+            exploration_constants: vec![0.5, std::f64::consts::SQRT_2],
+            playouts_per_simulation: vec![1, 4],
+            max_depth_per_playout: vec![20],
+        }
+    }
+
+    fn arena_config() -> ArenaConfig {
+        ArenaConfig {
+            games: 4,
+            max_moves: 20,
+        }
+    }
+
+    #[test]
+    fn test_grid_sweep() {
+        let report = sweep(
+            &Nim::new(10),
+            &args(20),
+            &space(),
+            SweepKind::Grid,
+            &arena_config(),
+        );
+        assert_eq!(report.results.len(), 4);
+        assert_eq!(
+            report.results[1].params,
+            TuningParams {
+                exploration_constant: 0.5,
+                playouts_per_simulation: 4,
+                max_depth_per_playout: 20,
+            }
+        );
+        assert!(report
+            .results
+            .iter()
+            .all(|result| result.wins + result.losses + result.draws == 4));
+        let best = report.best.unwrap();
+        assert!(report
+            .results
+            .iter()
+            .all(|result| result.score <= best.score));
+        assert_eq!(report.baseline, TuningParams::of(&args(20)));
+    }
+
+    #[test]
+    fn test_random_sweep() {
+        let report = sweep(
+            &Nim::new(10),
+            &args(20),
+            &space(),
+            SweepKind::Random { samples: 3 },
+            &arena_config(),
+        );
+        assert_eq!(report.results.len(), 3);
+        let grid = space().grid();
+        assert!(report
+            .results
+            .iter()
+            .all(|result| grid.contains(&result.params)));
+        for (i, result) in report.results.iter().enumerate() {
+            assert!(!report.results[i + 1..]
+                .iter()
+                .any(|other| other.params == result.params));
+        }
+    }
+
+    #[test]
+    fn test_report_serializes() {
+        let report = sweep(
+            &Nim::new(10),
+            &args(20),
+            &space(),
+            SweepKind::Random { samples: 1 },
+            &arena_config(),
+        );
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["results"].as_array().unwrap().len(), 1);
+        assert_eq!(json["best"]["params"], json["results"][0]["params"]);
+        assert_eq!(json["baseline"]["max_depth_per_playout"], 20);
+    }
+
+    #[test]
+    fn test_empty_sweep() {
+        let report = sweep(
+            &Nim::new(10),
+            &args(20),
+            &space(),
+            SweepKind::Random { samples: 0 },
+            &arena_config(),
+        );
+        assert!(report.results.is_empty());
+        assert_eq!(report.best, None);
+    }
+}
@@ -0,0 +1,76 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Example planning problems from Chapter 11, in PDDL.
+
+/// The air cargo transport domain, Figure 11.1, using types instead of the unary Cargo, Plane
+/// and Airport predicates.
+pub const AIR_CARGO_DOMAIN: &str = r#"
+(define (domain air-cargo)
+  (:requirements :strips :typing)
+  (:types cargo plane airport)
+  (:predicates (At ?x ?a) (In ?c ?p))
+  (:action Load
+    :parameters (?c - cargo ?p - plane ?a - airport)
+    :precondition (and (At ?c ?a) (At ?p ?a))
+    :effect (and (not (At ?c ?a)) (In ?c ?p)))
+  (:action Unload
+    :parameters (?c - cargo ?p - plane ?a - airport)
+    :precondition (and (In ?c ?p) (At ?p ?a))
+    :effect (and (At ?c ?a) (not (In ?c ?p))))
+  (:action Fly
+    :parameters (?p - plane ?from ?to - airport)
+    :precondition (and (At ?p ?from))
+    :effect (and (not (At ?p ?from)) (At ?p ?to))))
+"#;
+
+/// Two planes, each starting at the airport where the other one's cargo has to go.
+pub const AIR_CARGO_PROBLEM: &str = r#"
+(define (problem air-cargo-2)
+  (:domain air-cargo)
+  (:objects C1 C2 - cargo P1 P2 - plane SFO JFK - airport)
+  (:init (At C1 SFO) (At C2 JFK) (At P1 SFO) (At P2 JFK))
+  (:goal (and (At C1 JFK) (At C2 SFO))))
+"#;
+
+/// The spare tire domain, Figure 11.2. PutOn needs the axle to be free, which is a negative
+/// precondition.
+pub const SPARE_TIRE_DOMAIN: &str = r#"
+(define (domain spare-tire)
+  (:requirements :strips :negative-preconditions)
+  (:constants Flat Spare Axle Trunk Ground)
+  (:predicates (Tire ?t) (At ?obj ?loc))
+  (:action Remove
+    :parameters (?obj ?loc)
+    :precondition (At ?obj ?loc)
+    :effect (and (not (At ?obj ?loc)) (At ?obj Ground)))
+  (:action PutOn
+    :parameters (?t)
+    :precondition (and (Tire ?t) (At ?t Ground) (not (At Flat Axle)) (not (At Spare Axle)))
+    :effect (and (not (At ?t Ground)) (At ?t Axle)))
+  (:action LeaveOvernight
+    :parameters ()
+    :precondition ()
+    :effect (and (not (At Spare Ground)) (not (At Spare Axle)) (not (At Spare Trunk))
+                 (not (At Flat Ground)) (not (At Flat Axle)) (not (At Flat Trunk)))))
+"#;
+
+pub const SPARE_TIRE_PROBLEM: &str = r#"
+(define (problem spare-tire)
+  (:domain spare-tire)
+  (:init (Tire Flat) (Tire Spare) (At Flat Axle) (At Spare Trunk))
+  (:goal (At Spare Axle)))
+"#;
@@ -0,0 +1,364 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Grounding: instantiate every action schema with every type-correct binding of its parameters.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+use crate::{Atom, Domain, Fact, Literal, PlanningError, PlanningProblem, Term};
+
+/// An action with objects bound to its parameters, such as Fly(P1, SFO, JFK).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GroundAction {
+    pub name: String,
+    pub args: Vec<String>,
+    pub positive_preconditions: Vec<Fact>,
+    pub negative_preconditions: Vec<Fact>,
+    pub add_list: Vec<Fact>,
+    pub delete_list: Vec<Fact>,
+}
+
+impl GroundAction {
+    pub fn is_applicable(&self, state: &BTreeSet<Fact>) -> bool {
+        self.positive_preconditions
+            .iter()
+            .all(|fact| state.contains(fact))
+            && !self
+                .negative_preconditions
+                .iter()
+                .any(|fact| state.contains(fact))
+    }
+
+    /// RESULT(s, a) = (s − DEL(a)) ∪ ADD(a).
+    pub fn apply(&self, state: &BTreeSet<Fact>) -> BTreeSet<Fact> {
+        let mut result = state.clone();
+        for fact in &self.delete_list {
+            result.remove(fact);
+        }
+        result.extend(self.add_list.iter().cloned());
+        result
+    }
+}
+
+impl fmt::Display for GroundAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({})", self.name, self.args.join(", "))
+    }
+}
+
+/// A planning problem with every action grounded, ready for state-space search.
+#[derive(Debug, Clone)]
+pub struct GroundTask {
+    pub actions: Vec<GroundAction>,
+    pub init: BTreeSet<Fact>,
+    pub positive_goals: Vec<Fact>,
+    pub negative_goals: Vec<Fact>,
+}
+
+impl GroundTask {
+    pub fn is_goal(&self, state: &BTreeSet<Fact>) -> bool {
+        self.positive_goals.iter().all(|fact| state.contains(fact))
+            && !self.negative_goals.iter().any(|fact| state.contains(fact))
+    }
+}
+
+struct Grounder<'a> {
+    objects: HashMap<&'a str, &'a str>,
+
+    /// Predicates that no action changes, so their truth is fixed by the initial state.
+    static_predicates: HashSet<&'a str>,
+    init: &'a BTreeSet<Fact>,
+}
+
+impl<'a> Grounder<'a> {
+    fn object(&self, name: &str) -> Result<String, PlanningError> {
+        if self.objects.contains_key(name) {
+            Ok(name.to_string())
+        } else {
+            Err(PlanningError::UnknownObject(name.to_string()))
+        }
+    }
+
+    fn fact(&self, atom: &Atom, binding: &HashMap<&str, &str>) -> Result<Fact, String> {
+        let args = atom
+            .terms
+            .iter()
+            .map(|term| match term {
+                Term::Constant(name) => Ok(name.clone()),
+                Term::Variable(name) => binding
+                    .get(name.as_str())
+                    .map(|object| object.to_string())
+                    .ok_or_else(|| name.clone()),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Fact {
+            predicate: atom.predicate.clone(),
+            args,
+        })
+    }
+
+    /// Whether a static precondition rules the ground action out in every reachable state.
+    fn is_statically_false(&self, literal: &Literal, fact: &Fact) -> bool {
+        self.static_predicates
+            .contains(literal.atom.predicate.as_str())
+            && self.init.contains(fact) != literal.positive
+    }
+}
+
+/// Ground every action schema of the domain for the objects and constants of the problem.
+/// Ground actions whose preconditions on static predicates fail in the initial state can never
+/// be applied, so they are dropped.
+pub fn ground(domain: &Domain, problem: &PlanningProblem) -> Result<GroundTask, PlanningError> {
+    let mut objects = HashMap::new();
+    for (name, type_name) in domain.constants.iter().chain(problem.objects.iter()) {
+        domain.check_type(type_name)?;
+        objects.insert(name.as_str(), type_name.as_str());
+    }
+    let static_predicates = {
+        let dynamic: HashSet<&str> = domain
+            .actions
+            .iter()
+            .flat_map(|action| action.effect.iter())
+            .map(|literal| literal.atom.predicate.as_str())
+            .collect();
+        domain
+            .actions
+            .iter()
+            .flat_map(|action| action.precondition.iter())
+            .map(|literal| literal.atom.predicate.as_str())
+            .filter(|predicate| !dynamic.contains(predicate))
+            .collect()
+    };
+    let grounder = Grounder {
+        objects,
+        static_predicates,
+        init: &problem.init,
+    };
+
+    let mut actions = vec![];
+    for schema in &domain.actions {
+        let unbound = |variable: String| PlanningError::UnboundVariable {
+            action: schema.name.clone(),
+            variable,
+        };
+        let mut candidates = vec![];
+        for parameter in &schema.parameters {
+            domain.check_type(&parameter.type_name)?;
+            let mut matching: Vec<&str> = grounder
+                .objects
+                .iter()
+                .filter(|(_, type_name)| domain.is_subtype(type_name, &parameter.type_name))
+                .map(|(name, _)| *name)
+                .collect();
+            matching.sort_unstable();
+            candidates.push(matching);
+        }
+        for literal in schema.precondition.iter().chain(schema.effect.iter()) {
+            for term in &literal.atom.terms {
+                match term {
+                    Term::Constant(name) => {
+                        grounder.object(name)?;
+                    }
+                    Term::Variable(name) => {
+                        if !schema.parameters.iter().any(|p| &p.name == name) {
+                            return Err(unbound(name.clone()));
+                        }
+                    }
+                }
+            }
+        }
+
+        'bindings: for args in cartesian_product(&candidates) {
+            let binding: HashMap<&str, &str> = schema
+                .parameters
+                .iter()
+                .map(|parameter| parameter.name.as_str())
+                .zip(args.iter().copied())
+                .collect();
+            let mut action = GroundAction {
+                name: schema.name.clone(),
+                args: args.iter().map(|arg| arg.to_string()).collect(),
+                positive_preconditions: vec![],
+                negative_preconditions: vec![],
+                add_list: vec![],
+                delete_list: vec![],
+            };
+            for literal in &schema.precondition {
+                let fact = grounder.fact(&literal.atom, &binding).map_err(unbound)?;
+                if grounder.is_statically_false(literal, &fact) {
+                    continue 'bindings;
+                }
+                if literal.positive {
+                    action.positive_preconditions.push(fact);
+                } else {
+                    action.negative_preconditions.push(fact);
+                }
+            }
+            for literal in &schema.effect {
+                let fact = grounder.fact(&literal.atom, &binding).map_err(unbound)?;
+                if literal.positive {
+                    action.add_list.push(fact);
+                } else {
+                    action.delete_list.push(fact);
+                }
+            }
+            actions.push(action);
+        }
+    }
+
+    let mut positive_goals = vec![];
+    let mut negative_goals = vec![];
+    for literal in &problem.goal {
+        let fact = grounder
+            .fact(&literal.atom, &HashMap::new())
+            .map_err(|_| PlanningError::VariableInGoal(literal.to_string()))?;
+        for arg in &fact.args {
+            grounder.object(arg)?;
+        }
+        if literal.positive {
+            positive_goals.push(fact);
+        } else {
+            negative_goals.push(fact);
+        }
+    }
+
+    Ok(GroundTask {
+        actions,
+        init: problem.init.clone(),
+        positive_goals,
+        negative_goals,
+    })
+}
+
+/// Every way of picking one item from each list, in lexicographic order.
+fn cartesian_product<'a>(lists: &[Vec<&'a str>]) -> Vec<Vec<&'a str>> {
+    lists.iter().fold(vec![vec![]], |prefixes, list| {
+        prefixes
+            .iter()
+            .flat_map(|prefix| {
+                list.iter().map(move |item| {
+                    let mut next = prefix.clone();
+                    next.push(*item);
+                    next
+                })
+            })
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examples::{
+        AIR_CARGO_DOMAIN, AIR_CARGO_PROBLEM, SPARE_TIRE_DOMAIN, SPARE_TIRE_PROBLEM,
+    };
+    use crate::pddl::{parse_domain, parse_facts, parse_problem};
+
+    fn air_cargo() -> GroundTask {
+        ground(
+            &parse_domain(AIR_CARGO_DOMAIN).unwrap(),
+            &parse_problem(AIR_CARGO_PROBLEM).unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_cartesian_product() {
+        let product = cartesian_product(&[vec!["a", "b"], vec!["x", "y"]]);
+        assert_eq!(
+            product,
+            vec![
+                vec!["a", "x"],
+                vec!["a", "y"],
+                vec!["b", "x"],
+                vec!["b", "y"]
+            ]
+        );
+        assert_eq!(cartesian_product(&[]), vec![Vec::<&str>::new()]);
+    }
+
+    #[test]
+    fn test_ground_air_cargo_respects_types() {
+        let task = air_cargo();
+        // Load and Unload: 2 cargo × 2 planes × 2 airports. Fly: 2 planes × 2 × 2 airports.
+        assert_eq!(task.actions.len(), 24);
+        let names: Vec<String> = task.actions.iter().map(|a| a.to_string()).collect();
+        assert!(names.contains(&"Fly(P1, SFO, JFK)".to_string()));
+        assert!(!names.iter().any(|name| name.contains("Load(P1")));
+    }
+
+    #[test]
+    fn test_apply() {
+        let task = air_cargo();
+        let load = task
+            .actions
+            .iter()
+            .find(|action| action.to_string() == "Load(C1, P1, SFO)")
+            .unwrap();
+        assert!(load.is_applicable(&task.init));
+        let expected = parse_facts("(In C1 P1) (At C2 JFK) (At P1 SFO) (At P2 JFK)").unwrap();
+        assert_eq!(load.apply(&task.init), expected);
+        assert!(!load.is_applicable(&expected));
+    }
+
+    #[test]
+    fn test_static_preconditions_prune_actions() {
+        let task = ground(
+            &parse_domain(SPARE_TIRE_DOMAIN).unwrap(),
+            &parse_problem(SPARE_TIRE_PROBLEM).unwrap(),
+        )
+        .unwrap();
+        let put_on: Vec<String> = task
+            .actions
+            .iter()
+            .filter(|action| action.name == "PutOn")
+            .map(|action| action.to_string())
+            .collect();
+        assert_eq!(put_on, vec!["PutOn(Flat)", "PutOn(Spare)"]);
+        assert!(!task.is_goal(&task.init));
+    }
+
+    #[test]
+    fn test_ground_errors() {
+        let domain = parse_domain(AIR_CARGO_DOMAIN).unwrap();
+        let mut problem = parse_problem(AIR_CARGO_PROBLEM).unwrap();
+        problem.objects.push(("Q1".to_string(), "boat".to_string()));
+        assert_eq!(
+            ground(&domain, &problem).unwrap_err(),
+            PlanningError::UnknownType("boat".to_string())
+        );
+
+        let problem =
+            parse_problem("(define (problem p) (:objects P1 - plane) (:goal (At P1 LAX)))")
+                .unwrap();
+        assert_eq!(
+            ground(&domain, &problem).unwrap_err(),
+            PlanningError::UnknownObject("LAX".to_string())
+        );
+
+        let domain =
+            parse_domain("(define (domain d) (:action A :parameters (?x) :effect (P ?y)))")
+                .unwrap();
+        assert_eq!(
+            ground(&domain, &PlanningProblem::default()).unwrap_err(),
+            PlanningError::UnboundVariable {
+                action: "A".to_string(),
+                variable: "y".to_string()
+            }
+        );
+    }
+}
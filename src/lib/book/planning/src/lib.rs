@@ -0,0 +1,227 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Classical planning with STRIPS-style action schemas.
+//
+// See:
+// -  Chapter 11: Automated Planning, sections 11.1 to 11.3 (Chapter 10 in the third edition)
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use thiserror::Error;
+
+pub mod examples;
+pub mod grounding;
+pub mod pddl;
+pub mod planner;
+
+/// Type of objects and parameters that were not given a type.
+pub const OBJECT_TYPE: &str = "object";
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PlanningError {
+    #[error("unknown type {0}")]
+    UnknownType(String),
+
+    #[error("unknown object {0}")]
+    UnknownObject(String),
+
+    #[error("variable {variable} is not a parameter of action {action}")]
+    UnboundVariable { action: String, variable: String },
+
+    #[error("goal {0} contains a variable")]
+    VariableInGoal(String),
+}
+
+/// An argument of an atom in an action schema: a parameter to be bound, such as ?plane, or a
+/// constant object, such as SFO.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Term {
+    Variable(String),
+    Constant(String),
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Term::Variable(name) => write!(f, "?{}", name),
+            Term::Constant(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// A predicate applied to terms, such as At(?plane, ?from).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Atom {
+    pub predicate: String,
+    pub terms: Vec<Term>,
+}
+
+impl fmt::Display for Atom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let terms: Vec<String> = self.terms.iter().map(Term::to_string).collect();
+        write!(f, "{}({})", self.predicate, terms.join(", "))
+    }
+}
+
+/// An atom or its negation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Literal {
+    pub atom: Atom,
+    pub positive: bool,
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.positive {
+            write!(f, "{}", self.atom)
+        } else {
+            write!(f, "¬{}", self.atom)
+        }
+    }
+}
+
+/// A ground atom, with objects for every argument. A state is the set of facts that are true,
+/// and every other fact is false.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fact {
+    pub predicate: String,
+    pub args: Vec<String>,
+}
+
+impl Fact {
+    pub fn new(predicate: &str, args: &[&str]) -> Self {
+        Self {
+            predicate: predicate.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+}
+
+impl fmt::Display for Fact {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({})", self.predicate, self.args.join(", "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parameter {
+    pub name: String,
+    pub type_name: String,
+}
+
+/// An action schema, section 11.1: a family of ground actions, one for each way of binding the
+/// parameters to objects of the right types. The action is applicable in a state where every
+/// positive precondition holds and no negative precondition holds. The result deletes the
+/// negative effects and then adds the positive ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionSchema {
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    pub precondition: Vec<Literal>,
+    pub effect: Vec<Literal>,
+}
+
+/// The parts of a planning problem that do not change between problems: types, constants and
+/// action schemas.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Domain {
+    pub name: String,
+
+    /// Parent of each type. Types without a parent are subtypes of object.
+    pub types: HashMap<String, String>,
+
+    /// Objects shared by every problem in the domain, with their types.
+    pub constants: Vec<(String, String)>,
+    pub actions: Vec<ActionSchema>,
+}
+
+impl Domain {
+    /// Whether objects of type_name can bind a parameter of type ancestor.
+    pub fn is_subtype(&self, type_name: &str, ancestor: &str) -> bool {
+        let mut current = type_name;
+        loop {
+            if current == ancestor || ancestor == OBJECT_TYPE {
+                return true;
+            }
+            match self.types.get(current) {
+                Some(parent) => current = parent,
+                None => return false,
+            }
+        }
+    }
+
+    fn check_type(&self, type_name: &str) -> Result<(), PlanningError> {
+        if type_name == OBJECT_TYPE || self.types.contains_key(type_name) {
+            Ok(())
+        } else {
+            Err(PlanningError::UnknownType(type_name.to_string()))
+        }
+    }
+}
+
+/// A planning problem in a domain: the objects, the initial state, and the goal. The goal is a
+/// conjunction of ground literals.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PlanningProblem {
+    pub name: String,
+    pub objects: Vec<(String, String)>,
+    pub init: BTreeSet<Fact>,
+    pub goal: Vec<Literal>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subtypes() {
+        let domain = Domain {
+            types: HashMap::from([
+                ("plane".to_string(), "vehicle".to_string()),
+                ("vehicle".to_string(), OBJECT_TYPE.to_string()),
+            ]),
+            ..Domain::default()
+        };
+        assert!(domain.is_subtype("plane", "vehicle"));
+        assert!(domain.is_subtype("plane", OBJECT_TYPE));
+        assert!(!domain.is_subtype("vehicle", "plane"));
+        assert!(domain.check_type("plane").is_ok());
+        assert_eq!(
+            domain.check_type("boat"),
+            Err(PlanningError::UnknownType("boat".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let atom = Atom {
+            predicate: "At".to_string(),
+            terms: vec![
+                Term::Variable("p".to_string()),
+                Term::Constant("SFO".to_string()),
+            ],
+        };
+        assert_eq!(atom.to_string(), "At(?p, SFO)");
+        let literal = Literal {
+            atom,
+            positive: false,
+        };
+        assert_eq!(literal.to_string(), "¬At(?p, SFO)");
+        assert_eq!(Fact::new("Plane", &["P1"]).to_string(), "Plane(P1)");
+    }
+}
@@ -0,0 +1,449 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Parsing a subset of PDDL: STRIPS domains with typing, constants and negative preconditions.
+// Names are case-sensitive; keywords are not.
+
+use std::collections::BTreeSet;
+
+use thiserror::Error;
+
+use crate::{
+    ActionSchema, Atom, Domain, Fact, Literal, Parameter, PlanningProblem, Term, OBJECT_TYPE,
+};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PddlError {
+    #[error("unexpected ')' at position {0}")]
+    UnexpectedClose(usize),
+
+    #[error("unexpected end of input, expected ')'")]
+    UnexpectedEnd,
+
+    #[error("unexpected input after the definition at position {0}")]
+    TrailingInput(usize),
+
+    #[error("expected {expected}, found {found}")]
+    Expected {
+        expected: &'static str,
+        found: String,
+    },
+
+    #[error("unsupported section {0}")]
+    UnsupportedSection(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SExpr {
+    Symbol(String),
+    List(Vec<SExpr>),
+}
+
+impl SExpr {
+    fn describe(&self) -> String {
+        match self {
+            SExpr::Symbol(symbol) => symbol.clone(),
+            SExpr::List(items) => {
+                let items: Vec<String> = items.iter().map(SExpr::describe).collect();
+                format!("({})", items.join(" "))
+            }
+        }
+    }
+
+    fn symbol(&self, expected: &'static str) -> Result<&str, PddlError> {
+        match self {
+            SExpr::Symbol(symbol) => Ok(symbol),
+            SExpr::List(_) => Err(expected_error(expected, Some(self))),
+        }
+    }
+
+    fn list(&self, expected: &'static str) -> Result<&[SExpr], PddlError> {
+        match self {
+            SExpr::List(items) => Ok(items),
+            SExpr::Symbol(_) => Err(expected_error(expected, Some(self))),
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, SExpr::Symbol(symbol) if symbol.eq_ignore_ascii_case(keyword))
+    }
+}
+
+fn expected_error(expected: &'static str, found: Option<&SExpr>) -> PddlError {
+    PddlError::Expected {
+        expected,
+        found: found.map_or("nothing".to_string(), SExpr::describe),
+    }
+}
+
+/// Read a single S-expression, skipping whitespace and ';' comments.
+fn read(text: &str) -> Result<SExpr, PddlError> {
+    let mut stack: Vec<Vec<SExpr>> = vec![];
+    let mut result = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        if c == ';' {
+            while chars.next_if(|&(_, c)| c != '\n').is_some() {}
+            continue;
+        }
+        if c == ')' && stack.is_empty() {
+            return Err(PddlError::UnexpectedClose(position));
+        }
+        if result.is_some() {
+            return Err(PddlError::TrailingInput(position));
+        }
+        let expr = match c {
+            '(' => {
+                stack.push(vec![]);
+                continue;
+            }
+            ')' => SExpr::List(stack.pop().unwrap_or_default()),
+            _ => {
+                let mut symbol = c.to_string();
+                while let Some((_, c)) =
+                    chars.next_if(|&(_, c)| !c.is_whitespace() && !"();".contains(c))
+                {
+                    symbol.push(c);
+                }
+                SExpr::Symbol(symbol)
+            }
+        };
+        match stack.last_mut() {
+            Some(parent) => parent.push(expr),
+            None => result = Some(expr),
+        }
+    }
+    if !stack.is_empty() {
+        return Err(PddlError::UnexpectedEnd);
+    }
+    result.ok_or(PddlError::Expected {
+        expected: "a definition",
+        found: "nothing".to_string(),
+    })
+}
+
+/// Parse a list such as `a b - plane c` into names and types. Names without a type are objects.
+fn typed_list(items: &[SExpr]) -> Result<Vec<(String, String)>, PddlError> {
+    let mut result = vec![];
+    let mut untyped = 0;
+    let mut iter = items.iter();
+    while let Some(item) = iter.next() {
+        let symbol = item.symbol("a name")?;
+        if symbol == "-" {
+            let type_name = iter.next();
+            let type_name = type_name
+                .ok_or_else(|| expected_error("a type", None))?
+                .symbol("a type")?;
+            for (_, pending) in result[untyped..].iter_mut() {
+                *pending = type_name.to_string();
+            }
+            untyped = result.len();
+        } else {
+            result.push((symbol.to_string(), OBJECT_TYPE.to_string()));
+        }
+    }
+    Ok(result)
+}
+
+fn term(symbol: &str) -> Term {
+    match symbol.strip_prefix('?') {
+        Some(name) => Term::Variable(name.to_string()),
+        None => Term::Constant(symbol.to_string()),
+    }
+}
+
+fn atom(expr: &SExpr) -> Result<Atom, PddlError> {
+    let items = expr.list("an atom")?;
+    let (predicate, terms) = items
+        .split_first()
+        .ok_or_else(|| expected_error("an atom", Some(expr)))?;
+    Ok(Atom {
+        predicate: predicate.symbol("a predicate")?.to_string(),
+        terms: terms
+            .iter()
+            .map(|t| t.symbol("a term").map(term))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+fn literal(expr: &SExpr) -> Result<Literal, PddlError> {
+    match expr.list("a literal")? {
+        [not, inner] if not.is_keyword("not") => Ok(Literal {
+            atom: atom(inner)?,
+            positive: false,
+        }),
+        _ => Ok(Literal {
+            atom: atom(expr)?,
+            positive: true,
+        }),
+    }
+}
+
+/// Parse a conjunction of literals. `()` is the empty conjunction.
+fn conjunction(expr: &SExpr) -> Result<Vec<Literal>, PddlError> {
+    match expr.list("a conjunction")? {
+        [] => Ok(vec![]),
+        [and, conjuncts @ ..] if and.is_keyword("and") => conjuncts.iter().map(literal).collect(),
+        _ => Ok(vec![literal(expr)?]),
+    }
+}
+
+/// Check for `(define (<kind> <name>) ...)` and return the name and the sections.
+fn definition<'a>(expr: &'a SExpr, kind: &'static str) -> Result<(String, &'a [SExpr]), PddlError> {
+    match expr.list("(define ...)")? {
+        [define, header, sections @ ..] if define.is_keyword("define") => {
+            match header.list("a header")? {
+                [keyword, name] if keyword.is_keyword(kind) => {
+                    Ok((name.symbol("a name")?.to_string(), sections))
+                }
+                _ => Err(expected_error(kind, Some(header))),
+            }
+        }
+        _ => Err(expected_error("(define ...)", Some(expr))),
+    }
+}
+
+fn action(items: &[SExpr]) -> Result<ActionSchema, PddlError> {
+    let (name, fields) = items
+        .split_first()
+        .ok_or_else(|| expected_error("an action name", None))?;
+    let mut schema = ActionSchema {
+        name: name.symbol("an action name")?.to_string(),
+        parameters: vec![],
+        precondition: vec![],
+        effect: vec![],
+    };
+    for pair in fields.chunks(2) {
+        let [key, value] = pair else {
+            return Err(expected_error("a value", None));
+        };
+        let key = key.symbol("an action field")?.to_ascii_lowercase();
+        match key.as_str() {
+            ":parameters" => {
+                schema.parameters = typed_list(value.list("a parameter list")?)?
+                    .into_iter()
+                    .map(|(name, type_name)| match name.strip_prefix('?') {
+                        Some(name) => Ok(Parameter {
+                            name: name.to_string(),
+                            type_name,
+                        }),
+                        None => Err(PddlError::Expected {
+                            expected: "a variable",
+                            found: name,
+                        }),
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            ":precondition" => schema.precondition = conjunction(value)?,
+            ":effect" => schema.effect = conjunction(value)?,
+            _ => return Err(PddlError::UnsupportedSection(key)),
+        }
+    }
+    Ok(schema)
+}
+
+/// Parse a `(define (domain ...) ...)` definition.
+pub fn parse_domain(text: &str) -> Result<Domain, PddlError> {
+    let expr = read(text)?;
+    let (name, sections) = definition(&expr, "domain")?;
+    let mut domain = Domain {
+        name,
+        ..Domain::default()
+    };
+    for section in sections {
+        let items = section.list("a section")?;
+        let (key, body) = items
+            .split_first()
+            .ok_or_else(|| expected_error("a section", Some(section)))?;
+        let key = key.symbol("a section name")?.to_ascii_lowercase();
+        match key.as_str() {
+            ":requirements" | ":predicates" => {}
+            ":types" => domain.types.extend(typed_list(body)?),
+            ":constants" => domain.constants.extend(typed_list(body)?),
+            ":action" => domain.actions.push(action(body)?),
+            _ => return Err(PddlError::UnsupportedSection(key)),
+        }
+    }
+    Ok(domain)
+}
+
+/// Parse a `(define (problem ...) ...)` definition.
+pub fn parse_problem(text: &str) -> Result<PlanningProblem, PddlError> {
+    let expr = read(text)?;
+    let (name, sections) = definition(&expr, "problem")?;
+    let mut problem = PlanningProblem {
+        name,
+        ..PlanningProblem::default()
+    };
+    for section in sections {
+        let items = section.list("a section")?;
+        let (key, body) = items
+            .split_first()
+            .ok_or_else(|| expected_error("a section", Some(section)))?;
+        let key = key.symbol("a section name")?.to_ascii_lowercase();
+        match (key.as_str(), body) {
+            (":domain", _) => {}
+            (":objects", _) => problem.objects.extend(typed_list(body)?),
+            (":init", _) => {
+                for fact in body {
+                    problem.init.insert(ground(&atom(fact)?, fact)?);
+                }
+            }
+            (":goal", [goal]) => problem.goal = conjunction(goal)?,
+            _ => return Err(PddlError::UnsupportedSection(key)),
+        }
+    }
+    Ok(problem)
+}
+
+fn ground(atom: &Atom, expr: &SExpr) -> Result<Fact, PddlError> {
+    let args = atom
+        .terms
+        .iter()
+        .map(|term| match term {
+            Term::Constant(name) => Ok(name.clone()),
+            Term::Variable(_) => Err(expected_error("a ground atom", Some(expr))),
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Fact {
+        predicate: atom.predicate.clone(),
+        args,
+    })
+}
+
+/// Set of facts in an `:init` section, for building problems by hand.
+pub fn parse_facts(text: &str) -> Result<BTreeSet<Fact>, PddlError> {
+    let expr = read(&format!("({})", text))?;
+    expr.list("facts")?
+        .iter()
+        .map(|fact| ground(&atom(fact)?, fact))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::examples::{AIR_CARGO_DOMAIN, AIR_CARGO_PROBLEM, SPARE_TIRE_DOMAIN};
+
+    #[test]
+    fn test_read() {
+        let expr = read("(a (b c) ; comment\n d)").unwrap();
+        assert_eq!(expr.describe(), "(a (b c) d)");
+        assert_eq!(read("(a b"), Err(PddlError::UnexpectedEnd));
+        assert_eq!(read("(a))"), Err(PddlError::UnexpectedClose(3)));
+        assert_eq!(read("(a) b"), Err(PddlError::TrailingInput(4)));
+    }
+
+    #[test]
+    fn test_typed_list() {
+        let expr = read("(a b - t c - u d)").unwrap();
+        let types: HashMap<String, String> = typed_list(expr.list("types").unwrap())
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(types["a"], "t");
+        assert_eq!(types["b"], "t");
+        assert_eq!(types["c"], "u");
+        assert_eq!(types["d"], OBJECT_TYPE);
+    }
+
+    #[test]
+    fn test_parse_facts() {
+        let facts = parse_facts("(At C1 SFO) (Plane P1)").unwrap();
+        assert!(facts.contains(&Fact::new("At", &["C1", "SFO"])));
+        assert!(facts.contains(&Fact::new("Plane", &["P1"])));
+        assert!(parse_facts("(At ?c SFO)").is_err());
+    }
+
+    #[test]
+    fn test_parse_air_cargo_domain() {
+        let domain = parse_domain(AIR_CARGO_DOMAIN).unwrap();
+        assert_eq!(domain.name, "air-cargo");
+        assert_eq!(domain.actions.len(), 3);
+        let fly = &domain.actions[2];
+        assert_eq!(fly.name, "Fly");
+        assert_eq!(
+            fly.parameters,
+            vec![
+                Parameter {
+                    name: "p".to_string(),
+                    type_name: "plane".to_string()
+                },
+                Parameter {
+                    name: "from".to_string(),
+                    type_name: "airport".to_string()
+                },
+                Parameter {
+                    name: "to".to_string(),
+                    type_name: "airport".to_string()
+                },
+            ]
+        );
+        let effect: Vec<String> = fly.effect.iter().map(Literal::to_string).collect();
+        assert_eq!(effect, vec!["¬At(?p, ?from)", "At(?p, ?to)"]);
+    }
+
+    #[test]
+    fn test_parse_air_cargo_problem() {
+        let problem = parse_problem(AIR_CARGO_PROBLEM).unwrap();
+        assert_eq!(problem.objects.len(), 6);
+        assert!(problem.init.contains(&Fact::new("At", &["C1", "SFO"])));
+        let goal: Vec<String> = problem.goal.iter().map(Literal::to_string).collect();
+        assert_eq!(goal, vec!["At(C1, JFK)", "At(C2, SFO)"]);
+    }
+
+    #[test]
+    fn test_parse_spare_tire_domain() {
+        let domain = parse_domain(SPARE_TIRE_DOMAIN).unwrap();
+        assert_eq!(domain.constants.len(), 5);
+        let put_on = &domain.actions[1];
+        let precondition: Vec<String> =
+            put_on.precondition.iter().map(Literal::to_string).collect();
+        assert_eq!(
+            precondition,
+            vec![
+                "Tire(?t)",
+                "At(?t, Ground)",
+                "¬At(Flat, Axle)",
+                "¬At(Spare, Axle)"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            parse_domain("(define (problem p))"),
+            Err(PddlError::Expected { .. })
+        ));
+        assert_eq!(
+            parse_domain("(define (domain d) (:functions))"),
+            Err(PddlError::UnsupportedSection(":functions".to_string()))
+        );
+        assert!(matches!(
+            parse_domain("(define (domain d) (:action A :parameters (x)))"),
+            Err(PddlError::Expected {
+                expected: "a variable",
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,252 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Forward state-space planning, section 11.2.1, with the domain-independent heuristics of
+// section 11.3.
+
+use std::collections::BTreeSet;
+
+use search::informed::{astar_search, HeuristicProblem};
+use search::{Cost, Problem, SearchResult};
+
+use crate::grounding::{ground, GroundAction, GroundTask};
+use crate::{Domain, Fact, PlanningError, PlanningProblem};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PlanningHeuristic {
+    /// h = 0, so A* becomes uniform-cost search.
+    Blind,
+
+    /// Ignore every precondition, so each goal literal that does not hold can be achieved by one
+    /// action: h is the number of unsatisfied goal literals. Admissible as long as no action
+    /// achieves two goal literals at once.
+    IgnorePreconditions,
+
+    /// Ignore delete lists and negative preconditions, so facts only ever become true: h is the
+    /// number of rounds of applying every applicable action until all goals hold. Admissible,
+    /// because each round is at least one action of any real plan.
+    #[default]
+    IgnoreDeleteLists,
+}
+
+/// A ground planning task viewed as a search problem whose states are sets of facts.
+#[derive(Debug, Clone)]
+pub struct ForwardPlanner {
+    task: GroundTask,
+    heuristic: PlanningHeuristic,
+}
+
+impl ForwardPlanner {
+    pub fn new(task: GroundTask, heuristic: PlanningHeuristic) -> Self {
+        Self { task, heuristic }
+    }
+
+    pub fn task(&self) -> &GroundTask {
+        &self.task
+    }
+
+    fn unsatisfied_goals(&self, state: &BTreeSet<Fact>) -> usize {
+        let missing = self
+            .task
+            .positive_goals
+            .iter()
+            .filter(|fact| !state.contains(fact))
+            .count();
+        let present = self
+            .task
+            .negative_goals
+            .iter()
+            .filter(|fact| state.contains(fact))
+            .count();
+        missing + present
+    }
+
+    fn relaxed_rounds(&self, state: &BTreeSet<Fact>) -> Cost {
+        let mut reached = state.clone();
+        let mut rounds = 0;
+        while !self
+            .task
+            .positive_goals
+            .iter()
+            .all(|fact| reached.contains(fact))
+        {
+            let added: Vec<&Fact> = self
+                .task
+                .actions
+                .iter()
+                .filter(|action| {
+                    action
+                        .positive_preconditions
+                        .iter()
+                        .all(|fact| reached.contains(fact))
+                })
+                .flat_map(|action| action.add_list.iter())
+                .filter(|fact| !reached.contains(*fact))
+                .collect();
+            if added.is_empty() {
+                return Cost::INFINITY;
+            }
+            reached.extend(added.into_iter().cloned());
+            rounds += 1;
+        }
+        // Negative goals are ignored by the relaxation, but still need at least one action.
+        if rounds == 0 && !self.task.is_goal(state) {
+            rounds = 1;
+        }
+        rounds as Cost
+    }
+}
+
+impl Problem for ForwardPlanner {
+    type State = BTreeSet<Fact>;
+    type Action = GroundAction;
+
+    fn initial_state(&self) -> Self::State {
+        self.task.init.clone()
+    }
+
+    fn actions(&self, state: &Self::State) -> Vec<Self::Action> {
+        self.task
+            .actions
+            .iter()
+            .filter(|action| action.is_applicable(state))
+            .cloned()
+            .collect()
+    }
+
+    fn result(&self, state: &Self::State, action: &Self::Action) -> Self::State {
+        action.apply(state)
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.task.is_goal(state)
+    }
+}
+
+impl HeuristicProblem for ForwardPlanner {
+    fn heuristic(&self, state: &Self::State) -> Cost {
+        match self.heuristic {
+            PlanningHeuristic::Blind => 0.0,
+            PlanningHeuristic::IgnorePreconditions => self.unsatisfied_goals(state) as Cost,
+            PlanningHeuristic::IgnoreDeleteLists => self.relaxed_rounds(state),
+        }
+    }
+}
+
+/// Ground the problem and find a shortest plan with A* search.
+pub fn plan(
+    domain: &Domain,
+    problem: &PlanningProblem,
+    heuristic: PlanningHeuristic,
+) -> Result<SearchResult<BTreeSet<Fact>, GroundAction>, PlanningError> {
+    let planner = ForwardPlanner::new(ground(domain, problem)?, heuristic);
+    Ok(astar_search(&planner))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examples::{
+        AIR_CARGO_DOMAIN, AIR_CARGO_PROBLEM, SPARE_TIRE_DOMAIN, SPARE_TIRE_PROBLEM,
+    };
+    use crate::pddl::{parse_domain, parse_facts, parse_problem};
+
+    const HEURISTICS: [PlanningHeuristic; 3] = [
+        PlanningHeuristic::Blind,
+        PlanningHeuristic::IgnorePreconditions,
+        PlanningHeuristic::IgnoreDeleteLists,
+    ];
+
+    fn plan_names(domain: &str, problem: &str, heuristic: PlanningHeuristic) -> Vec<String> {
+        let result = plan(
+            &parse_domain(domain).unwrap(),
+            &parse_problem(problem).unwrap(),
+            heuristic,
+        )
+        .unwrap();
+        let solution = result.solution().expect("a plan");
+        solution.actions.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_air_cargo() {
+        let domain = parse_domain(AIR_CARGO_DOMAIN).unwrap();
+        let problem = parse_problem(AIR_CARGO_PROBLEM).unwrap();
+        for heuristic in HEURISTICS {
+            let result = plan(&domain, &problem, heuristic).unwrap();
+            let solution = result.solution().expect("a plan");
+            // Each cargo needs a Load, a Fly and an Unload.
+            assert_eq!(solution.len(), 6, "{:?}", heuristic);
+            let last = solution.states.last().unwrap();
+            assert!(last.contains(&Fact::new("At", &["C1", "JFK"])));
+            assert!(last.contains(&Fact::new("At", &["C2", "SFO"])));
+        }
+    }
+
+    #[test]
+    fn test_spare_tire() {
+        for heuristic in HEURISTICS {
+            let plan = plan_names(SPARE_TIRE_DOMAIN, SPARE_TIRE_PROBLEM, heuristic);
+            assert_eq!(plan.len(), 3, "{:?}: {:?}", heuristic, plan);
+            assert_eq!(plan[2], "PutOn(Spare)");
+            assert!(plan.contains(&"Remove(Flat, Axle)".to_string()));
+            assert!(plan.contains(&"Remove(Spare, Trunk)".to_string()));
+        }
+    }
+
+    #[test]
+    fn test_heuristics_guide_search() {
+        let domain = parse_domain(AIR_CARGO_DOMAIN).unwrap();
+        let problem = parse_problem(AIR_CARGO_PROBLEM).unwrap();
+        let expanded = |heuristic| {
+            plan(&domain, &problem, heuristic)
+                .unwrap()
+                .stats
+                .nodes_expanded
+        };
+        let blind = expanded(PlanningHeuristic::Blind);
+        assert!(expanded(PlanningHeuristic::IgnorePreconditions) < blind);
+        assert!(expanded(PlanningHeuristic::IgnoreDeleteLists) < blind);
+    }
+
+    #[test]
+    fn test_heuristic_values() {
+        let domain = parse_domain(AIR_CARGO_DOMAIN).unwrap();
+        let problem = parse_problem(AIR_CARGO_PROBLEM).unwrap();
+        let task = ground(&domain, &problem).unwrap();
+        let init = task.init.clone();
+        let h = |heuristic, state: &BTreeSet<Fact>| {
+            ForwardPlanner::new(task.clone(), heuristic).heuristic(state)
+        };
+        assert_eq!(h(PlanningHeuristic::IgnorePreconditions, &init), 2.0);
+        // Load and Fly in the first round, then Unload in the second.
+        assert_eq!(h(PlanningHeuristic::IgnoreDeleteLists, &init), 2.0);
+
+        let goal = parse_facts("(At C1 JFK) (At C2 SFO) (At P1 JFK) (At P2 SFO)").unwrap();
+        for heuristic in HEURISTICS {
+            assert_eq!(h(heuristic, &goal), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_unsolvable() {
+        let domain = parse_domain(SPARE_TIRE_DOMAIN).unwrap();
+        let mut problem = parse_problem(SPARE_TIRE_PROBLEM).unwrap();
+        problem.init.remove(&Fact::new("Tire", &["Spare"]));
+        let result = plan(&domain, &problem, PlanningHeuristic::IgnoreDeleteLists).unwrap();
+        assert!(result.solution().is_none());
+    }
+}
@@ -0,0 +1,384 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Multi-armed bandits: the simplest setting where exploration has to be traded off against
+// exploitation.
+//
+// See:
+// -  Chapter 17: Making Complex Decisions, section 17.3
+
+use monte_carlo_tree_search::ucb1;
+use rand::distributions::Distribution;
+use rand::{Rng, SeedableRng};
+use rand_distr::{Beta, Normal};
+use vacuum_cleaner::Environment;
+
+/// The reward distribution of one arm.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arm {
+    /// Reward 1 with probability p, otherwise 0.
+    Bernoulli(f64),
+    Gaussian {
+        mean: f64,
+        std_dev: f64,
+    },
+}
+
+impl Arm {
+    pub fn mean(&self) -> f64 {
+        match *self {
+            Arm::Bernoulli(p) => p,
+            Arm::Gaussian { mean, .. } => mean,
+        }
+    }
+
+    pub fn sample<_Rng: Rng>(&self, rng: &mut _Rng) -> f64 {
+        match *self {
+            Arm::Bernoulli(p) => {
+                if rng.gen_bool(p) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Arm::Gaussian { mean, std_dev } => Normal::new(mean, std_dev)
+                .expect("standard deviation is finite and not negative")
+                .sample(rng),
+        }
+    }
+}
+
+/// A simulated bandit. The action is the index of the arm to pull and the score is the reward
+/// of the last pull. There is nothing to perceive.
+#[derive(Debug, Clone)]
+pub struct Bandit {
+    arms: Vec<Arm>,
+    last_reward: f64,
+    rng: rand_pcg::Pcg64,
+}
+
+impl Bandit {
+    pub fn new(arms: Vec<Arm>, seed: u64) -> Self {
+        Self {
+            arms,
+            last_reward: 0.0,
+            rng: rand_pcg::Pcg64::seed_from_u64(seed),
+        }
+    }
+
+    /// Bernoulli arms with the given success probabilities.
+    pub fn bernoulli(probabilities: &[f64], seed: u64) -> Self {
+        Self::new(
+            probabilities.iter().map(|&p| Arm::Bernoulli(p)).collect(),
+            seed,
+        )
+    }
+
+    pub fn arms(&self) -> &[Arm] {
+        &self.arms
+    }
+
+    pub fn len(&self) -> usize {
+        self.arms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.arms.is_empty()
+    }
+
+    pub fn best_mean(&self) -> f64 {
+        self.arms
+            .iter()
+            .map(Arm::mean)
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    pub fn pull(&mut self, arm: usize) -> f64 {
+        self.execute_action(&arm);
+        self.last_reward
+    }
+}
+
+impl Environment for Bandit {
+    type Action = usize;
+    type Percept = ();
+    type Score = f64;
+
+    fn percept(&self) {}
+
+    fn execute_action(&mut self, action: &usize) {
+        self.last_reward = self.arms[*action].sample(&mut self.rng);
+    }
+
+    fn score(&self) -> f64 {
+        self.last_reward
+    }
+}
+
+/// What a policy has seen of each arm so far.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ArmStats {
+    pub pulls: Vec<usize>,
+    pub total_rewards: Vec<f64>,
+}
+
+impl ArmStats {
+    pub fn new(arms: usize) -> Self {
+        Self {
+            pulls: vec![0; arms],
+            total_rewards: vec![0.0; arms],
+        }
+    }
+
+    pub fn record(&mut self, arm: usize, reward: f64) {
+        self.pulls[arm] += 1;
+        self.total_rewards[arm] += reward;
+    }
+
+    /// Average reward of an arm, or 0 if it has not been pulled.
+    pub fn mean(&self, arm: usize) -> f64 {
+        if self.pulls[arm] == 0 {
+            0.0
+        } else {
+            self.total_rewards[arm] / self.pulls[arm] as f64
+        }
+    }
+
+    pub fn total_pulls(&self) -> usize {
+        self.pulls.iter().sum()
+    }
+}
+
+/// The arm with the highest score, with ties going to the lowest index.
+fn argmax(scores: impl Iterator<Item = f64>) -> usize {
+    let mut best = (0, f64::NEG_INFINITY);
+    for (arm, score) in scores.enumerate() {
+        if score > best.1 {
+            best = (arm, score);
+        }
+    }
+    best.0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BanditPolicy {
+    /// Pull a random arm with probability ε, otherwise the arm with the best average reward.
+    EpsilonGreedy(f64),
+
+    /// Pull the arm with the highest upper confidence bound, equation (17.13), using the same
+    /// UCB1 formula as Monte Carlo tree search.
+    Ucb1 { exploration_constant: f64 },
+
+    /// Thompson sampling for rewards in [0, 1]: sample a success probability for each arm from
+    /// its Beta(1 + total reward, 1 + pulls − total reward) posterior and pull the best.
+    ThompsonBeta,
+
+    /// Thompson sampling for Gaussian rewards with unit variance: sample each arm's mean from
+    /// N(average reward, 1 / (pulls + 1)) and pull the best.
+    ThompsonGaussian,
+}
+
+impl BanditPolicy {
+    pub fn name(&self) -> String {
+        match self {
+            BanditPolicy::EpsilonGreedy(epsilon) => format!("ε-greedy (ε = {})", epsilon),
+            BanditPolicy::Ucb1 {
+                exploration_constant,
+            } => format!("UCB1 (c = {:.2})", exploration_constant),
+            BanditPolicy::ThompsonBeta => "Thompson (Beta)".to_string(),
+            BanditPolicy::ThompsonGaussian => "Thompson (Gaussian)".to_string(),
+        }
+    }
+
+    /// The arm to pull next.
+    pub fn select<_Rng: Rng>(&self, stats: &ArmStats, rng: &mut _Rng) -> usize {
+        let arms = stats.pulls.len();
+        match *self {
+            BanditPolicy::EpsilonGreedy(epsilon) => {
+                if rng.gen_bool(epsilon) {
+                    rng.gen_range(0..arms)
+                } else {
+                    argmax((0..arms).map(|arm| stats.mean(arm)))
+                }
+            }
+            BanditPolicy::Ucb1 {
+                exploration_constant,
+            } => {
+                let total = stats.total_pulls() as i32;
+                argmax((0..arms).map(|arm| {
+                    ucb1(
+                        stats.mean(arm),
+                        stats.pulls[arm] as i32,
+                        total,
+                        exploration_constant,
+                    )
+                }))
+            }
+            BanditPolicy::ThompsonBeta => argmax((0..arms).map(|arm| {
+                let successes = stats.total_rewards[arm].clamp(0.0, stats.pulls[arm] as f64);
+                let failures = stats.pulls[arm] as f64 - successes;
+                Beta::new(1.0 + successes, 1.0 + failures)
+                    .expect("parameters are positive")
+                    .sample(rng)
+            })),
+            BanditPolicy::ThompsonGaussian => argmax((0..arms).map(|arm| {
+                let std_dev = (1.0 / (stats.pulls[arm] as f64 + 1.0)).sqrt();
+                Normal::new(stats.mean(arm), std_dev)
+                    .expect("standard deviation is positive")
+                    .sample(rng)
+            })),
+        }
+    }
+}
+
+/// The result of running a policy on a bandit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BanditRun {
+    pub stats: ArmStats,
+    pub total_reward: f64,
+
+    /// Regret after each step: the expected reward lost by not always pulling the best arm.
+    pub cumulative_regret: Vec<f64>,
+}
+
+impl BanditRun {
+    pub fn regret(&self) -> f64 {
+        self.cumulative_regret.last().copied().unwrap_or(0.0)
+    }
+}
+
+/// Pull arms of bandit chosen by policy, steps times.
+pub fn run_bandit(
+    bandit: &mut Bandit,
+    policy: &BanditPolicy,
+    steps: usize,
+    seed: u64,
+) -> BanditRun {
+    let mut rng = rand_pcg::Pcg64::seed_from_u64(seed);
+    let best_mean = bandit.best_mean();
+    let mut stats = ArmStats::new(bandit.len());
+    let mut total_reward = 0.0;
+    let mut regret = 0.0;
+    let mut cumulative_regret = Vec::with_capacity(steps);
+    for _ in 0..steps {
+        let arm = policy.select(&stats, &mut rng);
+        let reward = bandit.pull(arm);
+        stats.record(arm, reward);
+        total_reward += reward;
+        regret += best_mean - bandit.arms()[arm].mean();
+        cumulative_regret.push(regret);
+    }
+    BanditRun {
+        stats,
+        total_reward,
+        cumulative_regret,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROBABILITIES: [f64; 4] = [0.1, 0.3, 0.5, 0.6];
+
+    fn regret(policy: BanditPolicy) -> f64 {
+        let mut bandit = Bandit::bernoulli(&PROBABILITIES, 0);
+        run_bandit(&mut bandit, &policy, 20_000, 0).regret()
+    }
+
+    #[test]
+    fn test_arms() {
+        let mut rng = rand_pcg::Pcg64::seed_from_u64(0);
+        let arm = Arm::Bernoulli(0.3);
+        let total: f64 = (0..10_000).map(|_| arm.sample(&mut rng)).sum();
+        assert!((total / 10_000.0 - 0.3).abs() < 0.02);
+
+        let arm = Arm::Gaussian {
+            mean: 2.0,
+            std_dev: 1.0,
+        };
+        let total: f64 = (0..10_000).map(|_| arm.sample(&mut rng)).sum();
+        assert!((total / 10_000.0 - 2.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_ucb1_tries_every_arm_first() {
+        let policy = BanditPolicy::Ucb1 {
+            exploration_constant: 2f64.sqrt(),
+        };
+        let mut bandit = Bandit::bernoulli(&PROBABILITIES, 0);
+        let run = run_bandit(&mut bandit, &policy, 4, 0);
+        assert_eq!(run.stats.pulls, vec![1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn test_optimism_beats_epsilon_greedy() {
+        // ε-greedy never stops exploring, so its regret grows linearly. UCB1 explores more at
+        // first, so it takes a long horizon to pull ahead.
+        let epsilon_greedy = regret(BanditPolicy::EpsilonGreedy(0.1));
+        let ucb = regret(BanditPolicy::Ucb1 {
+            exploration_constant: 2f64.sqrt(),
+        });
+        let thompson = regret(BanditPolicy::ThompsonBeta);
+        assert!(ucb < epsilon_greedy, "{} vs {}", ucb, epsilon_greedy);
+        assert!(thompson < ucb, "{} vs {}", thompson, ucb);
+    }
+
+    #[test]
+    fn test_regret_grows_sublinearly() {
+        let mut bandit = Bandit::bernoulli(&PROBABILITIES, 1);
+        let run = run_bandit(&mut bandit, &BanditPolicy::ThompsonBeta, 10_000, 1);
+        let first_half = run.cumulative_regret[4_999];
+        let second_half = run.regret() - first_half;
+        assert!(
+            second_half < first_half / 2.0,
+            "{} {}",
+            first_half,
+            second_half
+        );
+        assert!(run.stats.pulls[3] > 9_000);
+    }
+
+    #[test]
+    fn test_gaussian_bandit() {
+        let arms = vec![
+            Arm::Gaussian {
+                mean: 0.0,
+                std_dev: 1.0,
+            },
+            Arm::Gaussian {
+                mean: 1.0,
+                std_dev: 1.0,
+            },
+        ];
+        for policy in [
+            BanditPolicy::EpsilonGreedy(0.1),
+            BanditPolicy::Ucb1 {
+                exploration_constant: 2.0,
+            },
+            BanditPolicy::ThompsonGaussian,
+        ] {
+            let mut bandit = Bandit::new(arms.clone(), 2);
+            let run = run_bandit(&mut bandit, &policy, 2_000, 2);
+            assert!(
+                run.stats.pulls[1] > 1_700,
+                "{}: {:?}",
+                policy.name(),
+                run.stats
+            );
+        }
+    }
+}
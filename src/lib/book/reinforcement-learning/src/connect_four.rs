@@ -0,0 +1,288 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// A toy Connect Four learner that uses afterstates: the position right after the agent's own
+// move, before the opponent replies. Many state-action pairs lead to the same afterstate, so
+// learning one value per afterstate generalizes better than learning Q-values.
+//
+// See:
+// -  Sutton and Barto, Reinforcement Learning: An Introduction, section 6.8
+
+use std::collections::HashMap;
+
+use connect_four_logic::{
+    get_legal_moves, is_terminal_position, Board, Move, MoveType, Player, TerminalPosition,
+};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use vacuum_cleaner::Environment;
+
+use crate::{EpisodeStats, EpisodicEnvironment};
+
+/// The board after player makes move, which must be legal.
+pub fn afterstate(board: &Board, player: Player, action: &Move) -> Board {
+    let mut board = board.clone();
+    match action.move_type {
+        MoveType::Insert => board.insert(action.column, player),
+        MoveType::Pop => board.pop(action.column, player),
+    }
+    .expect("legal move");
+    board
+}
+
+/// Reward for Player 1 in a position.
+fn reward(position: TerminalPosition) -> f64 {
+    match position {
+        TerminalPosition::IsTerminalWin(Player::Player1) => 1.0,
+        TerminalPosition::IsTerminalWin(Player::Player2) => -1.0,
+        TerminalPosition::IsTerminalDraw | TerminalPosition::IsNotTerminal => 0.0,
+    }
+}
+
+/// Connect Four against an opponent that plays uniformly random legal moves. The agent is Player
+/// 1 and moves first. The reward is 1 for a win, -1 for a loss and 0 otherwise.
+#[derive(Debug, Clone)]
+pub struct ConnectFourEnvironment {
+    board: Board,
+    last_reward: f64,
+    rng: rand_pcg::Pcg64,
+}
+
+impl ConnectFourEnvironment {
+    pub fn new(width: usize, height: usize, seed: u64) -> Self {
+        Self {
+            board: Board::new(width, height),
+            last_reward: 0.0,
+            rng: rand_pcg::Pcg64::seed_from_u64(seed),
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+}
+
+impl Environment for ConnectFourEnvironment {
+    type Action = Move;
+    type Percept = Board;
+    type Score = f64;
+
+    fn percept(&self) -> Board {
+        self.board.clone()
+    }
+
+    fn execute_action(&mut self, action: &Move) {
+        if self.is_terminal() {
+            self.last_reward = 0.0;
+            return;
+        }
+        self.board = afterstate(&self.board, Player::Player1, action);
+        if !self.is_terminal() {
+            let replies = get_legal_moves(&self.board, Player::Player2);
+            if let Some(reply) = replies.choose(&mut self.rng) {
+                self.board = afterstate(&self.board, Player::Player2, reply);
+            }
+        }
+        self.last_reward = reward(is_terminal_position(&self.board));
+    }
+
+    fn score(&self) -> f64 {
+        self.last_reward
+    }
+}
+
+impl EpisodicEnvironment for ConnectFourEnvironment {
+    fn reset(&mut self) {
+        self.board = Board::new(self.board.width, self.board.height);
+        self.last_reward = 0.0;
+    }
+
+    fn actions(&self) -> Vec<Move> {
+        if self.is_terminal() {
+            vec![]
+        } else {
+            get_legal_moves(&self.board, Player::Player1)
+        }
+    }
+
+    fn is_terminal(&self) -> bool {
+        is_terminal_position(&self.board) != TerminalPosition::IsNotTerminal
+    }
+}
+
+/// Learns V(afterstate) with TD(0) and plays ε-greedily with respect to it. Terminal afterstates
+/// are valued by their reward; other unseen afterstates are worth 0.
+#[derive(Debug, Clone)]
+pub struct AfterstateAgent {
+    values: HashMap<Board, f64>,
+    alpha: f64,
+    epsilon: f64,
+    rng: rand_pcg::Pcg64,
+}
+
+impl AfterstateAgent {
+    pub fn new(alpha: f64, epsilon: f64, seed: u64) -> Self {
+        Self {
+            values: HashMap::new(),
+            alpha,
+            epsilon,
+            rng: rand_pcg::Pcg64::seed_from_u64(seed),
+        }
+    }
+
+    pub fn set_epsilon(&mut self, epsilon: f64) {
+        self.epsilon = epsilon;
+    }
+
+    /// Number of afterstates with a learned value.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn value(&self, afterstate: &Board) -> f64 {
+        match is_terminal_position(afterstate) {
+            TerminalPosition::IsNotTerminal => self.values.get(afterstate).copied().unwrap_or(0.0),
+            position => reward(position),
+        }
+    }
+
+    /// A random move with probability ε, otherwise the move whose afterstate is worth the most.
+    pub fn choose_action(&mut self, board: &Board, actions: &[Move]) -> Option<Move> {
+        if self.rng.gen_bool(self.epsilon) {
+            return actions.choose(&mut self.rng).copied();
+        }
+        let mut best: Option<(Move, f64)> = None;
+        for action in actions {
+            let value = self.value(&afterstate(board, Player::Player1, action));
+            if best.is_none_or(|(_, best_value)| value > best_value) {
+                best = Some((*action, value));
+            }
+        }
+        best.map(|(action, _)| action)
+    }
+
+    fn update(&mut self, afterstate: &Board, target: f64) {
+        if is_terminal_position(afterstate) != TerminalPosition::IsNotTerminal {
+            return;
+        }
+        let value = self.values.entry(afterstate.clone()).or_insert(0.0);
+        *value += self.alpha * (target - *value);
+    }
+
+    /// Play one game, moving the value of each afterstate towards the reward plus the value of
+    /// the next afterstate.
+    pub fn run_episode(
+        &mut self,
+        environment: &mut ConnectFourEnvironment,
+        max_steps: usize,
+    ) -> EpisodeStats {
+        environment.reset();
+        let mut stats = EpisodeStats::default();
+        let mut previous: Option<Board> = None;
+        while stats.steps < max_steps {
+            let board = environment.percept();
+            let Some(action) = self.choose_action(&board, &environment.actions()) else {
+                break;
+            };
+            let current = afterstate(&board, Player::Player1, &action);
+            if let Some(previous) = &previous {
+                let target = self.value(&current);
+                self.update(previous, target);
+            }
+            environment.execute_action(&action);
+            stats.steps += 1;
+            stats.total_reward += environment.score();
+            if environment.is_terminal() {
+                // The opponent's reply ended the game, so the afterstate led straight to the
+                // final reward.
+                self.update(&current, environment.score());
+            }
+            previous = Some(current);
+        }
+        stats.finished = environment.is_terminal();
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn win_rate(agent: &mut AfterstateAgent, environment: &mut ConnectFourEnvironment) -> f64 {
+        let games = 500;
+        let wins = (0..games)
+            .filter(|_| agent.run_episode(environment, 100).total_reward > 0.0)
+            .count();
+        wins as f64 / games as f64
+    }
+
+    #[test]
+    fn test_environment() {
+        let mut environment = ConnectFourEnvironment::new(5, 4, 0);
+        assert_eq!(environment.actions().len(), 5);
+        let action = environment.actions()[2];
+        environment.execute_action(&action);
+        assert_eq!(environment.board().count_pieces(Player::Player1), 1);
+        assert_eq!(environment.board().count_pieces(Player::Player2), 1);
+        assert_eq!(environment.score(), 0.0);
+        environment.reset();
+        assert_eq!(environment.board().count_pieces(Player::Player1), 0);
+    }
+
+    #[test]
+    fn test_terminal_afterstates_are_worth_their_reward() {
+        let agent = AfterstateAgent::new(0.1, 0.1, 0);
+        let mut board = Board::new(5, 4);
+        for _ in 0..3 {
+            board.insert(0, Player::Player1).unwrap();
+        }
+        let win = afterstate(
+            &board,
+            Player::Player1,
+            &Move {
+                move_type: MoveType::Insert,
+                column: 0,
+            },
+        );
+        assert_eq!(agent.value(&win), 1.0);
+        assert_eq!(agent.value(&board), 0.0);
+    }
+
+    #[test]
+    fn test_learning_beats_random_play() {
+        let mut environment = ConnectFourEnvironment::new(5, 4, 0);
+        let mut random = AfterstateAgent::new(0.0, 1.0, 0);
+        let random_rate = win_rate(&mut random, &mut environment);
+
+        let mut agent = AfterstateAgent::new(0.2, 0.1, 0);
+        for _ in 0..3_000 {
+            agent.run_episode(&mut environment, 100);
+        }
+        agent.set_epsilon(0.0);
+        let learned_rate = win_rate(&mut agent, &mut environment);
+        assert!(
+            learned_rate > random_rate + 0.2,
+            "{} vs {}",
+            learned_rate,
+            random_rate
+        );
+        assert!(!agent.is_empty());
+    }
+}
@@ -0,0 +1,157 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// The 4x3 grid world of Figure 17.1 as an environment to learn in, without access to its
+// transition model.
+
+use mdp::grid_world::{Cell, Direction, GridWorld};
+use mdp::Mdp;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::SeedableRng;
+use vacuum_cleaner::Environment;
+
+use crate::EpisodicEnvironment;
+
+/// Runs a GridWorld by sampling its transition model. Every episode starts at the world's start
+/// square.
+#[derive(Debug, Clone)]
+pub struct GridWorldEnvironment {
+    world: GridWorld,
+    position: Cell,
+    last_reward: f64,
+    rng: rand_pcg::Pcg64,
+}
+
+impl GridWorldEnvironment {
+    pub fn new(world: GridWorld, seed: u64) -> Self {
+        Self {
+            position: world.start,
+            world,
+            last_reward: 0.0,
+            rng: rand_pcg::Pcg64::seed_from_u64(seed),
+        }
+    }
+
+    pub fn world(&self) -> &GridWorld {
+        &self.world
+    }
+}
+
+impl Environment for GridWorldEnvironment {
+    type Action = Direction;
+    type Percept = Cell;
+    type Score = f64;
+
+    fn percept(&self) -> Cell {
+        self.position
+    }
+
+    fn execute_action(&mut self, action: &Direction) {
+        if self.is_terminal() {
+            self.last_reward = 0.0;
+            return;
+        }
+        let transitions = self.world.transitions(&self.position, action);
+        let index = WeightedIndex::new(transitions.iter().map(|(_, p)| *p))
+            .expect("transition probabilities are positive")
+            .sample(&mut self.rng);
+        let next = transitions[index].0;
+        self.last_reward = self.world.reward(&self.position, action, &next);
+        self.position = next;
+    }
+
+    fn score(&self) -> f64 {
+        self.last_reward
+    }
+}
+
+impl EpisodicEnvironment for GridWorldEnvironment {
+    fn reset(&mut self) {
+        self.position = self.world.start;
+        self.last_reward = 0.0;
+    }
+
+    fn actions(&self) -> Vec<Direction> {
+        self.world.actions(&self.position)
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.world.is_terminal(&self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mdp::extract_policy;
+    use mdp::value_iteration::value_iteration;
+
+    use super::*;
+    use crate::td::{TdAgent, TdConfig};
+
+    #[test]
+    fn test_episode_ends_in_terminal_square() {
+        let mut environment = GridWorldEnvironment::new(GridWorld::four_by_three(), 0);
+        let mut agent = TdAgent::q_learning(TdConfig::default(), 0);
+        let stats = agent.run_episode(&mut environment, 10_000);
+        assert!(stats.finished);
+        assert!(environment.is_terminal());
+        assert!(environment.actions().is_empty());
+        environment.reset();
+        assert_eq!(environment.percept(), (1, 1));
+    }
+
+    #[test]
+    fn test_q_learning_finds_optimal_policy() {
+        let world = GridWorld::four_by_three();
+        let optimal = extract_policy(&world, &value_iteration(&world, 1e-6).utilities);
+        let mut environment = GridWorldEnvironment::new(world, 1);
+        let config = TdConfig {
+            epsilon: 0.2,
+            ..TdConfig::default()
+        };
+        let mut agent = TdAgent::q_learning(config, 1);
+        agent.train(&mut environment, 5_000, 1_000);
+        assert_eq!(agent.greedy_policy(), optimal);
+
+        // Q-values approach the utilities of Figure 17.3.
+        let q = agent.q_value(&(1, 1), &Direction::Up);
+        assert!((q - 0.705).abs() < 0.05, "{}", q);
+    }
+
+    #[test]
+    fn test_sarsa_learns_to_reach_the_goal() {
+        let mut environment = GridWorldEnvironment::new(GridWorld::four_by_three(), 2);
+        let mut agent = TdAgent::sarsa(TdConfig::default(), 2);
+        agent.train(&mut environment, 5_000, 1_000);
+        let policy = agent.greedy_policy();
+        for (cell, direction) in [
+            ((1, 1), Direction::Up),
+            ((1, 2), Direction::Up),
+            ((1, 3), Direction::Right),
+            ((2, 3), Direction::Right),
+            ((3, 3), Direction::Right),
+        ] {
+            assert_eq!(policy[&cell], direction, "{:?}", cell);
+        }
+
+        agent.set_epsilon(0.0);
+        let rewards: f64 = (0..100)
+            .map(|_| agent.run_episode(&mut environment, 1_000).total_reward)
+            .sum();
+        // The optimal policy's expected reward from (1, 1) is 0.705.
+        assert!(rewards / 100.0 > 0.6, "{}", rewards / 100.0);
+    }
+}
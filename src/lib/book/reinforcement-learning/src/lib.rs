@@ -0,0 +1,51 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Reinforcement learning: learning to act from rewards, without a model of the environment.
+//
+// See:
+// -  Chapter 22: Reinforcement Learning, sections 22.2 and 22.3
+
+use vacuum_cleaner::Environment;
+
+pub mod bandit;
+pub mod connect_four;
+pub mod grid_world;
+pub mod td;
+
+/// An Environment that runs in episodes, for agents that learn from rewards. The percept is the
+/// full state of the environment, and score() is the reward for the last action.
+pub trait EpisodicEnvironment: Environment {
+    /// Start a new episode.
+    fn reset(&mut self);
+
+    /// Actions available now. Empty once the episode is over.
+    fn actions(&self) -> Vec<Self::Action>;
+
+    fn is_terminal(&self) -> bool;
+}
+
+/// What happened in one episode.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct EpisodeStats {
+    pub steps: usize,
+
+    /// Undiscounted sum of rewards.
+    pub total_reward: f64,
+
+    /// Whether the episode reached a terminal state rather than running out of steps.
+    pub finished: bool,
+}
@@ -0,0 +1,289 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Temporal-difference control: Q-learning and SARSA, section 22.3.3.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::{EpisodeStats, EpisodicEnvironment};
+
+/// The learning rate α as a function of how many times a state-action pair has been updated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LearningRate {
+    Constant(f64),
+
+    /// α(n) = c / (c − 1 + n), which starts at 1 and decays slowly enough for the Q-values to
+    /// converge. Figure 22.8 uses c = 60.
+    Decaying(f64),
+}
+
+impl LearningRate {
+    /// The learning rate for the update after visits earlier updates.
+    pub fn rate(&self, visits: usize) -> f64 {
+        match *self {
+            LearningRate::Constant(alpha) => alpha,
+            LearningRate::Decaying(c) => c / (c + visits as f64),
+        }
+    }
+}
+
+impl Default for LearningRate {
+    fn default() -> Self {
+        LearningRate::Decaying(60.0)
+    }
+}
+
+/// Which action's Q-value the temporal-difference target uses for the next state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TdAlgorithm {
+    /// Off-policy: the best action in the next state, equation (22.8). Learns the Q-values of the
+    /// optimal policy whatever the exploration.
+    QLearning,
+
+    /// On-policy: the action the agent actually takes next, equation (22.9). Learns the Q-values
+    /// of the exploring policy itself.
+    Sarsa,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TdConfig {
+    pub learning_rate: LearningRate,
+
+    /// γ.
+    pub discount: f64,
+
+    /// Probability of exploring with a random action instead of the greedy one.
+    pub epsilon: f64,
+}
+
+impl Default for TdConfig {
+    fn default() -> Self {
+        Self {
+            learning_rate: LearningRate::default(),
+            discount: 1.0,
+            epsilon: 0.1,
+        }
+    }
+}
+
+/// A tabular temporal-difference agent with ε-greedy exploration. Unseen state-action pairs have
+/// a Q-value of 0.
+#[derive(Debug, Clone)]
+pub struct TdAgent<_State, _Action> {
+    algorithm: TdAlgorithm,
+    config: TdConfig,
+    q_values: HashMap<(_State, _Action), f64>,
+    visits: HashMap<(_State, _Action), usize>,
+    rng: rand_pcg::Pcg64,
+}
+
+impl<_State, _Action> TdAgent<_State, _Action>
+where
+    _State: Clone + Eq + Hash,
+    _Action: Clone + Eq + Hash,
+{
+    pub fn new(algorithm: TdAlgorithm, config: TdConfig, seed: u64) -> Self {
+        Self {
+            algorithm,
+            config,
+            q_values: HashMap::new(),
+            visits: HashMap::new(),
+            rng: rand_pcg::Pcg64::seed_from_u64(seed),
+        }
+    }
+
+    pub fn q_learning(config: TdConfig, seed: u64) -> Self {
+        Self::new(TdAlgorithm::QLearning, config, seed)
+    }
+
+    pub fn sarsa(config: TdConfig, seed: u64) -> Self {
+        Self::new(TdAlgorithm::Sarsa, config, seed)
+    }
+
+    pub fn algorithm(&self) -> TdAlgorithm {
+        self.algorithm
+    }
+
+    pub fn config(&self) -> &TdConfig {
+        &self.config
+    }
+
+    /// Change the exploration rate, e.g. to 0 to evaluate what has been learned.
+    pub fn set_epsilon(&mut self, epsilon: f64) {
+        self.config.epsilon = epsilon;
+    }
+
+    pub fn q_value(&self, state: &_State, action: &_Action) -> f64 {
+        self.q_values
+            .get(&(state.clone(), action.clone()))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// The action with the highest Q-value, with ties going to the earliest action.
+    pub fn greedy_action(&self, state: &_State, actions: &[_Action]) -> Option<_Action> {
+        let mut best: Option<(&_Action, f64)> = None;
+        for action in actions {
+            let value = self.q_value(state, action);
+            if best.is_none_or(|(_, best_value)| value > best_value) {
+                best = Some((action, value));
+            }
+        }
+        best.map(|(action, _)| action.clone())
+    }
+
+    /// A random action with probability ε, otherwise the greedy action.
+    pub fn choose_action(&mut self, state: &_State, actions: &[_Action]) -> Option<_Action> {
+        if self.rng.gen_bool(self.config.epsilon) {
+            actions.choose(&mut self.rng).cloned()
+        } else {
+            self.greedy_action(state, actions)
+        }
+    }
+
+    /// Move Q(state, action) towards target by the learning rate.
+    pub fn update(&mut self, state: &_State, action: &_Action, target: f64) {
+        let key = (state.clone(), action.clone());
+        let visits = self.visits.entry(key.clone()).or_insert(0);
+        let alpha = self.config.learning_rate.rate(*visits);
+        *visits += 1;
+        let q = self.q_values.entry(key).or_insert(0.0);
+        *q += alpha * (target - *q);
+    }
+
+    /// The greedy action in every state where some action has been tried.
+    pub fn greedy_policy(&self) -> HashMap<_State, _Action> {
+        let mut best: HashMap<_State, (_Action, f64)> = HashMap::new();
+        for ((state, action), value) in &self.q_values {
+            match best.get(state) {
+                Some((_, best_value)) if *best_value >= *value => {}
+                _ => {
+                    best.insert(state.clone(), (action.clone(), *value));
+                }
+            }
+        }
+        best.into_iter()
+            .map(|(state, (action, _))| (state, action))
+            .collect()
+    }
+
+    /// Run one episode in environment, learning after every step. Stops after max_steps if the
+    /// episode has not finished by then.
+    pub fn run_episode<_Environment>(
+        &mut self,
+        environment: &mut _Environment,
+        max_steps: usize,
+    ) -> EpisodeStats
+    where
+        _Environment: EpisodicEnvironment<Percept = _State, Action = _Action>,
+        _Environment::Score: Into<f64>,
+    {
+        environment.reset();
+        let mut stats = EpisodeStats::default();
+        let mut state = environment.percept();
+        let mut action = self.choose_action(&state, &environment.actions());
+        while let Some(current) = action {
+            if stats.steps == max_steps {
+                return stats;
+            }
+            environment.execute_action(&current);
+            let reward: f64 = environment.score().into();
+            stats.steps += 1;
+            stats.total_reward += reward;
+
+            let next_state = environment.percept();
+            let next_actions = environment.actions();
+            let next_action = self.choose_action(&next_state, &next_actions);
+            let next_value = match (self.algorithm, &next_action) {
+                (_, None) => 0.0,
+                (TdAlgorithm::QLearning, Some(_)) => {
+                    let best = self.greedy_action(&next_state, &next_actions);
+                    best.map_or(0.0, |best| self.q_value(&next_state, &best))
+                }
+                (TdAlgorithm::Sarsa, Some(next)) => self.q_value(&next_state, next),
+            };
+            self.update(&state, &current, reward + self.config.discount * next_value);
+            state = next_state;
+            action = next_action;
+        }
+        stats.finished = environment.is_terminal();
+        stats
+    }
+
+    /// Run episodes one after the other, returning the statistics of each.
+    pub fn train<_Environment>(
+        &mut self,
+        environment: &mut _Environment,
+        episodes: usize,
+        max_steps: usize,
+    ) -> Vec<EpisodeStats>
+    where
+        _Environment: EpisodicEnvironment<Percept = _State, Action = _Action>,
+        _Environment::Score: Into<f64>,
+    {
+        (0..episodes)
+            .map(|_| self.run_episode(environment, max_steps))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_learning_rate() {
+        assert_eq!(LearningRate::Constant(0.1).rate(100), 0.1);
+        let decaying = LearningRate::Decaying(60.0);
+        assert_eq!(decaying.rate(0), 1.0);
+        assert!((decaying.rate(60) - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_update() {
+        let config = TdConfig {
+            learning_rate: LearningRate::Constant(0.5),
+            ..TdConfig::default()
+        };
+        let mut agent: TdAgent<u8, u8> = TdAgent::q_learning(config, 0);
+        agent.update(&0, &1, 1.0);
+        assert_eq!(agent.q_value(&0, &1), 0.5);
+        agent.update(&0, &1, 1.0);
+        assert_eq!(agent.q_value(&0, &1), 0.75);
+        assert_eq!(agent.greedy_action(&0, &[0, 1, 2]), Some(1));
+        assert_eq!(agent.greedy_policy(), HashMap::from([(0, 1)]));
+    }
+
+    #[test]
+    fn test_epsilon_greedy() {
+        let config = TdConfig {
+            epsilon: 0.5,
+            ..TdConfig::default()
+        };
+        let mut agent: TdAgent<u8, u8> = TdAgent::sarsa(config, 0);
+        agent.update(&0, &2, 1.0);
+        let greedy = (0..1000)
+            .filter(|_| agent.choose_action(&0, &[0, 1, 2, 3]) == Some(2))
+            .count();
+        // 0.5 greedy plus 0.5 × 1/4 random.
+        assert!((575..675).contains(&greedy), "{}", greedy);
+        assert_eq!(agent.choose_action(&0, &[]), None);
+    }
+}
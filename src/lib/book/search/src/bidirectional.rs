@@ -0,0 +1,250 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Bidirectional search
+//
+// See:
+// -  Chapter 3: Solving Problems by Searching, section 3.4.5
+
+use std::collections::HashMap;
+
+use crate::uninformed::expand;
+use crate::{NodeArena, Problem, SearchOutcome, SearchResult, SearchStats, Solution};
+
+/// A problem that can also be searched backwards from its goal, because every action can be
+/// inverted: given a state we can list the states that lead to it, and the actions that get there.
+pub trait InvertibleProblem: Problem {
+    /// The goal state the backward search starts from. is_goal must be true for it.
+    fn goal_state(&self) -> Self::State;
+
+    /// Every (predecessor, action) pair such that doing action in predecessor results in state.
+    fn predecessors(&self, state: &Self::State) -> Vec<(Self::State, Self::Action)>;
+}
+
+/// What the backward search knows about a state it has reached.
+struct BackwardEntry<_State, _Action> {
+    /// The next state on the path to the goal, and the action that leads to it. None for the goal.
+    next: Option<(_State, _Action)>,
+    depth: usize,
+}
+
+/// Bidirectional breadth-first search. Searches forwards from the initial state and backwards from
+/// the goal state at the same time, stopping when the two searches meet in the middle. With
+/// branching factor b and a solution of depth d each search only needs to go to depth d/2, so
+/// this generates O(b^(d/2)) nodes rather than the O(b^d) of breadth-first search.
+///
+/// Each iteration expands a whole layer of whichever frontier is smaller. The searches meet when
+/// a newly generated state has already been reached by the other search, and the shortest of the
+/// paths found in that layer is returned, so like breadth-first search this finds a solution with
+/// the fewest actions.
+pub fn bidirectional_breadth_first_search<_Problem: InvertibleProblem>(
+    problem: &_Problem,
+) -> SearchResult<_Problem::State, _Problem::Action> {
+    let mut stats = SearchStats {
+        nodes_generated: 2,
+        ..SearchStats::default()
+    };
+
+    let mut arena = NodeArena::new();
+    let root = arena.root(problem.initial_state());
+    if problem.is_goal(&arena.get(root).state) {
+        return SearchResult {
+            outcome: SearchOutcome::Found(arena.solution(root)),
+            stats: SearchStats {
+                nodes_generated: 1,
+                ..stats
+            },
+        };
+    }
+    let mut forward_frontier = vec![root];
+    let mut forward_reached = HashMap::from([(arena.get(root).state.clone(), root)]);
+
+    let goal = problem.goal_state();
+    let mut backward_frontier = vec![goal.clone()];
+    let mut backward_reached = HashMap::from([(
+        goal,
+        BackwardEntry {
+            next: None,
+            depth: 0,
+        },
+    )]);
+
+    stats.observe_frontier(2);
+    while !forward_frontier.is_empty() && !backward_frontier.is_empty() {
+        // The state where the best path found so far meets, and that path's length.
+        let mut meeting: Option<(_Problem::State, usize)> = None;
+        let mut consider = |state: &_Problem::State, length: usize| {
+            if meeting.as_ref().is_none_or(|(_, best)| length < *best) {
+                meeting = Some((state.clone(), length));
+            }
+        };
+
+        if forward_frontier.len() <= backward_frontier.len() {
+            let mut next_frontier = vec![];
+            for node in forward_frontier.drain(..) {
+                for child in expand(problem, &mut arena, &mut stats, node) {
+                    let child_node = arena.get(child);
+                    if forward_reached.contains_key(&child_node.state) {
+                        continue;
+                    }
+                    forward_reached.insert(child_node.state.clone(), child);
+                    if let Some(entry) = backward_reached.get(&child_node.state) {
+                        consider(&child_node.state, child_node.depth + entry.depth);
+                    }
+                    next_frontier.push(child);
+                }
+            }
+            forward_frontier = next_frontier;
+        } else {
+            let mut next_frontier = vec![];
+            for state in backward_frontier.drain(..) {
+                stats.nodes_expanded += 1;
+                let depth = backward_reached[&state].depth + 1;
+                let predecessors = problem.predecessors(&state);
+                stats.nodes_generated += predecessors.len();
+                for (predecessor, action) in predecessors {
+                    if backward_reached.contains_key(&predecessor) {
+                        continue;
+                    }
+                    if let Some(&node) = forward_reached.get(&predecessor) {
+                        consider(&predecessor, arena.get(node).depth + depth);
+                    }
+                    backward_reached.insert(
+                        predecessor.clone(),
+                        BackwardEntry {
+                            next: Some((state.clone(), action)),
+                            depth,
+                        },
+                    );
+                    next_frontier.push(predecessor);
+                }
+            }
+            backward_frontier = next_frontier;
+        }
+        stats.observe_frontier(forward_frontier.len() + backward_frontier.len());
+
+        if let Some((state, _)) = meeting {
+            let solution = join_paths(
+                problem,
+                arena.solution(forward_reached[&state]),
+                &backward_reached,
+            );
+            return SearchResult {
+                outcome: SearchOutcome::Found(solution),
+                stats,
+            };
+        }
+    }
+    SearchResult {
+        outcome: SearchOutcome::NotFound,
+        stats,
+    }
+}
+
+/// Extend the forward path, which ends at the meeting state, by following the backward search's
+/// links from the meeting state to the goal.
+fn join_paths<_Problem: InvertibleProblem>(
+    problem: &_Problem,
+    mut solution: Solution<_Problem::State, _Problem::Action>,
+    backward_reached: &HashMap<_Problem::State, BackwardEntry<_Problem::State, _Problem::Action>>,
+) -> Solution<_Problem::State, _Problem::Action> {
+    let mut state = solution.states.last().unwrap().clone();
+    while let Some((next, action)) = &backward_reached[&state].next {
+        solution.path_cost += problem.step_cost(&state, action, next);
+        solution.actions.push(action.clone());
+        solution.states.push(next.clone());
+        state = next.clone();
+    }
+    solution
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_problems::{GridProblem, SmallGraph};
+    use crate::uninformed::breadth_first_search;
+
+    #[test]
+    fn test_finds_fewest_actions() {
+        let problem = SmallGraph::new();
+        let result = bidirectional_breadth_first_search(&problem);
+        let solution = result.solution().unwrap();
+        assert_eq!(solution.states, vec!["S", "G"]);
+        assert_eq!(solution.path_cost, 10.0);
+    }
+
+    #[test]
+    fn test_joins_forward_and_backward_paths() {
+        let problem = GridProblem { goal: (4, 4) };
+        let solution = bidirectional_breadth_first_search(&problem)
+            .solution()
+            .unwrap()
+            .clone();
+        assert_eq!(solution.len(), 8);
+        assert_eq!(solution.path_cost, 8.0);
+
+        // The actions must replay from the initial state through every listed state to the goal.
+        let mut state = problem.initial_state();
+        for (action, expected) in solution.actions.iter().zip(&solution.states[1..]) {
+            state = problem.result(&state, action);
+            assert_eq!(state, *expected);
+        }
+        assert!(problem.is_goal(&state));
+    }
+
+    #[test]
+    fn test_finds_goal_other_than_default() {
+        let problem = SmallGraph {
+            goal: "C",
+            ..SmallGraph::new()
+        };
+        let result = bidirectional_breadth_first_search(&problem);
+        assert_eq!(result.solution().unwrap().states, vec!["S", "A", "C"]);
+    }
+
+    #[test]
+    fn test_unreachable_goal() {
+        let mut problem = SmallGraph::new();
+        problem
+            .edges
+            .get_mut("A")
+            .unwrap()
+            .retain(|(next, _)| *next != "C");
+        problem.goal = "C";
+        let result = bidirectional_breadth_first_search(&problem);
+        assert!(result.solution().is_none());
+        assert_eq!(result.outcome, SearchOutcome::NotFound);
+    }
+
+    // In a grid the nodes within distance d grow with d^2, so two searches to depth d/2 should
+    // expand about half as many nodes as one search to depth d.
+    #[test]
+    fn test_expands_fewer_nodes_than_breadth_first_search() {
+        let problem = GridProblem { goal: (10, 10) };
+        let unidirectional = breadth_first_search(&problem);
+        let bidirectional = bidirectional_breadth_first_search(&problem);
+        assert_eq!(
+            bidirectional.solution().unwrap().len(),
+            unidirectional.solution().unwrap().len()
+        );
+        assert!(
+            bidirectional.stats.nodes_expanded * 3 < unidirectional.stats.nodes_expanded * 2,
+            "bidirectional {:?} vs breadth-first {:?}",
+            bidirectional.stats,
+            unidirectional.stats
+        );
+    }
+}
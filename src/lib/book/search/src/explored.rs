@@ -0,0 +1,256 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Record the search tree that a search algorithm explores, for visualization.
+//
+// See:
+// -  Chapter 3: Solving Problems by Searching, section 3.3 (Figure 3.4)
+
+use std::cell::{Ref, RefCell};
+
+use crate::graph::escape;
+use crate::informed::HeuristicProblem;
+use crate::{Cost, Problem};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExploredNode<_State, _Action> {
+    pub state: _State,
+
+    /// Action that generated this node from its parent. None for the root.
+    pub action: Option<_Action>,
+    pub parent: Option<usize>,
+
+    /// True once the node's successors have been generated. Nodes that were generated but not
+    /// expanded were either still in the frontier when the search ended, or were discarded as
+    /// repeats.
+    pub expanded: bool,
+}
+
+/// The part of a search tree that a search generated, as in Figure 3.4. Nodes refer to their
+/// parent by index, and the root is node 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExploredTree<_State, _Action> {
+    nodes: Vec<ExploredNode<_State, _Action>>,
+
+    /// Node most recently expanded, which is the parent of the nodes generated after it.
+    expanding: Option<usize>,
+}
+
+impl<_State, _Action> Default for ExploredTree<_State, _Action> {
+    fn default() -> Self {
+        Self {
+            nodes: vec![],
+            expanding: None,
+        }
+    }
+}
+
+impl<_State: Eq, _Action> ExploredTree<_State, _Action> {
+    pub fn nodes(&self) -> &[ExploredNode<_State, _Action>] {
+        &self.nodes
+    }
+
+    /// Nodes that were generated but never expanded.
+    pub fn unexpanded(&self) -> impl Iterator<Item = &ExploredNode<_State, _Action>> {
+        self.nodes.iter().filter(|node| !node.expanded)
+    }
+
+    /// The tree in GraphViz DOT format. Expanded nodes are filled, and unexpanded nodes are
+    /// dashed.
+    pub fn to_dot<_Label: Fn(&_State) -> String>(&self, label: _Label) -> String {
+        let mut dot = String::from("digraph {\n");
+        for (i, node) in self.nodes.iter().enumerate() {
+            let style = if node.expanded { "filled" } else { "dashed" };
+            dot.push_str(&format!(
+                "    {} [label=\"{}\", style={}];\n",
+                i,
+                escape(&label(&node.state)),
+                style
+            ));
+        }
+        for (i, node) in self.nodes.iter().enumerate() {
+            if let Some(parent) = node.parent {
+                dot.push_str(&format!("    {} -> {};\n", parent, i));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// The most recently generated node for state, preferring ones not yet expanded.
+    fn find(&self, state: &_State) -> Option<usize> {
+        let mut matching = (0..self.nodes.len())
+            .rev()
+            .filter(|&i| self.nodes[i].state == *state);
+        let latest = matching.clone().next();
+        matching.find(|&i| !self.nodes[i].expanded).or(latest)
+    }
+}
+
+/// Wraps a problem to record the tree explored by any search algorithm run on it. Search
+/// algorithms call Problem::actions once for each node they expand and Problem::result once for
+/// each node they generate, so the recorder rebuilds the tree from those calls without the
+/// algorithms having to know about it.
+///
+/// Calling initial_state starts a new tree, so for iterative deepening search only the last
+/// iteration is kept. Backward expansions by bidirectional search are not recorded.
+pub struct Recorder<_Problem: Problem> {
+    problem: _Problem,
+    tree: RefCell<ExploredTree<_Problem::State, _Problem::Action>>,
+}
+
+impl<_Problem: Problem> Recorder<_Problem> {
+    pub fn new(problem: _Problem) -> Self {
+        Self {
+            problem,
+            tree: RefCell::new(ExploredTree::default()),
+        }
+    }
+
+    pub fn problem(&self) -> &_Problem {
+        &self.problem
+    }
+
+    pub fn tree(&self) -> Ref<'_, ExploredTree<_Problem::State, _Problem::Action>> {
+        self.tree.borrow()
+    }
+
+    pub fn into_tree(self) -> ExploredTree<_Problem::State, _Problem::Action> {
+        self.tree.into_inner()
+    }
+}
+
+impl<_Problem: Problem> Problem for Recorder<_Problem> {
+    type State = _Problem::State;
+    type Action = _Problem::Action;
+
+    fn initial_state(&self) -> Self::State {
+        let state = self.problem.initial_state();
+        let mut tree = self.tree.borrow_mut();
+        *tree = ExploredTree::default();
+        tree.nodes.push(ExploredNode {
+            state: state.clone(),
+            action: None,
+            parent: None,
+            expanded: false,
+        });
+        state
+    }
+
+    fn actions(&self, state: &Self::State) -> Vec<Self::Action> {
+        let mut tree = self.tree.borrow_mut();
+        tree.expanding = tree.find(state);
+        if let Some(i) = tree.expanding {
+            tree.nodes[i].expanded = true;
+        }
+        self.problem.actions(state)
+    }
+
+    fn result(&self, state: &Self::State, action: &Self::Action) -> Self::State {
+        let next = self.problem.result(state, action);
+        let mut tree = self.tree.borrow_mut();
+        let parent = match tree.expanding {
+            Some(i) if tree.nodes[i].state == *state => Some(i),
+            _ => tree.find(state),
+        };
+        tree.nodes.push(ExploredNode {
+            state: next.clone(),
+            action: Some(action.clone()),
+            parent,
+            expanded: false,
+        });
+        next
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.problem.is_goal(state)
+    }
+
+    fn step_cost(&self, state: &Self::State, action: &Self::Action, next: &Self::State) -> Cost {
+        self.problem.step_cost(state, action, next)
+    }
+}
+
+impl<_Problem: HeuristicProblem> HeuristicProblem for Recorder<_Problem> {
+    fn heuristic(&self, state: &Self::State) -> Cost {
+        self.problem.heuristic(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::informed::astar_search;
+    use crate::test_problems::SmallGraph;
+    use crate::uninformed::{depth_limited_search, uniform_cost_search};
+
+    #[test]
+    fn test_records_tree() {
+        let recorder = Recorder::new(SmallGraph::new());
+        let result = uniform_cost_search(&recorder);
+        let tree = recorder.into_tree();
+        assert_eq!(tree.nodes().len(), result.stats.nodes_generated);
+        assert_eq!(
+            tree.nodes().iter().filter(|node| node.expanded).count(),
+            result.stats.nodes_expanded
+        );
+
+        // Following parents from the goal gives the solution path.
+        let goal = tree
+            .nodes()
+            .iter()
+            .position(|node| {
+                node.state == "G" && node.parent.is_some_and(|p| tree.nodes()[p].state == "B")
+            })
+            .unwrap();
+        let mut path = vec![];
+        let mut current = Some(goal);
+        while let Some(i) = current {
+            path.push(tree.nodes()[i].state);
+            current = tree.nodes()[i].parent;
+        }
+        path.reverse();
+        assert_eq!(path, result.solution().unwrap().states);
+    }
+
+    #[test]
+    fn test_restart_keeps_last_tree() {
+        let recorder = Recorder::new(SmallGraph::new());
+        depth_limited_search(&recorder, 3);
+        depth_limited_search(&recorder, 0);
+        assert_eq!(recorder.tree().nodes().len(), 1);
+        assert_eq!(recorder.tree().unexpanded().count(), 1);
+    }
+
+    #[test]
+    fn test_heuristic_passes_through() {
+        let recorder = Recorder::new(SmallGraph::new());
+        assert_eq!(
+            astar_search(&recorder).solution(),
+            astar_search(recorder.problem()).solution()
+        );
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let recorder = Recorder::new(SmallGraph::new());
+        depth_limited_search(&recorder, 1);
+        assert_eq!(
+            recorder.tree().to_dot(|state| state.to_string()),
+            "digraph {\n    0 [label=\"S\", style=filled];\n    1 [label=\"A\", style=dashed];\n    2 [label=\"G\", style=dashed];\n    0 -> 1;\n    0 -> 2;\n}\n"
+        );
+    }
+}
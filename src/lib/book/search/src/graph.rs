@@ -0,0 +1,334 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Route finding on any weighted graph.
+//
+// See:
+// -  Chapter 3: Solving Problems by Searching, section 3.1
+
+use std::fmt::Display;
+
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::{Direction, EdgeType, Undirected};
+
+use crate::bidirectional::InvertibleProblem;
+use crate::explored::ExploredTree;
+use crate::informed::HeuristicProblem;
+use crate::{Cost, Problem};
+
+/// Route-finding problem on a petgraph graph whose edge weights are step costs. States are node
+/// indices, and an action is the neighbouring node to move to. Graphs may be directed or
+/// undirected.
+///
+/// If node coordinates are given, the heuristic is the straight-line distance to the goal, which
+/// is admissible as long as no edge is shorter than the distance between its ends. Otherwise the
+/// heuristic is 0.
+#[derive(Debug, Clone)]
+pub struct GraphProblem<_Node, _Edges: EdgeType = Undirected> {
+    graph: Graph<_Node, Cost, _Edges>,
+    start: NodeIndex,
+    goal: NodeIndex,
+    coordinates: Option<Vec<(f64, f64)>>,
+}
+
+impl<_Node, _Edges: EdgeType> GraphProblem<_Node, _Edges> {
+    pub fn new(graph: Graph<_Node, Cost, _Edges>, start: NodeIndex, goal: NodeIndex) -> Self {
+        assert!(
+            start.index() < graph.node_count() && goal.index() < graph.node_count(),
+            "start and goal must be nodes of the graph"
+        );
+        Self {
+            graph,
+            start,
+            goal,
+            coordinates: None,
+        }
+    }
+
+    /// Coordinates of every node, indexed by node index, for the straight-line distance
+    /// heuristic.
+    pub fn with_coordinates(mut self, coordinates: Vec<(f64, f64)>) -> Self {
+        assert_eq!(
+            coordinates.len(),
+            self.graph.node_count(),
+            "there must be coordinates for every node"
+        );
+        self.coordinates = Some(coordinates);
+        self
+    }
+
+    pub fn graph(&self) -> &Graph<_Node, Cost, _Edges> {
+        &self.graph
+    }
+
+    pub fn start(&self) -> NodeIndex {
+        self.start
+    }
+
+    pub fn goal(&self) -> NodeIndex {
+        self.goal
+    }
+
+    /// The graph in GraphViz DOT format, with the start and goal highlighted.
+    pub fn to_dot(&self) -> String
+    where
+        _Node: Display,
+    {
+        let (kind, arrow) = if self.graph.is_directed() {
+            ("digraph", "->")
+        } else {
+            ("graph", "--")
+        };
+        let mut dot = format!("{} {{\n", kind);
+        for index in self.graph.node_indices() {
+            let style = if index == self.start || index == self.goal {
+                ", style=bold"
+            } else {
+                ""
+            };
+            dot.push_str(&format!(
+                "    {} [label=\"{}\"{}];\n",
+                index.index(),
+                escape(&self.graph[index].to_string()),
+                style
+            ));
+        }
+        for edge in self.graph.raw_edges() {
+            dot.push_str(&format!(
+                "    {} {} {} [label=\"{}\"];\n",
+                edge.source().index(),
+                arrow,
+                edge.target().index(),
+                edge.weight
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// A tree explored by searching this problem in GraphViz DOT format, labelled with node
+    /// weights.
+    pub fn explored_to_dot(&self, tree: &ExploredTree<NodeIndex, NodeIndex>) -> String
+    where
+        _Node: Display,
+    {
+        tree.to_dot(|index| self.graph[*index].to_string())
+    }
+}
+
+impl<_Node, _Edges: EdgeType> Problem for GraphProblem<_Node, _Edges> {
+    type State = NodeIndex;
+    type Action = NodeIndex;
+
+    fn initial_state(&self) -> Self::State {
+        self.start
+    }
+
+    fn actions(&self, state: &Self::State) -> Vec<Self::Action> {
+        // petgraph lists the most recently added edge first, so sort into a stable order.
+        let mut neighbours: Vec<NodeIndex> = self.graph.neighbors(*state).collect();
+        neighbours.sort();
+        neighbours.dedup();
+        neighbours
+    }
+
+    fn result(&self, _state: &Self::State, action: &Self::Action) -> Self::State {
+        *action
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        *state == self.goal
+    }
+
+    /// Weight of the cheapest edge from state to next_state.
+    fn step_cost(&self, state: &Self::State, _action: &Self::Action, next: &Self::State) -> Cost {
+        self.graph
+            .edges_connecting(*state, *next)
+            .map(|edge| *edge.weight())
+            .fold(Cost::INFINITY, Cost::min)
+    }
+}
+
+impl<_Node, _Edges: EdgeType> HeuristicProblem for GraphProblem<_Node, _Edges> {
+    fn heuristic(&self, state: &Self::State) -> Cost {
+        match &self.coordinates {
+            Some(coordinates) => {
+                let (x1, y1) = coordinates[state.index()];
+                let (x2, y2) = coordinates[self.goal.index()];
+                (x1 - x2).hypot(y1 - y2)
+            }
+            None => 0.0,
+        }
+    }
+}
+
+impl<_Node, _Edges: EdgeType> InvertibleProblem for GraphProblem<_Node, _Edges> {
+    fn goal_state(&self) -> Self::State {
+        self.goal
+    }
+
+    fn predecessors(&self, state: &Self::State) -> Vec<(Self::State, Self::Action)> {
+        let mut predecessors: Vec<NodeIndex> = self
+            .graph
+            .neighbors_directed(*state, Direction::Incoming)
+            .collect();
+        predecessors.sort();
+        predecessors.dedup();
+        predecessors
+            .into_iter()
+            .map(|predecessor| (predecessor, *state))
+            .collect()
+    }
+}
+
+/// Escape a label for use inside double quotes in DOT.
+pub(crate) fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::Directed;
+
+    use super::*;
+    use crate::bidirectional::bidirectional_breadth_first_search;
+    use crate::explored::Recorder;
+    use crate::informed::astar_search;
+    use crate::romania::{City, ROADS};
+    use crate::uninformed::{breadth_first_search, uniform_cost_search};
+
+    fn romania() -> GraphProblem<City> {
+        let mut graph = Graph::new_undirected();
+        let nodes: Vec<NodeIndex> = City::ALL.iter().map(|&city| graph.add_node(city)).collect();
+        let node = |city: City| nodes[City::ALL.iter().position(|&c| c == city).unwrap()];
+        for (a, b, distance) in ROADS {
+            graph.add_edge(node(a), node(b), distance);
+        }
+        GraphProblem::new(graph, node(City::Arad), node(City::Bucharest))
+    }
+
+    #[test]
+    fn test_romania_as_a_graph() {
+        let problem = romania();
+        let solution = uniform_cost_search(&problem).solution().unwrap().clone();
+        assert_eq!(solution.path_cost, 418.0);
+        let route: Vec<City> = solution
+            .states
+            .iter()
+            .map(|&i| problem.graph()[i])
+            .collect();
+        assert_eq!(
+            route,
+            vec![
+                City::Arad,
+                City::Sibiu,
+                City::RimnicuVilcea,
+                City::Pitesti,
+                City::Bucharest
+            ]
+        );
+        assert_eq!(
+            bidirectional_breadth_first_search(&problem)
+                .solution()
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_directed_graph() {
+        let mut graph: Graph<&str, Cost, Directed> = Graph::new();
+        let a = graph.add_node("a");
+        let b = graph.add_node("b");
+        let c = graph.add_node("c");
+        graph.add_edge(a, b, 1.0);
+        graph.add_edge(b, c, 1.0);
+        graph.add_edge(c, a, 1.0);
+        let forwards = GraphProblem::new(graph.clone(), a, c);
+        assert_eq!(breadth_first_search(&forwards).solution().unwrap().len(), 2);
+        assert_eq!(forwards.predecessors(&c), vec![(b, c)]);
+        let backwards = GraphProblem::new(graph, c, b);
+        assert_eq!(
+            breadth_first_search(&backwards).solution().unwrap().len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_parallel_edges_use_cheapest() {
+        let mut graph: Graph<&str, Cost, Undirected> = Graph::new_undirected();
+        let a = graph.add_node("a");
+        let b = graph.add_node("b");
+        graph.add_edge(a, b, 5.0);
+        graph.add_edge(a, b, 2.0);
+        let problem = GraphProblem::new(graph, a, b);
+        assert_eq!(problem.actions(&a), vec![b]);
+        assert_eq!(
+            uniform_cost_search(&problem).solution().unwrap().path_cost,
+            2.0
+        );
+    }
+
+    #[test]
+    fn test_coordinates_heuristic() {
+        // A square where the diagonal is a detour.
+        let mut graph: Graph<&str, Cost, Undirected> = Graph::new_undirected();
+        let a = graph.add_node("a");
+        let b = graph.add_node("b");
+        let c = graph.add_node("c");
+        let d = graph.add_node("d");
+        graph.add_edge(a, b, 1.0);
+        graph.add_edge(b, d, 1.0);
+        graph.add_edge(a, c, 1.0);
+        graph.add_edge(c, d, 1.0);
+        graph.add_edge(a, d, 3.0);
+        let problem = GraphProblem::new(graph, a, d).with_coordinates(vec![
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (0.0, 1.0),
+            (1.0, 1.0),
+        ]);
+        assert_eq!(problem.heuristic(&a), 2.0_f64.sqrt());
+        assert_eq!(problem.heuristic(&d), 0.0);
+        assert_eq!(astar_search(&problem).solution().unwrap().path_cost, 2.0);
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let mut graph: Graph<&str, Cost, Undirected> = Graph::new_undirected();
+        let a = graph.add_node("a");
+        let b = graph.add_node("say \"b\"");
+        graph.add_edge(a, b, 1.5);
+        let problem = GraphProblem::new(graph, a, b);
+        assert_eq!(
+            problem.to_dot(),
+            "graph {\n    0 [label=\"a\", style=bold];\n    1 [label=\"say \\\"b\\\"\", style=bold];\n    0 -- 1 [label=\"1.5\"];\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_explored_to_dot() {
+        let recorder = Recorder::new(romania());
+        let result = breadth_first_search(&recorder);
+        let tree = recorder.tree();
+        assert_eq!(tree.nodes().len(), result.stats.nodes_generated);
+        let dot = recorder.problem().explored_to_dot(&tree);
+        assert!(dot.starts_with("digraph {\n"));
+        assert!(dot.contains("[label=\"Arad\", style=filled]"));
+        assert!(dot.contains("[label=\"Bucharest\", style=dashed]"));
+    }
+}
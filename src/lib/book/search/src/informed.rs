@@ -0,0 +1,142 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Informed (heuristic) search strategies
+//
+// See:
+// -  Chapter 3: Solving Problems by Searching, sections 3.3.1 and 3.5
+
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::uninformed::{expand, found, not_found};
+use crate::{Cost, NodeArena, PriorityEntry, Problem, SearchResult, SearchStats};
+
+/// A problem with a heuristic function h(n), the estimated cost of the cheapest path from a state
+/// to a goal state.
+pub trait HeuristicProblem: Problem {
+    /// Estimated cost from state to the nearest goal. For A* to find optimal solutions this must
+    /// be admissible, i.e. never overestimate, and it must be 0 for goal states.
+    fn heuristic(&self, state: &Self::State) -> Cost;
+}
+
+/// Best-first search, Figure 3.7. Always expands the frontier node with the lowest value of the
+/// evaluation function, which is passed each node's state and path cost. A state is only added to
+/// the frontier again if a cheaper path to it is found. The goal test is applied when a node is
+/// expanded.
+///
+/// Uniform-cost, greedy best-first and A* search are all best-first search with different
+/// evaluation functions.
+pub fn best_first_search<_Problem, _Evaluation>(
+    problem: &_Problem,
+    evaluation: _Evaluation,
+) -> SearchResult<_Problem::State, _Problem::Action>
+where
+    _Problem: Problem,
+    _Evaluation: Fn(&_Problem::State, Cost) -> Cost,
+{
+    let mut arena = NodeArena::new();
+    let mut stats = SearchStats {
+        nodes_generated: 1,
+        ..SearchStats::default()
+    };
+    let root = arena.root(problem.initial_state());
+    let root_node = arena.get(root);
+    let mut frontier = BinaryHeap::from([PriorityEntry {
+        priority: evaluation(&root_node.state, 0.0),
+        node: root,
+    }]);
+    let mut reached = HashMap::from([(root_node.state.clone(), 0.0)]);
+    stats.observe_frontier(frontier.len());
+    while let Some(PriorityEntry { node, .. }) = frontier.pop() {
+        let node_ref = arena.get(node);
+        // Skip nodes that were superseded by a cheaper path to the same state.
+        if node_ref.path_cost > reached[&node_ref.state] {
+            continue;
+        }
+        if problem.is_goal(&node_ref.state) {
+            return found(&arena, node, stats);
+        }
+        for child in expand(problem, &mut arena, &mut stats, node) {
+            let child_node = arena.get(child);
+            let is_cheaper = reached
+                .get(&child_node.state)
+                .is_none_or(|&cost| child_node.path_cost < cost);
+            if is_cheaper {
+                reached.insert(child_node.state.clone(), child_node.path_cost);
+                frontier.push(PriorityEntry {
+                    priority: evaluation(&child_node.state, child_node.path_cost),
+                    node: child,
+                });
+            }
+        }
+        stats.observe_frontier(frontier.len());
+    }
+    not_found(stats)
+}
+
+/// Greedy best-first search, section 3.5.1: expands the node that looks closest to the goal, i.e.
+/// f(n) = h(n). Often fast, but the solution is not necessarily the cheapest.
+pub fn greedy_best_first_search<_Problem: HeuristicProblem>(
+    problem: &_Problem,
+) -> SearchResult<_Problem::State, _Problem::Action> {
+    best_first_search(problem, |state, _| problem.heuristic(state))
+}
+
+/// A* search, section 3.5.2: best-first search with f(n) = g(n) + h(n), the estimated cost of the
+/// cheapest solution through n. Finds the cheapest solution if the heuristic is admissible.
+pub fn astar_search<_Problem: HeuristicProblem>(
+    problem: &_Problem,
+) -> SearchResult<_Problem::State, _Problem::Action> {
+    best_first_search(problem, |state, path_cost| {
+        path_cost + problem.heuristic(state)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_problems::SmallGraph;
+    use crate::uninformed::uniform_cost_search;
+
+    impl HeuristicProblem for SmallGraph {
+        // Admissible, but misleading: G looks far away from S.
+        fn heuristic(&self, state: &Self::State) -> Cost {
+            match *state {
+                "S" => 3.0,
+                "A" => 2.0,
+                "B" => 1.0,
+                _ => 0.0,
+            }
+        }
+    }
+
+    #[test]
+    fn test_astar_finds_cheapest() {
+        let problem = SmallGraph::new();
+        let astar = astar_search(&problem);
+        assert_eq!(astar.solution(), uniform_cost_search(&problem).solution());
+        assert_eq!(astar.solution().unwrap().path_cost, 3.0);
+    }
+
+    #[test]
+    fn test_zero_heuristic_is_uniform_cost_search() {
+        let problem = SmallGraph::new();
+        assert_eq!(
+            best_first_search(&problem, |_, path_cost| path_cost),
+            uniform_cost_search(&problem)
+        );
+    }
+}
@@ -0,0 +1,390 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Solving problems by searching
+//
+// See:
+// -  Chapter 3: Solving Problems by Searching
+
+use std::cmp::Ordering;
+use std::hash::Hash;
+
+pub mod bidirectional;
+pub mod explored;
+pub mod graph;
+pub mod informed;
+pub mod local;
+pub mod n_queens;
+pub mod romania;
+pub mod sliding_puzzle;
+pub mod tsp;
+pub mod uninformed;
+
+pub type Cost = f64;
+
+/// A Problem is the formal definition of a search problem from section 3.1.2: an initial state,
+/// the actions available in each state, a transition model, a goal test, and an action cost
+/// function.
+pub trait Problem {
+    type State: Clone + Eq + Hash;
+    type Action: Clone;
+
+    fn initial_state(&self) -> Self::State;
+
+    /// Actions that can be executed in state.
+    fn actions(&self, state: &Self::State) -> Vec<Self::Action>;
+
+    /// The transition model: the state that results from doing action in state.
+    fn result(&self, state: &Self::State, action: &Self::Action) -> Self::State;
+
+    fn is_goal(&self, state: &Self::State) -> bool;
+
+    /// Cost of doing action in state to reach next_state. Defaults to 1 for every action, so that
+    /// the path cost is the number of actions.
+    fn step_cost(
+        &self,
+        _state: &Self::State,
+        _action: &Self::Action,
+        _next_state: &Self::State,
+    ) -> Cost {
+        1.0
+    }
+}
+
+/// A Solution is a path from the initial state to a goal state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution<_State, _Action> {
+    pub actions: Vec<_Action>,
+
+    /// Every state on the path, starting with the initial state, so there is one more state than
+    /// there are actions.
+    pub states: Vec<_State>,
+    pub path_cost: Cost,
+}
+
+impl<_State, _Action> Solution<_State, _Action> {
+    /// Number of actions in the solution.
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+}
+
+/// Counters that show how much work a search algorithm did, for comparing algorithms on the same
+/// problem.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SearchStats {
+    /// Nodes whose successors were generated.
+    pub nodes_expanded: usize,
+
+    /// Nodes created, including the root.
+    pub nodes_generated: usize,
+
+    /// Largest number of nodes in the frontier at any one time.
+    pub max_frontier_size: usize,
+}
+
+impl SearchStats {
+    pub(crate) fn observe_frontier(&mut self, frontier_size: usize) {
+        self.max_frontier_size = self.max_frontier_size.max(frontier_size);
+    }
+
+    pub(crate) fn add(&mut self, other: &SearchStats) {
+        self.nodes_expanded += other.nodes_expanded;
+        self.nodes_generated += other.nodes_generated;
+        self.max_frontier_size = self.max_frontier_size.max(other.max_frontier_size);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchOutcome<_State, _Action> {
+    Found(Solution<_State, _Action>),
+
+    /// A depth limit stopped the search before it could tell whether there is a solution.
+    Cutoff,
+
+    /// There is no solution.
+    NotFound,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult<_State, _Action> {
+    pub outcome: SearchOutcome<_State, _Action>,
+    pub stats: SearchStats,
+}
+
+impl<_State, _Action> SearchResult<_State, _Action> {
+    pub fn solution(&self) -> Option<&Solution<_State, _Action>> {
+        match &self.outcome {
+            SearchOutcome::Found(solution) => Some(solution),
+            _ => None,
+        }
+    }
+}
+
+/// Node in a search tree. Nodes are stored in a Vec, and refer to their parent by index.
+#[derive(Debug, Clone)]
+pub(crate) struct Node<_State, _Action> {
+    pub(crate) state: _State,
+    pub(crate) parent: Option<usize>,
+    pub(crate) action: Option<_Action>,
+    pub(crate) path_cost: Cost,
+    pub(crate) depth: usize,
+}
+
+/// Nodes of a search tree, so that paths can be recovered by following parents.
+pub(crate) struct NodeArena<_State, _Action> {
+    nodes: Vec<Node<_State, _Action>>,
+}
+
+impl<_State: Clone, _Action: Clone> NodeArena<_State, _Action> {
+    pub(crate) fn new() -> Self {
+        Self { nodes: vec![] }
+    }
+
+    pub(crate) fn root(&mut self, state: _State) -> usize {
+        self.nodes.push(Node {
+            state,
+            parent: None,
+            action: None,
+            path_cost: 0.0,
+            depth: 0,
+        });
+        self.nodes.len() - 1
+    }
+
+    pub(crate) fn child<_Problem>(
+        &mut self,
+        problem: &_Problem,
+        parent: usize,
+        action: _Action,
+    ) -> usize
+    where
+        _Problem: Problem<State = _State, Action = _Action>,
+    {
+        let parent_node = &self.nodes[parent];
+        let state = problem.result(&parent_node.state, &action);
+        let path_cost =
+            parent_node.path_cost + problem.step_cost(&parent_node.state, &action, &state);
+        let depth = parent_node.depth + 1;
+        self.nodes.push(Node {
+            state,
+            parent: Some(parent),
+            action: Some(action),
+            path_cost,
+            depth,
+        });
+        self.nodes.len() - 1
+    }
+
+    pub(crate) fn get(&self, index: usize) -> &Node<_State, _Action> {
+        &self.nodes[index]
+    }
+
+    /// The path from the root to the node at index.
+    pub(crate) fn solution(&self, index: usize) -> Solution<_State, _Action> {
+        let path_cost = self.nodes[index].path_cost;
+        let mut actions = vec![];
+        let mut states = vec![];
+        let mut current = Some(index);
+        while let Some(i) = current {
+            let node = &self.nodes[i];
+            states.push(node.state.clone());
+            if let Some(action) = &node.action {
+                actions.push(action.clone());
+            }
+            current = node.parent;
+        }
+        actions.reverse();
+        states.reverse();
+        Solution {
+            actions,
+            states,
+            path_cost,
+        }
+    }
+
+    /// True if state is on the path from the root to the node at index.
+    pub(crate) fn is_on_path(&self, index: usize, state: &_State) -> bool
+    where
+        _State: Eq,
+    {
+        let mut current = Some(index);
+        while let Some(i) = current {
+            if self.nodes[i].state == *state {
+                return true;
+            }
+            current = self.nodes[i].parent;
+        }
+        false
+    }
+}
+
+/// Entry in a priority queue ordered so that std::collections::BinaryHeap, which is a max-heap,
+/// pops the lowest priority first. Ties are broken in favour of the node generated first.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PriorityEntry {
+    pub(crate) priority: Cost,
+    pub(crate) node: usize,
+}
+
+impl PartialEq for PriorityEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PriorityEntry {}
+
+impl PartialOrd for PriorityEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PriorityEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .priority
+            .total_cmp(&self.priority)
+            .then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_problems {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    /// A small weighted, directed graph where the path with the fewest actions is not the
+    /// cheapest:
+    ///
+    ///   S -1-> A -1-> B -1-> G
+    ///   S -10-> G
+    ///   A -1-> C (dead end)
+    pub(crate) struct SmallGraph {
+        pub(crate) edges: HashMap<&'static str, Vec<(&'static str, Cost)>>,
+        pub(crate) start: &'static str,
+        pub(crate) goal: &'static str,
+    }
+
+    impl SmallGraph {
+        pub(crate) fn new() -> Self {
+            Self {
+                edges: HashMap::from([
+                    ("S", vec![("A", 1.0), ("G", 10.0)]),
+                    ("A", vec![("B", 1.0), ("C", 1.0), ("S", 1.0)]),
+                    ("B", vec![("G", 1.0)]),
+                    ("C", vec![]),
+                    ("G", vec![]),
+                ]),
+                start: "S",
+                goal: "G",
+            }
+        }
+    }
+
+    impl Problem for SmallGraph {
+        type State = &'static str;
+        type Action = &'static str;
+
+        fn initial_state(&self) -> Self::State {
+            self.start
+        }
+
+        fn actions(&self, state: &Self::State) -> Vec<Self::Action> {
+            self.edges[state].iter().map(|(next, _)| *next).collect()
+        }
+
+        fn result(&self, _state: &Self::State, action: &Self::Action) -> Self::State {
+            action
+        }
+
+        fn is_goal(&self, state: &Self::State) -> bool {
+            *state == self.goal
+        }
+
+        fn step_cost(&self, state: &Self::State, action: &Self::Action, _: &Self::State) -> Cost {
+            self.edges[state]
+                .iter()
+                .find(|(next, _)| next == action)
+                .unwrap()
+                .1
+        }
+    }
+
+    impl crate::bidirectional::InvertibleProblem for SmallGraph {
+        fn goal_state(&self) -> Self::State {
+            self.goal
+        }
+
+        fn predecessors(&self, state: &Self::State) -> Vec<(Self::State, Self::Action)> {
+            let mut predecessors: Vec<_> = self
+                .edges
+                .iter()
+                .filter(|(_, nexts)| nexts.iter().any(|(next, _)| next == state))
+                .map(|(from, _)| (*from, *state))
+                .collect();
+            predecessors.sort();
+            predecessors
+        }
+    }
+
+    /// An unbounded grid where the agent starts at the origin. Actions are the (dx, dy) offsets to
+    /// the four neighbours.
+    pub(crate) struct GridProblem {
+        pub(crate) goal: (i32, i32),
+    }
+
+    impl Problem for GridProblem {
+        type State = (i32, i32);
+        type Action = (i32, i32);
+
+        fn initial_state(&self) -> Self::State {
+            (0, 0)
+        }
+
+        fn actions(&self, _state: &Self::State) -> Vec<Self::Action> {
+            vec![(1, 0), (0, 1), (-1, 0), (0, -1)]
+        }
+
+        fn result(&self, state: &Self::State, action: &Self::Action) -> Self::State {
+            (state.0 + action.0, state.1 + action.1)
+        }
+
+        fn is_goal(&self, state: &Self::State) -> bool {
+            *state == self.goal
+        }
+    }
+
+    impl crate::bidirectional::InvertibleProblem for GridProblem {
+        fn goal_state(&self) -> Self::State {
+            self.goal
+        }
+
+        fn predecessors(&self, state: &Self::State) -> Vec<(Self::State, Self::Action)> {
+            // Every move is undone by the opposite move.
+            self.actions(state)
+                .into_iter()
+                .map(|(dx, dy)| ((state.0 + dx, state.1 + dy), (-dx, -dy)))
+                .collect()
+        }
+    }
+}
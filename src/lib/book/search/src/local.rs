@@ -0,0 +1,619 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Local search: optimize a complete state by moving between neighbouring states, without keeping
+// paths.
+//
+// See:
+// -  Chapter 4: Search in Complex Environments, section 4.1
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+/// A complete-state optimization problem: every state is a candidate solution, and the aim is to
+/// find a state with the highest value.
+pub trait Optimizable {
+    type State: Clone;
+
+    /// A random state, to start or restart a search from.
+    fn random_state(&self, rng: &mut rand_pcg::Pcg64) -> Self::State;
+
+    /// States that are one small change away from state.
+    fn neighbours(&self, state: &Self::State) -> Vec<Self::State>;
+
+    /// The objective function. Higher is better.
+    fn value(&self, state: &Self::State) -> f64;
+
+    /// Whether state is known to be a global optimum, so a search can stop early. Defaults to
+    /// false, for problems where the optimal value is unknown.
+    fn is_goal(&self, _state: &Self::State) -> bool {
+        false
+    }
+
+    /// A neighbour chosen uniformly at random, or None if state has no neighbours. Override this
+    /// when generating every neighbour is expensive.
+    fn random_neighbour(
+        &self,
+        state: &Self::State,
+        rng: &mut rand_pcg::Pcg64,
+    ) -> Option<Self::State> {
+        self.neighbours(state).choose(rng).cloned()
+    }
+}
+
+/// The best state a local search found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalSearchResult<_State> {
+    pub state: _State,
+    pub value: f64,
+
+    /// Number of moves made, summed over every restart.
+    pub steps: usize,
+
+    /// Number of times the search started from a new random state, including the first.
+    pub restarts: usize,
+}
+
+/// Steepest-ascent hill climbing, Figure 4.2. Moves to the best neighbour until no neighbour is
+/// better, so it stops at the first local maximum or plateau it reaches. Ties between equally good
+/// neighbours are broken at random.
+pub fn hill_climbing<_Problem: Optimizable>(
+    problem: &_Problem,
+    initial: _Problem::State,
+    rng: &mut rand_pcg::Pcg64,
+) -> LocalSearchResult<_Problem::State> {
+    let mut current = initial;
+    let mut value = problem.value(&current);
+    let mut steps = 0;
+    loop {
+        let neighbours = problem.neighbours(&current);
+        let values: Vec<f64> = neighbours.iter().map(|n| problem.value(n)).collect();
+        let best_value = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        if best_value <= value {
+            return LocalSearchResult {
+                state: current,
+                value,
+                steps,
+                restarts: 1,
+            };
+        }
+        let best: Vec<usize> = (0..neighbours.len())
+            .filter(|&i| values[i] == best_value)
+            .collect();
+        current = neighbours[*best.choose(rng).unwrap()].clone();
+        value = best_value;
+        steps += 1;
+    }
+}
+
+/// Random-restart hill climbing, section 4.1.1: runs hill climbing from random states until it
+/// reaches a goal, or max_restarts searches have been run, and returns the best state found.
+pub fn random_restart_hill_climbing<_Problem: Optimizable>(
+    problem: &_Problem,
+    max_restarts: usize,
+    seed: u64,
+) -> LocalSearchResult<_Problem::State> {
+    assert!(max_restarts > 0, "max_restarts must be positive");
+    let mut rng = rand_pcg::Pcg64::seed_from_u64(seed);
+    let mut best: Option<LocalSearchResult<_Problem::State>> = None;
+    let mut steps = 0;
+    for restart in 1..=max_restarts {
+        let initial = problem.random_state(&mut rng);
+        let result = hill_climbing(problem, initial, &mut rng);
+        steps += result.steps;
+        let is_goal = problem.is_goal(&result.state);
+        if best.as_ref().is_none_or(|best| result.value > best.value) {
+            best = Some(result);
+        }
+        let best = best.as_mut().unwrap();
+        best.steps = steps;
+        best.restarts = restart;
+        if is_goal {
+            break;
+        }
+    }
+    best.unwrap()
+}
+
+/// A temperature schedule for simulated annealing: the temperature at time step t, starting from
+/// t = 1. Annealing stops when the temperature reaches 0.
+pub trait Schedule {
+    fn temperature(&self, t: usize) -> f64;
+}
+
+impl<_Function: Fn(usize) -> f64> Schedule for _Function {
+    fn temperature(&self, t: usize) -> f64 {
+        self(t)
+    }
+}
+
+/// Temperature initial * e^(-decay * t), cut off to 0 after limit steps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExponentialSchedule {
+    pub initial: f64,
+    pub decay: f64,
+    pub limit: usize,
+}
+
+impl Default for ExponentialSchedule {
+    fn default() -> Self {
+        Self {
+            initial: 20.0,
+            decay: 0.005,
+            limit: 1000,
+        }
+    }
+}
+
+impl Schedule for ExponentialSchedule {
+    fn temperature(&self, t: usize) -> f64 {
+        if t >= self.limit {
+            0.0
+        } else {
+            self.initial * (-self.decay * t as f64).exp()
+        }
+    }
+}
+
+/// Temperature falling in a straight line from initial at t = 0 to 0 at t = steps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearSchedule {
+    pub initial: f64,
+    pub steps: usize,
+}
+
+impl Schedule for LinearSchedule {
+    fn temperature(&self, t: usize) -> f64 {
+        if t >= self.steps {
+            0.0
+        } else {
+            self.initial * (self.steps - t) as f64 / self.steps as f64
+        }
+    }
+}
+
+/// How often simulated annealing accepted or rejected the random moves it considered.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AnnealingStats {
+    /// Moves considered, one per time step.
+    pub steps: usize,
+
+    /// Moves that were at least as good as the current state, which are always accepted.
+    pub improving_moves: usize,
+
+    /// Moves that were worse, but accepted anyway with probability e^(delta / temperature).
+    pub accepted_worse_moves: usize,
+    pub rejected_moves: usize,
+}
+
+impl AnnealingStats {
+    /// Fraction of the moves considered that were accepted.
+    pub fn acceptance_rate(&self) -> f64 {
+        if self.steps == 0 {
+            return 0.0;
+        }
+        (self.improving_moves + self.accepted_worse_moves) as f64 / self.steps as f64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnealingResult<_State> {
+    /// Best state seen during the search. The final state may be worse, since annealing accepts
+    /// some downhill moves.
+    pub state: _State,
+    pub value: f64,
+    pub stats: AnnealingStats,
+}
+
+/// Simulated annealing, Figure 4.5. Starts from a random state and at each time step picks a
+/// random neighbour. A move that is no worse is always made, and a worse move is made with
+/// probability e^(delta / T), where delta is the change in value and T the current temperature.
+/// Early on, while the temperature is high, the search explores freely. As the temperature falls
+/// it behaves more and more like hill climbing. Stops when the temperature reaches 0 or a goal is
+/// found.
+pub fn simulated_annealing<_Problem: Optimizable, _Schedule: Schedule>(
+    problem: &_Problem,
+    schedule: &_Schedule,
+    seed: u64,
+) -> AnnealingResult<_Problem::State> {
+    let mut rng = rand_pcg::Pcg64::seed_from_u64(seed);
+    let mut current = problem.random_state(&mut rng);
+    let mut current_value = problem.value(&current);
+    let mut best = AnnealingResult {
+        state: current.clone(),
+        value: current_value,
+        stats: AnnealingStats::default(),
+    };
+    let mut stats = AnnealingStats::default();
+    for t in 1.. {
+        let temperature = schedule.temperature(t);
+        if temperature <= 0.0 || problem.is_goal(&current) {
+            break;
+        }
+        let Some(next) = problem.random_neighbour(&current, &mut rng) else {
+            break;
+        };
+        stats.steps += 1;
+        let next_value = problem.value(&next);
+        let delta = next_value - current_value;
+        if delta >= 0.0 {
+            stats.improving_moves += 1;
+        } else if rng.gen::<f64>() < (delta / temperature).exp() {
+            stats.accepted_worse_moves += 1;
+        } else {
+            stats.rejected_moves += 1;
+            continue;
+        }
+        current = next;
+        current_value = next_value;
+        if current_value > best.value {
+            best.state = current.clone();
+            best.value = current_value;
+        }
+    }
+    best.stats = stats;
+    best
+}
+
+/// Local beam search, section 4.1.3. Keeps track of k states rather than one. At each step it
+/// generates all the neighbours of all k states, and keeps the best k of them, so that useful
+/// information is passed between the parallel searches. Stops when a goal is found, when no
+/// neighbour is better than the best state so far, or after max_steps steps.
+pub fn local_beam_search<_Problem: Optimizable>(
+    problem: &_Problem,
+    k: usize,
+    max_steps: usize,
+    seed: u64,
+) -> LocalSearchResult<_Problem::State> {
+    beam_search(problem, k, max_steps, seed, true, |values, _| {
+        let mut order: Vec<usize> = (0..values.len()).collect();
+        order.sort_by(|&a, &b| values[b].total_cmp(&values[a]));
+        order.truncate(k);
+        order
+    })
+}
+
+/// Stochastic beam search, section 4.1.3. Like local beam search, but instead of the best k
+/// neighbours it keeps k neighbours chosen at random, with probability proportional to
+/// e^(value / temperature). This keeps the k states from crowding into one small region. Since
+/// it does not always keep the best states, it runs until a goal is found or for max_steps
+/// steps.
+pub fn stochastic_beam_search<_Problem: Optimizable>(
+    problem: &_Problem,
+    k: usize,
+    temperature: f64,
+    max_steps: usize,
+    seed: u64,
+) -> LocalSearchResult<_Problem::State> {
+    assert!(temperature > 0.0, "temperature must be positive");
+    beam_search(problem, k, max_steps, seed, false, |values, rng| {
+        // Subtract the maximum so that the weights cannot overflow.
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let weights = values
+            .iter()
+            .map(|value| ((value - max) / temperature).exp());
+        let distribution = WeightedIndex::new(weights).unwrap();
+        (0..k).map(|_| distribution.sample(rng)).collect()
+    })
+}
+
+/// Shared loop for the beam searches. select is given the values of all the neighbours and
+/// returns the indices of the ones to keep. If stop_at_local_maximum is true the search stops as
+/// soon as a step fails to improve on the best state so far.
+fn beam_search<_Problem, _Select>(
+    problem: &_Problem,
+    k: usize,
+    max_steps: usize,
+    seed: u64,
+    stop_at_local_maximum: bool,
+    select: _Select,
+) -> LocalSearchResult<_Problem::State>
+where
+    _Problem: Optimizable,
+    _Select: Fn(&[f64], &mut rand_pcg::Pcg64) -> Vec<usize>,
+{
+    assert!(k > 0, "k must be positive");
+    let mut rng = rand_pcg::Pcg64::seed_from_u64(seed);
+    let mut beam: Vec<_Problem::State> = (0..k).map(|_| problem.random_state(&mut rng)).collect();
+    let values: Vec<f64> = beam.iter().map(|state| problem.value(state)).collect();
+    let mut best = best_of(&beam, &values);
+    let mut steps = 0;
+    while steps < max_steps && !problem.is_goal(&best.state) {
+        let neighbours: Vec<_Problem::State> = beam
+            .iter()
+            .flat_map(|state| problem.neighbours(state))
+            .collect();
+        if neighbours.is_empty() {
+            break;
+        }
+        let neighbour_values: Vec<f64> = neighbours.iter().map(|n| problem.value(n)).collect();
+        steps += 1;
+        if let Some(goal) = neighbours.iter().position(|n| problem.is_goal(n)) {
+            best.state = neighbours[goal].clone();
+            best.value = neighbour_values[goal];
+            break;
+        }
+        let kept = select(&neighbour_values, &mut rng);
+        beam = kept.iter().map(|&i| neighbours[i].clone()).collect();
+        let values: Vec<f64> = kept.iter().map(|&i| neighbour_values[i]).collect();
+        let candidate = best_of(&beam, &values);
+        if candidate.value > best.value {
+            best = candidate;
+        } else if stop_at_local_maximum {
+            break;
+        }
+    }
+    best.steps = steps;
+    best
+}
+
+fn best_of<_State: Clone>(states: &[_State], values: &[f64]) -> LocalSearchResult<_State> {
+    let best = (0..states.len())
+        .max_by(|&a, &b| values[a].total_cmp(&values[b]))
+        .unwrap();
+    LocalSearchResult {
+        state: states[best].clone(),
+        value: values[best],
+        steps: 0,
+        restarts: 1,
+    }
+}
+
+/// A local search algorithm and its parameters, so that the algorithms of Chapter 4 can be run
+/// and compared on the same problems.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LocalSearchAlgorithm {
+    HillClimbing,
+    RandomRestartHillClimbing {
+        max_restarts: usize,
+    },
+    SimulatedAnnealing(ExponentialSchedule),
+    LocalBeam {
+        k: usize,
+        max_steps: usize,
+    },
+    StochasticBeam {
+        k: usize,
+        temperature: f64,
+        max_steps: usize,
+    },
+}
+
+impl LocalSearchAlgorithm {
+    pub fn name(&self) -> String {
+        match self {
+            LocalSearchAlgorithm::HillClimbing => "hill climbing".to_string(),
+            LocalSearchAlgorithm::RandomRestartHillClimbing { max_restarts } => {
+                format!("random restart ({})", max_restarts)
+            }
+            LocalSearchAlgorithm::SimulatedAnnealing(_) => "simulated annealing".to_string(),
+            LocalSearchAlgorithm::LocalBeam { k, .. } => format!("local beam (k={})", k),
+            LocalSearchAlgorithm::StochasticBeam { k, .. } => {
+                format!("stochastic beam (k={})", k)
+            }
+        }
+    }
+
+    pub fn run<_Problem: Optimizable>(
+        &self,
+        problem: &_Problem,
+        seed: u64,
+    ) -> LocalSearchResult<_Problem::State> {
+        match self {
+            LocalSearchAlgorithm::HillClimbing => {
+                let mut rng = rand_pcg::Pcg64::seed_from_u64(seed);
+                let initial = problem.random_state(&mut rng);
+                hill_climbing(problem, initial, &mut rng)
+            }
+            LocalSearchAlgorithm::RandomRestartHillClimbing { max_restarts } => {
+                random_restart_hill_climbing(problem, *max_restarts, seed)
+            }
+            LocalSearchAlgorithm::SimulatedAnnealing(schedule) => {
+                let result = simulated_annealing(problem, schedule, seed);
+                LocalSearchResult {
+                    state: result.state,
+                    value: result.value,
+                    steps: result.stats.steps,
+                    restarts: 1,
+                }
+            }
+            LocalSearchAlgorithm::LocalBeam { k, max_steps } => {
+                local_beam_search(problem, *k, *max_steps, seed)
+            }
+            LocalSearchAlgorithm::StochasticBeam {
+                k,
+                temperature,
+                max_steps,
+            } => stochastic_beam_search(problem, *k, *temperature, *max_steps, seed),
+        }
+    }
+}
+
+/// How one algorithm did over several trials.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalSearchSummary {
+    pub name: String,
+    pub trials: usize,
+
+    /// Trials that ended in a goal state.
+    pub solved: usize,
+    pub mean_value: f64,
+    pub best_value: f64,
+    pub mean_steps: f64,
+}
+
+/// Run each algorithm trials times on problem. Trial i uses seed + i, for every algorithm.
+pub fn compare_local_search<_Problem: Optimizable>(
+    problem: &_Problem,
+    algorithms: &[LocalSearchAlgorithm],
+    trials: usize,
+    seed: u64,
+) -> Vec<LocalSearchSummary> {
+    assert!(trials > 0, "trials must be positive");
+    algorithms
+        .iter()
+        .map(|algorithm| {
+            let results: Vec<LocalSearchResult<_Problem::State>> = (0..trials as u64)
+                .map(|trial| algorithm.run(problem, seed + trial))
+                .collect();
+            LocalSearchSummary {
+                name: algorithm.name(),
+                trials,
+                solved: results
+                    .iter()
+                    .filter(|result| problem.is_goal(&result.state))
+                    .count(),
+                mean_value: results.iter().map(|result| result.value).sum::<f64>() / trials as f64,
+                best_value: results
+                    .iter()
+                    .map(|result| result.value)
+                    .fold(f64::NEG_INFINITY, f64::max),
+                mean_steps: results
+                    .iter()
+                    .map(|result| result.steps as f64)
+                    .sum::<f64>()
+                    / trials as f64,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod test_problems {
+    use rand::Rng;
+
+    use super::*;
+
+    /// Maximize -(x - target)^2 over the integers, moving by 1 at a time. There is a single
+    /// maximum, so hill climbing always finds it.
+    pub(crate) struct Parabola {
+        pub(crate) target: i64,
+    }
+
+    impl Optimizable for Parabola {
+        type State = i64;
+
+        fn random_state(&self, rng: &mut rand_pcg::Pcg64) -> Self::State {
+            rng.gen_range(-100..=100)
+        }
+
+        fn neighbours(&self, state: &Self::State) -> Vec<Self::State> {
+            vec![state - 1, state + 1]
+        }
+
+        fn value(&self, state: &Self::State) -> f64 {
+            -((state - self.target).pow(2) as f64)
+        }
+
+        fn is_goal(&self, state: &Self::State) -> bool {
+            *state == self.target
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_problems::Parabola;
+    use super::*;
+
+    #[test]
+    fn test_hill_climbing_single_maximum() {
+        let mut rng = rand_pcg::Pcg64::seed_from_u64(0);
+        let result = hill_climbing(&Parabola { target: 7 }, -3, &mut rng);
+        assert_eq!(result.state, 7);
+        assert_eq!(result.value, 0.0);
+        assert_eq!(result.steps, 10);
+    }
+
+    #[test]
+    fn test_schedules() {
+        let exponential = ExponentialSchedule::default();
+        assert_eq!(exponential.temperature(0), 20.0);
+        assert!(exponential.temperature(500) < exponential.temperature(100));
+        assert_eq!(exponential.temperature(1000), 0.0);
+
+        let linear = LinearSchedule {
+            initial: 10.0,
+            steps: 5,
+        };
+        assert_eq!(linear.temperature(1), 8.0);
+        assert_eq!(linear.temperature(5), 0.0);
+
+        let custom = |t: usize| if t < 3 { 1.0 } else { 0.0 };
+        assert_eq!(custom.temperature(2), 1.0);
+    }
+
+    #[test]
+    fn test_simulated_annealing_finds_maximum() {
+        let problem = Parabola { target: 7 };
+        let result = simulated_annealing(&problem, &ExponentialSchedule::default(), 0);
+        assert_eq!(result.state, 7);
+        let stats = result.stats;
+        assert_eq!(
+            stats.steps,
+            stats.improving_moves + stats.accepted_worse_moves + stats.rejected_moves
+        );
+    }
+
+    #[test]
+    fn test_simulated_annealing_stops_when_cold() {
+        // Target far outside the random starting range, and too few steps to walk there.
+        let problem = Parabola { target: 10_000 };
+        let schedule = |t: usize| if t <= 50 { 1.0 } else { 0.0 };
+        let result = simulated_annealing(&problem, &schedule, 0);
+        assert_eq!(result.stats.steps, 50);
+        assert!(result.state < 10_000);
+    }
+
+    #[test]
+    fn test_local_beam_search_finds_maximum() {
+        let result = local_beam_search(&Parabola { target: 7 }, 4, 1000, 0);
+        assert_eq!(result.state, 7);
+        assert!(result.steps > 0);
+    }
+
+    #[test]
+    fn test_stochastic_beam_search_finds_maximum() {
+        let result = stochastic_beam_search(&Parabola { target: 7 }, 4, 1.0, 1000, 0);
+        assert_eq!(result.state, 7);
+    }
+
+    #[test]
+    fn test_compare_local_search() {
+        let algorithms = [
+            LocalSearchAlgorithm::HillClimbing,
+            LocalSearchAlgorithm::LocalBeam {
+                k: 2,
+                max_steps: 1000,
+            },
+        ];
+        let summaries = compare_local_search(&Parabola { target: 7 }, &algorithms, 5, 0);
+        assert_eq!(summaries.len(), 2);
+        for summary in summaries {
+            assert_eq!(summary.trials, 5);
+            assert_eq!(summary.solved, 5);
+            assert_eq!(summary.mean_value, 0.0);
+        }
+    }
+
+    #[test]
+    fn test_random_restart_stops_at_goal() {
+        let result = random_restart_hill_climbing(&Parabola { target: 7 }, 10, 0);
+        assert_eq!(result.state, 7);
+        assert_eq!(result.restarts, 1);
+    }
+}
@@ -0,0 +1,301 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// The n-queens problem: place n queens on an n by n chess board so that no queen attacks another.
+//
+// See:
+// -  Chapter 3: Solving Problems by Searching, section 3.2.1 (incremental formulation)
+// -  Chapter 4: Search in Complex Environments, section 4.1.1 (complete-state formulation)
+
+use rand::Rng;
+
+use crate::local::Optimizable;
+use crate::Problem;
+
+/// Queens, one per column: rows[column] is the row of the queen in that column.
+pub type Queens = Vec<usize>;
+
+/// Whether the queens in columns a and b attack each other, along a row or a diagonal. Queens in
+/// different columns never attack along a column.
+fn attacks(rows: &[usize], a: usize, b: usize) -> bool {
+    rows[a] == rows[b] || rows[a].abs_diff(rows[b]) == a.abs_diff(b)
+}
+
+/// Number of pairs of queens that attack each other, the heuristic h of Figure 4.3.
+pub fn attacking_pairs(rows: &[usize]) -> usize {
+    (0..rows.len())
+        .flat_map(|a| (a + 1..rows.len()).map(move |b| (a, b)))
+        .filter(|&(a, b)| attacks(rows, a, b))
+        .count()
+}
+
+/// Whether rows places n queens on an n by n board with no queen attacking another.
+pub fn is_solution(n: usize, rows: &[usize]) -> bool {
+    rows.len() == n && rows.iter().all(|&row| row < n) && attacking_pairs(rows) == 0
+}
+
+/// Complete-state formulation for local search. Every state has a queen in each column, and a
+/// neighbour moves a single queen to another square in its column, so each state has n(n - 1)
+/// neighbours. The value is minus the number of attacking pairs, so solutions have value 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NQueensLocal {
+    pub n: usize,
+}
+
+impl Optimizable for NQueensLocal {
+    type State = Queens;
+
+    fn random_state(&self, rng: &mut rand_pcg::Pcg64) -> Self::State {
+        (0..self.n).map(|_| rng.gen_range(0..self.n)).collect()
+    }
+
+    fn neighbours(&self, state: &Self::State) -> Vec<Self::State> {
+        let mut neighbours = Vec::with_capacity(self.n * (self.n - 1));
+        for column in 0..self.n {
+            for row in (0..self.n).filter(|&row| row != state[column]) {
+                let mut neighbour = state.clone();
+                neighbour[column] = row;
+                neighbours.push(neighbour);
+            }
+        }
+        neighbours
+    }
+
+    fn value(&self, state: &Self::State) -> f64 {
+        // Subtract rather than negate, so that solutions have value 0 rather than -0.
+        0.0 - attacking_pairs(state) as f64
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        attacking_pairs(state) == 0
+    }
+
+    fn random_neighbour(
+        &self,
+        state: &Self::State,
+        rng: &mut rand_pcg::Pcg64,
+    ) -> Option<Self::State> {
+        if self.n < 2 {
+            return None;
+        }
+        let column = rng.gen_range(0..self.n);
+        // Pick one of the other n - 1 rows.
+        let mut row = rng.gen_range(0..self.n - 1);
+        if row >= state[column] {
+            row += 1;
+        }
+        let mut neighbour = state.clone();
+        neighbour[column] = row;
+        Some(neighbour)
+    }
+}
+
+/// Incremental formulation for tree search. States are the rows of queens placed in the leftmost
+/// columns, and an action places a queen in the next column, in any row not attacked by a queen
+/// already placed. This is the book's improved formulation, which reduces the 8-queens state
+/// space from 1.8 x 10^14 states to just 2,057.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NQueensIncremental {
+    pub n: usize,
+}
+
+impl Problem for NQueensIncremental {
+    type State = Queens;
+
+    /// Row of the queen placed in the next column.
+    type Action = usize;
+
+    fn initial_state(&self) -> Self::State {
+        vec![]
+    }
+
+    fn actions(&self, state: &Self::State) -> Vec<Self::Action> {
+        if state.len() == self.n {
+            return vec![];
+        }
+        let column = state.len();
+        (0..self.n)
+            .filter(|&row| {
+                state
+                    .iter()
+                    .enumerate()
+                    .all(|(c, &r)| r != row && r.abs_diff(row) != c.abs_diff(column))
+            })
+            .collect()
+    }
+
+    fn result(&self, state: &Self::State, action: &Self::Action) -> Self::State {
+        let mut next = state.clone();
+        next.push(*action);
+        next
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        state.len() == self.n
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::local::{
+        compare_local_search, hill_climbing, random_restart_hill_climbing, simulated_annealing,
+        ExponentialSchedule, LocalSearchAlgorithm,
+    };
+    use crate::uninformed::{breadth_first_search, depth_first_search};
+
+    #[test]
+    fn test_attacking_pairs() {
+        // Figure 4.3(a): the state has h = 17.
+        assert_eq!(attacking_pairs(&[4, 5, 6, 3, 4, 5, 6, 5]), 17);
+        assert_eq!(attacking_pairs(&[0, 4, 7, 5, 2, 6, 1, 3]), 0);
+        // Two queens on the same row, and also on a diagonal with another.
+        assert_eq!(attacking_pairs(&[0, 0, 2]), 2);
+        assert_eq!(attacking_pairs(&[1, 3, 0, 2]), 0);
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let problem = NQueensLocal { n: 8 };
+        let state = vec![0; 8];
+        assert_eq!(problem.neighbours(&state).len(), 56);
+        let mut rng = rand_pcg::Pcg64::seed_from_u64(0);
+        for _ in 0..100 {
+            let neighbour = problem.random_neighbour(&state, &mut rng).unwrap();
+            let changed = (0..8).filter(|&c| neighbour[c] != state[c]).count();
+            assert_eq!(changed, 1);
+        }
+    }
+
+    #[test]
+    fn test_incremental_search_finds_solution() {
+        let problem = NQueensIncremental { n: 8 };
+        let solution = depth_first_search(&problem).solution().unwrap().clone();
+        let queens = solution.states.last().unwrap();
+        assert!(is_solution(8, queens));
+        assert_eq!(solution.actions, *queens);
+    }
+
+    #[test]
+    fn test_incremental_state_space_size() {
+        // Every reachable state is a partial placement with no attacks. Breadth-first search with
+        // no solution to find visits all of them.
+        let problem = NQueensIncremental { n: 8 };
+        struct NoGoal(NQueensIncremental);
+        impl Problem for NoGoal {
+            type State = Queens;
+            type Action = usize;
+            fn initial_state(&self) -> Queens {
+                self.0.initial_state()
+            }
+            fn actions(&self, state: &Queens) -> Vec<usize> {
+                self.0.actions(state)
+            }
+            fn result(&self, state: &Queens, action: &usize) -> Queens {
+                self.0.result(state, action)
+            }
+            fn is_goal(&self, _: &Queens) -> bool {
+                false
+            }
+        }
+        let result = breadth_first_search(&NoGoal(problem));
+        assert_eq!(result.stats.nodes_generated, 2057);
+    }
+
+    #[test]
+    fn test_no_solution_for_three_queens() {
+        let problem = NQueensIncremental { n: 3 };
+        assert!(breadth_first_search(&problem).solution().is_none());
+    }
+
+    #[test]
+    fn test_hill_climbing_gets_stuck_sometimes() {
+        // Section 4.1.1: steepest-ascent hill climbing solves only about 14% of random 8-queens
+        // states.
+        let problem = NQueensLocal { n: 8 };
+        let mut rng = rand_pcg::Pcg64::seed_from_u64(0);
+        let trials = 200;
+        let solved = (0..trials)
+            .filter(|_| {
+                let initial = problem.random_state(&mut rng);
+                problem.is_goal(&hill_climbing(&problem, initial, &mut rng).state)
+            })
+            .count();
+        assert!(solved > 0 && solved < trials / 2, "solved {}", solved);
+    }
+
+    #[test]
+    fn test_simulated_annealing_solves_eight_queens() {
+        let problem = NQueensLocal { n: 8 };
+        let schedule = ExponentialSchedule {
+            initial: 2.0,
+            decay: 0.001,
+            limit: 10_000,
+        };
+        let solved = (0..10)
+            .filter(|&seed| {
+                let result = simulated_annealing(&problem, &schedule, seed);
+                assert_eq!(
+                    result.stats.steps,
+                    result.stats.improving_moves
+                        + result.stats.accepted_worse_moves
+                        + result.stats.rejected_moves
+                );
+                is_solution(8, &result.state)
+            })
+            .count();
+        assert!(solved >= 8, "solved {}", solved);
+    }
+
+    #[test]
+    fn test_compare_local_search() {
+        let algorithms = [
+            LocalSearchAlgorithm::HillClimbing,
+            LocalSearchAlgorithm::RandomRestartHillClimbing { max_restarts: 100 },
+            LocalSearchAlgorithm::LocalBeam {
+                k: 10,
+                max_steps: 100,
+            },
+            LocalSearchAlgorithm::StochasticBeam {
+                k: 10,
+                temperature: 0.5,
+                max_steps: 100,
+            },
+        ];
+        let summaries = compare_local_search(&NQueensLocal { n: 8 }, &algorithms, 10, 0);
+        let solved: Vec<usize> = summaries.iter().map(|summary| summary.solved).collect();
+        assert_eq!(solved[1], 10);
+        assert!(solved[0] < solved[2], "{:?}", summaries);
+        assert!(solved[0] < solved[3], "{:?}", summaries);
+    }
+
+    #[test]
+    fn test_local_and_incremental_solutions_agree() {
+        let local = random_restart_hill_climbing(&NQueensLocal { n: 8 }, 100, 0);
+        assert!(is_solution(8, &local.state));
+
+        // A complete-state solution is a path of actions in the incremental formulation.
+        let problem = NQueensIncremental { n: 8 };
+        let mut state = problem.initial_state();
+        for row in &local.state {
+            assert!(problem.actions(&state).contains(row));
+            state = problem.result(&state, row);
+        }
+        assert!(problem.is_goal(&state));
+    }
+}
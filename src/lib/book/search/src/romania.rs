@@ -0,0 +1,340 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// The Romania road map, the running example of Chapter 3.
+//
+// See:
+// -  Figure 3.1: A simplified road map of part of Romania, with road distances in miles.
+// -  Figure 3.16: Values of straight-line distances to Bucharest.
+
+use std::fmt;
+
+use crate::bidirectional::InvertibleProblem;
+use crate::informed::HeuristicProblem;
+use crate::{Cost, Problem};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum City {
+    Arad,
+    Bucharest,
+    Craiova,
+    Drobeta,
+    Eforie,
+    Fagaras,
+    Giurgiu,
+    Hirsova,
+    Iasi,
+    Lugoj,
+    Mehadia,
+    Neamt,
+    Oradea,
+    Pitesti,
+    RimnicuVilcea,
+    Sibiu,
+    Timisoara,
+    Urziceni,
+    Vaslui,
+    Zerind,
+}
+
+impl City {
+    pub const ALL: [City; 20] = [
+        City::Arad,
+        City::Bucharest,
+        City::Craiova,
+        City::Drobeta,
+        City::Eforie,
+        City::Fagaras,
+        City::Giurgiu,
+        City::Hirsova,
+        City::Iasi,
+        City::Lugoj,
+        City::Mehadia,
+        City::Neamt,
+        City::Oradea,
+        City::Pitesti,
+        City::RimnicuVilcea,
+        City::Sibiu,
+        City::Timisoara,
+        City::Urziceni,
+        City::Vaslui,
+        City::Zerind,
+    ];
+
+    /// Straight-line distance to Bucharest, from Figure 3.16.
+    pub fn straight_line_distance_to_bucharest(&self) -> Cost {
+        match self {
+            City::Arad => 366.0,
+            City::Bucharest => 0.0,
+            City::Craiova => 160.0,
+            City::Drobeta => 242.0,
+            City::Eforie => 161.0,
+            City::Fagaras => 176.0,
+            City::Giurgiu => 77.0,
+            City::Hirsova => 151.0,
+            City::Iasi => 226.0,
+            City::Lugoj => 244.0,
+            City::Mehadia => 241.0,
+            City::Neamt => 234.0,
+            City::Oradea => 380.0,
+            City::Pitesti => 100.0,
+            City::RimnicuVilcea => 193.0,
+            City::Sibiu => 253.0,
+            City::Timisoara => 329.0,
+            City::Urziceni => 80.0,
+            City::Vaslui => 199.0,
+            City::Zerind => 374.0,
+        }
+    }
+
+    /// Cities joined to this one by a road, with the road distance, in alphabetical order.
+    pub fn neighbours(&self) -> Vec<(City, Cost)> {
+        let mut neighbours: Vec<(City, Cost)> = ROADS
+            .iter()
+            .filter_map(|&(a, b, distance)| {
+                if a == *self {
+                    Some((b, distance))
+                } else if b == *self {
+                    Some((a, distance))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        neighbours.sort_by_key(|(city, _)| *city);
+        neighbours
+    }
+
+    /// Length of the road between two cities, if there is one.
+    pub fn road_distance(&self, other: &City) -> Option<Cost> {
+        self.neighbours()
+            .into_iter()
+            .find(|(city, _)| city == other)
+            .map(|(_, distance)| distance)
+    }
+}
+
+impl fmt::Display for City {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            City::RimnicuVilcea => "Rimnicu Vilcea",
+            _ => return write!(f, "{:?}", self),
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Roads from Figure 3.1. Every road can be driven in both directions.
+pub const ROADS: [(City, City, Cost); 23] = [
+    (City::Arad, City::Zerind, 75.0),
+    (City::Arad, City::Sibiu, 140.0),
+    (City::Arad, City::Timisoara, 118.0),
+    (City::Zerind, City::Oradea, 71.0),
+    (City::Oradea, City::Sibiu, 151.0),
+    (City::Timisoara, City::Lugoj, 111.0),
+    (City::Lugoj, City::Mehadia, 70.0),
+    (City::Mehadia, City::Drobeta, 75.0),
+    (City::Drobeta, City::Craiova, 120.0),
+    (City::Craiova, City::RimnicuVilcea, 146.0),
+    (City::Craiova, City::Pitesti, 138.0),
+    (City::Sibiu, City::Fagaras, 99.0),
+    (City::Sibiu, City::RimnicuVilcea, 80.0),
+    (City::RimnicuVilcea, City::Pitesti, 97.0),
+    (City::Fagaras, City::Bucharest, 211.0),
+    (City::Pitesti, City::Bucharest, 101.0),
+    (City::Bucharest, City::Giurgiu, 90.0),
+    (City::Bucharest, City::Urziceni, 85.0),
+    (City::Urziceni, City::Hirsova, 98.0),
+    (City::Hirsova, City::Eforie, 86.0),
+    (City::Urziceni, City::Vaslui, 142.0),
+    (City::Vaslui, City::Iasi, 92.0),
+    (City::Iasi, City::Neamt, 87.0),
+];
+
+/// Route-finding problem on the Romania map. An action is the city to drive to next, and the step
+/// cost is the road distance.
+///
+/// The book only gives straight-line distances to Bucharest, so the heuristic is the
+/// straight-line distance when the goal is Bucharest and 0, which is trivially admissible,
+/// otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RomaniaProblem {
+    pub start: City,
+    pub goal: City,
+}
+
+impl RomaniaProblem {
+    pub fn new(start: City, goal: City) -> Self {
+        Self { start, goal }
+    }
+
+    /// The book's example problem: from Arad to Bucharest.
+    pub fn arad_to_bucharest() -> Self {
+        Self::new(City::Arad, City::Bucharest)
+    }
+}
+
+impl Problem for RomaniaProblem {
+    type State = City;
+    type Action = City;
+
+    fn initial_state(&self) -> Self::State {
+        self.start
+    }
+
+    fn actions(&self, state: &Self::State) -> Vec<Self::Action> {
+        state
+            .neighbours()
+            .into_iter()
+            .map(|(city, _)| city)
+            .collect()
+    }
+
+    fn result(&self, _state: &Self::State, action: &Self::Action) -> Self::State {
+        *action
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        *state == self.goal
+    }
+
+    fn step_cost(&self, state: &Self::State, action: &Self::Action, _: &Self::State) -> Cost {
+        state
+            .road_distance(action)
+            .expect("actions only drive along roads")
+    }
+}
+
+impl HeuristicProblem for RomaniaProblem {
+    fn heuristic(&self, state: &Self::State) -> Cost {
+        if self.goal == City::Bucharest {
+            state.straight_line_distance_to_bucharest()
+        } else {
+            0.0
+        }
+    }
+}
+
+impl InvertibleProblem for RomaniaProblem {
+    fn goal_state(&self) -> Self::State {
+        self.goal
+    }
+
+    fn predecessors(&self, state: &Self::State) -> Vec<(Self::State, Self::Action)> {
+        // Roads are two-way, so a city is reached from each of its neighbours.
+        self.actions(state)
+            .into_iter()
+            .map(|city| (city, *state))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bidirectional::bidirectional_breadth_first_search;
+    use crate::informed::{astar_search, greedy_best_first_search};
+    use crate::uninformed::{
+        breadth_first_search, depth_first_search, iterative_deepening_search, uniform_cost_search,
+    };
+    use crate::SearchResult;
+    use City::*;
+
+    fn route(result: &SearchResult<City, City>) -> (Vec<City>, Cost) {
+        let solution = result.solution().unwrap();
+        (solution.states.clone(), solution.path_cost)
+    }
+
+    #[test]
+    fn test_map_is_connected_and_symmetric() {
+        for city in City::ALL {
+            assert!(!city.neighbours().is_empty(), "{} has no roads", city);
+            for (neighbour, distance) in city.neighbours() {
+                assert_eq!(neighbour.road_distance(&city), Some(distance));
+            }
+        }
+        for city in City::ALL {
+            let problem = RomaniaProblem::new(city, Bucharest);
+            assert!(breadth_first_search(&problem).solution().is_some());
+        }
+    }
+
+    #[test]
+    fn test_heuristic_is_admissible() {
+        for city in City::ALL {
+            let problem = RomaniaProblem::new(city, Bucharest);
+            let optimal = uniform_cost_search(&problem).solution().unwrap().path_cost;
+            assert!(problem.heuristic(&city) <= optimal, "{}", city);
+        }
+    }
+
+    #[test]
+    fn test_optimal_route_arad_to_bucharest() {
+        // Figure 3.18: the optimal route goes through Rimnicu Vilcea and Pitesti.
+        let problem = RomaniaProblem::arad_to_bucharest();
+        let optimal = (vec![Arad, Sibiu, RimnicuVilcea, Pitesti, Bucharest], 418.0);
+        assert_eq!(route(&uniform_cost_search(&problem)), optimal);
+        assert_eq!(route(&astar_search(&problem)), optimal);
+    }
+
+    #[test]
+    fn test_fewest_actions_arad_to_bucharest() {
+        let problem = RomaniaProblem::arad_to_bucharest();
+        let fewest = (vec![Arad, Sibiu, Fagaras, Bucharest], 450.0);
+        assert_eq!(route(&breadth_first_search(&problem)), fewest);
+        assert_eq!(route(&iterative_deepening_search(&problem)), fewest);
+        assert_eq!(route(&bidirectional_breadth_first_search(&problem)), fewest);
+    }
+
+    #[test]
+    fn test_greedy_route_arad_to_bucharest() {
+        // Figure 3.17: greedy search heads through Fagaras, which is 32 miles longer.
+        let problem = RomaniaProblem::arad_to_bucharest();
+        let result = greedy_best_first_search(&problem);
+        assert_eq!(
+            route(&result),
+            (vec![Arad, Sibiu, Fagaras, Bucharest], 450.0)
+        );
+        assert!(result.stats.nodes_expanded < astar_search(&problem).stats.nodes_expanded);
+    }
+
+    #[test]
+    fn test_bidirectional_search_expands_fewer_nodes() {
+        // Across the whole map, from the far west to the far north east.
+        let problem = RomaniaProblem::new(Timisoara, Neamt);
+        let unidirectional = breadth_first_search(&problem);
+        let bidirectional = bidirectional_breadth_first_search(&problem);
+        assert_eq!(
+            bidirectional.solution().unwrap().len(),
+            unidirectional.solution().unwrap().len()
+        );
+        assert!(
+            bidirectional.stats.nodes_expanded < unidirectional.stats.nodes_expanded,
+            "bidirectional {:?} vs breadth-first {:?}",
+            bidirectional.stats,
+            unidirectional.stats
+        );
+    }
+
+    #[test]
+    fn test_depth_first_search_finds_a_route() {
+        let problem = RomaniaProblem::arad_to_bucharest();
+        let solution = depth_first_search(&problem).solution().unwrap().clone();
+        assert_eq!(solution.states.first(), Some(&Arad));
+        assert_eq!(solution.states.last(), Some(&Bucharest));
+        assert!(solution.path_cost >= 418.0);
+    }
+}
@@ -0,0 +1,458 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// The sliding-tile puzzle: the 8-puzzle, 15-puzzle and larger.
+//
+// See:
+// -  Chapter 3: Solving Problems by Searching, section 3.2.1 and section 3.6
+
+use std::fmt;
+
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use thiserror::Error;
+
+use crate::bidirectional::InvertibleProblem;
+use crate::informed::HeuristicProblem;
+use crate::{Cost, Problem};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BoardError {
+    #[error("board width must be at least 2, got {0}")]
+    TooNarrow(usize),
+
+    #[error("a board of width {width} needs {expected} tiles, got {actual}")]
+    WrongTileCount {
+        width: usize,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[error("tiles must be each of the numbers 0 to {max} exactly once")]
+    NotAPermutation { max: usize },
+}
+
+/// Direction the blank moves in. Moving the blank up slides the tile above it down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SlideAction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl SlideAction {
+    pub const ALL: [SlideAction; 4] = [
+        SlideAction::Up,
+        SlideAction::Down,
+        SlideAction::Left,
+        SlideAction::Right,
+    ];
+
+    pub fn opposite(&self) -> SlideAction {
+        match self {
+            SlideAction::Up => SlideAction::Down,
+            SlideAction::Down => SlideAction::Up,
+            SlideAction::Left => SlideAction::Right,
+            SlideAction::Right => SlideAction::Left,
+        }
+    }
+}
+
+/// A width by width board, stored row by row. Tile 0 is the blank. As in the book's Figure 3.3,
+/// the goal has the blank in the top left corner followed by the tiles in order, so in the goal
+/// every tile is at the index equal to its number.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Board {
+    width: usize,
+    tiles: Vec<u8>,
+}
+
+impl Board {
+    pub fn new(width: usize, tiles: Vec<u8>) -> Result<Self, BoardError> {
+        if width < 2 {
+            return Err(BoardError::TooNarrow(width));
+        }
+        let expected = width * width;
+        if tiles.len() != expected {
+            return Err(BoardError::WrongTileCount {
+                width,
+                expected,
+                actual: tiles.len(),
+            });
+        }
+        let mut seen = vec![false; expected];
+        for &tile in &tiles {
+            match seen.get_mut(tile as usize) {
+                Some(seen) if !*seen => *seen = true,
+                _ => return Err(BoardError::NotAPermutation { max: expected - 1 }),
+            }
+        }
+        Ok(Self { width, tiles })
+    }
+
+    pub fn goal(width: usize) -> Self {
+        assert!(width >= 2, "board width must be at least 2");
+        Self {
+            width,
+            tiles: (0..(width * width) as u8).collect(),
+        }
+    }
+
+    /// A board made by sliding the blank randomly moves times from the goal, never immediately
+    /// undoing the previous move. Scrambled boards are always solvable, and need at most moves
+    /// actions to solve.
+    pub fn scrambled(width: usize, moves: usize, seed: u64) -> Self {
+        let mut rng = rand_pcg::Pcg64::seed_from_u64(seed);
+        let mut board = Self::goal(width);
+        let mut previous: Option<SlideAction> = None;
+        for _ in 0..moves {
+            let actions: Vec<SlideAction> = board
+                .actions()
+                .into_iter()
+                .filter(|action| Some(action.opposite()) != previous)
+                .collect();
+            let action = *actions.choose(&mut rng).unwrap();
+            board = board.slide(action);
+            previous = Some(action);
+        }
+        board
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn tiles(&self) -> &[u8] {
+        &self.tiles
+    }
+
+    pub fn is_goal(&self) -> bool {
+        self.tiles
+            .iter()
+            .enumerate()
+            .all(|(i, &tile)| i == tile as usize)
+    }
+
+    fn blank_index(&self) -> usize {
+        self.tiles.iter().position(|&tile| tile == 0).unwrap()
+    }
+
+    /// Directions the blank can move in without leaving the board.
+    pub fn actions(&self) -> Vec<SlideAction> {
+        let (row, column) = self.row_column(self.blank_index());
+        SlideAction::ALL
+            .into_iter()
+            .filter(|action| match action {
+                SlideAction::Up => row > 0,
+                SlideAction::Down => row < self.width - 1,
+                SlideAction::Left => column > 0,
+                SlideAction::Right => column < self.width - 1,
+            })
+            .collect()
+    }
+
+    /// The board after moving the blank. Panics if the blank would leave the board.
+    pub fn slide(&self, action: SlideAction) -> Board {
+        let blank = self.blank_index();
+        let (row, column) = self.row_column(blank);
+        let target = match action {
+            SlideAction::Up if row > 0 => blank - self.width,
+            SlideAction::Down if row < self.width - 1 => blank + self.width,
+            SlideAction::Left if column > 0 => blank - 1,
+            SlideAction::Right if column < self.width - 1 => blank + 1,
+            _ => panic!("cannot move the blank {:?} off the board", action),
+        };
+        let mut tiles = self.tiles.clone();
+        tiles.swap(blank, target);
+        Board {
+            width: self.width,
+            tiles,
+        }
+    }
+
+    /// Whether the goal can be reached from this board. Half of all arrangements of the tiles
+    /// cannot be solved. Every move swaps the blank with a tile, which flips the parity of the
+    /// permutation, and also flips the parity of the blank's Manhattan distance from its goal
+    /// square. So the two parities must be equal, and it turns out that this is also sufficient.
+    pub fn is_solvable(&self) -> bool {
+        let mut visited = vec![false; self.tiles.len()];
+        let mut cycles = 0;
+        for start in 0..self.tiles.len() {
+            if visited[start] {
+                continue;
+            }
+            cycles += 1;
+            let mut i = start;
+            while !visited[i] {
+                visited[i] = true;
+                i = self.tiles[i] as usize;
+            }
+        }
+        let permutation_parity = (self.tiles.len() - cycles) % 2;
+        let (row, column) = self.row_column(self.blank_index());
+        permutation_parity == (row + column) % 2
+    }
+
+    /// h1: the number of tiles, not counting the blank, that are not in their goal square.
+    pub fn misplaced_tiles(&self) -> usize {
+        self.tiles
+            .iter()
+            .enumerate()
+            .filter(|&(i, &tile)| tile != 0 && i != tile as usize)
+            .count()
+    }
+
+    /// h2: the sum of the Manhattan distances of the tiles, not counting the blank, from their
+    /// goal squares.
+    pub fn manhattan_distance(&self) -> usize {
+        self.tiles
+            .iter()
+            .enumerate()
+            .filter(|&(_, &tile)| tile != 0)
+            .map(|(i, &tile)| {
+                let (row, column) = self.row_column(i);
+                let (goal_row, goal_column) = self.row_column(tile as usize);
+                row.abs_diff(goal_row) + column.abs_diff(goal_column)
+            })
+            .sum()
+    }
+
+    fn row_column(&self, index: usize) -> (usize, usize) {
+        (index / self.width, index % self.width)
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cell_width = (self.tiles.len() - 1).to_string().len();
+        for row in self.tiles.chunks(self.width) {
+            let cells: Vec<String> = row
+                .iter()
+                .map(|&tile| match tile {
+                    0 => format!("{:>1$}", "_", cell_width),
+                    _ => format!("{:>1$}", tile, cell_width),
+                })
+                .collect();
+            writeln!(f, "{}", cells.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Heuristics from section 3.6 for the sliding-tile puzzle. Both are admissible, and Manhattan
+/// distance dominates misplaced tiles.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PuzzleHeuristic {
+    MisplacedTiles,
+    #[default]
+    ManhattanDistance,
+}
+
+/// Search problem for solving a sliding-tile puzzle from a given board.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlidingPuzzle {
+    pub initial: Board,
+    pub heuristic: PuzzleHeuristic,
+}
+
+impl SlidingPuzzle {
+    pub fn new(initial: Board) -> Self {
+        Self {
+            initial,
+            heuristic: PuzzleHeuristic::default(),
+        }
+    }
+
+    pub fn with_heuristic(mut self, heuristic: PuzzleHeuristic) -> Self {
+        self.heuristic = heuristic;
+        self
+    }
+}
+
+impl Problem for SlidingPuzzle {
+    type State = Board;
+    type Action = SlideAction;
+
+    fn initial_state(&self) -> Self::State {
+        self.initial.clone()
+    }
+
+    fn actions(&self, state: &Self::State) -> Vec<Self::Action> {
+        state.actions()
+    }
+
+    fn result(&self, state: &Self::State, action: &Self::Action) -> Self::State {
+        state.slide(*action)
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        state.is_goal()
+    }
+}
+
+impl HeuristicProblem for SlidingPuzzle {
+    fn heuristic(&self, state: &Self::State) -> Cost {
+        match self.heuristic {
+            PuzzleHeuristic::MisplacedTiles => state.misplaced_tiles() as Cost,
+            PuzzleHeuristic::ManhattanDistance => state.manhattan_distance() as Cost,
+        }
+    }
+}
+
+impl InvertibleProblem for SlidingPuzzle {
+    fn goal_state(&self) -> Self::State {
+        Board::goal(self.initial.width())
+    }
+
+    fn predecessors(&self, state: &Self::State) -> Vec<(Self::State, Self::Action)> {
+        // Every move is undone by moving the blank back the way it came.
+        state
+            .actions()
+            .into_iter()
+            .map(|action| (state.slide(action), action.opposite()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bidirectional::bidirectional_breadth_first_search;
+    use crate::informed::astar_search;
+    use crate::uninformed::breadth_first_search;
+
+    /// The start state of Figure 3.25, which takes 26 actions to solve.
+    fn figure_3_25() -> Board {
+        Board::new(3, vec![7, 2, 4, 5, 0, 6, 8, 3, 1]).unwrap()
+    }
+
+    #[test]
+    fn test_new_validates_tiles() {
+        assert_eq!(Board::new(1, vec![0]), Err(BoardError::TooNarrow(1)));
+        assert_eq!(
+            Board::new(2, vec![0, 1, 2]),
+            Err(BoardError::WrongTileCount {
+                width: 2,
+                expected: 4,
+                actual: 3
+            })
+        );
+        assert_eq!(
+            Board::new(2, vec![0, 1, 2, 2]),
+            Err(BoardError::NotAPermutation { max: 3 })
+        );
+        assert_eq!(
+            Board::new(2, vec![0, 1, 2, 4]),
+            Err(BoardError::NotAPermutation { max: 3 })
+        );
+    }
+
+    #[test]
+    fn test_heuristics_figure_3_25() {
+        let board = figure_3_25();
+        assert_eq!(board.misplaced_tiles(), 8);
+        assert_eq!(board.manhattan_distance(), 18);
+        assert_eq!(Board::goal(3).manhattan_distance(), 0);
+    }
+
+    #[test]
+    fn test_is_solvable() {
+        assert!(Board::goal(3).is_solvable());
+        assert!(figure_3_25().is_solvable());
+        // Swapping two tiles makes any board unsolvable.
+        assert!(!Board::new(3, vec![0, 2, 1, 3, 4, 5, 6, 7, 8])
+            .unwrap()
+            .is_solvable());
+        assert!(!Board::new(
+            4,
+            vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 15, 14]
+        )
+        .unwrap()
+        .is_solvable());
+        for seed in 0..20 {
+            assert!(Board::scrambled(4, 50, seed).is_solvable());
+        }
+    }
+
+    #[test]
+    fn test_unsolvable_board_has_no_solution() {
+        let board = Board::new(2, vec![0, 2, 1, 3]).unwrap();
+        let result = breadth_first_search(&SlidingPuzzle::new(board));
+        assert!(result.solution().is_none());
+        // Only half of the 4! boards are reachable.
+        assert_eq!(result.stats.nodes_expanded, 12);
+    }
+
+    #[test]
+    fn test_scrambled_is_deterministic() {
+        assert_eq!(Board::scrambled(3, 20, 7), Board::scrambled(3, 20, 7));
+        assert_ne!(Board::scrambled(3, 20, 7), Board::goal(3));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(figure_3_25().to_string(), "7 2 4\n5 _ 6\n8 3 1\n");
+        assert_eq!(
+            Board::goal(4).to_string(),
+            " _  1  2  3\n 4  5  6  7\n 8  9 10 11\n12 13 14 15\n"
+        );
+    }
+
+    #[test]
+    fn test_astar_figure_3_25() {
+        let misplaced =
+            SlidingPuzzle::new(figure_3_25()).with_heuristic(PuzzleHeuristic::MisplacedTiles);
+        let manhattan = SlidingPuzzle::new(figure_3_25());
+        let misplaced_result = astar_search(&misplaced);
+        let manhattan_result = astar_search(&manhattan);
+        assert_eq!(misplaced_result.solution().unwrap().len(), 26);
+        assert_eq!(manhattan_result.solution().unwrap().len(), 26);
+        // Manhattan distance dominates misplaced tiles, so it expands fewer nodes.
+        assert!(manhattan_result.stats.nodes_expanded < misplaced_result.stats.nodes_expanded);
+    }
+
+    #[test]
+    fn test_bidirectional_search_expands_fewer_nodes() {
+        let problem = SlidingPuzzle::new(Board::scrambled(3, 16, 1));
+        let unidirectional = breadth_first_search(&problem);
+        let bidirectional = bidirectional_breadth_first_search(&problem);
+        assert_eq!(
+            bidirectional.solution().unwrap().len(),
+            unidirectional.solution().unwrap().len()
+        );
+        assert!(
+            bidirectional.stats.nodes_expanded * 4 < unidirectional.stats.nodes_expanded,
+            "bidirectional {:?} vs breadth-first {:?}",
+            bidirectional.stats,
+            unidirectional.stats
+        );
+    }
+
+    #[test]
+    fn test_solutions_solve_the_board() {
+        let board = Board::scrambled(4, 20, 3);
+        let solution = astar_search(&SlidingPuzzle::new(board.clone()))
+            .solution()
+            .unwrap()
+            .clone();
+        let solved = solution
+            .actions
+            .iter()
+            .fold(board, |board, action| board.slide(*action));
+        assert!(solved.is_goal());
+    }
+}
@@ -0,0 +1,170 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// The traveling salesperson problem: find the shortest tour that visits every city exactly once.
+//
+// See:
+// -  Chapter 3: Solving Problems by Searching, section 3.2.2
+// -  Chapter 4: Search in Complex Environments, section 4.1
+
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::local::Optimizable;
+
+/// Order in which the cities are visited, returning from the last city to the first.
+pub type Tour = Vec<usize>;
+
+/// Complete-state formulation of the traveling salesperson problem on points in the plane. A
+/// neighbour of a tour reverses one section of it, the "2-opt" move, which replaces two edges of
+/// the tour with two others. The value is minus the tour length.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TravelingSalesperson {
+    pub cities: Vec<(f64, f64)>,
+}
+
+impl TravelingSalesperson {
+    pub fn new(cities: Vec<(f64, f64)>) -> Self {
+        assert!(cities.len() >= 3, "a tour needs at least 3 cities");
+        Self { cities }
+    }
+
+    /// Cities at random points in the unit square.
+    pub fn random(cities: usize, seed: u64) -> Self {
+        let mut rng = rand_pcg::Pcg64::seed_from_u64(seed);
+        Self::new(
+            (0..cities)
+                .map(|_| (rng.gen::<f64>(), rng.gen::<f64>()))
+                .collect(),
+        )
+    }
+
+    pub fn distance(&self, a: usize, b: usize) -> f64 {
+        let (x1, y1) = self.cities[a];
+        let (x2, y2) = self.cities[b];
+        (x1 - x2).hypot(y1 - y2)
+    }
+
+    /// Length of the closed tour, including the way back to the start.
+    pub fn tour_length(&self, tour: &[usize]) -> f64 {
+        (0..tour.len())
+            .map(|i| self.distance(tour[i], tour[(i + 1) % tour.len()]))
+            .sum()
+    }
+
+    fn reverse(tour: &[usize], i: usize, j: usize) -> Tour {
+        let mut neighbour = tour.to_vec();
+        neighbour[i..=j].reverse();
+        neighbour
+    }
+}
+
+impl Optimizable for TravelingSalesperson {
+    type State = Tour;
+
+    fn random_state(&self, rng: &mut rand_pcg::Pcg64) -> Self::State {
+        let mut tour: Tour = (0..self.cities.len()).collect();
+        tour.shuffle(rng);
+        tour
+    }
+
+    fn neighbours(&self, state: &Self::State) -> Vec<Self::State> {
+        let n = state.len();
+        (0..n - 1)
+            .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+            .map(|(i, j)| Self::reverse(state, i, j))
+            .collect()
+    }
+
+    fn value(&self, state: &Self::State) -> f64 {
+        -self.tour_length(state)
+    }
+
+    fn random_neighbour(
+        &self,
+        state: &Self::State,
+        rng: &mut rand_pcg::Pcg64,
+    ) -> Option<Self::State> {
+        let i = rng.gen_range(0..state.len() - 1);
+        let j = rng.gen_range(i + 1..state.len());
+        Some(Self::reverse(state, i, j))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::local::{random_restart_hill_climbing, simulated_annealing, ExponentialSchedule};
+
+    /// Cities evenly spaced around the unit circle, where the shortest tour goes around the
+    /// circle.
+    fn circle(n: usize) -> (TravelingSalesperson, f64) {
+        let cities = (0..n)
+            .map(|i| {
+                let angle = 2.0 * PI * i as f64 / n as f64;
+                (angle.cos(), angle.sin())
+            })
+            .collect();
+        let side = 2.0 * (PI / n as f64).sin();
+        (TravelingSalesperson::new(cities), side * n as f64)
+    }
+
+    #[test]
+    fn test_tour_length() {
+        let square =
+            TravelingSalesperson::new(vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+        assert_eq!(square.tour_length(&[0, 1, 2, 3]), 4.0);
+        assert_eq!(
+            square.tour_length(&[0, 2, 1, 3]),
+            2.0 + 2.0 * 2.0_f64.sqrt()
+        );
+    }
+
+    #[test]
+    fn test_neighbours_are_tours() {
+        let problem = TravelingSalesperson::random(6, 0);
+        let tour = vec![0, 1, 2, 3, 4, 5];
+        let neighbours = problem.neighbours(&tour);
+        assert_eq!(neighbours.len(), 15);
+        for mut neighbour in neighbours {
+            neighbour.sort();
+            assert_eq!(neighbour, tour);
+        }
+    }
+
+    #[test]
+    fn test_simulated_annealing_finds_optimal_tour() {
+        let (problem, optimal) = circle(10);
+        let schedule = ExponentialSchedule {
+            initial: 1.0,
+            decay: 0.002,
+            limit: 5000,
+        };
+        let result = simulated_annealing(&problem, &schedule, 0);
+        assert!((-result.value - optimal).abs() < 1e-9, "{}", -result.value);
+        assert!(result.stats.accepted_worse_moves > 0);
+        assert!(result.stats.rejected_moves > 0);
+    }
+
+    #[test]
+    fn test_hill_climbing_finds_optimal_tour() {
+        let (problem, optimal) = circle(10);
+        let result = random_restart_hill_climbing(&problem, 20, 0);
+        assert!((-result.value - optimal).abs() < 1e-9);
+    }
+}
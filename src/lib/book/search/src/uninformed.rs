@@ -0,0 +1,286 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Uninformed search strategies
+//
+// See:
+// -  Chapter 3: Solving Problems by Searching, section 3.4
+
+use std::collections::{HashSet, VecDeque};
+
+use crate::informed::best_first_search;
+use crate::{NodeArena, Problem, SearchOutcome, SearchResult, SearchStats};
+
+/// Generate the children of the node at index, counting the work in stats.
+pub(crate) fn expand<_Problem: Problem>(
+    problem: &_Problem,
+    arena: &mut NodeArena<_Problem::State, _Problem::Action>,
+    stats: &mut SearchStats,
+    index: usize,
+) -> Vec<usize> {
+    stats.nodes_expanded += 1;
+    let actions = problem.actions(&arena.get(index).state);
+    stats.nodes_generated += actions.len();
+    actions
+        .into_iter()
+        .map(|action| arena.child(problem, index, action))
+        .collect()
+}
+
+/// Breadth-first search, Figure 3.9. Finds a solution with the fewest actions. The goal test is
+/// applied when a node is generated rather than when it is expanded, which saves expanding a whole
+/// layer of the tree.
+pub fn breadth_first_search<_Problem: Problem>(
+    problem: &_Problem,
+) -> SearchResult<_Problem::State, _Problem::Action> {
+    let mut arena = NodeArena::new();
+    let mut stats = SearchStats {
+        nodes_generated: 1,
+        ..SearchStats::default()
+    };
+    let root = arena.root(problem.initial_state());
+    if problem.is_goal(&arena.get(root).state) {
+        return found(&arena, root, stats);
+    }
+
+    let mut frontier = VecDeque::from([root]);
+    let mut reached = HashSet::from([arena.get(root).state.clone()]);
+    stats.observe_frontier(frontier.len());
+    while let Some(node) = frontier.pop_front() {
+        for child in expand(problem, &mut arena, &mut stats, node) {
+            let state = &arena.get(child).state;
+            if problem.is_goal(state) {
+                return found(&arena, child, stats);
+            }
+            if reached.insert(state.clone()) {
+                frontier.push_back(child);
+            }
+        }
+        stats.observe_frontier(frontier.len());
+    }
+    not_found(stats)
+}
+
+/// Depth-first search. This is the graph search version, which never expands a state twice, so it
+/// is complete in finite state spaces. The solution is not necessarily the shortest
+/// or the cheapest.
+pub fn depth_first_search<_Problem: Problem>(
+    problem: &_Problem,
+) -> SearchResult<_Problem::State, _Problem::Action> {
+    let mut arena = NodeArena::new();
+    let mut stats = SearchStats {
+        nodes_generated: 1,
+        ..SearchStats::default()
+    };
+    let root = arena.root(problem.initial_state());
+    let mut frontier = vec![root];
+    let mut expanded = HashSet::new();
+    stats.observe_frontier(frontier.len());
+    while let Some(node) = frontier.pop() {
+        if !expanded.insert(arena.get(node).state.clone()) {
+            continue;
+        }
+        if problem.is_goal(&arena.get(node).state) {
+            return found(&arena, node, stats);
+        }
+        // Push children in reverse so that the first action is explored first.
+        let children = expand(problem, &mut arena, &mut stats, node);
+        for child in children.into_iter().rev() {
+            if !expanded.contains(&arena.get(child).state) {
+                frontier.push(child);
+            }
+        }
+        stats.observe_frontier(frontier.len());
+    }
+    not_found(stats)
+}
+
+/// Uniform-cost search, i.e. Dijkstra's algorithm: best-first search ordered by path cost. Finds
+/// the cheapest solution as long as every step cost is positive. The goal test is applied when a
+/// node is expanded, because a cheaper path to the goal may still be in the frontier when the goal
+/// is first generated.
+pub fn uniform_cost_search<_Problem: Problem>(
+    problem: &_Problem,
+) -> SearchResult<_Problem::State, _Problem::Action> {
+    best_first_search(problem, |_, path_cost| path_cost)
+}
+
+/// Depth-limited search, Figure 3.12. Depth-first search that does not expand nodes at depth
+/// limit, so it only finds solutions with at most limit actions. It keeps no reached set, only
+/// checking for cycles along the current path, so its frontier stays linear in the limit. Unlike
+/// the book's version, every node generated is kept until the search returns, so that the
+/// solution can be read back by following parents.
+///
+/// Returns SearchOutcome::Cutoff if the limit stopped the search, and SearchOutcome::NotFound only
+/// if there is no solution at any depth.
+pub fn depth_limited_search<_Problem: Problem>(
+    problem: &_Problem,
+    limit: usize,
+) -> SearchResult<_Problem::State, _Problem::Action> {
+    let mut arena = NodeArena::new();
+    let mut stats = SearchStats {
+        nodes_generated: 1,
+        ..SearchStats::default()
+    };
+    let root = arena.root(problem.initial_state());
+    let mut frontier = vec![root];
+    let mut cutoff = false;
+    stats.observe_frontier(frontier.len());
+    while let Some(node) = frontier.pop() {
+        if problem.is_goal(&arena.get(node).state) {
+            return found(&arena, node, stats);
+        }
+        if arena.get(node).depth >= limit {
+            cutoff = true;
+            continue;
+        }
+        let children = expand(problem, &mut arena, &mut stats, node);
+        for child in children.into_iter().rev() {
+            let is_cycle = arena.is_on_path(node, &arena.get(child).state);
+            if !is_cycle {
+                frontier.push(child);
+            }
+        }
+        stats.observe_frontier(frontier.len());
+    }
+    SearchResult {
+        outcome: if cutoff {
+            SearchOutcome::Cutoff
+        } else {
+            SearchOutcome::NotFound
+        },
+        stats,
+    }
+}
+
+/// Iterative deepening search, Figure 3.12. Runs depth-limited search with limits 0, 1, 2, ...
+/// until it finds a solution or proves there is none. Like breadth-first search it finds a
+/// solution with the fewest actions, but like depth-first search its frontier stays small. The
+/// nodes generated by each iteration are kept until that iteration returns, as in
+/// depth_limited_search. The stats are the totals over every iteration.
+///
+/// In an infinite state space with no solution this never returns.
+pub fn iterative_deepening_search<_Problem: Problem>(
+    problem: &_Problem,
+) -> SearchResult<_Problem::State, _Problem::Action> {
+    let mut stats = SearchStats::default();
+    for limit in 0.. {
+        let result = depth_limited_search(problem, limit);
+        stats.add(&result.stats);
+        if !matches!(result.outcome, SearchOutcome::Cutoff) {
+            return SearchResult {
+                outcome: result.outcome,
+                stats,
+            };
+        }
+    }
+    unreachable!()
+}
+
+pub(crate) fn found<_State: Clone, _Action: Clone>(
+    arena: &NodeArena<_State, _Action>,
+    node: usize,
+    stats: SearchStats,
+) -> SearchResult<_State, _Action> {
+    SearchResult {
+        outcome: SearchOutcome::Found(arena.solution(node)),
+        stats,
+    }
+}
+
+pub(crate) fn not_found<_State, _Action>(stats: SearchStats) -> SearchResult<_State, _Action> {
+    SearchResult {
+        outcome: SearchOutcome::NotFound,
+        stats,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_problems::SmallGraph;
+
+    #[test]
+    fn test_breadth_first_search_finds_fewest_actions() {
+        let result = breadth_first_search(&SmallGraph::new());
+        let solution = result.solution().unwrap();
+        assert_eq!(solution.states, vec!["S", "G"]);
+        assert_eq!(solution.path_cost, 10.0);
+        assert_eq!(result.stats.nodes_expanded, 1);
+    }
+
+    #[test]
+    fn test_uniform_cost_search_finds_cheapest() {
+        let result = uniform_cost_search(&SmallGraph::new());
+        let solution = result.solution().unwrap();
+        assert_eq!(solution.states, vec!["S", "A", "B", "G"]);
+        assert_eq!(solution.actions, vec!["A", "B", "G"]);
+        assert_eq!(solution.path_cost, 3.0);
+    }
+
+    #[test]
+    fn test_depth_first_search_finds_a_solution() {
+        let result = depth_first_search(&SmallGraph::new());
+        let solution = result.solution().unwrap();
+        assert_eq!(solution.states, vec!["S", "A", "B", "G"]);
+        assert!(result.stats.max_frontier_size >= 1);
+    }
+
+    #[test]
+    fn test_depth_limited_search_reports_cutoff() {
+        let problem = SmallGraph {
+            edges: std::collections::HashMap::from([
+                ("S", vec![("A", 1.0)]),
+                ("A", vec![("B", 1.0), ("S", 1.0)]),
+                ("B", vec![("G", 1.0)]),
+                ("G", vec![]),
+            ]),
+            start: "S",
+            goal: "G",
+        };
+        assert_eq!(
+            depth_limited_search(&problem, 2).outcome,
+            SearchOutcome::Cutoff
+        );
+        assert_eq!(
+            depth_limited_search(&problem, 3).solution().unwrap().len(),
+            3
+        );
+
+        let unreachable = SmallGraph {
+            goal: "Z",
+            ..problem
+        };
+        assert_eq!(
+            depth_limited_search(&unreachable, 10).outcome,
+            SearchOutcome::NotFound
+        );
+        assert_eq!(
+            iterative_deepening_search(&unreachable).outcome,
+            SearchOutcome::NotFound
+        );
+    }
+
+    #[test]
+    fn test_iterative_deepening_matches_breadth_first() {
+        let problem = SmallGraph::new();
+        let result = iterative_deepening_search(&problem);
+        assert_eq!(result.solution(), breadth_first_search(&problem).solution());
+        // Limits 0 and 1: the root is generated twice and expanded once.
+        assert_eq!(result.stats.nodes_expanded, 1);
+        assert_eq!(result.stats.nodes_generated, 4);
+    }
+}
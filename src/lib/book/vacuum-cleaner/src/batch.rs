@@ -0,0 +1,155 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Running many independent simulations in parallel.
+
+use rayon::prelude::*;
+
+use crate::comparison::{mean, standard_deviation};
+use crate::{Agent, Environment, Simulation};
+
+/// BatchConfig describes one kind of simulation in a batch. Environments and agents are created
+/// inside the worker threads, so they do not need to be Send, but the factories must be Sync.
+pub struct BatchConfig<'a, _Environment, _Agent> {
+    pub name: String,
+    pub time_steps: i32,
+
+    /// Creates the environment for a trial from the trial's seed.
+    pub new_environment: Box<dyn Fn(u64) -> _Environment + Send + Sync + 'a>,
+
+    /// Creates the agent for a trial from the trial's seed.
+    pub new_agent: Box<dyn Fn(u64) -> _Agent + Send + Sync + 'a>,
+}
+
+impl<'a, _Environment, _Agent> BatchConfig<'a, _Environment, _Agent> {
+    pub fn new<E, A>(name: &str, time_steps: i32, new_environment: E, new_agent: A) -> Self
+    where
+        E: Fn(u64) -> _Environment + Send + Sync + 'a,
+        A: Fn(u64) -> _Agent + Send + Sync + 'a,
+    {
+        Self {
+            name: name.to_string(),
+            time_steps,
+            new_environment: Box::new(new_environment),
+            new_agent: Box::new(new_agent),
+        }
+    }
+}
+
+/// Aggregated scores for one BatchConfig.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchResult {
+    pub name: String,
+
+    /// Score of every trial, in trial order.
+    pub scores: Vec<f64>,
+    pub mean: f64,
+
+    /// Sample standard deviation.
+    pub standard_deviation: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// run_batch runs trials simulations for every config across all available threads, and returns
+/// one BatchResult per config in the same order as configs.
+///
+/// Trial i uses seed + i for both its environment and agent, for every config, so results are
+/// reproducible and configs are compared on the same sampled environments regardless of how the
+/// work is scheduled.
+pub fn run_batch<_Environment, _Agent>(
+    configs: &[BatchConfig<'_, _Environment, _Agent>],
+    trials: usize,
+    seed: u64,
+) -> Vec<BatchResult>
+where
+    _Environment: Environment,
+    _Environment::Score: Into<f64>,
+    _Agent: Agent<Action = _Environment::Action, Percept = _Environment::Percept>,
+{
+    let scores: Vec<f64> = (0..configs.len() * trials)
+        .into_par_iter()
+        .map(|job| {
+            let config = &configs[job / trials];
+            let trial_seed = seed.wrapping_add((job % trials) as u64);
+            let mut simulation = Simulation::new(
+                (config.new_environment)(trial_seed),
+                (config.new_agent)(trial_seed),
+                config.time_steps,
+            );
+            simulation.run();
+            simulation.score().into()
+        })
+        .collect();
+
+    configs
+        .iter()
+        .zip(scores.chunks(trials.max(1)))
+        .map(|(config, scores)| BatchResult {
+            name: config.name.clone(),
+            scores: scores.to_vec(),
+            mean: mean(scores),
+            standard_deviation: standard_deviation(scores),
+            min: scores.iter().copied().fold(f64::INFINITY, f64::min),
+            max: scores.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::murphy::{MurphyConfig, MurphyReflexVacuumAgent, MurphyVacuumWorldEnvironment};
+    use crate::vacuum_world::VacuumWorldEnvironment;
+
+    fn murphy_config(
+        name: &str,
+        sensor_error_probability: f64,
+    ) -> BatchConfig<'static, MurphyVacuumWorldEnvironment, MurphyReflexVacuumAgent> {
+        BatchConfig::new(
+            name,
+            100,
+            move |seed| {
+                MurphyVacuumWorldEnvironment::new(
+                    VacuumWorldEnvironment::default(),
+                    MurphyConfig {
+                        sensor_error_probability,
+                        ..MurphyConfig::default()
+                    },
+                    Rc::new(RefCell::new(rand_pcg::Pcg64::seed_from_u64(seed))),
+                )
+            },
+            |_| MurphyReflexVacuumAgent::new(),
+        )
+    }
+
+    #[test]
+    fn test_run_batch_is_reproducible_and_ordered() {
+        let configs = [murphy_config("reliable", 0.0), murphy_config("noisy", 0.4)];
+        let results = run_batch(&configs, 20, 7);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].name, "reliable");
+        assert_eq!(results[0].scores.len(), 20);
+        assert!(results[0].min <= results[0].mean && results[0].mean <= results[0].max);
+        assert!(results[0].mean > results[1].mean);
+        assert_eq!(run_batch(&configs, 20, 7), results);
+    }
+}
@@ -0,0 +1,383 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Vacuum world with a battery, where every action costs energy.
+//
+// See:
+// -  Chapter 2: Intelligent Agents, section 2.2 "Good Behavior: The Concept of Rationality"
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::vacuum_world::{
+    AllSquaresClean, ModelBasedVacuumAgent, VaccuumWorldLocalPercept, VacuumWorldAction,
+    VacuumWorldEnvironment, VacuumWorldLocation,
+};
+use crate::{Agent, Environment, PerformanceMeasure, StepEvent, Termination};
+
+/// Energy costs and charging for a BatteryVacuumWorldEnvironment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatteryConfig {
+    /// Maximum charge, and the charge at the start.
+    pub capacity: i32,
+
+    /// Charge used by Left, Right, Up, and Down.
+    pub move_cost: i32,
+
+    /// Charge used by Suck.
+    pub suck_cost: i32,
+
+    /// Charge restored by a NoOp on the dock.
+    pub charge_rate: i32,
+
+    /// Location of the charging dock.
+    pub dock_location: VacuumWorldLocation,
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 20,
+            move_cost: 1,
+            suck_cost: 3,
+            charge_rate: 5,
+            dock_location: VacuumWorldLocation { x: 0, y: 0 },
+        }
+    }
+}
+
+impl BatteryConfig {
+    /// Charge used by an action. NoOp is free.
+    pub fn action_cost(&self, action: &VacuumWorldAction) -> i32 {
+        match action {
+            VacuumWorldAction::Left
+            | VacuumWorldAction::Right
+            | VacuumWorldAction::Up
+            | VacuumWorldAction::Down => self.move_cost,
+            VacuumWorldAction::Suck => self.suck_cost,
+            VacuumWorldAction::NoOp => 0,
+        }
+    }
+}
+
+/// BatteryPercept is the local percept plus a reading of the battery level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BatteryPercept {
+    pub local: VaccuumWorldLocalPercept,
+    pub charge: i32,
+}
+
+/// BatteryVacuumWorldEnvironment is a VacuumWorldEnvironment where every action uses charge from a
+/// battery. A NoOp on the dock square recharges the battery. Actions that cost more than the
+/// remaining charge are refused and do nothing, so an agent that runs flat away from the dock is
+/// stranded for the rest of the simulation.
+///
+/// The score is still the number of clean squares. Whether a rational agent should also be
+/// rewarded for saving energy is the performance measure design question from section 2.2.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatteryVacuumWorldEnvironment {
+    world: VacuumWorldEnvironment,
+    config: BatteryConfig,
+    charge: i32,
+    last_charge_used: i32,
+}
+
+impl BatteryVacuumWorldEnvironment {
+    pub fn new(world: VacuumWorldEnvironment, config: BatteryConfig) -> Self {
+        Self {
+            world,
+            config,
+            charge: config.capacity,
+            last_charge_used: 0,
+        }
+    }
+
+    pub fn world(&self) -> &VacuumWorldEnvironment {
+        &self.world
+    }
+
+    pub fn config(&self) -> &BatteryConfig {
+        &self.config
+    }
+
+    pub fn charge(&self) -> i32 {
+        self.charge
+    }
+
+    /// Charge used by the most recent action, which is 0 if it was refused or recharged.
+    pub fn last_charge_used(&self) -> i32 {
+        self.last_charge_used
+    }
+}
+
+impl Environment for BatteryVacuumWorldEnvironment {
+    type Action = VacuumWorldAction;
+    type Percept = BatteryPercept;
+    type Score = i32;
+
+    fn percept(&self) -> Self::Percept {
+        BatteryPercept {
+            local: self.world.percept(),
+            charge: self.charge,
+        }
+    }
+
+    fn execute_action(&mut self, action: &Self::Action) {
+        self.last_charge_used = 0;
+        self.world.clear_blocked();
+        if *action == VacuumWorldAction::NoOp
+            && self.world.agent_location() == self.config.dock_location
+        {
+            self.charge = (self.charge + self.config.charge_rate).min(self.config.capacity);
+            return;
+        }
+
+        let cost = self.config.action_cost(action);
+        if cost > self.charge {
+            return;
+        }
+        self.charge -= cost;
+        self.last_charge_used = cost;
+        self.world.execute_action(action);
+    }
+
+    fn score(&self) -> Self::Score {
+        self.world.score()
+    }
+}
+
+impl fmt::Display for BatteryVacuumWorldEnvironment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.world.fmt(f)?;
+        writeln!(f, "charge: {}/{}", self.charge, self.config.capacity)
+    }
+}
+
+impl Termination<BatteryVacuumWorldEnvironment> for AllSquaresClean {
+    fn should_terminate(&mut self, event: &StepEvent<'_, BatteryVacuumWorldEnvironment>) -> bool {
+        event.environment.world().all_squares_clean()
+    }
+}
+
+/// EnergyWeighted awards a point per clean square per time step and takes off energy_weight for
+/// every unit of charge the action used. Refused actions use no charge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnergyWeighted {
+    pub energy_weight: i32,
+}
+
+impl PerformanceMeasure<BatteryVacuumWorldEnvironment> for EnergyWeighted {
+    fn score(
+        &mut self,
+        _action: &VacuumWorldAction,
+        environment: &BatteryVacuumWorldEnvironment,
+    ) -> i32 {
+        environment.score() - self.energy_weight * environment.last_charge_used()
+    }
+}
+
+/// BatteryAwareVacuumAgent cleans like a ModelBasedVacuumAgent, but heads back to the dock while it
+/// still has enough charge to get there, and recharges to full before carrying on. It knows the
+/// battery config, including where the dock is.
+///
+/// The capacity must be enough to reach the farthest square, suck, and get back, otherwise the
+/// agent keeps turning back before it gets there. Like the inner agent it learns where obstacles
+/// are from bumps, and it goes back by the shortest route it knows of. That route can turn out to
+/// be blocked, so with obstacles leave some spare capacity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatteryAwareVacuumAgent {
+    inner: ModelBasedVacuumAgent,
+    config: BatteryConfig,
+    charging: bool,
+}
+
+impl BatteryAwareVacuumAgent {
+    pub fn new(height: i32, width: i32, config: BatteryConfig) -> Self {
+        Self {
+            inner: ModelBasedVacuumAgent::new(height, width),
+            config,
+            charging: false,
+        }
+    }
+
+    /// Charge needed to get back to the dock from location, by the shortest route the agent knows
+    /// of. The agent got here from the dock, so there is one.
+    fn charge_to_dock(&self, location: &VacuumWorldLocation) -> i32 {
+        let dock = self.config.dock_location;
+        let distance = self
+            .inner
+            .distance(*location, dock)
+            .unwrap_or_else(|| (location.x - dock.x).abs() + (location.y - dock.y).abs());
+        distance * self.config.move_cost
+    }
+
+    fn move_towards_dock(&mut self, location: &VacuumWorldLocation) -> VacuumWorldAction {
+        self.inner
+            .move_towards(*location, self.config.dock_location)
+            .unwrap_or(VacuumWorldAction::NoOp)
+    }
+}
+
+impl Agent for BatteryAwareVacuumAgent {
+    type Action = VacuumWorldAction;
+    type Percept = BatteryPercept;
+
+    fn act(&mut self, percept: &Self::Percept) -> Self::Action {
+        // The inner agent learns from every percept, including those after moves towards the
+        // dock, so that it knows about the obstacles it bumped into on the way.
+        self.inner.learn(&percept.local);
+        let location = percept.local.location;
+        let at_dock = location == self.config.dock_location;
+        if self.charging && at_dock && percept.charge < self.config.capacity {
+            return VacuumWorldAction::NoOp;
+        }
+        self.charging = false;
+
+        // Only carry on cleaning if, after the most expensive action, there is still enough charge
+        // to get back to the dock. The inner agent is not asked for an action when the agent goes
+        // to charge, because it would predict the effect of an action that is never taken.
+        let reserve = self.config.suck_cost.max(self.config.move_cost) + self.config.move_cost;
+        if percept.charge < self.charge_to_dock(&location) + reserve {
+            self.charging = true;
+            return self.move_towards_dock(&location);
+        }
+        self.inner.act(&percept.local)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::ControlFlow;
+
+    use super::*;
+    use crate::Simulation;
+
+    #[test]
+    fn test_battery_refuses_actions_when_empty() {
+        let config = BatteryConfig {
+            capacity: 3,
+            ..BatteryConfig::default()
+        };
+        let mut env = BatteryVacuumWorldEnvironment::new(VacuumWorldEnvironment::default(), config);
+        env.execute_action(&VacuumWorldAction::Suck);
+        assert_eq!(env.charge(), 0);
+        assert_eq!(env.score(), 1);
+
+        env.execute_action(&VacuumWorldAction::Right);
+        assert_eq!(
+            env.world().agent_location(),
+            VacuumWorldLocation { x: 0, y: 0 }
+        );
+        assert_eq!(env.percept().charge, 0);
+    }
+
+    #[test]
+    fn test_noop_on_dock_recharges() {
+        let mut env = BatteryVacuumWorldEnvironment::new(
+            VacuumWorldEnvironment::default(),
+            BatteryConfig::default(),
+        );
+        env.execute_action(&VacuumWorldAction::Right);
+        env.execute_action(&VacuumWorldAction::NoOp);
+        assert_eq!(env.charge(), 19);
+        env.execute_action(&VacuumWorldAction::Suck);
+        env.execute_action(&VacuumWorldAction::Left);
+        assert_eq!(env.charge(), 15);
+        env.execute_action(&VacuumWorldAction::NoOp);
+        assert_eq!(env.charge(), 20);
+    }
+
+    #[test]
+    fn test_energy_weighted_measure() {
+        let config = BatteryConfig {
+            capacity: 4,
+            ..BatteryConfig::default()
+        };
+        let mut simulation = Simulation::with_measure(
+            BatteryVacuumWorldEnvironment::new(VacuumWorldEnvironment::default(), config),
+            BatteryObliviousAgent(ModelBasedVacuumAgent::new(1, 2)),
+            3,
+            EnergyWeighted { energy_weight: 1 },
+        );
+        simulation.run();
+        // Suck scores 1 - 3 for its charge, Right scores 1 - 1, then Suck is refused because the
+        // battery is empty and scores 1.
+        assert_eq!(simulation.score(), -1);
+    }
+
+    /// Ignores the battery, to show what happens to an agent that does not plan for charging.
+    struct BatteryObliviousAgent(ModelBasedVacuumAgent);
+
+    impl Agent for BatteryObliviousAgent {
+        type Action = VacuumWorldAction;
+        type Percept = BatteryPercept;
+
+        fn act(&mut self, percept: &Self::Percept) -> Self::Action {
+            self.0.act(&percept.local)
+        }
+    }
+
+    #[test]
+    fn test_battery_aware_agent_cleans_without_getting_stranded() {
+        let config = BatteryConfig::default();
+        let new_env =
+            || BatteryVacuumWorldEnvironment::new(VacuumWorldEnvironment::new(4, 4), config);
+
+        let mut simulation = Simulation::new(
+            new_env(),
+            BatteryObliviousAgent(ModelBasedVacuumAgent::new(4, 4)),
+            1000,
+        );
+        simulation.run();
+        assert!(!simulation.environment().world().all_squares_clean());
+
+        let mut simulation =
+            Simulation::new(new_env(), BatteryAwareVacuumAgent::new(4, 4, config), 1000);
+        let mut stranded = false;
+        simulation.run_with_observer(&mut |event: &StepEvent<
+            '_,
+            BatteryVacuumWorldEnvironment,
+        >| {
+            let env = event.environment;
+            stranded |= env.charge() < config.move_cost
+                && env.world().agent_location() != config.dock_location;
+            ControlFlow::Continue(())
+        });
+        assert!(!stranded);
+        assert!(simulation.environment().world().all_squares_clean());
+    }
+
+    #[test]
+    fn test_battery_aware_agent_cleans_around_obstacles() {
+        // A wall between the dock at the bottom left and the right-hand column, with a gap at
+        // the top.
+        let obstacles: Vec<VacuumWorldLocation> =
+            (0..3).map(|y| VacuumWorldLocation { x: 2, y }).collect();
+        let config = BatteryConfig {
+            capacity: 40,
+            ..BatteryConfig::default()
+        };
+        let world = VacuumWorldEnvironment::new(4, 4).with_obstacles(&obstacles);
+        let mut simulation = Simulation::new(
+            BatteryVacuumWorldEnvironment::new(world, config),
+            BatteryAwareVacuumAgent::new(4, 4, config),
+            1000,
+        );
+        simulation.run();
+        assert!(simulation.environment().world().all_squares_clean());
+    }
+}
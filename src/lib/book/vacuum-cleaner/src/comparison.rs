@@ -0,0 +1,253 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Comparing agents over many sampled environments.
+
+use rand::{Rng, SeedableRng};
+
+use crate::{Agent, Environment, Simulation};
+
+/// Number of random sign flips used by the permutation test in compare_agents.
+pub const PERMUTATION_RESAMPLES: usize = 10_000;
+
+/// An Agent behind a Box, so that agents of different types can be compared.
+pub type BoxedAgent<_Environment> = Box<
+    dyn Agent<
+        Action = <_Environment as Environment>::Action,
+        Percept = <_Environment as Environment>::Percept,
+    >,
+>;
+
+/// A Contender is a named way of creating an agent.
+pub struct Contender<'a, _Environment: Environment> {
+    pub name: String,
+
+    /// Creates a fresh agent for a trial. The argument is the trial's seed, for agents that need
+    /// randomness.
+    pub new_agent: Box<dyn Fn(u64) -> BoxedAgent<_Environment> + 'a>,
+}
+
+impl<'a, _Environment: Environment> Contender<'a, _Environment> {
+    pub fn new<F>(name: &str, new_agent: F) -> Self
+    where
+        F: Fn(u64) -> BoxedAgent<_Environment> + 'a,
+    {
+        Self {
+            name: name.to_string(),
+            new_agent: Box::new(new_agent),
+        }
+    }
+}
+
+/// Scores for one contender, one per trial.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentSummary {
+    pub name: String,
+    pub scores: Vec<f64>,
+    pub mean: f64,
+
+    /// Sample standard deviation.
+    pub standard_deviation: f64,
+}
+
+/// PairedComparison compares two contenders, by index into Comparison::summaries, over the same
+/// trials.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairedComparison {
+    pub first: usize,
+    pub second: usize,
+
+    /// Mean of first's score minus second's score.
+    pub mean_difference: f64,
+
+    /// Two-sided p-value of a paired sign-flip permutation test, with the null hypothesis that
+    /// both contenders score the same on average. A small p-value, e.g. below 0.05, means the
+    /// difference is unlikely to be down to chance.
+    pub p_value: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub summaries: Vec<AgentSummary>,
+
+    /// Every pair of contenders, in order.
+    pub pairs: Vec<PairedComparison>,
+}
+
+/// compare_agents runs every contender on the same sampled environments and compares their
+/// scores. Each trial draws a seed from seed; new_environment creates the trial's environment
+/// from it, and each contender's new_agent creates its agent from it, so every contender faces
+/// exactly the same environments.
+pub fn compare_agents<_Environment, F>(
+    contenders: &[Contender<'_, _Environment>],
+    new_environment: F,
+    trials: usize,
+    time_steps: i32,
+    seed: u64,
+) -> Comparison
+where
+    _Environment: Environment,
+    _Environment::Score: Into<f64>,
+    F: Fn(u64) -> _Environment,
+{
+    let mut rng = rand_pcg::Pcg64::seed_from_u64(seed);
+    let trial_seeds: Vec<u64> = (0..trials).map(|_| rng.gen()).collect();
+
+    let summaries: Vec<AgentSummary> = contenders
+        .iter()
+        .map(|contender| {
+            let scores: Vec<f64> = trial_seeds
+                .iter()
+                .map(|&trial_seed| {
+                    let mut simulation = Simulation::new(
+                        new_environment(trial_seed),
+                        (contender.new_agent)(trial_seed),
+                        time_steps,
+                    );
+                    simulation.run();
+                    simulation.score().into()
+                })
+                .collect();
+            AgentSummary {
+                name: contender.name.clone(),
+                mean: mean(&scores),
+                standard_deviation: standard_deviation(&scores),
+                scores,
+            }
+        })
+        .collect();
+
+    let mut pairs = vec![];
+    for first in 0..summaries.len() {
+        for second in first + 1..summaries.len() {
+            let differences: Vec<f64> = summaries[first]
+                .scores
+                .iter()
+                .zip(&summaries[second].scores)
+                .map(|(a, b)| a - b)
+                .collect();
+            pairs.push(PairedComparison {
+                first,
+                second,
+                mean_difference: mean(&differences),
+                p_value: sign_flip_p_value(&differences, &mut rng),
+            });
+        }
+    }
+
+    Comparison { summaries, pairs }
+}
+
+pub fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Sample standard deviation, i.e. divided by n - 1.
+pub fn standard_deviation(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let mean = mean(values);
+    let sum_of_squares: f64 = values.iter().map(|value| (value - mean).powi(2)).sum();
+    (sum_of_squares / (values.len() - 1) as f64).sqrt()
+}
+
+/// If both contenders were equally good then each paired difference would be as likely to be
+/// negative as positive. Randomly flipping the signs gives the distribution of the mean difference
+/// under that null hypothesis, and the p-value is how often it is at least as extreme as observed.
+fn sign_flip_p_value(differences: &[f64], rng: &mut rand_pcg::Pcg64) -> f64 {
+    let observed = mean(differences).abs();
+    if observed == 0.0 {
+        return 1.0;
+    }
+    let mut at_least_as_extreme = 0;
+    for _ in 0..PERMUTATION_RESAMPLES {
+        let flipped: f64 = differences
+            .iter()
+            .map(|&difference| {
+                if rng.gen_bool(0.5) {
+                    difference
+                } else {
+                    -difference
+                }
+            })
+            .sum();
+        if (flipped / differences.len() as f64).abs() >= observed {
+            at_least_as_extreme += 1;
+        }
+    }
+    (at_least_as_extreme + 1) as f64 / (PERMUTATION_RESAMPLES + 1) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::random::RandomAgent;
+    use crate::vacuum_world::{ModelBasedVacuumAgent, VacuumWorldAction, VacuumWorldEnvironment};
+
+    #[test]
+    fn test_mean_and_standard_deviation() {
+        let values = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        assert_eq!(mean(&values), 5.0);
+        assert!((standard_deviation(&values) - (32.0f64 / 7.0).sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_model_based_agent_beats_random_agent() {
+        let contenders = [
+            Contender::new("random", |seed| -> BoxedAgent<VacuumWorldEnvironment> {
+                Box::new(RandomAgent::new(
+                    VacuumWorldAction::ALL.to_vec(),
+                    Rc::new(RefCell::new(rand_pcg::Pcg64::seed_from_u64(seed))),
+                ))
+            }),
+            Contender::new("model-based", |_| -> BoxedAgent<VacuumWorldEnvironment> {
+                Box::new(ModelBasedVacuumAgent::new(3, 3))
+            }),
+            Contender::new(
+                "model-based again",
+                |_| -> BoxedAgent<VacuumWorldEnvironment> {
+                    Box::new(ModelBasedVacuumAgent::new(3, 3))
+                },
+            ),
+        ];
+        let comparison = compare_agents(
+            &contenders,
+            |seed| VacuumWorldEnvironment::new_random(3, 3, 0.5, seed),
+            50,
+            30,
+            42,
+        );
+
+        assert_eq!(comparison.summaries.len(), 3);
+        assert!(comparison.summaries[1].mean > comparison.summaries[0].mean);
+        assert_eq!(comparison.pairs.len(), 3);
+
+        let random_vs_model_based = &comparison.pairs[0];
+        assert!(random_vs_model_based.mean_difference < 0.0);
+        assert!(random_vs_model_based.p_value < 0.01);
+
+        let model_based_vs_itself = &comparison.pairs[2];
+        assert_eq!(model_based_vs_itself.mean_difference, 0.0);
+        assert_eq!(model_based_vs_itself.p_value, 1.0);
+    }
+}
@@ -0,0 +1,157 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Evaluating agents over many initial configurations of the vacuum world.
+//
+// See:
+// -  Chapter 2: Intelligent Agents, Exercise 2.12
+
+use std::collections::HashMap;
+
+use rayon::prelude::*;
+
+use crate::vacuum_world::{
+    SquareState, VaccuumWorldLocalPercept, VacuumWorldAction, VacuumWorldEnvironment,
+    VacuumWorldLocation,
+};
+use crate::{Agent, Simulation};
+
+/// The score of an agent for one initial configuration of the world.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigurationScore {
+    /// State of every square at the start, in the same order as `Evaluation::locations`.
+    pub squares: Vec<SquareState>,
+    pub agent_location: VacuumWorldLocation,
+    pub score: i32,
+}
+
+/// The result of evaluate_all_configs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    /// Every location in the world, row by row.
+    pub locations: Vec<VacuumWorldLocation>,
+    pub scores: Vec<ConfigurationScore>,
+}
+
+impl Evaluation {
+    /// The average score over all configurations.
+    pub fn average_score(&self) -> f64 {
+        if self.scores.is_empty() {
+            return 0.0;
+        }
+        let total: i64 = self.scores.iter().map(|s| i64::from(s.score)).sum();
+        total as f64 / self.scores.len() as f64
+    }
+}
+
+/// All locations in a world, row by row.
+pub fn all_locations(height: i32, width: i32) -> Vec<VacuumWorldLocation> {
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| VacuumWorldLocation { x, y }))
+        .collect()
+}
+
+/// Run a fresh agent for time_steps in every possible initial configuration of a height by width
+/// world, i.e. every combination of dirt placement and agent location, and record the score for
+/// each. For the two-square world of Figure 2.2 there are 4 dirt placements and 2 agent locations.
+///
+/// Configurations are run in parallel across all available threads, and scores are returned in
+/// the same order as a sequential run. The number of configurations is
+/// 2^(height * width) * height * width, so this is still only practical for small worlds.
+pub fn evaluate_all_configs<_Agent, F>(
+    height: i32,
+    width: i32,
+    time_steps: i32,
+    new_agent: F,
+) -> Evaluation
+where
+    _Agent: Agent<Action = VacuumWorldAction, Percept = VaccuumWorldLocalPercept>,
+    F: Fn() -> _Agent + Sync,
+{
+    let locations = all_locations(height, width);
+    let scores = (0..(1u64 << locations.len()))
+        .into_par_iter()
+        .flat_map_iter(|dirt_bits| {
+            let squares: Vec<SquareState> = (0..locations.len())
+                .map(|i| {
+                    if dirt_bits & (1 << i) != 0 {
+                        SquareState::Dirty
+                    } else {
+                        SquareState::Clean
+                    }
+                })
+                .collect();
+            let dirt_map: HashMap<VacuumWorldLocation, SquareState> = locations
+                .iter()
+                .copied()
+                .zip(squares.iter().copied())
+                .collect();
+            let new_agent = &new_agent;
+            locations.iter().map(move |agent_location| {
+                let environment = VacuumWorldEnvironment::new_with_dirt_map(
+                    height,
+                    width,
+                    &dirt_map,
+                    *agent_location,
+                );
+                let mut simulation = Simulation::new(environment, new_agent(), time_steps);
+                simulation.run();
+                ConfigurationScore {
+                    squares: squares.clone(),
+                    agent_location: *agent_location,
+                    score: simulation.score(),
+                }
+            })
+        })
+        .collect();
+    Evaluation { locations, scores }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vacuum_world::ReflexVacuumAgent;
+
+    #[test]
+    fn test_evaluate_all_configs_two_square_world() {
+        let evaluation = evaluate_all_configs(1, 2, 1000, ReflexVacuumAgent::new);
+        assert_eq!(evaluation.scores.len(), 8);
+
+        let score_for = |squares: [SquareState; 2], x: i32| {
+            evaluation
+                .scores
+                .iter()
+                .find(|s| s.squares == squares && s.agent_location.x == x)
+                .unwrap()
+                .score
+        };
+        // Both dirty, start on the left: suck (1), move right (1), suck (2), then 2 per step.
+        assert_eq!(
+            score_for([SquareState::Dirty, SquareState::Dirty], 0),
+            1 + 1 + 2 * 998
+        );
+        // Both clean: 2 points every step.
+        assert_eq!(score_for([SquareState::Clean, SquareState::Clean], 0), 2000);
+        // Only the other square dirty: move (1), suck (2), then 2 per step.
+        assert_eq!(
+            score_for([SquareState::Clean, SquareState::Dirty], 0),
+            1 + 2 * 999
+        );
+
+        let average = evaluation.average_score();
+        assert!(average > 1995.0 && average < 2000.0, "{}", average);
+    }
+}
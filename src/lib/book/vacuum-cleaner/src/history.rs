@@ -0,0 +1,270 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Percept sequences
+//
+// See:
+// -  Chapter 2: Intelligent Agents, section 2.4, Figure 2.7
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Agent;
+
+/// PerceptHistory is the agent's percept sequence, oldest first. A bounded history is a ring
+/// buffer that forgets the oldest percept once it is full, which is all that most model-based
+/// agents need. Table-driven agents need the full sequence and use an unbounded history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PerceptHistory<_Percept> {
+    percepts: VecDeque<_Percept>,
+    capacity: Option<usize>,
+
+    /// Number of percepts ever pushed, including forgotten ones.
+    total_pushed: usize,
+}
+
+impl<_Percept> PerceptHistory<_Percept> {
+    /// A history that remembers only the most recent capacity percepts.
+    pub fn with_capacity(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        Self {
+            percepts: VecDeque::with_capacity(capacity),
+            capacity: Some(capacity),
+            total_pushed: 0,
+        }
+    }
+
+    /// A history that remembers every percept.
+    pub fn unbounded() -> Self {
+        Self {
+            percepts: VecDeque::new(),
+            capacity: None,
+            total_pushed: 0,
+        }
+    }
+
+    pub fn push(&mut self, percept: _Percept) {
+        if Some(self.percepts.len()) == self.capacity {
+            self.percepts.pop_front();
+        }
+        self.percepts.push_back(percept);
+        self.total_pushed += 1;
+    }
+
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    /// Number of percepts currently remembered.
+    pub fn len(&self) -> usize {
+        self.percepts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.percepts.is_empty()
+    }
+
+    pub fn total_pushed(&self) -> usize {
+        self.total_pushed
+    }
+
+    /// True if no percepts have been forgotten, i.e. the history is the full percept sequence.
+    pub fn is_complete(&self) -> bool {
+        self.total_pushed == self.percepts.len()
+    }
+
+    pub fn latest(&self) -> Option<&_Percept> {
+        self.percepts.back()
+    }
+
+    /// The percept from n time steps ago, where 0 is the latest.
+    pub fn nth_latest(&self, n: usize) -> Option<&_Percept> {
+        self.percepts
+            .len()
+            .checked_sub(n + 1)
+            .map(|i| &self.percepts[i])
+    }
+
+    /// Remembered percepts, oldest first.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &_Percept> + ExactSizeIterator {
+        self.percepts.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.percepts.clear();
+        self.total_pushed = 0;
+    }
+}
+
+/// A HistoryAgent chooses an action from its percept history rather than from only the latest
+/// percept. Wrap it in AgentWithHistory to use it as an Agent.
+pub trait HistoryAgent {
+    type Action;
+    type Percept;
+
+    fn act_on_history(&mut self, history: &PerceptHistory<Self::Percept>) -> Self::Action;
+}
+
+/// AgentWithHistory adapts a HistoryAgent to an Agent by recording each percept before asking the
+/// inner agent for an action.
+pub struct AgentWithHistory<_HistoryAgent: HistoryAgent> {
+    inner: _HistoryAgent,
+    history: PerceptHistory<_HistoryAgent::Percept>,
+}
+
+impl<_HistoryAgent: HistoryAgent> AgentWithHistory<_HistoryAgent> {
+    pub fn new(inner: _HistoryAgent, history: PerceptHistory<_HistoryAgent::Percept>) -> Self {
+        Self { inner, history }
+    }
+
+    pub fn inner(&self) -> &_HistoryAgent {
+        &self.inner
+    }
+
+    pub fn history(&self) -> &PerceptHistory<_HistoryAgent::Percept> {
+        &self.history
+    }
+}
+
+impl<_HistoryAgent> Agent for AgentWithHistory<_HistoryAgent>
+where
+    _HistoryAgent: HistoryAgent,
+    _HistoryAgent::Percept: Clone,
+{
+    type Action = _HistoryAgent::Action;
+    type Percept = _HistoryAgent::Percept;
+
+    fn act(&mut self, percept: &Self::Percept) -> Self::Action {
+        self.history.push(percept.clone());
+        self.inner.act_on_history(&self.history)
+    }
+}
+
+/// TableDrivenAgent is the TABLE-DRIVEN-AGENT from Figure 2.7. It looks up the entire percept
+/// sequence in a table of actions, and returns default_action for sequences missing from the
+/// table. Use it with an unbounded PerceptHistory.
+pub struct TableDrivenAgent<_Percept, _Action> {
+    table: HashMap<Vec<_Percept>, _Action>,
+    default_action: _Action,
+}
+
+impl<_Percept, _Action> TableDrivenAgent<_Percept, _Action>
+where
+    _Percept: Clone + Eq + Hash,
+    _Action: Clone,
+{
+    pub fn new(table: HashMap<Vec<_Percept>, _Action>, default_action: _Action) -> Self {
+        Self {
+            table,
+            default_action,
+        }
+    }
+
+    /// A TableDrivenAgent wrapped so that it can be used as an Agent.
+    pub fn into_agent(self) -> AgentWithHistory<Self> {
+        AgentWithHistory::new(self, PerceptHistory::unbounded())
+    }
+}
+
+impl<_Percept, _Action> HistoryAgent for TableDrivenAgent<_Percept, _Action>
+where
+    _Percept: Clone + Eq + Hash,
+    _Action: Clone,
+{
+    type Action = _Action;
+    type Percept = _Percept;
+
+    fn act_on_history(&mut self, history: &PerceptHistory<Self::Percept>) -> Self::Action {
+        let sequence: Vec<_Percept> = history.iter().cloned().collect();
+        self.table
+            .get(&sequence)
+            .cloned()
+            .unwrap_or_else(|| self.default_action.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vacuum_world::{
+        SquareState, VaccuumWorldLocalPercept, VacuumWorldAction, VacuumWorldLocation,
+    };
+
+    #[test]
+    fn test_bounded_history_forgets_oldest() {
+        let mut history = PerceptHistory::with_capacity(3);
+        for percept in 0..5 {
+            history.push(percept);
+        }
+        assert_eq!(history.iter().copied().collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!(history.latest(), Some(&4));
+        assert_eq!(history.nth_latest(2), Some(&2));
+        assert_eq!(history.nth_latest(3), None);
+        assert_eq!(history.total_pushed(), 5);
+        assert!(!history.is_complete());
+
+        let json = serde_json::to_string(&history).unwrap();
+        let mut round_tripped: PerceptHistory<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_tripped, history);
+        round_tripped.push(5);
+        assert_eq!(round_tripped.len(), 3);
+    }
+
+    #[test]
+    fn test_table_driven_agent() {
+        // Part of the table from Figure 2.3.
+        let a = VacuumWorldLocation { x: 0, y: 0 };
+        let b = VacuumWorldLocation { x: 1, y: 0 };
+        let percept = |location, square_state| VaccuumWorldLocalPercept {
+            location,
+            square_state,
+            bump: false,
+        };
+        let table = HashMap::from([
+            (
+                vec![percept(a, SquareState::Clean)],
+                VacuumWorldAction::Right,
+            ),
+            (
+                vec![percept(a, SquareState::Dirty)],
+                VacuumWorldAction::Suck,
+            ),
+            (
+                vec![
+                    percept(a, SquareState::Clean),
+                    percept(b, SquareState::Dirty),
+                ],
+                VacuumWorldAction::Suck,
+            ),
+        ]);
+        let mut agent = TableDrivenAgent::new(table, VacuumWorldAction::NoOp).into_agent();
+        assert_eq!(
+            agent.act(&percept(a, SquareState::Clean)),
+            VacuumWorldAction::Right
+        );
+        assert_eq!(
+            agent.act(&percept(b, SquareState::Dirty)),
+            VacuumWorldAction::Suck
+        );
+        assert_eq!(
+            agent.act(&percept(b, SquareState::Clean)),
+            VacuumWorldAction::NoOp
+        );
+        assert_eq!(agent.history().len(), 3);
+    }
+}
@@ -19,8 +19,21 @@
 // See:
 // -  Chapter 2: Intelligent Agents, page 40
 
+use std::ops::ControlFlow;
+
 use num_traits::Zero;
+use serde::{Deserialize, Serialize};
 
+pub mod batch;
+pub mod battery;
+pub mod comparison;
+pub mod evaluation;
+pub mod history;
+pub mod map_learning;
+pub mod murphy;
+pub mod random;
+pub mod render;
+pub mod trace;
 pub mod vacuum_world;
 
 /// An Agent acts in a Performance, Environment, Action, Sensing (PEAS) cycle.
@@ -41,6 +54,15 @@ pub trait Agent {
     fn act(&mut self, percept: &Self::Percept) -> Self::Action;
 }
 
+impl<_Agent: Agent + ?Sized> Agent for Box<_Agent> {
+    type Action = _Agent::Action;
+    type Percept = _Agent::Percept;
+
+    fn act(&mut self, percept: &Self::Percept) -> Self::Action {
+        (**self).act(percept)
+    }
+}
+
 /// An Environment runs a single Agent in a Performance, Environment, Action, Sensing (PEAS) cycle.
 ///
 /// Notice that the Environment is not aware of an Agent.
@@ -57,21 +79,142 @@ pub trait Environment {
     fn score(&self) -> Self::Score;
 }
 
+/// StepEvent describes a single time step of a Simulation, after the Agent's action has been
+/// executed in the Environment.
+pub struct StepEvent<'a, _Environment: Environment> {
+    /// Index of the time step, starting at 0.
+    pub step: i32,
+    pub percept: &'a _Environment::Percept,
+    pub action: &'a _Environment::Action,
+
+    /// The score added by this time step.
+    pub score_delta: _Environment::Score,
+
+    /// The Environment after the action, so that observers can take a snapshot of it.
+    pub environment: &'a _Environment,
+}
+
+/// StepRecord is an owned record of a single time step of a Simulation, returned by
+/// Simulation::step and Simulation::steps.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepRecord<_Percept, _Action, _Score> {
+    /// Index of the time step, starting at 0.
+    pub step: i32,
+    pub percept: _Percept,
+    pub action: _Action,
+
+    /// The score added by this time step.
+    pub score_delta: _Score,
+
+    /// The total score after this time step.
+    pub score: _Score,
+}
+
+/// An Observer is notified by Simulation::run_with_observer after every time step. This lets
+/// experiments log traces, build visualizations, or stop early without modifying the simulation
+/// loop. Returning ControlFlow::Break stops the simulation after the current step.
+///
+/// Any FnMut(&StepEvent) -> ControlFlow<()> closure is an Observer.
+pub trait Observer<_Environment: Environment> {
+    fn observe(&mut self, event: &StepEvent<'_, _Environment>) -> ControlFlow<()>;
+}
+
+impl<_Environment, F> Observer<_Environment> for F
+where
+    _Environment: Environment,
+    F: FnMut(&StepEvent<'_, _Environment>) -> ControlFlow<()>,
+{
+    fn observe(&mut self, event: &StepEvent<'_, _Environment>) -> ControlFlow<()> {
+        self(event)
+    }
+}
+
+/// A PerformanceMeasure scores every time step of a Simulation, and the Simulation's score is the
+/// sum. Keeping it separate from the Environment means that different measures, e.g. penalizing
+/// movement or rewarding speed, can be compared in the same Environment. See section 2.2
+/// "Good Behavior: The Concept of Rationality".
+pub trait PerformanceMeasure<_Environment: Environment> {
+    /// The score for a time step, given the action the agent took and the Environment after it.
+    fn score(
+        &mut self,
+        action: &_Environment::Action,
+        environment: &_Environment,
+    ) -> _Environment::Score;
+}
+
+/// EnvironmentScore is the default PerformanceMeasure, which is the Environment's own score.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct EnvironmentScore;
+
+impl<_Environment: Environment> PerformanceMeasure<_Environment> for EnvironmentScore {
+    fn score(
+        &mut self,
+        _action: &_Environment::Action,
+        environment: &_Environment,
+    ) -> _Environment::Score {
+        environment.score()
+    }
+}
+
+/// A Termination decides whether a Simulation should stop before it has run all of its time steps,
+/// e.g. once every square is clean. It is checked after every time step.
+///
+/// Any FnMut(&StepEvent) -> bool closure is a Termination.
+pub trait Termination<_Environment: Environment> {
+    fn should_terminate(&mut self, event: &StepEvent<'_, _Environment>) -> bool;
+}
+
+impl<_Environment, F> Termination<_Environment> for F
+where
+    _Environment: Environment,
+    F: FnMut(&StepEvent<'_, _Environment>) -> bool,
+{
+    fn should_terminate(&mut self, event: &StepEvent<'_, _Environment>) -> bool {
+        self(event)
+    }
+}
+
+/// The result of running a Simulation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimulationOutcome<_Score> {
+    pub score: _Score,
+
+    /// Number of time steps actually run, which is less than the Simulation's time steps if it
+    /// terminated early.
+    pub steps_taken: i32,
+}
+
 /// A Simulation runs a single Agent in multiple Performance, Environment, Action, Sensing (PEAS)
 /// cycles. The Agent's score (Performance) is continually kept up to date.
 ///
+/// A Simulation can be serialized if its Environment and Agent can. Stopping a run early with an
+/// Observer, serializing the Simulation, and later deserializing and running it again resumes
+/// from where it stopped, which lets long experiments be checkpointed.
+///
+/// The score comes from a PerformanceMeasure, which by default is the Environment's own score.
+///
 /// The Simulation is aware of both the Environment and the single Agent. Notice that the Agent's
 /// generic Action and Percept come from the Environment. The Agent still does not need to know that
 /// the Environment exists, but the Agent definitely needs the Environment's Action and Percept
 /// types.
-pub struct Simulation<_Environment, _Agent>
+#[derive(Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "_Environment: Serialize, _Agent: Serialize, _Measure: Serialize, \
+                 _Environment::Score: Serialize",
+    deserialize = "_Environment: Deserialize<'de>, _Agent: Deserialize<'de>, \
+                   _Measure: Deserialize<'de>, _Environment::Score: Deserialize<'de>"
+))]
+pub struct Simulation<_Environment, _Agent, _Measure = EnvironmentScore>
 where
     _Environment: Environment,
     _Agent: Agent<Action = _Environment::Action, Percept = _Environment::Percept>,
+    _Measure: PerformanceMeasure<_Environment>,
 {
     environment: _Environment,
     agent: _Agent,
+    measure: _Measure,
     time_steps: i32,
+    steps_taken: i32,
     score: _Environment::Score,
 }
 
@@ -81,24 +224,311 @@ where
     _Agent: Agent<Action = _Environment::Action, Percept = _Environment::Percept>,
 {
     pub fn new(environment: _Environment, agent: _Agent, time_steps: i32) -> Self {
+        Self::with_measure(environment, agent, time_steps, EnvironmentScore)
+    }
+}
+
+impl<_Environment, _Agent, _Measure> Simulation<_Environment, _Agent, _Measure>
+where
+    _Environment: Environment,
+    _Agent: Agent<Action = _Environment::Action, Percept = _Environment::Percept>,
+    _Measure: PerformanceMeasure<_Environment>,
+{
+    /// Create a Simulation that is scored by measure instead of the Environment's own score.
+    pub fn with_measure(
+        environment: _Environment,
+        agent: _Agent,
+        time_steps: i32,
+        measure: _Measure,
+    ) -> Self {
         Self {
             environment,
             agent,
+            measure,
             time_steps,
+            steps_taken: 0,
             score: _Environment::Score::zero(),
         }
     }
 
     pub fn run(&mut self) {
-        for _ in 0..self.time_steps {
-            let percept = self.environment.percept();
-            let action = self.agent.act(&percept);
-            self.environment.execute_action(&action);
-            self.score += self.environment.score();
+        self.run_with_observer(&mut |_: &StepEvent<'_, _Environment>| ControlFlow::Continue(()));
+    }
+
+    /// Run the simulation, calling the observer after every time step. The simulation stops early
+    /// if the observer returns ControlFlow::Break, and running it again continues from there.
+    pub fn run_with_observer<_Observer>(&mut self, observer: &mut _Observer)
+    where
+        _Observer: Observer<_Environment>,
+    {
+        while let Some(record) = self.step() {
+            let event = StepEvent {
+                step: record.step,
+                percept: &record.percept,
+                action: &record.action,
+                score_delta: record.score_delta,
+                environment: &self.environment,
+            };
+            if observer.observe(&event).is_break() {
+                break;
+            }
+        }
+    }
+
+    /// Run a single time step, or return None if all time steps have already run.
+    pub fn step(
+        &mut self,
+    ) -> Option<StepRecord<_Environment::Percept, _Environment::Action, _Environment::Score>> {
+        if self.steps_taken >= self.time_steps {
+            return None;
         }
+        let step = self.steps_taken;
+        let percept = self.environment.percept();
+        let action = self.agent.act(&percept);
+        self.environment.execute_action(&action);
+        let score_delta = self.measure.score(&action, &self.environment);
+        self.score += score_delta;
+        self.steps_taken += 1;
+        Some(StepRecord {
+            step,
+            percept,
+            action,
+            score_delta,
+            score: self.score,
+        })
+    }
+
+    /// An iterator that runs one time step each time it is advanced, so that callers can drive
+    /// the simulation lazily, e.g. interleaved with UI updates. Dropping the iterator stops the
+    /// simulation, and it can be resumed later.
+    pub fn steps(&mut self) -> Steps<'_, _Environment, _Agent, _Measure> {
+        Steps { simulation: self }
+    }
+
+    /// Run the simulation until the termination condition is met or all time steps have run.
+    pub fn run_until<_Termination>(
+        &mut self,
+        termination: &mut _Termination,
+    ) -> SimulationOutcome<_Environment::Score>
+    where
+        _Termination: Termination<_Environment>,
+    {
+        self.run_with_observer(&mut |event: &StepEvent<'_, _Environment>| {
+            if termination.should_terminate(event) {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        self.outcome()
     }
 
     pub fn score(&self) -> <_Environment as Environment>::Score {
         self.score
     }
+
+    pub fn steps_taken(&self) -> i32 {
+        self.steps_taken
+    }
+
+    pub fn outcome(&self) -> SimulationOutcome<_Environment::Score> {
+        SimulationOutcome {
+            score: self.score,
+            steps_taken: self.steps_taken,
+        }
+    }
+
+    pub fn environment(&self) -> &_Environment {
+        &self.environment
+    }
+
+    pub fn agent(&self) -> &_Agent {
+        &self.agent
+    }
+
+    pub fn measure(&self) -> &_Measure {
+        &self.measure
+    }
+}
+
+/// Iterator returned by Simulation::steps.
+pub struct Steps<'a, _Environment, _Agent, _Measure>
+where
+    _Environment: Environment,
+    _Agent: Agent<Action = _Environment::Action, Percept = _Environment::Percept>,
+    _Measure: PerformanceMeasure<_Environment>,
+{
+    simulation: &'a mut Simulation<_Environment, _Agent, _Measure>,
+}
+
+impl<'a, _Environment, _Agent, _Measure> Steps<'a, _Environment, _Agent, _Measure>
+where
+    _Environment: Environment,
+    _Agent: Agent<Action = _Environment::Action, Percept = _Environment::Percept>,
+    _Measure: PerformanceMeasure<_Environment>,
+{
+    /// The Simulation being stepped, e.g. to look at the Environment between steps.
+    pub fn simulation(&self) -> &Simulation<_Environment, _Agent, _Measure> {
+        self.simulation
+    }
+}
+
+impl<'a, _Environment, _Agent, _Measure> Iterator for Steps<'a, _Environment, _Agent, _Measure>
+where
+    _Environment: Environment,
+    _Agent: Agent<Action = _Environment::Action, Percept = _Environment::Percept>,
+    _Measure: PerformanceMeasure<_Environment>,
+{
+    type Item = StepRecord<_Environment::Percept, _Environment::Action, _Environment::Score>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.simulation.step()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.simulation.time_steps - self.simulation.steps_taken).max(0) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vacuum_world::{
+        ModelBasedVacuumAgent, ReflexVacuumAgent, VacuumWorldAction, VacuumWorldEnvironment,
+        VacuumWorldLocation,
+    };
+
+    #[test]
+    fn test_observer_sees_every_step() {
+        let mut simulation = Simulation::new(
+            VacuumWorldEnvironment::default(),
+            ReflexVacuumAgent::new(),
+            5,
+        );
+        let mut events = vec![];
+        simulation.run_with_observer(&mut |event: &StepEvent<'_, VacuumWorldEnvironment>| {
+            events.push((
+                event.step,
+                event.percept.location,
+                *event.action,
+                event.score_delta,
+                event.environment.agent_location(),
+            ));
+            ControlFlow::Continue(())
+        });
+
+        let left = VacuumWorldLocation { x: 0, y: 0 };
+        let right = VacuumWorldLocation { x: 1, y: 0 };
+        assert_eq!(
+            events,
+            vec![
+                (0, left, VacuumWorldAction::Suck, 1, left),
+                (1, left, VacuumWorldAction::Right, 1, right),
+                (2, right, VacuumWorldAction::Suck, 2, right),
+                (3, right, VacuumWorldAction::Left, 2, left),
+                (4, left, VacuumWorldAction::Right, 2, right),
+            ]
+        );
+        assert_eq!(simulation.score(), 8);
+    }
+
+    #[test]
+    fn test_observer_can_stop_simulation() {
+        let mut simulation = Simulation::new(
+            VacuumWorldEnvironment::default(),
+            ReflexVacuumAgent::new(),
+            1000,
+        );
+        // Stop as soon as both squares are clean.
+        simulation.run_with_observer(&mut |event: &StepEvent<'_, VacuumWorldEnvironment>| {
+            if event.score_delta == 2 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        assert_eq!(simulation.score(), 4);
+        assert_eq!(simulation.steps_taken(), 3);
+    }
+
+    #[test]
+    fn test_steps_iterator() {
+        let mut simulation = Simulation::new(
+            VacuumWorldEnvironment::default(),
+            ReflexVacuumAgent::new(),
+            5,
+        );
+        let mut steps = simulation.steps();
+        assert_eq!(steps.size_hint(), (5, Some(5)));
+        let first = steps.next().unwrap();
+        assert_eq!(first.action, VacuumWorldAction::Suck);
+        assert_eq!(
+            steps.simulation().environment().agent_location(),
+            VacuumWorldLocation { x: 0, y: 0 }
+        );
+        let actions: Vec<VacuumWorldAction> = steps
+            .take_while(|record| record.score < 4)
+            .map(|record| record.action)
+            .collect();
+        assert_eq!(actions, vec![VacuumWorldAction::Right]);
+        // take_while consumed the step that reached a score of 4.
+        assert_eq!(simulation.steps_taken(), 3);
+        assert_eq!(simulation.score(), 4);
+
+        let rest: Vec<StepRecord<_, _, _>> = simulation.steps().collect();
+        assert_eq!(rest.len(), 2);
+        assert_eq!(rest.last().unwrap().step, 4);
+        assert_eq!(rest.last().unwrap().score, 8);
+        assert!(simulation.step().is_none());
+    }
+
+    #[test]
+    fn test_checkpoint_and_resume() {
+        let new_simulation = || {
+            Simulation::new(
+                VacuumWorldEnvironment::new_random(3, 3, 0.5, 5),
+                ModelBasedVacuumAgent::new(3, 3),
+                40,
+            )
+        };
+        let mut uninterrupted = new_simulation();
+        uninterrupted.run();
+
+        let mut simulation = new_simulation();
+        simulation.run_until(&mut |event: &StepEvent<'_, VacuumWorldEnvironment>| event.step == 9);
+        let checkpoint = serde_json::to_string(&simulation).unwrap();
+        let mut resumed: Simulation<VacuumWorldEnvironment, ModelBasedVacuumAgent> =
+            serde_json::from_str(&checkpoint).unwrap();
+        assert_eq!(resumed.steps_taken(), 10);
+        resumed.run();
+        assert_eq!(resumed.outcome(), uninterrupted.outcome());
+        assert_eq!(resumed.environment(), uninterrupted.environment());
+    }
+
+    #[test]
+    fn test_run_until_reports_steps_taken() {
+        let mut simulation = Simulation::new(
+            VacuumWorldEnvironment::default(),
+            ReflexVacuumAgent::new(),
+            1000,
+        );
+        let outcome = simulation
+            .run_until(&mut |event: &StepEvent<'_, VacuumWorldEnvironment>| event.step == 4);
+        assert_eq!(
+            outcome,
+            SimulationOutcome {
+                score: 8,
+                steps_taken: 5
+            }
+        );
+
+        let mut simulation = Simulation::new(
+            VacuumWorldEnvironment::default(),
+            ReflexVacuumAgent::new(),
+            10,
+        );
+        let outcome = simulation.run_until(&mut |_: &StepEvent<'_, VacuumWorldEnvironment>| false);
+        assert_eq!(outcome.steps_taken, 10);
+    }
 }
@@ -0,0 +1,177 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// A vacuum agent for a world whose geography is unknown.
+//
+// See:
+// -  Chapter 2: Intelligent Agents, section 2.4 "Learning agents", and Exercise 2.14
+
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::vacuum_world::{
+    location_map, SquareState, VaccuumWorldLocalPercept, VacuumWorldAction, VacuumWorldLocation,
+    MOVES,
+};
+use crate::Agent;
+
+/// What a MapLearningVacuumAgent knows about a square.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MapCell {
+    /// The agent bumped into it, so it is an obstacle or outside the world.
+    Blocked,
+
+    /// The agent has been there and saw this state.
+    Free(SquareState),
+}
+
+/// MapLearningVacuumAgent does not know the size or shape of the world, or where any obstacles
+/// are. It builds a map as it goes: every percept tells it the state of the square it is on, and a
+/// Bump tells it that the square it tried to move into is blocked. The environment must use
+/// BoundaryBehavior::Bump, which is the default.
+///
+/// It sucks when the current square is dirty, otherwise it takes the shortest route through
+/// squares it knows are free, found by breadth-first search, to the nearest square it has not
+/// seen yet. Once there are no reachable unseen squares the whole world is clean and it only
+/// issues NoOp.
+///
+/// This is a small example of a learning agent: the map is the knowledge that the percepts
+/// improve, and the route planner is the performance element that uses it.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct MapLearningVacuumAgent {
+    #[serde(with = "location_map")]
+    map: HashMap<VacuumWorldLocation, MapCell>,
+    last_action: Option<VacuumWorldAction>,
+}
+
+impl MapLearningVacuumAgent {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// What the agent knows about a square, or None if it has never been there or bumped into it.
+    pub fn believed_cell(&self, location: &VacuumWorldLocation) -> Option<MapCell> {
+        self.map.get(location).copied()
+    }
+
+    fn learn(&mut self, percept: &VaccuumWorldLocalPercept) {
+        if percept.bump {
+            if let Some(action) = self.last_action {
+                self.map
+                    .insert(percept.location.moved(&action), MapCell::Blocked);
+            }
+        }
+        self.map
+            .insert(percept.location, MapCell::Free(percept.square_state));
+    }
+
+    /// Breadth-first search through free squares to the nearest unseen or dirty square. Returns
+    /// the first move of the route, or None if there is no such square.
+    fn first_move_to_nearest_target(&self, from: VacuumWorldLocation) -> Option<VacuumWorldAction> {
+        let mut first_moves: HashMap<VacuumWorldLocation, VacuumWorldAction> = HashMap::new();
+        let mut frontier = VecDeque::from([from]);
+        while let Some(current) = frontier.pop_front() {
+            for action in MOVES {
+                let next = current.moved(&action);
+                if next == from || first_moves.contains_key(&next) {
+                    continue;
+                }
+                let first_move = first_moves.get(&current).copied().unwrap_or(action);
+                match self.map.get(&next) {
+                    None | Some(MapCell::Free(SquareState::Dirty)) => return Some(first_move),
+                    Some(MapCell::Free(SquareState::Clean)) => {
+                        first_moves.insert(next, first_move);
+                        frontier.push_back(next);
+                    }
+                    Some(MapCell::Blocked) => {}
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Agent for MapLearningVacuumAgent {
+    type Action = VacuumWorldAction;
+    type Percept = VaccuumWorldLocalPercept;
+
+    fn act(&mut self, percept: &Self::Percept) -> Self::Action {
+        self.learn(percept);
+        let action = if percept.square_state == SquareState::Dirty {
+            self.map
+                .insert(percept.location, MapCell::Free(SquareState::Clean));
+            VacuumWorldAction::Suck
+        } else {
+            self.first_move_to_nearest_target(percept.location)
+                .unwrap_or(VacuumWorldAction::NoOp)
+        };
+        self.last_action = Some(action);
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vacuum_world::{ConsecutiveNoOps, VacuumWorldEnvironment};
+    use crate::{Environment, Simulation};
+
+    #[test]
+    fn test_map_learning_agent_learns_walls_from_bumps() {
+        let mut env = VacuumWorldEnvironment::new(1, 1);
+        let mut agent = MapLearningVacuumAgent::new();
+        for _ in 0..6 {
+            let action = agent.act(&env.percept());
+            env.execute_action(&action);
+        }
+        let origin = VacuumWorldLocation { x: 0, y: 0 };
+        for action in MOVES {
+            assert_eq!(
+                agent.believed_cell(&origin.moved(&action)),
+                Some(MapCell::Blocked)
+            );
+        }
+        assert_eq!(agent.act(&env.percept()), VacuumWorldAction::NoOp);
+    }
+
+    #[test]
+    fn test_map_learning_agent_cleans_unknown_world_with_obstacles() {
+        // A 4x5 world with a wall down the middle that has a gap at the top.
+        let obstacles: Vec<VacuumWorldLocation> =
+            (0..3).map(|y| VacuumWorldLocation { x: 2, y }).collect();
+        for start in [
+            VacuumWorldLocation { x: 0, y: 0 },
+            VacuumWorldLocation { x: 4, y: 0 },
+            VacuumWorldLocation { x: 3, y: 3 },
+        ] {
+            let mut dirt_map = HashMap::new();
+            for location in crate::evaluation::all_locations(4, 5) {
+                dirt_map.insert(location, SquareState::Dirty);
+            }
+            let env = VacuumWorldEnvironment::new_with_dirt_map(4, 5, &dirt_map, start)
+                .with_obstacles(&obstacles);
+            let mut simulation = Simulation::new(env, MapLearningVacuumAgent::new(), 1000);
+            let outcome = simulation.run_until(&mut ConsecutiveNoOps::new(1));
+            assert!(
+                simulation.environment().all_squares_clean(),
+                "start {:?}",
+                start
+            );
+            assert!(outcome.steps_taken < 100, "start {:?}", start);
+        }
+    }
+}
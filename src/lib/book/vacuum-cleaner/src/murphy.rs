@@ -0,0 +1,275 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Murphy's law vacuum world, where actions and sensors sometimes fail.
+//
+// See:
+// -  Chapter 2: Intelligent Agents, Exercise 2.13
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use rand::Rng;
+
+use crate::history::PerceptHistory;
+use crate::vacuum_world::{
+    AllSquaresClean, SquareState, VaccuumWorldLocalPercept, VacuumWorldAction,
+    VacuumWorldEnvironment,
+};
+use crate::{Agent, Environment, StepEvent, Termination};
+
+/// How often things go wrong in a MurphyVacuumWorldEnvironment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MurphyConfig {
+    /// Probability that Suck leaves a dirty square dirty.
+    pub suck_failure_probability: f64,
+
+    /// Probability that Suck deposits dirt when applied to a clean square.
+    pub dirt_deposit_probability: f64,
+
+    /// Probability that the dirt sensor reports the wrong square state.
+    pub sensor_error_probability: f64,
+}
+
+impl Default for MurphyConfig {
+    /// The probabilities from Exercise 2.13: Suck fails 25% of the time and the dirt sensor is
+    /// wrong 10% of the time. The book only says that Suck "sometimes" deposits dirt on a clean
+    /// square, so that is a coin flip.
+    fn default() -> Self {
+        Self {
+            suck_failure_probability: 0.25,
+            dirt_deposit_probability: 0.5,
+            sensor_error_probability: 0.1,
+        }
+    }
+}
+
+/// MurphyVacuumWorldEnvironment is a VacuumWorldEnvironment where Suck is unreliable and the dirt
+/// sensor is noisy. The score is still the number of squares that are actually clean.
+///
+/// All randomness comes from the injected rng, so a seeded rng gives a repeatable run.
+pub struct MurphyVacuumWorldEnvironment {
+    world: VacuumWorldEnvironment,
+    config: MurphyConfig,
+    rng: Rc<RefCell<rand_pcg::Pcg64>>,
+}
+
+impl MurphyVacuumWorldEnvironment {
+    pub fn new(
+        world: VacuumWorldEnvironment,
+        config: MurphyConfig,
+        rng: Rc<RefCell<rand_pcg::Pcg64>>,
+    ) -> Self {
+        Self { world, config, rng }
+    }
+
+    /// The underlying world, with the true state of every square.
+    pub fn world(&self) -> &VacuumWorldEnvironment {
+        &self.world
+    }
+}
+
+impl Environment for MurphyVacuumWorldEnvironment {
+    type Action = VacuumWorldAction;
+    type Percept = VaccuumWorldLocalPercept;
+    type Score = i32;
+
+    fn percept(&self) -> Self::Percept {
+        let mut percept = self.world.percept();
+        if self
+            .rng
+            .borrow_mut()
+            .gen_bool(self.config.sensor_error_probability)
+        {
+            percept.square_state = match percept.square_state {
+                SquareState::Clean => SquareState::Dirty,
+                SquareState::Dirty => SquareState::Clean,
+            };
+        }
+        percept
+    }
+
+    fn execute_action(&mut self, action: &Self::Action) {
+        if *action != VacuumWorldAction::Suck {
+            self.world.execute_action(action);
+            return;
+        }
+
+        self.world.clear_blocked();
+        let location = self.world.agent_location();
+        let mut rng = self.rng.borrow_mut();
+        let next_state = match self.world.square_state(&location).unwrap() {
+            SquareState::Dirty if rng.gen_bool(self.config.suck_failure_probability) => {
+                SquareState::Dirty
+            }
+            SquareState::Dirty => SquareState::Clean,
+            SquareState::Clean if rng.gen_bool(self.config.dirt_deposit_probability) => {
+                SquareState::Dirty
+            }
+            SquareState::Clean => SquareState::Clean,
+        };
+        self.world.set_square_state(location, next_state);
+    }
+
+    fn score(&self) -> Self::Score {
+        self.world.score()
+    }
+}
+
+/// Draws the true state of the world, not what the noisy sensor reports.
+impl fmt::Display for MurphyVacuumWorldEnvironment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.world.fmt(f)
+    }
+}
+
+impl Termination<MurphyVacuumWorldEnvironment> for AllSquaresClean {
+    fn should_terminate(&mut self, event: &StepEvent<'_, MurphyVacuumWorldEnvironment>) -> bool {
+        event.environment.world().all_squares_clean()
+    }
+}
+
+/// MurphyReflexVacuumAgent is a reflex agent for the two-square world adapted to a noisy dirt
+/// sensor. Sucking a clean square can make it dirty, so the agent only sucks once the sensor has
+/// reported dirt twice in a row at the same location. Failed sucks are retried straight away
+/// because the dirt has already been confirmed.
+///
+/// Strictly this is not a simple reflex agent, because it remembers the previous percept.
+/// Exercise 2.13 asks whether a simple reflex agent can do well here, and this is the smallest
+/// amount of state that helps.
+pub struct MurphyReflexVacuumAgent {
+    history: PerceptHistory<VaccuumWorldLocalPercept>,
+}
+
+impl Default for MurphyReflexVacuumAgent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MurphyReflexVacuumAgent {
+    pub fn new() -> Self {
+        Self {
+            history: PerceptHistory::with_capacity(2),
+        }
+    }
+}
+
+impl Agent for MurphyReflexVacuumAgent {
+    type Action = VacuumWorldAction;
+    type Percept = VaccuumWorldLocalPercept;
+
+    fn act(&mut self, percept: &Self::Percept) -> Self::Action {
+        self.history.push(*percept);
+        match percept.square_state {
+            SquareState::Dirty => {
+                let confirmed = self.history.nth_latest(1) == Some(percept);
+                if confirmed {
+                    VacuumWorldAction::Suck
+                } else {
+                    VacuumWorldAction::NoOp
+                }
+            }
+            SquareState::Clean => {
+                if percept.location.x == 0 {
+                    VacuumWorldAction::Right
+                } else {
+                    VacuumWorldAction::Left
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::vacuum_world::ReflexVacuumAgent;
+    use crate::Simulation;
+
+    fn new_env(config: MurphyConfig, seed: u64) -> MurphyVacuumWorldEnvironment {
+        MurphyVacuumWorldEnvironment::new(
+            VacuumWorldEnvironment::default(),
+            config,
+            Rc::new(RefCell::new(rand_pcg::Pcg64::seed_from_u64(seed))),
+        )
+    }
+
+    fn average_score<_Agent, F>(new_agent: F) -> f64
+    where
+        _Agent: Agent<Action = VacuumWorldAction, Percept = VaccuumWorldLocalPercept>,
+        F: Fn() -> _Agent,
+    {
+        let trials = 50;
+        let total: i32 = (0..trials)
+            .map(|seed| {
+                let mut simulation =
+                    Simulation::new(new_env(MurphyConfig::default(), seed), new_agent(), 1000);
+                simulation.run();
+                simulation.score()
+            })
+            .sum();
+        f64::from(total) / f64::from(trials as i32)
+    }
+
+    #[test]
+    fn test_murphy_with_no_noise_behaves_like_vacuum_world() {
+        let config = MurphyConfig {
+            suck_failure_probability: 0.0,
+            dirt_deposit_probability: 0.0,
+            sensor_error_probability: 0.0,
+        };
+        let mut simulation = Simulation::new(new_env(config, 42), ReflexVacuumAgent::new(), 1000);
+        simulation.run();
+        assert_eq!(simulation.score(), 1 + 1 + 2 * 998);
+    }
+
+    #[test]
+    fn test_murphy_suck_on_clean_square_can_deposit_dirt() {
+        let config = MurphyConfig {
+            suck_failure_probability: 0.0,
+            dirt_deposit_probability: 1.0,
+            sensor_error_probability: 0.0,
+        };
+        let mut env = new_env(config, 42);
+        env.execute_action(&VacuumWorldAction::Suck);
+        assert_eq!(env.percept().square_state, SquareState::Clean);
+        env.execute_action(&VacuumWorldAction::Suck);
+        assert_eq!(env.percept().square_state, SquareState::Dirty);
+    }
+
+    #[test]
+    fn test_murphy_sensor_can_lie() {
+        let config = MurphyConfig {
+            suck_failure_probability: 0.0,
+            dirt_deposit_probability: 0.0,
+            sensor_error_probability: 1.0,
+        };
+        let env = new_env(config, 42);
+        assert_eq!(env.world().percept().square_state, SquareState::Dirty);
+        assert_eq!(env.percept().square_state, SquareState::Clean);
+    }
+
+    #[test]
+    fn test_murphy_adapted_agent_beats_reflex_agent() {
+        let reflex = average_score(ReflexVacuumAgent::new);
+        let adapted = average_score(MurphyReflexVacuumAgent::new);
+        assert!(adapted > reflex, "reflex: {}, adapted: {}", reflex, adapted);
+    }
+}
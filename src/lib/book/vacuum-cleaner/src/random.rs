@@ -0,0 +1,84 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+use rand::seq::SliceRandom;
+
+use crate::Agent;
+
+/// RandomAgent ignores its percepts and picks an action uniformly at random. It is the baseline
+/// that any sensible agent should beat.
+///
+/// All randomness comes from the injected rng, so a seeded rng gives a repeatable run.
+pub struct RandomAgent<_Percept, _Action> {
+    actions: Vec<_Action>,
+    rng: Rc<RefCell<rand_pcg::Pcg64>>,
+    _percept: PhantomData<fn(&_Percept)>,
+}
+
+impl<_Percept, _Action> RandomAgent<_Percept, _Action> {
+    pub fn new(actions: Vec<_Action>, rng: Rc<RefCell<rand_pcg::Pcg64>>) -> Self {
+        assert!(!actions.is_empty(), "actions must not be empty");
+        Self {
+            actions,
+            rng,
+            _percept: PhantomData,
+        }
+    }
+}
+
+impl<_Percept, _Action: Clone> Agent for RandomAgent<_Percept, _Action> {
+    type Action = _Action;
+    type Percept = _Percept;
+
+    fn act(&mut self, _percept: &Self::Percept) -> Self::Action {
+        self.actions
+            .choose(&mut *self.rng.borrow_mut())
+            .unwrap()
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::vacuum_world::{VacuumWorldAction, VacuumWorldEnvironment};
+    use crate::Environment;
+
+    #[test]
+    fn test_random_agent_is_repeatable_for_a_seed() {
+        let new_agent = || {
+            RandomAgent::new(
+                VacuumWorldAction::ALL.to_vec(),
+                Rc::new(RefCell::new(rand_pcg::Pcg64::seed_from_u64(3))),
+            )
+        };
+        let env = VacuumWorldEnvironment::default();
+        let mut a = new_agent();
+        let mut b = new_agent();
+        let actions_a: Vec<_> = (0..50).map(|_| a.act(&env.percept())).collect();
+        let actions_b: Vec<_> = (0..50).map(|_| b.act(&env.percept())).collect();
+        assert_eq!(actions_a, actions_b);
+        for action in VacuumWorldAction::ALL {
+            assert!(actions_a.contains(&action));
+        }
+    }
+}
@@ -0,0 +1,138 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Terminal rendering of simulations.
+
+use std::fmt::{Debug, Display};
+use std::io::{self, Write};
+use std::ops::ControlFlow;
+use std::time::Duration;
+
+use crate::{Environment, Observer, StepEvent};
+
+/// ANSI escape codes that clear the terminal and move the cursor to the top left.
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// AsciiRenderer is an Observer that prints the Environment, using its Display implementation,
+/// after every time step. In animated mode it redraws in place and pauses between steps so that
+/// the simulation can be watched.
+///
+/// Rendering stops the simulation if writing fails, e.g. because stdout was closed.
+pub struct AsciiRenderer<_Writer: Write> {
+    writer: _Writer,
+    animation_delay: Option<Duration>,
+}
+
+impl AsciiRenderer<io::Stdout> {
+    pub fn stdout() -> Self {
+        Self::new(io::stdout())
+    }
+}
+
+impl<_Writer: Write> AsciiRenderer<_Writer> {
+    pub fn new(writer: _Writer) -> Self {
+        Self {
+            writer,
+            animation_delay: None,
+        }
+    }
+
+    /// Clear the terminal before drawing each step, and pause for delay after it.
+    pub fn animated(mut self, delay: Duration) -> Self {
+        self.animation_delay = Some(delay);
+        self
+    }
+
+    /// Draw the Environment before the first time step.
+    pub fn render_initial<_Environment: Display>(
+        &mut self,
+        environment: &_Environment,
+    ) -> io::Result<()> {
+        if self.animation_delay.is_some() {
+            write!(self.writer, "{}", CLEAR_SCREEN)?;
+        }
+        writeln!(self.writer, "initial")?;
+        write!(self.writer, "{}", environment)?;
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> _Writer {
+        self.writer
+    }
+
+    fn render<_Environment>(&mut self, event: &StepEvent<'_, _Environment>) -> io::Result<()>
+    where
+        _Environment: Environment + Display,
+        _Environment::Action: Debug,
+        _Environment::Score: Display,
+    {
+        if self.animation_delay.is_some() {
+            write!(self.writer, "{}", CLEAR_SCREEN)?;
+        }
+        writeln!(
+            self.writer,
+            "step {}: {:?}, score {}",
+            event.step, event.action, event.score_delta
+        )?;
+        write!(self.writer, "{}", event.environment)?;
+        self.writer.flush()
+    }
+}
+
+impl<_Environment, _Writer> Observer<_Environment> for AsciiRenderer<_Writer>
+where
+    _Environment: Environment + Display,
+    _Environment::Action: Debug,
+    _Environment::Score: Display,
+    _Writer: Write,
+{
+    fn observe(&mut self, event: &StepEvent<'_, _Environment>) -> ControlFlow<()> {
+        if self.render(event).is_err() {
+            return ControlFlow::Break(());
+        }
+        if let Some(delay) = self.animation_delay {
+            std::thread::sleep(delay);
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vacuum_world::{ReflexVacuumAgent, VacuumWorldEnvironment};
+    use crate::Simulation;
+
+    #[test]
+    fn test_ascii_renderer_draws_every_step() {
+        let mut simulation = Simulation::new(
+            VacuumWorldEnvironment::default(),
+            ReflexVacuumAgent::new(),
+            3,
+        );
+        let mut renderer = AsciiRenderer::new(vec![]);
+        renderer.render_initial(simulation.environment()).unwrap();
+        simulation.run_with_observer(&mut renderer);
+        let output = String::from_utf8(renderer.into_inner()).unwrap();
+        assert_eq!(
+            output,
+            "initial\n[*] * \n\
+             step 0: Suck, score 1\n[.] * \n\
+             step 1: Right, score 1\n . [*]\n\
+             step 2: Suck, score 2\n . [.]\n"
+        );
+    }
+}
@@ -0,0 +1,297 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Recording and replaying simulations.
+
+use std::ops::ControlFlow;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Agent, Environment, EnvironmentScore, Observer, PerformanceMeasure, Simulation, StepEvent,
+};
+
+/// What happened in a single time step of a simulation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceStep<_Percept, _Action, _Score> {
+    pub percept: _Percept,
+    pub action: _Action,
+    pub score_delta: _Score,
+}
+
+/// SimulationTrace is the sequence of percepts, actions, and scores from a simulation. It can be
+/// serialized for offline analysis or visualization, and replayed against a fresh Environment to
+/// check that the simulation is deterministic.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimulationTrace<_Percept, _Action, _Score> {
+    pub steps: Vec<TraceStep<_Percept, _Action, _Score>>,
+
+    /// Total score over all steps.
+    pub score: _Score,
+}
+
+/// TraceRecorder is an Observer that builds a SimulationTrace.
+pub struct TraceRecorder<_Environment: Environment> {
+    trace: SimulationTrace<_Environment::Percept, _Environment::Action, _Environment::Score>,
+}
+
+impl<_Environment: Environment> Default for TraceRecorder<_Environment> {
+    fn default() -> Self {
+        Self {
+            trace: SimulationTrace {
+                steps: vec![],
+                score: num_traits::Zero::zero(),
+            },
+        }
+    }
+}
+
+impl<_Environment: Environment> TraceRecorder<_Environment> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn into_trace(
+        self,
+    ) -> SimulationTrace<_Environment::Percept, _Environment::Action, _Environment::Score> {
+        self.trace
+    }
+}
+
+impl<_Environment> Observer<_Environment> for TraceRecorder<_Environment>
+where
+    _Environment: Environment,
+    _Environment::Percept: Clone,
+    _Environment::Action: Clone,
+{
+    fn observe(&mut self, event: &StepEvent<'_, _Environment>) -> ControlFlow<()> {
+        self.trace.steps.push(TraceStep {
+            percept: event.percept.clone(),
+            action: event.action.clone(),
+            score_delta: event.score_delta,
+        });
+        self.trace.score += event.score_delta;
+        ControlFlow::Continue(())
+    }
+}
+
+impl<_Environment, _Agent, _Measure> Simulation<_Environment, _Agent, _Measure>
+where
+    _Environment: Environment,
+    _Environment::Percept: Clone,
+    _Environment::Action: Clone,
+    _Agent: Agent<Action = _Environment::Action, Percept = _Environment::Percept>,
+    _Measure: PerformanceMeasure<_Environment>,
+{
+    /// Run the simulation and record a trace of every time step.
+    pub fn run_traced(
+        &mut self,
+    ) -> SimulationTrace<_Environment::Percept, _Environment::Action, _Environment::Score> {
+        let mut recorder = TraceRecorder::new();
+        self.run_with_observer(&mut recorder);
+        recorder.into_trace()
+    }
+}
+
+/// SnapshotTrace is a SimulationTrace that also holds a copy of the Environment before the first
+/// time step and after every time step, for offline analysis of the full world state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "_Environment: Serialize, _Environment::Percept: Serialize, \
+                 _Environment::Action: Serialize, _Environment::Score: Serialize",
+    deserialize = "_Environment: Deserialize<'de>, _Environment::Percept: Deserialize<'de>, \
+                   _Environment::Action: Deserialize<'de>, _Environment::Score: Deserialize<'de>"
+))]
+pub struct SnapshotTrace<_Environment: Environment> {
+    pub trace: SimulationTrace<_Environment::Percept, _Environment::Action, _Environment::Score>,
+    pub initial: _Environment,
+
+    /// The Environment after each step, in the same order as trace.steps.
+    pub snapshots: Vec<_Environment>,
+}
+
+impl<_Environment, _Agent, _Measure> Simulation<_Environment, _Agent, _Measure>
+where
+    _Environment: Environment + Clone,
+    _Environment::Percept: Clone,
+    _Environment::Action: Clone,
+    _Agent: Agent<Action = _Environment::Action, Percept = _Environment::Percept>,
+    _Measure: PerformanceMeasure<_Environment>,
+{
+    /// Run the simulation and record a trace of every time step, including snapshots of the
+    /// Environment.
+    pub fn run_traced_with_snapshots(&mut self) -> SnapshotTrace<_Environment> {
+        let initial = self.environment().clone();
+        let mut recorder = TraceRecorder::new();
+        let mut snapshots = vec![];
+        self.run_with_observer(&mut |event: &StepEvent<'_, _Environment>| {
+            snapshots.push(event.environment.clone());
+            recorder.observe(event)
+        });
+        SnapshotTrace {
+            trace: recorder.into_trace(),
+            initial,
+            snapshots,
+        }
+    }
+}
+
+/// Where a replay diverged from its trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum ReplayError {
+    /// The Environment gave a different percept to the one recorded.
+    #[error("percept differs from trace at step {0}")]
+    PerceptMismatch(usize),
+
+    /// Executing the recorded action gave a different score to the one recorded.
+    #[error("score differs from trace at step {0}")]
+    ScoreMismatch(usize),
+}
+
+/// replay re-drives an Environment with the actions from a trace, without an Agent. It checks that
+/// every percept and score matches the trace, so with a freshly constructed Environment this
+/// verifies that the simulation is deterministic. On success returns the total score.
+///
+/// Scores are the Environment's own score. Use replay_with_measure for a trace recorded from a
+/// Simulation with a different PerformanceMeasure.
+pub fn replay<_Environment>(
+    environment: &mut _Environment,
+    trace: &SimulationTrace<_Environment::Percept, _Environment::Action, _Environment::Score>,
+) -> Result<_Environment::Score, ReplayError>
+where
+    _Environment: Environment,
+    _Environment::Percept: PartialEq,
+    _Environment::Score: PartialEq,
+{
+    replay_with_measure(environment, &mut EnvironmentScore, trace)
+}
+
+/// replay_with_measure is replay, scoring each step with measure. The measure should be in the
+/// same state as the one used to record the trace, e.g. freshly constructed.
+pub fn replay_with_measure<_Environment, _Measure>(
+    environment: &mut _Environment,
+    measure: &mut _Measure,
+    trace: &SimulationTrace<_Environment::Percept, _Environment::Action, _Environment::Score>,
+) -> Result<_Environment::Score, ReplayError>
+where
+    _Environment: Environment,
+    _Environment::Percept: PartialEq,
+    _Environment::Score: PartialEq,
+    _Measure: PerformanceMeasure<_Environment>,
+{
+    let mut score: _Environment::Score = num_traits::Zero::zero();
+    for (step, recorded) in trace.steps.iter().enumerate() {
+        if environment.percept() != recorded.percept {
+            return Err(ReplayError::PerceptMismatch(step));
+        }
+        environment.execute_action(&recorded.action);
+        let score_delta = measure.score(&recorded.action, environment);
+        if score_delta != recorded.score_delta {
+            return Err(ReplayError::ScoreMismatch(step));
+        }
+        score += score_delta;
+    }
+    Ok(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::murphy::{MurphyConfig, MurphyReflexVacuumAgent, MurphyVacuumWorldEnvironment};
+    use crate::vacuum_world::{SweepingVacuumAgent, VacuumWorldAction, VacuumWorldEnvironment};
+
+    #[test]
+    fn test_trace_replays_and_round_trips_through_json() {
+        let mut simulation = Simulation::new(
+            VacuumWorldEnvironment::new_random(3, 3, 0.5, 42),
+            SweepingVacuumAgent::new(3, 3),
+            50,
+        );
+        let trace = simulation.run_traced();
+        assert_eq!(trace.steps.len(), 50);
+        assert_eq!(trace.score, simulation.score());
+
+        let json = serde_json::to_string(&trace).unwrap();
+        let trace = serde_json::from_str(&json).unwrap();
+        let mut environment = VacuumWorldEnvironment::new_random(3, 3, 0.5, 42);
+        assert_eq!(replay(&mut environment, &trace), Ok(simulation.score()));
+    }
+
+    #[test]
+    fn test_snapshot_trace_round_trips_through_json() {
+        let mut simulation = Simulation::new(
+            VacuumWorldEnvironment::new_random(2, 2, 0.5, 3),
+            SweepingVacuumAgent::new(2, 2),
+            8,
+        );
+        let snapshot_trace = simulation.run_traced_with_snapshots();
+        assert_eq!(snapshot_trace.snapshots.len(), 8);
+        assert_eq!(
+            snapshot_trace.snapshots.last(),
+            Some(simulation.environment())
+        );
+
+        let json = serde_json::to_string(&snapshot_trace).unwrap();
+        let round_tripped: SnapshotTrace<VacuumWorldEnvironment> =
+            serde_json::from_str(&json).unwrap();
+        assert_eq!(round_tripped.trace, snapshot_trace.trace);
+        assert_eq!(round_tripped.snapshots, snapshot_trace.snapshots);
+        let mut environment = round_tripped.initial;
+        assert_eq!(
+            replay(&mut environment, &round_tripped.trace),
+            Ok(simulation.score())
+        );
+    }
+
+    #[test]
+    fn test_replay_detects_divergence() {
+        let mut simulation = Simulation::new(
+            VacuumWorldEnvironment::default(),
+            SweepingVacuumAgent::new(1, 2),
+            10,
+        );
+        let mut trace = simulation.run_traced();
+        trace.steps[2].action = VacuumWorldAction::NoOp;
+        let mut environment = VacuumWorldEnvironment::default();
+        assert_eq!(
+            replay(&mut environment, &trace),
+            Err(ReplayError::ScoreMismatch(2))
+        );
+    }
+
+    #[test]
+    fn test_replay_of_seeded_stochastic_environment_is_deterministic() {
+        let new_environment = || {
+            MurphyVacuumWorldEnvironment::new(
+                VacuumWorldEnvironment::default(),
+                MurphyConfig::default(),
+                Rc::new(RefCell::new(rand_pcg::Pcg64::seed_from_u64(7))),
+            )
+        };
+        let mut simulation =
+            Simulation::new(new_environment(), MurphyReflexVacuumAgent::new(), 200);
+        let trace = simulation.run_traced();
+        assert_eq!(
+            replay(&mut new_environment(), &trace),
+            Ok(simulation.score())
+        );
+    }
+}
@@ -14,22 +14,42 @@
  * with this program. If not, see <https://www.gnu.org/licenses/>
  */
 
-use crate::{Agent, Environment};
-use std::collections::HashMap;
+use crate::{Agent, Environment, PerformanceMeasure, StepEvent, Termination};
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct VacuumWorldLocation {
     pub x: i32,
     pub y: i32,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+impl VacuumWorldLocation {
+    /// Where the agent would end up after action if nothing is in the way.
+    pub fn moved(&self, action: &VacuumWorldAction) -> Self {
+        let (dx, dy) = match action {
+            VacuumWorldAction::Left => (-1, 0),
+            VacuumWorldAction::Right => (1, 0),
+            VacuumWorldAction::Up => (0, 1),
+            VacuumWorldAction::Down => (0, -1),
+            VacuumWorldAction::Suck | VacuumWorldAction::NoOp => (0, 0),
+        };
+        Self {
+            x: self.x + dx,
+            y: self.y + dy,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum SquareState {
     Clean,
     Dirty,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum VacuumWorldAction {
     Left,
     Right,
@@ -39,15 +59,64 @@ pub enum VacuumWorldAction {
     NoOp,
 }
 
+impl VacuumWorldAction {
+    pub const ALL: [VacuumWorldAction; 6] = [
+        VacuumWorldAction::Left,
+        VacuumWorldAction::Right,
+        VacuumWorldAction::Up,
+        VacuumWorldAction::Down,
+        VacuumWorldAction::Suck,
+        VacuumWorldAction::NoOp,
+    ];
+}
+
 /// VacuumWorldLocalPercept is the Percept that the Agent receives from the Environment for just
 /// a single location, e.g imagine a dirt sensor looking right down.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct VaccuumWorldLocalPercept {
     pub location: VacuumWorldLocation,
     pub square_state: SquareState,
+
+    /// The agent's last move was blocked by the edge of the world or an obstacle.
+    #[serde(default)]
+    pub bump: bool,
 }
 
-#[derive(Default)]
+/// Serializes a map keyed by location as a list of pairs, because formats like JSON only allow
+/// string keys.
+pub(crate) mod location_map {
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::VacuumWorldLocation;
+
+    pub fn serialize<V, S>(
+        map: &HashMap<VacuumWorldLocation, V>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        V: Serialize,
+        S: Serializer,
+    {
+        let mut pairs: Vec<(&VacuumWorldLocation, &V)> = map.iter().collect();
+        pairs.sort_by_key(|(location, _)| (location.y, location.x));
+        serializer.collect_seq(pairs)
+    }
+
+    pub fn deserialize<'de, V, D>(
+        deserializer: D,
+    ) -> Result<HashMap<VacuumWorldLocation, V>, D::Error>
+    where
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let pairs: Vec<(VacuumWorldLocation, V)> = Vec::deserialize(deserializer)?;
+        Ok(pairs.into_iter().collect())
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct ReflexVacuumAgent {}
 
 impl ReflexVacuumAgent {
@@ -71,11 +140,253 @@ impl Agent for ReflexVacuumAgent {
     }
 }
 
+/// SweepingVacuumAgent cleans a width by height world by sweeping it in a serpentine (boustrophedon)
+/// pattern: along a row to its end, one row up or down, then back along that row the other way.
+/// When it reaches the top or bottom row it reverses and sweeps back. It sucks whenever the
+/// current square is dirty.
+///
+/// In the two-square world this behaves exactly like ReflexVacuumAgent. The only state it keeps is
+/// which way it is heading; it knows the size of the world so that it can turn at the edges.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepingVacuumAgent {
+    height: i32,
+    width: i32,
+    heading_right: bool,
+    heading_up: bool,
+}
+
+impl SweepingVacuumAgent {
+    pub fn new(height: i32, width: i32) -> Self {
+        Self {
+            height,
+            width,
+            heading_right: true,
+            heading_up: true,
+        }
+    }
+
+    fn can_move_vertically(&self, location: &VacuumWorldLocation) -> bool {
+        if self.heading_up {
+            location.y < self.height - 1
+        } else {
+            location.y > 0
+        }
+    }
+
+    fn horizontal_action(&self) -> VacuumWorldAction {
+        if self.heading_right {
+            VacuumWorldAction::Right
+        } else {
+            VacuumWorldAction::Left
+        }
+    }
+}
+
+impl Agent for SweepingVacuumAgent {
+    type Action = VacuumWorldAction;
+    type Percept = VaccuumWorldLocalPercept;
+
+    fn act(&mut self, percept: &Self::Percept) -> Self::Action {
+        if percept.square_state == SquareState::Dirty {
+            return VacuumWorldAction::Suck;
+        }
+
+        let location = percept.location;
+        let at_row_end = if self.heading_right {
+            location.x >= self.width - 1
+        } else {
+            location.x <= 0
+        };
+        if !at_row_end {
+            return self.horizontal_action();
+        }
+
+        if !self.can_move_vertically(&location) {
+            self.heading_up = !self.heading_up;
+        }
+        self.heading_right = !self.heading_right;
+        if self.can_move_vertically(&location) {
+            if self.heading_up {
+                VacuumWorldAction::Up
+            } else {
+                VacuumWorldAction::Down
+            }
+        } else {
+            // A single-row world, so just turn around.
+            self.horizontal_action()
+        }
+    }
+}
+
+/// The moves, in the order agents that plan routes try them.
+pub(crate) const MOVES: [VacuumWorldAction; 4] = [
+    VacuumWorldAction::Left,
+    VacuumWorldAction::Right,
+    VacuumWorldAction::Up,
+    VacuumWorldAction::Down,
+];
+
+/// ModelBasedVacuumAgent is a model-based reflex agent, see Chapter 2 "Model-based reflex
+/// agents". Unlike ReflexVacuumAgent it keeps internal state: a model of which squares it has seen
+/// and whether they are clean. It knows the size of the world but not where the dirt is.
+///
+/// The model is updated from each percept and from the predicted effect of the agent's own
+/// actions, i.e. after Suck the current square is assumed to be clean. Once the model says every
+/// square is clean the agent stops moving and only issues NoOp.
+///
+/// The agent does not know where any obstacles are. A Bump tells it that the square it tried to
+/// move into is blocked, and it plans its moves around the blocked squares it knows about, so in
+/// a world with obstacles the environment must use BoundaryBehavior::Bump, which is the default.
+/// Squares it can't reach are never cleaned, and it issues NoOp once every square it can reach is
+/// clean.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelBasedVacuumAgent {
+    height: i32,
+    width: i32,
+    #[serde(with = "location_map")]
+    model: HashMap<VacuumWorldLocation, SquareState>,
+    #[serde(default)]
+    blocked: HashSet<VacuumWorldLocation>,
+    #[serde(default)]
+    last_action: Option<VacuumWorldAction>,
+}
+
+impl ModelBasedVacuumAgent {
+    pub fn new(height: i32, width: i32) -> Self {
+        Self {
+            height,
+            width,
+            model: HashMap::new(),
+            blocked: HashSet::new(),
+            last_action: None,
+        }
+    }
+
+    /// What the agent believes about a square, or None if it has never been there.
+    pub fn believed_square_state(&self, location: &VacuumWorldLocation) -> Option<SquareState> {
+        self.model.get(location).copied()
+    }
+
+    /// Whether the agent has bumped into the square.
+    pub fn believes_blocked(&self, location: &VacuumWorldLocation) -> bool {
+        self.blocked.contains(location)
+    }
+
+    /// Update the model from a percept. Learning from the same percept twice changes nothing.
+    pub(crate) fn learn(&mut self, percept: &VaccuumWorldLocalPercept) {
+        if let Some(action) = self.last_action.take() {
+            if percept.bump {
+                self.blocked.insert(percept.location.moved(&action));
+            }
+        }
+        self.model.insert(percept.location, percept.square_state);
+    }
+
+    /// Number of moves from `from` to each square it can reach, going through squares that are
+    /// inside the world and not known to be blocked.
+    fn distances_from(&self, from: VacuumWorldLocation) -> HashMap<VacuumWorldLocation, i32> {
+        let mut distances = HashMap::from([(from, 0)]);
+        let mut frontier = VecDeque::from([from]);
+        while let Some(current) = frontier.pop_front() {
+            for action in MOVES {
+                let next = current.moved(&action);
+                let inside =
+                    (0..self.width).contains(&next.x) && (0..self.height).contains(&next.y);
+                if inside && !self.blocked.contains(&next) && !distances.contains_key(&next) {
+                    distances.insert(next, distances[&current] + 1);
+                    frontier.push_back(next);
+                }
+            }
+        }
+        distances
+    }
+
+    /// Number of moves on the shortest route from `from` to `to` that the agent knows of, or None
+    /// if it knows of none.
+    pub(crate) fn distance(
+        &self,
+        from: VacuumWorldLocation,
+        to: VacuumWorldLocation,
+    ) -> Option<i32> {
+        self.distances_from(to).get(&from).copied()
+    }
+
+    /// The first move of the shortest route from `from` to `to` that the agent knows of, or None
+    /// if it is already there or knows of no route. The move is remembered so that a Bump in the
+    /// next percept can be learned from.
+    pub(crate) fn move_towards(
+        &mut self,
+        from: VacuumWorldLocation,
+        to: VacuumWorldLocation,
+    ) -> Option<VacuumWorldAction> {
+        let distances = self.distances_from(to);
+        let distance = *distances.get(&from)?;
+        let action = MOVES
+            .into_iter()
+            .find(|action| distances.get(&from.moved(action)) == Some(&(distance - 1)))?;
+        self.last_action = Some(action);
+        Some(action)
+    }
+
+    /// The nearest reachable square, by the number of moves to it, that is not known to be clean.
+    fn nearest_unknown(&self, from: VacuumWorldLocation) -> Option<VacuumWorldLocation> {
+        self.distances_from(from)
+            .into_iter()
+            .filter(|(location, _)| self.model.get(location) != Some(&SquareState::Clean))
+            .min_by_key(|(location, distance)| (*distance, location.x, location.y))
+            .map(|(location, _)| location)
+    }
+}
+
+impl Agent for ModelBasedVacuumAgent {
+    type Action = VacuumWorldAction;
+    type Percept = VaccuumWorldLocalPercept;
+
+    fn act(&mut self, percept: &Self::Percept) -> Self::Action {
+        self.learn(percept);
+
+        if percept.square_state == SquareState::Dirty {
+            self.model.insert(percept.location, SquareState::Clean);
+            return VacuumWorldAction::Suck;
+        }
+
+        self.nearest_unknown(percept.location)
+            .and_then(|target| self.move_towards(percept.location, target))
+            .unwrap_or(VacuumWorldAction::NoOp)
+    }
+}
+
+/// What happens when the agent tries to move off the edge of the world or onto an obstacle. The
+/// agent never moves in any case. Different exercises in the book assume different conventions.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BoundaryBehavior {
+    /// Silently stay put. The agent can only tell from its location that it did not move.
+    Clamp,
+
+    /// Stay put, and perceive a Bump in the next percept.
+    #[default]
+    Bump,
+
+    /// Stay put, and lose penalty points from the score for that time step.
+    Penalize(i32),
+}
+
+/// VacuumWorldEnvironment can be serialized, e.g. to checkpoint a long experiment or to embed
+/// snapshots of the world in a trace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct VacuumWorldEnvironment {
     height: i32,
     width: i32,
+    #[serde(with = "location_map")]
     squares: HashMap<VacuumWorldLocation, SquareState>,
+    obstacles: HashSet<VacuumWorldLocation>,
     agent_location: VacuumWorldLocation,
+    #[serde(default)]
+    boundary_behavior: BoundaryBehavior,
+
+    /// The agent's last move was blocked.
+    #[serde(default)]
+    blocked: bool,
 }
 
 impl Default for VacuumWorldEnvironment {
@@ -98,8 +409,117 @@ impl VacuumWorldEnvironment {
             height,
             width,
             squares,
+            obstacles: HashSet::new(),
             agent_location: VacuumWorldLocation { x: 0, y: 0 },
+            boundary_behavior: BoundaryBehavior::default(),
+            blocked: false,
+        }
+    }
+
+    /// Create a world where each square's state is taken from dirt_map, so that experiments can
+    /// choose the dirt placement. Squares missing from dirt_map are clean.
+    pub fn new_with_dirt_map(
+        height: i32,
+        width: i32,
+        dirt_map: &HashMap<VacuumWorldLocation, SquareState>,
+        agent_location: VacuumWorldLocation,
+    ) -> Self {
+        let mut squares = HashMap::new();
+        for x in 0..width {
+            for y in 0..height {
+                let location = VacuumWorldLocation { x, y };
+                let square_state = dirt_map
+                    .get(&location)
+                    .copied()
+                    .unwrap_or(SquareState::Clean);
+                squares.insert(location, square_state);
+            }
+        }
+        Self {
+            height,
+            width,
+            squares,
+            obstacles: HashSet::new(),
+            agent_location,
+            boundary_behavior: BoundaryBehavior::default(),
+            blocked: false,
+        }
+    }
+
+    /// Create a world where each square is independently dirty with probability dirt_probability.
+    /// The same seed always gives the same dirt placement. The agent starts at (0, 0).
+    pub fn new_random(height: i32, width: i32, dirt_probability: f64, seed: u64) -> Self {
+        let mut rng = rand_pcg::Pcg64::seed_from_u64(seed);
+        let mut dirt_map = HashMap::new();
+        for x in 0..width {
+            for y in 0..height {
+                let square_state = if rng.gen_bool(dirt_probability) {
+                    SquareState::Dirty
+                } else {
+                    SquareState::Clean
+                };
+                dirt_map.insert(VacuumWorldLocation { x, y }, square_state);
+            }
         }
+        Self::new_with_dirt_map(height, width, &dirt_map, VacuumWorldLocation { x: 0, y: 0 })
+    }
+
+    /// Put obstacles on the given squares. Obstacles are not squares that can be clean or dirty,
+    /// and the agent cannot move onto them. The agent must not start on an obstacle.
+    pub fn with_obstacles(mut self, obstacles: &[VacuumWorldLocation]) -> Self {
+        for obstacle in obstacles {
+            assert_ne!(
+                *obstacle, self.agent_location,
+                "agent starts on an obstacle"
+            );
+            self.squares.remove(obstacle);
+            self.obstacles.insert(*obstacle);
+        }
+        self
+    }
+
+    /// Set what happens when the agent tries to move off the edge of the world or onto an
+    /// obstacle. The default is BoundaryBehavior::Bump.
+    pub fn with_boundary_behavior(mut self, boundary_behavior: BoundaryBehavior) -> Self {
+        self.boundary_behavior = boundary_behavior;
+        self
+    }
+
+    pub fn boundary_behavior(&self) -> BoundaryBehavior {
+        self.boundary_behavior
+    }
+
+    pub fn is_obstacle(&self, location: &VacuumWorldLocation) -> bool {
+        self.obstacles.contains(location)
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn agent_location(&self) -> VacuumWorldLocation {
+        self.agent_location
+    }
+
+    pub fn square_state(&self, location: &VacuumWorldLocation) -> Option<SquareState> {
+        self.squares.get(location).copied()
+    }
+
+    pub(crate) fn set_square_state(&mut self, location: VacuumWorldLocation, state: SquareState) {
+        self.squares.insert(location, state);
+    }
+
+    /// For wrapping environments that handle an action themselves without moving the agent.
+    pub(crate) fn clear_blocked(&mut self) {
+        self.blocked = false;
+    }
+
+    pub fn all_squares_clean(&self) -> bool {
+        self.squares.values().all(|&s| s == SquareState::Clean)
     }
 
     fn count_clean_squares(&self) -> i32 {
@@ -119,40 +539,139 @@ impl Environment for VacuumWorldEnvironment {
         VaccuumWorldLocalPercept {
             location: self.agent_location,
             square_state: *self.squares.get(&self.agent_location).unwrap(),
+            bump: self.blocked && self.boundary_behavior == BoundaryBehavior::Bump,
         }
     }
 
     fn execute_action(&mut self, action: &Self::Action) {
-        match action {
-            VacuumWorldAction::Left => {
-                self.agent_location.x -= 1;
-            }
-            VacuumWorldAction::Right => {
-                self.agent_location.x += 1;
-            }
-            VacuumWorldAction::Up => {
-                self.agent_location.y += 1;
-            }
-            VacuumWorldAction::Down => {
-                self.agent_location.y -= 1;
+        if *action == VacuumWorldAction::Suck {
+            self.squares.insert(self.agent_location, SquareState::Clean);
+        }
+        let next = self.agent_location.moved(action);
+        self.blocked = !self.squares.contains_key(&next);
+        if !self.blocked {
+            self.agent_location = next;
+        }
+    }
+
+    fn score(&self) -> Self::Score {
+        match self.boundary_behavior {
+            BoundaryBehavior::Penalize(penalty) if self.blocked => {
+                self.count_clean_squares() - penalty
             }
-            VacuumWorldAction::Suck => {
-                self.squares.insert(self.agent_location, SquareState::Clean);
+            _ => self.count_clean_squares(),
+        }
+    }
+}
+
+/// Draws the world from the top row down. Clean squares are '.', dirty squares are '*', and
+/// obstacles are '#'. The agent's square is in brackets, e.g. "[*]" is the agent on a dirty
+/// square.
+impl fmt::Display for VacuumWorldEnvironment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in (0..self.height).rev() {
+            for x in 0..self.width {
+                let location = VacuumWorldLocation { x, y };
+                let square = if self.is_obstacle(&location) {
+                    '#'
+                } else {
+                    match self.squares[&location] {
+                        SquareState::Clean => '.',
+                        SquareState::Dirty => '*',
+                    }
+                };
+                if location == self.agent_location {
+                    write!(f, "[{}]", square)?;
+                } else {
+                    write!(f, " {} ", square)?;
+                }
             }
-            VacuumWorldAction::NoOp => {}
+            writeln!(f)?;
         }
-        self.agent_location.x = self.agent_location.x.clamp(0, self.width - 1);
-        self.agent_location.y = self.agent_location.y.clamp(0, self.height - 1);
+        Ok(())
     }
+}
 
-    fn score(&self) -> Self::Score {
-        self.count_clean_squares()
+/// CleanSquaresMinusMoves awards a point per clean square per time step, like the Environment's own
+/// score, but takes off move_penalty for every Left, Right, Up, or Down. Exercise 2.10 asks how
+/// agents should behave under this kind of measure.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CleanSquaresMinusMoves {
+    pub move_penalty: i32,
+}
+
+impl PerformanceMeasure<VacuumWorldEnvironment> for CleanSquaresMinusMoves {
+    fn score(&mut self, action: &VacuumWorldAction, environment: &VacuumWorldEnvironment) -> i32 {
+        let penalty = match action {
+            VacuumWorldAction::Left
+            | VacuumWorldAction::Right
+            | VacuumWorldAction::Up
+            | VacuumWorldAction::Down => self.move_penalty,
+            VacuumWorldAction::Suck | VacuumWorldAction::NoOp => 0,
+        };
+        environment.score() - penalty
+    }
+}
+
+/// TimeToAllClean scores -1 for every time step until every square is clean, and 0 afterwards,
+/// so the total score is minus the time the agent took to clean the world.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeToAllClean {
+    done: bool,
+}
+
+impl PerformanceMeasure<VacuumWorldEnvironment> for TimeToAllClean {
+    fn score(&mut self, _action: &VacuumWorldAction, environment: &VacuumWorldEnvironment) -> i32 {
+        if self.done {
+            return 0;
+        }
+        self.done = environment.all_squares_clean();
+        -1
+    }
+}
+
+/// Terminates a Simulation once every square is clean.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AllSquaresClean;
+
+impl Termination<VacuumWorldEnvironment> for AllSquaresClean {
+    fn should_terminate(&mut self, event: &StepEvent<'_, VacuumWorldEnvironment>) -> bool {
+        event.environment.all_squares_clean()
+    }
+}
+
+/// Terminates a Simulation once the agent has issued NoOp limit times in a row, i.e. the agent
+/// believes it has nothing left to do.
+#[derive(Debug, Clone, Copy)]
+pub struct ConsecutiveNoOps {
+    limit: i32,
+    count: i32,
+}
+
+impl ConsecutiveNoOps {
+    pub fn new(limit: i32) -> Self {
+        Self { limit, count: 0 }
+    }
+}
+
+impl<_Environment> Termination<_Environment> for ConsecutiveNoOps
+where
+    _Environment: Environment<Action = VacuumWorldAction>,
+{
+    fn should_terminate(&mut self, event: &StepEvent<'_, _Environment>) -> bool {
+        if *event.action == VacuumWorldAction::NoOp {
+            self.count += 1;
+        } else {
+            self.count = 0;
+        }
+        self.count >= self.limit
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Simulation;
 
     #[test]
     fn test_reflex_vacuum_agent() {
@@ -160,25 +679,203 @@ mod tests {
         let percept = VaccuumWorldLocalPercept {
             location: VacuumWorldLocation { x: 0, y: 0 },
             square_state: SquareState::Dirty,
+            bump: false,
         };
         assert_eq!(agent.act(&percept), VacuumWorldAction::Suck);
         let percept = VaccuumWorldLocalPercept {
             location: VacuumWorldLocation { x: 1, y: 0 },
             square_state: SquareState::Dirty,
+            bump: false,
         };
         assert_eq!(agent.act(&percept), VacuumWorldAction::Suck);
         let percept = VaccuumWorldLocalPercept {
             location: VacuumWorldLocation { x: 0, y: 0 },
             square_state: SquareState::Clean,
+            bump: false,
         };
         assert_eq!(agent.act(&percept), VacuumWorldAction::Right);
         let percept = VaccuumWorldLocalPercept {
             location: VacuumWorldLocation { x: 1, y: 0 },
             square_state: SquareState::Clean,
+            bump: false,
         };
         assert_eq!(agent.act(&percept), VacuumWorldAction::Left);
     }
 
+    #[test]
+    fn test_model_based_vacuum_agent_stops_once_everything_is_clean() {
+        let mut agent = ModelBasedVacuumAgent::new(1, 2);
+        let mut env = VacuumWorldEnvironment::default();
+        let mut actions = vec![];
+        for _ in 0..5 {
+            let action = agent.act(&env.percept());
+            env.execute_action(&action);
+            actions.push(action);
+        }
+        assert_eq!(
+            actions,
+            vec![
+                VacuumWorldAction::Suck,
+                VacuumWorldAction::Right,
+                VacuumWorldAction::Suck,
+                VacuumWorldAction::NoOp,
+                VacuumWorldAction::NoOp,
+            ]
+        );
+        assert_eq!(
+            agent.believed_square_state(&VacuumWorldLocation { x: 0, y: 0 }),
+            Some(SquareState::Clean)
+        );
+    }
+
+    #[test]
+    fn test_all_squares_clean_terminates_simulation() {
+        let mut simulation = Simulation::new(
+            VacuumWorldEnvironment::new(3, 3),
+            SweepingVacuumAgent::new(3, 3),
+            1000,
+        );
+        let outcome = simulation.run_until(&mut AllSquaresClean);
+        assert!(simulation.environment().all_squares_clean());
+        assert!(outcome.steps_taken < 1000);
+    }
+
+    #[test]
+    fn test_performance_measures() {
+        let mut simulation = Simulation::with_measure(
+            VacuumWorldEnvironment::default(),
+            ReflexVacuumAgent::new(),
+            10,
+            CleanSquaresMinusMoves { move_penalty: 1 },
+        );
+        simulation.run();
+        // Suck scores 1, Right scores 1 - 1, Suck scores 2, then 7 moves back and forth with both
+        // squares clean score 2 - 1 each.
+        assert_eq!(simulation.score(), 10);
+
+        let mut simulation = Simulation::with_measure(
+            VacuumWorldEnvironment::new(3, 3),
+            SweepingVacuumAgent::new(3, 3),
+            1000,
+            TimeToAllClean::default(),
+        );
+        let outcome = simulation.run_until(&mut AllSquaresClean);
+        assert_eq!(outcome.score, -outcome.steps_taken);
+
+        let mut simulation = Simulation::with_measure(
+            VacuumWorldEnvironment::new(3, 3),
+            SweepingVacuumAgent::new(3, 3),
+            1000,
+            TimeToAllClean::default(),
+        );
+        simulation.run();
+        assert_eq!(simulation.score(), outcome.score);
+    }
+
+    #[test]
+    fn test_consecutive_noops_terminates_simulation() {
+        let mut simulation = Simulation::new(
+            VacuumWorldEnvironment::default(),
+            ModelBasedVacuumAgent::new(1, 2),
+            1000,
+        );
+        // Suck, Right, Suck, then NoOp forever.
+        let outcome = simulation.run_until(&mut ConsecutiveNoOps::new(3));
+        assert_eq!(outcome.steps_taken, 6);
+        assert_eq!(outcome.score, 1 + 1 + 2 + 2 + 2 + 2);
+    }
+
+    #[test]
+    fn test_model_based_vacuum_agent_cleans_a_grid() {
+        let mut agent = ModelBasedVacuumAgent::new(3, 3);
+        let mut env = VacuumWorldEnvironment::new(3, 3);
+        let mut last_action = VacuumWorldAction::NoOp;
+        for _ in 0..50 {
+            last_action = agent.act(&env.percept());
+            env.execute_action(&last_action);
+        }
+        assert_eq!(env.score(), 9);
+        assert_eq!(last_action, VacuumWorldAction::NoOp);
+    }
+
+    #[test]
+    fn test_model_based_vacuum_agent_plans_around_obstacles() {
+        // The agent starts bottom left, behind a wall. The bottom right square is walled in.
+        //  *  *  *
+        //  *  #  #
+        // [*] #  *
+        let obstacles = [
+            VacuumWorldLocation { x: 1, y: 0 },
+            VacuumWorldLocation { x: 1, y: 1 },
+            VacuumWorldLocation { x: 2, y: 1 },
+        ];
+        let mut agent = ModelBasedVacuumAgent::new(3, 3);
+        let mut env = VacuumWorldEnvironment::new(3, 3).with_obstacles(&obstacles);
+        let mut last_action = VacuumWorldAction::NoOp;
+        for _ in 0..50 {
+            last_action = agent.act(&env.percept());
+            env.execute_action(&last_action);
+        }
+        // All five squares the agent can reach are clean, and it has stopped.
+        assert_eq!(env.score(), 5);
+        assert_eq!(last_action, VacuumWorldAction::NoOp);
+        assert!(agent.believes_blocked(&VacuumWorldLocation { x: 1, y: 0 }));
+        assert_eq!(
+            agent.believed_square_state(&VacuumWorldLocation { x: 2, y: 0 }),
+            None
+        );
+    }
+
+    #[test]
+    fn test_sweeping_vacuum_agent_matches_reflex_agent_in_two_square_world() {
+        let mut sweeping_env = VacuumWorldEnvironment::default();
+        let mut reflex_env = VacuumWorldEnvironment::default();
+        let mut sweeping_agent = SweepingVacuumAgent::new(1, 2);
+        let mut reflex_agent = ReflexVacuumAgent::new();
+        for _ in 0..10 {
+            let sweeping_action = sweeping_agent.act(&sweeping_env.percept());
+            let reflex_action = reflex_agent.act(&reflex_env.percept());
+            assert_eq!(sweeping_action, reflex_action);
+            sweeping_env.execute_action(&sweeping_action);
+            reflex_env.execute_action(&reflex_action);
+        }
+    }
+
+    #[test]
+    fn test_sweeping_vacuum_agent_cleans_grids_from_any_start() {
+        for (height, width) in [(1, 5), (4, 1), (3, 4), (5, 5)] {
+            for x in 0..width {
+                for y in 0..height {
+                    let mut dirt_map = HashMap::new();
+                    for location in crate::evaluation::all_locations(height, width) {
+                        dirt_map.insert(location, SquareState::Dirty);
+                    }
+                    let mut env = VacuumWorldEnvironment::new_with_dirt_map(
+                        height,
+                        width,
+                        &dirt_map,
+                        VacuumWorldLocation { x, y },
+                    );
+                    let mut agent = SweepingVacuumAgent::new(height, width);
+                    // Two full sweeps, with a suck and a move per square, is always enough.
+                    for _ in 0..(4 * height * width) {
+                        let action = agent.act(&env.percept());
+                        env.execute_action(&action);
+                    }
+                    assert_eq!(
+                        env.score(),
+                        height * width,
+                        "height {} width {} start ({}, {})",
+                        height,
+                        width,
+                        x,
+                        y
+                    );
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_vacuum_world_environment_returns_dirty_percept() {
         let env = VacuumWorldEnvironment::default();
@@ -221,6 +918,94 @@ mod tests {
         assert_eq!(env.score(), 2);
     }
 
+    #[test]
+    fn test_vacuum_world_dirt_map_sets_square_states_and_agent_location() {
+        let mut dirt_map = HashMap::new();
+        dirt_map.insert(VacuumWorldLocation { x: 1, y: 0 }, SquareState::Dirty);
+        let env = VacuumWorldEnvironment::new_with_dirt_map(
+            1,
+            2,
+            &dirt_map,
+            VacuumWorldLocation { x: 1, y: 0 },
+        );
+        assert_eq!(
+            env.square_state(&VacuumWorldLocation { x: 0, y: 0 }),
+            Some(SquareState::Clean)
+        );
+        let percept = env.percept();
+        assert_eq!(percept.location, VacuumWorldLocation { x: 1, y: 0 });
+        assert_eq!(percept.square_state, SquareState::Dirty);
+        assert_eq!(env.score(), 1);
+    }
+
+    #[test]
+    fn test_vacuum_world_random_is_deterministic_for_a_seed() {
+        let squares = |env: &VacuumWorldEnvironment| {
+            (0..10)
+                .flat_map(|x| (0..10).map(move |y| VacuumWorldLocation { x, y }))
+                .map(|location| env.square_state(&location).unwrap())
+                .collect::<Vec<SquareState>>()
+        };
+        let env1 = VacuumWorldEnvironment::new_random(10, 10, 0.5, 42);
+        let env2 = VacuumWorldEnvironment::new_random(10, 10, 0.5, 42);
+        let env3 = VacuumWorldEnvironment::new_random(10, 10, 0.5, 43);
+        assert_eq!(squares(&env1), squares(&env2));
+        assert_ne!(squares(&env1), squares(&env3));
+        assert!(squares(&env1).contains(&SquareState::Dirty));
+        assert!(squares(&env1).contains(&SquareState::Clean));
+    }
+
+    #[test]
+    fn test_vacuum_world_random_dirt_probability_extremes() {
+        let all_clean = VacuumWorldEnvironment::new_random(3, 3, 0.0, 42);
+        assert_eq!(all_clean.score(), 9);
+        let all_dirty = VacuumWorldEnvironment::new_random(3, 3, 1.0, 42);
+        assert_eq!(all_dirty.score(), 0);
+    }
+
+    #[test]
+    fn test_obstacles_block_the_agent() {
+        let mut env =
+            VacuumWorldEnvironment::new(2, 3).with_obstacles(&[VacuumWorldLocation { x: 1, y: 0 }]);
+        env.execute_action(&VacuumWorldAction::Right);
+        assert_eq!(env.agent_location(), VacuumWorldLocation { x: 0, y: 0 });
+        assert_eq!(env.square_state(&VacuumWorldLocation { x: 1, y: 0 }), None);
+
+        env.execute_action(&VacuumWorldAction::Suck);
+        env.execute_action(&VacuumWorldAction::Up);
+        assert_eq!(env.score(), 1);
+        assert_eq!(env.to_string(), "[*] *  * \n .  #  * \n");
+    }
+
+    #[test]
+    fn test_boundary_behaviors() {
+        let run = |boundary_behavior| {
+            let mut env =
+                VacuumWorldEnvironment::default().with_boundary_behavior(boundary_behavior);
+            env.execute_action(&VacuumWorldAction::Left);
+            assert_eq!(env.agent_location(), VacuumWorldLocation { x: 0, y: 0 });
+            let result = (env.percept().bump, env.score());
+            env.execute_action(&VacuumWorldAction::Right);
+            assert!(!env.percept().bump);
+            assert_eq!(env.score(), 0);
+            result
+        };
+        assert_eq!(run(BoundaryBehavior::Clamp), (false, 0));
+        assert_eq!(run(BoundaryBehavior::Bump), (true, 0));
+        assert_eq!(run(BoundaryBehavior::Penalize(5)), (false, -5));
+    }
+
+    #[test]
+    fn test_vacuum_world_serde_round_trip() {
+        let mut env = VacuumWorldEnvironment::new_random(3, 4, 0.5, 1)
+            .with_obstacles(&[VacuumWorldLocation { x: 2, y: 2 }]);
+        env.execute_action(&VacuumWorldAction::Up);
+        let json = serde_json::to_string(&env).unwrap();
+        let round_tripped: VacuumWorldEnvironment = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_tripped, env);
+        assert_eq!(round_tripped.to_string(), env.to_string());
+    }
+
     #[test]
     fn test_vacuum_world_noop_does_nothing() {
         let mut env = VacuumWorldEnvironment::default();
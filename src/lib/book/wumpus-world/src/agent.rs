@@ -0,0 +1,222 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use vacuum_cleaner::Agent;
+
+use crate::{Direction, WumpusWorldAction, WumpusWorldLocation, WumpusWorldPercept};
+
+/// CautiousWumpusAgent only ever moves into squares it has proven safe, so it never dies.
+///
+/// The agent does not know the size of the cave. It keeps track of its own location by dead
+/// reckoning, and learns where the walls are from Bump percepts. A square is safe if it has been
+/// visited, or if it is next to a visited square with no Breeze (so it is not a pit) and next to a
+/// visited square with no Stench (so it is not the wumpus). This is a small part of the logical
+/// inference in section 7.3, enough to solve Figure 7.2.
+///
+/// The agent grabs the gold as soon as it perceives Glitter, then returns to the start and climbs
+/// out. If it runs out of safe unvisited squares without finding the gold it also returns to the
+/// start and climbs out. It never shoots.
+pub struct CautiousWumpusAgent {
+    location: WumpusWorldLocation,
+    direction: Direction,
+    has_gold: bool,
+    last_action: Option<WumpusWorldAction>,
+    visited: HashSet<WumpusWorldLocation>,
+    breezy: HashSet<WumpusWorldLocation>,
+    smelly: HashSet<WumpusWorldLocation>,
+    walls: HashSet<WumpusWorldLocation>,
+}
+
+impl Default for CautiousWumpusAgent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CautiousWumpusAgent {
+    pub fn new() -> Self {
+        Self {
+            location: WumpusWorldLocation::START,
+            direction: Direction::East,
+            has_gold: false,
+            last_action: None,
+            visited: HashSet::new(),
+            breezy: HashSet::new(),
+            smelly: HashSet::new(),
+            walls: HashSet::new(),
+        }
+    }
+
+    /// Where the agent believes it is.
+    pub fn location(&self) -> WumpusWorldLocation {
+        self.location
+    }
+
+    pub fn is_safe(&self, location: &WumpusWorldLocation) -> bool {
+        if location.x < 0 || location.y < 0 || self.walls.contains(location) {
+            return false;
+        }
+        if self.visited.contains(location) {
+            return true;
+        }
+        let visited_neighbours = || {
+            location
+                .neighbours()
+                .into_iter()
+                .filter(|neighbour| self.visited.contains(neighbour))
+        };
+        let no_pit = visited_neighbours().any(|neighbour| !self.breezy.contains(&neighbour));
+        let no_wumpus = visited_neighbours().any(|neighbour| !self.smelly.contains(&neighbour));
+        no_pit && no_wumpus
+    }
+
+    fn update(&mut self, percept: &WumpusWorldPercept) {
+        match self.last_action {
+            Some(WumpusWorldAction::Forward) => {
+                let next = self.location.step(self.direction);
+                if percept.bump {
+                    self.walls.insert(next);
+                } else {
+                    self.location = next;
+                }
+            }
+            Some(WumpusWorldAction::TurnLeft) => self.direction = self.direction.left(),
+            Some(WumpusWorldAction::TurnRight) => self.direction = self.direction.right(),
+            _ => {}
+        }
+        self.visited.insert(self.location);
+        if percept.breeze {
+            self.breezy.insert(self.location);
+        }
+        if percept.stench {
+            self.smelly.insert(self.location);
+        }
+    }
+
+    /// Breadth-first search through safe squares for the nearest square that satisfies is_goal.
+    /// Returns the first square to move to, or None if no goal is reachable or the agent is
+    /// already at a goal.
+    fn first_step_towards<F>(&self, is_goal: F) -> Option<WumpusWorldLocation>
+    where
+        F: Fn(&WumpusWorldLocation) -> bool,
+    {
+        let mut parents: HashMap<WumpusWorldLocation, WumpusWorldLocation> = HashMap::new();
+        let mut frontier = VecDeque::from([self.location]);
+        while let Some(current) = frontier.pop_front() {
+            if is_goal(&current) {
+                if current == self.location {
+                    return None;
+                }
+                let mut step = current;
+                while parents[&step] != self.location {
+                    step = parents[&step];
+                }
+                return Some(step);
+            }
+            for neighbour in current.neighbours() {
+                if neighbour != self.location
+                    && !parents.contains_key(&neighbour)
+                    && self.is_safe(&neighbour)
+                {
+                    parents.insert(neighbour, current);
+                    frontier.push_back(neighbour);
+                }
+            }
+        }
+        None
+    }
+
+    fn move_towards(&self, next: WumpusWorldLocation) -> WumpusWorldAction {
+        if self.location.step(self.direction) == next {
+            WumpusWorldAction::Forward
+        } else if self.location.step(self.direction.right()) == next {
+            WumpusWorldAction::TurnRight
+        } else {
+            WumpusWorldAction::TurnLeft
+        }
+    }
+
+    fn choose_action(&mut self, percept: &WumpusWorldPercept) -> WumpusWorldAction {
+        if percept.glitter && !self.has_gold {
+            self.has_gold = true;
+            return WumpusWorldAction::Grab;
+        }
+        if !self.has_gold {
+            if let Some(next) = self.first_step_towards(|location| !self.visited.contains(location))
+            {
+                return self.move_towards(next);
+            }
+        }
+        match self.first_step_towards(|location| *location == WumpusWorldLocation::START) {
+            Some(next) => self.move_towards(next),
+            None => WumpusWorldAction::Climb,
+        }
+    }
+}
+
+impl Agent for CautiousWumpusAgent {
+    type Action = WumpusWorldAction;
+    type Percept = WumpusWorldPercept;
+
+    fn act(&mut self, percept: &Self::Percept) -> Self::Action {
+        self.update(percept);
+        let action = self.choose_action(percept);
+        self.last_action = Some(action);
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vacuum_cleaner::Simulation;
+
+    use super::*;
+    use crate::{AgentStatus, GameOver, WumpusWorldEnvironment};
+
+    #[test]
+    fn test_cautious_agent_solves_figure_7_2() {
+        let mut simulation = Simulation::new(
+            WumpusWorldEnvironment::default(),
+            CautiousWumpusAgent::new(),
+            1000,
+        );
+        let outcome = simulation.run_until(&mut GameOver);
+        assert_eq!(simulation.environment().status(), AgentStatus::ClimbedOut);
+        assert!(simulation.environment().has_gold());
+        assert!(outcome.score > 900);
+    }
+
+    #[test]
+    fn test_cautious_agent_never_dies() {
+        for seed in 0..200 {
+            let mut simulation = Simulation::new(
+                WumpusWorldEnvironment::new_random(4, 0.2, seed),
+                CautiousWumpusAgent::new(),
+                1000,
+            );
+            let outcome = simulation.run_until(&mut GameOver);
+            assert_eq!(
+                simulation.environment().status(),
+                AgentStatus::ClimbedOut,
+                "seed {}",
+                seed
+            );
+            assert!(outcome.steps_taken < 1000);
+        }
+    }
+}
@@ -0,0 +1,540 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Wumpus world
+//
+// See:
+// -  Chapter 7: Logical Agents, section 7.2
+
+use std::collections::HashSet;
+
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use vacuum_cleaner::{Environment, StepEvent, Termination};
+
+pub mod agent;
+
+/// Reward for every action.
+pub const ACTION_REWARD: i32 = -1;
+
+/// Additional reward for using up the arrow.
+pub const ARROW_REWARD: i32 = -10;
+
+/// Additional reward for falling into a pit or being eaten by the wumpus.
+pub const DEATH_REWARD: i32 = -1000;
+
+/// Additional reward for climbing out of the cave with the gold.
+pub const GOLD_REWARD: i32 = 1000;
+
+/// Locations are zero-indexed, so the book's [1,1] is (0, 0). x increases to the east and y
+/// increases to the north.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct WumpusWorldLocation {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl WumpusWorldLocation {
+    /// The square the agent starts in, and the only square it can climb out of.
+    pub const START: WumpusWorldLocation = WumpusWorldLocation { x: 0, y: 0 };
+
+    pub fn step(&self, direction: Direction) -> Self {
+        match direction {
+            Direction::North => Self {
+                x: self.x,
+                y: self.y + 1,
+            },
+            Direction::East => Self {
+                x: self.x + 1,
+                y: self.y,
+            },
+            Direction::South => Self {
+                x: self.x,
+                y: self.y - 1,
+            },
+            Direction::West => Self {
+                x: self.x - 1,
+                y: self.y,
+            },
+        }
+    }
+
+    /// Directly, not diagonally, adjacent locations. Some may be outside the cave.
+    pub fn neighbours(&self) -> [Self; 4] {
+        Direction::ALL.map(|direction| self.step(direction))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    pub fn left(self) -> Self {
+        match self {
+            Direction::North => Direction::West,
+            Direction::East => Direction::North,
+            Direction::South => Direction::East,
+            Direction::West => Direction::South,
+        }
+    }
+
+    pub fn right(self) -> Self {
+        match self {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WumpusWorldAction {
+    Forward,
+    TurnLeft,
+    TurnRight,
+    Grab,
+    Shoot,
+    Climb,
+}
+
+/// WumpusWorldPercept is what the agent senses in its current square. Bump and Scream are only
+/// perceived in the time step right after the action that caused them.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct WumpusWorldPercept {
+    /// The wumpus is in this square or a directly adjacent one.
+    pub stench: bool,
+
+    /// A pit is in a directly adjacent square.
+    pub breeze: bool,
+
+    /// The gold is in this square.
+    pub glitter: bool,
+
+    /// The agent walked into a wall.
+    pub bump: bool,
+
+    /// The wumpus was killed by the arrow.
+    pub scream: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AgentStatus {
+    Alive,
+    Dead,
+    ClimbedOut,
+}
+
+/// WumpusWorldEnvironment is a square cave of rooms. The agent starts in (0, 0) facing east. The
+/// episode ends when the agent dies or climbs out, after which actions have no effect and score
+/// nothing.
+///
+/// The performance measure follows the book: +1000 for climbing out with the gold, -1000 for
+/// dying, -1 for each action, and -10 for using up the arrow. score() returns the reward for the
+/// most recent action, so the Simulation's total score is the book's performance measure.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WumpusWorldEnvironment {
+    size: i32,
+    pits: HashSet<WumpusWorldLocation>,
+    wumpus_location: WumpusWorldLocation,
+    wumpus_alive: bool,
+    gold_location: Option<WumpusWorldLocation>,
+    agent_location: WumpusWorldLocation,
+    agent_direction: Direction,
+    has_arrow: bool,
+    has_gold: bool,
+    status: AgentStatus,
+    bump: bool,
+    scream: bool,
+    last_reward: i32,
+}
+
+impl Default for WumpusWorldEnvironment {
+    /// The cave from Figure 7.2.
+    fn default() -> Self {
+        let location = |x, y| WumpusWorldLocation { x, y };
+        WumpusWorldEnvironment::new(
+            4,
+            location(0, 2),
+            location(1, 2),
+            &[location(2, 0), location(2, 2), location(3, 3)],
+        )
+    }
+}
+
+impl WumpusWorldEnvironment {
+    /// Create a size x size cave with the given wumpus, gold, and pits. The start square must be
+    /// free of the wumpus and pits.
+    pub fn new(
+        size: i32,
+        wumpus_location: WumpusWorldLocation,
+        gold_location: WumpusWorldLocation,
+        pits: &[WumpusWorldLocation],
+    ) -> Self {
+        let in_bounds = |location: &WumpusWorldLocation| {
+            (0..size).contains(&location.x) && (0..size).contains(&location.y)
+        };
+        assert!(in_bounds(&wumpus_location) && wumpus_location != WumpusWorldLocation::START);
+        assert!(in_bounds(&gold_location));
+        assert!(pits
+            .iter()
+            .all(|pit| in_bounds(pit) && *pit != WumpusWorldLocation::START));
+        Self {
+            size,
+            pits: pits.iter().copied().collect(),
+            wumpus_location,
+            wumpus_alive: true,
+            gold_location: Some(gold_location),
+            agent_location: WumpusWorldLocation::START,
+            agent_direction: Direction::East,
+            has_arrow: true,
+            has_gold: false,
+            status: AgentStatus::Alive,
+            bump: false,
+            scream: false,
+            last_reward: 0,
+        }
+    }
+
+    /// Create a random cave as described in the book: every square other than the start is a pit
+    /// with probability pit_probability, and the wumpus and gold are placed uniformly at random in
+    /// squares other than the start. The same seed always gives the same cave.
+    pub fn new_random(size: i32, pit_probability: f64, seed: u64) -> Self {
+        let mut rng = rand_pcg::Pcg64::seed_from_u64(seed);
+        let mut random_non_start_location = || loop {
+            let location = WumpusWorldLocation {
+                x: rng.gen_range(0..size),
+                y: rng.gen_range(0..size),
+            };
+            if location != WumpusWorldLocation::START {
+                return location;
+            }
+        };
+        let wumpus_location = random_non_start_location();
+        let gold_location = random_non_start_location();
+
+        let mut pits = vec![];
+        for x in 0..size {
+            for y in 0..size {
+                let location = WumpusWorldLocation { x, y };
+                if location != WumpusWorldLocation::START && rng.gen_bool(pit_probability) {
+                    pits.push(location);
+                }
+            }
+        }
+        Self::new(size, wumpus_location, gold_location, &pits)
+    }
+
+    pub fn size(&self) -> i32 {
+        self.size
+    }
+
+    pub fn agent_location(&self) -> WumpusWorldLocation {
+        self.agent_location
+    }
+
+    pub fn agent_direction(&self) -> Direction {
+        self.agent_direction
+    }
+
+    pub fn status(&self) -> AgentStatus {
+        self.status
+    }
+
+    /// True once the agent has died or climbed out.
+    pub fn is_terminal(&self) -> bool {
+        self.status != AgentStatus::Alive
+    }
+
+    pub fn has_arrow(&self) -> bool {
+        self.has_arrow
+    }
+
+    pub fn has_gold(&self) -> bool {
+        self.has_gold
+    }
+
+    pub fn wumpus_location(&self) -> WumpusWorldLocation {
+        self.wumpus_location
+    }
+
+    pub fn wumpus_alive(&self) -> bool {
+        self.wumpus_alive
+    }
+
+    /// Where the gold is, or None if the agent has grabbed it.
+    pub fn gold_location(&self) -> Option<WumpusWorldLocation> {
+        self.gold_location
+    }
+
+    pub fn is_pit(&self, location: &WumpusWorldLocation) -> bool {
+        self.pits.contains(location)
+    }
+
+    fn in_bounds(&self, location: &WumpusWorldLocation) -> bool {
+        (0..self.size).contains(&location.x) && (0..self.size).contains(&location.y)
+    }
+
+    fn forward(&mut self) {
+        let next = self.agent_location.step(self.agent_direction);
+        if !self.in_bounds(&next) {
+            self.bump = true;
+            return;
+        }
+        self.agent_location = next;
+        if self.is_pit(&next) || (self.wumpus_alive && self.wumpus_location == next) {
+            self.status = AgentStatus::Dead;
+            self.last_reward += DEATH_REWARD;
+        }
+    }
+
+    fn shoot(&mut self) {
+        if !self.has_arrow {
+            return;
+        }
+        self.has_arrow = false;
+        self.last_reward += ARROW_REWARD;
+
+        // The arrow flies in a straight line until it hits the wumpus or a wall.
+        let mut arrow = self.agent_location.step(self.agent_direction);
+        while self.in_bounds(&arrow) {
+            if self.wumpus_alive && arrow == self.wumpus_location {
+                self.wumpus_alive = false;
+                self.scream = true;
+                return;
+            }
+            arrow = arrow.step(self.agent_direction);
+        }
+    }
+}
+
+impl Environment for WumpusWorldEnvironment {
+    type Action = WumpusWorldAction;
+    type Percept = WumpusWorldPercept;
+    type Score = i32;
+
+    fn percept(&self) -> Self::Percept {
+        let location = self.agent_location;
+        let neighbours = location.neighbours();
+        WumpusWorldPercept {
+            stench: self.wumpus_location == location || neighbours.contains(&self.wumpus_location),
+            breeze: neighbours.iter().any(|neighbour| self.is_pit(neighbour)),
+            glitter: self.gold_location == Some(location),
+            bump: self.bump,
+            scream: self.scream,
+        }
+    }
+
+    fn execute_action(&mut self, action: &Self::Action) {
+        self.bump = false;
+        self.scream = false;
+        if self.is_terminal() {
+            self.last_reward = 0;
+            return;
+        }
+
+        self.last_reward = ACTION_REWARD;
+        match action {
+            WumpusWorldAction::Forward => self.forward(),
+            WumpusWorldAction::TurnLeft => self.agent_direction = self.agent_direction.left(),
+            WumpusWorldAction::TurnRight => self.agent_direction = self.agent_direction.right(),
+            WumpusWorldAction::Grab => {
+                if self.gold_location == Some(self.agent_location) {
+                    self.gold_location = None;
+                    self.has_gold = true;
+                }
+            }
+            WumpusWorldAction::Shoot => self.shoot(),
+            WumpusWorldAction::Climb => {
+                if self.agent_location == WumpusWorldLocation::START {
+                    self.status = AgentStatus::ClimbedOut;
+                    if self.has_gold {
+                        self.last_reward += GOLD_REWARD;
+                    }
+                }
+            }
+        }
+    }
+
+    fn score(&self) -> Self::Score {
+        self.last_reward
+    }
+}
+
+/// Terminates a Simulation once the agent has died or climbed out of the cave.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GameOver;
+
+impl Termination<WumpusWorldEnvironment> for GameOver {
+    fn should_terminate(&mut self, event: &StepEvent<'_, WumpusWorldEnvironment>) -> bool {
+        event.environment.is_terminal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn execute_all(env: &mut WumpusWorldEnvironment, actions: &[WumpusWorldAction]) -> i32 {
+        actions
+            .iter()
+            .map(|action| {
+                env.execute_action(action);
+                env.score()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_percepts_in_figure_7_2() {
+        let mut env = WumpusWorldEnvironment::default();
+        assert_eq!(env.percept(), WumpusWorldPercept::default());
+
+        env.execute_action(&WumpusWorldAction::Forward);
+        let percept = env.percept();
+        assert!(percept.breeze && !percept.stench);
+
+        execute_all(
+            &mut env,
+            &[
+                WumpusWorldAction::TurnLeft,
+                WumpusWorldAction::TurnLeft,
+                WumpusWorldAction::Forward,
+                WumpusWorldAction::TurnRight,
+                WumpusWorldAction::Forward,
+            ],
+        );
+        assert_eq!(env.agent_location(), WumpusWorldLocation { x: 0, y: 1 });
+        let percept = env.percept();
+        assert!(percept.stench && !percept.breeze);
+
+        execute_all(
+            &mut env,
+            &[
+                WumpusWorldAction::TurnRight,
+                WumpusWorldAction::Forward,
+                WumpusWorldAction::TurnLeft,
+                WumpusWorldAction::Forward,
+            ],
+        );
+        assert_eq!(env.agent_location(), WumpusWorldLocation { x: 1, y: 2 });
+        let percept = env.percept();
+        assert!(percept.stench && percept.breeze && percept.glitter);
+    }
+
+    #[test]
+    fn test_bump_is_perceived_once() {
+        let mut env = WumpusWorldEnvironment::default();
+        env.execute_action(&WumpusWorldAction::TurnRight);
+        env.execute_action(&WumpusWorldAction::Forward);
+        assert!(env.percept().bump);
+        assert_eq!(env.agent_location(), WumpusWorldLocation::START);
+        env.execute_action(&WumpusWorldAction::TurnLeft);
+        assert!(!env.percept().bump);
+    }
+
+    #[test]
+    fn test_falling_into_pit_ends_episode() {
+        let mut env = WumpusWorldEnvironment::default();
+        let score = execute_all(
+            &mut env,
+            &[WumpusWorldAction::Forward, WumpusWorldAction::Forward],
+        );
+        assert_eq!(score, 2 * ACTION_REWARD + DEATH_REWARD);
+        assert_eq!(env.status(), AgentStatus::Dead);
+
+        env.execute_action(&WumpusWorldAction::TurnLeft);
+        assert_eq!(env.score(), 0);
+        assert_eq!(env.agent_direction(), Direction::East);
+    }
+
+    #[test]
+    fn test_shooting_the_wumpus() {
+        let mut env = WumpusWorldEnvironment::default();
+        let score = execute_all(
+            &mut env,
+            &[WumpusWorldAction::TurnLeft, WumpusWorldAction::Shoot],
+        );
+        assert_eq!(score, 2 * ACTION_REWARD + ARROW_REWARD);
+        assert!(env.percept().scream);
+        assert!(!env.wumpus_alive());
+        assert!(!env.has_arrow());
+
+        // The dead wumpus still smells, but is harmless.
+        let score = execute_all(
+            &mut env,
+            &[WumpusWorldAction::Forward, WumpusWorldAction::Forward],
+        );
+        assert_eq!(score, 2 * ACTION_REWARD);
+        assert!(env.percept().stench);
+        assert_eq!(env.status(), AgentStatus::Alive);
+
+        // No arrow left.
+        assert_eq!(
+            execute_all(&mut env, &[WumpusWorldAction::Shoot]),
+            ACTION_REWARD
+        );
+    }
+
+    #[test]
+    fn test_climbing_out_with_gold() {
+        let mut env = WumpusWorldEnvironment::new(
+            4,
+            WumpusWorldLocation { x: 3, y: 3 },
+            WumpusWorldLocation { x: 1, y: 0 },
+            &[],
+        );
+        let score = execute_all(
+            &mut env,
+            &[
+                WumpusWorldAction::Forward,
+                WumpusWorldAction::Grab,
+                WumpusWorldAction::TurnLeft,
+                WumpusWorldAction::TurnLeft,
+                WumpusWorldAction::Forward,
+                WumpusWorldAction::Climb,
+            ],
+        );
+        assert_eq!(score, 6 * ACTION_REWARD + GOLD_REWARD);
+        assert_eq!(env.status(), AgentStatus::ClimbedOut);
+        assert!(env.has_gold());
+    }
+
+    #[test]
+    fn test_wumpus_world_random_is_deterministic_for_a_seed() {
+        for seed in 0..20 {
+            let a = WumpusWorldEnvironment::new_random(4, 0.2, seed);
+            let b = WumpusWorldEnvironment::new_random(4, 0.2, seed);
+            assert_eq!(a.wumpus_location(), b.wumpus_location());
+            assert_eq!(a.gold_location(), b.gold_location());
+            assert_eq!(a.pits, b.pits);
+            assert!(!a.is_pit(&WumpusWorldLocation::START));
+            assert_ne!(a.wumpus_location(), WumpusWorldLocation::START);
+        }
+    }
+}
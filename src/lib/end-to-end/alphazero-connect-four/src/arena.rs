@@ -0,0 +1,154 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Playing two evaluators against each other to decide whether a newly trained network is
+// better than the one it would replace.
+
+use rand_pcg::Pcg64;
+
+use crate::puct::{search, SearchConfig};
+use crate::self_play::choose_move;
+use crate::{Action, Evaluator, Position, State};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArenaConfig {
+    pub games: usize,
+
+    /// Searched without root noise.
+    pub search: SearchConfig,
+
+    /// Sample this many opening moves in proportion to visit counts, as in self-play, so that
+    /// the games aren't all identical.
+    pub temperature_moves: usize,
+    pub max_moves: usize,
+}
+
+impl Default for ArenaConfig {
+    fn default() -> Self {
+        Self {
+            games: 20,
+            search: SearchConfig::default().without_noise(),
+            temperature_moves: 2,
+            max_moves: 100,
+        }
+    }
+}
+
+/// Results from the challenger's point of view.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ArenaResult {
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+}
+
+impl ArenaResult {
+    pub fn games(&self) -> usize {
+        self.wins + self.losses + self.draws
+    }
+
+    /// Wins plus half the draws, as a fraction of the games played.
+    pub fn score(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.0;
+        }
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.games() as f64
+    }
+}
+
+/// Play `config.games` games between the two evaluators, alternating which one moves first.
+pub fn arena<Challenger, Champion>(
+    challenger: &Challenger,
+    champion: &Champion,
+    width: usize,
+    height: usize,
+    config: &ArenaConfig,
+    rng: &mut Pcg64,
+) -> ArenaResult
+where
+    Challenger: Evaluator<State, Action>,
+    Champion: Evaluator<State, Action>,
+{
+    let search_config = config.search.without_noise();
+    let mut result = ArenaResult::default();
+    for game in 0..config.games {
+        let challenger_moves_first = game % 2 == 0;
+        let mut position = Position::new(width, height);
+        let mut moves = 0;
+        while position.outcome().is_none() && moves < config.max_moves {
+            let challenger_to_move = (moves % 2 == 0) == challenger_moves_first;
+            let searched = if challenger_to_move {
+                search(&position, challenger, &search_config, rng)
+            } else {
+                search(&position, champion, &search_config, rng)
+            };
+            let mv = choose_move(&searched, moves < config.temperature_moves, rng);
+            position = position.play(mv);
+            moves += 1;
+        }
+        // The outcome is for the side to move, which is whoever didn't make the last move.
+        let challenger_to_move = (moves % 2 == 0) == challenger_moves_first;
+        let outcome = position.outcome().unwrap_or(0.0);
+        if outcome == 0.0 {
+            result.draws += 1;
+        } else if (outcome > 0.0) == challenger_to_move {
+            result.wins += 1;
+        } else {
+            result.losses += 1;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UniformEvaluator;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_plays_every_game() {
+        let config = ArenaConfig {
+            games: 4,
+            search: SearchConfig {
+                simulations: 20,
+                ..SearchConfig::default()
+            },
+            ..ArenaConfig::default()
+        };
+        let mut rng = Pcg64::seed_from_u64(2);
+        let result = arena(
+            &UniformEvaluator,
+            &UniformEvaluator,
+            4,
+            4,
+            &config,
+            &mut rng,
+        );
+        assert_eq!(result.games(), 4);
+    }
+
+    #[test]
+    fn test_score() {
+        let result = ArenaResult {
+            wins: 3,
+            losses: 1,
+            draws: 2,
+        };
+        assert!((result.score() - 4.0 / 6.0).abs() < 1e-12);
+        assert_eq!(ArenaResult::default().score(), 0.0);
+    }
+}
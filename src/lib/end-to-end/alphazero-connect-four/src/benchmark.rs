@@ -0,0 +1,104 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Benchmarking NN-guided PUCT against vanilla UCT with random playouts.
+
+use std::sync::Arc;
+
+use mcts_connect_four::puct::{Position, SearchConfig};
+use mcts_connect_four::{
+    get_best_mcts_move, get_best_puct_move, Action, MctsConfig, Player, State,
+};
+use monte_carlo_tree_search::{shared_rng, Evaluator};
+
+use crate::arena::ArenaResult;
+
+/// A UCT configuration for benchmarks: the default search budget, without writing or keeping
+/// any debug trees.
+pub fn uct_config(iterations: i32) -> MctsConfig {
+    MctsConfig {
+        iterations,
+        tree_dump_dir: None,
+        debug_track_trees: monte_carlo_tree_search::DebugTrackTrees::None,
+        ..MctsConfig::default()
+    }
+}
+
+/// Play `games` games of PUCT guided by `evaluator` against UCT, alternating who moves first.
+/// Results are from PUCT's point of view. Games that reach `max_moves` are draws.
+#[allow(clippy::too_many_arguments)]
+pub fn compare_with_uct<Evaluator_: Evaluator<State, Action>>(
+    evaluator: &Evaluator_,
+    puct_config: &SearchConfig,
+    uct_config: &MctsConfig,
+    width: usize,
+    height: usize,
+    games: usize,
+    max_moves: usize,
+    seed: u64,
+) -> ArenaResult {
+    let rng = shared_rng(seed);
+    let mut result = ArenaResult::default();
+    for game in 0..games {
+        let puct_player = if game % 2 == 0 {
+            Player::Player1
+        } else {
+            Player::Player2
+        };
+        let mut state = State::new(width, height, Player::Player1, Player::Player1);
+        let mut moves = 0;
+        while Position::from(&state).outcome().is_none() && moves < max_moves {
+            // UCT scores its playouts for who_am_i, so that has to be the side to move.
+            state.who_am_i = state.turn;
+            let best = if state.turn == puct_player {
+                get_best_puct_move(&state, evaluator, puct_config, Arc::clone(&rng))
+            } else {
+                get_best_mcts_move(&state, uct_config, Arc::clone(&rng))
+            };
+            state.apply_move(&mcts_connect_four::Action(best.actual_move));
+            moves += 1;
+        }
+        let outcome = Position::from(&state).outcome().unwrap_or(0.0);
+        if outcome == 0.0 {
+            result.draws += 1;
+        } else if (outcome > 0.0) == (state.turn == puct_player) {
+            result.wins += 1;
+        } else {
+            result.losses += 1;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UniformEvaluator;
+
+    #[test]
+    fn test_compare_with_uct_plays_every_game() {
+        let puct = SearchConfig {
+            simulations: 20,
+            ..SearchConfig::default()
+        };
+        let uct = MctsConfig {
+            playouts_per_simulation: 5,
+            ..uct_config(20)
+        };
+        let result = compare_with_uct(&UniformEvaluator, &puct, &uct, 4, 4, 2, 40, 1);
+        assert_eq!(result.games(), 2);
+    }
+}
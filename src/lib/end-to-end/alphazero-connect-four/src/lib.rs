@@ -0,0 +1,212 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+//! A small AlphaZero-style training loop for Connect Four: self-play games searched with PUCT
+//! Monte Carlo tree search, a policy network and a value network trained on the results, and
+//! an arena that only promotes a new network if it beats the old one.
+
+pub mod arena;
+pub mod benchmark;
+#[cfg(feature = "onnx")]
+pub mod onnx;
+pub mod pipeline;
+pub mod self_play;
+
+use std::path::Path;
+
+pub use mcts_connect_four::puct;
+use mcts_connect_four::puct::side_to_move_evaluation;
+pub use mcts_connect_four::puct::{Position, UniformEvaluator};
+pub use mcts_connect_four::{Action, State};
+pub use monte_carlo_tree_search::Evaluator;
+use ndarray::{Array1, Array2};
+use nn::connect_four::{encode, encode_batch, input_size, move_index, policy_size, ValueNetwork};
+use nn::mlp::{softmax, Activation, Network};
+use nn::serialization::SerializationError;
+use nn::train::{train, Adam, Loss, MlpModel, Model, TrainingConfig};
+
+/// One training example from self-play: the position, the search's visit distribution over
+/// moves, and the final result of the game for the side to move.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Example {
+    pub position: Position,
+    pub policy: Vec<f64>,
+    pub outcome: f64,
+}
+
+/// Losses after training a [`PolicyValueNetwork`] on a set of examples.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrainingLosses {
+    pub policy: f64,
+    pub value: f64,
+}
+
+/// A policy network, with one logit per move, and a value network, both pure-Rust
+/// multilayer perceptrons over the [`nn::connect_four`] encoding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyValueNetwork {
+    pub policy: Network,
+    pub value: ValueNetwork,
+}
+
+impl PolicyValueNetwork {
+    /// The policy network has two ReLU hidden layers of 64 units, and the value network is
+    /// [`ValueNetwork::new`].
+    pub fn new(width: usize, height: usize, seed: u64) -> Self {
+        Self {
+            policy: Network::new(
+                &[input_size(width, height), 64, 64, policy_size(width)],
+                Activation::Relu,
+                Activation::Identity,
+                seed,
+            ),
+            value: ValueNetwork::new(width, height, seed.wrapping_add(1)),
+        }
+    }
+
+    /// Train both networks on the examples with Adam. The policy network minimizes the cross
+    /// entropy against the search's visit distribution and the value network the squared error
+    /// against the game outcome.
+    pub fn train(
+        &mut self,
+        examples: &[Example],
+        config: &TrainingConfig,
+        learning_rate: f64,
+    ) -> TrainingLosses {
+        let inputs = encode_batch(
+            examples
+                .iter()
+                .map(|example| (&example.position.board, example.position.to_move)),
+        );
+        let outputs = self.policy.outputs();
+        let mut policy_targets = Array2::zeros((examples.len(), outputs));
+        for (mut row, example) in policy_targets.rows_mut().into_iter().zip(examples) {
+            row.assign(&Array1::from(example.policy.clone()));
+        }
+        let value_targets =
+            Array2::from_shape_fn((examples.len(), 1), |(i, _)| examples[i].outcome);
+
+        let mut policy = MlpModel::new(
+            self.policy.clone(),
+            Loss::SoftmaxCrossEntropy,
+            Adam::new(learning_rate),
+        );
+        train(
+            &mut policy,
+            (&inputs, &policy_targets),
+            None,
+            config,
+            |_| {},
+        );
+        let mut value = MlpModel::new(
+            self.value.network.clone(),
+            Loss::MeanSquaredError,
+            Adam::new(learning_rate),
+        );
+        train(&mut value, (&inputs, &value_targets), None, config, |_| {});
+
+        let losses = TrainingLosses {
+            policy: policy.evaluate(&inputs, &policy_targets),
+            value: value.evaluate(&inputs, &value_targets),
+        };
+        self.policy = policy.network;
+        self.value.network = value.network;
+        losses
+    }
+
+    /// Save to `policy.json` and `value.json` inside `directory`, which must exist.
+    pub fn save(&self, directory: impl AsRef<Path>) -> Result<(), SerializationError> {
+        let directory = directory.as_ref();
+        self.policy.save_file(directory.join("policy.json"))?;
+        self.value.network.save_file(directory.join("value.json"))
+    }
+
+    /// Export both networks to one ONNX model, for [`onnx::OnnxEvaluator`] or other runtimes.
+    #[cfg(feature = "onnx")]
+    pub fn save_onnx(&self, path: impl AsRef<Path>) -> Result<(), nn::onnx::OnnxError> {
+        nn::onnx::save_networks(&[&self.policy, &self.value.network], path)
+    }
+
+    pub fn load(
+        directory: impl AsRef<Path>,
+        width: usize,
+        height: usize,
+    ) -> Result<Self, SerializationError> {
+        let directory = directory.as_ref();
+        Ok(Self {
+            policy: Network::load_file(directory.join("policy.json"))?,
+            value: ValueNetwork {
+                network: Network::load_file(directory.join("value.json"))?,
+                width,
+                height,
+            },
+        })
+    }
+}
+
+/// The policy network's softmax gives the priors, indexed with
+/// [`nn::connect_four::move_index`], and the value network the value for the side to move.
+impl Evaluator<State, Action> for PolicyValueNetwork {
+    fn evaluate(&self, state: &State) -> (Vec<(Action, f64)>, f64) {
+        let input = encode(&state.board, state.turn.into());
+        let priors = softmax(self.policy.forward(input.view()).view());
+        let value = self.value.network.forward(input.view())[0];
+        let width = state.board.width;
+        side_to_move_evaluation(state, |mv| priors[move_index(mv, width)], value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_training_fits_examples() {
+        let position = Position::new(4, 4);
+        let mut policy = vec![0.0; policy_size(4)];
+        policy[2] = 1.0;
+        let examples = vec![Example {
+            position: position.clone(),
+            policy,
+            outcome: 0.5,
+        }];
+        let mut network = PolicyValueNetwork::new(4, 4, 3);
+        let config = TrainingConfig {
+            epochs: 100,
+            batch_size: 1,
+            ..TrainingConfig::default()
+        };
+        let losses = network.train(&examples, &config, 0.01);
+        assert!(losses.policy < 0.05, "{losses:?}");
+        assert!(losses.value < 0.01, "{losses:?}");
+        let actions: Vec<Action> = position.legal_moves().into_iter().map(Action).collect();
+        let (priors, reward) = network.evaluate_actions(&State::from(&position), &actions);
+        assert!(priors[2] > 0.9);
+        // A value of 0.5 for the side to move is a reward of 0.75.
+        assert!((reward - 0.75).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let directory = std::env::temp_dir().join(format!("alphazero-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let network = PolicyValueNetwork::new(4, 4, 3);
+        network.save(&directory).unwrap();
+        let loaded = PolicyValueNetwork::load(&directory, 4, 4);
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(loaded.unwrap(), network);
+    }
+}
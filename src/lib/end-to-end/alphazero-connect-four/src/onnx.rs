@@ -0,0 +1,127 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+//! An [`Evaluator`] backed by an ONNX model, so that a policy-value network trained elsewhere
+//! can guide the PUCT search.
+
+use std::path::Path;
+
+use mcts_connect_four::puct::side_to_move_evaluation;
+use nn::connect_four::{encode, input_size, move_index, policy_size};
+use nn::mlp::softmax;
+use nn::onnx::{OnnxError, OnnxModel};
+
+use crate::{Action, Evaluator, Position, State};
+
+/// An ONNX model over the [`nn::connect_four`] encoding with two outputs: policy logits, one
+/// per move in [`move_index`] order, and a value in [-1, 1] for the side to move. This is the
+/// layout of [`crate::PolicyValueNetwork`], which can be exported with
+/// [`PolicyValueNetwork::save_onnx`](crate::PolicyValueNetwork::save_onnx).
+#[derive(Debug, Clone)]
+pub struct OnnxEvaluator {
+    model: OnnxModel,
+    width: usize,
+}
+
+impl OnnxEvaluator {
+    /// Checks the model's outputs on the empty board.
+    pub fn new(model: OnnxModel, width: usize, height: usize) -> Result<Self, OnnxError> {
+        let evaluator = OnnxEvaluator { model, width };
+        evaluator.outputs(&Position::new(width, height))?;
+        Ok(evaluator)
+    }
+
+    pub fn load_file(
+        path: impl AsRef<Path>,
+        width: usize,
+        height: usize,
+    ) -> Result<Self, OnnxError> {
+        let model = OnnxModel::load_file(path, input_size(width, height))?;
+        Self::new(model, width, height)
+    }
+
+    /// The policy and the value for the side to move.
+    fn outputs(&self, position: &Position) -> Result<(Vec<f64>, f64), OnnxError> {
+        let input = encode(&position.board, position.to_move);
+        let outputs = self.model.run(input.view())?;
+        match outputs.as_slice() {
+            [policy, value, ..] if policy.len() == policy_size(self.width) && value.len() == 1 => {
+                Ok((softmax(policy.view()).to_vec(), value[0]))
+            }
+            _ => Err(OnnxError::UnexpectedOutputs(format!(
+                "expected policy logits of size {} and a value, got sizes {:?}",
+                policy_size(self.width),
+                outputs
+                    .iter()
+                    .map(|output| output.len())
+                    .collect::<Vec<_>>()
+            ))),
+        }
+    }
+}
+
+impl Evaluator<State, Action> for OnnxEvaluator {
+    /// Panics if the model fails, which [`OnnxEvaluator::new`] has already ruled out for
+    /// well-formed positions.
+    fn evaluate(&self, state: &State) -> (Vec<(Action, f64)>, f64) {
+        let (priors, value) = self
+            .outputs(&Position::from(state))
+            .expect("ONNX model evaluation failed");
+        side_to_move_evaluation(state, |mv| priors[move_index(mv, self.width)], value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PolicyValueNetwork;
+    use nn::onnx::networks_to_onnx;
+
+    #[test]
+    fn test_matches_policy_value_network() {
+        let network = PolicyValueNetwork::new(5, 4, 9);
+        let path = std::env::temp_dir().join(format!("aima-az-{}.onnx", std::process::id()));
+        network.save_onnx(&path).unwrap();
+        let evaluator = OnnxEvaluator::load_file(&path, 5, 4).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut position = Position::new(5, 4);
+        for _ in 0..3 {
+            let moves = position.legal_moves();
+            let actions: Vec<Action> = moves.iter().copied().map(Action).collect();
+            let state = State::from(&position);
+            let (expected_priors, expected_value) = network.evaluate_actions(&state, &actions);
+            let (priors, value) = evaluator.evaluate_actions(&state, &actions);
+            assert!((value - expected_value).abs() < 1e-5);
+            for (p, q) in priors.iter().zip(&expected_priors) {
+                assert!((p - q).abs() < 1e-5);
+            }
+            position = position.play(moves[1]);
+        }
+    }
+
+    #[test]
+    fn test_rejects_wrong_outputs() {
+        // Only the policy network, so there is no value output.
+        let network = PolicyValueNetwork::new(5, 4, 9);
+        let proto = networks_to_onnx(&[&network.policy]);
+        let model = OnnxModel::from_proto(&proto, input_size(5, 4)).unwrap();
+        assert!(matches!(
+            OnnxEvaluator::new(model, 5, 4),
+            Err(OnnxError::UnexpectedOutputs(_))
+        ));
+    }
+}
@@ -0,0 +1,183 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// The full training loop: self-play, train, and promote the new network only if it wins.
+
+use nn::train::TrainingConfig;
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+
+use crate::arena::{arena, ArenaConfig, ArenaResult};
+use crate::self_play::{self_play_game, ReplayBuffer, SelfPlayConfig};
+use crate::{Action, Evaluator, PolicyValueNetwork, State, TrainingLosses, UniformEvaluator};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PipelineConfig {
+    pub width: usize,
+    pub height: usize,
+    pub iterations: usize,
+    pub games_per_iteration: usize,
+    pub self_play: SelfPlayConfig,
+    pub training: TrainingConfig,
+    pub learning_rate: f64,
+    pub replay_buffer_size: usize,
+    pub arena: ArenaConfig,
+
+    /// A trained network replaces the current best when its arena score is at least this.
+    pub acceptance_score: f64,
+    pub seed: u64,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            width: 7,
+            height: 6,
+            iterations: 10,
+            games_per_iteration: 20,
+            self_play: SelfPlayConfig::default(),
+            training: TrainingConfig {
+                epochs: 10,
+                batch_size: 64,
+                ..TrainingConfig::default()
+            },
+            learning_rate: 0.001,
+            replay_buffer_size: 20_000,
+            arena: ArenaConfig::default(),
+            acceptance_score: 0.55,
+            seed: 42,
+        }
+    }
+}
+
+/// What happened in one iteration of the pipeline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IterationReport {
+    /// Counting from 1.
+    pub iteration: usize,
+    pub new_examples: usize,
+    pub buffer_size: usize,
+    pub losses: TrainingLosses,
+    pub arena: ArenaResult,
+    pub accepted: bool,
+}
+
+/// Run the pipeline, calling `on_iteration` after each iteration, and return the best network.
+///
+/// Each iteration plays `games_per_iteration` self-play games with the current best network,
+/// adds their examples to the replay buffer, trains a copy of the best network on the whole
+/// buffer, and plays the copy against the best network in the arena. The first iteration's
+/// games use the uniform evaluator, since an untrained network knows nothing yet.
+pub fn run_pipeline(
+    config: &PipelineConfig,
+    mut on_iteration: impl FnMut(&IterationReport),
+) -> PolicyValueNetwork {
+    let mut rng = Pcg64::seed_from_u64(config.seed);
+    let mut best = PolicyValueNetwork::new(config.width, config.height, config.seed);
+    let mut buffer = ReplayBuffer::new(config.replay_buffer_size);
+    for iteration in 1..=config.iterations {
+        let mut new_examples = 0;
+        for _ in 0..config.games_per_iteration {
+            let examples = if iteration == 1 {
+                play(&UniformEvaluator, config, &mut rng)
+            } else {
+                play(&best, config, &mut rng)
+            };
+            new_examples += examples.len();
+            buffer.extend(examples);
+        }
+
+        let mut candidate = best.clone();
+        let losses = candidate.train(&buffer.examples(), &config.training, config.learning_rate);
+        let result = arena(
+            &candidate,
+            &best,
+            config.width,
+            config.height,
+            &config.arena,
+            &mut rng,
+        );
+        let accepted = result.score() >= config.acceptance_score;
+        if accepted {
+            best = candidate;
+        }
+        on_iteration(&IterationReport {
+            iteration,
+            new_examples,
+            buffer_size: buffer.len(),
+            losses,
+            arena: result,
+            accepted,
+        });
+    }
+    best
+}
+
+fn play<Evaluator_: Evaluator<State, Action>>(
+    evaluator: &Evaluator_,
+    config: &PipelineConfig,
+    rng: &mut Pcg64,
+) -> Vec<crate::Example> {
+    self_play_game(
+        evaluator,
+        config.width,
+        config.height,
+        &config.self_play,
+        rng,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puct::SearchConfig;
+
+    #[test]
+    fn test_pipeline_runs_end_to_end() {
+        let search = SearchConfig {
+            simulations: 10,
+            ..SearchConfig::default()
+        };
+        let config = PipelineConfig {
+            width: 4,
+            height: 4,
+            iterations: 2,
+            games_per_iteration: 2,
+            self_play: SelfPlayConfig {
+                search: search.clone(),
+                ..SelfPlayConfig::default()
+            },
+            training: TrainingConfig {
+                epochs: 2,
+                batch_size: 16,
+                ..TrainingConfig::default()
+            },
+            arena: ArenaConfig {
+                games: 2,
+                search,
+                ..ArenaConfig::default()
+            },
+            ..PipelineConfig::default()
+        };
+        let mut reports = vec![];
+        let network = run_pipeline(&config, |report| reports.push(report.clone()));
+        assert_eq!(reports.len(), 2);
+        assert!(reports[0].new_examples > 0);
+        assert!(reports[1].buffer_size > reports[0].buffer_size);
+        assert!(reports.iter().all(|report| report.arena.games() == 2));
+        assert_eq!(network.policy.inputs(), nn::connect_four::input_size(4, 4));
+    }
+}
@@ -0,0 +1,208 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Generating training examples by having the current network play against itself.
+
+use std::collections::VecDeque;
+
+use connect_four_logic::Move;
+use nn::connect_four::{move_index, policy_size};
+use rand::Rng;
+use rand_pcg::Pcg64;
+
+use crate::puct::{search, SearchConfig, SearchResult};
+use crate::{Action, Evaluator, Example, Position, State};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelfPlayConfig {
+    pub search: SearchConfig,
+
+    /// For this many moves at the start of the game, moves are sampled in proportion to their
+    /// visit counts (temperature 1) so games differ. After that the most visited move is played
+    /// (temperature 0).
+    pub temperature_moves: usize,
+
+    /// Games that reach this many moves are scored as draws. Pops mean a game of Connect Four
+    /// can otherwise go on forever.
+    pub max_moves: usize,
+}
+
+impl Default for SelfPlayConfig {
+    fn default() -> Self {
+        Self {
+            search: SearchConfig::default(),
+            temperature_moves: 8,
+            max_moves: 100,
+        }
+    }
+}
+
+/// Pick a move from a search result. With `sample` set, moves are drawn in proportion to their
+/// visit counts, and otherwise the most visited move is played.
+pub fn choose_move(result: &SearchResult, sample: bool, rng: &mut Pcg64) -> Move {
+    if sample {
+        let total: u32 = result.moves.iter().map(|(_, visits, _)| visits).sum();
+        if total > 0 {
+            let mut target = rng.gen_range(0..total);
+            for &(mv, visits, _) in &result.moves {
+                if target < visits {
+                    return mv;
+                }
+                target -= visits;
+            }
+        }
+    }
+    result
+        .best_move()
+        .expect("search ran from a position with moves")
+}
+
+/// The search's visit counts as a distribution over every [`policy_size`] move index, which is
+/// the policy network's training target.
+pub fn visit_policy(result: &SearchResult, width: usize) -> Vec<f64> {
+    let mut policy = vec![0.0; policy_size(width)];
+    for (&(mv, _, _), p) in result.moves.iter().zip(result.visit_distribution()) {
+        policy[move_index(mv, width)] = p;
+    }
+    policy
+}
+
+/// Play one game of the evaluator against itself from an empty board. Returns an example for
+/// every position, labelled with the final result from that position's side to move.
+pub fn self_play_game<Evaluator_: Evaluator<State, Action>>(
+    evaluator: &Evaluator_,
+    width: usize,
+    height: usize,
+    config: &SelfPlayConfig,
+    rng: &mut Pcg64,
+) -> Vec<Example> {
+    let mut position = Position::new(width, height);
+    let mut history: Vec<(Position, Vec<f64>)> = vec![];
+    while position.outcome().is_none() && history.len() < config.max_moves {
+        let result = search(&position, evaluator, &config.search, rng);
+        let mv = choose_move(&result, history.len() < config.temperature_moves, rng);
+        history.push((position.clone(), visit_policy(&result, width)));
+        position = position.play(mv);
+    }
+    // The final outcome is for whoever would move next, so walking backwards it alternates
+    // sign from one position to the one before.
+    let mut outcome = position.outcome().unwrap_or(0.0);
+    let mut examples: Vec<Example> = history
+        .into_iter()
+        .rev()
+        .map(|(position, policy)| {
+            outcome = -outcome;
+            Example {
+                position,
+                policy,
+                outcome,
+            }
+        })
+        .collect();
+    examples.reverse();
+    examples
+}
+
+/// The most recent training examples, dropping the oldest once `capacity` is reached.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayBuffer {
+    capacity: usize,
+    examples: VecDeque<Example>,
+}
+
+impl ReplayBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            examples: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn extend(&mut self, examples: impl IntoIterator<Item = Example>) {
+        for example in examples {
+            if self.examples.len() == self.capacity {
+                self.examples.pop_front();
+            }
+            self.examples.push_back(example);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.examples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.examples.is_empty()
+    }
+
+    pub fn examples(&self) -> Vec<Example> {
+        self.examples.iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UniformEvaluator;
+    use rand::SeedableRng;
+
+    fn config() -> SelfPlayConfig {
+        SelfPlayConfig {
+            search: SearchConfig {
+                simulations: 30,
+                ..SearchConfig::default()
+            },
+            ..SelfPlayConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_self_play_examples() {
+        let mut rng = Pcg64::seed_from_u64(4);
+        let examples = self_play_game(&UniformEvaluator, 4, 4, &config(), &mut rng);
+        assert!(!examples.is_empty());
+        assert!(examples.len() <= config().max_moves);
+        for example in &examples {
+            assert!((example.policy.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        }
+        for pair in examples.windows(2) {
+            assert_ne!(pair[0].position.to_move, pair[1].position.to_move);
+            assert_eq!(pair[0].outcome, -pair[1].outcome);
+        }
+    }
+
+    #[test]
+    fn test_max_moves_scores_a_draw() {
+        let mut rng = Pcg64::seed_from_u64(4);
+        let config = SelfPlayConfig {
+            max_moves: 3,
+            ..config()
+        };
+        let examples = self_play_game(&UniformEvaluator, 7, 6, &config, &mut rng);
+        assert_eq!(examples.len(), 3);
+        assert!(examples.iter().all(|example| example.outcome == 0.0));
+    }
+
+    #[test]
+    fn test_replay_buffer_drops_the_oldest() {
+        let mut rng = Pcg64::seed_from_u64(4);
+        let examples = self_play_game(&UniformEvaluator, 4, 4, &config(), &mut rng);
+        let mut buffer = ReplayBuffer::new(3);
+        buffer.extend(examples.clone());
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(buffer.examples(), examples[examples.len() - 3..].to_vec());
+    }
+}
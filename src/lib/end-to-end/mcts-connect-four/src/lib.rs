@@ -14,14 +14,16 @@
  * with this program. If not, see <https://www.gnu.org/licenses/>
  */
 
-use std::cell::RefCell;
-use std::path::PathBuf;
-use std::rc::Rc;
+pub mod puct;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use rand::prelude::SliceRandom;
 use serde::ser::{Serialize, SerializeStruct};
 use serde_derive::{Deserialize, Serialize};
 
+pub use monte_carlo_tree_search::{shared_rng, SharedRng};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Action(pub connect_four_logic::Move);
 
@@ -34,6 +36,15 @@ impl Serialize for Action {
     }
 }
 
+impl<'de> serde::Deserialize<'de> for Action {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        connect_four_logic::Move::deserialize(deserializer).map(Action)
+    }
+}
+
 impl monte_carlo_tree_search::Action for Action {}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -76,6 +87,32 @@ impl Serialize for State {
     }
 }
 
+// is_terminal_position is worked out from the board, so it is ignored when deserializing.
+impl<'de> serde::Deserialize<'de> for State {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Fields {
+            board: connect_four_logic::Board,
+            turn: Player,
+            who_am_i: Player,
+        }
+
+        let Fields {
+            board,
+            turn,
+            who_am_i,
+        } = Fields::deserialize(deserializer)?;
+        Ok(State {
+            board,
+            turn,
+            who_am_i,
+        })
+    }
+}
+
 impl std::fmt::Display for State {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         format!("{}", self.board).fmt(f)?;
@@ -134,13 +171,28 @@ impl monte_carlo_tree_search::State<Action> for State {
         max_depth_per_playout: monte_carlo_tree_search::Int,
         rng: &mut monte_carlo_tree_search::Rng,
     ) -> Vec<monte_carlo_tree_search::SimulationResult> {
-        (0..playouts)
+        self.simulate_playouts(playouts, max_depth_per_playout, rng)
             .into_iter()
-            // .into_par_iter()
-            .map(|_| playout(self.clone(), max_depth_per_playout, rng))
+            .map(|playout| playout.result)
             .collect()
     }
 
+    fn simulate_playouts(
+        &self,
+        playouts: monte_carlo_tree_search::Int,
+        max_depth_per_playout: monte_carlo_tree_search::Int,
+        rng: &mut monte_carlo_tree_search::Rng,
+    ) -> Vec<monte_carlo_tree_search::Playout<Action>> {
+        monte_carlo_tree_search::run_playouts(
+            self,
+            &PLAYOUT_POLICY,
+            outcome,
+            playouts,
+            max_depth_per_playout,
+            rng,
+        )
+    }
+
     fn get_actions(&self) -> Vec<Action> {
         let player = match self.turn {
             Player::Player1 => connect_four_logic::Player::Player1,
@@ -191,72 +243,64 @@ impl monte_carlo_tree_search::State<Action> for State {
     }
 }
 
-fn playout(
-    state: State,
-    max_depth: monte_carlo_tree_search::Int,
-    rng: &mut monte_carlo_tree_search::Rng,
-) -> monte_carlo_tree_search::SimulationResult {
-    let mut current_player: connect_four_logic::Player = state.turn.into();
-    let mut board = state.board;
-    let mut depth = 0;
-    while depth < max_depth {
-        if connect_four_logic::is_terminal_position(&board)
-            != connect_four_logic::TerminalPosition::IsNotTerminal
-        {
-            break;
-        }
-
-        let moves = connect_four_logic::get_legal_moves(&board, current_player);
-        if moves.is_empty() {
-            break;
+// Player1 is player 0 in the arena and Player2 is player 1.
+impl monte_carlo_tree_search::ArenaGame<Action> for State {
+    fn player_to_move(&self) -> usize {
+        match self.turn {
+            Player::Player1 => 0,
+            Player::Player2 => 1,
         }
+    }
 
-        // Check if any of the moves are winning moves. If so, take that move.
-        let mut used_winning_move = false;
-        for m in moves.iter() {
-            let mut board_copy = board.clone();
-            match &m.move_type {
-                connect_four_logic::MoveType::Insert => {
-                    board_copy.insert(m.column, current_player).unwrap();
-                }
-                connect_four_logic::MoveType::Pop => {
-                    board_copy.pop(m.column, current_player).unwrap();
-                }
-            }
-            if connect_four_logic::is_terminal_position(&board_copy)
-                == connect_four_logic::TerminalPosition::IsTerminalWin(current_player)
-            {
-                used_winning_move = true;
-                board = board_copy;
-                depth += 1;
-                current_player.other();
-                break;
-            }
-        }
-        if used_winning_move {
-            break;
+    fn searched_by(&self, player: usize) -> Self {
+        let who_am_i = if player == 0 {
+            Player::Player1
+        } else {
+            Player::Player2
+        };
+        Self {
+            who_am_i,
+            ..self.clone()
         }
+    }
 
-        let random_move = moves.choose(rng).unwrap();
-        match random_move.move_type {
-            connect_four_logic::MoveType::Insert => {
-                board.insert(random_move.column, current_player).unwrap();
-            }
-            connect_four_logic::MoveType::Pop => {
-                board.pop(random_move.column, current_player).unwrap();
-            }
-        }
-        depth += 1;
-        current_player.other();
+    fn reward(&self, player: usize) -> f64 {
+        outcome(&self.searched_by(player)).reward()
     }
+}
 
-    let who_am_i: connect_four_logic::Player = state.who_am_i.into();
-    if connect_four_logic::is_terminal_position(&board)
-        == connect_four_logic::TerminalPosition::IsTerminalWin(who_am_i)
+/// Playouts take a winning move whenever there is one and are random otherwise.
+const PLAYOUT_POLICY: monte_carlo_tree_search::EpsilonGreedy<fn(&State, &Action) -> f64> =
+    monte_carlo_tree_search::EpsilonGreedy {
+        epsilon: 0.0,
+        heuristic: winning_move,
+    };
+
+/// 1 if playing `action` wins the game for the player to move, else 0.
+fn winning_move(state: &State, action: &Action) -> f64 {
+    let player: connect_four_logic::Player = state.turn.into();
+    let next_state = monte_carlo_tree_search::State::get_next_state(state, action);
+    if connect_four_logic::is_terminal_position(&next_state.board)
+        == connect_four_logic::TerminalPosition::IsTerminalWin(player)
     {
-        monte_carlo_tree_search::SimulationResult::Win
+        1.0
     } else {
-        monte_carlo_tree_search::SimulationResult::NotWin
+        0.0
+    }
+}
+
+/// Score the state a playout ended in for `who_am_i`. A draw is worth half a win, so the search
+/// prefers drawing to losing.
+fn outcome(state: &State) -> monte_carlo_tree_search::SimulationResult {
+    let who_am_i: connect_four_logic::Player = state.who_am_i.into();
+    match connect_four_logic::is_terminal_position(&state.board) {
+        connect_four_logic::TerminalPosition::IsTerminalWin(winner) if winner == who_am_i => {
+            monte_carlo_tree_search::SimulationResult::Win
+        }
+        connect_four_logic::TerminalPosition::IsTerminalDraw => {
+            monte_carlo_tree_search::SimulationResult::Reward(0.5)
+        }
+        _ => monte_carlo_tree_search::SimulationResult::NotWin,
     }
 }
 
@@ -267,9 +311,12 @@ pub struct MctsConfig {
     pub max_depth_per_playout: monte_carlo_tree_search::Int,
     pub tree_dump_dir: Option<PathBuf>,
     pub debug_track_trees: monte_carlo_tree_search::DebugTrackTrees,
+    pub transpositions: monte_carlo_tree_search::Transpositions,
+    pub reward_perspective: monte_carlo_tree_search::RewardPerspective,
 }
 
 impl MctsConfig {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         iterations: monte_carlo_tree_search::Int,
         exploration_constant: monte_carlo_tree_search::Float,
@@ -277,6 +324,8 @@ impl MctsConfig {
         max_depth_per_playout: monte_carlo_tree_search::Int,
         tree_dump_dir: Option<PathBuf>,
         debug_track_trees: monte_carlo_tree_search::DebugTrackTrees,
+        transpositions: monte_carlo_tree_search::Transpositions,
+        reward_perspective: monte_carlo_tree_search::RewardPerspective,
     ) -> Self {
         Self {
             iterations,
@@ -285,6 +334,8 @@ impl MctsConfig {
             max_depth_per_playout,
             tree_dump_dir,
             debug_track_trees,
+            transpositions,
+            reward_perspective,
         }
     }
 
@@ -302,42 +353,368 @@ impl Default for MctsConfig {
             50,
             Some(PathBuf::from("/tmp/tree-dump-dir")),
             monte_carlo_tree_search::DebugTrackTrees::Track,
+            monte_carlo_tree_search::Transpositions::Separate,
+            monte_carlo_tree_search::RewardPerspective::Alternating,
         )
     }
 }
 
+/// SearchStats describes the cost of the search that produced a move, so callers can adapt
+/// search budgets to the device they run on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchStats {
+    pub iterations: monte_carlo_tree_search::Int,
+    pub nodes: usize,
+    pub approximate_tree_bytes: usize,
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct BestMctsMove {
     pub actual_move: connect_four_logic::Move,
     pub debug_trees: Option<Vec<monte_carlo_tree_search::MctsNodeForSerialization<State, Action>>>,
+    pub stats: SearchStats,
+
+    // Depth, principal variation and speed of a UCT search. None for PUCT searches.
+    pub search_info: Option<monte_carlo_tree_search::SearchInfo<Action>>,
 }
 
-pub fn get_best_mcts_move(
+pub fn get_best_mcts_move(state: &State, config: &MctsConfig, rng: SharedRng) -> BestMctsMove {
+    search(&mut new_mcts(state, config, rng))
+}
+
+fn new_mcts(
     state: &State,
     config: &MctsConfig,
-    rng: Rc<RefCell<rand_pcg::Pcg64>>,
-) -> BestMctsMove {
-    let mut mcts = monte_carlo_tree_search::Mcts::<State, Action>::new(
-        state.clone(),
-        monte_carlo_tree_search::MctsArgs {
-            iteration_limit: monte_carlo_tree_search::IterationLimitKind::Iterations(
-                config.iterations,
-            ),
-            exploration_constant: config.exploration_constant,
-            playouts_per_simulation: config.playouts_per_simulation,
-            max_depth_per_playout: config.max_depth_per_playout,
-            rng,
-            tree_dump_dir: config.tree_dump_dir.clone(),
-            debug_track_trees: config.debug_track_trees,
-        },
-    );
+    rng: SharedRng,
+) -> monte_carlo_tree_search::Mcts<State, Action> {
+    monte_carlo_tree_search::Mcts::<State, Action>::new(state.clone(), mcts_args(config, rng))
+}
+
+fn mcts_args(config: &MctsConfig, rng: SharedRng) -> monte_carlo_tree_search::MctsArgs {
+    monte_carlo_tree_search::MctsArgs {
+        iteration_limit: monte_carlo_tree_search::IterationLimitKind::Iterations(config.iterations),
+        exploration_constant: config.exploration_constant,
+        playouts_per_simulation: config.playouts_per_simulation,
+        max_depth_per_playout: config.max_depth_per_playout,
+        rng,
+        tree_dump_dir: config.tree_dump_dir.clone(),
+        debug_track_trees: config.debug_track_trees,
+        transpositions: config.transpositions,
+        reward_perspective: config.reward_perspective,
+        solver: monte_carlo_tree_search::Solver::Enabled,
+        ..monte_carlo_tree_search::MctsArgs::default()
+    }
+}
 
+fn search(mcts: &mut monte_carlo_tree_search::Mcts<State, Action>) -> BestMctsMove {
     mcts.run();
     let best_move = mcts.best_action().unwrap();
     let debug_trees = mcts.debug_trees();
+    let stats = SearchStats {
+        iterations: mcts.iterations_run(),
+        nodes: mcts.node_count(),
+        approximate_tree_bytes: mcts.approximate_tree_bytes(),
+    };
 
     BestMctsMove {
         actual_move: best_move.0,
         debug_trees,
+        stats,
+        search_info: Some(mcts.search_info()),
+    }
+}
+
+/// MctsPlayer is get_best_mcts_move for a whole game: it keeps the search tree between moves.
+/// Pass every move played, its own and the opponent's, to apply_move, and the next search starts
+/// from the statistics already gathered for the position reached.
+pub struct MctsPlayer {
+    config: MctsConfig,
+    rng: SharedRng,
+    mcts: Option<monte_carlo_tree_search::Mcts<State, Action>>,
+}
+
+impl MctsPlayer {
+    pub fn new(config: MctsConfig, rng: SharedRng) -> Self {
+        Self {
+            config,
+            rng,
+            mcts: None,
+        }
+    }
+
+    /// A player that carries on with the tree saved by save_checkpoint. The game continues from
+    /// the tree's root state.
+    pub fn resume(
+        config: MctsConfig,
+        rng: SharedRng,
+        checkpoint: impl AsRef<Path>,
+    ) -> Result<Self, monte_carlo_tree_search::CheckpointError> {
+        let mcts = monte_carlo_tree_search::Mcts::load_checkpoint(
+            checkpoint,
+            mcts_args(&config, Arc::clone(&rng)),
+        )?;
+        Ok(Self {
+            config,
+            rng,
+            mcts: Some(mcts),
+        })
+    }
+
+    pub fn config(&self) -> &MctsConfig {
+        &self.config
+    }
+
+    /// The position the kept tree is rooted at, if there is one.
+    pub fn root_state(&self) -> Option<&State> {
+        self.mcts.as_ref().map(|mcts| mcts.root_state())
+    }
+
+    /// Save the kept tree to `path`, for resume. Before the first search there is no tree, and
+    /// nothing is saved.
+    pub fn save_checkpoint(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<(), monte_carlo_tree_search::CheckpointError> {
+        match &self.mcts {
+            Some(mcts) => mcts.save_checkpoint(path),
+            None => Ok(()),
+        }
+    }
+
+    /// Search from `state`, reusing the tree if it is rooted at `state` and starting a new one
+    /// otherwise.
+    pub fn best_move(&mut self, state: &State) -> BestMctsMove {
+        let mcts = match self.mcts.take() {
+            Some(mcts) if mcts.root_state() == state => mcts,
+            _ => new_mcts(state, &self.config, Arc::clone(&self.rng)),
+        };
+        search(self.mcts.insert(mcts))
+    }
+
+    /// Move the tree's root along `actual_move`. A move that isn't legal in the tree's root
+    /// position means the game went somewhere else, so the tree is dropped.
+    pub fn apply_move(&mut self, actual_move: connect_four_logic::Move) {
+        use monte_carlo_tree_search::State as _;
+
+        let action = Action(actual_move);
+        match self.mcts.as_mut() {
+            Some(mcts) if mcts.root_state().get_actions().contains(&action) => {
+                mcts.advance_root(action)
+            }
+            _ => self.mcts = None,
+        }
+    }
+
+    /// Number of nodes in the kept tree, which the next search starts from.
+    pub fn node_count(&self) -> usize {
+        self.mcts.as_ref().map_or(0, |mcts| mcts.node_count())
+    }
+}
+
+/// Like get_best_mcts_move, but searching with PUCT guided by an evaluator instead of UCT with
+/// random playouts. With a trained policy and value network as the evaluator this is the
+/// "NN-guided" mode. The root noise in the config is ignored since this is for playing, not
+/// for generating training games.
+pub fn get_best_puct_move(
+    state: &State,
+    evaluator: &dyn monte_carlo_tree_search::Evaluator<State, Action>,
+    config: &puct::SearchConfig,
+    rng: SharedRng,
+) -> BestMctsMove {
+    let result = puct::search(
+        &puct::Position::from(state),
+        evaluator,
+        &config.without_noise(),
+        &mut rng.lock().unwrap(),
+    );
+    BestMctsMove {
+        actual_move: result.best_move().unwrap(),
+        debug_trees: None,
+        stats: SearchStats {
+            iterations: config.simulations as monte_carlo_tree_search::Int,
+            nodes: result.nodes,
+            approximate_tree_bytes: result.approximate_tree_bytes,
+        },
+        search_info: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn new_player() -> MctsPlayer {
+        let config = MctsConfig {
+            iterations: 200,
+            playouts_per_simulation: 5,
+            tree_dump_dir: None,
+            debug_track_trees: monte_carlo_tree_search::DebugTrackTrees::None,
+            ..MctsConfig::default()
+        };
+        MctsPlayer::new(config, shared_rng(42))
+    }
+
+    fn insert(column: usize) -> connect_four_logic::Move {
+        connect_four_logic::Move {
+            move_type: connect_four_logic::MoveType::Insert,
+            column,
+        }
+    }
+
+    #[test]
+    fn test_player_reuses_tree_between_moves() {
+        let mut player = new_player();
+        let mut state = State::new(7, 6, Player::Player1, Player::Player1);
+        let first = player.best_move(&state);
+        for actual_move in [first.actual_move, insert(0)] {
+            state.apply_move(&Action(actual_move));
+            player.apply_move(actual_move);
+        }
+        let kept = player.node_count();
+        assert!(kept > 1);
+
+        let second = player.best_move(&state);
+        assert!(second.stats.nodes > kept);
+    }
+
+    #[test]
+    fn test_player_resumes_from_checkpoint() {
+        let mut player = new_player();
+        let mut state = State::new(7, 6, Player::Player1, Player::Player1);
+        let first = player.best_move(&state);
+        for actual_move in [first.actual_move, insert(0)] {
+            state.apply_move(&Action(actual_move));
+            player.apply_move(actual_move);
+        }
+        let path = std::env::temp_dir().join(format!("aima-c4-{}.cbor", std::process::id()));
+        player.save_checkpoint(&path).unwrap();
+
+        let resumed = MctsPlayer::resume(new_player().config, shared_rng(42), &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(resumed.root_state(), Some(&state));
+        assert_eq!(resumed.node_count(), player.node_count());
+    }
+
+    #[test]
+    fn test_player_drops_tree_after_unknown_move() {
+        let mut player = new_player();
+        let state = State::new(7, 6, Player::Player1, Player::Player1);
+        player.best_move(&state);
+        // Nothing can be popped from an empty board.
+        player.apply_move(connect_four_logic::Move {
+            move_type: connect_four_logic::MoveType::Pop,
+            column: 0,
+        });
+        assert_eq!(player.node_count(), 0);
+    }
+
+    // Recording the moves doesn't change the playouts.
+    #[test]
+    fn test_simulate_playouts_records_moves() {
+        use monte_carlo_tree_search::State as _;
+
+        let state = State::new(7, 6, Player::Player1, Player::Player1);
+        let results = state.simulate(20, 50, &mut rand_pcg::Pcg64::seed_from_u64(7));
+        let playouts = state.simulate_playouts(20, 50, &mut rand_pcg::Pcg64::seed_from_u64(7));
+        assert_eq!(
+            results,
+            playouts
+                .iter()
+                .map(|playout| playout.result)
+                .collect::<Vec<_>>()
+        );
+        assert!(playouts
+            .iter()
+            .all(|playout| playout.actions.len() >= 7 && playout.actions.len() <= 50));
+    }
+
+    #[test]
+    fn test_draw_is_half_a_win() {
+        use monte_carlo_tree_search::State as _;
+
+        // Full, and Player 1 has nothing to pop.
+        let state = State {
+            board: connect_four_logic::Board::from_notation("111/222/111/222").unwrap(),
+            turn: Player::Player1,
+            who_am_i: Player::Player1,
+        };
+        let results = state.simulate(3, 10, &mut rand_pcg::Pcg64::seed_from_u64(7));
+        assert_eq!(
+            results,
+            vec![monte_carlo_tree_search::SimulationResult::Reward(0.5).after(0); 3]
+        );
+    }
+
+    // Player 1 has three in a row along the bottom. With alternating perspectives the search
+    // expects Player 1 to take the win, so Player 2 blocks at column 3.
+    #[test]
+    fn test_alternating_perspective_blocks_a_win() {
+        let state = State {
+            board: connect_four_logic::Board::from_notation(
+                "......./......./......./......./2.2..../111....",
+            )
+            .unwrap(),
+            turn: Player::Player2,
+            who_am_i: Player::Player2,
+        };
+        let config = MctsConfig {
+            iterations: 300,
+            playouts_per_simulation: 5,
+            tree_dump_dir: None,
+            debug_track_trees: monte_carlo_tree_search::DebugTrackTrees::None,
+            reward_perspective: monte_carlo_tree_search::RewardPerspective::Alternating,
+            ..MctsConfig::default()
+        };
+        let best = get_best_mcts_move(&state, &config, shared_rng(42));
+        assert_eq!(best.actual_move, insert(3));
+    }
+
+    #[test]
+    fn test_arena_plays_every_game() {
+        let config = MctsConfig {
+            iterations: 20,
+            playouts_per_simulation: 2,
+            tree_dump_dir: None,
+            debug_track_trees: monte_carlo_tree_search::DebugTrackTrees::None,
+            ..MctsConfig::default()
+        };
+        let args = mcts_args(&config, shared_rng(42));
+        let result = monte_carlo_tree_search::arena(
+            &State::new(5, 4, Player::Player1, Player::Player1),
+            &args,
+            &args,
+            &monte_carlo_tree_search::ArenaConfig {
+                games: 2,
+                max_moves: 40,
+            },
+        );
+        assert_eq!(result.games(), 2);
+    }
+
+    // Player 1 wins at once by completing the bottom row at column 3. The solver proves it,
+    // stopping the search long before its iteration budget runs out.
+    #[test]
+    fn test_solver_proves_a_win() {
+        let state = State {
+            board: connect_four_logic::Board::from_notation(
+                "......./......./......./......./22...2./111....",
+            )
+            .unwrap(),
+            turn: Player::Player1,
+            who_am_i: Player::Player1,
+        };
+        let config = MctsConfig {
+            iterations: 300,
+            playouts_per_simulation: 5,
+            tree_dump_dir: None,
+            debug_track_trees: monte_carlo_tree_search::DebugTrackTrees::None,
+            ..MctsConfig::default()
+        };
+        let best = get_best_mcts_move(&state, &config, shared_rng(42));
+        assert_eq!(best.actual_move, insert(3));
+        let info = best.search_info.unwrap();
+        assert_eq!(info.proven_reward, Some(1.0));
+        assert!(info.iterations < 300, "{}", info.iterations);
     }
 }
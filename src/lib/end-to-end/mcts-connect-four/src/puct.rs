@@ -0,0 +1,452 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// PUCT Monte Carlo tree search guided by an evaluator, as in AlphaZero. Instead of random
+// playouts, each simulation expands one leaf and backs up the evaluator's value for it, and
+// selection trades the mean value of a move off against its prior probability.
+
+use connect_four_logic::{
+    get_legal_moves, is_terminal_position, Board, Move, MoveType, Player, TerminalPosition,
+};
+use monte_carlo_tree_search::{DirichletNoise, Evaluator};
+use rand_pcg::Pcg64;
+
+use crate::{Action, State};
+
+/// A board together with whose turn it is.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Position {
+    pub board: Board,
+    pub to_move: Player,
+}
+
+impl Position {
+    /// An empty board with Player 1 to move.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            board: Board::new(width, height),
+            to_move: Player::Player1,
+        }
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        get_legal_moves(&self.board, self.to_move)
+    }
+
+    pub fn play(&self, mv: Move) -> Self {
+        let mut board = self.board.clone();
+        match mv.move_type {
+            MoveType::Insert => board.insert(mv.column, self.to_move),
+            MoveType::Pop => board.pop(mv.column, self.to_move),
+        }
+        .expect("move must be legal");
+        let mut to_move = self.to_move;
+        to_move.other();
+        Self { board, to_move }
+    }
+
+    /// If the game is over, its value for the side to move: 1 for a win, −1 for a loss and 0
+    /// for a draw.
+    pub fn outcome(&self) -> Option<f64> {
+        match is_terminal_position(&self.board) {
+            TerminalPosition::IsNotTerminal => None,
+            TerminalPosition::IsTerminalDraw => Some(0.0),
+            TerminalPosition::IsTerminalWin(winner) if winner == self.to_move => Some(1.0),
+            TerminalPosition::IsTerminalWin(_) => Some(-1.0),
+        }
+    }
+}
+
+impl From<&crate::State> for Position {
+    fn from(state: &crate::State) -> Self {
+        Self {
+            board: state.board.clone(),
+            to_move: state.turn.into(),
+        }
+    }
+}
+
+/// The state searched by the side to move in `position`, which is how the search hands
+/// positions to its Evaluator.
+impl From<&Position> for State {
+    fn from(position: &Position) -> Self {
+        let turn = match position.to_move {
+            Player::Player1 => crate::Player::Player1,
+            Player::Player2 => crate::Player::Player2,
+        };
+        Self {
+            board: position.board.clone(),
+            turn,
+            who_am_i: turn,
+        }
+    }
+}
+
+/// The result of Evaluator::evaluate for `state` from a prior for each legal move and the
+/// value of the position for the side to move, between −1 and 1, which becomes the expected
+/// reward of `state.who_am_i`. This is how evaluators that think in terms of the side to move,
+/// such as a policy and value network, guide the search.
+pub fn side_to_move_evaluation(
+    state: &State,
+    prior: impl Fn(Move) -> f64,
+    value: f64,
+) -> (Vec<(Action, f64)>, f64) {
+    let priors = get_legal_moves(&state.board, state.turn.into())
+        .into_iter()
+        .map(|mv| (Action(mv), prior(mv)))
+        .collect();
+    let reward = (value + 1.0) / 2.0;
+    if state.turn == state.who_am_i {
+        (priors, reward)
+    } else {
+        (priors, 1.0 - reward)
+    }
+}
+
+/// Every move equally likely and every position even. Searching with this is plain PUCT
+/// without any learned knowledge.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct UniformEvaluator;
+
+impl Evaluator<State, Action> for UniformEvaluator {
+    fn evaluate(&self, _state: &State) -> (Vec<(Action, f64)>, f64) {
+        (vec![], 0.5)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchConfig {
+    pub simulations: usize,
+
+    /// Weight of the prior-driven exploration term.
+    pub c_puct: f64,
+
+    /// Dirichlet noise mixed into the root priors. None turns the noise off, which is what you
+    /// want outside self-play.
+    pub root_noise: Option<DirichletNoise>,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            simulations: 200,
+            c_puct: 1.5,
+            root_noise: Some(DirichletNoise::new(1.0, 0.25).expect("valid root noise")),
+        }
+    }
+}
+
+impl SearchConfig {
+    /// The same search without root noise, for playing to win rather than to explore.
+    pub fn without_noise(&self) -> Self {
+        Self {
+            root_noise: None,
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Edge {
+    mv: Move,
+    prior: f64,
+    visits: u32,
+
+    /// Sum of the values backed up through this edge, from the point of view of the player
+    /// making the move.
+    total_value: f64,
+    child: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    edges: Vec<Edge>,
+    expanded: bool,
+}
+
+/// What the search found at the root.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    /// Every legal move with its visit count and mean value for the player making it.
+    pub moves: Vec<(Move, u32, f64)>,
+
+    /// Mean value of the root for the side to move, over its own evaluation and every
+    /// simulation.
+    pub value: f64,
+
+    /// Number of nodes in the search tree, including the root.
+    pub nodes: usize,
+
+    /// Heap bytes used by the nodes and their edges, not counting allocator overhead.
+    pub approximate_tree_bytes: usize,
+}
+
+impl SearchResult {
+    /// Visit counts normalized to sum to 1, in the same order as `moves`.
+    pub fn visit_distribution(&self) -> Vec<f64> {
+        let total: u32 = self.moves.iter().map(|(_, visits, _)| visits).sum();
+        self.moves
+            .iter()
+            .map(|&(_, visits, _)| visits as f64 / total.max(1) as f64)
+            .collect()
+    }
+
+    /// The most visited move.
+    pub fn best_move(&self) -> Option<Move> {
+        self.moves
+            .iter()
+            .max_by_key(|(_, visits, _)| *visits)
+            .map(|(mv, _, _)| *mv)
+    }
+}
+
+struct Search<'a, Evaluator_: ?Sized> {
+    nodes: Vec<Node>,
+    evaluator: &'a Evaluator_,
+    config: &'a SearchConfig,
+    rng: &'a mut Pcg64,
+}
+
+/// Run `config.simulations` simulations from `position`. The position must not be terminal.
+pub fn search<Evaluator_: Evaluator<State, Action> + ?Sized>(
+    position: &Position,
+    evaluator: &Evaluator_,
+    config: &SearchConfig,
+    rng: &mut Pcg64,
+) -> SearchResult {
+    let mut search = Search {
+        nodes: vec![Node::default()],
+        evaluator,
+        config,
+        rng,
+    };
+    // Expanding the root first means every simulation ends up as a visit to one of its moves.
+    let mut total = search.expand(0, position);
+    for _ in 0..config.simulations {
+        total += search.simulate(0, position.clone());
+    }
+    let root = &search.nodes[0];
+    SearchResult {
+        moves: root
+            .edges
+            .iter()
+            .map(|edge| {
+                let mean = if edge.visits == 0 {
+                    0.0
+                } else {
+                    edge.total_value / edge.visits as f64
+                };
+                (edge.mv, edge.visits, mean)
+            })
+            .collect(),
+        value: total / (config.simulations + 1) as f64,
+        nodes: search.nodes.len(),
+        approximate_tree_bytes: search.nodes.capacity() * std::mem::size_of::<Node>()
+            + search
+                .nodes
+                .iter()
+                .map(|node| node.edges.capacity() * std::mem::size_of::<Edge>())
+                .sum::<usize>(),
+    }
+}
+
+impl<'a, Evaluator_: Evaluator<State, Action> + ?Sized> Search<'a, Evaluator_> {
+    /// One simulation below `node`. Returns the value of `position` for its side to move.
+    fn simulate(&mut self, node: usize, position: Position) -> f64 {
+        if let Some(outcome) = position.outcome() {
+            return outcome;
+        }
+        if !self.nodes[node].expanded {
+            return self.expand(node, &position);
+        }
+
+        let parent_visits: u32 = self.nodes[node].edges.iter().map(|e| e.visits).sum();
+        let exploration = self.config.c_puct * (parent_visits.max(1) as f64).sqrt();
+        let chosen = self.nodes[node]
+            .edges
+            .iter()
+            .enumerate()
+            .map(|(i, edge)| {
+                // Unvisited moves count as even, so the prior alone decides their order.
+                let mean = if edge.visits == 0 {
+                    0.0
+                } else {
+                    edge.total_value / edge.visits as f64
+                };
+                (
+                    i,
+                    mean + exploration * edge.prior / (1.0 + edge.visits as f64),
+                )
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i)
+            .expect("expanded non-terminal nodes have moves");
+
+        let next = position.play(self.nodes[node].edges[chosen].mv);
+        let child = match self.nodes[node].edges[chosen].child {
+            Some(child) => child,
+            None => {
+                self.nodes.push(Node::default());
+                let child = self.nodes.len() - 1;
+                self.nodes[node].edges[chosen].child = Some(child);
+                child
+            }
+        };
+        // The child's value is for the opponent, so it flips sign on the way up.
+        let value = -self.simulate(child, next);
+        let edge = &mut self.nodes[node].edges[chosen];
+        edge.visits += 1;
+        edge.total_value += value;
+        value
+    }
+
+    fn expand(&mut self, node: usize, position: &Position) -> f64 {
+        let moves = position.legal_moves();
+        let actions: Vec<Action> = moves.iter().copied().map(Action).collect();
+        let (mut legal, reward) = self
+            .evaluator
+            .evaluate_actions(&State::from(position), &actions);
+        if let (0, Some(noise)) = (node, self.config.root_noise) {
+            noise.mix(&mut legal, self.rng);
+        }
+        self.nodes[node].edges = moves
+            .into_iter()
+            .zip(legal)
+            .map(|(mv, prior)| Edge {
+                mv,
+                prior,
+                visits: 0,
+                total_value: 0.0,
+                child: None,
+            })
+            .collect();
+        self.nodes[node].expanded = true;
+        // The state is searched by the side to move, so its reward converts straight back.
+        2.0 * reward - 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn config(simulations: usize) -> SearchConfig {
+        SearchConfig {
+            simulations,
+            ..SearchConfig::default()
+        }
+        .without_noise()
+    }
+
+    #[test]
+    fn test_position_outcome_is_from_the_side_to_move() {
+        let won = Position {
+            board: Board::from_notation("..../1.../1.../1.../1222").unwrap(),
+            to_move: Player::Player2,
+        };
+        assert_eq!(won.outcome(), Some(-1.0));
+        assert_eq!(Position::new(4, 4).outcome(), None);
+        let next = Position::new(4, 4).play(Position::new(4, 4).legal_moves()[0]);
+        assert_eq!(next.to_move, Player::Player2);
+        assert_eq!(next.board.count_pieces(Player::Player1), 1);
+    }
+
+    #[test]
+    fn test_side_to_move_evaluation() {
+        let mut state = State::from(&Position::new(4, 4));
+        let (priors, reward) = side_to_move_evaluation(&state, |mv| mv.column as f64, 1.0);
+        assert_eq!(priors.len(), 4);
+        assert_eq!(priors[3].1, 3.0);
+        assert_eq!(reward, 1.0);
+        // The value is for the side to move, so it flips for the other player.
+        state.who_am_i = crate::Player::Player2;
+        let (_, reward) = side_to_move_evaluation(&state, |_| 1.0, 0.5);
+        assert_eq!(reward, 0.25);
+    }
+
+    #[test]
+    fn test_takes_the_winning_move() {
+        let position = Position {
+            board: Board::from_notation("...../...../1..../1..../1222.").unwrap(),
+            to_move: Player::Player1,
+        };
+        let mut rng = Pcg64::seed_from_u64(1);
+        let result = search(&position, &UniformEvaluator, &config(200), &mut rng);
+        assert_eq!(
+            result.best_move(),
+            Some(Move {
+                move_type: MoveType::Insert,
+                column: 0
+            })
+        );
+        assert!(result.value > 0.5);
+    }
+
+    #[test]
+    fn test_blocks_the_opponents_winning_move() {
+        let position = Position {
+            board: Board::from_notation("...../...../...../2..../2.111").unwrap(),
+            to_move: Player::Player2,
+        };
+        let mut rng = Pcg64::seed_from_u64(1);
+        let result = search(&position, &UniformEvaluator, &config(400), &mut rng);
+        assert_eq!(
+            result.best_move(),
+            Some(Move {
+                move_type: MoveType::Insert,
+                column: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_visits_add_up_to_the_simulations() {
+        let position = Position::new(4, 4);
+        let mut rng = Pcg64::seed_from_u64(1);
+        let noisy = SearchConfig {
+            simulations: 50,
+            ..SearchConfig::default()
+        };
+        let result = search(&position, &UniformEvaluator, &noisy, &mut rng);
+        // There are no pieces to pop yet, so only the four inserts are legal.
+        assert_eq!(result.moves.len(), 4);
+        let visits: u32 = result.moves.iter().map(|(_, visits, _)| visits).sum();
+        assert_eq!(visits, 50);
+        let distribution = result.visit_distribution();
+        assert!((distribution.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert!(result.nodes > 1);
+    }
+
+    #[test]
+    fn test_priors_steer_the_search() {
+        // All the prior on the last legal move, and no value information.
+        let favourite = |state: &State| {
+            let moves = get_legal_moves(&state.board, state.turn.into());
+            (vec![(Action(*moves.last().unwrap()), 1.0)], 0.5)
+        };
+        let position = Position::new(7, 6);
+        let mut rng = Pcg64::seed_from_u64(1);
+        let result = search(&position, &favourite, &config(50), &mut rng);
+        assert_eq!(
+            result.best_move(),
+            Some(Move {
+                move_type: MoveType::Insert,
+                column: 6
+            })
+        );
+    }
+}
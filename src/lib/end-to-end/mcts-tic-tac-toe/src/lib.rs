@@ -0,0 +1,191 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+//! Tic-Tac-Toe played with Monte Carlo Tree Search.
+//!
+//! This is the smallest end-to-end example of the monte-carlo-tree-search crate. It is small
+//! enough that a search can be checked against the known optimal play.
+
+use serde::ser::{Serialize, SerializeStruct};
+
+pub use tic_tac_toe_logic::Player;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Action(pub tic_tac_toe_logic::Move);
+
+impl Serialize for Action {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl monte_carlo_tree_search::Action for Action {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct State {
+    pub board: tic_tac_toe_logic::Board,
+    pub turn: Player,
+    pub who_am_i: Player,
+}
+
+// implement Serialize for State, but add an additional field that calls is_terminal
+impl Serialize for State {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("State", 4)?;
+        state.serialize_field("board", &self.board)?;
+        state.serialize_field("turn", &self.turn)?;
+        state.serialize_field("who_am_i", &self.who_am_i)?;
+        state.serialize_field(
+            "is_terminal_position",
+            &tic_tac_toe_logic::is_terminal_position(&self.board),
+        )?;
+        state.end()
+    }
+}
+
+impl std::fmt::Display for State {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.board)?;
+        write!(f, "{}'s turn", self.turn)
+    }
+}
+
+impl State {
+    pub fn new(turn: Player, who_am_i: Player) -> Self {
+        Self {
+            board: tic_tac_toe_logic::Board::new(),
+            turn,
+            who_am_i,
+        }
+    }
+
+    pub fn apply_move(&mut self, action: &Action) {
+        self.board
+            .place(&action.0, self.turn)
+            .expect("Invalid move");
+        self.turn.other();
+    }
+}
+
+impl monte_carlo_tree_search::State<Action> for State {
+    fn simulate(
+        &self,
+        playouts: monte_carlo_tree_search::Int,
+        max_depth_per_playout: monte_carlo_tree_search::Int,
+        rng: &mut monte_carlo_tree_search::Rng,
+    ) -> Vec<monte_carlo_tree_search::SimulationResult> {
+        monte_carlo_tree_search::run_playouts(
+            self,
+            &PLAYOUT_POLICY,
+            outcome,
+            playouts,
+            max_depth_per_playout,
+            rng,
+        )
+        .into_iter()
+        .map(|playout| playout.result)
+        .collect()
+    }
+
+    fn get_actions(&self) -> Vec<Action> {
+        tic_tac_toe_logic::get_legal_moves(&self.board)
+            .into_iter()
+            .map(Action)
+            .collect()
+    }
+
+    fn get_next_state(&self, action: &Action) -> Self {
+        let mut next_state = self.clone();
+        next_state.apply_move(action);
+        next_state
+    }
+
+    fn is_terminal(&self) -> bool {
+        tic_tac_toe_logic::is_terminal_position(&self.board)
+            != tic_tac_toe_logic::TerminalPosition::IsNotTerminal
+    }
+}
+
+/// Playouts take an immediately winning move when there is one and are random otherwise. This
+/// is the same playout policy as mcts-connect-four.
+const PLAYOUT_POLICY: monte_carlo_tree_search::EpsilonGreedy<fn(&State, &Action) -> f64> =
+    monte_carlo_tree_search::EpsilonGreedy {
+        epsilon: 0.0,
+        heuristic: winning_move,
+    };
+
+/// 1 if playing `action` wins the game for the player to move, else 0.
+fn winning_move(state: &State, action: &Action) -> f64 {
+    let mut board = state.board.clone();
+    board.place(&action.0, state.turn).unwrap();
+    if tic_tac_toe_logic::is_terminal_position(&board)
+        == tic_tac_toe_logic::TerminalPosition::IsTerminalWin(state.turn)
+    {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+fn outcome(state: &State) -> monte_carlo_tree_search::SimulationResult {
+    if tic_tac_toe_logic::is_terminal_position(&state.board)
+        == tic_tac_toe_logic::TerminalPosition::IsTerminalWin(state.who_am_i)
+    {
+        monte_carlo_tree_search::SimulationResult::Win
+    } else {
+        monte_carlo_tree_search::SimulationResult::NotWin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use monte_carlo_tree_search::shared_rng;
+
+    use super::*;
+
+    // X has two in a row on the top row and it is X's turn, so MCTS must complete the row.
+    #[test]
+    fn test_takes_winning_move() {
+        let mut state = State::new(Player::Player1, Player::Player1);
+        for (column, row) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+            state.apply_move(&Action(tic_tac_toe_logic::Move { column, row }));
+        }
+
+        let mut mcts = monte_carlo_tree_search::Mcts::new(
+            state,
+            monte_carlo_tree_search::MctsArgs {
+                iteration_limit: monte_carlo_tree_search::IterationLimitKind::Iterations(200),
+                max_depth_per_playout: 9,
+                rng: shared_rng(42),
+                reward_perspective: monte_carlo_tree_search::RewardPerspective::Alternating,
+                solver: monte_carlo_tree_search::Solver::Enabled,
+                ..monte_carlo_tree_search::MctsArgs::default()
+            },
+        );
+        mcts.run();
+
+        assert_eq!(
+            mcts.best_action(),
+            Some(Action(tic_tac_toe_logic::Move { column: 2, row: 0 }))
+        );
+    }
+}
@@ -37,6 +37,35 @@ pub enum ConnectFourError {
     /// Column is not yours. You can only pop from your own columns.
     #[error("column is not yours: {0}")]
     ColumnNotYours(usize),
+
+    /// Board notation could not be parsed.
+    #[error("invalid notation: {0}")]
+    InvalidNotation(String),
+
+    /// A piece has an empty cell below it, which cannot happen because pieces always fall to the
+    /// bottom of a column.
+    #[error("floating piece at column {column}, row {row}")]
+    FloatingPiece {
+        /// Column of the floating piece.
+        column: usize,
+
+        /// Row of the floating piece.
+        row: usize,
+    },
+
+    /// The number of pieces for each player is inconsistent with whose turn it is.
+    #[error("piece counts inconsistent with turn: player 1 has {player1}, player 2 has {player2}")]
+    PieceParity {
+        /// Number of pieces belonging to Player 1.
+        player1: usize,
+
+        /// Number of pieces belonging to Player 2.
+        player2: usize,
+    },
+
+    /// The position is already won or drawn, so there is nothing left to play.
+    #[error("position is already terminal")]
+    AlreadyTerminal,
 }
 
 /// Connect Four cell. Part of the board.
@@ -223,6 +252,110 @@ impl Board {
             Err(e) => Err(e),
         }
     }
+
+    /// Parse a board from notation. Rows are given top to bottom and separated by '/'. Each row
+    /// has one character per column: '.' for empty, '1' for Player 1, and '2' for Player 2. This
+    /// is the same layout that Display uses, without the row and column numbers.
+    ///
+    /// For example an empty 3x2 board is ".../..." and a board with a single Player 1 piece in the
+    /// bottom left corner is ".../1..".
+    pub fn from_notation(notation: &str) -> Result<Self, ConnectFourError> {
+        let rows: Vec<&str> = notation.trim().split('/').collect();
+        let width = rows[0].chars().count();
+        if width == 0 {
+            return Err(ConnectFourError::InvalidNotation(
+                "rows must not be empty".to_string(),
+            ));
+        }
+        let mut cells = Vec::with_capacity(width * rows.len());
+        for (row, row_notation) in rows.iter().enumerate() {
+            if row_notation.chars().count() != width {
+                return Err(ConnectFourError::InvalidNotation(format!(
+                    "row {} has {} columns, expected {}",
+                    row,
+                    row_notation.chars().count(),
+                    width
+                )));
+            }
+            for c in row_notation.chars() {
+                let cell = match c {
+                    '.' => Cell::Empty,
+                    '1' => Cell::Player(Player::Player1),
+                    '2' => Cell::Player(Player::Player2),
+                    _ => {
+                        return Err(ConnectFourError::InvalidNotation(format!(
+                            "unexpected character '{}' in row {}",
+                            c, row
+                        )))
+                    }
+                };
+                cells.push(cell);
+            }
+        }
+        Ok(Self {
+            cells,
+            width,
+            height: rows.len(),
+        })
+    }
+
+    /// Write the board in the notation accepted by from_notation.
+    pub fn to_notation(&self) -> String {
+        (0..self.height)
+            .map(|row| {
+                (0..self.width)
+                    .map(|col| match self.get(col, row) {
+                        Cell::Empty => '.',
+                        Cell::Player(Player::Player1) => '1',
+                        Cell::Player(Player::Player2) => '2',
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>()
+            .join("/")
+    }
+
+    /// Count the pieces on the board belonging to a player.
+    pub fn count_pieces(&self, player: Player) -> usize {
+        self.cells
+            .iter()
+            .filter(|&&cell| cell == Cell::Player(player))
+            .count()
+    }
+}
+
+/// Check that a position set up by hand could have been reached by playing, and that it still
+/// has moves left to play.
+///
+/// - No piece may have an empty cell below it.
+/// - Player 1 always moves first, so if it is Player 1's turn both players have the same number of
+///   pieces, and if it is Player 2's turn then Player 1 has exactly one more piece. Note that pops
+///   in the popout variant can break this, so this assumes the position was reached by inserts.
+/// - The position must not already be won or drawn.
+pub fn validate_position(board: &Board, turn: Player) -> Result<(), ConnectFourError> {
+    for col in 0..board.width {
+        for row in 0..board.height - 1 {
+            if board.get(col, row) != Cell::Empty && board.get(col, row + 1) == Cell::Empty {
+                return Err(ConnectFourError::FloatingPiece { column: col, row });
+            }
+        }
+    }
+
+    let player1 = board.count_pieces(Player::Player1);
+    let player2 = board.count_pieces(Player::Player2);
+    let parity_ok = match turn {
+        Player::Player1 => player1 == player2,
+        Player::Player2 => player1 == player2 + 1,
+    };
+    if !parity_ok {
+        return Err(ConnectFourError::PieceParity { player1, player2 });
+    }
+
+    if is_terminal_position(board) != TerminalPosition::IsNotTerminal {
+        return Err(ConnectFourError::AlreadyTerminal);
+    }
+
+    Ok(())
 }
 
 /// MoveType is either Insert or Pop.
@@ -582,6 +715,69 @@ mod tests {
         assert_eq!(Ok(0), board.can_insert(0));
     }
 
+    #[test]
+    fn test_notation_round_trip() {
+        let mut board = Board::new(7, 6);
+        board.insert(3, Player::Player1).expect("insert failed");
+        board.insert(3, Player::Player2).expect("insert failed");
+        board.insert(0, Player::Player1).expect("insert failed");
+        let notation = board.to_notation();
+        assert_eq!(notation, "......./......./......./......./...2.../1..1...");
+        assert_eq!(Board::from_notation(&notation), Ok(board));
+    }
+
+    #[test]
+    fn test_notation_rejects_ragged_rows_and_bad_characters() {
+        assert!(matches!(
+            Board::from_notation(".../.."),
+            Err(ConnectFourError::InvalidNotation(_))
+        ));
+        assert!(matches!(
+            Board::from_notation(".../.x."),
+            Err(ConnectFourError::InvalidNotation(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_position_accepts_reachable_position() {
+        let board = Board::from_notation("......./......./......./......./...2.../1..1...")
+            .expect("parse failed");
+        assert_eq!(validate_position(&board, Player::Player2), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_position_rejects_floating_piece() {
+        let board = Board::from_notation("......./......./......./...1.../......./...2...")
+            .expect("parse failed");
+        assert_eq!(
+            validate_position(&board, Player::Player1),
+            Err(ConnectFourError::FloatingPiece { column: 3, row: 3 })
+        );
+    }
+
+    #[test]
+    fn test_validate_position_rejects_bad_parity() {
+        let board = Board::from_notation("......./......./......./......./......./1..1...")
+            .expect("parse failed");
+        assert_eq!(
+            validate_position(&board, Player::Player2),
+            Err(ConnectFourError::PieceParity {
+                player1: 2,
+                player2: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate_position_rejects_terminal_position() {
+        let board = Board::from_notation("......./......./......./......./2222.../1111...")
+            .expect("parse failed");
+        assert_eq!(
+            validate_position(&board, Player::Player1),
+            Err(ConnectFourError::AlreadyTerminal)
+        );
+    }
+
     fn vec_of_player() -> impl Strategy<Value = Vec<Player>> {
         prop::collection::vec(
             prop_oneof![Just(Player::Player1), Just(Player::Player2)],
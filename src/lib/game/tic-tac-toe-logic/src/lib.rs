@@ -0,0 +1,285 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+#![warn(missing_docs)]
+
+//! Tic-Tac-Toe game logic.
+//!
+//! This is a library for the Tic-Tac-Toe game used as the example game in Chapter 5 "Adversarial
+//! Search and Games", see Figure 5.1 page 148. It is intended to be used by an algorithm to
+//! simulate or play the game.
+
+use serde::{Deserialize, Serialize};
+
+/// Width and height of the board.
+pub const SIZE: usize = 3;
+
+/// Tic-Tac-Toe error.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TicTacToeError {
+    /// Cell is outside the board.
+    #[error("cell is out of bounds: column {0}, row {1}")]
+    OutOfBounds(usize, usize),
+
+    /// Cell already has a piece in it.
+    #[error("cell is occupied: column {0}, row {1}")]
+    CellOccupied(usize, usize),
+}
+
+/// Tic-Tac-Toe player. Player 1 plays X and moves first, Player 2 plays O.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Player {
+    /// Player 1, X.
+    Player1,
+
+    /// Player 2, O.
+    Player2,
+}
+
+impl Player {
+    /// Get the other player.
+    pub fn other(&mut self) {
+        match self {
+            Player::Player1 => *self = Player::Player2,
+            Player::Player2 => *self = Player::Player1,
+        }
+    }
+}
+
+impl std::fmt::Display for Player {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Player::Player1 => write!(f, "Player 1"),
+            Player::Player2 => write!(f, "Player 2"),
+        }
+    }
+}
+
+/// Tic-Tac-Toe cell. Part of the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Cell {
+    /// Empty cell.
+    Empty,
+
+    /// Cell for a piece belonging to a player.
+    Player(Player),
+}
+
+/// Tic-Tac-Toe move, which places a piece in a cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Move {
+    /// The column, starting at 0 on the left.
+    pub column: usize,
+
+    /// The row, starting at 0 at the top.
+    pub row: usize,
+}
+
+impl std::fmt::Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.column, self.row)
+    }
+}
+
+/// Tic-Tac-Toe board. This only contains the cells, and not the players or the turn.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Board {
+    /// Cells, either empty or containing a player, in row-major order.
+    pub cells: [Cell; SIZE * SIZE],
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// print out cells, X for Player 1 and O for Player 2.
+impl std::fmt::Display for Board {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in 0..SIZE {
+            for col in 0..SIZE {
+                let c = match self.get(col, row) {
+                    Cell::Empty => '.',
+                    Cell::Player(Player::Player1) => 'X',
+                    Cell::Player(Player::Player2) => 'O',
+                };
+                write!(f, "{}", c)?;
+                if col < SIZE - 1 {
+                    write!(f, " ")?;
+                }
+            }
+            if row < SIZE - 1 {
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Board {
+    /// Create a new empty board.
+    pub fn new() -> Self {
+        Self {
+            cells: [Cell::Empty; SIZE * SIZE],
+        }
+    }
+
+    /// Get a cell.
+    pub fn get(&self, col: usize, row: usize) -> Cell {
+        self.cells[row * SIZE + col]
+    }
+
+    /// Check if you can place a piece in a cell.
+    pub fn can_place(&self, m: &Move) -> Result<(), TicTacToeError> {
+        if m.column >= SIZE || m.row >= SIZE {
+            return Err(TicTacToeError::OutOfBounds(m.column, m.row));
+        }
+        if self.get(m.column, m.row) != Cell::Empty {
+            return Err(TicTacToeError::CellOccupied(m.column, m.row));
+        }
+        Ok(())
+    }
+
+    /// Place a piece for a player in a cell.
+    pub fn place(&mut self, m: &Move, player: Player) -> Result<(), TicTacToeError> {
+        self.can_place(m)?;
+        self.cells[m.row * SIZE + m.column] = Cell::Player(player);
+        Ok(())
+    }
+}
+
+/// Get all the legal moves, which are all the empty cells. Returns no moves if the game is over.
+pub fn get_legal_moves(board: &Board) -> Vec<Move> {
+    if is_terminal_position(board) != TerminalPosition::IsNotTerminal {
+        return vec![];
+    }
+    (0..SIZE)
+        .flat_map(|row| (0..SIZE).map(move |column| Move { column, row }))
+        .filter(|m| board.can_place(m).is_ok())
+        .collect()
+}
+
+/// Whether a position is terminal, and if so, who won.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TerminalPosition {
+    /// The game is terminal, and some Player has won.
+    IsTerminalWin(Player),
+
+    /// The game is terminal, and it is a draw.
+    IsTerminalDraw,
+
+    /// The game is not terminal.
+    IsNotTerminal,
+}
+
+/// All eight lines of three cells, as (column, row) pairs.
+const LINES: [[(usize, usize); 3]; 8] = [
+    [(0, 0), (1, 0), (2, 0)],
+    [(0, 1), (1, 1), (2, 1)],
+    [(0, 2), (1, 2), (2, 2)],
+    [(0, 0), (0, 1), (0, 2)],
+    [(1, 0), (1, 1), (1, 2)],
+    [(2, 0), (2, 1), (2, 2)],
+    [(0, 0), (1, 1), (2, 2)],
+    [(2, 0), (1, 1), (0, 2)],
+];
+
+/// Check if a position is terminal.
+pub fn is_terminal_position(board: &Board) -> TerminalPosition {
+    for line in LINES.iter() {
+        let [a, b, c] = line.map(|(col, row)| board.get(col, row));
+        if let Cell::Player(player) = a {
+            if a == b && b == c {
+                return TerminalPosition::IsTerminalWin(player);
+            }
+        }
+    }
+    if board.cells.iter().all(|&cell| cell != Cell::Empty) {
+        TerminalPosition::IsTerminalDraw
+    } else {
+        TerminalPosition::IsNotTerminal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(moves: &[(usize, usize)]) -> Board {
+        let mut board = Board::new();
+        let mut player = Player::Player1;
+        for &(column, row) in moves {
+            board
+                .place(&Move { column, row }, player)
+                .expect("place failed");
+            player.other();
+        }
+        board
+    }
+
+    #[test]
+    fn test_empty_board_has_nine_moves_and_is_not_terminal() {
+        let board = Board::new();
+        assert_eq!(get_legal_moves(&board).len(), 9);
+        assert_eq!(
+            is_terminal_position(&board),
+            TerminalPosition::IsNotTerminal
+        );
+    }
+
+    #[test]
+    fn test_cannot_place_on_occupied_or_out_of_bounds_cell() {
+        let board = play(&[(1, 1)]);
+        assert_eq!(
+            board.can_place(&Move { column: 1, row: 1 }),
+            Err(TicTacToeError::CellOccupied(1, 1))
+        );
+        assert_eq!(
+            board.can_place(&Move { column: 3, row: 0 }),
+            Err(TicTacToeError::OutOfBounds(3, 0))
+        );
+    }
+
+    #[test]
+    fn test_diagonal_win_has_no_legal_moves() {
+        let board = play(&[(0, 0), (1, 0), (1, 1), (2, 0), (2, 2)]);
+        assert_eq!(
+            is_terminal_position(&board),
+            TerminalPosition::IsTerminalWin(Player::Player1)
+        );
+        assert!(get_legal_moves(&board).is_empty());
+    }
+
+    #[test]
+    fn test_full_board_without_line_is_draw() {
+        let board = play(&[
+            (0, 0),
+            (1, 1),
+            (2, 2),
+            (1, 0),
+            (1, 2),
+            (0, 2),
+            (2, 0),
+            (2, 1),
+            (0, 1),
+        ]);
+        assert_eq!(
+            is_terminal_position(&board),
+            TerminalPosition::IsTerminalDraw
+        );
+    }
+}
@@ -0,0 +1,74 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+//! Compare the Reingold–Tilford and Buchheim layouts on large random trees.
+//!
+//! Run with `cargo bench -p graphics --bench layout`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use graphics::{BuchheimLayout, Layout, ReingoldTilfordLayout, Size, Tree};
+use rand::Rng;
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+
+/// A random tree of `nodes` nodes where each node is a child of one of the last `window`
+/// nodes added. A small window gives deep, narrow trees and a large one bushy, shallow trees.
+fn random_tree(nodes: usize, window: usize, seed: u64) -> Tree<usize> {
+    let mut rng = Pcg64::seed_from_u64(seed);
+    let random_size = |rng: &mut Pcg64| Size::new(rng.gen_range(0.5..3.0), 1.0);
+    let mut tree = Tree::new(0, random_size(&mut rng));
+    let mut ids = vec![tree.root_id()];
+    for i in 1..nodes {
+        let parent = ids[rng.gen_range(ids.len().saturating_sub(window)..ids.len())];
+        let size = random_size(&mut rng);
+        ids.push(tree.add_child(parent, i, size));
+    }
+    tree
+}
+
+fn bench_layouts(c: &mut Criterion) {
+    let reingold_tilford = ReingoldTilfordLayout::new(1.0, 2.0, 1.0);
+    let buchheim = BuchheimLayout::new(1.0, 2.0, 1.0);
+    for (shape, window) in [("deep", 8), ("bushy", usize::MAX)] {
+        let mut group = c.benchmark_group(format!("layout_{shape}"));
+        group.sample_size(10);
+        for nodes in [1_000, 4_000, 16_000] {
+            let tree = random_tree(nodes, window, 42);
+            group.bench_with_input(
+                BenchmarkId::new("reingold_tilford", nodes),
+                &tree,
+                |b, tree| {
+                    b.iter_batched_ref(
+                        || tree.clone(),
+                        |tree| reingold_tilford.layout(tree),
+                        criterion::BatchSize::LargeInput,
+                    )
+                },
+            );
+            group.bench_with_input(BenchmarkId::new("buchheim", nodes), &tree, |b, tree| {
+                b.iter_batched_ref(
+                    || tree.clone(),
+                    |tree| buchheim.layout(tree),
+                    criterion::BatchSize::LargeInput,
+                )
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_layouts);
+criterion_main!(benches);
@@ -0,0 +1,463 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+//! Edge geometry for laid-out trees: where to draw the connector from each parent to each of
+//! its children, and where to put a label on it.
+//!
+//! Edges leave a parent from the side facing its children and enter a child from the side
+//! facing its parent, so they depend on the [`Orientation`] the tree was laid out with. In
+//! radial layouts edges run along the line between the two nodes' centers, clipped to the
+//! nodes, whatever the [`EdgeStyle`].
+
+use serde::{Deserialize, Serialize};
+
+use crate::layout::{Data, NodeId, Orientation, Position, Size, Tree};
+
+/// A point in layout coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Self {
+        Point { x, y }
+    }
+
+    fn lerp(&self, other: &Point, t: f64) -> Point {
+        Point::new(
+            self.x + (other.x - self.x) * t,
+            self.y + (other.y - self.y) * t,
+        )
+    }
+}
+
+/// How to draw the connector between a parent and a child.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EdgeStyle {
+    /// A single line segment.
+    #[default]
+    Straight,
+
+    /// Horizontal and vertical segments, bending halfway between a parent and its nearest
+    /// child, so that all of a parent's edges share their first bend.
+    Orthogonal,
+
+    /// A cubic Bézier curve that leaves the parent and enters the child along the direction
+    /// the tree grows.
+    Bezier,
+}
+
+/// The geometry of one edge, from parent to child.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EdgePath {
+    Line {
+        from: Point,
+        to: Point,
+    },
+    Polyline {
+        points: Vec<Point>,
+    },
+    CubicBezier {
+        from: Point,
+        control1: Point,
+        control2: Point,
+        to: Point,
+    },
+}
+
+impl EdgePath {
+    /// Where the edge leaves the parent.
+    pub fn start(&self) -> Point {
+        match self {
+            EdgePath::Line { from, .. } | EdgePath::CubicBezier { from, .. } => *from,
+            EdgePath::Polyline { points } => points[0],
+        }
+    }
+
+    /// Where the edge enters the child.
+    pub fn end(&self) -> Point {
+        match self {
+            EdgePath::Line { to, .. } | EdgePath::CubicBezier { to, .. } => *to,
+            EdgePath::Polyline { points } => points[points.len() - 1],
+        }
+    }
+
+    /// A point for a label: the middle of a line or curve, or the middle of the last segment
+    /// of a polyline, which is the only part not shared with the child's siblings.
+    pub fn label_anchor(&self) -> Point {
+        match self {
+            EdgePath::Line { from, to } => from.lerp(to, 0.5),
+            EdgePath::Polyline { points } => {
+                let last = points.len() - 1;
+                points[last - 1].lerp(&points[last], 0.5)
+            }
+            EdgePath::CubicBezier {
+                from,
+                control1,
+                control2,
+                to,
+            } => Point::new(
+                (from.x + 3.0 * control1.x + 3.0 * control2.x + to.x) / 8.0,
+                (from.y + 3.0 * control1.y + 3.0 * control2.y + to.y) / 8.0,
+            ),
+        }
+    }
+}
+
+/// A connector from a parent to one of its children.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Edge {
+    pub parent: NodeId,
+    pub child: NodeId,
+    pub path: EdgePath,
+    /// Where to draw a label for the edge, see [`EdgePath::label_anchor`].
+    pub label_anchor: Point,
+    pub label: Option<String>,
+}
+
+/// The edges of a laid-out tree, parents in preorder and each parent's children left to right.
+pub fn route_edges<_Data: Data>(
+    tree: &Tree<_Data>,
+    orientation: Orientation,
+    style: EdgeStyle,
+) -> Vec<Edge> {
+    route_labelled_edges(tree, orientation, style, |_| None)
+}
+
+/// Like [`route_edges`], with each edge labelled by `label` applied to the child's data, e.g.
+/// the action that leads to the child.
+pub fn route_labelled_edges<_Data, _Label>(
+    tree: &Tree<_Data>,
+    orientation: Orientation,
+    style: EdgeStyle,
+    label: _Label,
+) -> Vec<Edge>
+where
+    _Data: Data,
+    _Label: Fn(&_Data) -> Option<String>,
+{
+    let mut edges = vec![];
+    let mut stack = vec![tree.root_id()];
+    while let Some(parent) = stack.pop() {
+        let children = tree.children(parent);
+        if children.is_empty() {
+            continue;
+        }
+        let parent_box = NodeBox::of(tree, parent);
+        let child_boxes: Vec<NodeBox> = children
+            .iter()
+            .map(|&child| NodeBox::of(tree, child))
+            .collect();
+        let bend = bend(orientation, &parent_box, &child_boxes);
+        for (&child, child_box) in children.iter().zip(&child_boxes) {
+            let path = path(orientation, style, &parent_box, child_box, bend);
+            edges.push(Edge {
+                parent,
+                child,
+                label_anchor: path.label_anchor(),
+                path,
+                label: tree.data(child).as_ref().and_then(&label),
+            });
+        }
+        stack.extend(children.into_iter().rev());
+    }
+    edges
+}
+
+/// A node's extent.
+struct NodeBox {
+    position: Position,
+    size: Size,
+}
+
+impl NodeBox {
+    fn of<_Data: Data>(tree: &Tree<_Data>, id: NodeId) -> Self {
+        NodeBox {
+            position: tree.position(id).unwrap(),
+            size: tree.size(id).unwrap(),
+        }
+    }
+
+    fn center(&self) -> Point {
+        Point::new(
+            self.position.x + self.size.width / 2.0,
+            self.position.y + self.size.height / 2.0,
+        )
+    }
+
+    fn top(&self) -> Point {
+        Point::new(self.center().x, self.position.y)
+    }
+
+    fn bottom(&self) -> Point {
+        Point::new(self.center().x, self.position.y + self.size.height)
+    }
+
+    fn left(&self) -> Point {
+        Point::new(self.position.x, self.center().y)
+    }
+
+    fn right(&self) -> Point {
+        Point::new(self.position.x + self.size.width, self.center().y)
+    }
+
+    /// The fraction of the way along `direction`, from the center, at which a ray leaves the
+    /// box.
+    fn exit(&self, direction: Point) -> f64 {
+        let along = |half: f64, d: f64| {
+            if d == 0.0 {
+                f64::INFINITY
+            } else {
+                half / d.abs()
+            }
+        };
+        along(self.size.width / 2.0, direction.x).min(along(self.size.height / 2.0, direction.y))
+    }
+}
+
+/// The coordinate, along the direction the tree grows, halfway between the parent and its
+/// nearest child. Orthogonal and Bézier edges turn there.
+fn bend(orientation: Orientation, parent: &NodeBox, children: &[NodeBox]) -> f64 {
+    let nearest = |coordinate: fn(&NodeBox) -> f64, pick: fn(f64, f64) -> f64, start: f64| {
+        children.iter().map(coordinate).fold(start, pick)
+    };
+    match orientation {
+        Orientation::TopDown => {
+            let top = nearest(|child| child.top().y, f64::min, f64::INFINITY);
+            (parent.bottom().y + top) / 2.0
+        }
+        Orientation::BottomUp => {
+            let bottom = nearest(|child| child.bottom().y, f64::max, f64::NEG_INFINITY);
+            (parent.top().y + bottom) / 2.0
+        }
+        Orientation::LeftRight => {
+            let left = nearest(|child| child.left().x, f64::min, f64::INFINITY);
+            (parent.right().x + left) / 2.0
+        }
+        Orientation::Radial => 0.0,
+    }
+}
+
+fn path(
+    orientation: Orientation,
+    style: EdgeStyle,
+    parent: &NodeBox,
+    child: &NodeBox,
+    bend: f64,
+) -> EdgePath {
+    let (from, to) = match orientation {
+        Orientation::TopDown => (parent.bottom(), child.top()),
+        Orientation::BottomUp => (parent.top(), child.bottom()),
+        Orientation::LeftRight => (parent.right(), child.left()),
+        Orientation::Radial => {
+            let (start, end) = (parent.center(), child.center());
+            let direction = Point::new(end.x - start.x, end.y - start.y);
+            let from = start.lerp(&end, parent.exit(direction));
+            let to = end.lerp(&start, child.exit(direction));
+            return EdgePath::Line { from, to };
+        }
+    };
+    // The points where the edge crosses the bend, above or beside each end.
+    let (from_bend, to_bend) = if orientation == Orientation::LeftRight {
+        (Point::new(bend, from.y), Point::new(bend, to.y))
+    } else {
+        (Point::new(from.x, bend), Point::new(to.x, bend))
+    };
+    match style {
+        EdgeStyle::Straight => EdgePath::Line { from, to },
+        EdgeStyle::Orthogonal => EdgePath::Polyline {
+            points: vec![from, from_bend, to_bend, to],
+        },
+        EdgeStyle::Bezier => EdgePath::CubicBezier {
+            from,
+            control1: from_bend,
+            control2: to_bend,
+            to,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{Layout, ReingoldTilfordLayout};
+
+    /// A wide root over two unit children, at (0.5, 0), (0, 2) and (2, 2) top-down.
+    fn laid_out_tree(orientation: Orientation) -> (Tree<&'static str>, NodeId, NodeId, NodeId) {
+        let mut tree = Tree::new("root", Size::new(2.0, 1.0));
+        let root = tree.root_id();
+        let left = tree.add_child(root, "left", Size::new(1.0, 1.0));
+        let right = tree.add_child(root, "right", Size::new(1.0, 1.0));
+        ReingoldTilfordLayout::new(1.0, 2.0, 1.0)
+            .with_orientation(orientation)
+            .layout(&mut tree);
+        (tree, root, left, right)
+    }
+
+    #[test]
+    fn test_straight_edges() {
+        let (tree, root, left, right) = laid_out_tree(Orientation::TopDown);
+        let edges = route_edges(&tree, Orientation::TopDown, EdgeStyle::Straight);
+        assert_eq!(edges.len(), 2);
+        assert_eq!((edges[0].parent, edges[0].child), (root, left));
+        assert_eq!((edges[1].parent, edges[1].child), (root, right));
+        assert_eq!(
+            edges[0].path,
+            EdgePath::Line {
+                from: Point::new(1.5, 1.0),
+                to: Point::new(0.5, 2.0),
+            }
+        );
+        assert_eq!(edges[0].label_anchor, Point::new(1.0, 1.5));
+        assert_eq!(edges[0].label, None);
+    }
+
+    #[test]
+    fn test_orthogonal_edges_share_the_bend() {
+        let (tree, ..) = laid_out_tree(Orientation::TopDown);
+        let edges = route_edges(&tree, Orientation::TopDown, EdgeStyle::Orthogonal);
+        assert_eq!(
+            edges[0].path,
+            EdgePath::Polyline {
+                points: vec![
+                    Point::new(1.5, 1.0),
+                    Point::new(1.5, 1.5),
+                    Point::new(0.5, 1.5),
+                    Point::new(0.5, 2.0),
+                ],
+            }
+        );
+        assert_eq!(edges[0].label_anchor, Point::new(0.5, 1.75));
+        let EdgePath::Polyline { points } = &edges[1].path else {
+            panic!("expected a polyline");
+        };
+        assert_eq!(points[1], Point::new(1.5, 1.5));
+        assert_eq!(points[3], Point::new(2.5, 2.0));
+    }
+
+    #[test]
+    fn test_bezier_edges() {
+        let (tree, ..) = laid_out_tree(Orientation::TopDown);
+        let edges = route_edges(&tree, Orientation::TopDown, EdgeStyle::Bezier);
+        assert_eq!(
+            edges[0].path,
+            EdgePath::CubicBezier {
+                from: Point::new(1.5, 1.0),
+                control1: Point::new(1.5, 1.5),
+                control2: Point::new(0.5, 1.5),
+                to: Point::new(0.5, 2.0),
+            }
+        );
+        // The curve's midpoint.
+        assert_eq!(edges[0].label_anchor, Point::new(1.0, 1.5));
+    }
+
+    // Edges attach to the sides of the nodes that face each other.
+    #[test]
+    fn test_edges_follow_orientation() {
+        for orientation in [Orientation::BottomUp, Orientation::LeftRight] {
+            let (tree, root, left, _) = laid_out_tree(orientation);
+            let parent = NodeBox::of(&tree, root);
+            let child = NodeBox::of(&tree, left);
+            for style in [
+                EdgeStyle::Straight,
+                EdgeStyle::Orthogonal,
+                EdgeStyle::Bezier,
+            ] {
+                let path = &route_edges(&tree, orientation, style)[0].path;
+                let expected = match orientation {
+                    Orientation::BottomUp => (parent.top(), child.bottom()),
+                    _ => (parent.right(), child.left()),
+                };
+                assert_eq!((path.start(), path.end()), expected);
+            }
+        }
+    }
+
+    // Radial edges run between the centers, starting and ending on the nodes' borders.
+    #[test]
+    fn test_radial_edges() {
+        let (tree, root, left, _) = laid_out_tree(Orientation::Radial);
+        let parent = NodeBox::of(&tree, root);
+        let child = NodeBox::of(&tree, left);
+        let edge = &route_edges(&tree, Orientation::Radial, EdgeStyle::Bezier)[0];
+        let EdgePath::Line { from, to } = edge.path else {
+            panic!("expected a line");
+        };
+
+        let on_border = |point: Point, node: &NodeBox| {
+            let Position { x, y, .. } = node.position;
+            let Size { width, height } = node.size;
+            let inside = point.x >= x - 1e-9
+                && point.x <= x + width + 1e-9
+                && point.y >= y - 1e-9
+                && point.y <= y + height + 1e-9;
+            let on_edge = [x, x + width]
+                .iter()
+                .any(|edge| (point.x - edge).abs() < 1e-9)
+                || [y, y + height]
+                    .iter()
+                    .any(|edge| (point.y - edge).abs() < 1e-9);
+            inside && on_edge
+        };
+        assert!(on_border(from, &parent));
+        assert!(on_border(to, &child));
+
+        let (start, end) = (parent.center(), child.center());
+        let cross = |point: Point| {
+            (end.x - start.x) * (point.y - start.y) - (end.y - start.y) * (point.x - start.x)
+        };
+        assert!(cross(from).abs() < 1e-9 && cross(to).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_labelled_edges() {
+        let (tree, ..) = laid_out_tree(Orientation::TopDown);
+        let edges =
+            route_labelled_edges(&tree, Orientation::TopDown, EdgeStyle::Straight, |data| {
+                (*data != "right").then(|| format!("to {}", data))
+            });
+        assert_eq!(edges[0].label.as_deref(), Some("to left"));
+        assert_eq!(edges[1].label, None);
+    }
+
+    #[test]
+    fn test_edges_are_in_preorder() {
+        let unit = Size::new(1.0, 1.0);
+        let mut tree = Tree::new("root", unit);
+        let root = tree.root_id();
+        let a = tree.add_child(root, "a", unit);
+        let b = tree.add_child(root, "b", unit);
+        let a1 = tree.add_child(a, "a1", unit);
+        let b1 = tree.add_child(b, "b1", unit);
+        ReingoldTilfordLayout::new(1.0, 1.0, 1.0).layout(&mut tree);
+        let pairs: Vec<(NodeId, NodeId)> =
+            route_edges(&tree, Orientation::TopDown, EdgeStyle::Straight)
+                .into_iter()
+                .map(|edge| (edge.parent, edge.child))
+                .collect();
+        assert_eq!(pairs, vec![(root, a), (root, b), (a, a1), (b, b1)]);
+        assert!(route_edges(
+            &Tree::new("alone", unit),
+            Orientation::TopDown,
+            EdgeStyle::Straight
+        )
+        .is_empty());
+    }
+}
@@ -14,14 +14,49 @@
  * with this program. If not, see <https://www.gnu.org/licenses/>
  */
 
-use std::cell::RefCell;
+//! Tree layout: give every node of a tree an (x, y) position so that the tree can be drawn
+//! tidily, parents centered over their children and no two subtrees overlapping.
+//!
+//! Build a [`Tree`] from any data with [`Tree::new`] and [`Tree::add_child`], run a [`Layout`]
+//! such as [`ReingoldTilfordLayout`] on it, then read positions back with [`Tree::position`] or
+//! take a snapshot of the whole tree with [`DebugTree`].
+//!
+//! After [`ReingoldTilfordLayout::layout`] with the default [`Orientation::TopDown`], with each
+//! node's extent given by its position (its top-left corner) and its [`Size`]:
+//!
+//! - Nodes at the same depth share a row as tall as the tallest of them, and are centered
+//!   vertically within it. The root's row starts at y = 0, and rows are `level_separation`
+//!   apart.
+//! - x is at least 0, and the leftmost node's left edge is at x = 0.
+//! - Children keep the order in which they were added, left to right.
+//! - Every parent's center is over the midpoint of its leftmost and rightmost children's
+//!   centers.
+//! - Adjacent nodes at the same depth have a gap of at least the smaller of
+//!   `sibling_separation` and `tree_distance` between their edges; siblings' subtrees are at
+//!   least `tree_distance` apart below the siblings.
+//!
+//! Other orientations are transforms of this layout, see [`Orientation`].
+
+use serde::{Deserialize, Serialize};
+use slotmap::{new_key_type, SlotMap};
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
-use std::rc::{Rc, Weak};
 
-#[derive(Debug, Clone, Copy)]
-struct Position {
-    x: f64,
-    y: f64,
+mod buchheim;
+mod incremental;
+mod orientation;
+
+pub use buchheim::BuchheimLayout;
+pub use incremental::IncrementalLayout;
+pub use orientation::Orientation;
+
+/// Position of a node: its top-left corner. `modifier` is working state of the layout algorithm,
+/// the offset still to be applied to the node's descendants; a finished layout leaves it at 0.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Position {
+    pub x: f64,
+    pub y: f64,
+    #[serde(skip)]
     modifier: f64,
 }
 
@@ -35,340 +70,584 @@ impl Default for Position {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Size {
-    width: f64,
-    height: f64,
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Size {
+    pub width: f64,
+    pub height: f64,
 }
 
+impl Size {
+    pub fn new(width: f64, height: f64) -> Self {
+        Size { width, height }
+    }
+}
+
+/// The payload of a node: anything that can be cloned and printed.
 pub trait Data: Debug + Clone {}
 
+impl<T: Debug + Clone> Data for T {}
+
+/// Identifies a node. Ids are unique within the process, so nodes of different trees never
+/// share one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct NodeId(pub usize);
+
 static NODE_COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
 
-pub type NodeWeakRef<_Data> = Weak<RefCell<Node<_Data>>>;
-pub type NodeRef<_Data> = Rc<RefCell<Node<_Data>>>;
+new_key_type! {
+    /// Index of a node in its tree's arena.
+    struct NodeKey;
+}
 
-/// Node in a tree of layout objects. Each node has a parent (except the root) and children.
-///
-/// See: https://timidger.github.io/posts/designing-a-bi-mutable-directional-tree-safely-in-rust/
+/// Node in a tree of layout objects. Nodes live in their tree's arena and refer to their parent
+/// and children by key, so the layout passes can walk and update the tree without reference
+/// counting or interior mutability.
 #[derive(Debug, Clone)]
 struct Node<_Data>
 where
     _Data: Data,
 {
-    id: usize,
+    id: NodeId,
     data: _Data,
-    _parent: Option<NodeWeakRef<_Data>>,
-    _children: Vec<NodeRef<_Data>>,
+    parent: Option<NodeKey>,
+    children: Vec<NodeKey>,
+    /// Index of the node among its parent's children: 0 for the leftmost child and the root.
+    index: usize,
     position: Position,
     size: Size,
 }
 
-/// DebugNode is a kind of node that does not use references, it clones everything. This makes it
-/// easier to debug and serialize for debugging. There are no parent pointers in DebugNode because
-/// this allows us to avoid needing Box<_> in the children Vec.
-#[derive(Debug, Clone)]
-struct DebugNode<_Data>
+impl<_Data> Node<_Data>
 where
     _Data: Data,
 {
-    id: usize,
-    data: _Data,
-    children: Vec<DebugNode<_Data>>,
-    position: Position,
-    size: Size,
-}
+    fn new(data: _Data, size: Size, parent: Option<NodeKey>, index: usize) -> Self {
+        // Ordering is relaxed because we don't care about the order of the ids, just that they are
+        // unique.
+        let id = NodeId(NODE_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed));
 
-impl<_Data: Data> From<Node<_Data>> for DebugNode<_Data> {
-    fn from(node: Node<_Data>) -> Self {
-        DebugNode {
-            id: node.id,
-            data: node.data,
-            children: node
-                ._children
-                .into_iter()
-                .map(|x| x.borrow().clone().into())
-                .collect(),
-            position: node.position,
-            size: node.size,
+        Node {
+            id,
+            data,
+            parent,
+            children: Vec::new(),
+            index,
+            position: Position::default(),
+            size,
         }
     }
+
+    fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
 }
 
-/// add_child both adds the child to the parent's children and sets the parent of the child.
-///
-/// This cannot be a method on Node because it requires a fresh NodeRef<_Data> to
-/// the parent so that we can downgrade it to a Weak<RefCell<Node<_Data>>>.
-fn add_child<_Data: Data>(parent: NodeRef<_Data>, child: NodeRef<_Data>) {
-    parent.borrow_mut().add_child(child.clone());
-    child.borrow_mut().set_parent(parent);
+/// DebugNode is a kind of node that does not use references, it clones everything. This makes it
+/// easier to debug and serialize for debugging. There are no parent pointers in DebugNode because
+/// this allows us to avoid needing Box<_> in the children Vec.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebugNode<_Data>
+where
+    _Data: Data,
+{
+    pub id: usize,
+    pub data: _Data,
+    pub children: Vec<DebugNode<_Data>>,
+    pub position: Position,
+    pub size: Size,
 }
 
-impl<_Data> Node<_Data>
+/// Tree of layout objects. Nodes can be added but not removed; layouts change the nodes'
+/// positions.
+///
+/// Nodes are stored in an arena with index-based parent and child links.
+#[derive(Debug, Clone)]
+pub struct Tree<_Data>
 where
     _Data: Data,
 {
-    fn new(size: Size, data: _Data) -> Rc<RefCell<Self>> {
-        // Ordering is relaxed because we don't care about the order of the ids, just that they are
-        // unique.
-        let id = NODE_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    root: NodeKey,
+    nodes: SlotMap<NodeKey, Node<_Data>>,
+    keys: HashMap<NodeId, NodeKey>,
+}
 
-        Rc::new(RefCell::new(Node {
-            id,
-            data,
-            _parent: None,
-            _children: Vec::new(),
-            position: Position::default(),
-            size,
-        }))
+impl<_Data: Data> Tree<_Data> {
+    pub fn new(root_data: _Data, root_size: Size) -> Self {
+        let mut nodes = SlotMap::with_key();
+        let root = nodes.insert(Node::new(root_data, root_size, None, 0));
+        let keys = HashMap::from([(nodes[root].id, root)]);
+        Tree { root, nodes, keys }
     }
 
-    fn add_child(&mut self, child: NodeRef<_Data>) {
-        self._children.push(child);
+    fn node(&self, id: NodeId) -> Option<&Node<_Data>> {
+        self.keys.get(&id).map(|&key| &self.nodes[key])
     }
 
-    fn set_parent(&mut self, parent: NodeRef<_Data>) {
-        self._parent = Some(Rc::downgrade(&parent));
+    pub fn root_id(&self) -> NodeId {
+        self.nodes[self.root].id
     }
 
-    fn is_leaf(&self) -> bool {
-        self._children.is_empty()
+    /// Add a node as the new rightmost child of `parent`. Panics if `parent` is not in this
+    /// tree.
+    pub fn add_child(&mut self, parent: NodeId, data: _Data, size: Size) -> NodeId {
+        let parent = *self.keys.get(&parent).expect("parent is not in this tree");
+        let index = self.nodes[parent].children.len();
+        let child = self
+            .nodes
+            .insert(Node::new(data, size, Some(parent), index));
+        self.nodes[parent].children.push(child);
+        let id = self.nodes[child].id;
+        self.keys.insert(id, child);
+        id
     }
 
-    fn parent(&self) -> Option<NodeRef<_Data>> {
-        self._parent.as_ref().map(|x| x.upgrade().unwrap())
+    pub fn len(&self) -> usize {
+        self.nodes.len()
     }
 
-    fn is_leftmost(&self) -> bool {
-        match self.parent() {
-            Some(parent) => parent.borrow().leftmost_child().unwrap().borrow().id == self.id,
-            None => true,
-        }
+    /// Always false: a tree has at least its root.
+    pub fn is_empty(&self) -> bool {
+        false
     }
 
-    fn is_rightmost(&self) -> bool {
-        match self.parent() {
-            Some(parent) => parent.borrow().rightmost_child().unwrap().borrow().id == self.id,
-            None => true,
-        }
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.keys.contains_key(&id)
     }
 
-    fn children(&self) -> Vec<NodeRef<_Data>> {
-        self._children.to_vec()
+    /// A copy of the node's data.
+    pub fn data(&self, id: NodeId) -> Option<_Data> {
+        self.node(id).map(|node| node.data.clone())
     }
 
-    fn leftmost_child(&self) -> Option<NodeRef<_Data>> {
-        self._children.first().cloned()
+    /// The node's position from the last layout.
+    pub fn position(&self, id: NodeId) -> Option<Position> {
+        self.node(id).map(|node| node.position)
     }
 
-    fn rightmost_child(&self) -> Option<NodeRef<_Data>> {
-        self._children.last().cloned()
+    pub fn size(&self, id: NodeId) -> Option<Size> {
+        self.node(id).map(|node| node.size)
     }
 
-    fn is_leftmost_child(&self) -> bool {
-        match self.leftmost_child() {
-            Some(leftmost_child) => leftmost_child.borrow().id == self.id,
-            None => false,
-        }
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        let parent = self.node(id)?.parent?;
+        Some(self.nodes[parent].id)
     }
 
-    fn is_rightmost_child(&self) -> bool {
-        match self.rightmost_child() {
-            Some(rightmost_child) => rightmost_child.borrow().id == self.id,
-            None => false,
-        }
+    /// The node's children, left to right. Empty for a leaf or an unknown id.
+    pub fn children(&self, id: NodeId) -> Vec<NodeId> {
+        self.node(id).map_or(vec![], |node| {
+            node.children
+                .iter()
+                .map(|&child| self.nodes[child].id)
+                .collect()
+        })
     }
 
-    fn previous_sibling(&self) -> Option<NodeRef<_Data>> {
-        let parent = self.parent()?;
-        let children = parent.borrow().children();
-        let index = children
-            .iter()
-            .position(|x| x.borrow().id == self.id)
-            .unwrap();
-        if index == 0 {
-            None
-        } else {
-            Some(children[index - 1].clone())
-        }
+    fn previous_sibling(&self, key: NodeKey) -> Option<NodeKey> {
+        let node = &self.nodes[key];
+        let index = node.index.checked_sub(1)?;
+        Some(self.nodes[node.parent?].children[index])
     }
 
-    fn next_sibling(&self) -> Option<NodeRef<_Data>> {
-        let parent = self.parent()?;
-        let children = parent.borrow().children();
-        let index = children
-            .iter()
-            .position(|x| x.borrow().id == self.id)
-            .unwrap();
-        if index == children.len() - 1 {
-            None
-        } else {
-            Some(children[index + 1].clone())
-        }
+    fn next_sibling(&self, key: NodeKey) -> Option<NodeKey> {
+        let node = &self.nodes[key];
+        self.nodes[node.parent?]
+            .children
+            .get(node.index + 1)
+            .copied()
     }
 
-    fn leftmost_sibling(&self) -> Option<NodeRef<_Data>> {
-        let parent = self.parent()?;
-        let children = parent.borrow().children();
-        Some(children.first().unwrap().clone())
+    fn leftmost_sibling(&self, key: NodeKey) -> Option<NodeKey> {
+        let parent = self.nodes[key].parent?;
+        self.nodes[parent].children.first().copied()
     }
 
-    fn rightmost_sibling(&self) -> Option<NodeRef<_Data>> {
-        let parent = self.parent()?;
-        let children = parent.borrow().children();
-        Some(children.last().unwrap().clone())
+    fn leftmost_child(&self, key: NodeKey) -> Option<NodeKey> {
+        self.nodes[key].children.first().copied()
     }
-}
 
-impl<_Data> PartialEq for Node<_Data>
-where
-    _Data: Data,
-{
-    fn eq(&self, other: &Self) -> bool {
-        self.id == other.id
+    fn rightmost_child(&self, key: NodeKey) -> Option<NodeKey> {
+        self.nodes[key].children.last().copied()
     }
-}
 
-/// Tree of layout objects. The tree is immutable, but the layout objects themselves are
-/// mutable.
-#[derive(Debug, Clone)]
-struct Tree<_Data>
-where
-    _Data: Data,
-{
-    _root: NodeRef<_Data>,
-}
+    /// Every node after its ancestors, children left to right.
+    fn preorder(&self) -> Vec<NodeKey> {
+        let mut order = Vec::with_capacity(self.nodes.len());
+        let mut stack = vec![self.root];
+        while let Some(key) = stack.pop() {
+            order.push(key);
+            stack.extend(self.nodes[key].children.iter().rev());
+        }
+        order
+    }
 
-impl<_Data: Data> Tree<_Data> {
-    fn new(root_data: _Data, root_size: Size) -> Self {
-        let root = Node::new(root_size, root_data);
-        Tree { _root: root }
+    /// Every node after its descendants and after the subtrees of its left siblings. This is the
+    /// reverse of a preorder walk that visits children right to left.
+    fn postorder(&self) -> Vec<NodeKey> {
+        let mut order = Vec::with_capacity(self.nodes.len());
+        let mut stack = vec![self.root];
+        while let Some(key) = stack.pop() {
+            order.push(key);
+            stack.extend(self.nodes[key].children.iter());
+        }
+        order.reverse();
+        order
     }
 
-    fn root(&self) -> NodeRef<_Data> {
-        self._root.clone()
+    fn debug_node(&self, key: NodeKey) -> DebugNode<_Data> {
+        let node = &self.nodes[key];
+        DebugNode {
+            id: node.id.0,
+            data: node.data.clone(),
+            children: node
+                .children
+                .iter()
+                .map(|&child| self.debug_node(child))
+                .collect(),
+            position: node.position,
+            size: node.size,
+        }
     }
 }
 
-#[derive(Debug, Clone)]
-struct DebugTree<_Data: Data> {
-    root: DebugNode<_Data>,
+/// A snapshot of a tree that owns copies of all its nodes, for inspecting or serializing the
+/// result of a layout.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebugTree<_Data: Data> {
+    pub root: DebugNode<_Data>,
 }
 
 impl<_Data: Data> From<Tree<_Data>> for DebugTree<_Data> {
     fn from(tree: Tree<_Data>) -> Self {
-        let root: Node<_Data> = tree.root().borrow().clone();
         DebugTree {
-            root: DebugNode::from(root),
+            root: tree.debug_node(tree.root),
         }
     }
 }
 
-trait Layout<_Data>
+/// An algorithm that positions the nodes of a tree.
+pub trait Layout<_Data>
 where
     _Data: Data,
 {
     fn layout(&self, tree: &mut Tree<_Data>);
     fn sibling_separation(&self) -> f64;
     fn tree_distance(&self) -> f64;
-    fn node_size(&self) -> i32;
+    fn level_separation(&self) -> f64;
+
+    /// The direction the laid-out tree grows in, which decides where its edges attach.
+    fn orientation(&self) -> Orientation {
+        Orientation::TopDown
+    }
 }
 
-struct ReingoldTilfordLayout<_Data>
-where
-    _Data: Data,
-{
+// initialize x to -1, y to depth, and mod to 0 for each node. depth
+// is the depth of the node in the tree. The root node is at depth 0.
+//
+// Until place_rows, x is the center of the node rather than its left edge.
+fn initialize_nodes<_Data: Data>(tree: &mut Tree<_Data>) {
+    for key in tree.preorder() {
+        let depth = tree.nodes[key]
+            .parent
+            .map_or(0.0, |parent| tree.nodes[parent].position.y + 1.0);
+        tree.nodes[key].position = Position {
+            x: -1.0,
+            y: depth,
+            modifier: 0.0,
+        };
+    }
+}
+
+/// Turn each node's center x and depth into its top-left corner. Each depth is as tall as its
+/// tallest node, shorter nodes are centered vertically within it, and depths are
+/// `level_separation` apart.
+fn place_rows<_Data: Data>(tree: &mut Tree<_Data>, level_separation: f64) {
+    let mut level_heights: Vec<f64> = vec![];
+    for node in tree.nodes.values() {
+        let depth = node.position.y as usize;
+        if level_heights.len() <= depth {
+            level_heights.resize(depth + 1, 0.0);
+        }
+        level_heights[depth] = level_heights[depth].max(node.size.height);
+    }
+    let level_tops: Vec<f64> = level_heights
+        .iter()
+        .scan(0.0, |top, height| {
+            let level_top = *top;
+            *top += height + level_separation;
+            Some(level_top)
+        })
+        .collect();
+
+    for node in tree.nodes.values_mut() {
+        let depth = node.position.y as usize;
+        node.position.x -= node.size.width / 2.0;
+        node.position.y = level_tops[depth] + (level_heights[depth] - node.size.height) / 2.0;
+    }
+}
+
+/// The Reingold–Tilford tidy tree layout. Subtrees are laid out bottom-up, each placed as
+/// close as possible to its left sibling without overlapping it, and parents are centered
+/// over their children.
+///
+/// Gaps are measured between the edges of nodes, using each node's own size.
+///
+/// - `sibling_separation`: the gap between adjacent siblings.
+/// - `tree_distance`: the gap between neighboring subtrees, below their roots.
+/// - `level_separation`: the gap between the bottom of the tallest node at one depth and the top
+///   of the nodes at the next.
+///
+/// The tree grows downwards unless another [`Orientation`] is set with
+/// [`ReingoldTilfordLayout::with_orientation`]. Separations are along the tree's own axes: for
+/// [`Orientation::LeftRight`], siblings are `sibling_separation` apart vertically and depths
+/// are `level_separation` apart horizontally.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReingoldTilfordLayout {
     sibling_separation: f64,
     tree_distance: f64,
-    node_size: i32,
-    phantom_data: std::marker::PhantomData<_Data>,
+    level_separation: f64,
+    orientation: Orientation,
 }
 
-impl<_Data: Data> ReingoldTilfordLayout<_Data> {
-    fn new(sibling_separation: f64, tree_distance: f64, node_size: i32) -> Self {
+impl ReingoldTilfordLayout {
+    pub fn new(sibling_separation: f64, tree_distance: f64, level_separation: f64) -> Self {
         ReingoldTilfordLayout {
             sibling_separation,
             tree_distance,
-            node_size,
-            phantom_data: std::marker::PhantomData,
+            level_separation,
+            orientation: Orientation::default(),
         }
     }
 
-    // initialize x to -1, y to depth, and mod to 0 for each node. depth
-    // is the depth of the node in the tree. The root node is at depth 0.
-    fn initialize_nodes(&self, node: NodeRef<_Data>, depth: f64) {
-        let mut node = node.borrow_mut();
-        node.position.x = -1.0;
-        node.position.y = depth;
-        node.position.modifier = 0.0;
-        for child in node.children() {
-            self.initialize_nodes(child, depth + 1.0);
+    pub fn with_orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Distance between the centers of two adjacent nodes at the same depth that are `gap`
+    /// apart.
+    fn center_distance<_Data: Data>(
+        tree: &Tree<_Data>,
+        left: NodeKey,
+        right: NodeKey,
+        gap: f64,
+    ) -> f64 {
+        (tree.nodes[left].size.width + tree.nodes[right].size.width) / 2.0 + gap
+    }
+
+    fn calculate_initial_x<_Data: Data>(&self, tree: &mut Tree<_Data>) {
+        for key in tree.postorder() {
+            let previous_x = tree.previous_sibling(key).map(|previous| {
+                tree.nodes[previous].position.x
+                    + Self::center_distance(tree, previous, key, self.sibling_separation)
+            });
+
+            // If no children, this node goes at 0 if it is the first node in a set, otherwise
+            // just clear of its previous sibling.
+            if tree.nodes[key].is_leaf() {
+                tree.nodes[key].position.x = previous_x.unwrap_or(0.0);
+                continue;
+            }
+
+            // The children are centered under the node: its only child's x, or the midpoint of
+            // its leftmost and rightmost children.
+            let leftmost_x = tree.nodes[tree.leftmost_child(key).unwrap()].position.x;
+            let rightmost_x = tree.nodes[tree.rightmost_child(key).unwrap()].position.x;
+            let mid = (leftmost_x + rightmost_x) / 2.0;
+
+            match previous_x {
+                // if node is left most, set its x to the midpoint of its children
+                None => tree.nodes[key].position.x = mid,
+                // Otherwise, place it just clear of its previous sibling, and shift its children
+                // by the modifier so that they stay centered under it.
+                Some(previous_x) => {
+                    let node = &mut tree.nodes[key];
+                    node.position.x = previous_x;
+                    node.position.modifier = node.position.x - mid;
+                    self.check_for_conflicts(tree, key);
+                }
+            }
         }
     }
 
-    fn calculate_initial_x(&self, node: NodeRef<_Data>) {
-        for child in node.borrow().children() {
-            self.calculate_initial_x(child);
+    /// The extent at each depth of the subtree rooted at `key`, relative to the subtree's own
+    /// modifiers: `edge` gives a node's edge from its center and width, and `pick` reduces the
+    /// edges of the nodes at one depth.
+    fn contour<_Data: Data>(
+        &self,
+        tree: &Tree<_Data>,
+        key: NodeKey,
+        edge: fn(f64, f64) -> f64,
+        pick: fn(f64, f64) -> f64,
+    ) -> BTreeMap<usize, f64> {
+        let mut contour = BTreeMap::new();
+        let mut stack = vec![(key, 0.0)];
+        while let Some((key, modifier_sum)) = stack.pop() {
+            let node = &tree.nodes[key];
+            let x = edge(node.position.x + modifier_sum, node.size.width);
+            contour
+                .entry(node.position.y as usize)
+                .and_modify(|value| *value = pick(*value, x))
+                .or_insert(x);
+            let modifier_sum = modifier_sum + node.position.modifier;
+            stack.extend(node.children.iter().map(|&child| (child, modifier_sum)));
         }
+        contour
+    }
+
+    /// Leftmost left edge at each depth in the subtree rooted at `key`.
+    fn left_contour<_Data: Data>(&self, tree: &Tree<_Data>, key: NodeKey) -> BTreeMap<usize, f64> {
+        self.contour(tree, key, |x, width| x - width / 2.0, f64::min)
+    }
+
+    /// Rightmost right edge at each depth in the subtree rooted at `key`.
+    fn right_contour<_Data: Data>(&self, tree: &Tree<_Data>, key: NodeKey) -> BTreeMap<usize, f64> {
+        self.contour(tree, key, |x, width| x + width / 2.0, f64::max)
+    }
+
+    /// Push the subtree rooted at `key` right until, at every depth below the node, it is at
+    /// least `tree_distance` clear of the subtree of each sibling to its left. After each shift
+    /// the siblings between the two subtrees are spread out evenly, unless that would make
+    /// them collide.
+    fn check_for_conflicts<_Data: Data>(&self, tree: &mut Tree<_Data>, key: NodeKey) {
+        let depth = tree.nodes[key].position.y as usize;
+        let mut node_contour = self.left_contour(tree, key);
+
+        let mut sibling = tree.leftmost_sibling(key);
+        while let Some(current) = sibling {
+            if current == key {
+                break;
+            }
+            let sibling_contour = self.right_contour(tree, current);
 
-        let node_position = node.borrow().position;
-
-        // If no children
-        if node.borrow().is_leaf() {
-            // If this is the first node in a set, set its x to 0
-            if node.borrow().is_leftmost() {
-                node.borrow_mut().position.x = 0.0;
-            // Otherwise, set its x to the x of its previous sibling plus the sibling separation
-            } else {
-                let previous_sibling = node.borrow().previous_sibling().unwrap();
-                let previous_sibling = previous_sibling.borrow();
-                node.borrow_mut().position.x =
-                    previous_sibling.position.x + self.node_size as f64 + self.sibling_separation;
+            let mut shift: f64 = 0.0;
+            for (level, right) in sibling_contour.range(depth + 1..) {
+                if let Some(left) = node_contour.get(level) {
+                    shift = shift.max(self.tree_distance - (left - right));
+                }
+            }
+            if shift > 0.0 {
+                let node = &mut tree.nodes[key];
+                node.position.x += shift;
+                node.position.modifier += shift;
+                for value in node_contour.values_mut() {
+                    *value += shift;
+                }
+                self.center_nodes_between(tree, current, key);
             }
-        // If there is only one child
-        } else if node.borrow().children().len() == 1 {
-            let child = node.borrow().leftmost_child().unwrap();
-            let child = child.borrow();
-
-            // if this is the first node in a set, set its X value equal to its child's X value
-            if node.borrow().is_leftmost() {
-                node.borrow_mut().position.x = child.position.x;
-            } else {
-                // Otherwise, set its x to the x of its previous sibling plus the sibling separation
-                let previous_sibling = node.borrow().previous_sibling().unwrap();
-                let previous_sibling = previous_sibling.borrow();
-                node.borrow_mut().position.x =
-                    previous_sibling.position.x + self.node_size as f64 + self.sibling_separation;
-                node.borrow_mut().position.modifier = node_position.x - child.position.x;
+            sibling = tree.next_sibling(current);
+        }
+    }
+
+    /// Space the siblings strictly between `left` and `right` evenly between them, so that
+    /// shifting a subtree right doesn't leave the smaller subtrees before it bunched up on the
+    /// left. The move is undone if it would make any of the siblings' subtrees overlap.
+    fn center_nodes_between<_Data: Data>(
+        &self,
+        tree: &mut Tree<_Data>,
+        left: NodeKey,
+        right: NodeKey,
+    ) {
+        let parent = tree.nodes[left].parent.unwrap();
+        let (left_index, right_index) = (tree.nodes[left].index, tree.nodes[right].index);
+        let nodes_between = right_index - left_index - 1;
+        if nodes_between == 0 {
+            return;
+        }
+
+        let left_x = tree.nodes[left].position.x;
+        let distance_between_nodes =
+            (tree.nodes[right].position.x - left_x) / (nodes_between + 1) as f64;
+        let mut offsets = vec![];
+        for (count, index) in (left_index + 1..right_index).enumerate() {
+            let desired_x = left_x + distance_between_nodes * (count + 1) as f64;
+            let middle = tree.nodes[parent].children[index];
+            let middle = &mut tree.nodes[middle];
+            let offset = desired_x - middle.position.x;
+            middle.position.x += offset;
+            middle.position.modifier += offset;
+            offsets.push(offset);
+        }
+
+        let siblings = &tree.nodes[parent].children[left_index..=right_index];
+        if self.siblings_overlap(tree, siblings) {
+            for (index, offset) in (left_index + 1..right_index).zip(offsets) {
+                let middle = tree.nodes[parent].children[index];
+                let middle = &mut tree.nodes[middle];
+                middle.position.x -= offset;
+                middle.position.modifier -= offset;
             }
-        } else {
-            let leftmost_child = node.borrow().leftmost_child().unwrap();
-            let leftmost_child = leftmost_child.borrow();
-            let rightmost_child = node.borrow().rightmost_child().unwrap();
-            let rightmost_child = rightmost_child.borrow();
-            let mid = (leftmost_child.position.x + rightmost_child.position.x) / 2.0;
-
-            // if node is left most, set its x to the midpoint of its children
-            if node.borrow().is_leftmost() {
-                node.borrow_mut().position.x = mid;
-            } else {
-                // Otherwise, set its x to the x of its previous sibling plus the sibling separation
-                let previous_sibling = node.borrow().previous_sibling().unwrap();
-                let previous_sibling = previous_sibling.borrow();
-                node.borrow_mut().position.x =
-                    previous_sibling.position.x + self.node_size as f64 + self.sibling_separation;
-                node.borrow_mut().position.modifier = node_position.x - mid;
+        }
+    }
+
+    /// Whether any two of these siblings, or their subtrees, are closer than the layout allows.
+    fn siblings_overlap<_Data: Data>(&self, tree: &Tree<_Data>, siblings: &[NodeKey]) -> bool {
+        const EPSILON: f64 = 1e-9;
+        let contours: Vec<(BTreeMap<usize, f64>, BTreeMap<usize, f64>)> = siblings
+            .iter()
+            .map(|&sibling| {
+                (
+                    self.left_contour(tree, sibling),
+                    self.right_contour(tree, sibling),
+                )
+            })
+            .collect();
+        let depth = tree.nodes[siblings[0]].position.y as usize;
+        for (i, (_, right)) in contours.iter().enumerate() {
+            for (left, _) in &contours[i + 1..] {
+                for (level, right_x) in right {
+                    let Some(left_x) = left.get(level) else {
+                        continue;
+                    };
+                    let min_distance = if *level == depth {
+                        self.sibling_separation
+                    } else {
+                        self.tree_distance
+                    };
+                    if left_x - right_x < min_distance - EPSILON {
+                        return true;
+                    }
+                }
             }
         }
+        false
     }
 
-    fn check_for_conflicts(&self, _tree: &mut Tree<_Data>) {}
+    /// Shift the whole tree right if any node ended up with its left edge at a negative x.
+    fn check_all_children_on_screen<_Data: Data>(&self, tree: &mut Tree<_Data>) {
+        let contour = self.left_contour(tree, tree.root);
+        let min_x = contour.values().copied().fold(f64::INFINITY, f64::min);
+        if min_x < 0.0 {
+            let root = &mut tree.nodes[tree.root];
+            root.position.x -= min_x;
+            root.position.modifier -= min_x;
+        }
+    }
+
+    /// Second pass: add the sum of its ancestors' modifiers to each node's x, then place the
+    /// rows.
+    fn calculate_final_positions<_Data: Data>(&self, tree: &mut Tree<_Data>) {
+        let mut stack = vec![(tree.root, 0.0)];
+        while let Some((key, modifier_sum)) = stack.pop() {
+            let node = &mut tree.nodes[key];
+            node.position.x += modifier_sum;
+            let modifier_sum = modifier_sum + node.position.modifier;
+            node.position.modifier = 0.0;
+            stack.extend(node.children.iter().map(|&child| (child, modifier_sum)));
+        }
+        place_rows(tree, self.level_separation);
+    }
 }
 
-impl<_Data: Data> Layout<_Data> for ReingoldTilfordLayout<_Data> {
+impl<_Data: Data> Layout<_Data> for ReingoldTilfordLayout {
     fn layout(&self, tree: &mut Tree<_Data>) {
-        self.initialize_nodes(tree.root(), 0.0 /*depth*/);
-        self.calculate_initial_x(tree.root());
+        orientation::lay_out(tree, self.orientation, self.sibling_separation, |tree| {
+            initialize_nodes(tree);
+            self.calculate_initial_x(tree);
+            self.check_all_children_on_screen(tree);
+            self.calculate_final_positions(tree);
+        });
     }
 
     fn sibling_separation(&self) -> f64 {
@@ -379,101 +658,43 @@ impl<_Data: Data> Layout<_Data> for ReingoldTilfordLayout<_Data> {
         self.tree_distance
     }
 
-    fn node_size(&self) -> i32 {
-        self.node_size
+    fn level_separation(&self) -> f64 {
+        self.level_separation
+    }
+
+    fn orientation(&self) -> Orientation {
+        self.orientation
     }
 }
 
 #[cfg(test)]
-mod tests {
+pub(super) mod tests {
     use super::*;
     use proptest::prelude::*;
+    use proptest::test_runner::TestCaseError;
     use std::collections::VecDeque;
 
     #[derive(Debug, Clone)]
-    struct TestNodeData {
+    pub(super) struct TestNodeData {
         name: String,
     }
 
-    impl Data for TestNodeData {}
-
-    fn create_test_tree() -> Tree<TestNodeData> {
-        let tree = Tree::new(
-            TestNodeData {
-                name: "root".to_string(),
-            },
-            Size {
-                width: 100.0,
-                height: 100.0,
-            },
-        );
-        let root = tree.root();
-        let child1 = Node::new(
-            Size {
-                width: 100.0,
-                height: 100.0,
-            },
-            TestNodeData {
-                name: "child1".to_string(),
-            },
-        );
-        add_child(root.clone(), child1);
-
-        let child2 = Node::new(
-            Size {
-                width: 100.0,
-                height: 100.0,
-            },
-            TestNodeData {
-                name: "child2".to_string(),
-            },
-        );
-        add_child(root.clone(), child2);
-
-        let child3 = Node::new(
-            Size {
-                width: 100.0,
-                height: 100.0,
-            },
-            TestNodeData {
-                name: "child3".to_string(),
-            },
-        );
-        add_child(root.clone(), child3.clone());
-
-        let child3_1 = Node::new(
-            Size {
-                width: 100.0,
-                height: 100.0,
-            },
-            TestNodeData {
-                name: "child3_1".to_string(),
-            },
-        );
-        add_child(child3.clone(), child3_1);
-
-        let child3_2 = Node::new(
-            Size {
-                width: 100.0,
-                height: 100.0,
-            },
-            TestNodeData {
-                name: "child3_2".to_string(),
-            },
-        );
-        add_child(child3.clone(), child3_2);
-
-        let child3_3 = Node::new(
-            Size {
-                width: 100.0,
-                height: 100.0,
-            },
-            TestNodeData {
-                name: "child3_3".to_string(),
-            },
-        );
-        add_child(child3.clone(), child3_3);
+    pub(super) fn data(name: &str) -> TestNodeData {
+        TestNodeData {
+            name: name.to_string(),
+        }
+    }
 
+    pub(super) fn create_test_tree() -> Tree<TestNodeData> {
+        let size = unit_size();
+        let mut tree = Tree::new(data("root"), size);
+        let root = tree.root_id();
+        tree.add_child(root, data("child1"), size);
+        tree.add_child(root, data("child2"), size);
+        let child3 = tree.add_child(root, data("child3"), size);
+        tree.add_child(child3, data("child3_1"), size);
+        tree.add_child(child3, data("child3_2"), size);
+        tree.add_child(child3, data("child3_3"), size);
         tree
     }
 
@@ -481,15 +702,14 @@ mod tests {
     #[test]
     fn test_create_tree() {
         let tree = create_test_tree();
-        let root = tree.root();
-        let root = root.borrow();
+        let root = &tree.nodes[tree.root];
         assert_eq!(root.data.name, "root");
-        assert_eq!(root.children().len(), 3);
+        assert_eq!(root.children.len(), 3);
 
         // for each child, check that the parent is the root
-        for child in root.children() {
-            let child = child.borrow();
-            assert_eq!(child.parent().unwrap().borrow().id, root.id);
+        for (index, &child) in root.children.iter().enumerate() {
+            assert_eq!(tree.nodes[child].parent, Some(tree.root));
+            assert_eq!(tree.nodes[child].index, index);
         }
     }
 
@@ -497,190 +717,318 @@ mod tests {
     #[test]
     fn test_is_leaf() {
         let tree = create_test_tree();
-        let root = tree.root();
-        let root = root.borrow();
+        let root = &tree.nodes[tree.root];
         assert!(!root.is_leaf());
 
-        let children = root.children();
-        assert!(children[0].borrow().is_leaf());
-        assert!(children[1].borrow().is_leaf());
-        assert!(!children[2].borrow().is_leaf());
-    }
-
-    // test that only the first child of the root is the leftmost child.
-    #[test]
-    fn test_is_leftmost_child() {
-        let tree = create_test_tree();
-        let root = tree.root();
-        let root = root.borrow();
-        let children = root.children();
-
-        assert_eq!(
-            children[0].borrow().id,
-            root.leftmost_child().unwrap().borrow().id
-        );
-    }
-
-    // test that only the last child of the root is the rightmost child.
-    #[test]
-    fn test_is_rightmost_child() {
-        let tree = create_test_tree();
-        let root = tree.root();
-        let root = root.borrow();
-        let children = root.children();
-
-        assert_eq!(
-            children[2].borrow().id,
-            root.rightmost_child().unwrap().borrow().id
-        );
+        let children = &root.children;
+        assert!(tree.nodes[children[0]].is_leaf());
+        assert!(tree.nodes[children[1]].is_leaf());
+        assert!(!tree.nodes[children[2]].is_leaf());
     }
 
     // test that get_previous_sibling returns the correct sibling.
     #[test]
     fn test_get_previous_sibling() {
         let tree = create_test_tree();
-        let root = tree.root();
-        let root = root.borrow();
+        let children = &tree.nodes[tree.root].children;
 
-        assert_eq!(root.children()[0].borrow().previous_sibling(), None);
-        assert_eq!(
-            root.children()[1].borrow().previous_sibling(),
-            Some(root.children()[0].clone())
-        );
-        assert_eq!(
-            root.children()[2].borrow().previous_sibling(),
-            Some(root.children()[1].clone())
-        )
+        assert_eq!(tree.previous_sibling(tree.root), None);
+        assert_eq!(tree.previous_sibling(children[0]), None);
+        assert_eq!(tree.previous_sibling(children[1]), Some(children[0]));
+        assert_eq!(tree.previous_sibling(children[2]), Some(children[1]));
     }
 
     // test that get_next_sibling returns the correct sibling.
     #[test]
     fn test_get_next_sibling() {
         let tree = create_test_tree();
-        let root = tree.root();
-        let root = root.borrow();
-        let children = root.children();
+        let children = &tree.nodes[tree.root].children;
 
-        assert_eq!(
-            children[0].borrow().next_sibling(),
-            Some(children[1].clone())
-        );
-        assert_eq!(
-            children[1].borrow().next_sibling(),
-            Some(children[2].clone())
-        );
-        assert_eq!(children[2].borrow().next_sibling(), None);
+        assert_eq!(tree.next_sibling(tree.root), None);
+        assert_eq!(tree.next_sibling(children[0]), Some(children[1]));
+        assert_eq!(tree.next_sibling(children[1]), Some(children[2]));
+        assert_eq!(tree.next_sibling(children[2]), None);
     }
 
     // test get_leftmost_sibling returns the leftmost sibling.
     #[test]
     fn test_get_leftmost_sibling() {
         let tree = create_test_tree();
-        let root = tree.root();
-        let root = root.borrow();
-        let children = root.children();
+        let children = &tree.nodes[tree.root].children;
 
-        assert_eq!(
-            children[0].borrow().leftmost_sibling(),
-            Some(children[0].clone())
-        );
-        assert_eq!(
-            children[1].borrow().leftmost_sibling(),
-            Some(children[0].clone())
-        );
-        assert_eq!(
-            children[2].borrow().leftmost_sibling(),
-            Some(children[0].clone())
-        );
+        assert_eq!(tree.leftmost_sibling(tree.root), None);
+        for &child in children {
+            assert_eq!(tree.leftmost_sibling(child), Some(children[0]));
+        }
     }
 
-    // test get_leftmost_child returns the leftmost child.
+    // test get_leftmost_child and get_rightmost_child return the first and last children.
     #[test]
-    fn test_get_leftmost_child() {
+    fn test_get_leftmost_and_rightmost_child() {
         let tree = create_test_tree();
-        let root = tree.root();
-        let root = root.borrow();
-        let children = root.children();
+        let children = &tree.nodes[tree.root].children;
 
-        assert_eq!(root.leftmost_child(), Some(children[0].clone()));
+        assert_eq!(tree.leftmost_child(tree.root), Some(children[0]));
+        assert_eq!(tree.rightmost_child(tree.root), Some(children[2]));
+        assert_eq!(tree.leftmost_child(children[0]), None);
     }
 
-    // test get_rightmost_child returns the rightmost child.
+    // Preorder puts parents before children; postorder puts children before parents and left
+    // subtrees before right ones.
     #[test]
-    fn test_get_rightmost_child() {
+    fn test_traversal_orders() {
         let tree = create_test_tree();
-        let root = tree.root();
-        let root = root.borrow();
-        let children = root.children();
-
-        assert_eq!(root.rightmost_child(), Some(children[2].clone()));
+        let names = |keys: Vec<NodeKey>| -> Vec<String> {
+            keys.into_iter()
+                .map(|key| tree.nodes[key].data.name.clone())
+                .collect()
+        };
+        assert_eq!(
+            names(tree.preorder()),
+            vec!["root", "child1", "child2", "child3", "child3_1", "child3_2", "child3_3"]
+        );
+        assert_eq!(
+            names(tree.postorder()),
+            vec!["child1", "child2", "child3_1", "child3_2", "child3_3", "child3", "root"]
+        );
     }
 
     // test initialize_nodes initializes the nodes correctly. For all nodex X == -1, mod == 0,
     // and the depth is correct.
     #[test]
-    fn test_reingold_tilford_initialize_nodes() {
+    fn test_initialize_nodes() {
         let mut tree = create_test_tree();
-        let node_size = 1;
-        let sibling_distance = 1.0;
-        let tree_distance = 2.0;
-        let layout = ReingoldTilfordLayout::new(sibling_distance, tree_distance, node_size);
-        layout.initialize_nodes(tree.root(), 0.0 /*depth*/);
+        initialize_nodes(&mut tree);
 
-        let root = tree.root();
-        let root = root.borrow();
+        let root = &tree.nodes[tree.root];
         assert_eq!(root.position.x, -1.0);
         assert_eq!(root.position.modifier, 0.0);
         assert_eq!(root.position.y, 0.0);
-        for child in root.children() {
-            assert_eq!(child.borrow().position.x, -1.0);
-            assert_eq!(child.borrow().position.modifier, 0.0);
-            assert_eq!(child.borrow().position.y, 1.0);
+        for &child in &root.children {
+            let child = &tree.nodes[child];
+            assert_eq!(child.position.x, -1.0);
+            assert_eq!(child.position.modifier, 0.0);
+            assert_eq!(child.position.y, 1.0);
+        }
+    }
+
+    pub(super) fn unit_size() -> Size {
+        Size::new(1.0, 1.0)
+    }
+
+    /// Add children with the given names to `parent` and return their ids.
+    pub(super) fn add_children(
+        tree: &mut Tree<TestNodeData>,
+        parent: NodeId,
+        names: &[&str],
+    ) -> Vec<NodeId> {
+        names
+            .iter()
+            .map(|name| tree.add_child(parent, data(name), unit_size()))
+            .collect()
+    }
+
+    /// Lay out the tree with sibling separation 1, tree distance 2 and level separation 1, and
+    /// return the final (x, y) of every node by name.
+    fn layout_positions(tree: &mut Tree<TestNodeData>) -> Vec<(String, f64, f64)> {
+        positions_after(&ReingoldTilfordLayout::new(1.0, 2.0, 1.0), tree)
+    }
+
+    /// Lay out the tree with `layout` and return the final (x, y) of every node by name, in
+    /// breadth-first order.
+    pub(super) fn positions_after(
+        layout: &impl Layout<TestNodeData>,
+        tree: &mut Tree<TestNodeData>,
+    ) -> Vec<(String, f64, f64)> {
+        layout.layout(tree);
+        positions(tree)
+    }
+
+    /// The (x, y) of every node by name, in breadth-first order.
+    pub(super) fn positions(tree: &Tree<TestNodeData>) -> Vec<(String, f64, f64)> {
+        let debug_tree: DebugTree<TestNodeData> = tree.clone().into();
+        let mut positions = vec![];
+        let mut queue = VecDeque::from([debug_tree.root]);
+        while let Some(node) = queue.pop_front() {
+            positions.push((node.data.name.clone(), node.position.x, node.position.y));
+            queue.extend(node.children);
+        }
+        positions
+    }
+
+    pub(super) fn expected(positions: &[(&str, f64, f64)]) -> Vec<(String, f64, f64)> {
+        positions
+            .iter()
+            .map(|(name, x, y)| (name.to_string(), *x, *y))
+            .collect()
+    }
+
+    #[test]
+    fn test_public_api() {
+        let mut tree = Tree::new("root", Size::new(2.0, 1.0));
+        let root = tree.root_id();
+        let left = tree.add_child(root, "left", Size::new(1.0, 1.0));
+        let right = tree.add_child(root, "right", Size::new(1.0, 1.0));
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.children(root), vec![left, right]);
+        assert_eq!(tree.parent(right), Some(root));
+        assert_eq!(tree.parent(root), None);
+        assert_eq!(tree.data(left), Some("left"));
+        assert_eq!(tree.size(root), Some(Size::new(2.0, 1.0)));
+
+        ReingoldTilfordLayout::new(1.0, 2.0, 1.0).layout(&mut tree);
+        let position = |id| {
+            let position = tree.position(id).unwrap();
+            (position.x, position.y)
+        };
+        assert_eq!(position(left), (0.0, 2.0));
+        assert_eq!(position(right), (2.0, 2.0));
+        // The root is twice as wide, so its left edge is half a node left of the children's
+        // midpoint.
+        assert_eq!(position(root), (0.5, 0.0));
+
+        let other = Tree::new("other", Size::new(1.0, 1.0));
+        assert!(!tree.contains(other.root_id()));
+        assert_eq!(tree.position(other.root_id()), None);
+    }
+
+    // The passes are iterative, so a very deep tree doesn't overflow the stack.
+    #[test]
+    fn test_reingold_tilford_deep_tree() {
+        let mut tree = Tree::new(0, unit_size());
+        let mut parent = tree.root_id();
+        let mut ids = vec![parent];
+        for depth in 1..100_000 {
+            parent = tree.add_child(parent, depth, unit_size());
+            ids.push(parent);
         }
+        let sibling = tree.add_child(ids[0], -1, unit_size());
+
+        ReingoldTilfordLayout::new(1.0, 2.0, 0.0).layout(&mut tree);
+        let deepest = tree.position(parent).unwrap();
+        assert_eq!((deepest.x, deepest.y), (0.0, 99_999.0));
+        assert_eq!(tree.position(sibling).unwrap().x, 2.0);
+        assert_eq!(tree.position(ids[0]).unwrap().x, 1.0);
     }
 
-    fn arbitrary_tree(max_iterations: usize) -> impl Strategy<Value = Tree<TestNodeData>> {
-        let tree = Tree::new(
-            TestNodeData {
-                name: "root".to_string(),
-            },
-            Size {
-                width: 100.0,
-                height: 100.0,
-            },
+    #[test]
+    fn test_reingold_tilford_layout_test_tree() {
+        let mut tree = create_test_tree();
+        assert_eq!(
+            layout_positions(&mut tree),
+            expected(&[
+                ("root", 2.0, 0.0),
+                ("child1", 0.0, 2.0),
+                ("child2", 2.0, 2.0),
+                ("child3", 4.0, 2.0),
+                ("child3_1", 2.0, 4.0),
+                ("child3_2", 4.0, 4.0),
+                ("child3_3", 6.0, 4.0),
+            ])
+        );
+    }
+
+    // Two subtrees whose children would overlap: the right one is pushed over so that its
+    // leftmost child is tree_distance + node_size from the left one's rightmost child.
+    #[test]
+    fn test_reingold_tilford_resolves_conflicts() {
+        let mut tree = Tree::new(data("root"), unit_size());
+        let root = tree.root_id();
+        let subtrees = add_children(&mut tree, root, &["a", "b"]);
+        add_children(&mut tree, subtrees[0], &["a1", "a2", "a3"]);
+        add_children(&mut tree, subtrees[1], &["b1", "b2", "b3"]);
+        assert_eq!(
+            layout_positions(&mut tree),
+            expected(&[
+                ("root", 5.5, 0.0),
+                ("a", 2.0, 2.0),
+                ("b", 9.0, 2.0),
+                ("a1", 0.0, 4.0),
+                ("a2", 2.0, 4.0),
+                ("a3", 4.0, 4.0),
+                ("b1", 7.0, 4.0),
+                ("b2", 9.0, 4.0),
+                ("b3", 11.0, 4.0),
+            ])
         );
-        let node = tree.root();
-        let node = node.borrow();
-
-        Just(tree)
-    }
-
-    /// arbitrary_node creates a node adds it to the parent.
-    fn arbitrary_node(
-        parent: Rc<RefCell<Node<TestNodeData>>>,
-    ) -> impl Strategy<Value = Rc<RefCell<Node<TestNodeData>>>> {
-        let node = Node::new(
-            Size {
-                width: 100.0,
-                height: 100.0,
-            },
-            TestNodeData {
-                name: "node".to_string(),
-            },
+    }
+
+    // A leaf between two subtrees that were pushed apart ends up halfway between them.
+    #[test]
+    fn test_reingold_tilford_centers_nodes_between() {
+        let mut tree = Tree::new(data("root"), unit_size());
+        let root = tree.root_id();
+        let subtrees = add_children(&mut tree, root, &["a", "m", "b"]);
+        add_children(&mut tree, subtrees[0], &["a1", "a2", "a3"]);
+        add_children(&mut tree, subtrees[2], &["b1", "b2", "b3"]);
+        assert_eq!(
+            layout_positions(&mut tree),
+            expected(&[
+                ("root", 5.5, 0.0),
+                ("a", 2.0, 2.0),
+                ("m", 5.5, 2.0),
+                ("b", 9.0, 2.0),
+                ("a1", 0.0, 4.0),
+                ("a2", 2.0, 4.0),
+                ("a3", 4.0, 4.0),
+                ("b1", 7.0, 4.0),
+                ("b2", 9.0, 4.0),
+                ("b3", 11.0, 4.0),
+            ])
         );
-        parent.borrow_mut().add_child(node.clone());
-        Just(node)
+    }
+
+    /// Random trees of up to `max_nodes` nodes: node i + 1 is a child of one of nodes 0..=i.
+    /// Nodes are between 0.5 and 3 wide and between 0.5 and 2 tall.
+    pub(super) fn arbitrary_tree(max_nodes: usize) -> impl Strategy<Value = Tree<TestNodeData>> {
+        let size = (0.5..3.0, 0.5..2.0).prop_map(|(width, height)| Size::new(width, height));
+        (
+            size.clone(),
+            prop::collection::vec((any::<prop::sample::Index>(), size), 0..max_nodes),
+        )
+            .prop_map(|(root_size, nodes)| {
+                let mut tree = Tree::new(data("0"), root_size);
+                let mut ids = vec![tree.root_id()];
+                for (i, (parent, size)) in nodes.into_iter().enumerate() {
+                    let parent = ids[parent.index(ids.len())];
+                    ids.push(tree.add_child(parent, data(&(i + 1).to_string()), size));
+                }
+                tree
+            })
+    }
+
+    // Siblings of different widths are spaced by the gaps between their edges, and the parent
+    // is centered over the middle of its children.
+    #[test]
+    fn test_reingold_tilford_variable_widths() {
+        let mut tree = Tree::new(data("root"), Size::new(2.0, 1.0));
+        let root = tree.root_id();
+        let wide = tree.add_child(root, data("wide"), Size::new(4.0, 3.0));
+        let narrow = tree.add_child(root, data("narrow"), Size::new(1.0, 1.0));
+        let medium = tree.add_child(root, data("medium"), Size::new(3.0, 1.0));
+        ReingoldTilfordLayout::new(1.0, 2.0, 1.0).layout(&mut tree);
+
+        let position = |id| {
+            let position = tree.position(id).unwrap();
+            (position.x, position.y)
+        };
+        assert_eq!(position(wide), (0.0, 2.0));
+        // The row is as tall as the wide node, so the shorter nodes are centered in it.
+        assert_eq!(position(narrow), (5.0, 3.0));
+        assert_eq!(position(medium), (7.0, 3.0));
+        // Children's centers are 2 and 8.5, so the root's center is at 5.25.
+        assert_eq!(position(root), (4.25, 0.0));
     }
 
     #[test]
     fn test_reingold_tilford_calculate_x() {
-        let tree = create_test_tree();
+        let mut tree = create_test_tree();
         let sibling_distance = 1.0;
         let tree_distance = 2.0;
-        let node_size = 1;
-        let layout = ReingoldTilfordLayout::new(sibling_distance, tree_distance, node_size);
-        layout.initialize_nodes(tree.root(), 0.0 /*depth*/);
-        layout.calculate_initial_x(tree.root());
+        let level_separation = 1.0;
+        let layout = ReingoldTilfordLayout::new(sibling_distance, tree_distance, level_separation);
+        initialize_nodes(&mut tree);
+        layout.calculate_initial_x(&mut tree);
 
         let _debug_tree: DebugTree<TestNodeData> = tree.into();
         let mut queue = VecDeque::new();
@@ -693,5 +1041,54 @@ mod tests {
         }
     }
 
-    proptest! {}
+    /// Check a laid out tree: adjacent nodes at the same depth never come closer than 1 (the
+    /// smaller of the sibling separation and the tree distance used by the tests), every parent
+    /// is centered over its children, rows don't overlap, and the leftmost node is at x = 0.
+    pub(super) fn check_layout_properties(tree: Tree<TestNodeData>) -> Result<(), TestCaseError> {
+        let debug_tree: DebugTree<TestNodeData> = tree.into();
+        let center = |node: &DebugNode<TestNodeData>| node.position.x + node.size.width / 2.0;
+        // (left, right, top, bottom) of each node, by depth, left to right.
+        let mut levels: BTreeMap<usize, Vec<(f64, f64, f64, f64)>> = BTreeMap::new();
+        let mut queue = VecDeque::from([(debug_tree.root, 0)]);
+        while let Some((node, depth)) = queue.pop_front() {
+            let Position { x, y, .. } = node.position;
+            let Size { width, height } = node.size;
+            levels
+                .entry(depth)
+                .or_default()
+                .push((x, x + width, y, y + height));
+            if let (Some(first), Some(last)) = (node.children.first(), node.children.last()) {
+                let mid = (center(first) + center(last)) / 2.0;
+                prop_assert!((center(&node) - mid).abs() < 1e-9);
+            }
+            queue.extend(node.children.into_iter().map(|child| (child, depth + 1)));
+        }
+        let mut min_x = f64::INFINITY;
+        let mut previous_bottom = f64::NEG_INFINITY;
+        for nodes in levels.values() {
+            for pair in nodes.windows(2) {
+                prop_assert!(pair[1].0 - pair[0].1 >= 1.0 - 1e-9, "{:?}", nodes);
+            }
+            min_x = min_x.min(nodes[0].0);
+            let top = nodes
+                .iter()
+                .map(|node| node.2)
+                .fold(f64::INFINITY, f64::min);
+            prop_assert!(top - previous_bottom >= 1.0 - 1e-9);
+            previous_bottom = nodes
+                .iter()
+                .map(|node| node.3)
+                .fold(f64::NEG_INFINITY, f64::max);
+        }
+        prop_assert!(min_x.abs() < 1e-9);
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn test_reingold_tilford_layout_properties(mut tree in arbitrary_tree(40)) {
+            layout_positions(&mut tree);
+            check_layout_properties(tree)?;
+        }
+    }
 }
@@ -0,0 +1,451 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+//! The Buchheim–Jünger–Leipert layout: Walker's tidy tree algorithm in linear time.
+//!
+//! See: Christoph Buchheim, Michael Jünger and Sebastian Leipert, "Improving Walker's Algorithm
+//! to Run in Linear Time", Graph Drawing 2002.
+//!
+//! Like Reingold–Tilford it places each subtree as close as possible to its left siblings, but
+//! it follows the subtrees' contours with threads instead of walking them again for every
+//! sibling, and it spreads the smaller subtrees between two that were pushed apart evenly as it
+//! goes. Subtrees of very different heights are handled in O(n) overall.
+
+use slotmap::SecondaryMap;
+
+use super::{initialize_nodes, orientation, place_rows, Data, Layout, NodeKey, Orientation, Tree};
+
+/// Working state of a node during the first walk.
+#[derive(Debug, Clone, Copy)]
+struct Walk {
+    /// Preliminary x of the node's center, relative to its parent's subtree.
+    prelim: f64,
+    /// Offset to apply to the node's descendants.
+    modifier: f64,
+    /// Pending shift of the node's subtree, applied by `execute_shifts`.
+    shift: f64,
+    /// Pending change in shift per sibling, applied by `execute_shifts`.
+    change: f64,
+    /// Next node on the contour when the node is a leaf of a contour.
+    thread: Option<NodeKey>,
+    /// The ancestor through which the node was last reached on a right contour.
+    ancestor: NodeKey,
+}
+
+/// The Buchheim–Jünger–Leipert tidy tree layout, a linear-time alternative to
+/// [`super::ReingoldTilfordLayout`] with the same parameters and the same guarantees: siblings
+/// are `sibling_separation` apart, neighboring subtrees `tree_distance` apart below their
+/// roots, depths `level_separation` apart, and every parent is centered over its children.
+///
+/// Where a subtree is pushed away from its left siblings, the subtrees in between are spread
+/// out evenly, so results can differ slightly from Reingold–Tilford's.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BuchheimLayout {
+    sibling_separation: f64,
+    tree_distance: f64,
+    level_separation: f64,
+    orientation: Orientation,
+}
+
+impl BuchheimLayout {
+    pub fn new(sibling_separation: f64, tree_distance: f64, level_separation: f64) -> Self {
+        BuchheimLayout {
+            sibling_separation,
+            tree_distance,
+            level_separation,
+            orientation: Orientation::default(),
+        }
+    }
+
+    pub fn with_orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Distance between the centers of two nodes at the same depth whose edges are `gap` apart.
+    fn distance<_Data: Data>(tree: &Tree<_Data>, left: NodeKey, right: NodeKey, gap: f64) -> f64 {
+        (tree.nodes[left].size.width + tree.nodes[right].size.width) / 2.0 + gap
+    }
+
+    /// Postorder pass: preliminary x and modifier for every node.
+    fn first_walk<_Data: Data>(&self, tree: &Tree<_Data>, walks: &mut SecondaryMap<NodeKey, Walk>) {
+        // The default ancestor of each parent whose children are being apportioned.
+        let mut default_ancestors: SecondaryMap<NodeKey, NodeKey> = SecondaryMap::new();
+        for key in tree.postorder() {
+            let previous_x = tree.previous_sibling(key).map(|previous| {
+                walks[previous].prelim
+                    + Self::distance(tree, previous, key, self.sibling_separation)
+            });
+            if tree.nodes[key].is_leaf() {
+                walks[key].prelim = previous_x.unwrap_or(0.0);
+            } else {
+                Self::execute_shifts(tree, walks, key);
+                let leftmost = walks[tree.leftmost_child(key).unwrap()].prelim;
+                let rightmost = walks[tree.rightmost_child(key).unwrap()].prelim;
+                let mid = (leftmost + rightmost) / 2.0;
+                match previous_x {
+                    None => walks[key].prelim = mid,
+                    Some(previous_x) => {
+                        walks[key].prelim = previous_x;
+                        walks[key].modifier = previous_x - mid;
+                    }
+                }
+            }
+
+            if let Some(parent) = tree.nodes[key].parent {
+                let default_ancestor = default_ancestors.get(parent).copied().unwrap_or(key);
+                let default_ancestor = self.apportion(tree, walks, key, default_ancestor);
+                default_ancestors.insert(parent, default_ancestor);
+            }
+        }
+    }
+
+    /// The leftmost child of `key`, or its thread.
+    fn next_left<_Data: Data>(
+        tree: &Tree<_Data>,
+        walks: &SecondaryMap<NodeKey, Walk>,
+        key: NodeKey,
+    ) -> Option<NodeKey> {
+        tree.leftmost_child(key).or(walks[key].thread)
+    }
+
+    /// The rightmost child of `key`, or its thread.
+    fn next_right<_Data: Data>(
+        tree: &Tree<_Data>,
+        walks: &SecondaryMap<NodeKey, Walk>,
+        key: NodeKey,
+    ) -> Option<NodeKey> {
+        tree.rightmost_child(key).or(walks[key].thread)
+    }
+
+    /// Push the subtree of `key` right until it is clear of its left siblings' subtrees, by
+    /// following the right contour of the left siblings and the left contour of the subtree
+    /// down together. Returns the new default ancestor.
+    fn apportion<_Data: Data>(
+        &self,
+        tree: &Tree<_Data>,
+        walks: &mut SecondaryMap<NodeKey, Walk>,
+        key: NodeKey,
+        mut default_ancestor: NodeKey,
+    ) -> NodeKey {
+        let Some(left_sibling) = tree.previous_sibling(key) else {
+            return default_ancestor;
+        };
+        // Inner and outer contour nodes of the right (the subtree) and left (its siblings)
+        // sides, and the sums of modifiers along each contour.
+        let (mut inner_right, mut outer_right) = (key, key);
+        let mut inner_left = left_sibling;
+        let mut outer_left = tree.leftmost_sibling(key).unwrap();
+        let mut sum_inner_right = walks[inner_right].modifier;
+        let mut sum_outer_right = walks[outer_right].modifier;
+        let mut sum_inner_left = walks[inner_left].modifier;
+        let mut sum_outer_left = walks[outer_left].modifier;
+
+        while let (Some(next_inner_left), Some(next_inner_right)) = (
+            Self::next_right(tree, walks, inner_left),
+            Self::next_left(tree, walks, inner_right),
+        ) {
+            inner_left = next_inner_left;
+            inner_right = next_inner_right;
+            outer_left = Self::next_left(tree, walks, outer_left).unwrap();
+            outer_right = Self::next_right(tree, walks, outer_right).unwrap();
+            walks[outer_right].ancestor = key;
+
+            let shift = (walks[inner_left].prelim + sum_inner_left)
+                - (walks[inner_right].prelim + sum_inner_right)
+                + Self::distance(tree, inner_left, inner_right, self.tree_distance);
+            if shift > 0.0 {
+                let ancestor = Self::ancestor(tree, walks, inner_left, key, default_ancestor);
+                Self::move_subtree(tree, walks, ancestor, key, shift);
+                sum_inner_right += shift;
+                sum_outer_right += shift;
+            }
+            sum_inner_left += walks[inner_left].modifier;
+            sum_inner_right += walks[inner_right].modifier;
+            sum_outer_left += walks[outer_left].modifier;
+            sum_outer_right += walks[outer_right].modifier;
+        }
+
+        // Thread the shorter side's contour onto the taller side's.
+        if let (Some(next), None) = (
+            Self::next_right(tree, walks, inner_left),
+            Self::next_right(tree, walks, outer_right),
+        ) {
+            walks[outer_right].thread = Some(next);
+            walks[outer_right].modifier += sum_inner_left - sum_outer_right;
+        }
+        if let (Some(next), None) = (
+            Self::next_left(tree, walks, inner_right),
+            Self::next_left(tree, walks, outer_left),
+        ) {
+            walks[outer_left].thread = Some(next);
+            walks[outer_left].modifier += sum_inner_right - sum_outer_left;
+            default_ancestor = key;
+        }
+        default_ancestor
+    }
+
+    /// The sibling of `key` whose subtree contains `inner_left`: its recorded ancestor if that
+    /// is a sibling of `key`, otherwise the default ancestor.
+    fn ancestor<_Data: Data>(
+        tree: &Tree<_Data>,
+        walks: &SecondaryMap<NodeKey, Walk>,
+        inner_left: NodeKey,
+        key: NodeKey,
+        default_ancestor: NodeKey,
+    ) -> NodeKey {
+        let ancestor = walks[inner_left].ancestor;
+        if tree.nodes[ancestor].parent == tree.nodes[key].parent {
+            ancestor
+        } else {
+            default_ancestor
+        }
+    }
+
+    /// Shift the subtree of `right` by `shift`, and record that the siblings between `left` and
+    /// `right` should be shifted by evenly growing fractions of it.
+    fn move_subtree<_Data: Data>(
+        tree: &Tree<_Data>,
+        walks: &mut SecondaryMap<NodeKey, Walk>,
+        left: NodeKey,
+        right: NodeKey,
+        shift: f64,
+    ) {
+        let subtrees = (tree.nodes[right].index - tree.nodes[left].index) as f64;
+        walks[right].change -= shift / subtrees;
+        walks[right].shift += shift;
+        walks[left].change += shift / subtrees;
+        walks[right].prelim += shift;
+        walks[right].modifier += shift;
+    }
+
+    /// Apply the shifts recorded by `move_subtree` to the children of `key`, right to left.
+    fn execute_shifts<_Data: Data>(
+        tree: &Tree<_Data>,
+        walks: &mut SecondaryMap<NodeKey, Walk>,
+        key: NodeKey,
+    ) {
+        let (mut shift, mut change) = (0.0, 0.0);
+        for &child in tree.nodes[key].children.iter().rev() {
+            let walk = &mut walks[child];
+            walk.prelim += shift;
+            walk.modifier += shift;
+            change += walk.change;
+            shift += walk.shift + change;
+        }
+    }
+
+    /// Preorder pass: each node's center x is its preliminary x plus its ancestors' modifiers.
+    /// The tree is then shifted so that its leftmost edge is at 0.
+    fn second_walk<_Data: Data>(
+        &self,
+        tree: &mut Tree<_Data>,
+        walks: &SecondaryMap<NodeKey, Walk>,
+    ) {
+        let mut min_x = f64::INFINITY;
+        let mut stack = vec![(tree.root, 0.0)];
+        while let Some((key, modifier_sum)) = stack.pop() {
+            let node = &mut tree.nodes[key];
+            node.position.x = walks[key].prelim + modifier_sum;
+            min_x = min_x.min(node.position.x - node.size.width / 2.0);
+            let modifier_sum = modifier_sum + walks[key].modifier;
+            stack.extend(node.children.iter().map(|&child| (child, modifier_sum)));
+        }
+        for node in tree.nodes.values_mut() {
+            node.position.x -= min_x;
+        }
+    }
+}
+
+impl<_Data: Data> Layout<_Data> for BuchheimLayout {
+    fn layout(&self, tree: &mut Tree<_Data>) {
+        orientation::lay_out(tree, self.orientation, self.sibling_separation, |tree| {
+            initialize_nodes(tree);
+            let mut walks: SecondaryMap<NodeKey, Walk> = tree
+                .nodes
+                .keys()
+                .map(|key| {
+                    let walk = Walk {
+                        prelim: 0.0,
+                        modifier: 0.0,
+                        shift: 0.0,
+                        change: 0.0,
+                        thread: None,
+                        ancestor: key,
+                    };
+                    (key, walk)
+                })
+                .collect();
+            self.first_walk(tree, &mut walks);
+            self.second_walk(tree, &walks);
+            place_rows(tree, self.level_separation);
+        });
+    }
+
+    fn sibling_separation(&self) -> f64 {
+        self.sibling_separation
+    }
+
+    fn tree_distance(&self) -> f64 {
+        self.tree_distance
+    }
+
+    fn level_separation(&self) -> f64 {
+        self.level_separation
+    }
+
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{
+        add_children, arbitrary_tree, check_layout_properties, create_test_tree, data, expected,
+        positions_after, unit_size, TestNodeData,
+    };
+    use super::*;
+    use crate::layout::{ReingoldTilfordLayout, Size};
+    use proptest::prelude::*;
+
+    fn layout_positions(tree: &mut Tree<TestNodeData>) -> Vec<(String, f64, f64)> {
+        positions_after(&BuchheimLayout::new(1.0, 2.0, 1.0), tree)
+    }
+
+    #[test]
+    fn test_buchheim_layout_test_tree() {
+        let mut tree = create_test_tree();
+        assert_eq!(
+            layout_positions(&mut tree),
+            expected(&[
+                ("root", 2.0, 0.0),
+                ("child1", 0.0, 2.0),
+                ("child2", 2.0, 2.0),
+                ("child3", 4.0, 2.0),
+                ("child3_1", 2.0, 4.0),
+                ("child3_2", 4.0, 4.0),
+                ("child3_3", 6.0, 4.0),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_buchheim_resolves_conflicts() {
+        let mut tree = Tree::new(data("root"), unit_size());
+        let root = tree.root_id();
+        let subtrees = add_children(&mut tree, root, &["a", "b"]);
+        add_children(&mut tree, subtrees[0], &["a1", "a2", "a3"]);
+        add_children(&mut tree, subtrees[1], &["b1", "b2", "b3"]);
+        assert_eq!(
+            layout_positions(&mut tree),
+            expected(&[
+                ("root", 5.5, 0.0),
+                ("a", 2.0, 2.0),
+                ("b", 9.0, 2.0),
+                ("a1", 0.0, 4.0),
+                ("a2", 2.0, 4.0),
+                ("a3", 4.0, 4.0),
+                ("b1", 7.0, 4.0),
+                ("b2", 9.0, 4.0),
+                ("b3", 11.0, 4.0),
+            ])
+        );
+    }
+
+    // Two leaves between subtrees that were pushed apart are spread out evenly: the push is
+    // shared in thirds rather than bunching the leaves up against the left subtree.
+    #[test]
+    fn test_buchheim_spreads_nodes_between() {
+        let mut tree = Tree::new(data("root"), unit_size());
+        let root = tree.root_id();
+        let subtrees = add_children(&mut tree, root, &["a", "m", "n", "b"]);
+        add_children(&mut tree, subtrees[0], &["a1", "a2", "a3"]);
+        add_children(&mut tree, subtrees[3], &["b1", "b2", "b3"]);
+        let positions = layout_positions(&mut tree);
+        let x = |name: &str| positions.iter().find(|(n, _, _)| n == name).unwrap().1;
+        assert!((x("m") - x("a") - (x("b") - x("a")) / 3.0).abs() < 1e-9);
+        assert!((x("n") - x("m") - (x("b") - x("a")) / 3.0).abs() < 1e-9);
+        assert_eq!(x("b1") - x("a3"), 3.0);
+    }
+
+    // A shallow subtree between two deep ones must not let the deep ones overlap underneath
+    // it, which is what the threads are for.
+    #[test]
+    fn test_buchheim_subtrees_of_different_heights() {
+        let mut tree = Tree::new(data("root"), unit_size());
+        let root = tree.root_id();
+        let subtrees = add_children(&mut tree, root, &["a", "m", "b"]);
+        let mut left = subtrees[0];
+        let mut right = subtrees[2];
+        for depth in 0..4 {
+            left = add_children(
+                &mut tree,
+                left,
+                &[&format!("a{depth}l"), &format!("a{depth}r")],
+            )[1];
+            right = add_children(
+                &mut tree,
+                right,
+                &[&format!("b{depth}l"), &format!("b{depth}r")],
+            )[0];
+        }
+        layout_positions(&mut tree);
+        check_layout_properties(tree).unwrap();
+    }
+
+    // Same 100,000-deep chain as the Reingold–Tilford test.
+    #[test]
+    fn test_buchheim_deep_tree() {
+        let mut tree = Tree::new(0, unit_size());
+        let mut parent = tree.root_id();
+        let mut ids = vec![parent];
+        for depth in 1..100_000 {
+            parent = tree.add_child(parent, depth, unit_size());
+            ids.push(parent);
+        }
+        let sibling = tree.add_child(ids[0], -1, unit_size());
+
+        BuchheimLayout::new(1.0, 2.0, 0.0).layout(&mut tree);
+        let deepest = tree.position(parent).unwrap();
+        assert_eq!((deepest.x, deepest.y), (0.0, 99_999.0));
+        assert_eq!(tree.position(sibling).unwrap().x, 2.0);
+        assert_eq!(tree.position(ids[0]).unwrap().x, 1.0);
+    }
+
+    // Without subtrees to spread between, both layouts agree.
+    #[test]
+    fn test_buchheim_matches_reingold_tilford_on_variable_widths() {
+        let mut tree = Tree::new(data("root"), Size::new(2.0, 1.0));
+        let root = tree.root_id();
+        tree.add_child(root, data("wide"), Size::new(4.0, 3.0));
+        tree.add_child(root, data("narrow"), Size::new(1.0, 1.0));
+        tree.add_child(root, data("medium"), Size::new(3.0, 1.0));
+        let mut other = tree.clone();
+        assert_eq!(
+            positions_after(&BuchheimLayout::new(1.0, 2.0, 1.0), &mut tree),
+            positions_after(&ReingoldTilfordLayout::new(1.0, 2.0, 1.0), &mut other)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_buchheim_layout_properties(mut tree in arbitrary_tree(40)) {
+            layout_positions(&mut tree);
+            check_layout_properties(tree)?;
+        }
+    }
+}
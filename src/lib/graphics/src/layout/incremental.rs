@@ -0,0 +1,397 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+//! Incremental layout of a growing tree, such as a Monte Carlo search tree as it expands.
+//!
+//! [`IncrementalLayout`] owns a [`Tree`] and remembers the shape of every laid-out subtree: its
+//! left and right contours and the offsets of its children. Adding a node only invalidates the
+//! shapes of its ancestors, so each [`IncrementalLayout::update`] re-lays out one path from the
+//! new nodes to the root and reuses every other subtree as it was. Turning the shapes into
+//! positions is a single cheap pass over the tree.
+//!
+//! Subtrees are packed against their left siblings as in Reingold–Tilford, but smaller subtrees
+//! between two that were pushed apart are not spread out, since that would make a node's
+//! position depend on siblings added after it. Keeping contours for every subtree costs memory
+//! proportional to the sum of the subtrees' heights, which suits bushy search trees rather
+//! than long chains.
+
+use slotmap::SecondaryMap;
+use std::collections::HashSet;
+
+use super::{orientation, place_rows, Data, NodeId, NodeKey, Orientation, Position, Size, Tree};
+
+/// The layout of a subtree relative to the center of its root.
+#[derive(Debug, Clone, Default)]
+struct Shape {
+    /// Offset of each child's center from the node's center, left to right.
+    child_offsets: Vec<f64>,
+    /// Leftmost edge of the subtree at each depth, starting with the node itself.
+    left: Vec<f64>,
+    /// Rightmost edge of the subtree at each depth, starting with the node itself.
+    right: Vec<f64>,
+}
+
+/// A tree whose layout is kept up to date as nodes are added, re-laying out only the subtrees
+/// that changed.
+///
+/// Uses the same parameters as [`super::ReingoldTilfordLayout`] and gives the same guarantees
+/// (see the [module documentation](super)): parents centered over their children, adjacent
+/// nodes at least the smaller of `sibling_separation` and `tree_distance` apart, rows
+/// `level_separation` apart and the leftmost edge at x = 0.
+///
+/// ```
+/// use graphics::layout::{IncrementalLayout, Size, Tree};
+///
+/// let mut layout = IncrementalLayout::new(Tree::new("root", Size::new(1.0, 1.0)), 1.0, 2.0, 1.0);
+/// let root = layout.tree().root_id();
+/// let child = layout.add_child(root, "child", Size::new(1.0, 1.0));
+/// layout.update();
+/// let sibling = layout.add_child(root, "sibling", Size::new(1.0, 1.0));
+/// // Only the root's subtree has to be laid out again, not the first child's.
+/// assert_eq!(layout.update(), 2);
+/// assert_eq!(layout.tree().position(sibling).unwrap().x, 2.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct IncrementalLayout<_Data: Data> {
+    tree: Tree<_Data>,
+    sibling_separation: f64,
+    tree_distance: f64,
+    level_separation: f64,
+    orientation: Orientation,
+    /// Shapes of the subtrees laid out so far.
+    shapes: SecondaryMap<NodeKey, Shape>,
+    /// Depth of every node, the root being at depth 0.
+    depths: SecondaryMap<NodeKey, usize>,
+    /// Nodes whose subtrees changed since the last update.
+    dirty: HashSet<NodeKey>,
+}
+
+impl<_Data: Data> IncrementalLayout<_Data> {
+    /// Take ownership of `tree`. Nothing is laid out until the first [`Self::update`].
+    pub fn new(
+        tree: Tree<_Data>,
+        sibling_separation: f64,
+        tree_distance: f64,
+        level_separation: f64,
+    ) -> Self {
+        let mut depths = SecondaryMap::new();
+        for key in tree.preorder() {
+            let depth = tree.nodes[key]
+                .parent
+                .map_or(0, |parent| depths[parent] + 1);
+            depths.insert(key, depth);
+        }
+        let dirty = tree.nodes.keys().collect();
+        IncrementalLayout {
+            tree,
+            sibling_separation,
+            tree_distance,
+            level_separation,
+            orientation: Orientation::default(),
+            shapes: SecondaryMap::new(),
+            depths,
+            dirty,
+        }
+    }
+
+    /// Changing the orientation invalidates every subtree, since a left-to-right layout packs
+    /// nodes by their heights rather than their widths.
+    pub fn with_orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self.shapes.clear();
+        self.dirty = self.tree.nodes.keys().collect();
+        self
+    }
+
+    /// The tree, with the positions from the last update. Nodes added since then are at the
+    /// origin.
+    pub fn tree(&self) -> &Tree<_Data> {
+        &self.tree
+    }
+
+    pub fn into_tree(self) -> Tree<_Data> {
+        self.tree
+    }
+
+    /// Add a node as the new rightmost child of `parent`, like [`Tree::add_child`], and mark
+    /// its ancestors for re-layout. Panics if `parent` is not in the tree.
+    pub fn add_child(&mut self, parent: NodeId, data: _Data, size: Size) -> NodeId {
+        let id = self.tree.add_child(parent, data, size);
+        let key = self.tree.keys[&id];
+        let parent = self.tree.nodes[key]
+            .parent
+            .expect("a new node has a parent");
+        self.depths.insert(key, self.depths[parent] + 1);
+        // Ancestors of a dirty node are already dirty, so stop at the first one.
+        let mut next = Some(key);
+        while let Some(key) = next {
+            if !self.dirty.insert(key) {
+                break;
+            }
+            next = self.tree.nodes[key].parent;
+        }
+        id
+    }
+
+    /// Whether nodes were added since the last update.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Re-lay out the subtrees that changed since the last update and reposition every node.
+    /// Returns the number of subtrees that were laid out again.
+    pub fn update(&mut self) -> usize {
+        let mut dirty: Vec<NodeKey> = self.dirty.drain().collect();
+        // Deepest first, so that children's shapes are ready before their parents'.
+        dirty.sort_unstable_by_key(|&key| std::cmp::Reverse(self.depths[key]));
+        let IncrementalLayout {
+            tree,
+            sibling_separation,
+            tree_distance,
+            level_separation,
+            orientation,
+            shapes,
+            depths,
+            ..
+        } = self;
+        orientation::lay_out(tree, *orientation, *sibling_separation, |tree| {
+            for &key in &dirty {
+                let shape = Self::shape(tree, shapes, key, *sibling_separation, *tree_distance);
+                shapes.insert(key, shape);
+            }
+            Self::place(tree, shapes, depths, *level_separation);
+        });
+        dirty.len()
+    }
+
+    /// Lay out the subtree of `key` from its children's shapes: each child is placed as close
+    /// as possible to the children on its left, and the node is centered over its children.
+    fn shape(
+        tree: &Tree<_Data>,
+        shapes: &SecondaryMap<NodeKey, Shape>,
+        key: NodeKey,
+        sibling_separation: f64,
+        tree_distance: f64,
+    ) -> Shape {
+        let node = &tree.nodes[key];
+        let half_width = node.size.width / 2.0;
+        let Some((&first, rest)) = node.children.split_first() else {
+            return Shape {
+                child_offsets: vec![],
+                left: vec![-half_width],
+                right: vec![half_width],
+            };
+        };
+
+        // Contours of the children placed so far, relative to the first child's center.
+        let first = &shapes[first];
+        let mut left = first.left.clone();
+        let mut right = first.right.clone();
+        let mut offsets = vec![0.0];
+        for &child in rest {
+            let child = &shapes[child];
+            let offset = right
+                .iter()
+                .zip(&child.left)
+                .enumerate()
+                .map(|(depth, (right, left))| {
+                    let gap = if depth == 0 {
+                        sibling_separation
+                    } else {
+                        tree_distance
+                    };
+                    right - left + gap
+                })
+                .fold(f64::NEG_INFINITY, f64::max);
+            for (depth, &edge) in child.right.iter().enumerate() {
+                match right.get_mut(depth) {
+                    Some(right) => *right = offset + edge,
+                    None => right.push(offset + edge),
+                }
+            }
+            left.extend(
+                child.left[left.len().min(child.left.len())..]
+                    .iter()
+                    .map(|edge| offset + edge),
+            );
+            offsets.push(offset);
+        }
+
+        let mid = (offsets[0] + offsets[offsets.len() - 1]) / 2.0;
+        Shape {
+            child_offsets: offsets.iter().map(|offset| offset - mid).collect(),
+            left: std::iter::once(-half_width)
+                .chain(left.iter().map(|edge| edge - mid))
+                .collect(),
+            right: std::iter::once(half_width)
+                .chain(right.iter().map(|edge| edge - mid))
+                .collect(),
+        }
+    }
+
+    /// Position every node from the shapes: centers from the children's offsets, shifted so the
+    /// leftmost edge is at 0, then rows by depth.
+    fn place(
+        tree: &mut Tree<_Data>,
+        shapes: &SecondaryMap<NodeKey, Shape>,
+        depths: &SecondaryMap<NodeKey, usize>,
+        level_separation: f64,
+    ) {
+        let root_shape = &shapes[tree.root];
+        let min_x = root_shape
+            .left
+            .iter()
+            .copied()
+            .fold(f64::INFINITY, f64::min);
+        let mut stack = vec![(tree.root, -min_x)];
+        while let Some((key, x)) = stack.pop() {
+            let node = &mut tree.nodes[key];
+            node.position = Position {
+                x,
+                y: depths[key] as f64,
+                modifier: 0.0,
+            };
+            stack.extend(
+                node.children
+                    .iter()
+                    .zip(&shapes[key].child_offsets)
+                    .map(|(&child, offset)| (child, x + offset)),
+            );
+        }
+        place_rows(tree, level_separation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{
+        add_children, arbitrary_tree, check_layout_properties, create_test_tree, data, expected,
+        positions, positions_after, unit_size, TestNodeData,
+    };
+    use super::*;
+    use crate::layout::ReingoldTilfordLayout;
+    use proptest::prelude::*;
+
+    fn layout_positions(tree: Tree<TestNodeData>) -> Vec<(String, f64, f64)> {
+        let mut layout = IncrementalLayout::new(tree, 1.0, 2.0, 1.0);
+        layout.update();
+        positions(layout.tree())
+    }
+
+    // Without subtrees to spread between, the layout is the same as Reingold–Tilford's.
+    #[test]
+    fn test_incremental_matches_reingold_tilford() {
+        let mut tree = Tree::new(data("root"), unit_size());
+        let root = tree.root_id();
+        let subtrees = add_children(&mut tree, root, &["a", "b"]);
+        add_children(&mut tree, subtrees[0], &["a1", "a2", "a3"]);
+        add_children(&mut tree, subtrees[1], &["b1", "b2", "b3"]);
+        for tree in [create_test_tree(), tree] {
+            let mut other = tree.clone();
+            assert_eq!(
+                layout_positions(tree),
+                positions_after(&ReingoldTilfordLayout::new(1.0, 2.0, 1.0), &mut other)
+            );
+        }
+    }
+
+    // A leaf between two subtrees that were pushed apart stays next to its left sibling.
+    #[test]
+    fn test_incremental_packs_nodes_between_left() {
+        let mut tree = Tree::new(data("root"), unit_size());
+        let root = tree.root_id();
+        let subtrees = add_children(&mut tree, root, &["a", "m", "b"]);
+        add_children(&mut tree, subtrees[0], &["a1", "a2", "a3"]);
+        add_children(&mut tree, subtrees[2], &["b1", "b2", "b3"]);
+        assert_eq!(
+            layout_positions(tree),
+            expected(&[
+                ("root", 5.5, 0.0),
+                ("a", 2.0, 2.0),
+                ("m", 4.0, 2.0),
+                ("b", 9.0, 2.0),
+                ("a1", 0.0, 4.0),
+                ("a2", 2.0, 4.0),
+                ("a3", 4.0, 4.0),
+                ("b1", 7.0, 4.0),
+                ("b2", 9.0, 4.0),
+                ("b3", 11.0, 4.0),
+            ])
+        );
+    }
+
+    // Only the new nodes and their ancestors are laid out again.
+    #[test]
+    fn test_incremental_update_counts() {
+        let mut layout = IncrementalLayout::new(create_test_tree(), 1.0, 2.0, 1.0);
+        assert!(layout.is_dirty());
+        assert_eq!(layout.update(), 7);
+        assert!(!layout.is_dirty());
+        assert_eq!(layout.update(), 0);
+
+        let root = layout.tree().root_id();
+        let child3 = layout.tree().children(root)[2];
+        let grandchild = layout.tree().children(child3)[0];
+        let new = layout.add_child(grandchild, data("child3_1_1"), unit_size());
+        layout.add_child(new, data("child3_1_1_1"), unit_size());
+        layout.add_child(child3, data("child3_4"), unit_size());
+        // The two new nodes under child3_1, child3_1 itself, child3_4, child3 and the root.
+        assert_eq!(layout.update(), 6);
+    }
+
+    // Growing a tree node by node, updating after each addition, ends with the same layout as
+    // laying out the finished tree in one go.
+    #[test]
+    fn test_incremental_matches_layout_from_scratch() {
+        let mut layout = IncrementalLayout::new(Tree::new(data("0"), unit_size()), 1.0, 2.0, 1.0);
+        layout.update();
+        let mut ids = vec![layout.tree().root_id()];
+        for i in 1..40_usize {
+            let parent = ids[(i * 7) % ids.len()];
+            let size = Size::new(0.5 + (i % 3) as f64, 0.5 + (i % 2) as f64);
+            ids.push(layout.add_child(parent, data(&i.to_string()), size));
+            if i % 3 == 0 {
+                layout.update();
+            }
+        }
+        layout.update();
+        let incremental = positions(layout.tree());
+        assert_eq!(layout_positions(layout.into_tree()), incremental);
+    }
+
+    #[test]
+    fn test_incremental_orientation() {
+        let mut layout = IncrementalLayout::new(create_test_tree(), 1.0, 2.0, 1.0)
+            .with_orientation(Orientation::LeftRight);
+        layout.update();
+        let mut other = create_test_tree();
+        let reingold_tilford =
+            ReingoldTilfordLayout::new(1.0, 2.0, 1.0).with_orientation(Orientation::LeftRight);
+        assert_eq!(
+            positions(layout.tree()),
+            positions_after(&reingold_tilford, &mut other)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_incremental_layout_properties(tree in arbitrary_tree(40)) {
+            let mut layout = IncrementalLayout::new(tree, 1.0, 2.0, 1.0);
+            layout.update();
+            check_layout_properties(layout.into_tree())?;
+        }
+    }
+}
@@ -0,0 +1,326 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+//! Orientations: transforms applied to a finished top-down layout.
+//!
+//! The tidy layout always grows the tree downwards. [`Orientation::BottomUp`] mirrors it
+//! vertically, [`Orientation::LeftRight`] runs the layout on transposed node sizes and then
+//! swaps x and y, and [`Orientation::Radial`] wraps the rows into concentric rings around the
+//! root.
+
+use serde::{Deserialize, Serialize};
+use std::f64::consts::TAU;
+
+use super::{Data, Tree};
+
+/// Which way a laid-out tree grows from its root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Orientation {
+    /// Root at the top, children below their parents.
+    #[default]
+    TopDown,
+
+    /// Root at the bottom, children above their parents.
+    BottomUp,
+
+    /// Root on the left, children to the right of their parents. Siblings are stacked by
+    /// height, and each depth is a column as wide as its widest node.
+    LeftRight,
+
+    /// Root in the middle, each depth a ring around it. Left-to-right order becomes clockwise
+    /// order starting from the top. The rings are spread out so that the first ring is at least
+    /// as long as the tree was wide; deeper rings are longer still.
+    Radial,
+}
+
+impl Orientation {
+    /// Whether the layout pass should run on nodes with width and height swapped.
+    fn is_transposed(self) -> bool {
+        self == Orientation::LeftRight
+    }
+}
+
+/// Run a top-down layout `pass` and transform its result into `orientation`. `gap` separates
+/// the two ends of the tree in a radial layout.
+pub(super) fn lay_out<_Data: Data>(
+    tree: &mut Tree<_Data>,
+    orientation: Orientation,
+    gap: f64,
+    pass: impl FnOnce(&mut Tree<_Data>),
+) {
+    let transposed = orientation.is_transposed();
+    if transposed {
+        transpose_sizes(tree);
+    }
+    pass(tree);
+    if transposed {
+        transpose_sizes(tree);
+    }
+    apply(tree, orientation, gap);
+}
+
+/// Swap the width and height of every node.
+fn transpose_sizes<_Data: Data>(tree: &mut Tree<_Data>) {
+    for node in tree.nodes.values_mut() {
+        std::mem::swap(&mut node.size.width, &mut node.size.height);
+    }
+}
+
+/// Turn a top-down layout, whose leftmost and topmost edges are at 0, into `orientation`.
+/// For [`Orientation::LeftRight`] the layout must have run on transposed sizes, which must be
+/// transposed back first. `gap` separates the two ends of the tree in a radial layout.
+fn apply<_Data: Data>(tree: &mut Tree<_Data>, orientation: Orientation, gap: f64) {
+    match orientation {
+        Orientation::TopDown => {}
+        Orientation::BottomUp => flip_vertically(tree),
+        Orientation::LeftRight => {
+            for node in tree.nodes.values_mut() {
+                std::mem::swap(&mut node.position.x, &mut node.position.y);
+            }
+        }
+        Orientation::Radial => wrap_radially(tree, gap),
+    }
+}
+
+fn flip_vertically<_Data: Data>(tree: &mut Tree<_Data>) {
+    let bottom = tree
+        .nodes
+        .values()
+        .map(|node| node.position.y + node.size.height)
+        .fold(0.0, f64::max);
+    for node in tree.nodes.values_mut() {
+        node.position.y = bottom - node.position.y - node.size.height;
+    }
+}
+
+fn wrap_radially<_Data: Data>(tree: &mut Tree<_Data>, gap: f64) {
+    let center = |x: f64, size: f64| x + size / 2.0;
+    let root = &tree.nodes[tree.root];
+    let root_y = center(root.position.y, root.size.height);
+    let span = tree
+        .nodes
+        .values()
+        .map(|node| node.position.x + node.size.width)
+        .fold(0.0, f64::max)
+        + gap;
+    let first_ring = tree
+        .nodes
+        .values()
+        .map(|node| center(node.position.y, node.size.height) - root_y)
+        .filter(|&radius| radius > 0.0)
+        .fold(f64::INFINITY, f64::min);
+    let scale = if first_ring.is_finite() {
+        (span / (TAU * first_ring)).max(1.0)
+    } else {
+        1.0
+    };
+
+    for node in tree.nodes.values_mut() {
+        let radius = scale * (center(node.position.y, node.size.height) - root_y);
+        let angle = TAU * center(node.position.x, node.size.width) / span;
+        node.position.x = radius * angle.sin() - node.size.width / 2.0;
+        node.position.y = -radius * angle.cos() - node.size.height / 2.0;
+    }
+
+    let (min_x, min_y) = tree
+        .nodes
+        .values()
+        .fold((f64::INFINITY, f64::INFINITY), |(x, y), node| {
+            (x.min(node.position.x), y.min(node.position.y))
+        });
+    for node in tree.nodes.values_mut() {
+        node.position.x -= min_x;
+        node.position.y -= min_y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{Layout, NodeId, ReingoldTilfordLayout, Size};
+    use proptest::prelude::*;
+
+    fn layout(tree: &mut Tree<&'static str>, orientation: Orientation) {
+        ReingoldTilfordLayout::new(1.0, 2.0, 1.0)
+            .with_orientation(orientation)
+            .layout(tree);
+    }
+
+    fn position(tree: &Tree<&'static str>, id: NodeId) -> (f64, f64) {
+        let position = tree.position(id).unwrap();
+        (position.x, position.y)
+    }
+
+    fn center(tree: &Tree<&'static str>, id: NodeId) -> (f64, f64) {
+        let (x, y) = position(tree, id);
+        let size = tree.size(id).unwrap();
+        (x + size.width / 2.0, y + size.height / 2.0)
+    }
+
+    /// A root with children "a" and "b", and "a" with children "a1" and "a2", all unit sized.
+    fn small_tree() -> (Tree<&'static str>, Vec<NodeId>) {
+        let unit = Size::new(1.0, 1.0);
+        let mut tree = Tree::new("root", unit);
+        let root = tree.root_id();
+        let a = tree.add_child(root, "a", unit);
+        let b = tree.add_child(root, "b", unit);
+        let a1 = tree.add_child(a, "a1", unit);
+        let a2 = tree.add_child(a, "a2", unit);
+        (tree, vec![root, a, b, a1, a2])
+    }
+
+    #[test]
+    fn test_bottom_up_mirrors_top_down() {
+        let (mut top_down, ids) = small_tree();
+        layout(&mut top_down, Orientation::TopDown);
+        let (mut bottom_up, bottom_up_ids) = small_tree();
+        layout(&mut bottom_up, Orientation::BottomUp);
+        for (&id, &other) in ids.iter().zip(&bottom_up_ids) {
+            let (x, y) = position(&top_down, id);
+            assert_eq!(position(&bottom_up, other), (x, 4.0 - y));
+        }
+    }
+
+    // Depths become columns as wide as their widest node, and siblings are stacked by height.
+    #[test]
+    fn test_left_right_uses_heights_for_siblings() {
+        let mut tree = Tree::new("root", Size::new(2.0, 1.0));
+        let root = tree.root_id();
+        let tall = tree.add_child(root, "tall", Size::new(1.0, 3.0));
+        let short = tree.add_child(root, "short", Size::new(1.0, 1.0));
+        layout(&mut tree, Orientation::LeftRight);
+
+        assert_eq!(position(&tree, tall), (3.0, 0.0));
+        assert_eq!(position(&tree, short), (3.0, 4.0));
+        // Centered between the children's centers, 1.5 and 4.5.
+        assert_eq!(position(&tree, root), (0.0, 2.5));
+        assert_eq!(tree.size(tall), Some(Size::new(1.0, 3.0)));
+    }
+
+    #[test]
+    fn test_radial() {
+        let (mut tree, ids) = small_tree();
+        layout(&mut tree, Orientation::Radial);
+        let (root, a, b, a1, a2) = (ids[0], ids[1], ids[2], ids[3], ids[4]);
+
+        let (root_x, root_y) = center(&tree, root);
+        let polar = |id| {
+            let (x, y) = center(&tree, id);
+            let (dx, dy) = (x - root_x, y - root_y);
+            // Clockwise from the top, in [0, 2π).
+            let angle = dx.atan2(-dy).rem_euclid(TAU);
+            ((dx * dx + dy * dy).sqrt(), angle)
+        };
+
+        // Each depth is a ring around the root, further out than the last.
+        let (a_radius, a_angle) = polar(a);
+        let (b_radius, b_angle) = polar(b);
+        let (a1_radius, a1_angle) = polar(a1);
+        let (a2_radius, a2_angle) = polar(a2);
+        assert!((a_radius - b_radius).abs() < 1e-9);
+        assert!((a1_radius - a2_radius).abs() < 1e-9);
+        assert!(a1_radius > a_radius && a_radius > 0.0);
+
+        // Left-to-right order becomes clockwise order.
+        assert!(a1_angle < a_angle && a_angle < a2_angle && a2_angle < b_angle);
+
+        // Nothing is left of or above the origin, and something touches each axis.
+        let positions: Vec<(f64, f64)> = ids.iter().map(|&id| position(&tree, id)).collect();
+        let min_x = positions.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+        let min_y = positions.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+        assert!(min_x.abs() < 1e-9 && min_y.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_radial_single_node() {
+        let mut tree = Tree::new("root", Size::new(2.0, 1.0));
+        layout(&mut tree, Orientation::Radial);
+        assert_eq!(position(&tree, tree.root_id()), (0.0, 0.0));
+    }
+
+    /// Random trees of up to `max_nodes` nodes with sizes between 0.5 and 3.
+    fn arbitrary_tree(max_nodes: usize) -> impl Strategy<Value = Tree<&'static str>> {
+        let size = (0.5..3.0, 0.5..3.0).prop_map(|(width, height)| Size::new(width, height));
+        (
+            size.clone(),
+            prop::collection::vec((any::<prop::sample::Index>(), size), 0..max_nodes),
+        )
+            .prop_map(|(root_size, nodes)| {
+                let mut tree = Tree::new("node", root_size);
+                let mut ids = vec![tree.root_id()];
+                for (parent, size) in nodes {
+                    let parent = ids[parent.index(ids.len())];
+                    ids.push(tree.add_child(parent, "node", size));
+                }
+                tree
+            })
+    }
+
+    fn orientation() -> impl Strategy<Value = Orientation> {
+        prop_oneof![
+            Just(Orientation::TopDown),
+            Just(Orientation::BottomUp),
+            Just(Orientation::LeftRight),
+        ]
+    }
+
+    proptest! {
+        // The rectilinear orientations never make nodes overlap, keep the tree touching both
+        // axes, and put every child on the far side of its parent along the growth direction.
+        #[test]
+        fn test_orientation_properties(
+            mut tree in arbitrary_tree(30),
+            orientation in orientation(),
+        ) {
+            layout(&mut tree, orientation);
+            let boxes: Vec<(NodeId, f64, f64, f64, f64)> = tree
+                .keys
+                .keys()
+                .map(|&id| {
+                    let (x, y) = position(&tree, id);
+                    let size = tree.size(id).unwrap();
+                    (id, x, y, x + size.width, y + size.height)
+                })
+                .collect();
+            for (i, a) in boxes.iter().enumerate() {
+                for b in &boxes[i + 1..] {
+                    let overlap = a.1 < b.3 - 1e-9
+                        && b.1 < a.3 - 1e-9
+                        && a.2 < b.4 - 1e-9
+                        && b.2 < a.4 - 1e-9;
+                    prop_assert!(!overlap, "{:?} overlaps {:?}", a, b);
+                }
+            }
+            let min_x = boxes.iter().map(|b| b.1).fold(f64::INFINITY, f64::min);
+            let min_y = boxes.iter().map(|b| b.2).fold(f64::INFINITY, f64::min);
+            prop_assert!(min_x.abs() < 1e-9 && min_y.abs() < 1e-9);
+
+            for &(id, left, top, _, bottom) in &boxes {
+                let Some(parent) = tree.parent(id) else {
+                    continue;
+                };
+                let (parent_x, parent_y) = position(&tree, parent);
+                let parent_size = tree.size(parent).unwrap();
+                match orientation {
+                    Orientation::TopDown => prop_assert!(top > parent_y + parent_size.height),
+                    Orientation::BottomUp => prop_assert!(bottom < parent_y),
+                    Orientation::LeftRight => prop_assert!(left > parent_x + parent_size.width),
+                    Orientation::Radial => unreachable!(),
+                }
+            }
+        }
+    }
+}
@@ -1 +1,34 @@
-mod layout;
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+//! Drawing trees, such as Monte Carlo search trees: tidy layouts in [`layout`], connectors between
+//! laid-out nodes in [`edges`], an adapter from recorded search trees in [`mcts`],
+//! serializable snapshots of laid-out trees in [`result`], and PNG images of them in [`raster`].
+
+pub mod edges;
+pub mod layout;
+pub mod mcts;
+pub mod raster;
+pub mod result;
+
+pub use edges::{route_edges, route_labelled_edges, Edge, EdgePath, EdgeStyle, Point};
+pub use layout::{
+    BuchheimLayout, DebugNode, DebugTree, IncrementalLayout, Layout, NodeId, Orientation, Position,
+    ReingoldTilfordLayout, Size, Tree,
+};
+pub use mcts::{layout_mcts_tree, mcts_tree, Color, MctsNodeData, MctsStyle};
+pub use raster::{rasterize, render_png, save_png, RasterError, RasterStyle};
+pub use result::{BoundingBox, LayoutNode, LayoutResult};
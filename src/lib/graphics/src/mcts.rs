@@ -0,0 +1,544 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+//! Adapter from the debug trees recorded by `monte_carlo_tree_search::Mcts` to layout trees.
+//!
+//! Each search node becomes a layout node whose size grows with its share of the root's visits
+//! and whose color runs from red (never wins) to green (always wins).
+//!
+//! Search trees run to thousands of nodes, too many to draw legibly. Children with few visits,
+//! or below a maximum depth, can be folded into a single summary node per parent, such as
+//! "+12 children, 3% of visits".
+
+use monte_carlo_tree_search::{Action, Float, Int, MctsNodeForSerialization, State};
+use serde::{Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+
+use crate::edges::{route_labelled_edges, EdgeStyle};
+use crate::layout::{Layout, Size, Tree};
+use crate::result::LayoutResult;
+
+/// An RGB color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub fn new(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b }
+    }
+
+    /// The color as a CSS hex string, e.g. "#ff0000".
+    pub fn to_hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
+    /// Linear interpolation from `self` (t = 0) to `other` (t = 1). `t` is clamped to [0, 1].
+    pub fn lerp(&self, other: &Color, t: f64) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let channel =
+            |a: u8, b: u8| (f64::from(a) + (f64::from(b) - f64::from(a)) * t).round() as u8;
+        Color {
+            r: channel(self.r, other.r),
+            g: channel(self.g, other.g),
+            b: channel(self.b, other.b),
+        }
+    }
+}
+
+/// Serialized as its hex string, ready for CSS.
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+/// How search statistics map to node sizes and colors, and which nodes are shown.
+///
+/// A node's width and height are `min_size + (max_size - min_size) * sqrt(visits / root
+/// visits)`, so area is roughly proportional to visits. Its color is `loss_color` blended
+/// towards `win_color` by its win rate; unvisited nodes get `unvisited_color` and summary
+/// nodes `summary_color`.
+///
+/// Children with fewer than `min_visits` visits, and all children of nodes at `max_depth`, are
+/// folded into one summary node per parent. By default every node is shown.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MctsStyle {
+    pub min_size: f64,
+    pub max_size: f64,
+    pub loss_color: Color,
+    pub win_color: Color,
+    pub unvisited_color: Color,
+    pub summary_color: Color,
+    pub min_visits: Int,
+    pub max_depth: Option<usize>,
+}
+
+impl Default for MctsStyle {
+    fn default() -> Self {
+        MctsStyle {
+            min_size: 0.25,
+            max_size: 1.0,
+            loss_color: Color::new(0xd7, 0x30, 0x27),
+            win_color: Color::new(0x1a, 0x98, 0x50),
+            unvisited_color: Color::new(0xbd, 0xbd, 0xbd),
+            summary_color: Color::new(0x9e, 0x9a, 0xc8),
+            min_visits: 0,
+            max_depth: None,
+        }
+    }
+}
+
+impl MctsStyle {
+    pub fn with_sizes(mut self, min_size: f64, max_size: f64) -> Self {
+        self.min_size = min_size;
+        self.max_size = max_size;
+        self
+    }
+
+    pub fn with_colors(mut self, loss_color: Color, win_color: Color) -> Self {
+        self.loss_color = loss_color;
+        self.win_color = win_color;
+        self
+    }
+
+    pub fn with_min_visits(mut self, min_visits: Int) -> Self {
+        self.min_visits = min_visits;
+        self
+    }
+
+    /// Show nodes down to `max_depth`, the root being at depth 0.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Whether a child at `depth` with `visits` visits is folded into its parent's summary.
+    fn collapses(&self, depth: usize, visits: Int) -> bool {
+        visits < self.min_visits || self.max_depth.is_some_and(|max_depth| depth > max_depth)
+    }
+
+    fn size(&self, visits: Int, root_visits: Int) -> Size {
+        let share = if root_visits > 0 {
+            (f64::from(visits) / f64::from(root_visits)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let side = self.min_size + (self.max_size - self.min_size) * share.sqrt();
+        Size::new(side, side)
+    }
+
+    fn color(&self, win_rate: Option<f64>) -> Color {
+        match win_rate {
+            Some(win_rate) => self.loss_color.lerp(&self.win_color, win_rate),
+            None => self.unvisited_color,
+        }
+    }
+}
+
+/// Sibling subtrees folded into a single node, see [`MctsStyle`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Summary {
+    /// How many siblings were folded.
+    pub children: usize,
+    /// How many search nodes their subtrees held.
+    pub nodes: usize,
+    /// Their visits as a fraction of the root's.
+    pub share_of_visits: f64,
+}
+
+/// E.g. "+12 children, 3% of visits".
+impl Display for Summary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let noun = if self.children == 1 {
+            "child"
+        } else {
+            "children"
+        };
+        write!(
+            f,
+            "+{} {}, {:.0}% of visits",
+            self.children,
+            noun,
+            self.share_of_visits * 100.0
+        )
+    }
+}
+
+/// The data carried by each node of a laid-out search tree.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MctsNodeData<_Action: Action> {
+    /// The action that led to this node; None for the root and for summaries.
+    pub action: Option<_Action>,
+    /// For a summary, the total over the folded subtrees' roots.
+    pub visits: Int,
+    /// Total reward, which is the number of wins if playouts are only won or not. For a
+    /// summary, the total over the folded subtrees' roots.
+    pub wins: Float,
+    /// wins / visits, or None if the node was never visited.
+    pub win_rate: Option<f64>,
+    pub color: Color,
+    /// Some if this node stands in for folded siblings.
+    pub summary: Option<Summary>,
+}
+
+/// Convert a search tree into a layout tree, keeping the search's child order (most visited
+/// first), with a parent's summary node, if any, after its other children. Positions are unset
+/// until a layout runs.
+pub fn mcts_tree<_State: State<_Action>, _Action: Action>(
+    root: &MctsNodeForSerialization<_State, _Action>,
+    style: &MctsStyle,
+) -> Tree<MctsNodeData<_Action>> {
+    let root_visits = root.visits();
+    let (data, size) = node_data(root, root_visits, style);
+    let mut tree = Tree::new(data, size);
+    let mut stack = vec![(tree.root_id(), root, 0)];
+    while let Some((id, node, depth)) = stack.pop() {
+        let mut folded = vec![];
+        for child in node.children() {
+            if style.collapses(depth + 1, child.visits()) {
+                folded.push(child);
+                continue;
+            }
+            let (data, size) = node_data(child, root_visits, style);
+            let child_id = tree.add_child(id, data, size);
+            stack.push((child_id, child, depth + 1));
+        }
+        if !folded.is_empty() {
+            let (data, size) = summary_data(&folded, root_visits, style);
+            tree.add_child(id, data, size);
+        }
+    }
+    tree
+}
+
+/// Convert a search tree and lay it out in one call, returning a snapshot ready to draw or
+/// serialize to JSON. The snapshot includes straight edges labelled with the actions that lead
+/// to each child, or with the summary of folded children.
+pub fn layout_mcts_tree<_State, _Action, _Layout>(
+    root: &MctsNodeForSerialization<_State, _Action>,
+    style: &MctsStyle,
+    layout: &_Layout,
+) -> LayoutResult<MctsNodeData<_Action>>
+where
+    _State: State<_Action>,
+    _Action: Action,
+    _Layout: Layout<MctsNodeData<_Action>>,
+{
+    let mut tree = mcts_tree(root, style);
+    layout.layout(&mut tree);
+    let edges = route_labelled_edges(
+        &tree,
+        layout.orientation(),
+        EdgeStyle::Straight,
+        |data| match (&data.summary, data.action) {
+            (Some(summary), _) => Some(summary.to_string()),
+            (None, action) => action.map(|action| format!("{:?}", action)),
+        },
+    );
+    LayoutResult::from(&tree).with_edges(edges)
+}
+
+fn node_data<_State: State<_Action>, _Action: Action>(
+    node: &MctsNodeForSerialization<_State, _Action>,
+    root_visits: Int,
+    style: &MctsStyle,
+) -> (MctsNodeData<_Action>, Size) {
+    let visits = node.visits();
+    let wins = node.wins();
+    let win_rate = (visits > 0).then(|| wins / f64::from(visits));
+    let data = MctsNodeData {
+        action: node.action(),
+        visits,
+        wins,
+        win_rate,
+        color: style.color(win_rate),
+        summary: None,
+    };
+    (data, style.size(visits, root_visits))
+}
+
+fn summary_data<_State: State<_Action>, _Action: Action>(
+    folded: &[&MctsNodeForSerialization<_State, _Action>],
+    root_visits: Int,
+    style: &MctsStyle,
+) -> (MctsNodeData<_Action>, Size) {
+    let visits: Int = folded.iter().map(|node| node.visits()).sum();
+    let wins: Float = folded.iter().map(|node| node.wins()).sum();
+    let mut nodes = 0;
+    let mut stack = folded.to_vec();
+    while let Some(node) = stack.pop() {
+        nodes += 1;
+        stack.extend(node.children());
+    }
+    let share_of_visits = if root_visits > 0 {
+        f64::from(visits) / f64::from(root_visits)
+    } else {
+        0.0
+    };
+    let data = MctsNodeData {
+        action: None,
+        visits,
+        wins,
+        win_rate: (visits > 0).then(|| wins / f64::from(visits)),
+        color: style.summary_color,
+        summary: Some(Summary {
+            children: folded.len(),
+            nodes,
+            share_of_visits,
+        }),
+    };
+    (data, style.size(visits, root_visits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::ReingoldTilfordLayout;
+    use mcts_tic_tac_toe::{Action as TicTacToeAction, Player, State as TicTacToeState};
+    use monte_carlo_tree_search::shared_rng;
+    use tic_tac_toe_logic::Move;
+
+    type Node = MctsNodeForSerialization<TicTacToeState, TicTacToeAction>;
+
+    fn action(column: usize, row: usize) -> Option<TicTacToeAction> {
+        Some(TicTacToeAction(Move { column, row }))
+    }
+
+    // Root with 100 visits; the first child has two children of its own.
+    fn search_tree() -> Node {
+        Node::new(
+            None,
+            100,
+            37.0,
+            vec![
+                Node::new(
+                    action(1, 1),
+                    64,
+                    48.0,
+                    vec![
+                        Node::new(action(0, 0), 40, 10.0, vec![]),
+                        Node::new(action(2, 2), 0, 0.0, vec![]),
+                    ],
+                ),
+                Node::new(action(0, 1), 36, 0.0, vec![]),
+            ],
+        )
+    }
+
+    fn search_count(node: &Node) -> usize {
+        1 + node.children().map(search_count).sum::<usize>()
+    }
+
+    #[test]
+    fn test_color_lerp_and_hex() {
+        let black = Color::new(0, 0, 0);
+        let white = Color::new(255, 255, 255);
+        assert_eq!(black.lerp(&white, 0.0), black);
+        assert_eq!(black.lerp(&white, 1.0), white);
+        assert_eq!(black.lerp(&white, 0.5).to_hex(), "#808080");
+        assert_eq!(black.lerp(&white, 2.0), white);
+    }
+
+    #[test]
+    fn test_mcts_tree_keeps_structure_and_order() {
+        let tree = mcts_tree(&search_tree(), &MctsStyle::default());
+        assert_eq!(tree.len(), 5);
+
+        let root = tree.root_id();
+        let children = tree.children(root);
+        assert_eq!(children.len(), 2);
+        let first = tree.data(children[0]).unwrap();
+        assert_eq!(first.action, action(1, 1));
+        assert_eq!((first.visits, first.wins), (64, 48.0));
+        assert_eq!(tree.data(children[1]).unwrap().action, action(0, 1));
+
+        let grandchildren = tree.children(children[0]);
+        assert_eq!(grandchildren.len(), 2);
+        assert_eq!(tree.data(grandchildren[0]).unwrap().action, action(0, 0));
+        assert!(tree.children(children[1]).is_empty());
+    }
+
+    #[test]
+    fn test_sizes_scale_with_visits() {
+        let style = MctsStyle::default().with_sizes(0.5, 2.0);
+        let tree = mcts_tree(&search_tree(), &style);
+        let root = tree.root_id();
+        let children = tree.children(root);
+        let grandchildren = tree.children(children[0]);
+
+        assert_eq!(tree.size(root).unwrap(), Size::new(2.0, 2.0));
+        // sqrt(64 / 100) = 0.8, so 0.5 + 1.5 * 0.8.
+        assert!((tree.size(children[0]).unwrap().width - 1.7).abs() < 1e-12);
+        assert_eq!(tree.size(grandchildren[1]).unwrap().width, 0.5);
+    }
+
+    #[test]
+    fn test_colors_follow_win_rate() {
+        let style = MctsStyle::default();
+        let tree = mcts_tree(&search_tree(), &style);
+        let children = tree.children(tree.root_id());
+        let grandchildren = tree.children(children[0]);
+
+        let best = tree.data(children[0]).unwrap();
+        assert_eq!(best.win_rate, Some(0.75));
+        assert_eq!(best.color, style.loss_color.lerp(&style.win_color, 0.75));
+
+        let losing = tree.data(children[1]).unwrap();
+        assert_eq!(losing.win_rate, Some(0.0));
+        assert_eq!(losing.color, style.loss_color);
+
+        let unvisited = tree.data(grandchildren[1]).unwrap();
+        assert_eq!(unvisited.win_rate, None);
+        assert_eq!(unvisited.color, style.unvisited_color);
+    }
+
+    #[test]
+    fn test_min_visits_folds_children() {
+        let style = MctsStyle::default().with_min_visits(30);
+        let tree = mcts_tree(&search_tree(), &style);
+        assert_eq!(tree.len(), 5);
+
+        let children = tree.children(tree.root_id());
+        assert_eq!(children.len(), 2);
+        let grandchildren = tree.children(children[0]);
+        assert_eq!(tree.data(grandchildren[0]).unwrap().action, action(0, 0));
+
+        let summary = tree.data(grandchildren[1]).unwrap();
+        assert_eq!(summary.action, None);
+        assert_eq!(summary.color, style.summary_color);
+        assert_eq!(summary.win_rate, None);
+        let expected = Summary {
+            children: 1,
+            nodes: 1,
+            share_of_visits: 0.0,
+        };
+        assert_eq!(summary.summary, Some(expected));
+        assert_eq!(expected.to_string(), "+1 child, 0% of visits");
+    }
+
+    #[test]
+    fn test_max_depth_folds_children() {
+        let tree = mcts_tree(&search_tree(), &MctsStyle::default().with_max_depth(1));
+        assert_eq!(tree.len(), 4);
+        let children = tree.children(tree.root_id());
+        let folded = tree.children(children[0]);
+        assert_eq!(folded.len(), 1);
+        let summary = tree.data(folded[0]).unwrap();
+        assert_eq!((summary.visits, summary.wins), (40, 10.0));
+        assert_eq!(
+            summary.summary.unwrap().to_string(),
+            "+2 children, 40% of visits"
+        );
+        assert!(tree.children(children[1]).is_empty());
+
+        // At depth 0 the whole tree below the root is one summary.
+        let tree = mcts_tree(&search_tree(), &MctsStyle::default().with_max_depth(0));
+        assert_eq!(tree.len(), 2);
+        let summary = tree.data(tree.children(tree.root_id())[0]).unwrap();
+        assert_eq!(summary.summary.unwrap().nodes, 4);
+        assert_eq!(summary.visits, 100);
+    }
+
+    #[test]
+    fn test_layout_mcts_tree() {
+        let layout = ReingoldTilfordLayout::new(1.0, 1.0, 1.0);
+        let tree = layout_mcts_tree(&search_tree(), &MctsStyle::default(), &layout);
+        let root = tree.root().unwrap();
+        assert_eq!(root.data.visits, 100);
+        assert_eq!(tree.len(), 5);
+
+        // The root is centered over its children, which sit in the next row down.
+        let left = tree.node(root.children[0]).unwrap();
+        let right = tree.node(root.children[1]).unwrap();
+        let (root_x, root_y) = root.center();
+        let (left_x, left_y) = left.center();
+        let (right_x, right_y) = right.center();
+        assert_eq!(root_y, 0.5);
+        assert!((left_y - right_y).abs() < 1e-9);
+        assert!(left_y > root_y + 1.0);
+        assert!(left_x + left.size.width / 2.0 < right_x - right.size.width / 2.0);
+        assert!((root_x - (left_x + right_x) / 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_layout_recorded_search() {
+        let mut mcts = monte_carlo_tree_search::Mcts::new(
+            TicTacToeState::new(Player::Player1, Player::Player1),
+            monte_carlo_tree_search::MctsArgs {
+                iteration_limit: monte_carlo_tree_search::IterationLimitKind::Iterations(50),
+                playouts_per_simulation: 1,
+                max_depth_per_playout: 9,
+                rng: shared_rng(42),
+                debug_track_trees: monte_carlo_tree_search::DebugTrackTrees::Track,
+                ..monte_carlo_tree_search::MctsArgs::default()
+            },
+        );
+        mcts.run();
+
+        let trees = mcts.debug_trees().unwrap();
+        let last = trees.last().unwrap();
+        let layout = ReingoldTilfordLayout::new(1.0, 1.0, 1.0);
+        let tree = layout_mcts_tree(last, &MctsStyle::default(), &layout);
+        let root = tree.root().unwrap();
+        assert_eq!(root.data.visits, last.visits());
+        assert_eq!(root.children.len(), last.children().count());
+        assert_eq!(tree.len(), search_count(last));
+
+        // Folding rarely visited children shrinks the tree but keeps every parent's visits
+        // accounted for among its children.
+        let style = MctsStyle::default().with_min_visits(5);
+        let folded = layout_mcts_tree(last, &style, &layout);
+        assert!(folded.len() < tree.len());
+        let root = folded.root().unwrap();
+        let child_visits: Int = root
+            .children
+            .iter()
+            .map(|&child| folded.node(child).unwrap().data.visits)
+            .sum();
+        assert_eq!(
+            child_visits,
+            last.children().map(|child| child.visits()).sum::<Int>()
+        );
+        let summaries = folded
+            .nodes
+            .iter()
+            .filter(|node| node.data.summary.is_some());
+        assert!(summaries.count() > 0);
+    }
+
+    #[test]
+    fn test_json_export() {
+        let style = MctsStyle::default();
+        let layout = ReingoldTilfordLayout::new(1.0, 1.0, 1.0);
+        let tree = layout_mcts_tree(&search_tree(), &style, &layout);
+        let value: serde_json::Value = serde_json::from_str(&tree.to_json().unwrap()).unwrap();
+        let root = &value["nodes"][0];
+        assert_eq!(root["data"]["visits"], 100);
+        assert_eq!(root["data"]["action"], serde_json::Value::Null);
+        let color = style.loss_color.lerp(&style.win_color, 0.37);
+        assert_eq!(root["data"]["color"], color.to_hex());
+        assert!(value["nodes"][1]["data"]["action"].is_object());
+
+        // One edge per child, labelled with the action.
+        assert_eq!(tree.edges.len(), 4);
+        let label = value["edges"][0]["label"].as_str().unwrap();
+        assert!(label.contains("column: 1, row: 1"), "{}", label);
+    }
+}
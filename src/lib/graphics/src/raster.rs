@@ -0,0 +1,317 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+//! Raster output: draw a [`LayoutResult`] into a PNG image, without an SVG toolchain.
+//!
+//! Nodes are drawn as filled, outlined boxes and edges as lines, polylines or curves following
+//! their [`EdgePath`]s. Labels are not drawn; use the JSON output for labelled diagrams.
+
+use std::path::Path;
+
+use thiserror::Error;
+use tiny_skia::{Paint, PathBuilder, Pixmap, Rect, Stroke, Transform};
+
+use crate::edges::{EdgePath, Point};
+use crate::mcts::Color;
+use crate::result::LayoutResult;
+
+/// Images wider or taller than this many pixels are refused rather than allocated.
+pub const MAX_SIDE: u32 = 16_384;
+
+#[derive(Debug, Error)]
+pub enum RasterError {
+    #[error("image would be {width}x{height} pixels, more than {MAX_SIDE} on a side")]
+    TooLarge { width: u32, height: u32 },
+    #[error("failed to encode PNG: {0}")]
+    Encode(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// How a laid-out tree maps to pixels.
+///
+/// Layout units are multiplied by `scale`, and `margin` pixels of `background` surround the
+/// drawing. Edges are `edge_width` pixels wide; node outlines are one pixel of `outline_color`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RasterStyle {
+    pub scale: f64,
+    pub margin: f64,
+    pub background: Color,
+    pub edge_color: Color,
+    pub edge_width: f64,
+    pub outline_color: Color,
+}
+
+impl Default for RasterStyle {
+    fn default() -> Self {
+        RasterStyle {
+            scale: 40.0,
+            margin: 20.0,
+            background: Color::new(0xff, 0xff, 0xff),
+            edge_color: Color::new(0x96, 0x96, 0x96),
+            edge_width: 1.5,
+            outline_color: Color::new(0x42, 0x42, 0x42),
+        }
+    }
+}
+
+impl RasterStyle {
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_margin(mut self, margin: f64) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn with_background(mut self, background: Color) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn with_edges(mut self, edge_color: Color, edge_width: f64) -> Self {
+        self.edge_color = edge_color;
+        self.edge_width = edge_width;
+        self
+    }
+}
+
+/// Draw `result` into a new image just large enough to hold it. Each node is filled with the
+/// color `fill` picks from its data, e.g. `|data| data.color` for a search tree from
+/// [`crate::layout_mcts_tree`].
+pub fn rasterize<_Data>(
+    result: &LayoutResult<_Data>,
+    style: &RasterStyle,
+    fill: impl Fn(&_Data) -> Color,
+) -> Result<Pixmap, RasterError> {
+    // No nodes: an empty drawing at the origin.
+    let bounds = result.bounds().unwrap_or_default();
+    let side = |extent: f64| (extent * style.scale + 2.0 * style.margin).ceil().max(1.0);
+    let (width, height) = (side(bounds.width()), side(bounds.height()));
+    if width > f64::from(MAX_SIDE) || height > f64::from(MAX_SIDE) {
+        return Err(RasterError::TooLarge {
+            width: width as u32,
+            height: height as u32,
+        });
+    }
+    let (width, height) = (width as u32, height as u32);
+    let mut pixmap = Pixmap::new(width, height).ok_or(RasterError::TooLarge { width, height })?;
+    pixmap.fill(skia_color(style.background));
+
+    let to_pixels = |point: Point| {
+        (
+            ((point.x - bounds.min_x) * style.scale + style.margin) as f32,
+            ((point.y - bounds.min_y) * style.scale + style.margin) as f32,
+        )
+    };
+
+    let edge_paint = paint(style.edge_color);
+    let edge_stroke = Stroke {
+        width: style.edge_width as f32,
+        ..Stroke::default()
+    };
+    for edge in &result.edges {
+        if let Some(path) = edge_path(&edge.path, to_pixels) {
+            pixmap.stroke_path(
+                &path,
+                &edge_paint,
+                &edge_stroke,
+                Transform::identity(),
+                None,
+            );
+        }
+    }
+
+    let outline_paint = paint(style.outline_color);
+    let outline_stroke = Stroke::default();
+    for node in &result.nodes {
+        let (x, y) = to_pixels(Point::new(node.position.x, node.position.y));
+        let width = (node.size.width * style.scale) as f32;
+        let height = (node.size.height * style.scale) as f32;
+        let Some(rect) = Rect::from_xywh(x, y, width, height) else {
+            continue;
+        };
+        pixmap.fill_rect(rect, &paint(fill(&node.data)), Transform::identity(), None);
+        let outline = PathBuilder::from_rect(rect);
+        pixmap.stroke_path(
+            &outline,
+            &outline_paint,
+            &outline_stroke,
+            Transform::identity(),
+            None,
+        );
+    }
+    Ok(pixmap)
+}
+
+/// [`rasterize`] and encode the image as PNG.
+pub fn render_png<_Data>(
+    result: &LayoutResult<_Data>,
+    style: &RasterStyle,
+    fill: impl Fn(&_Data) -> Color,
+) -> Result<Vec<u8>, RasterError> {
+    rasterize(result, style, fill)?
+        .encode_png()
+        .map_err(|err| RasterError::Encode(err.to_string()))
+}
+
+/// [`render_png`] and write the image to `path`.
+pub fn save_png<_Data>(
+    result: &LayoutResult<_Data>,
+    style: &RasterStyle,
+    fill: impl Fn(&_Data) -> Color,
+    path: impl AsRef<Path>,
+) -> Result<(), RasterError> {
+    std::fs::write(path, render_png(result, style, fill)?)?;
+    Ok(())
+}
+
+fn edge_path(path: &EdgePath, to_pixels: impl Fn(Point) -> (f32, f32)) -> Option<tiny_skia::Path> {
+    let mut builder = PathBuilder::new();
+    let (x, y) = to_pixels(path.start());
+    builder.move_to(x, y);
+    match path {
+        EdgePath::Line { to, .. } => {
+            let (x, y) = to_pixels(*to);
+            builder.line_to(x, y);
+        }
+        EdgePath::Polyline { points } => {
+            for &point in &points[1..] {
+                let (x, y) = to_pixels(point);
+                builder.line_to(x, y);
+            }
+        }
+        EdgePath::CubicBezier {
+            control1,
+            control2,
+            to,
+            ..
+        } => {
+            let (x1, y1) = to_pixels(*control1);
+            let (x2, y2) = to_pixels(*control2);
+            let (x, y) = to_pixels(*to);
+            builder.cubic_to(x1, y1, x2, y2, x, y);
+        }
+    }
+    builder.finish()
+}
+
+fn paint(color: Color) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color(skia_color(color));
+    paint.anti_alias = true;
+    paint
+}
+
+fn skia_color(color: Color) -> tiny_skia::Color {
+    tiny_skia::Color::from_rgba8(color.r, color.g, color.b, 0xff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edges::{route_edges, EdgeStyle};
+    use crate::layout::{Layout, Orientation, ReingoldTilfordLayout, Size, Tree};
+
+    const RED: Color = Color {
+        r: 0xff,
+        g: 0,
+        b: 0,
+    };
+
+    /// A root with two unit-size children, laid out with unit gaps and straight edges.
+    fn laid_out(orientation: Orientation, style: EdgeStyle) -> LayoutResult<&'static str> {
+        let size = Size::new(1.0, 1.0);
+        let mut tree = Tree::new("root", size);
+        let root = tree.root_id();
+        tree.add_child(root, "a", size);
+        tree.add_child(root, "b", size);
+        let layout = ReingoldTilfordLayout::new(1.0, 1.0, 1.0).with_orientation(orientation);
+        layout.layout(&mut tree);
+        LayoutResult::from(&tree).with_edges(route_edges(&tree, orientation, style))
+    }
+
+    fn pixel(pixmap: &Pixmap, x: u32, y: u32) -> (u8, u8, u8) {
+        let pixel = pixmap.pixel(x, y).unwrap();
+        (pixel.red(), pixel.green(), pixel.blue())
+    }
+
+    // The tree is 3 x 3 units, so at 10 pixels per unit with a 5 pixel margin the image is
+    // 40 x 40. Node interiors get their fill color and the margin the background.
+    #[test]
+    fn test_rasterize_size_and_colors() {
+        let result = laid_out(Orientation::TopDown, EdgeStyle::Straight);
+        let style = RasterStyle::default().with_scale(10.0).with_margin(5.0);
+        let pixmap = rasterize(&result, &style, |_| RED).unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (40, 40));
+        assert_eq!(pixel(&pixmap, 1, 1), (0xff, 0xff, 0xff));
+        // Centers of the root (x 1..2, y 0..1) and of child "b" (x 2..3, y 2..3).
+        assert_eq!(pixel(&pixmap, 20, 10), (0xff, 0, 0));
+        assert_eq!(pixel(&pixmap, 30, 30), (0xff, 0, 0));
+        // Between the children, where nothing is drawn.
+        assert_eq!(pixel(&pixmap, 20, 30), (0xff, 0xff, 0xff));
+    }
+
+    // The fill comes from each node's data.
+    #[test]
+    fn test_rasterize_fill_per_node() {
+        let result = laid_out(Orientation::TopDown, EdgeStyle::Straight);
+        let style = RasterStyle::default().with_scale(10.0).with_margin(5.0);
+        let blue = Color::new(0, 0, 0xff);
+        let pixmap = rasterize(
+            &result,
+            &style,
+            |data| if *data == "a" { blue } else { RED },
+        )
+        .unwrap();
+        assert_eq!(pixel(&pixmap, 10, 30), (0, 0, 0xff));
+        assert_eq!(pixel(&pixmap, 30, 30), (0xff, 0, 0));
+    }
+
+    // An orthogonal edge's shared bend is drawn halfway between the rows.
+    #[test]
+    fn test_rasterize_draws_edges() {
+        let result = laid_out(Orientation::TopDown, EdgeStyle::Orthogonal);
+        let style = RasterStyle::default()
+            .with_scale(10.0)
+            .with_margin(5.0)
+            .with_edges(Color::new(0, 0, 0), 2.0);
+        let pixmap = rasterize(&result, &style, |_| RED).unwrap();
+        assert_eq!(pixel(&pixmap, 15, 20), (0, 0, 0));
+        assert_eq!(pixel(&pixmap, 25, 20), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_render_png_signature() {
+        for orientation in [Orientation::LeftRight, Orientation::Radial] {
+            let result = laid_out(orientation, EdgeStyle::Bezier);
+            let png = render_png(&result, &RasterStyle::default(), |_| RED).unwrap();
+            assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        }
+    }
+
+    #[test]
+    fn test_rasterize_too_large() {
+        let result = laid_out(Orientation::TopDown, EdgeStyle::Straight);
+        let style = RasterStyle::default().with_scale(10_000.0);
+        assert!(matches!(
+            rasterize(&result, &style, |_| RED),
+            Err(RasterError::TooLarge { .. })
+        ));
+    }
+}
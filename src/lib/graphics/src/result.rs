@@ -0,0 +1,345 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+//! Laid-out trees as plain data, for front ends that only draw.
+//!
+//! A [`LayoutResult`] lists every node of a [`Tree`] with its id, links, position, size and data,
+//! and optionally the tree's [`Edge`]s. It serializes to JSON such as:
+//!
+//! ```json
+//! {"root": 0, "nodes": [{"id": 0, "parent": null, "children": [1], "depth": 0,
+//!   "position": {"x": 0.0, "y": 0.0}, "size": {"width": 1.0, "height": 1.0}, "data": "root"}, ...],
+//!  "edges": []}
+//! ```
+//!
+//! For interactive front ends it also answers geometry queries: [`LayoutResult::node_at`] finds
+//! the node under a point, for hover and click handling, and [`LayoutNode::bounds`] and
+//! [`LayoutResult::bounds`] give the boxes to hit-test against or fit into a viewport.
+
+use serde::{Deserialize, Serialize};
+
+use crate::edges::{Edge, EdgePath, Point};
+use crate::layout::{Data, NodeId, Position, Size, Tree};
+
+/// An axis-aligned box, in layout units.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+impl BoundingBox {
+    pub fn new(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Self {
+        BoundingBox {
+            min_x,
+            min_y,
+            max_x,
+            max_y,
+        }
+    }
+
+    /// The smallest box containing all of `points`, or None if there are none.
+    pub fn around(points: impl IntoIterator<Item = Point>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        let mut bounds = BoundingBox::new(first.x, first.y, first.x, first.y);
+        for point in points {
+            bounds.min_x = bounds.min_x.min(point.x);
+            bounds.min_y = bounds.min_y.min(point.y);
+            bounds.max_x = bounds.max_x.max(point.x);
+            bounds.max_y = bounds.max_y.max(point.y);
+        }
+        Some(bounds)
+    }
+
+    pub fn width(&self) -> f64 {
+        self.max_x - self.min_x
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max_y - self.min_y
+    }
+
+    /// Whether (x, y) is inside the box or on its edge.
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        (self.min_x..=self.max_x).contains(&x) && (self.min_y..=self.max_y).contains(&y)
+    }
+
+    /// Whether the boxes overlap or touch.
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.min_x <= other.max_x
+            && other.min_x <= self.max_x
+            && self.min_y <= other.max_y
+            && other.min_y <= self.max_y
+    }
+
+    fn corners(&self) -> [Point; 2] {
+        [
+            Point::new(self.min_x, self.min_y),
+            Point::new(self.max_x, self.max_y),
+        ]
+    }
+}
+
+/// One node of a [`LayoutResult`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayoutNode<_Data> {
+    pub id: NodeId,
+    /// None for the root.
+    pub parent: Option<NodeId>,
+    /// Children, left to right.
+    pub children: Vec<NodeId>,
+    /// 0 for the root.
+    pub depth: usize,
+    pub position: Position,
+    pub size: Size,
+    pub data: _Data,
+}
+
+/// Snapshot of a laid-out tree as a flat list of nodes. Nodes are in preorder, so the root
+/// comes first and every parent comes before its children.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayoutResult<_Data> {
+    pub root: NodeId,
+    pub nodes: Vec<LayoutNode<_Data>>,
+    /// Empty unless added with [`LayoutResult::with_edges`].
+    #[serde(default)]
+    pub edges: Vec<Edge>,
+}
+
+impl<_Data> LayoutNode<_Data> {
+    /// The (x, y) of the center of the node.
+    pub fn center(&self) -> (f64, f64) {
+        (
+            self.position.x + self.size.width / 2.0,
+            self.position.y + self.size.height / 2.0,
+        )
+    }
+
+    /// The box the node occupies.
+    pub fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            self.position.x,
+            self.position.y,
+            self.position.x + self.size.width,
+            self.position.y + self.size.height,
+        )
+    }
+}
+
+impl<_Data> LayoutResult<_Data> {
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Always false for a result built from a tree, which has at least its root.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&LayoutNode<_Data>> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    pub fn root(&self) -> Option<&LayoutNode<_Data>> {
+        self.node(self.root)
+    }
+
+    pub fn with_edges(mut self, edges: Vec<Edge>) -> Self {
+        self.edges = edges;
+        self
+    }
+
+    /// The node whose box contains (x, y), if any. Laid-out nodes don't overlap, so there is at
+    /// most one, except on shared edges where the first in preorder wins. This is a linear scan;
+    /// cache the result while the pointer doesn't move.
+    pub fn node_at(&self, x: f64, y: f64) -> Option<&LayoutNode<_Data>> {
+        self.nodes.iter().find(|node| node.bounds().contains(x, y))
+    }
+
+    /// The smallest box containing every node and edge, or None if there are no nodes. A
+    /// Bézier edge is bounded by its control points, so the box may be slightly larger than the
+    /// drawing.
+    pub fn bounds(&self) -> Option<BoundingBox> {
+        let nodes = self.nodes.iter().flat_map(|node| node.bounds().corners());
+        let edges = self.edges.iter().flat_map(|edge| path_points(&edge.path));
+        BoundingBox::around(nodes.chain(edges))
+    }
+}
+
+/// The points defining a path. A Bézier curve lies within the hull of its control points.
+fn path_points(path: &EdgePath) -> Vec<Point> {
+    match path {
+        EdgePath::Line { from, to } => vec![*from, *to],
+        EdgePath::Polyline { points } => points.clone(),
+        EdgePath::CubicBezier {
+            from,
+            control1,
+            control2,
+            to,
+        } => vec![*from, *control1, *control2, *to],
+    }
+}
+
+impl<_Data: Serialize> LayoutResult<_Data> {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+}
+
+impl<_Data: Data> From<&Tree<_Data>> for LayoutResult<_Data> {
+    fn from(tree: &Tree<_Data>) -> Self {
+        let root = tree.root_id();
+        let mut nodes = Vec::with_capacity(tree.len());
+        let mut stack = vec![(root, 0)];
+        while let Some((id, depth)) = stack.pop() {
+            let children = tree.children(id);
+            stack.extend(children.iter().rev().map(|&child| (child, depth + 1)));
+            nodes.push(LayoutNode {
+                id,
+                parent: tree.parent(id),
+                children,
+                depth,
+                position: tree.position(id).unwrap_or_default(),
+                size: tree.size(id).expect("node is in the tree"),
+                data: tree.data(id).expect("node is in the tree"),
+            });
+        }
+        LayoutResult {
+            root,
+            nodes,
+            edges: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edges::{route_edges, EdgeStyle};
+    use crate::layout::{Layout, Orientation, ReingoldTilfordLayout};
+
+    fn laid_out_tree() -> Tree<String> {
+        let size = Size::new(1.0, 1.0);
+        let mut tree = Tree::new("root".to_string(), size);
+        let root = tree.root_id();
+        let a = tree.add_child(root, "a".to_string(), size);
+        tree.add_child(a, "a1".to_string(), size);
+        tree.add_child(a, "a2".to_string(), size);
+        tree.add_child(root, "b".to_string(), size);
+        ReingoldTilfordLayout::new(1.0, 1.0, 1.0).layout(&mut tree);
+        tree
+    }
+
+    #[test]
+    fn test_nodes_are_in_preorder() {
+        let tree = laid_out_tree();
+        let result = LayoutResult::from(&tree);
+        let names: Vec<&str> = result.nodes.iter().map(|node| node.data.as_str()).collect();
+        assert_eq!(names, vec!["root", "a", "a1", "a2", "b"]);
+        assert_eq!(result.len(), tree.len());
+        assert_eq!(result.root().unwrap().data, "root");
+    }
+
+    #[test]
+    fn test_nodes_match_tree() {
+        let tree = laid_out_tree();
+        let result = LayoutResult::from(&tree);
+        for node in &result.nodes {
+            assert_eq!(Some(node.position), tree.position(node.id));
+            assert_eq!(Some(node.size), tree.size(node.id));
+            assert_eq!(node.parent, tree.parent(node.id));
+            assert_eq!(node.children, tree.children(node.id));
+            match node.parent {
+                None => assert_eq!(node.depth, 0),
+                Some(parent) => assert_eq!(node.depth, result.node(parent).unwrap().depth + 1),
+            }
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let result = LayoutResult::from(&laid_out_tree());
+        let json = result.to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let root = &value["nodes"][0];
+        assert_eq!(root["id"], value["root"]);
+        assert_eq!(root["parent"], serde_json::Value::Null);
+        assert_eq!(root["data"], "root");
+        assert_eq!(root["size"]["width"], 1.0);
+        assert!(root["position"].get("modifier").is_none());
+
+        let parsed: LayoutResult<String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, result);
+    }
+
+    // The test tree's nodes, as top-left corners: root (2, 0), a (1, 2), a1 (0, 4), a2 (2, 4)
+    // and b (3, 2), all 1 x 1.
+    #[test]
+    fn test_node_at() {
+        let result = LayoutResult::from(&laid_out_tree());
+        let name_at = |x, y| result.node_at(x, y).map(|node| node.data.as_str());
+        assert_eq!(name_at(2.5, 0.5), Some("root"));
+        assert_eq!(name_at(1.0, 2.0), Some("a"));
+        assert_eq!(name_at(2.9, 4.9), Some("a2"));
+        assert_eq!(name_at(3.5, 2.5), Some("b"));
+        assert_eq!(name_at(1.5, 1.5), None);
+        assert_eq!(name_at(-0.1, 4.5), None);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let result = LayoutResult::from(&laid_out_tree());
+        let a = &result.nodes[1];
+        assert_eq!(a.bounds(), BoundingBox::new(1.0, 2.0, 2.0, 3.0));
+        assert_eq!(result.bounds(), Some(BoundingBox::new(0.0, 0.0, 4.0, 5.0)));
+        assert!(a.bounds().intersects(&BoundingBox::new(1.5, 2.5, 9.0, 9.0)));
+        assert!(!a.bounds().intersects(&BoundingBox::new(2.5, 2.5, 9.0, 9.0)));
+
+        let empty: LayoutResult<String> = LayoutResult {
+            root: result.root,
+            nodes: vec![],
+            edges: vec![],
+        };
+        assert_eq!(empty.bounds(), None);
+    }
+
+    // The world box covers edges as well as nodes, including Bézier control points, which in a
+    // radial layout can lie outside the nodes' box.
+    #[test]
+    fn test_bounds_include_edges() {
+        let mut tree = laid_out_tree();
+        ReingoldTilfordLayout::new(1.0, 1.0, 1.0)
+            .with_orientation(Orientation::Radial)
+            .layout(&mut tree);
+        let nodes_only = LayoutResult::from(&tree);
+        let with_edges = nodes_only.clone().with_edges(route_edges(
+            &tree,
+            Orientation::Radial,
+            EdgeStyle::Bezier,
+        ));
+        let (inner, outer) = (nodes_only.bounds().unwrap(), with_edges.bounds().unwrap());
+        assert!(outer.min_x <= inner.min_x && outer.max_x >= inner.max_x);
+        assert!(outer.min_y <= inner.min_y && outer.max_y >= inner.max_y);
+        for edge in &with_edges.edges {
+            for point in path_points(&edge.path) {
+                assert!(outer.contains(point.x, point.y));
+            }
+        }
+    }
+}
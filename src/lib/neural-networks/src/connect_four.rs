@@ -0,0 +1,215 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// The bridge between Connect Four positions and networks: encoding boards as network inputs,
+// numbering moves for policy outputs, and a small value network.
+
+use connect_four_logic::{Board, Cell, Move, MoveType, Player};
+use ndarray::{Array1, Array2};
+#[cfg(feature = "torch")]
+use tch::Tensor;
+
+use crate::mlp::{Activation, Network};
+
+/// Planes in an encoded position: Player 1's pieces, Player 2's pieces, and the side to move.
+pub const PLANES: usize = 3;
+
+/// Length of the encoding of a `width` × `height` board.
+pub fn input_size(width: usize, height: usize) -> usize {
+    PLANES * width * height
+}
+
+/// Encode a position as [`PLANES`] planes of `width` × `height` values, each stored row by row
+/// in the board's own cell order. The first two planes are 1 where that player has a piece,
+/// and the last plane is all ones when Player 1 is to move and all zeros otherwise.
+pub fn encode(board: &Board, to_move: Player) -> Array1<f64> {
+    let cells = board.cells.len();
+    let mut encoded = Array1::zeros(PLANES * cells);
+    for (i, cell) in board.cells.iter().enumerate() {
+        match cell {
+            Cell::Empty => {}
+            Cell::Player(Player::Player1) => encoded[i] = 1.0,
+            Cell::Player(Player::Player2) => encoded[cells + i] = 1.0,
+        }
+    }
+    if to_move == Player::Player1 {
+        encoded.slice_mut(ndarray::s![2 * cells..]).fill(1.0);
+    }
+    encoded
+}
+
+/// Encode several positions, one per row.
+pub fn encode_batch<'a>(positions: impl IntoIterator<Item = (&'a Board, Player)>) -> Array2<f64> {
+    let rows: Vec<Array1<f64>> = positions
+        .into_iter()
+        .map(|(board, to_move)| encode(board, to_move))
+        .collect();
+    let width = rows.first().map_or(0, Array1::len);
+    let mut batch = Array2::zeros((rows.len(), width));
+    for (mut row, encoded) in batch.rows_mut().into_iter().zip(rows) {
+        row.assign(&encoded);
+    }
+    batch
+}
+
+/// [`encode`] as a float tensor of shape [planes, height, width], for convolutional networks.
+#[cfg(feature = "torch")]
+pub fn encode_tensor(board: &Board, to_move: Player) -> Tensor {
+    let values: Vec<f32> = encode(board, to_move).iter().map(|&x| x as f32).collect();
+    Tensor::from_slice(&values).view([PLANES as i64, board.height as i64, board.width as i64])
+}
+
+/// Number of policy outputs for a board `width` columns wide: an insert and a pop per column.
+pub fn policy_size(width: usize) -> usize {
+    2 * width
+}
+
+/// Index of a move in a policy vector. Inserts come first, then pops, each in column order.
+pub fn move_index(mv: Move, width: usize) -> usize {
+    match mv.move_type {
+        MoveType::Insert => mv.column,
+        MoveType::Pop => width + mv.column,
+    }
+}
+
+pub fn index_move(index: usize, width: usize) -> Move {
+    if index < width {
+        Move {
+            move_type: MoveType::Insert,
+            column: index,
+        }
+    } else {
+        Move {
+            move_type: MoveType::Pop,
+            column: index - width,
+        }
+    }
+}
+
+/// Estimates how good a position is for the side to move, from −1 (lost) to 1 (won).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueNetwork {
+    pub network: Network,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl ValueNetwork {
+    /// Two ReLU hidden layers of 64 and 32 units and a tanh output.
+    pub fn new(width: usize, height: usize, seed: u64) -> Self {
+        Self {
+            network: Network::new(
+                &Self::layer_sizes(width, height),
+                Activation::Relu,
+                Activation::Tanh,
+                seed,
+            ),
+            width,
+            height,
+        }
+    }
+
+    pub fn layer_sizes(width: usize, height: usize) -> Vec<usize> {
+        vec![input_size(width, height), 64, 32, 1]
+    }
+
+    pub fn evaluate(&self, board: &Board, to_move: Player) -> f64 {
+        self.network.forward(encode(board, to_move).view())[0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::train::{train, Adam, Loss, MlpModel, TrainingConfig};
+    use connect_four_logic::get_legal_moves;
+    use ndarray::array;
+
+    #[test]
+    fn test_encode() {
+        let board = Board::from_notation(".../2../1.1").unwrap();
+        let encoded = encode(&board, Player::Player2);
+        assert_eq!(encoded.len(), input_size(3, 3));
+        let planes: Vec<Vec<f64>> = encoded
+            .as_slice()
+            .unwrap()
+            .chunks(9)
+            .map(<[f64]>::to_vec)
+            .collect();
+        assert_eq!(planes[0], vec![0., 0., 0., 0., 0., 0., 1., 0., 1.]);
+        assert_eq!(planes[1], vec![0., 0., 0., 1., 0., 0., 0., 0., 0.]);
+        assert_eq!(planes[2], vec![0.0; 9]);
+        assert_eq!(
+            encode(&board, Player::Player1)
+                .slice(ndarray::s![18..])
+                .sum(),
+            9.0
+        );
+    }
+
+    #[test]
+    fn test_encode_batch() {
+        let empty = Board::new(2, 2);
+        let one = Board::from_notation("../1.").unwrap();
+        let batch = encode_batch([(&empty, Player::Player1), (&one, Player::Player2)]);
+        assert_eq!(batch.dim(), (2, 12));
+        assert_eq!(batch.row(1), encode(&one, Player::Player2));
+    }
+
+    #[test]
+    fn test_move_indices_round_trip() {
+        let board = Board::from_notation("..../1.2.").unwrap();
+        for mv in get_legal_moves(&board, Player::Player1) {
+            let index = move_index(mv, board.width);
+            assert!(index < policy_size(board.width));
+            assert_eq!(index_move(index, board.width), mv);
+        }
+    }
+
+    #[test]
+    fn test_value_network_fits_positions() {
+        let positions = [
+            (
+                Board::from_notation("..../..../1.../1.../1222").unwrap(),
+                1.0,
+            ),
+            (
+                Board::from_notation("..../..../2.../2.../2111").unwrap(),
+                -1.0,
+            ),
+        ];
+        let mut value = ValueNetwork::new(4, 5, 1);
+        let inputs = encode_batch(positions.iter().map(|(b, _)| (b, Player::Player1)));
+        let targets = array![[1.0], [-1.0]];
+        let mut model = MlpModel::new(
+            value.network.clone(),
+            Loss::MeanSquaredError,
+            Adam::new(0.01),
+        );
+        let config = TrainingConfig {
+            epochs: 200,
+            batch_size: 2,
+            ..TrainingConfig::default()
+        };
+        train(&mut model, (&inputs, &targets), None, &config, |_| {});
+        value.network = model.network;
+        for (board, target) in &positions {
+            let estimate = value.evaluate(board, Player::Player1);
+            assert!((-1.0..=1.0).contains(&estimate));
+            assert!((estimate - target).abs() < 0.1, "{estimate} vs {target}");
+        }
+    }
+}
@@ -0,0 +1,623 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Loading datasets for the learning chapters: CSV files and the MNIST IDX format, plus
+// train/validation/test splitting.
+
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use ndarray::{Array1, Array2, Axis};
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DatasetError {
+    #[error("i/o error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("csv error: {0}")]
+    Csv(#[from] csv::Error),
+
+    #[error("unknown column {0}")]
+    UnknownColumn(String),
+
+    #[error("row {row}, column {column}: {value:?} is not a number")]
+    NotANumber {
+        row: usize,
+        column: String,
+        value: String,
+    },
+
+    #[error("one-hot labels need exactly one label column, found {0}")]
+    OneHotNeedsOneLabel(usize),
+
+    #[error("bad IDX magic number {0:#010x}")]
+    BadIdxMagic(u32),
+
+    #[error("unsupported IDX data type {0:#04x}, only unsigned bytes are supported")]
+    UnsupportedIdxType(u8),
+
+    #[error("expected a {expected}-dimensional IDX array, found {found} dimensions")]
+    WrongIdxDimensions { expected: usize, found: usize },
+
+    #[error("{images} images but {labels} labels")]
+    LengthMismatch { images: usize, labels: usize },
+
+    #[error("split fractions must be non-negative and sum to less than 1")]
+    InvalidSplit,
+}
+
+/// Examples stored as the rows of an input matrix and a target matrix, ready for
+/// [`crate::train::train`].
+pub trait Dataset {
+    fn inputs(&self) -> &Array2<f64>;
+    fn targets(&self) -> &Array2<f64>;
+
+    fn len(&self) -> usize {
+        self.inputs().nrows()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The examples at the given indices, in that order.
+    fn select(&self, indices: &[usize]) -> ArrayDataset {
+        ArrayDataset::new(
+            self.inputs().select(Axis(0), indices),
+            self.targets().select(Axis(0), indices),
+        )
+    }
+
+    /// Shuffle the examples with `seed` and split them into training, validation and test
+    /// sets. The validation and test sets get the given fractions of the examples, rounded
+    /// down, and the training set gets the rest.
+    fn split(
+        &self,
+        validation_fraction: f64,
+        test_fraction: f64,
+        seed: u64,
+    ) -> Result<Splits, DatasetError> {
+        if validation_fraction < 0.0
+            || test_fraction < 0.0
+            || validation_fraction + test_fraction >= 1.0
+        {
+            return Err(DatasetError::InvalidSplit);
+        }
+        let mut indices: Vec<usize> = (0..self.len()).collect();
+        indices.shuffle(&mut Pcg64::seed_from_u64(seed));
+        let validation_len = (self.len() as f64 * validation_fraction) as usize;
+        let test_len = (self.len() as f64 * test_fraction) as usize;
+        let (validation, rest) = indices.split_at(validation_len);
+        let (test, training) = rest.split_at(test_len);
+        Ok(Splits {
+            training: self.select(training),
+            validation: self.select(validation),
+            test: self.select(test),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Splits {
+    pub training: ArrayDataset,
+    pub validation: ArrayDataset,
+    pub test: ArrayDataset,
+}
+
+/// The simplest [`Dataset`]: two matrices with one row per example.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArrayDataset {
+    pub inputs: Array2<f64>,
+    pub targets: Array2<f64>,
+}
+
+impl ArrayDataset {
+    pub fn new(inputs: Array2<f64>, targets: Array2<f64>) -> Self {
+        assert_eq!(
+            inputs.nrows(),
+            targets.nrows(),
+            "inputs and targets need the same number of rows"
+        );
+        Self { inputs, targets }
+    }
+}
+
+impl Dataset for ArrayDataset {
+    fn inputs(&self) -> &Array2<f64> {
+        &self.inputs
+    }
+
+    fn targets(&self) -> &Array2<f64> {
+        &self.targets
+    }
+}
+
+/// How to rescale each feature column.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Normalization {
+    #[default]
+    None,
+
+    /// Rescale to [0, 1].
+    MinMax,
+
+    /// Subtract the mean and divide by the standard deviation.
+    ZScore,
+}
+
+/// A fitted normalization, x ↦ (x − offset) / scale per column, so the statistics from the
+/// training data can be reused on new data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Normalizer {
+    pub offsets: Array1<f64>,
+    pub scales: Array1<f64>,
+}
+
+impl Normalizer {
+    pub fn fit(data: &Array2<f64>, normalization: Normalization) -> Self {
+        let columns = data.ncols();
+        let (offsets, scales) = match normalization {
+            Normalization::None => (Array1::zeros(columns), Array1::ones(columns)),
+            Normalization::MinMax => {
+                let min = data.fold_axis(Axis(0), f64::INFINITY, |&a, &x| a.min(x));
+                let max = data.fold_axis(Axis(0), f64::NEG_INFINITY, |&a, &x| a.max(x));
+                let range = &max - &min;
+                (min, range)
+            }
+            Normalization::ZScore => {
+                let mean = data
+                    .mean_axis(Axis(0))
+                    .unwrap_or_else(|| Array1::zeros(columns));
+                let std_dev = data.std_axis(Axis(0), 0.0);
+                (mean, std_dev)
+            }
+        };
+        // A constant column would divide by zero, so leave its spread alone.
+        let scales = scales.mapv(|s| if s > 0.0 { s } else { 1.0 });
+        Self { offsets, scales }
+    }
+
+    pub fn apply(&self, data: &mut Array2<f64>) {
+        for mut row in data.rows_mut() {
+            row -= &self.offsets;
+            row /= &self.scales;
+        }
+    }
+}
+
+/// A column of a CSV file, by zero-based position or by header name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Column {
+    Index(usize),
+    Name(String),
+}
+
+impl From<usize> for Column {
+    fn from(index: usize) -> Self {
+        Column::Index(index)
+    }
+}
+
+impl From<&str> for Column {
+    fn from(name: &str) -> Self {
+        Column::Name(name.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvOptions {
+    pub has_header: bool,
+    pub delimiter: u8,
+
+    /// Feature columns, or every column that isn't a label if empty.
+    pub features: Vec<Column>,
+    pub labels: Vec<Column>,
+
+    /// Turn a single label column of class names into one-hot targets, instead of parsing the
+    /// labels as numbers.
+    pub one_hot_labels: bool,
+    pub normalization: Normalization,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            has_header: true,
+            delimiter: b',',
+            features: vec![],
+            labels: vec![],
+            one_hot_labels: false,
+            normalization: Normalization::None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvDataset {
+    pub data: ArrayDataset,
+    pub feature_names: Vec<String>,
+    pub label_names: Vec<String>,
+
+    /// The class names, in one-hot order, when `one_hot_labels` was set.
+    pub classes: Vec<String>,
+    pub normalizer: Normalizer,
+}
+
+impl Dataset for CsvDataset {
+    fn inputs(&self) -> &Array2<f64> {
+        &self.data.inputs
+    }
+
+    fn targets(&self) -> &Array2<f64> {
+        &self.data.targets
+    }
+}
+
+pub fn load_csv_file(
+    path: impl AsRef<Path>,
+    options: &CsvOptions,
+) -> Result<CsvDataset, DatasetError> {
+    load_csv(BufReader::new(File::open(path)?), options)
+}
+
+pub fn load_csv<R: Read>(reader: R, options: &CsvOptions) -> Result<CsvDataset, DatasetError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(options.has_header)
+        .delimiter(options.delimiter)
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    let records: Vec<csv::StringRecord> = reader.records().collect::<Result<_, _>>()?;
+    let headers: Vec<String> = if options.has_header {
+        reader.headers()?.iter().map(str::to_string).collect()
+    } else {
+        let width = records.first().map_or(0, csv::StringRecord::len);
+        (0..width).map(|i| i.to_string()).collect()
+    };
+
+    let resolve = |column: &Column| match column {
+        Column::Index(index) if *index < headers.len() => Ok(*index),
+        Column::Index(index) => Err(DatasetError::UnknownColumn(index.to_string())),
+        Column::Name(name) => headers
+            .iter()
+            .position(|header| header == name)
+            .ok_or_else(|| DatasetError::UnknownColumn(name.clone())),
+    };
+    let labels: Vec<usize> = options
+        .labels
+        .iter()
+        .map(resolve)
+        .collect::<Result<_, _>>()?;
+    let features: Vec<usize> = if options.features.is_empty() {
+        (0..headers.len()).filter(|i| !labels.contains(i)).collect()
+    } else {
+        options
+            .features
+            .iter()
+            .map(resolve)
+            .collect::<Result<_, _>>()?
+    };
+    if options.one_hot_labels && labels.len() != 1 {
+        return Err(DatasetError::OneHotNeedsOneLabel(labels.len()));
+    }
+
+    let parse = |row: usize, record: &csv::StringRecord, column: usize| {
+        let value = record.get(column).unwrap_or("");
+        value.parse::<f64>().map_err(|_| DatasetError::NotANumber {
+            row,
+            column: headers[column].clone(),
+            value: value.to_string(),
+        })
+    };
+    let mut inputs = Array2::zeros((records.len(), features.len()));
+    for (row, record) in records.iter().enumerate() {
+        for (j, &column) in features.iter().enumerate() {
+            inputs[[row, j]] = parse(row, record, column)?;
+        }
+    }
+
+    let mut classes = vec![];
+    let targets = if options.one_hot_labels {
+        let names: Vec<&str> = records
+            .iter()
+            .map(|record| record.get(labels[0]).unwrap_or(""))
+            .collect();
+        classes = names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let mut targets = Array2::zeros((records.len(), classes.len()));
+        for (row, name) in names.iter().enumerate() {
+            let class = classes.iter().position(|class| class == name).unwrap();
+            targets[[row, class]] = 1.0;
+        }
+        targets
+    } else {
+        let mut targets = Array2::zeros((records.len(), labels.len()));
+        for (row, record) in records.iter().enumerate() {
+            for (j, &column) in labels.iter().enumerate() {
+                targets[[row, j]] = parse(row, record, column)?;
+            }
+        }
+        targets
+    };
+
+    let normalizer = Normalizer::fit(&inputs, options.normalization);
+    normalizer.apply(&mut inputs);
+    Ok(CsvDataset {
+        data: ArrayDataset::new(inputs, targets),
+        feature_names: features.iter().map(|&i| headers[i].clone()).collect(),
+        label_names: labels.iter().map(|&i| headers[i].clone()).collect(),
+        classes,
+        normalizer,
+    })
+}
+
+/// An array of unsigned bytes in the IDX format used by MNIST.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdxArray {
+    pub dimensions: Vec<usize>,
+    pub data: Vec<u8>,
+}
+
+const IDX_UNSIGNED_BYTE: u8 = 0x08;
+
+/// Read an IDX file: two zero bytes, a data type byte, the number of dimensions, each
+/// dimension as a big-endian u32, then the data. Only unsigned byte data is supported, which
+/// is all MNIST uses. The file must already be decompressed.
+pub fn read_idx<R: Read>(mut reader: R) -> Result<IdxArray, DatasetError> {
+    let mut header = [0u8; 4];
+    reader.read_exact(&mut header)?;
+    if header[0] != 0 || header[1] != 0 {
+        return Err(DatasetError::BadIdxMagic(u32::from_be_bytes(header)));
+    }
+    if header[2] != IDX_UNSIGNED_BYTE {
+        return Err(DatasetError::UnsupportedIdxType(header[2]));
+    }
+    let mut dimensions = Vec::with_capacity(header[3] as usize);
+    for _ in 0..header[3] {
+        let mut dimension = [0u8; 4];
+        reader.read_exact(&mut dimension)?;
+        dimensions.push(u32::from_be_bytes(dimension) as usize);
+    }
+    let mut data = vec![0u8; dimensions.iter().product()];
+    reader.read_exact(&mut data)?;
+    Ok(IdxArray { dimensions, data })
+}
+
+/// MNIST digits: each image flattened to a row of pixels scaled to [0, 1], with one-hot
+/// targets over the ten digits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MnistDataset {
+    pub data: ArrayDataset,
+    pub labels: Vec<u8>,
+    pub rows: usize,
+    pub columns: usize,
+}
+
+impl Dataset for MnistDataset {
+    fn inputs(&self) -> &Array2<f64> {
+        &self.data.inputs
+    }
+
+    fn targets(&self) -> &Array2<f64> {
+        &self.data.targets
+    }
+}
+
+pub fn load_mnist_files(
+    images: impl AsRef<Path>,
+    labels: impl AsRef<Path>,
+) -> Result<MnistDataset, DatasetError> {
+    load_mnist(
+        BufReader::new(File::open(images)?),
+        BufReader::new(File::open(labels)?),
+    )
+}
+
+pub fn load_mnist<R1: Read, R2: Read>(
+    images: R1,
+    labels: R2,
+) -> Result<MnistDataset, DatasetError> {
+    let images = read_idx(images)?;
+    let labels = read_idx(labels)?;
+    if images.dimensions.len() != 3 {
+        return Err(DatasetError::WrongIdxDimensions {
+            expected: 3,
+            found: images.dimensions.len(),
+        });
+    }
+    if labels.dimensions.len() != 1 {
+        return Err(DatasetError::WrongIdxDimensions {
+            expected: 1,
+            found: labels.dimensions.len(),
+        });
+    }
+    let (count, rows, columns) = (
+        images.dimensions[0],
+        images.dimensions[1],
+        images.dimensions[2],
+    );
+    if count != labels.dimensions[0] {
+        return Err(DatasetError::LengthMismatch {
+            images: count,
+            labels: labels.dimensions[0],
+        });
+    }
+    let inputs = Array2::from_shape_vec(
+        (count, rows * columns),
+        images
+            .data
+            .iter()
+            .map(|&pixel| pixel as f64 / 255.0)
+            .collect(),
+    )
+    .expect("IDX data length matches its dimensions");
+    let mut targets = Array2::zeros((count, 10));
+    for (row, &label) in labels.data.iter().enumerate() {
+        targets[[row, (label as usize).min(9)]] = 1.0;
+    }
+    Ok(MnistDataset {
+        data: ArrayDataset::new(inputs, targets),
+        labels: labels.data,
+        rows,
+        columns,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    const IRIS: &str = "\
+sepal_length,sepal_width,species
+5.1,3.5,setosa
+7.0,3.2,versicolor
+6.3,3.3,virginica
+4.9,3.0,setosa
+";
+
+    #[test]
+    fn test_csv_with_one_hot_labels() {
+        let options = CsvOptions {
+            labels: vec!["species".into()],
+            one_hot_labels: true,
+            ..CsvOptions::default()
+        };
+        let dataset = load_csv(IRIS.as_bytes(), &options).unwrap();
+        assert_eq!(dataset.feature_names, vec!["sepal_length", "sepal_width"]);
+        assert_eq!(dataset.classes, vec!["setosa", "versicolor", "virginica"]);
+        assert_eq!(dataset.len(), 4);
+        assert_eq!(dataset.inputs().row(1), array![7.0, 3.2]);
+        assert_eq!(dataset.targets().row(2), array![0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_csv_column_selection_and_normalization() {
+        let data = "1,10,0.5\n2,20,0.25\n3,30,0.75\n";
+        let options = CsvOptions {
+            has_header: false,
+            features: vec![1.into()],
+            labels: vec![2.into()],
+            normalization: Normalization::MinMax,
+            ..CsvOptions::default()
+        };
+        let dataset = load_csv(data.as_bytes(), &options).unwrap();
+        assert_eq!(dataset.inputs(), &array![[0.0], [0.5], [1.0]]);
+        assert_eq!(dataset.targets(), &array![[0.5], [0.25], [0.75]]);
+
+        let options = CsvOptions {
+            normalization: Normalization::ZScore,
+            ..options
+        };
+        let dataset = load_csv(data.as_bytes(), &options).unwrap();
+        let column = dataset.inputs().column(0);
+        assert!(column.mean().unwrap().abs() < 1e-12);
+        assert!((column.std(0.0) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_csv_errors() {
+        let options = CsvOptions {
+            labels: vec!["colour".into()],
+            ..CsvOptions::default()
+        };
+        assert!(matches!(
+            load_csv(IRIS.as_bytes(), &options),
+            Err(DatasetError::UnknownColumn(name)) if name == "colour"
+        ));
+        let options = CsvOptions {
+            labels: vec!["sepal_width".into()],
+            ..CsvOptions::default()
+        };
+        assert!(matches!(
+            load_csv(IRIS.as_bytes(), &options),
+            Err(DatasetError::NotANumber { row: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_split_partitions_the_examples() {
+        let inputs = Array2::from_shape_fn((10, 1), |(i, _)| i as f64);
+        let dataset = ArrayDataset::new(inputs.clone(), inputs);
+        let splits = dataset.split(0.2, 0.3, 7).unwrap();
+        assert_eq!(splits.training.len(), 5);
+        assert_eq!(splits.validation.len(), 2);
+        assert_eq!(splits.test.len(), 3);
+        let mut all: Vec<f64> = [&splits.training, &splits.validation, &splits.test]
+            .iter()
+            .flat_map(|split| split.inputs.iter().copied().collect::<Vec<_>>())
+            .collect();
+        all.sort_by(f64::total_cmp);
+        assert_eq!(all, (0..10).map(|i| i as f64).collect::<Vec<_>>());
+        assert_eq!(splits, dataset.split(0.2, 0.3, 7).unwrap());
+        assert!(matches!(
+            dataset.split(0.5, 0.5, 7),
+            Err(DatasetError::InvalidSplit)
+        ));
+    }
+
+    fn idx(dimensions: &[u32], data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0, 0, IDX_UNSIGNED_BYTE, dimensions.len() as u8];
+        for dimension in dimensions {
+            bytes.extend(dimension.to_be_bytes());
+        }
+        bytes.extend(data);
+        bytes
+    }
+
+    #[test]
+    fn test_mnist() {
+        let images = idx(&[2, 2, 2], &[0, 255, 51, 0, 255, 255, 255, 255]);
+        let labels = idx(&[2], &[7, 1]);
+        let dataset = load_mnist(images.as_slice(), labels.as_slice()).unwrap();
+        assert_eq!((dataset.rows, dataset.columns), (2, 2));
+        assert_eq!(dataset.labels, vec![7, 1]);
+        assert_eq!(dataset.inputs().row(0), array![0.0, 1.0, 0.2, 0.0]);
+        assert_eq!(dataset.targets()[[0, 7]], 1.0);
+        assert_eq!(dataset.targets().row(1).sum(), 1.0);
+    }
+
+    #[test]
+    fn test_mnist_errors() {
+        let images = idx(&[2, 2, 2], &[0; 8]);
+        assert!(matches!(
+            load_mnist(images.as_slice(), idx(&[3], &[0; 3]).as_slice()),
+            Err(DatasetError::LengthMismatch {
+                images: 2,
+                labels: 3
+            })
+        ));
+        assert!(matches!(
+            load_mnist(images.as_slice(), idx(&[2], &[0]).as_slice()),
+            Err(DatasetError::Io(_))
+        ));
+        let mut floats = idx(&[1], &[0]);
+        floats[2] = 0x0d;
+        assert!(matches!(
+            read_idx(floats.as_slice()),
+            Err(DatasetError::UnsupportedIdxType(0x0d))
+        ));
+    }
+}
@@ -0,0 +1,746 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Decision tree learning with information gain, for discrete and continuous attributes, with
+// optional χ² pruning.
+//
+// See:
+// -  Chapter 19: Learning from Examples, section 19.3 and figure 19.5
+
+use std::fmt;
+
+use ndarray::{Array1, Array2, ArrayView1};
+
+use crate::examples::Attribute;
+use crate::learner::SupervisedLearner;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeKind {
+    /// Values are encoded as indices into the list of value names.
+    Discrete(Vec<String>),
+    /// Split on a threshold, x ≤ t versus x > t. A continuous attribute may be tested again
+    /// further down the tree with a different threshold.
+    Continuous,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeAttribute {
+    pub name: String,
+    pub kind: AttributeKind,
+}
+
+impl TreeAttribute {
+    pub fn discrete(name: &str, values: &[&str]) -> Self {
+        TreeAttribute {
+            name: name.to_string(),
+            kind: AttributeKind::Discrete(values.iter().map(|v| v.to_string()).collect()),
+        }
+    }
+
+    pub fn continuous(name: &str) -> Self {
+        TreeAttribute {
+            name: name.to_string(),
+            kind: AttributeKind::Continuous,
+        }
+    }
+}
+
+impl From<&Attribute> for TreeAttribute {
+    fn from(attribute: &Attribute) -> Self {
+        TreeAttribute::discrete(attribute.name, attribute.values)
+    }
+}
+
+/// A learned tree. Every node remembers how many training examples of each class reached it,
+/// and the plurality class of those examples, which is the prediction for inputs with an
+/// attribute value that no training example had at that node.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecisionTree {
+    Leaf {
+        class: f64,
+        counts: Vec<usize>,
+    },
+    Discrete {
+        attribute: usize,
+        children: Vec<DecisionTree>,
+        class: f64,
+        counts: Vec<usize>,
+    },
+    Threshold {
+        attribute: usize,
+        threshold: f64,
+        below: Box<DecisionTree>,
+        above: Box<DecisionTree>,
+        class: f64,
+        counts: Vec<usize>,
+    },
+}
+
+impl DecisionTree {
+    pub fn class(&self) -> f64 {
+        match self {
+            DecisionTree::Leaf { class, .. }
+            | DecisionTree::Discrete { class, .. }
+            | DecisionTree::Threshold { class, .. } => *class,
+        }
+    }
+
+    /// Training examples of each class, in the order of the learner's classes.
+    pub fn counts(&self) -> &[usize] {
+        match self {
+            DecisionTree::Leaf { counts, .. }
+            | DecisionTree::Discrete { counts, .. }
+            | DecisionTree::Threshold { counts, .. } => counts,
+        }
+    }
+
+    pub fn children(&self) -> Vec<&DecisionTree> {
+        match self {
+            DecisionTree::Leaf { .. } => vec![],
+            DecisionTree::Discrete { children, .. } => children.iter().collect(),
+            DecisionTree::Threshold { below, above, .. } => vec![below, above],
+        }
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        matches!(self, DecisionTree::Leaf { .. })
+    }
+
+    pub fn node_count(&self) -> usize {
+        1 + self
+            .children()
+            .iter()
+            .map(|child| child.node_count())
+            .sum::<usize>()
+    }
+
+    /// Number of tests on the longest path from the root to a leaf.
+    pub fn depth(&self) -> usize {
+        self.children()
+            .iter()
+            .map(|child| 1 + child.depth())
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn classify(&self, input: ArrayView1<f64>) -> f64 {
+        match self {
+            DecisionTree::Leaf { class, .. } => *class,
+            DecisionTree::Discrete {
+                attribute,
+                children,
+                class,
+                ..
+            } => {
+                let value = input[*attribute];
+                if value < 0.0 || value.fract() != 0.0 {
+                    return *class;
+                }
+                children
+                    .get(value as usize)
+                    .map_or(*class, |child| child.classify(input))
+            }
+            DecisionTree::Threshold {
+                attribute,
+                threshold,
+                below,
+                above,
+                ..
+            } => {
+                if input[*attribute] <= *threshold {
+                    below.classify(input)
+                } else {
+                    above.classify(input)
+                }
+            }
+        }
+    }
+}
+
+/// Critical values of the χ² distribution used to decide whether a split is significant.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Significance {
+    OnePercent,
+    FivePercent,
+    TenPercent,
+}
+
+impl Significance {
+    /// The upper critical value of χ² with `degrees_of_freedom`, by the Wilson–Hilferty
+    /// approximation. It is within a few percent of the exact value, which is plenty for
+    /// pruning.
+    pub fn critical_value(self, degrees_of_freedom: usize) -> f64 {
+        let z = match self {
+            Significance::OnePercent => 2.326,
+            Significance::FivePercent => 1.645,
+            Significance::TenPercent => 1.282,
+        };
+        let k = degrees_of_freedom.max(1) as f64;
+        let a = 2.0 / (9.0 * k);
+        k * (1.0 - a + z * a.sqrt()).powi(3)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Pruning {
+    #[default]
+    None,
+    /// χ² pruning, section 19.3.4. A test whose children are all leaves is replaced by a leaf
+    /// when the class counts in its children don't deviate significantly from what an
+    /// irrelevant attribute would give. Repeats bottom-up until no more tests can be pruned.
+    ChiSquared(Significance),
+}
+
+/// LEARN-DECISION-TREE from figure 19.5. Chooses the most important attribute at each node by
+/// information gain, the expected reduction in entropy. Ties go to the attribute that comes
+/// first, and plurality votes go to the class with the smallest label.
+#[derive(Debug, Clone)]
+pub struct DecisionTreeLearner {
+    attributes: Vec<TreeAttribute>,
+    pruning: Pruning,
+    class_names: Option<Vec<String>>,
+    classes: Vec<f64>,
+    tree: Option<DecisionTree>,
+}
+
+impl DecisionTreeLearner {
+    pub fn new(attributes: Vec<TreeAttribute>) -> Self {
+        DecisionTreeLearner {
+            attributes,
+            pruning: Pruning::None,
+            class_names: None,
+            classes: vec![],
+            tree: None,
+        }
+    }
+
+    pub fn with_pruning(mut self, pruning: Pruning) -> Self {
+        self.pruning = pruning;
+        self
+    }
+
+    /// Names for the classes 0.0, 1.0, ... used when printing the tree.
+    pub fn with_class_names(mut self, names: &[&str]) -> Self {
+        self.class_names = Some(names.iter().map(|name| name.to_string()).collect());
+        self
+    }
+
+    pub fn attributes(&self) -> &[TreeAttribute] {
+        &self.attributes
+    }
+
+    /// The distinct target values seen by fit, in increasing order.
+    pub fn classes(&self) -> &[f64] {
+        &self.classes
+    }
+
+    /// The learned tree, or None before fit.
+    pub fn tree(&self) -> Option<&DecisionTree> {
+        self.tree.as_ref()
+    }
+
+    fn class_index(&self, target: f64) -> usize {
+        self.classes
+            .iter()
+            .position(|&class| class == target)
+            .unwrap()
+    }
+
+    fn counts(&self, rows: &[usize], targets: &Array1<f64>) -> Vec<usize> {
+        let mut counts = vec![0; self.classes.len()];
+        for &row in rows {
+            counts[self.class_index(targets[row])] += 1;
+        }
+        counts
+    }
+
+    fn plurality(&self, counts: &[usize]) -> f64 {
+        let mut best = 0;
+        for (index, &count) in counts.iter().enumerate() {
+            if count > counts[best] {
+                best = index;
+            }
+        }
+        self.classes[best]
+    }
+
+    fn learn(
+        &self,
+        inputs: &Array2<f64>,
+        targets: &Array1<f64>,
+        rows: &[usize],
+        candidates: &[usize],
+        parent_class: f64,
+    ) -> DecisionTree {
+        let counts = self.counts(rows, targets);
+        if rows.is_empty() {
+            return DecisionTree::Leaf {
+                class: parent_class,
+                counts,
+            };
+        }
+        let class = self.plurality(&counts);
+        if counts.iter().filter(|&&count| count > 0).count() == 1 {
+            return DecisionTree::Leaf { class, counts };
+        }
+
+        let mut best: Option<(f64, Split)> = None;
+        for &attribute in candidates {
+            if let Some((gain, split)) = self.best_split(inputs, targets, rows, &counts, attribute)
+            {
+                if best
+                    .as_ref()
+                    .is_none_or(|(best_gain, _)| gain > *best_gain + 1e-12)
+                {
+                    best = Some((gain, split));
+                }
+            }
+        }
+        let Some((_, split)) = best else {
+            return DecisionTree::Leaf { class, counts };
+        };
+
+        match split {
+            Split::Discrete { attribute, values } => {
+                let remaining: Vec<usize> = candidates
+                    .iter()
+                    .copied()
+                    .filter(|&candidate| candidate != attribute)
+                    .collect();
+                let children = (0..values)
+                    .map(|value| {
+                        let subset: Vec<usize> = rows
+                            .iter()
+                            .copied()
+                            .filter(|&row| inputs[[row, attribute]] == value as f64)
+                            .collect();
+                        self.learn(inputs, targets, &subset, &remaining, class)
+                    })
+                    .collect();
+                DecisionTree::Discrete {
+                    attribute,
+                    children,
+                    class,
+                    counts,
+                }
+            }
+            Split::Threshold {
+                attribute,
+                threshold,
+            } => {
+                let (below, above): (Vec<usize>, Vec<usize>) = rows
+                    .iter()
+                    .partition(|&&row| inputs[[row, attribute]] <= threshold);
+                DecisionTree::Threshold {
+                    attribute,
+                    threshold,
+                    below: Box::new(self.learn(inputs, targets, &below, candidates, class)),
+                    above: Box::new(self.learn(inputs, targets, &above, candidates, class)),
+                    class,
+                    counts,
+                }
+            }
+        }
+    }
+
+    /// The information gain of testing `attribute` at a node with the given examples. For a
+    /// continuous attribute, the best threshold among the midpoints between consecutive
+    /// distinct values, or None if all the examples have the same value.
+    fn best_split(
+        &self,
+        inputs: &Array2<f64>,
+        targets: &Array1<f64>,
+        rows: &[usize],
+        counts: &[usize],
+        attribute: usize,
+    ) -> Option<(f64, Split)> {
+        let before = entropy(counts);
+        match &self.attributes[attribute].kind {
+            AttributeKind::Discrete(values) => {
+                let mut branch_counts = vec![vec![0; self.classes.len()]; values.len()];
+                for &row in rows {
+                    let value = inputs[[row, attribute]] as usize;
+                    branch_counts[value][self.class_index(targets[row])] += 1;
+                }
+                Some((
+                    before - remainder(&branch_counts, rows.len()),
+                    Split::Discrete {
+                        attribute,
+                        values: values.len(),
+                    },
+                ))
+            }
+            AttributeKind::Continuous => {
+                let mut sorted: Vec<(f64, usize)> = rows
+                    .iter()
+                    .map(|&row| (inputs[[row, attribute]], self.class_index(targets[row])))
+                    .collect();
+                sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+                let mut below = vec![0; self.classes.len()];
+                let mut best: Option<(f64, f64)> = None;
+                for i in 0..sorted.len() - 1 {
+                    below[sorted[i].1] += 1;
+                    if sorted[i].0 == sorted[i + 1].0 {
+                        continue;
+                    }
+                    let above: Vec<usize> = counts.iter().zip(&below).map(|(c, b)| c - b).collect();
+                    let gain = before - remainder(&[below.clone(), above], rows.len());
+                    if best.is_none_or(|(best_gain, _)| gain > best_gain + 1e-12) {
+                        best = Some((gain, (sorted[i].0 + sorted[i + 1].0) / 2.0));
+                    }
+                }
+                best.map(|(gain, threshold)| {
+                    (
+                        gain,
+                        Split::Threshold {
+                            attribute,
+                            threshold,
+                        },
+                    )
+                })
+            }
+        }
+    }
+
+    fn prune(&self, tree: DecisionTree, significance: Significance) -> DecisionTree {
+        let tree = match tree {
+            DecisionTree::Leaf { .. } => return tree,
+            DecisionTree::Discrete {
+                attribute,
+                children,
+                class,
+                counts,
+            } => DecisionTree::Discrete {
+                attribute,
+                children: children
+                    .into_iter()
+                    .map(|child| self.prune(child, significance))
+                    .collect(),
+                class,
+                counts,
+            },
+            DecisionTree::Threshold {
+                attribute,
+                threshold,
+                below,
+                above,
+                class,
+                counts,
+            } => DecisionTree::Threshold {
+                attribute,
+                threshold,
+                below: Box::new(self.prune(*below, significance)),
+                above: Box::new(self.prune(*above, significance)),
+                class,
+                counts,
+            },
+        };
+        let children = tree.children();
+        if !children.iter().all(|child| child.is_leaf()) {
+            return tree;
+        }
+        let branch_counts: Vec<&[usize]> = children
+            .iter()
+            .map(|child| child.counts())
+            .filter(|counts| counts.iter().sum::<usize>() > 0)
+            .collect();
+        let total: usize = tree.counts().iter().sum();
+        let mut deviation = 0.0;
+        for branch in &branch_counts {
+            let branch_total: usize = branch.iter().sum();
+            for (class, &observed) in branch.iter().enumerate() {
+                let expected = tree.counts()[class] as f64 * branch_total as f64 / total as f64;
+                if expected > 0.0 {
+                    deviation += (observed as f64 - expected).powi(2) / expected;
+                }
+            }
+        }
+        let classes = tree.counts().iter().filter(|&&count| count > 0).count();
+        let degrees_of_freedom =
+            (branch_counts.len().saturating_sub(1)) * (classes.saturating_sub(1));
+        if deviation < significance.critical_value(degrees_of_freedom) {
+            DecisionTree::Leaf {
+                class: tree.class(),
+                counts: tree.counts().to_vec(),
+            }
+        } else {
+            tree
+        }
+    }
+
+    fn class_name(&self, class: f64) -> String {
+        self.class_names
+            .as_ref()
+            .and_then(|names| {
+                if class >= 0.0 && class.fract() == 0.0 {
+                    names.get(class as usize).cloned()
+                } else {
+                    None
+                }
+            })
+            .unwrap_or_else(|| class.to_string())
+    }
+
+    fn write_tree(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        tree: &DecisionTree,
+        depth: usize,
+    ) -> fmt::Result {
+        let branch = |f: &mut fmt::Formatter<'_>, test: String, child: &DecisionTree| {
+            write!(f, "{}{}", "|   ".repeat(depth), test)?;
+            if child.is_leaf() {
+                writeln!(f, ": {}", self.class_name(child.class()))
+            } else {
+                writeln!(f)?;
+                self.write_tree(f, child, depth + 1)
+            }
+        };
+        match tree {
+            DecisionTree::Leaf { .. } => Ok(()),
+            DecisionTree::Discrete {
+                attribute,
+                children,
+                ..
+            } => {
+                let attribute = &self.attributes[*attribute];
+                let AttributeKind::Discrete(values) = &attribute.kind else {
+                    unreachable!("discrete test on a continuous attribute");
+                };
+                for (value, child) in values.iter().zip(children) {
+                    branch(f, format!("{} = {}", attribute.name, value), child)?;
+                }
+                Ok(())
+            }
+            DecisionTree::Threshold {
+                attribute,
+                threshold,
+                below,
+                above,
+                ..
+            } => {
+                let name = &self.attributes[*attribute].name;
+                branch(f, format!("{} <= {}", name, threshold), below)?;
+                branch(f, format!("{} > {}", name, threshold), above)
+            }
+        }
+    }
+}
+
+enum Split {
+    Discrete { attribute: usize, values: usize },
+    Threshold { attribute: usize, threshold: f64 },
+}
+
+/// Entropy in bits of the class distribution with the given counts.
+pub fn entropy(counts: &[usize]) -> f64 {
+    let total: usize = counts.iter().sum();
+    if total == 0 {
+        return 0.0;
+    }
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Expected entropy remaining after a test that splits `total` examples into the branches
+/// with the given class counts.
+fn remainder(branch_counts: &[Vec<usize>], total: usize) -> f64 {
+    branch_counts
+        .iter()
+        .map(|counts| counts.iter().sum::<usize>() as f64 / total as f64 * entropy(counts))
+        .sum()
+}
+
+impl SupervisedLearner for DecisionTreeLearner {
+    fn fit(&mut self, inputs: &Array2<f64>, targets: &Array1<f64>) {
+        let mut classes: Vec<f64> = targets.to_vec();
+        classes.sort_by(f64::total_cmp);
+        classes.dedup();
+        self.classes = classes;
+        let rows: Vec<usize> = (0..targets.len()).collect();
+        let candidates: Vec<usize> = (0..self.attributes.len()).collect();
+        let default = self.classes.first().copied().unwrap_or(0.0);
+        let tree = self.learn(inputs, targets, &rows, &candidates, default);
+        self.tree = Some(match self.pruning {
+            Pruning::None => tree,
+            Pruning::ChiSquared(significance) => self.prune(tree, significance),
+        });
+    }
+
+    /// Panics if called before fit.
+    fn predict(&self, input: ArrayView1<f64>) -> f64 {
+        self.tree
+            .as_ref()
+            .expect("predict called before fit")
+            .classify(input)
+    }
+}
+
+/// Prints the tree one test per line, indented by depth, with the class after each leaf test.
+impl fmt::Display for DecisionTreeLearner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.tree {
+            None => writeln!(f, "(not fitted)"),
+            Some(tree) if tree.is_leaf() => writeln!(f, "{}", self.class_name(tree.class())),
+            Some(tree) => self.write_tree(f, tree, 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examples::{earthquakes, restaurant, RESTAURANT_ATTRIBUTES};
+    use crate::learner::{accuracy, cross_validation};
+    use ndarray::array;
+
+    fn restaurant_learner() -> DecisionTreeLearner {
+        DecisionTreeLearner::new(
+            RESTAURANT_ATTRIBUTES
+                .iter()
+                .map(TreeAttribute::from)
+                .collect(),
+        )
+        .with_class_names(&["No", "Yes"])
+    }
+
+    #[test]
+    fn test_entropy() {
+        assert_eq!(entropy(&[6, 6]), 1.0);
+        assert_eq!(entropy(&[4, 0]), 0.0);
+        assert!((entropy(&[1, 3]) - 0.8113).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_restaurant_tree_matches_figure_19_6() {
+        let (inputs, targets) = restaurant();
+        let mut learner = restaurant_learner();
+        learner.fit(&inputs, &targets);
+        assert_eq!(accuracy(&learner, &inputs, &targets), 1.0);
+
+        let tree = learner.tree().unwrap();
+        let DecisionTree::Discrete { attribute, .. } = tree else {
+            panic!("expected a test at the root");
+        };
+        assert_eq!(RESTAURANT_ATTRIBUTES[*attribute].name, "Patrons");
+        assert_eq!(tree.depth(), 4);
+
+        let printed = learner.to_string();
+        let expected = "\
+Patrons = None: No
+Patrons = Some: Yes
+Patrons = Full
+|   Hungry = No: No
+|   Hungry = Yes
+|   |   Type = French: No
+|   |   Type = Italian: No
+|   |   Type = Thai
+|   |   |   Fri/Sat = No: No
+|   |   |   Fri/Sat = Yes: Yes
+|   |   Type = Burger: Yes
+";
+        assert_eq!(printed, expected);
+    }
+
+    #[test]
+    fn test_restaurant_cross_validation() {
+        let (inputs, targets) = restaurant();
+        let scores = cross_validation(restaurant_learner, &inputs, &targets, 12, 1, accuracy);
+        let mean = scores.iter().sum::<f64>() / scores.len() as f64;
+        assert!(mean >= 0.5, "{:?}", scores);
+    }
+
+    #[test]
+    fn test_continuous_attributes() {
+        let (inputs, targets) = earthquakes();
+        let mut learner = DecisionTreeLearner::new(vec![
+            TreeAttribute::continuous("x1"),
+            TreeAttribute::continuous("x2"),
+        ]);
+        learner.fit(&inputs, &targets);
+        assert_eq!(accuracy(&learner, &inputs, &targets), 1.0);
+        assert!(matches!(
+            learner.tree().unwrap(),
+            DecisionTree::Threshold { .. }
+        ));
+        assert!(learner.to_string().starts_with("x"));
+    }
+
+    #[test]
+    fn test_unseen_value_uses_plurality() {
+        let inputs = array![[0.0], [0.0], [1.0]];
+        let targets = array![1.0, 1.0, 0.0];
+        let mut learner =
+            DecisionTreeLearner::new(vec![TreeAttribute::discrete("a", &["x", "y", "z"])]);
+        learner.fit(&inputs, &targets);
+        assert_eq!(learner.predict(array![2.0].view()), 1.0);
+        assert_eq!(learner.predict(array![7.0].view()), 1.0);
+    }
+
+    #[test]
+    fn test_chi_squared_pruning() {
+        // The first attribute is irrelevant noise; the second decides the class.
+        let noise = [
+            0., 1., 1., 0., 1., 0., 0., 1., 1., 0., 1., 0., 0., 1., 0., 1.,
+        ];
+        let inputs = Array2::from_shape_fn(
+            (16, 2),
+            |(i, j)| {
+                if j == 0 {
+                    noise[i]
+                } else {
+                    (i % 2) as f64
+                }
+            },
+        );
+        let mut targets = inputs.column(1).to_owned();
+        // One mislabeled example makes the tree split on the noise to try to fit it.
+        targets[4] = 1.0 - targets[4];
+        let attributes = vec![
+            TreeAttribute::discrete("Noise", &["a", "b"]),
+            TreeAttribute::discrete("Signal", &["off", "on"]),
+        ];
+
+        let mut unpruned = DecisionTreeLearner::new(attributes.clone());
+        unpruned.fit(&inputs, &targets);
+        assert!(unpruned.tree().unwrap().depth() > 1);
+
+        let mut pruned = DecisionTreeLearner::new(attributes)
+            .with_pruning(Pruning::ChiSquared(Significance::FivePercent));
+        pruned.fit(&inputs, &targets);
+        let tree = pruned.tree().unwrap();
+        assert_eq!(tree.depth(), 1);
+        let DecisionTree::Discrete { attribute, .. } = tree else {
+            panic!("expected the signal test to survive pruning");
+        };
+        assert_eq!(*attribute, 1);
+    }
+
+    #[test]
+    fn test_critical_values() {
+        // Exact values are 3.841, 5.991 and 18.307.
+        for (df, exact) in [(1, 3.841), (2, 5.991), (10, 18.307)] {
+            let approx = Significance::FivePercent.critical_value(df);
+            assert!((approx - exact).abs() / exact < 0.05, "{} {}", df, approx);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Choosing which device tensors live on.
+
+use tch::Device;
+
+/// Environment variable that overrides automatic device selection. Accepts `cpu`, `mps`,
+/// `cuda`, or `cuda:N` for a specific GPU.
+pub const DEVICE_ENV_VAR: &str = "AIMA_DEVICE";
+
+/// Parse a device name such as `cpu`, `mps`, `cuda` or `cuda:1`, ignoring case and surrounding
+/// whitespace.
+pub fn parse_device(name: &str) -> Option<Device> {
+    let name = name.trim().to_ascii_lowercase();
+    match name.as_str() {
+        "cpu" => Some(Device::Cpu),
+        "mps" => Some(Device::Mps),
+        "cuda" => Some(Device::Cuda(0)),
+        _ => name
+            .strip_prefix("cuda:")
+            .and_then(|index| index.parse().ok())
+            .map(Device::Cuda),
+    }
+}
+
+/// Pick a device: the one named by `AIMA_DEVICE` if set, otherwise CUDA, then MPS, then CPU,
+/// whichever is available first. An unrecognised override is reported and ignored.
+pub fn select_device() -> Device {
+    let requested = std::env::var(DEVICE_ENV_VAR).ok();
+    select_device_with(requested.as_deref())
+}
+
+/// [`select_device`] with the override passed in rather than read from the environment.
+pub fn select_device_with(requested: Option<&str>) -> Device {
+    if let Some(name) = requested {
+        match parse_device(name) {
+            Some(device) => return device,
+            None => eprintln!("ignoring unrecognised {DEVICE_ENV_VAR}={name:?}"),
+        }
+    }
+    if tch::Cuda::is_available() {
+        Device::Cuda(0)
+    } else if tch::utils::has_mps() {
+        Device::Mps
+    } else {
+        Device::Cpu
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_device() {
+        assert_eq!(parse_device("cpu"), Some(Device::Cpu));
+        assert_eq!(parse_device(" MPS "), Some(Device::Mps));
+        assert_eq!(parse_device("cuda"), Some(Device::Cuda(0)));
+        assert_eq!(parse_device("cuda:2"), Some(Device::Cuda(2)));
+        assert_eq!(parse_device("cuda:x"), None);
+        assert_eq!(parse_device("tpu"), None);
+    }
+
+    #[test]
+    fn test_override_wins() {
+        assert_eq!(select_device_with(Some("cpu")), Device::Cpu);
+    }
+
+    #[test]
+    fn test_fallback_is_available() {
+        match select_device_with(Some("not a device")) {
+            Device::Cuda(_) => assert!(tch::Cuda::is_available()),
+            Device::Mps => assert!(tch::utils::has_mps()),
+            _ => {}
+        }
+    }
+}
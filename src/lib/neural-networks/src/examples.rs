@@ -0,0 +1,178 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Small datasets in the style of the book's running examples for Chapter 19.
+//
+// See:
+// -  Chapter 19: Learning from Examples, figures 19.2, 19.13 and 19.15
+
+use ndarray::{array, Array1, Array2};
+
+/// A discrete attribute. Its values are encoded as their index in `values`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attribute {
+    pub name: &'static str,
+    pub values: &'static [&'static str],
+}
+
+const BOOLEAN: &[&str] = &["No", "Yes"];
+
+/// The input attributes of the restaurant examples, in column order.
+pub const RESTAURANT_ATTRIBUTES: [Attribute; 10] = [
+    Attribute {
+        name: "Alternate",
+        values: BOOLEAN,
+    },
+    Attribute {
+        name: "Bar",
+        values: BOOLEAN,
+    },
+    Attribute {
+        name: "Fri/Sat",
+        values: BOOLEAN,
+    },
+    Attribute {
+        name: "Hungry",
+        values: BOOLEAN,
+    },
+    Attribute {
+        name: "Patrons",
+        values: &["None", "Some", "Full"],
+    },
+    Attribute {
+        name: "Price",
+        values: &["$", "$$", "$$$"],
+    },
+    Attribute {
+        name: "Raining",
+        values: BOOLEAN,
+    },
+    Attribute {
+        name: "Reservation",
+        values: BOOLEAN,
+    },
+    Attribute {
+        name: "Type",
+        values: &["French", "Italian", "Thai", "Burger"],
+    },
+    Attribute {
+        name: "WaitEstimate",
+        values: &["0-10", "10-30", "30-60", ">60"],
+    },
+];
+
+/// The twelve examples for the restaurant domain, figure 19.2. Each row is encoded with
+/// `RESTAURANT_ATTRIBUTES`, and the target is WillWait, 1.0 for Yes.
+pub fn restaurant() -> (Array2<f64>, Array1<f64>) {
+    let inputs = array![
+        [1., 0., 0., 1., 1., 2., 0., 1., 0., 0.],
+        [1., 0., 0., 1., 2., 0., 0., 0., 2., 2.],
+        [0., 1., 0., 0., 1., 0., 0., 0., 3., 0.],
+        [1., 0., 1., 1., 2., 0., 1., 0., 2., 1.],
+        [1., 0., 1., 0., 2., 2., 0., 1., 0., 3.],
+        [0., 1., 0., 1., 1., 1., 1., 1., 1., 0.],
+        [0., 1., 0., 0., 0., 0., 1., 0., 3., 0.],
+        [0., 0., 0., 1., 1., 1., 1., 1., 2., 0.],
+        [0., 1., 1., 0., 2., 0., 1., 0., 3., 3.],
+        [1., 1., 1., 1., 2., 2., 0., 1., 1., 1.],
+        [0., 0., 0., 0., 0., 0., 0., 0., 2., 0.],
+        [1., 1., 1., 1., 2., 0., 0., 0., 3., 2.],
+    ];
+    let targets = array![1., 0., 1., 1., 0., 1., 0., 1., 0., 0., 0., 1.];
+    (inputs, targets)
+}
+
+/// Seismic readings in the style of figure 19.15: body wave magnitude x₁ and surface wave
+/// magnitude x₂. The target is 1.0 for a nuclear explosion and 0.0 for an earthquake, and the
+/// two classes are linearly separable.
+pub fn earthquakes() -> (Array2<f64>, Array1<f64>) {
+    let earthquakes = [
+        [4.8, 4.0],
+        [5.0, 4.4],
+        [5.2, 4.6],
+        [5.3, 5.0],
+        [5.4, 4.8],
+        [5.5, 4.9],
+        [5.6, 5.4],
+        [5.8, 5.5],
+        [5.9, 5.8],
+        [6.0, 5.9],
+        [6.1, 6.2],
+        [6.3, 6.4],
+    ];
+    let explosions = [
+        [5.4, 3.6],
+        [5.5, 3.7],
+        [5.6, 3.9],
+        [5.7, 4.0],
+        [5.8, 4.2],
+        [5.9, 4.3],
+        [6.0, 4.5],
+        [6.1, 4.6],
+        [6.2, 4.8],
+        [6.3, 5.0],
+        [6.4, 5.1],
+        [6.5, 5.2],
+    ];
+    // Interleave the classes so that learners that visit examples in order see both early.
+    let rows: Vec<([f64; 2], f64)> = earthquakes
+        .iter()
+        .zip(&explosions)
+        .flat_map(|(&earthquake, &explosion)| [(earthquake, 0.0), (explosion, 1.0)])
+        .collect();
+    let inputs = Array2::from_shape_fn((rows.len(), 2), |(i, j)| rows[i].0[j]);
+    let targets = rows.iter().map(|(_, target)| *target).collect();
+    (inputs, targets)
+}
+
+/// House prices in the style of figure 19.13: floor space in thousands of square feet, and
+/// asking price in thousands of dollars, scattered around y = 246 + 232x.
+pub fn house_prices() -> (Array2<f64>, Array1<f64>) {
+    let data = array![
+        [0.8, 457.],
+        [1.1, 483.],
+        [1.3, 578.],
+        [1.5, 572.],
+        [1.7, 652.],
+        [1.9, 652.],
+        [2.1, 753.],
+        [2.3, 770.],
+        [2.6, 877.],
+        [2.9, 893.],
+        [3.2, 1003.],
+        [3.4, 1015.],
+    ];
+    let inputs = data.slice(ndarray::s![.., 0..1]).to_owned();
+    let targets = data.column(1).to_owned();
+    (inputs, targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restaurant_encoding() {
+        let (inputs, targets) = restaurant();
+        assert_eq!(inputs.dim(), (12, RESTAURANT_ATTRIBUTES.len()));
+        assert_eq!(targets.sum(), 6.0);
+        for row in inputs.outer_iter() {
+            for (value, attribute) in row.iter().zip(&RESTAURANT_ATTRIBUTES) {
+                assert!((*value as usize) < attribute.values.len());
+            }
+        }
+    }
+}
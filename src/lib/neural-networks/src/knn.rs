@@ -0,0 +1,443 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// k-nearest-neighbors classification and regression, with pluggable distance metrics and a
+// k-d tree index.
+//
+// See:
+// -  Chapter 19: Learning from Examples, sections 19.7.1 and 19.7.2
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use ndarray::{Array1, Array2, ArrayView1};
+
+use crate::learner::SupervisedLearner;
+
+/// A distance between two inputs.
+pub trait Metric {
+    fn distance(&self, a: ArrayView1<f64>, b: ArrayView1<f64>) -> f64;
+
+    /// A lower bound on the distance between two inputs whose values along one axis differ by
+    /// `difference`, used by the k-d tree to skip subtrees. The default of 0 is always safe
+    /// but never skips anything.
+    fn axis_lower_bound(&self, _difference: f64) -> f64 {
+        0.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Euclidean;
+
+impl Metric for Euclidean {
+    fn distance(&self, a: ArrayView1<f64>, b: ArrayView1<f64>) -> f64 {
+        a.iter()
+            .zip(b)
+            .map(|(x, y)| (x - y) * (x - y))
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    fn axis_lower_bound(&self, difference: f64) -> f64 {
+        difference.abs()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Manhattan;
+
+impl Metric for Manhattan {
+    fn distance(&self, a: ArrayView1<f64>, b: ArrayView1<f64>) -> f64 {
+        a.iter().zip(b).map(|(x, y)| (x - y).abs()).sum()
+    }
+
+    fn axis_lower_bound(&self, difference: f64) -> f64 {
+        difference.abs()
+    }
+}
+
+/// The Minkowski distance Lᵖ, equation (19.10). p = 1 is Manhattan and p = 2 is Euclidean.
+#[derive(Debug, Clone, Copy)]
+pub struct Minkowski(pub f64);
+
+impl Metric for Minkowski {
+    fn distance(&self, a: ArrayView1<f64>, b: ArrayView1<f64>) -> f64 {
+        a.iter()
+            .zip(b)
+            .map(|(x, y)| (x - y).abs().powf(self.0))
+            .sum::<f64>()
+            .powf(1.0 / self.0)
+    }
+
+    fn axis_lower_bound(&self, difference: f64) -> f64 {
+        difference.abs()
+    }
+}
+
+/// The number of attributes on which two inputs differ, for Boolean or discrete attributes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Hamming;
+
+impl Metric for Hamming {
+    fn distance(&self, a: ArrayView1<f64>, b: ArrayView1<f64>) -> f64 {
+        a.iter().zip(b).filter(|(x, y)| x != y).count() as f64
+    }
+}
+
+/// How the targets of the k nearest neighbors are combined into a prediction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Vote {
+    /// The most common target, ties going to the smallest. For classification.
+    #[default]
+    Plurality,
+    /// The mean target. For regression.
+    Mean,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Index {
+    /// Compare the query with every example.
+    BruteForce,
+    /// A k-d tree, which answers queries in about O(log N) time when there are many more
+    /// examples than dimensions.
+    #[default]
+    KdTree,
+}
+
+/// A k-d tree node: the example stored at the node, the axis it splits on, and its subtrees
+/// with smaller and larger values along that axis.
+#[derive(Debug, Clone)]
+struct KdNode {
+    example: usize,
+    axis: usize,
+    left: Option<usize>,
+    right: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct KdTree {
+    nodes: Vec<KdNode>,
+    root: Option<usize>,
+}
+
+impl KdTree {
+    /// Splits on the median along each axis in turn.
+    fn build(inputs: &Array2<f64>) -> Self {
+        let mut tree = KdTree::default();
+        let mut examples: Vec<usize> = (0..inputs.nrows()).collect();
+        tree.root = tree.build_subtree(inputs, &mut examples, 0);
+        tree
+    }
+
+    fn build_subtree(
+        &mut self,
+        inputs: &Array2<f64>,
+        examples: &mut [usize],
+        depth: usize,
+    ) -> Option<usize> {
+        if examples.is_empty() {
+            return None;
+        }
+        let axis = depth % inputs.ncols().max(1);
+        examples.sort_by(|&a, &b| {
+            inputs[[a, axis]]
+                .total_cmp(&inputs[[b, axis]])
+                .then(a.cmp(&b))
+        });
+        let median = examples.len() / 2;
+        let (smaller, rest) = examples.split_at_mut(median);
+        let (example, larger) = rest.split_first_mut().unwrap();
+        let example = *example;
+        let left = self.build_subtree(inputs, smaller, depth + 1);
+        let right = self.build_subtree(inputs, larger, depth + 1);
+        self.nodes.push(KdNode {
+            example,
+            axis,
+            left,
+            right,
+        });
+        Some(self.nodes.len() - 1)
+    }
+
+    fn search<Metric_: Metric>(
+        &self,
+        inputs: &Array2<f64>,
+        metric: &Metric_,
+        query: ArrayView1<f64>,
+        node: Option<usize>,
+        nearest: &mut Nearest,
+    ) {
+        let Some(node) = node else {
+            return;
+        };
+        let node = &self.nodes[node];
+        nearest.offer(
+            node.example,
+            metric.distance(inputs.row(node.example), query),
+        );
+        let difference = query[node.axis] - inputs[[node.example, node.axis]];
+        let (near, far) = if difference <= 0.0 {
+            (node.left, node.right)
+        } else {
+            (node.right, node.left)
+        };
+        self.search(inputs, metric, query, near, nearest);
+        if nearest.admits(metric.axis_lower_bound(difference)) {
+            self.search(inputs, metric, query, far, nearest);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Neighbor {
+    distance: f64,
+    example: usize,
+}
+
+impl Eq for Neighbor {}
+
+impl Ord for Neighbor {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .total_cmp(&other.distance)
+            .then(self.example.cmp(&other.example))
+    }
+}
+
+impl PartialOrd for Neighbor {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The k closest examples seen so far, in a max-heap so the farthest is cheap to replace.
+/// Equally distant examples are ordered by index, so every index gives the same answer.
+struct Nearest {
+    k: usize,
+    heap: BinaryHeap<Neighbor>,
+}
+
+impl Nearest {
+    fn new(k: usize) -> Self {
+        Nearest {
+            k,
+            heap: BinaryHeap::with_capacity(k + 1),
+        }
+    }
+
+    fn offer(&mut self, example: usize, distance: f64) {
+        self.heap.push(Neighbor { distance, example });
+        if self.heap.len() > self.k {
+            self.heap.pop();
+        }
+    }
+
+    /// Whether an example at `distance` could still be among the k nearest.
+    fn admits(&self, distance: f64) -> bool {
+        self.heap.len() < self.k
+            || self
+                .heap
+                .peek()
+                .is_some_and(|worst| distance <= worst.distance)
+    }
+
+    fn into_sorted(self) -> Vec<(usize, f64)> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|neighbor| (neighbor.example, neighbor.distance))
+            .collect()
+    }
+}
+
+/// k-nearest-neighbors. Fitting just stores the examples, and building the index if there is
+/// one; all the work happens at prediction time.
+#[derive(Debug, Clone)]
+pub struct KNearestNeighbors<Metric_: Metric = Euclidean> {
+    k: usize,
+    metric: Metric_,
+    vote: Vote,
+    index: Index,
+    inputs: Array2<f64>,
+    targets: Array1<f64>,
+    kd_tree: KdTree,
+}
+
+impl KNearestNeighbors<Euclidean> {
+    pub fn new(k: usize) -> Self {
+        KNearestNeighbors::with_metric(k, Euclidean)
+    }
+}
+
+impl<Metric_: Metric> KNearestNeighbors<Metric_> {
+    pub fn with_metric(k: usize, metric: Metric_) -> Self {
+        KNearestNeighbors {
+            k: k.max(1),
+            metric,
+            vote: Vote::default(),
+            index: Index::default(),
+            inputs: Array2::zeros((0, 0)),
+            targets: Array1::zeros(0),
+            kd_tree: KdTree::default(),
+        }
+    }
+
+    pub fn with_vote(mut self, vote: Vote) -> Self {
+        self.vote = vote;
+        self
+    }
+
+    pub fn with_index(mut self, index: Index) -> Self {
+        self.index = index;
+        self
+    }
+
+    /// The k examples closest to `query`, nearest first, as (row, distance) pairs.
+    pub fn nearest(&self, query: ArrayView1<f64>) -> Vec<(usize, f64)> {
+        let mut nearest = Nearest::new(self.k);
+        match self.index {
+            Index::BruteForce => {
+                for (example, input) in self.inputs.outer_iter().enumerate() {
+                    nearest.offer(example, self.metric.distance(input, query));
+                }
+            }
+            Index::KdTree => self.kd_tree.search(
+                &self.inputs,
+                &self.metric,
+                query,
+                self.kd_tree.root,
+                &mut nearest,
+            ),
+        }
+        nearest.into_sorted()
+    }
+}
+
+impl<Metric_: Metric> SupervisedLearner for KNearestNeighbors<Metric_> {
+    fn fit(&mut self, inputs: &Array2<f64>, targets: &Array1<f64>) {
+        self.inputs = inputs.clone();
+        self.targets = targets.clone();
+        self.kd_tree = match self.index {
+            Index::BruteForce => KdTree::default(),
+            Index::KdTree => KdTree::build(inputs),
+        };
+    }
+
+    fn predict(&self, input: ArrayView1<f64>) -> f64 {
+        let neighbors: Vec<f64> = self
+            .nearest(input)
+            .into_iter()
+            .map(|(example, _)| self.targets[example])
+            .collect();
+        match self.vote {
+            Vote::Mean => neighbors.iter().sum::<f64>() / neighbors.len().max(1) as f64,
+            Vote::Plurality => {
+                let mut sorted = neighbors;
+                sorted.sort_by(f64::total_cmp);
+                let mut best = (f64::NAN, 0);
+                for run in sorted.chunk_by(|a, b| a == b) {
+                    if run.len() > best.1 {
+                        best = (run[0], run.len());
+                    }
+                }
+                best.0
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examples::{earthquakes, restaurant};
+    use crate::learner::{accuracy, cross_validation};
+    use ndarray::array;
+    use rand::{Rng, SeedableRng};
+    use rand_pcg::Pcg64;
+
+    fn random_points(rows: usize, columns: usize, seed: u64) -> Array2<f64> {
+        let mut rng = Pcg64::seed_from_u64(seed);
+        Array2::from_shape_fn((rows, columns), |_| rng.gen_range(0.0..10.0))
+    }
+
+    fn check_index_agrees<Metric_: Metric + Copy>(metric: Metric_) {
+        let inputs = random_points(300, 3, 1);
+        let targets = Array1::zeros(300);
+        let mut brute = KNearestNeighbors::with_metric(5, metric).with_index(Index::BruteForce);
+        let mut kd = KNearestNeighbors::with_metric(5, metric).with_index(Index::KdTree);
+        brute.fit(&inputs, &targets);
+        kd.fit(&inputs, &targets);
+        for query in random_points(50, 3, 2).outer_iter() {
+            assert_eq!(brute.nearest(query), kd.nearest(query));
+        }
+    }
+
+    #[test]
+    fn test_kd_tree_matches_brute_force() {
+        check_index_agrees(Euclidean);
+        check_index_agrees(Manhattan);
+        check_index_agrees(Minkowski(3.0));
+        check_index_agrees(Hamming);
+    }
+
+    #[test]
+    fn test_metrics() {
+        let a = array![0.0, 0.0];
+        let b = array![3.0, 4.0];
+        assert_eq!(Euclidean.distance(a.view(), b.view()), 5.0);
+        assert_eq!(Manhattan.distance(a.view(), b.view()), 7.0);
+        assert!((Minkowski(2.0).distance(a.view(), b.view()) - 5.0).abs() < 1e-12);
+        assert_eq!(Hamming.distance(a.view(), array![0.0, 1.0].view()), 1.0);
+    }
+
+    #[test]
+    fn test_classifies_earthquakes() {
+        let (inputs, targets) = earthquakes();
+        let mut knn = KNearestNeighbors::new(1);
+        knn.fit(&inputs, &targets);
+        assert_eq!(accuracy(&knn, &inputs, &targets), 1.0);
+        assert_eq!(knn.predict(array![6.4, 4.0].view()), 1.0);
+        assert_eq!(knn.predict(array![5.0, 5.5].view()), 0.0);
+
+        let scores = cross_validation(
+            || KNearestNeighbors::new(3),
+            &inputs,
+            &targets,
+            4,
+            3,
+            accuracy,
+        );
+        let mean = scores.iter().sum::<f64>() / scores.len() as f64;
+        assert!(mean >= 0.75, "{:?}", scores);
+    }
+
+    #[test]
+    fn test_hamming_on_restaurant() {
+        let (inputs, targets) = restaurant();
+        let mut knn = KNearestNeighbors::with_metric(1, Hamming);
+        knn.fit(&inputs, &targets);
+        assert_eq!(accuracy(&knn, &inputs, &targets), 1.0);
+    }
+
+    #[test]
+    fn test_mean_vote_regression() {
+        let inputs = array![[0.0], [1.0], [2.0], [10.0]];
+        let targets = array![0.0, 2.0, 4.0, 20.0];
+        let mut knn = KNearestNeighbors::new(3).with_vote(Vote::Mean);
+        knn.fit(&inputs, &targets);
+        assert_eq!(knn.predict(array![1.2].view()), 2.0);
+    }
+}
@@ -0,0 +1,195 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// The interface shared by the Chapter 19 learners, and helpers for evaluating them.
+//
+// See:
+// -  Chapter 19: Learning from Examples, sections 19.1 and 19.4
+
+use ndarray::{Array1, Array2, ArrayView1, Axis};
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+
+/// Learns a hypothesis h from examples (x, y). Inputs are the rows of a matrix, and each has a
+/// single target: a real number for regression, or a class label for classification. Class
+/// labels are stored as small whole numbers, 0.0 and 1.0 for a Boolean attribute.
+pub trait SupervisedLearner {
+    fn fit(&mut self, inputs: &Array2<f64>, targets: &Array1<f64>);
+
+    fn predict(&self, input: ArrayView1<f64>) -> f64;
+
+    fn predict_all(&self, inputs: &Array2<f64>) -> Array1<f64> {
+        inputs.outer_iter().map(|x| self.predict(x)).collect()
+    }
+}
+
+/// Fraction of examples whose prediction equals the target.
+pub fn accuracy<Learner: SupervisedLearner + ?Sized>(
+    learner: &Learner,
+    inputs: &Array2<f64>,
+    targets: &Array1<f64>,
+) -> f64 {
+    let correct = learner
+        .predict_all(inputs)
+        .iter()
+        .zip(targets)
+        .filter(|(prediction, target)| prediction == target)
+        .count();
+    correct as f64 / targets.len().max(1) as f64
+}
+
+pub fn mean_squared_error<Learner: SupervisedLearner + ?Sized>(
+    learner: &Learner,
+    inputs: &Array2<f64>,
+    targets: &Array1<f64>,
+) -> f64 {
+    let errors = learner.predict_all(inputs) - targets;
+    errors.mapv(|e| e * e).sum() / targets.len().max(1) as f64
+}
+
+/// k-fold cross-validation, section 19.4.1. The examples are shuffled with `seed` and split
+/// into k folds. Each fold in turn is held out while a fresh learner from `make_learner` is
+/// trained on the rest, and `score` is measured on the held-out fold. Returns the score of
+/// each fold.
+pub fn cross_validation<Learner: SupervisedLearner>(
+    make_learner: impl Fn() -> Learner,
+    inputs: &Array2<f64>,
+    targets: &Array1<f64>,
+    k: usize,
+    seed: u64,
+    score: impl Fn(&Learner, &Array2<f64>, &Array1<f64>) -> f64,
+) -> Vec<f64> {
+    let mut indices: Vec<usize> = (0..targets.len()).collect();
+    indices.shuffle(&mut Pcg64::seed_from_u64(seed));
+    let k = k.clamp(2, targets.len().max(2));
+    (0..k)
+        .map(|fold| {
+            let (held_out, training): (Vec<usize>, Vec<usize>) = indices.iter().enumerate().fold(
+                (vec![], vec![]),
+                |(mut held_out, mut training), (position, &index)| {
+                    if position % k == fold {
+                        held_out.push(index);
+                    } else {
+                        training.push(index);
+                    }
+                    (held_out, training)
+                },
+            );
+            let mut learner = make_learner();
+            learner.fit(
+                &inputs.select(Axis(0), &training),
+                &targets.select(Axis(0), &training),
+            );
+            score(
+                &learner,
+                &inputs.select(Axis(0), &held_out),
+                &targets.select(Axis(0), &held_out),
+            )
+        })
+        .collect()
+}
+
+/// Predictions over an evenly spaced `steps` × `steps` grid of two-dimensional inputs,
+/// spanning `x_range` along the columns and `y_range` along the rows, with row 0 at the bottom.
+/// Plotting this shows a classifier's decision regions.
+pub fn decision_grid<Learner: SupervisedLearner + ?Sized>(
+    learner: &Learner,
+    x_range: (f64, f64),
+    y_range: (f64, f64),
+    steps: usize,
+) -> Array2<f64> {
+    let coordinate = |(low, high): (f64, f64), i: usize| {
+        if steps < 2 {
+            low
+        } else {
+            low + (high - low) * i as f64 / (steps - 1) as f64
+        }
+    };
+    Array2::from_shape_fn((steps, steps), |(row, column)| {
+        let input = ndarray::array![coordinate(x_range, column), coordinate(y_range, row)];
+        learner.predict(input.view())
+    })
+}
+
+/// A learner whose hypothesis depends on w · x for a weight vector w, with w₀ the weight of a
+/// dummy input that is always 1.
+pub trait LinearModel {
+    fn weights(&self) -> &Array1<f64>;
+
+    /// For two inputs, the decision boundary w₀ + w₁x₁ + w₂x₂ = 0 as the line
+    /// x₂ = intercept + slope × x₁. None if there aren't exactly two inputs or the line is
+    /// vertical.
+    fn boundary_line(&self) -> Option<(f64, f64)> {
+        let w = self.weights();
+        if w.len() != 3 || w[2] == 0.0 {
+            return None;
+        }
+        Some((-w[0] / w[2], -w[1] / w[2]))
+    }
+}
+
+/// w · x with the dummy input x₀ = 1 prepended to x.
+pub(crate) fn dot_with_bias(weights: &Array1<f64>, input: ArrayView1<f64>) -> f64 {
+    weights[0] + weights.slice(ndarray::s![1..]).dot(&input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    /// Predicts 1 when the first input is positive.
+    struct Sign;
+
+    impl SupervisedLearner for Sign {
+        fn fit(&mut self, _inputs: &Array2<f64>, _targets: &Array1<f64>) {}
+
+        fn predict(&self, input: ArrayView1<f64>) -> f64 {
+            if input[0] > 0.0 {
+                1.0
+            } else {
+                0.0
+            }
+        }
+    }
+
+    #[test]
+    fn test_accuracy_and_error() {
+        let inputs = array![[1.0], [-1.0], [2.0], [-2.0]];
+        let targets = array![1.0, 0.0, 0.0, 0.0];
+        assert_eq!(accuracy(&Sign, &inputs, &targets), 0.75);
+        assert_eq!(mean_squared_error(&Sign, &inputs, &targets), 0.25);
+    }
+
+    #[test]
+    fn test_cross_validation_uses_every_example_once() {
+        let inputs = Array2::from_shape_fn((10, 1), |(i, _)| i as f64 - 4.5);
+        let targets = inputs.column(0).mapv(|x| if x > 0.0 { 1.0 } else { 0.0 });
+        let held_out_sizes =
+            cross_validation(|| Sign, &inputs, &targets, 3, 1, |_, _, y| y.len() as f64);
+        assert_eq!(held_out_sizes, vec![4.0, 3.0, 3.0]);
+        let scores = cross_validation(|| Sign, &inputs, &targets, 5, 1, accuracy);
+        assert!(scores.iter().all(|&score| score == 1.0));
+    }
+
+    #[test]
+    fn test_decision_grid() {
+        let grid = decision_grid(&Sign, (-1.0, 1.0), (0.0, 1.0), 3);
+        assert_eq!(grid.row(0), array![0.0, 0.0, 1.0]);
+        assert_eq!(grid.row(2), array![0.0, 0.0, 1.0]);
+    }
+}
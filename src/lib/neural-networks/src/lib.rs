@@ -14,16 +14,51 @@
  * with this program. If not, see <https://www.gnu.org/licenses/>
  */
 
-use tch::Tensor;
+pub mod connect_four;
+pub mod dataset;
+pub mod decision_tree;
+#[cfg(feature = "torch")]
+pub mod device;
+pub mod examples;
+pub mod knn;
+pub mod learner;
+pub mod linear;
+pub mod mlp;
+pub mod naive_bayes;
+#[cfg(feature = "onnx")]
+pub mod onnx;
+pub mod serialization;
+#[cfg(feature = "torch")]
+pub mod tch_model;
+pub mod train;
 
-pub fn grad_example() {
+#[cfg(feature = "torch")]
+use tch::{Device, Tensor};
+
+#[cfg(feature = "torch")]
+pub use device::select_device;
+
+/// Compute y = x² + x + 36 at x = 2 on the given device and differentiate it with autograd.
+/// Returns (y, dy/dx), which should be (42, 5).
+#[cfg(feature = "torch")]
+pub fn grad_example(device: Device) -> (f64, f64) {
     let mut x = Tensor::from(2.0f32)
-        .to_device(tch::Device::Mps)
+        .to_device(device)
         .set_requires_grad(true);
     let y = &x * &x + &x + 36;
-    println!("{}", y.double_value(&[]));
+    let value = y.double_value(&[]);
     x.zero_grad();
     y.backward();
-    let dy_over_dx = x.grad();
-    println!("{}", dy_over_dx.double_value(&[]));
+    let dy_over_dx = x.grad().double_value(&[]);
+    (value, dy_over_dx)
+}
+
+#[cfg(all(test, feature = "torch"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grad_example_on_cpu() {
+        assert_eq!(grad_example(Device::Cpu), (42.0, 5.0));
+    }
 }
@@ -0,0 +1,279 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Linear models: the perceptron learning rule, linear regression by gradient descent, and
+// logistic regression.
+//
+// See:
+// -  Chapter 19: Learning from Examples, section 19.6
+
+use ndarray::{Array1, Array2, ArrayView1};
+
+use crate::learner::{dot_with_bias, LinearModel, SupervisedLearner};
+
+fn sigmoid(z: f64) -> f64 {
+    1.0 / (1.0 + (-z).exp())
+}
+
+/// Univariate linear regression in closed form, equation (19.3): the w₀ and w₁ that minimize
+/// the squared loss of y = w₀ + w₁x.
+pub fn univariate_least_squares(xs: &[f64], ys: &[f64]) -> (f64, f64) {
+    let n = xs.len() as f64;
+    let sum_x: f64 = xs.iter().sum();
+    let sum_y: f64 = ys.iter().sum();
+    let sum_xy: f64 = xs.iter().zip(ys).map(|(x, y)| x * y).sum();
+    let sum_xx: f64 = xs.iter().map(|x| x * x).sum();
+    let w1 = (n * sum_xy - sum_x * sum_y) / (n * sum_xx - sum_x * sum_x);
+    let w0 = (sum_y - w1 * sum_x) / n;
+    (w0, w1)
+}
+
+/// A linear classifier with a hard threshold, h(x) = 1 if w · x ≥ 0 and 0 otherwise, trained
+/// with the perceptron learning rule wᵢ ← wᵢ + α(y − h(x))xᵢ, one example at a time. Training
+/// stops early after an epoch with no mistakes, which happens only if the data are linearly
+/// separable.
+#[derive(Debug, Clone)]
+pub struct Perceptron {
+    weights: Array1<f64>,
+    learning_rate: f64,
+    max_epochs: usize,
+    epochs_run: usize,
+}
+
+impl Perceptron {
+    pub fn new(learning_rate: f64, max_epochs: usize) -> Self {
+        Perceptron {
+            weights: Array1::zeros(0),
+            learning_rate,
+            max_epochs,
+            epochs_run: 0,
+        }
+    }
+
+    /// Epochs used by the last call to fit.
+    pub fn epochs_run(&self) -> usize {
+        self.epochs_run
+    }
+}
+
+impl SupervisedLearner for Perceptron {
+    fn fit(&mut self, inputs: &Array2<f64>, targets: &Array1<f64>) {
+        self.weights = Array1::zeros(inputs.ncols() + 1);
+        self.epochs_run = 0;
+        while self.epochs_run < self.max_epochs {
+            self.epochs_run += 1;
+            let mut mistakes = 0;
+            for (x, &y) in inputs.outer_iter().zip(targets) {
+                let error = y - self.predict(x);
+                if error != 0.0 {
+                    mistakes += 1;
+                    self.weights[0] += self.learning_rate * error;
+                    self.weights
+                        .slice_mut(ndarray::s![1..])
+                        .scaled_add(self.learning_rate * error, &x);
+                }
+            }
+            if mistakes == 0 {
+                break;
+            }
+        }
+    }
+
+    fn predict(&self, input: ArrayView1<f64>) -> f64 {
+        if dot_with_bias(&self.weights, input) >= 0.0 {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+impl LinearModel for Perceptron {
+    fn weights(&self) -> &Array1<f64> {
+        &self.weights
+    }
+}
+
+/// Multivariable linear regression, h(x) = w · x, fitted by batch gradient descent on the
+/// squared loss. Each epoch takes one step wᵢ ← wᵢ + α Σⱼ (yⱼ − h(xⱼ)) xⱼ,ᵢ / N; dividing by N
+/// keeps the step size independent of the number of examples.
+#[derive(Debug, Clone)]
+pub struct LinearRegression {
+    weights: Array1<f64>,
+    learning_rate: f64,
+    epochs: usize,
+}
+
+impl LinearRegression {
+    pub fn new(learning_rate: f64, epochs: usize) -> Self {
+        LinearRegression {
+            weights: Array1::zeros(0),
+            learning_rate,
+            epochs,
+        }
+    }
+}
+
+impl SupervisedLearner for LinearRegression {
+    fn fit(&mut self, inputs: &Array2<f64>, targets: &Array1<f64>) {
+        self.weights = Array1::zeros(inputs.ncols() + 1);
+        let n = targets.len().max(1) as f64;
+        for _ in 0..self.epochs {
+            let errors = targets - &self.predict_all(inputs);
+            let step = self.learning_rate / n;
+            self.weights[0] += step * errors.sum();
+            let gradient = inputs.t().dot(&errors);
+            self.weights
+                .slice_mut(ndarray::s![1..])
+                .scaled_add(step, &gradient);
+        }
+    }
+
+    fn predict(&self, input: ArrayView1<f64>) -> f64 {
+        dot_with_bias(&self.weights, input)
+    }
+}
+
+impl LinearModel for LinearRegression {
+    fn weights(&self) -> &Array1<f64> {
+        &self.weights
+    }
+}
+
+/// A linear classifier with a logistic threshold, h(x) = 1 / (1 + e^(−w · x)), read as the
+/// probability that the example belongs to class 1. Trained one example at a time with the
+/// book's update for the squared loss, wᵢ ← wᵢ + α(y − h(x)) h(x)(1 − h(x)) xᵢ.
+#[derive(Debug, Clone)]
+pub struct LogisticRegression {
+    weights: Array1<f64>,
+    learning_rate: f64,
+    epochs: usize,
+}
+
+impl LogisticRegression {
+    pub fn new(learning_rate: f64, epochs: usize) -> Self {
+        LogisticRegression {
+            weights: Array1::zeros(0),
+            learning_rate,
+            epochs,
+        }
+    }
+
+    pub fn probability(&self, input: ArrayView1<f64>) -> f64 {
+        sigmoid(dot_with_bias(&self.weights, input))
+    }
+}
+
+impl SupervisedLearner for LogisticRegression {
+    fn fit(&mut self, inputs: &Array2<f64>, targets: &Array1<f64>) {
+        self.weights = Array1::zeros(inputs.ncols() + 1);
+        for _ in 0..self.epochs {
+            for (x, &y) in inputs.outer_iter().zip(targets) {
+                let h = self.probability(x);
+                let step = self.learning_rate * (y - h) * h * (1.0 - h);
+                self.weights[0] += step;
+                self.weights
+                    .slice_mut(ndarray::s![1..])
+                    .scaled_add(step, &x);
+            }
+        }
+    }
+
+    fn predict(&self, input: ArrayView1<f64>) -> f64 {
+        if self.probability(input) >= 0.5 {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+impl LinearModel for LogisticRegression {
+    fn weights(&self) -> &Array1<f64> {
+        &self.weights
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examples::{earthquakes, house_prices, restaurant};
+    use crate::learner::{accuracy, cross_validation, decision_grid, mean_squared_error};
+
+    #[test]
+    fn test_perceptron_separates_earthquakes_from_explosions() {
+        let (inputs, targets) = earthquakes();
+        let mut perceptron = Perceptron::new(0.1, 10_000);
+        perceptron.fit(&inputs, &targets);
+        assert!(perceptron.epochs_run() < 10_000);
+        assert_eq!(accuracy(&perceptron, &inputs, &targets), 1.0);
+
+        // Explosions lie below the boundary line, earthquakes above it.
+        let (intercept, slope) = perceptron.boundary_line().unwrap();
+        for (x, &y) in inputs.outer_iter().zip(&targets) {
+            let below = x[1] < intercept + slope * x[0];
+            assert_eq!(below, y == 1.0);
+        }
+    }
+
+    #[test]
+    fn test_perceptron_fits_restaurant_examples() {
+        let (inputs, targets) = restaurant();
+        let mut perceptron = Perceptron::new(0.1, 1_000);
+        perceptron.fit(&inputs, &targets);
+        assert_eq!(accuracy(&perceptron, &inputs, &targets), 1.0);
+    }
+
+    #[test]
+    fn test_linear_regression_matches_closed_form() {
+        let (inputs, targets) = house_prices();
+        let mut regression = LinearRegression::new(0.1, 5_000);
+        regression.fit(&inputs, &targets);
+
+        let xs: Vec<f64> = inputs.column(0).to_vec();
+        let (w0, w1) = univariate_least_squares(&xs, targets.as_slice().unwrap());
+        assert!((regression.weights()[0] - w0).abs() < 1e-6 * w0.abs());
+        assert!((regression.weights()[1] - w1).abs() < 1e-6 * w1.abs());
+        assert!(mean_squared_error(&regression, &inputs, &targets) < 30.0 * 30.0);
+    }
+
+    #[test]
+    fn test_logistic_regression_on_earthquakes() {
+        let (inputs, targets) = earthquakes();
+        let mut logistic = LogisticRegression::new(1.0, 5_000);
+        logistic.fit(&inputs, &targets);
+        assert_eq!(accuracy(&logistic, &inputs, &targets), 1.0);
+
+        // Probabilities are confident far from the boundary.
+        assert!(logistic.probability(ndarray::array![6.5, 3.5].view()) > 0.9);
+        assert!(logistic.probability(ndarray::array![4.5, 6.0].view()) < 0.1);
+
+        let grid = decision_grid(&logistic, (4.5, 6.5), (3.5, 6.5), 5);
+        assert_eq!(grid[[0, 4]], 1.0);
+        assert_eq!(grid[[4, 0]], 0.0);
+
+        let scores = cross_validation(
+            || LogisticRegression::new(1.0, 2_000),
+            &inputs,
+            &targets,
+            4,
+            7,
+            accuracy,
+        );
+        let mean = scores.iter().sum::<f64>() / scores.len() as f64;
+        assert!(mean >= 0.75, "{:?}", scores);
+    }
+}
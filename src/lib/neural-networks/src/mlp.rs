@@ -0,0 +1,395 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// A small multilayer perceptron built on ndarray, with no libtorch dependency.
+//
+// See:
+// -  Chapter 21: Deep Learning, sections 21.1 and 21.4.1
+
+use ndarray::{Array1, Array2, ArrayView1, Axis};
+use rand::{Rng, SeedableRng};
+use rand_distr::{Distribution, Normal, Uniform};
+use rand_pcg::Pcg64;
+use serde::{Deserialize, Serialize};
+
+/// The nonlinearity g applied to a unit's weighted input, section 21.1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Activation {
+    Identity,
+    Sigmoid,
+    Relu,
+    Tanh,
+}
+
+impl Activation {
+    pub fn apply(self, z: f64) -> f64 {
+        match self {
+            Activation::Identity => z,
+            Activation::Sigmoid => 1.0 / (1.0 + (-z).exp()),
+            Activation::Relu => z.max(0.0),
+            Activation::Tanh => z.tanh(),
+        }
+    }
+
+    /// g'(z), given both the input z and the output a = g(z) since the sigmoid and tanh
+    /// derivatives are cheapest in terms of a.
+    pub fn derivative(self, z: f64, a: f64) -> f64 {
+        match self {
+            Activation::Identity => 1.0,
+            Activation::Sigmoid => a * (1.0 - a),
+            Activation::Relu => {
+                if z > 0.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Activation::Tanh => 1.0 - a * a,
+        }
+    }
+}
+
+/// Turn logits into a probability distribution, exp(zᵢ) / Σⱼ exp(zⱼ). The largest logit is
+/// subtracted first so large logits don't overflow.
+pub fn softmax(logits: ArrayView1<f64>) -> Array1<f64> {
+    let max = logits.fold(f64::NEG_INFINITY, |max, &z| max.max(z));
+    let exp = logits.mapv(|z| (z - max).exp());
+    let total = exp.sum();
+    exp / total
+}
+
+/// A fully connected layer computing g(W x + b). `weights` has one row per output unit and one
+/// column per input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+    pub weights: Array2<f64>,
+    pub biases: Array1<f64>,
+    pub activation: Activation,
+}
+
+impl Layer {
+    /// A layer with random weights and zero biases. ReLU layers use He initialization and
+    /// the rest use Glorot (Xavier) initialization, which keep the variance of activations
+    /// roughly constant from layer to layer.
+    pub fn new<R: Rng>(inputs: usize, outputs: usize, activation: Activation, rng: &mut R) -> Self {
+        let weights = match activation {
+            Activation::Relu => {
+                let normal = Normal::new(0.0, (2.0 / inputs as f64).sqrt()).unwrap();
+                Array2::from_shape_simple_fn((outputs, inputs), || normal.sample(rng))
+            }
+            _ => {
+                let limit = (6.0 / (inputs + outputs) as f64).sqrt();
+                let uniform = Uniform::new_inclusive(-limit, limit);
+                Array2::from_shape_simple_fn((outputs, inputs), || uniform.sample(rng))
+            }
+        };
+        Self {
+            weights,
+            biases: Array1::zeros(outputs),
+            activation,
+        }
+    }
+
+    pub fn inputs(&self) -> usize {
+        self.weights.ncols()
+    }
+
+    pub fn outputs(&self) -> usize {
+        self.weights.nrows()
+    }
+
+    /// Returns the weighted inputs z = W x + b and the activations a = g(z).
+    pub fn forward(&self, input: ArrayView1<f64>) -> (Array1<f64>, Array1<f64>) {
+        let z = self.weights.dot(&input) + &self.biases;
+        let a = z.mapv(|z| self.activation.apply(z));
+        (z, a)
+    }
+}
+
+/// Everything computed on a forward pass that backpropagation needs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForwardTrace {
+    /// The input to each layer followed by the network's output, so one longer than the
+    /// number of layers.
+    pub activations: Vec<Array1<f64>>,
+
+    /// The weighted inputs z of each layer.
+    pub weighted_inputs: Vec<Array1<f64>>,
+}
+
+impl ForwardTrace {
+    pub fn output(&self) -> &Array1<f64> {
+        self.activations.last().unwrap()
+    }
+}
+
+/// The gradient of a loss with respect to every weight and bias, laid out like the network.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradients {
+    pub weights: Vec<Array2<f64>>,
+    pub biases: Vec<Array1<f64>>,
+}
+
+impl Gradients {
+    pub fn zeros_like(network: &Network) -> Self {
+        Self {
+            weights: network
+                .layers
+                .iter()
+                .map(|layer| Array2::zeros(layer.weights.raw_dim()))
+                .collect(),
+            biases: network
+                .layers
+                .iter()
+                .map(|layer| Array1::zeros(layer.biases.raw_dim()))
+                .collect(),
+        }
+    }
+
+    pub fn add(&mut self, other: &Gradients) {
+        for (mine, theirs) in self.weights.iter_mut().zip(&other.weights) {
+            *mine += theirs;
+        }
+        for (mine, theirs) in self.biases.iter_mut().zip(&other.biases) {
+            *mine += theirs;
+        }
+    }
+
+    pub fn scale(&mut self, factor: f64) {
+        self.weights.iter_mut().for_each(|w| *w *= factor);
+        self.biases.iter_mut().for_each(|b| *b *= factor);
+    }
+}
+
+/// A feedforward network: a stack of fully connected layers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Network {
+    pub layers: Vec<Layer>,
+}
+
+impl Network {
+    /// A network with the given layer sizes, from the input size to the output size. Hidden
+    /// layers use `hidden` and the last layer uses `output`.
+    pub fn new(sizes: &[usize], hidden: Activation, output: Activation, seed: u64) -> Self {
+        assert!(
+            sizes.len() >= 2,
+            "a network needs an input and an output size"
+        );
+        let mut rng = Pcg64::seed_from_u64(seed);
+        let layers = sizes
+            .windows(2)
+            .enumerate()
+            .map(|(i, pair)| {
+                let activation = if i + 2 == sizes.len() { output } else { hidden };
+                Layer::new(pair[0], pair[1], activation, &mut rng)
+            })
+            .collect();
+        Self { layers }
+    }
+
+    pub fn from_layers(layers: Vec<Layer>) -> Self {
+        Self { layers }
+    }
+
+    pub fn inputs(&self) -> usize {
+        self.layers.first().map_or(0, Layer::inputs)
+    }
+
+    pub fn outputs(&self) -> usize {
+        self.layers.last().map_or(0, Layer::outputs)
+    }
+
+    pub fn forward(&self, input: ArrayView1<f64>) -> Array1<f64> {
+        self.layers
+            .iter()
+            .fold(input.to_owned(), |a, layer| layer.forward(a.view()).1)
+    }
+
+    pub fn forward_trace(&self, input: ArrayView1<f64>) -> ForwardTrace {
+        let mut activations = vec![input.to_owned()];
+        let mut weighted_inputs = Vec::with_capacity(self.layers.len());
+        for layer in &self.layers {
+            let (z, a) = layer.forward(activations.last().unwrap().view());
+            weighted_inputs.push(z);
+            activations.push(a);
+        }
+        ForwardTrace {
+            activations,
+            weighted_inputs,
+        }
+    }
+
+    /// Back-propagation, section 21.4.1: given ∂L/∂a for the output layer, push the error
+    /// back through the layers and collect ∂L/∂W and ∂L/∂b. Each layer's error term is
+    /// Δ = ∂L/∂a ⊙ g'(z), its weight gradient is Δ xᵀ, and the error passed to the layer below
+    /// is Wᵀ Δ.
+    pub fn backward(&self, trace: &ForwardTrace, output_gradient: Array1<f64>) -> Gradients {
+        let mut weights = Vec::with_capacity(self.layers.len());
+        let mut biases = Vec::with_capacity(self.layers.len());
+        let mut gradient = output_gradient;
+        for (i, layer) in self.layers.iter().enumerate().rev() {
+            let z = &trace.weighted_inputs[i];
+            let a = &trace.activations[i + 1];
+            let mut delta = gradient;
+            for ((d, &z), &a) in delta.iter_mut().zip(z).zip(a) {
+                *d *= layer.activation.derivative(z, a);
+            }
+            let input = trace.activations[i].view();
+            weights.push(
+                delta
+                    .view()
+                    .insert_axis(Axis(1))
+                    .dot(&input.insert_axis(Axis(0))),
+            );
+            gradient = layer.weights.t().dot(&delta);
+            biases.push(delta);
+        }
+        weights.reverse();
+        biases.reverse();
+        Gradients { weights, biases }
+    }
+
+    /// Gradients of the squared error ½ Σ (a − y)² for one example, and the loss itself.
+    pub fn backpropagate(
+        &self,
+        input: ArrayView1<f64>,
+        target: ArrayView1<f64>,
+    ) -> (f64, Gradients) {
+        let trace = self.forward_trace(input);
+        let error = trace.output() - &target;
+        let loss = 0.5 * error.mapv(|e| e * e).sum();
+        (loss, self.backward(&trace, error))
+    }
+
+    /// w ← w − α ∂L/∂w for every weight and bias.
+    pub fn apply_gradients(&mut self, gradients: &Gradients, learning_rate: f64) {
+        for (layer, (w, b)) in self
+            .layers
+            .iter_mut()
+            .zip(gradients.weights.iter().zip(&gradients.biases))
+        {
+            layer.weights.scaled_add(-learning_rate, w);
+            layer.biases.scaled_add(-learning_rate, b);
+        }
+    }
+
+    /// One step of stochastic gradient descent on a single example. Returns the loss before
+    /// the step.
+    pub fn train_step(
+        &mut self,
+        input: ArrayView1<f64>,
+        target: ArrayView1<f64>,
+        learning_rate: f64,
+    ) -> f64 {
+        let (loss, gradients) = self.backpropagate(input, target);
+        self.apply_gradients(&gradients, learning_rate);
+        loss
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::{array, Array2};
+
+    fn xor() -> (Array2<f64>, Array2<f64>) {
+        (
+            array![[0.0, 0.0], [0.0, 1.0], [1.0, 0.0], [1.0, 1.0]],
+            array![[0.0], [1.0], [1.0], [0.0]],
+        )
+    }
+
+    #[test]
+    fn test_activation_derivatives_match_finite_differences() {
+        let h = 1e-6;
+        for activation in [
+            Activation::Identity,
+            Activation::Sigmoid,
+            Activation::Relu,
+            Activation::Tanh,
+        ] {
+            for z in [-1.5, -0.3, 0.4, 2.0] {
+                let numeric = (activation.apply(z + h) - activation.apply(z - h)) / (2.0 * h);
+                let analytic = activation.derivative(z, activation.apply(z));
+                assert!((numeric - analytic).abs() < 1e-6, "{activation:?} at {z}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_softmax() {
+        let p = softmax(array![1.0, 2.0, 3.0].view());
+        assert!((p.sum() - 1.0).abs() < 1e-12);
+        assert!(p[0] < p[1] && p[1] < p[2]);
+        assert_eq!(softmax(array![1000.0, 1000.0].view()), array![0.5, 0.5]);
+    }
+
+    #[test]
+    fn test_shapes() {
+        let network = Network::new(&[3, 5, 2], Activation::Relu, Activation::Identity, 1);
+        assert_eq!(network.inputs(), 3);
+        assert_eq!(network.outputs(), 2);
+        assert_eq!(network.forward(array![1.0, 2.0, 3.0].view()).len(), 2);
+        assert_eq!(
+            network,
+            Network::new(&[3, 5, 2], Activation::Relu, Activation::Identity, 1)
+        );
+    }
+
+    #[test]
+    fn test_backpropagation_matches_finite_differences() {
+        let network = Network::new(&[2, 3, 2], Activation::Tanh, Activation::Sigmoid, 7);
+        let input = array![0.3, -0.8];
+        let target = array![1.0, 0.0];
+        let (_, gradients) = network.backpropagate(input.view(), target.view());
+        let h = 1e-6;
+        for (l, layer) in network.layers.iter().enumerate() {
+            for ((row, col), _) in layer.weights.indexed_iter() {
+                let loss_with = |delta: f64| {
+                    let mut nudged = network.clone();
+                    nudged.layers[l].weights[[row, col]] += delta;
+                    nudged.backpropagate(input.view(), target.view()).0
+                };
+                let numeric = (loss_with(h) - loss_with(-h)) / (2.0 * h);
+                assert!((numeric - gradients.weights[l][[row, col]]).abs() < 1e-6);
+            }
+            for i in 0..layer.biases.len() {
+                let loss_with = |delta: f64| {
+                    let mut nudged = network.clone();
+                    nudged.layers[l].biases[i] += delta;
+                    nudged.backpropagate(input.view(), target.view()).0
+                };
+                let numeric = (loss_with(h) - loss_with(-h)) / (2.0 * h);
+                assert!((numeric - gradients.biases[l][i]).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_learns_xor() {
+        let (inputs, targets) = xor();
+        let mut network = Network::new(&[2, 4, 1], Activation::Tanh, Activation::Sigmoid, 3);
+        for _ in 0..5_000 {
+            for (x, y) in inputs.outer_iter().zip(targets.outer_iter()) {
+                network.train_step(x, y, 0.5);
+            }
+        }
+        for (x, y) in inputs.outer_iter().zip(targets.outer_iter()) {
+            assert!((network.forward(x)[0] - y[0]).abs() < 0.1, "{x} -> {y}");
+        }
+    }
+}
@@ -0,0 +1,328 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Naive Bayes classification with categorical and Gaussian likelihoods.
+//
+// See:
+// -  Chapter 20: Learning Probabilistic Models, section 20.2.2
+// -  Chapter 12: Quantifying Uncertainty, section 12.6
+
+use std::f64::consts::PI;
+
+use ndarray::{Array1, Array2, ArrayView1};
+
+use crate::decision_tree::{AttributeKind, TreeAttribute};
+use crate::learner::SupervisedLearner;
+
+/// The model of P(xᵢ | class) for one attribute.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Likelihood {
+    /// A discrete attribute whose values are encoded as 0, 1, ..., values − 1.
+    Categorical { values: usize },
+    /// A continuous attribute, normally distributed within each class.
+    Gaussian,
+}
+
+impl From<&TreeAttribute> for Likelihood {
+    fn from(attribute: &TreeAttribute) -> Self {
+        match &attribute.kind {
+            AttributeKind::Discrete(values) => Likelihood::Categorical {
+                values: values.len(),
+            },
+            AttributeKind::Continuous => Likelihood::Gaussian,
+        }
+    }
+}
+
+/// Fitted parameters of one attribute's likelihood in one class.
+#[derive(Debug, Clone, PartialEq)]
+enum Parameters {
+    /// log P(xᵢ = v | class) for each value v, and for a value never seen in training.
+    Categorical {
+        log_probabilities: Vec<f64>,
+        unseen: f64,
+    },
+    Gaussian {
+        mean: f64,
+        variance: f64,
+    },
+}
+
+impl Parameters {
+    fn log_likelihood(&self, value: f64) -> f64 {
+        match self {
+            Parameters::Categorical {
+                log_probabilities,
+                unseen,
+            } => {
+                if value < 0.0 || value.fract() != 0.0 {
+                    return *unseen;
+                }
+                log_probabilities
+                    .get(value as usize)
+                    .copied()
+                    .unwrap_or(*unseen)
+            }
+            Parameters::Gaussian { mean, variance } => {
+                -0.5 * (2.0 * PI * variance).ln() - (value - mean).powi(2) / (2.0 * variance)
+            }
+        }
+    }
+}
+
+/// The naive Bayes model assumes the attributes are conditionally independent given the
+/// class, so P(class | x) ∝ P(class) Πᵢ P(xᵢ | class). Probabilities are estimated from counts
+/// with Laplace smoothing, adding a pseudocount to every value so that a value never seen with
+/// a class doesn't rule the class out.
+#[derive(Debug, Clone)]
+pub struct NaiveBayes {
+    likelihoods: Vec<Likelihood>,
+    pseudocount: f64,
+    classes: Vec<f64>,
+    log_priors: Vec<f64>,
+    parameters: Vec<Vec<Parameters>>,
+}
+
+impl NaiveBayes {
+    pub fn new(likelihoods: Vec<Likelihood>) -> Self {
+        NaiveBayes {
+            likelihoods,
+            pseudocount: 1.0,
+            classes: vec![],
+            log_priors: vec![],
+            parameters: vec![],
+        }
+    }
+
+    pub fn from_attributes(attributes: &[TreeAttribute]) -> Self {
+        NaiveBayes::new(attributes.iter().map(Likelihood::from).collect())
+    }
+
+    /// The Laplace smoothing pseudocount, 1 by default. 0 gives maximum-likelihood estimates.
+    pub fn with_pseudocount(mut self, pseudocount: f64) -> Self {
+        self.pseudocount = pseudocount;
+        self
+    }
+
+    /// The distinct target values seen by fit, in increasing order.
+    pub fn classes(&self) -> &[f64] {
+        &self.classes
+    }
+
+    /// P(class | input) for each class, in the order of `classes`.
+    pub fn class_probabilities(&self, input: ArrayView1<f64>) -> Vec<f64> {
+        let log_posteriors: Vec<f64> = self
+            .log_priors
+            .iter()
+            .zip(&self.parameters)
+            .map(|(log_prior, parameters)| {
+                log_prior
+                    + parameters
+                        .iter()
+                        .zip(input)
+                        .map(|(parameters, &value)| parameters.log_likelihood(value))
+                        .sum::<f64>()
+            })
+            .collect();
+        let max = log_posteriors
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let unnormalized: Vec<f64> = log_posteriors.iter().map(|l| (l - max).exp()).collect();
+        let total: f64 = unnormalized.iter().sum();
+        unnormalized.iter().map(|p| p / total).collect()
+    }
+
+    fn fit_attribute(&self, likelihood: Likelihood, values: &[f64]) -> Parameters {
+        let n = values.len() as f64;
+        match likelihood {
+            Likelihood::Categorical { values: count } => {
+                let mut counts = vec![0.0; count];
+                for &value in values {
+                    if let Some(c) = counts.get_mut(value as usize) {
+                        *c += 1.0;
+                    }
+                }
+                let denominator = n + self.pseudocount * count as f64;
+                Parameters::Categorical {
+                    log_probabilities: counts
+                        .iter()
+                        .map(|c| ((c + self.pseudocount) / denominator).ln())
+                        .collect(),
+                    unseen: (self.pseudocount / denominator).ln(),
+                }
+            }
+            Likelihood::Gaussian => {
+                let mean = values.iter().sum::<f64>() / n;
+                let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+                // A floor keeps a constant attribute from giving an infinite density.
+                Parameters::Gaussian {
+                    mean,
+                    variance: variance.max(1e-9),
+                }
+            }
+        }
+    }
+}
+
+impl SupervisedLearner for NaiveBayes {
+    fn fit(&mut self, inputs: &Array2<f64>, targets: &Array1<f64>) {
+        let mut classes: Vec<f64> = targets.to_vec();
+        classes.sort_by(f64::total_cmp);
+        classes.dedup();
+        let total = targets.len() as f64;
+        self.log_priors = vec![];
+        self.parameters = vec![];
+        for &class in &classes {
+            let rows: Vec<usize> = (0..targets.len())
+                .filter(|&row| targets[row] == class)
+                .collect();
+            self.log_priors.push((rows.len() as f64 / total).ln());
+            self.parameters.push(
+                self.likelihoods
+                    .iter()
+                    .enumerate()
+                    .map(|(attribute, &likelihood)| {
+                        let values: Vec<f64> =
+                            rows.iter().map(|&row| inputs[[row, attribute]]).collect();
+                        self.fit_attribute(likelihood, &values)
+                    })
+                    .collect(),
+            );
+        }
+        self.classes = classes;
+    }
+
+    /// The most probable class, ties going to the smallest. Panics if called before fit.
+    fn predict(&self, input: ArrayView1<f64>) -> f64 {
+        let probabilities = self.class_probabilities(input);
+        let mut best = 0;
+        for (index, &p) in probabilities.iter().enumerate() {
+            if p > probabilities[best] {
+                best = index;
+            }
+        }
+        self.classes[best]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decision_tree::DecisionTreeLearner;
+    use crate::examples::{earthquakes, restaurant, RESTAURANT_ATTRIBUTES};
+    use crate::knn::KNearestNeighbors;
+    use crate::learner::{accuracy, cross_validation};
+    use crate::linear::LogisticRegression;
+    use ndarray::array;
+
+    fn restaurant_attributes() -> Vec<TreeAttribute> {
+        RESTAURANT_ATTRIBUTES
+            .iter()
+            .map(TreeAttribute::from)
+            .collect()
+    }
+
+    #[test]
+    fn test_categorical_estimates() {
+        let inputs = array![[0.0], [0.0], [1.0], [1.0]];
+        let targets = array![0.0, 0.0, 0.0, 1.0];
+        let mut bayes =
+            NaiveBayes::new(vec![Likelihood::Categorical { values: 2 }]).with_pseudocount(0.0);
+        bayes.fit(&inputs, &targets);
+        // P(c=0 | x=1) ∝ 3/4 · 1/3 and P(c=1 | x=1) ∝ 1/4 · 1, so they are equal.
+        let probabilities = bayes.class_probabilities(array![1.0].view());
+        assert!((probabilities[0] - 0.5).abs() < 1e-12);
+        assert_eq!(bayes.predict(array![0.0].view()), 0.0);
+    }
+
+    #[test]
+    fn test_laplace_smoothing_handles_unseen_values() {
+        let inputs = array![[0.0], [1.0]];
+        let targets = array![0.0, 1.0];
+        let mut bayes = NaiveBayes::new(vec![Likelihood::Categorical { values: 3 }]);
+        bayes.fit(&inputs, &targets);
+        let probabilities = bayes.class_probabilities(array![2.0].view());
+        assert!((probabilities[0] - 0.5).abs() < 1e-12);
+        assert!(bayes.class_probabilities(array![0.0].view())[0] > 0.5);
+    }
+
+    #[test]
+    fn test_restaurant() {
+        let (inputs, targets) = restaurant();
+        let mut bayes = NaiveBayes::from_attributes(&restaurant_attributes());
+        bayes.fit(&inputs, &targets);
+        assert!(accuracy(&bayes, &inputs, &targets) >= 0.75);
+        for input in inputs.outer_iter() {
+            let total: f64 = bayes.class_probabilities(input).iter().sum();
+            assert!((total - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_gaussian_on_earthquakes() {
+        let (inputs, targets) = earthquakes();
+        let mut bayes = NaiveBayes::new(vec![Likelihood::Gaussian; 2]);
+        bayes.fit(&inputs, &targets);
+        assert!(accuracy(&bayes, &inputs, &targets) >= 0.75);
+        assert_eq!(bayes.predict(array![6.4, 4.0].view()), 1.0);
+        assert_eq!(bayes.predict(array![5.0, 5.5].view()), 0.0);
+    }
+
+    #[test]
+    fn test_compare_learners_on_restaurant() {
+        let (inputs, targets) = restaurant();
+        let mean = |scores: Vec<f64>| scores.iter().sum::<f64>() / scores.len() as f64;
+        let folds = 6;
+        let scores = [
+            mean(cross_validation(
+                || NaiveBayes::from_attributes(&restaurant_attributes()),
+                &inputs,
+                &targets,
+                folds,
+                1,
+                accuracy,
+            )),
+            mean(cross_validation(
+                || DecisionTreeLearner::new(restaurant_attributes()),
+                &inputs,
+                &targets,
+                folds,
+                1,
+                accuracy,
+            )),
+            mean(cross_validation(
+                || KNearestNeighbors::new(3),
+                &inputs,
+                &targets,
+                folds,
+                1,
+                accuracy,
+            )),
+            mean(cross_validation(
+                || LogisticRegression::new(1.0, 500),
+                &inputs,
+                &targets,
+                folds,
+                1,
+                accuracy,
+            )),
+        ];
+        // Twelve examples are too few to rank the learners, but they should all beat chance
+        // somewhere.
+        assert!(scores.iter().any(|&score| score > 0.5), "{:?}", scores);
+    }
+}
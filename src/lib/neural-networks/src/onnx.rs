@@ -0,0 +1,299 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Inference with ONNX models, so that networks trained elsewhere, for example exported from
+// PyTorch with torch.onnx.export, can be used without libtorch. Runs on tract, which is pure
+// Rust and also builds for WASM. Our own networks can be exported to ONNX too.
+
+use std::io::Read;
+use std::path::Path;
+
+use ndarray::{Array1, ArrayView1};
+use prost::Message;
+use thiserror::Error;
+use tract_onnx::pb::{
+    attribute_proto::AttributeType, tensor_proto::DataType, tensor_shape_proto, type_proto,
+    AttributeProto, GraphProto, ModelProto, NodeProto, OperatorSetIdProto, TensorProto,
+    TensorShapeProto, TypeProto, ValueInfoProto,
+};
+use tract_onnx::prelude::*;
+
+use crate::mlp::{Activation, Network};
+
+#[derive(Debug, Error)]
+pub enum OnnxError {
+    /// tract's errors don't implement std::error::Error, so only their message is kept.
+    #[error("onnx error: {0}")]
+    Tract(String),
+
+    #[error("i/o error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("model takes {expected} inputs but was given {actual}")]
+    WrongInputSize { expected: usize, actual: usize },
+
+    #[error("unexpected model outputs: {0}")]
+    UnexpectedOutputs(String),
+}
+
+impl From<TractError> for OnnxError {
+    fn from(error: TractError) -> Self {
+        OnnxError::Tract(format!("{:#}", error))
+    }
+}
+
+/// An ONNX model with one input, a batch of one vector of `input_size` f32 values, of shape
+/// [1, input_size]. It may have several outputs, such as the policy logits and value of a
+/// policy-value network; each is flattened to a vector.
+#[derive(Debug, Clone)]
+pub struct OnnxModel {
+    plan: TypedRunnableModel<TypedModel>,
+    input_size: usize,
+}
+
+impl OnnxModel {
+    pub fn load_file(path: impl AsRef<Path>, input_size: usize) -> Result<Self, OnnxError> {
+        Self::optimize(onnx().model_for_path(path)?, input_size)
+    }
+
+    pub fn load(reader: &mut impl Read, input_size: usize) -> Result<Self, OnnxError> {
+        Self::optimize(onnx().model_for_read(reader)?, input_size)
+    }
+
+    /// From an already decoded protobuf model.
+    pub fn from_proto(proto: &ModelProto, input_size: usize) -> Result<Self, OnnxError> {
+        Self::optimize(onnx().model_for_proto_model(proto)?, input_size)
+    }
+
+    fn optimize(model: InferenceModel, input_size: usize) -> Result<Self, OnnxError> {
+        let plan = model
+            .with_input_fact(0, f32::fact([1, input_size]).into())?
+            .into_optimized()?
+            .into_runnable()?;
+        Ok(OnnxModel { plan, input_size })
+    }
+
+    pub fn input_size(&self) -> usize {
+        self.input_size
+    }
+
+    /// Every output of the model for one input.
+    pub fn run(&self, input: ArrayView1<f64>) -> Result<Vec<Array1<f64>>, OnnxError> {
+        if input.len() != self.input_size {
+            return Err(OnnxError::WrongInputSize {
+                expected: self.input_size,
+                actual: input.len(),
+            });
+        }
+        let values: Vec<f32> = input.iter().map(|&x| x as f32).collect();
+        let tensor = Tensor::from_shape(&[1, self.input_size], &values)?;
+        let outputs = self.plan.run(tvec!(tensor.into()))?;
+        outputs
+            .iter()
+            .map(|output| {
+                Ok(output
+                    .to_array_view::<f32>()?
+                    .iter()
+                    .map(|&x| x as f64)
+                    .collect())
+            })
+            .collect()
+    }
+
+    /// The first output of the model for one input.
+    pub fn forward(&self, input: ArrayView1<f64>) -> Result<Array1<f64>, OnnxError> {
+        Ok(self.run(input)?.swap_remove(0))
+    }
+}
+fn tensor(name: &str, dims: &[usize], values: impl Iterator<Item = f64>) -> TensorProto {
+    TensorProto {
+        name: name.to_string(),
+        dims: dims.iter().map(|&d| d as i64).collect(),
+        data_type: DataType::Float as i32,
+        float_data: values.map(|x| x as f32).collect(),
+        ..Default::default()
+    }
+}
+
+fn node(op_type: &str, inputs: &[&str], output: &str) -> NodeProto {
+    NodeProto {
+        op_type: op_type.to_string(),
+        name: output.to_string(),
+        input: inputs.iter().map(|s| s.to_string()).collect(),
+        output: vec![output.to_string()],
+        ..Default::default()
+    }
+}
+
+fn value_info(name: &str) -> ValueInfoProto {
+    ValueInfoProto {
+        name: name.to_string(),
+        ..Default::default()
+    }
+}
+
+/// A float input of shape [batch, size], with a symbolic batch dimension.
+fn input_info(name: &str, size: usize) -> ValueInfoProto {
+    let dimension = |value| tensor_shape_proto::Dimension {
+        value: Some(value),
+        ..Default::default()
+    };
+    ValueInfoProto {
+        name: name.to_string(),
+        r#type: Some(TypeProto {
+            value: Some(type_proto::Value::TensorType(type_proto::Tensor {
+                elem_type: DataType::Float as i32,
+                shape: Some(TensorShapeProto {
+                    dim: vec![
+                        dimension(tensor_shape_proto::dimension::Value::DimParam(
+                            "batch".to_string(),
+                        )),
+                        dimension(tensor_shape_proto::dimension::Value::DimValue(size as i64)),
+                    ],
+                }),
+            })),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+/// Export networks that share an input to one ONNX model with an output per network, in
+/// order. Each network is a sequence of Gemm nodes with transB = 1, since our weights are
+/// outputs × inputs, and activations: what torch.onnx.export produces for a stack of Linear
+/// layers. The input "x" has shape [batch, inputs].
+pub fn networks_to_onnx(networks: &[&Network]) -> ModelProto {
+    let mut nodes = vec![];
+    let mut initializers = vec![];
+    let mut outputs = vec![];
+    for (n, network) in networks.iter().enumerate() {
+        let mut current = "x".to_string();
+        for (l, layer) in network.layers.iter().enumerate() {
+            let weights = format!("w{}_{}", n, l);
+            let biases = format!("b{}_{}", n, l);
+            initializers.push(tensor(
+                &weights,
+                &[layer.outputs(), layer.inputs()],
+                layer.weights.iter().copied(),
+            ));
+            initializers.push(tensor(
+                &biases,
+                &[layer.outputs()],
+                layer.biases.iter().copied(),
+            ));
+            let gemm = format!("gemm{}_{}", n, l);
+            let mut gemm_node = node("Gemm", &[&current, &weights, &biases], &gemm);
+            gemm_node.attribute.push(AttributeProto {
+                name: "transB".to_string(),
+                r#type: AttributeType::Int as i32,
+                i: 1,
+                ..Default::default()
+            });
+            nodes.push(gemm_node);
+            current = gemm;
+            let op_type = match layer.activation {
+                Activation::Identity => continue,
+                Activation::Sigmoid => "Sigmoid",
+                Activation::Relu => "Relu",
+                Activation::Tanh => "Tanh",
+            };
+            let activated = format!("act{}_{}", n, l);
+            nodes.push(node(op_type, &[&current], &activated));
+            current = activated;
+        }
+        outputs.push(value_info(&current));
+    }
+    ModelProto {
+        ir_version: 7,
+        opset_import: vec![OperatorSetIdProto {
+            domain: String::new(),
+            version: 13,
+        }],
+        graph: Some(GraphProto {
+            name: "networks".to_string(),
+            node: nodes,
+            initializer: initializers,
+            input: vec![input_info("x", networks[0].inputs())],
+            output: outputs,
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+/// Write [`networks_to_onnx`] of the networks to a .onnx file.
+pub fn save_networks(networks: &[&Network], path: impl AsRef<Path>) -> Result<(), OnnxError> {
+    std::fs::write(path, networks_to_onnx(networks).encode_to_vec())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    fn assert_close(a: &Array1<f64>, b: &Array1<f64>) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b) {
+            assert!((x - y).abs() < 1e-5, "{} vs {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_matches_network() {
+        let network = Network::new(&[3, 5, 2], Activation::Relu, Activation::Sigmoid, 4);
+        let model = OnnxModel::from_proto(&networks_to_onnx(&[&network]), 3).unwrap();
+        for input in [array![0.5, -1.0, 2.0], array![0.0, 0.0, 0.0]] {
+            assert_close(
+                &model.forward(input.view()).unwrap(),
+                &network.forward(input.view()),
+            );
+        }
+    }
+
+    #[test]
+    fn test_load_file_with_two_outputs() {
+        let policy = Network::new(&[4, 8, 3], Activation::Tanh, Activation::Identity, 1);
+        let value = Network::new(&[4, 6, 1], Activation::Relu, Activation::Tanh, 2);
+        let path = std::env::temp_dir().join(format!("aima-nn-{}.onnx", std::process::id()));
+        save_networks(&[&policy, &value], &path).unwrap();
+        let model = OnnxModel::load_file(&path, 4).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let input = array![0.1, 0.2, -0.3, 0.4];
+        let outputs = model.run(input.view()).unwrap();
+        assert_eq!(outputs.len(), 2);
+        assert_close(&outputs[0], &policy.forward(input.view()));
+        assert_close(&outputs[1], &value.forward(input.view()));
+    }
+
+    #[test]
+    fn test_errors() {
+        let network = Network::new(&[2, 1], Activation::Identity, Activation::Identity, 0);
+        let model = OnnxModel::from_proto(&networks_to_onnx(&[&network]), 2).unwrap();
+        assert!(matches!(
+            model.forward(array![1.0].view()),
+            Err(OnnxError::WrongInputSize {
+                expected: 2,
+                actual: 1
+            })
+        ));
+        assert!(matches!(
+            OnnxModel::load(&mut &b"not a model"[..], 2),
+            Err(OnnxError::Tract(_))
+        ));
+    }
+}
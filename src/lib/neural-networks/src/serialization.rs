@@ -0,0 +1,227 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Saving and loading trained networks.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use ndarray::{Array1, Array2};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::mlp::{Activation, Layer, Network};
+
+/// Written at the top of every model file so other JSON isn't mistaken for a network.
+pub const FORMAT_NAME: &str = "aima-rs-mlp";
+
+/// Bump this whenever the layout of [`ModelFile`] changes, and keep reading older versions.
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum SerializationError {
+    #[error("i/o error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("not a model file, format is {0:?}")]
+    WrongFormat(String),
+
+    #[error("model file version {found} is newer than the supported version {supported}")]
+    UnsupportedVersion { found: u32, supported: u32 },
+
+    #[error("layer {layer} has the wrong number of weights or biases for its shape")]
+    WrongShape { layer: usize },
+
+    #[error("layer {layer} takes {inputs} inputs but the layer before it has {outputs} outputs")]
+    LayerMismatch {
+        layer: usize,
+        inputs: usize,
+        outputs: usize,
+    },
+}
+
+/// The on-disk form of a [`Network`]: its architecture plus row-major weights.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelFile {
+    pub format: String,
+    pub version: u32,
+    pub layers: Vec<LayerFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayerFile {
+    pub inputs: usize,
+    pub outputs: usize,
+    pub activation: Activation,
+
+    /// `outputs` rows of `inputs` weights each.
+    pub weights: Vec<f64>,
+    pub biases: Vec<f64>,
+}
+
+impl From<&Network> for ModelFile {
+    fn from(network: &Network) -> Self {
+        Self {
+            format: FORMAT_NAME.to_string(),
+            version: FORMAT_VERSION,
+            layers: network
+                .layers
+                .iter()
+                .map(|layer| LayerFile {
+                    inputs: layer.inputs(),
+                    outputs: layer.outputs(),
+                    activation: layer.activation,
+                    weights: layer.weights.iter().copied().collect(),
+                    biases: layer.biases.to_vec(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<ModelFile> for Network {
+    type Error = SerializationError;
+
+    fn try_from(file: ModelFile) -> Result<Self, Self::Error> {
+        if file.format != FORMAT_NAME {
+            return Err(SerializationError::WrongFormat(file.format));
+        }
+        if file.version > FORMAT_VERSION {
+            return Err(SerializationError::UnsupportedVersion {
+                found: file.version,
+                supported: FORMAT_VERSION,
+            });
+        }
+        let mut layers: Vec<Layer> = Vec::with_capacity(file.layers.len());
+        for (i, layer) in file.layers.into_iter().enumerate() {
+            if let Some(previous) = layers.last() {
+                if previous.outputs() != layer.inputs {
+                    return Err(SerializationError::LayerMismatch {
+                        layer: i,
+                        inputs: layer.inputs,
+                        outputs: previous.outputs(),
+                    });
+                }
+            }
+            if layer.biases.len() != layer.outputs {
+                return Err(SerializationError::WrongShape { layer: i });
+            }
+            let weights = Array2::from_shape_vec((layer.outputs, layer.inputs), layer.weights)
+                .map_err(|_| SerializationError::WrongShape { layer: i })?;
+            layers.push(Layer {
+                weights,
+                biases: Array1::from(layer.biases),
+                activation: layer.activation,
+            });
+        }
+        Ok(Network::from_layers(layers))
+    }
+}
+
+impl Network {
+    pub fn save<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        serde_json::to_writer(writer, &ModelFile::from(self))?;
+        Ok(())
+    }
+
+    pub fn load<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        let file: ModelFile = serde_json::from_reader(reader)?;
+        Network::try_from(file)
+    }
+
+    pub fn save_file(&self, path: impl AsRef<Path>) -> Result<(), SerializationError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.save(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn load_file(path: impl AsRef<Path>) -> Result<Self, SerializationError> {
+        Self::load(BufReader::new(File::open(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    fn network() -> Network {
+        Network::new(&[3, 4, 2], Activation::Relu, Activation::Sigmoid, 5)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let network = network();
+        let mut bytes = vec![];
+        network.save(&mut bytes).unwrap();
+        let loaded = Network::load(bytes.as_slice()).unwrap();
+        assert_eq!(loaded, network);
+        let input = array![0.1, -0.4, 0.9];
+        assert_eq!(loaded.forward(input.view()), network.forward(input.view()));
+    }
+
+    #[test]
+    fn test_round_trip_through_a_file() {
+        let path = std::env::temp_dir().join(format!("aima-nn-{}.json", std::process::id()));
+        network().save_file(&path).unwrap();
+        let loaded = Network::load_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), network());
+    }
+
+    #[test]
+    fn test_rejects_bad_files() {
+        let good = ModelFile::from(&network());
+
+        let mut file = good.clone();
+        file.format = "something-else".to_string();
+        assert!(matches!(
+            Network::try_from(file),
+            Err(SerializationError::WrongFormat(_))
+        ));
+
+        let mut file = good.clone();
+        file.version = FORMAT_VERSION + 1;
+        assert!(matches!(
+            Network::try_from(file),
+            Err(SerializationError::UnsupportedVersion { .. })
+        ));
+
+        let mut file = good.clone();
+        file.layers[0].weights.pop();
+        assert!(matches!(
+            Network::try_from(file),
+            Err(SerializationError::WrongShape { layer: 0 })
+        ));
+
+        let mut file = good;
+        file.layers.swap(0, 1);
+        assert!(matches!(
+            Network::try_from(file),
+            Err(SerializationError::LayerMismatch { layer: 1, .. })
+        ));
+
+        assert!(matches!(
+            Network::load("not json".as_bytes()),
+            Err(SerializationError::Json(_))
+        ));
+    }
+}
@@ -0,0 +1,184 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// A libtorch-backed multilayer perceptron that plugs into the same training loop as the
+// pure-Rust network.
+
+use std::path::Path;
+
+use ndarray::Array2;
+use tch::nn::{self, Module, OptimizerConfig};
+use tch::{Device, Kind, Reduction, TchError, Tensor};
+
+use crate::mlp::Activation;
+use crate::train::{Loss, Model};
+
+/// Which libtorch optimizer a [`TchModel`] uses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TchOptimizer {
+    Sgd { learning_rate: f64, momentum: f64 },
+    Adam { learning_rate: f64 },
+}
+
+pub struct TchModel {
+    pub var_store: nn::VarStore,
+    network: nn::Sequential,
+    optimizer: nn::Optimizer,
+    loss: Loss,
+    device: Device,
+}
+
+impl TchModel {
+    /// A fully connected network with the given layer sizes, like
+    /// [`crate::mlp::Network::new`].
+    pub fn mlp(
+        sizes: &[usize],
+        hidden: Activation,
+        output: Activation,
+        loss: Loss,
+        optimizer: TchOptimizer,
+        device: Device,
+    ) -> Result<Self, TchError> {
+        let var_store = nn::VarStore::new(device);
+        let root = var_store.root();
+        let mut network = nn::seq();
+        for (i, pair) in sizes.windows(2).enumerate() {
+            network = network.add(nn::linear(
+                &root / format!("layer{i}"),
+                pair[0] as i64,
+                pair[1] as i64,
+                Default::default(),
+            ));
+            let activation = if i + 2 == sizes.len() { output } else { hidden };
+            network = match activation {
+                Activation::Identity => network,
+                Activation::Sigmoid => network.add_fn(|xs| xs.sigmoid()),
+                Activation::Relu => network.add_fn(|xs| xs.relu()),
+                Activation::Tanh => network.add_fn(|xs| xs.tanh()),
+            };
+        }
+        let optimizer = match optimizer {
+            TchOptimizer::Sgd {
+                learning_rate,
+                momentum,
+            } => nn::Sgd {
+                momentum,
+                ..Default::default()
+            }
+            .build(&var_store, learning_rate)?,
+            TchOptimizer::Adam { learning_rate } => {
+                nn::Adam::default().build(&var_store, learning_rate)?
+            }
+        };
+        Ok(Self {
+            var_store,
+            network,
+            optimizer,
+            loss,
+            device,
+        })
+    }
+
+    pub fn device(&self) -> Device {
+        self.device
+    }
+
+    /// Save the weights with libtorch's own format. Loading them needs a model built with the
+    /// same sizes and activations, since the file holds only the named tensors.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), TchError> {
+        self.var_store.save(path)
+    }
+
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<(), TchError> {
+        self.var_store.load(path)
+    }
+
+    pub fn forward(&self, inputs: &Array2<f64>) -> Array2<f64> {
+        let output = tch::no_grad(|| self.network.forward(&self.to_tensor(inputs)));
+        let shape = (inputs.nrows(), output.size()[1] as usize);
+        let values: Vec<f64> = Vec::<f64>::try_from(output.to_kind(Kind::Double).flatten(0, -1))
+            .expect("output is a flat tensor of doubles");
+        Array2::from_shape_vec(shape, values).expect("output has one row per input")
+    }
+
+    fn to_tensor(&self, array: &Array2<f64>) -> Tensor {
+        let values: Vec<f32> = array.iter().map(|&x| x as f32).collect();
+        Tensor::from_slice(&values)
+            .view([array.nrows() as i64, array.ncols() as i64])
+            .to_device(self.device)
+    }
+
+    fn loss(&self, inputs: &Array2<f64>, targets: &Array2<f64>) -> Tensor {
+        let output = self.network.forward(&self.to_tensor(inputs));
+        let targets = self.to_tensor(targets);
+        match self.loss {
+            Loss::MeanSquaredError => output.mse_loss(&targets, Reduction::Mean),
+            Loss::CrossEntropy => output
+                .clamp(1e-7, 1.0 - 1e-7)
+                .binary_cross_entropy::<Tensor>(&targets, None, Reduction::Mean),
+            Loss::SoftmaxCrossEntropy => -(&targets * output.log_softmax(-1, Kind::Float))
+                .sum_dim_intlist(-1, false, Kind::Float)
+                .mean(Kind::Float),
+        }
+    }
+}
+
+impl Model for TchModel {
+    fn train_batch(&mut self, inputs: &Array2<f64>, targets: &Array2<f64>) -> f64 {
+        let loss = self.loss(inputs, targets);
+        self.optimizer.backward_step(&loss);
+        loss.double_value(&[])
+    }
+
+    fn evaluate(&self, inputs: &Array2<f64>, targets: &Array2<f64>) -> f64 {
+        tch::no_grad(|| self.loss(inputs, targets).double_value(&[]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::train::{train, TrainingConfig};
+    use ndarray::array;
+
+    #[test]
+    fn test_learns_xor_on_cpu() {
+        tch::manual_seed(0);
+        let inputs = array![[0.0, 0.0], [0.0, 1.0], [1.0, 0.0], [1.0, 1.0]];
+        let targets = array![[0.0], [1.0], [1.0], [0.0]];
+        let mut model = TchModel::mlp(
+            &[2, 8, 1],
+            Activation::Tanh,
+            Activation::Sigmoid,
+            Loss::CrossEntropy,
+            TchOptimizer::Adam {
+                learning_rate: 0.05,
+            },
+            Device::Cpu,
+        )
+        .unwrap();
+        let config = TrainingConfig {
+            epochs: 500,
+            batch_size: 4,
+            ..TrainingConfig::default()
+        };
+        train(&mut model, (&inputs, &targets), None, &config, |_| {});
+        let predictions = model.forward(&inputs);
+        for (prediction, target) in predictions.iter().zip(&targets) {
+            assert!((prediction - target).abs() < 0.2);
+        }
+    }
+}
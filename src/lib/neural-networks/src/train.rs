@@ -0,0 +1,547 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+// Training: loss functions, optimizers, minibatching and early stopping.
+//
+// See:
+// -  Chapter 21: Deep Learning, section 21.4
+
+use ndarray::{Array, Array1, Array2, ArrayView1, Axis, Dimension, Zip};
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+
+use crate::mlp::{softmax, Gradients, Network};
+
+/// Outputs are clamped this far away from 0 and 1 before taking logarithms.
+const CROSS_ENTROPY_CLAMP: f64 = 1e-7;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Loss {
+    /// Mean over output units of (a − y)².
+    #[default]
+    MeanSquaredError,
+
+    /// Mean over output units of −y ln a − (1 − y) ln(1 − a). Each output must be in (0, 1),
+    /// e.g. from a sigmoid.
+    CrossEntropy,
+
+    /// −Σ y ln softmax(a), for a last layer with the identity activation whose outputs are
+    /// logits, and targets that are a probability distribution over the outputs.
+    SoftmaxCrossEntropy,
+}
+
+impl Loss {
+    pub fn value(self, output: ArrayView1<f64>, target: ArrayView1<f64>) -> f64 {
+        let n = output.len() as f64;
+        match self {
+            Loss::MeanSquaredError => {
+                Zip::from(output)
+                    .and(target)
+                    .fold(0.0, |sum, &a, &y| sum + (a - y) * (a - y))
+                    / n
+            }
+            Loss::CrossEntropy => {
+                Zip::from(output).and(target).fold(0.0, |sum, &a, &y| {
+                    let a = a.clamp(CROSS_ENTROPY_CLAMP, 1.0 - CROSS_ENTROPY_CLAMP);
+                    sum - y * a.ln() - (1.0 - y) * (1.0 - a).ln()
+                }) / n
+            }
+            Loss::SoftmaxCrossEntropy => Zip::from(&softmax(output))
+                .and(target)
+                .fold(0.0, |sum, &p, &y| sum - y * p.max(CROSS_ENTROPY_CLAMP).ln()),
+        }
+    }
+
+    /// ∂L/∂a, the gradient with respect to the network's output.
+    pub fn gradient(self, output: ArrayView1<f64>, target: ArrayView1<f64>) -> Array1<f64> {
+        let n = output.len() as f64;
+        match self {
+            Loss::MeanSquaredError => Zip::from(output)
+                .and(target)
+                .map_collect(|&a, &y| 2.0 * (a - y) / n),
+            Loss::CrossEntropy => Zip::from(output).and(target).map_collect(|&a, &y| {
+                let a = a.clamp(CROSS_ENTROPY_CLAMP, 1.0 - CROSS_ENTROPY_CLAMP);
+                (a - y) / (a * (1.0 - a)) / n
+            }),
+            Loss::SoftmaxCrossEntropy => {
+                let total: f64 = target.sum();
+                Zip::from(&softmax(output))
+                    .and(target)
+                    .map_collect(|&p, &y| p * total - y)
+            }
+        }
+    }
+}
+
+/// Updates a network's parameters from the gradient of the loss.
+pub trait Optimizer {
+    fn step(&mut self, network: &mut Network, gradients: &Gradients);
+}
+
+/// Stochastic gradient descent with optional momentum: v ← μ v + g, w ← w − α v.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sgd {
+    pub learning_rate: f64,
+    pub momentum: f64,
+    velocity: Option<Gradients>,
+}
+
+impl Sgd {
+    pub fn new(learning_rate: f64) -> Self {
+        Self::with_momentum(learning_rate, 0.0)
+    }
+
+    pub fn with_momentum(learning_rate: f64, momentum: f64) -> Self {
+        Self {
+            learning_rate,
+            momentum,
+            velocity: None,
+        }
+    }
+}
+
+impl Optimizer for Sgd {
+    fn step(&mut self, network: &mut Network, gradients: &Gradients) {
+        let velocity = self
+            .velocity
+            .get_or_insert_with(|| Gradients::zeros_like(network));
+        let (learning_rate, momentum) = (self.learning_rate, self.momentum);
+        let update = |param: &mut f64, &g: &f64, v: &mut f64| {
+            *v = momentum * *v + g;
+            *param -= learning_rate * *v;
+        };
+        for (l, layer) in network.layers.iter_mut().enumerate() {
+            zip3(
+                &mut layer.weights,
+                &gradients.weights[l],
+                &mut velocity.weights[l],
+                update,
+            );
+            zip3(
+                &mut layer.biases,
+                &gradients.biases[l],
+                &mut velocity.biases[l],
+                update,
+            );
+        }
+    }
+}
+
+/// Adam (Kingma and Ba, 2015): gradient descent scaled by bias-corrected running averages of
+/// the gradient and its square, section 21.4.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adam {
+    pub learning_rate: f64,
+    pub beta1: f64,
+    pub beta2: f64,
+    pub epsilon: f64,
+    steps: i32,
+    first_moment: Option<Gradients>,
+    second_moment: Option<Gradients>,
+}
+
+impl Adam {
+    pub fn new(learning_rate: f64) -> Self {
+        Self {
+            learning_rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+            steps: 0,
+            first_moment: None,
+            second_moment: None,
+        }
+    }
+}
+
+impl Optimizer for Adam {
+    fn step(&mut self, network: &mut Network, gradients: &Gradients) {
+        self.steps += 1;
+        let first = self
+            .first_moment
+            .get_or_insert_with(|| Gradients::zeros_like(network));
+        let second = self
+            .second_moment
+            .get_or_insert_with(|| Gradients::zeros_like(network));
+        let (beta1, beta2, epsilon) = (self.beta1, self.beta2, self.epsilon);
+        let step_size = self.learning_rate * (1.0 - beta2.powi(self.steps)).sqrt()
+            / (1.0 - beta1.powi(self.steps));
+        for (l, layer) in network.layers.iter_mut().enumerate() {
+            adam_update(
+                &mut layer.weights,
+                &gradients.weights[l],
+                &mut first.weights[l],
+                &mut second.weights[l],
+                (beta1, beta2, epsilon, step_size),
+            );
+            adam_update(
+                &mut layer.biases,
+                &gradients.biases[l],
+                &mut first.biases[l],
+                &mut second.biases[l],
+                (beta1, beta2, epsilon, step_size),
+            );
+        }
+    }
+}
+
+fn zip3<D: Dimension>(
+    param: &mut Array<f64, D>,
+    gradient: &Array<f64, D>,
+    state: &mut Array<f64, D>,
+    f: impl Fn(&mut f64, &f64, &mut f64),
+) {
+    Zip::from(param).and(gradient).and(state).for_each(f);
+}
+
+fn adam_update<D: Dimension>(
+    param: &mut Array<f64, D>,
+    gradient: &Array<f64, D>,
+    first: &mut Array<f64, D>,
+    second: &mut Array<f64, D>,
+    (beta1, beta2, epsilon, step_size): (f64, f64, f64, f64),
+) {
+    Zip::from(param)
+        .and(gradient)
+        .and(first)
+        .and(second)
+        .for_each(|w, &g, m, v| {
+            *m = beta1 * *m + (1.0 - beta1) * g;
+            *v = beta2 * *v + (1.0 - beta2) * g * g;
+            *w -= step_size * *m / (v.sqrt() + epsilon);
+        });
+}
+
+/// Something that can be trained by [`train`]. Examples are the rows of `inputs` and
+/// `targets`.
+pub trait Model {
+    /// Take one optimizer step on a minibatch. Returns the mean loss over the batch before the
+    /// step.
+    fn train_batch(&mut self, inputs: &Array2<f64>, targets: &Array2<f64>) -> f64;
+
+    /// Mean loss over the examples, leaving the model unchanged.
+    fn evaluate(&self, inputs: &Array2<f64>, targets: &Array2<f64>) -> f64;
+}
+
+/// A pure-Rust [`Network`] together with its loss and optimizer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MlpModel<Optimizer_> {
+    pub network: Network,
+    pub loss: Loss,
+    pub optimizer: Optimizer_,
+}
+
+impl<Optimizer_: Optimizer> MlpModel<Optimizer_> {
+    pub fn new(network: Network, loss: Loss, optimizer: Optimizer_) -> Self {
+        Self {
+            network,
+            loss,
+            optimizer,
+        }
+    }
+}
+
+impl<Optimizer_: Optimizer> Model for MlpModel<Optimizer_> {
+    fn train_batch(&mut self, inputs: &Array2<f64>, targets: &Array2<f64>) -> f64 {
+        let mut gradients = Gradients::zeros_like(&self.network);
+        let mut total_loss = 0.0;
+        for (x, y) in inputs.outer_iter().zip(targets.outer_iter()) {
+            let trace = self.network.forward_trace(x);
+            total_loss += self.loss.value(trace.output().view(), y);
+            let output_gradient = self.loss.gradient(trace.output().view(), y);
+            gradients.add(&self.network.backward(&trace, output_gradient));
+        }
+        let n = inputs.nrows().max(1) as f64;
+        gradients.scale(1.0 / n);
+        self.optimizer.step(&mut self.network, &gradients);
+        total_loss / n
+    }
+
+    fn evaluate(&self, inputs: &Array2<f64>, targets: &Array2<f64>) -> f64 {
+        let total: f64 = inputs
+            .outer_iter()
+            .zip(targets.outer_iter())
+            .map(|(x, y)| self.loss.value(self.network.forward(x).view(), y))
+            .sum();
+        total / inputs.nrows().max(1) as f64
+    }
+}
+
+/// Stop when the monitored loss hasn't improved by at least `min_delta` for `patience` epochs.
+/// The monitored loss is the validation loss when there is a validation set and the training
+/// loss otherwise.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EarlyStopping {
+    pub patience: usize,
+    pub min_delta: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrainingConfig {
+    pub epochs: usize,
+    pub batch_size: usize,
+
+    /// Shuffle the examples before every epoch, using `seed`.
+    pub shuffle: bool,
+    pub seed: u64,
+    pub early_stopping: Option<EarlyStopping>,
+}
+
+impl Default for TrainingConfig {
+    fn default() -> Self {
+        Self {
+            epochs: 100,
+            batch_size: 32,
+            shuffle: true,
+            seed: 42,
+            early_stopping: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EpochMetrics {
+    /// Counting from 1.
+    pub epoch: usize,
+
+    /// Mean of the minibatch losses over the epoch.
+    pub training_loss: f64,
+    pub validation_loss: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrainingHistory {
+    pub epochs: Vec<EpochMetrics>,
+    pub stopped_early: bool,
+}
+
+impl TrainingHistory {
+    /// The epoch with the lowest monitored loss.
+    pub fn best_epoch(&self) -> Option<&EpochMetrics> {
+        self.epochs
+            .iter()
+            .min_by(|a, b| monitored(a).total_cmp(&monitored(b)))
+    }
+}
+
+fn monitored(metrics: &EpochMetrics) -> f64 {
+    metrics.validation_loss.unwrap_or(metrics.training_loss)
+}
+
+/// Split `0..len` into minibatches of at most `batch_size` indices, shuffled if `rng` is given.
+pub fn minibatches(len: usize, batch_size: usize, rng: Option<&mut Pcg64>) -> Vec<Vec<usize>> {
+    let mut indices: Vec<usize> = (0..len).collect();
+    if let Some(rng) = rng {
+        indices.shuffle(rng);
+    }
+    indices
+        .chunks(batch_size.max(1))
+        .map(<[usize]>::to_vec)
+        .collect()
+}
+
+/// Minibatch training for `config.epochs` epochs, calling `on_epoch` after each one with that
+/// epoch's metrics.
+pub fn train<Model_: Model>(
+    model: &mut Model_,
+    training: (&Array2<f64>, &Array2<f64>),
+    validation: Option<(&Array2<f64>, &Array2<f64>)>,
+    config: &TrainingConfig,
+    mut on_epoch: impl FnMut(&EpochMetrics),
+) -> TrainingHistory {
+    let (inputs, targets) = training;
+    let mut rng = Pcg64::seed_from_u64(config.seed);
+    let mut history = TrainingHistory {
+        epochs: Vec::with_capacity(config.epochs),
+        stopped_early: false,
+    };
+    let mut best = f64::INFINITY;
+    let mut epochs_without_improvement = 0;
+    for epoch in 1..=config.epochs {
+        let batches = minibatches(
+            inputs.nrows(),
+            config.batch_size,
+            config.shuffle.then_some(&mut rng),
+        );
+        let mut total = 0.0;
+        for batch in &batches {
+            total += model.train_batch(
+                &inputs.select(Axis(0), batch),
+                &targets.select(Axis(0), batch),
+            );
+        }
+        let metrics = EpochMetrics {
+            epoch,
+            training_loss: total / batches.len().max(1) as f64,
+            validation_loss: validation.map(|(x, y)| model.evaluate(x, y)),
+        };
+        on_epoch(&metrics);
+        history.epochs.push(metrics);
+
+        if let Some(stopping) = config.early_stopping {
+            let loss = monitored(&metrics);
+            if loss < best - stopping.min_delta {
+                best = loss;
+                epochs_without_improvement = 0;
+            } else {
+                epochs_without_improvement += 1;
+                if epochs_without_improvement >= stopping.patience {
+                    history.stopped_early = true;
+                    break;
+                }
+            }
+        }
+    }
+    history
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mlp::Activation;
+    use ndarray::array;
+
+    fn xor() -> (Array2<f64>, Array2<f64>) {
+        (
+            array![[0.0, 0.0], [0.0, 1.0], [1.0, 0.0], [1.0, 1.0]],
+            array![[0.0], [1.0], [1.0], [0.0]],
+        )
+    }
+
+    fn xor_network() -> Network {
+        Network::new(&[2, 4, 1], Activation::Tanh, Activation::Sigmoid, 3)
+    }
+
+    #[test]
+    fn test_loss_gradients_match_finite_differences() {
+        let output = array![0.2, 0.7, 0.9];
+        let target = array![0.0, 1.0, 1.0];
+        let h = 1e-6;
+        for loss in [
+            Loss::MeanSquaredError,
+            Loss::CrossEntropy,
+            Loss::SoftmaxCrossEntropy,
+        ] {
+            let gradient = loss.gradient(output.view(), target.view());
+            for i in 0..output.len() {
+                let mut up = output.clone();
+                up[i] += h;
+                let mut down = output.clone();
+                down[i] -= h;
+                let numeric = (loss.value(up.view(), target.view())
+                    - loss.value(down.view(), target.view()))
+                    / (2.0 * h);
+                assert!((numeric - gradient[i]).abs() < 1e-5, "{loss:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_minibatches_cover_every_example_once() {
+        let mut rng = Pcg64::seed_from_u64(1);
+        let batches = minibatches(10, 3, Some(&mut rng));
+        assert_eq!(
+            batches.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![3, 3, 3, 1]
+        );
+        let mut all: Vec<usize> = batches.concat();
+        assert_ne!(all, (0..10).collect::<Vec<_>>());
+        all.sort();
+        assert_eq!(all, (0..10).collect::<Vec<_>>());
+        assert_eq!(minibatches(4, 2, None), vec![vec![0, 1], vec![2, 3]]);
+    }
+
+    #[test]
+    fn test_optimizers_learn_xor() {
+        let (inputs, targets) = xor();
+        let config = TrainingConfig {
+            epochs: 2_000,
+            batch_size: 4,
+            ..TrainingConfig::default()
+        };
+        let mut sgd = MlpModel::new(
+            xor_network(),
+            Loss::CrossEntropy,
+            Sgd::with_momentum(0.5, 0.9),
+        );
+        let mut adam = MlpModel::new(xor_network(), Loss::CrossEntropy, Adam::new(0.05));
+        for loss in [
+            train(&mut sgd, (&inputs, &targets), None, &config, |_| {})
+                .epochs
+                .last()
+                .unwrap()
+                .training_loss,
+            train(&mut adam, (&inputs, &targets), None, &config, |_| {})
+                .epochs
+                .last()
+                .unwrap()
+                .training_loss,
+        ] {
+            assert!(loss < 0.05, "final loss {loss}");
+        }
+        for (x, y) in inputs.outer_iter().zip(targets.outer_iter()) {
+            assert!((adam.network.forward(x)[0] - y[0]).abs() < 0.2);
+        }
+    }
+
+    #[test]
+    fn test_momentum_speeds_up_training() {
+        let (inputs, targets) = xor();
+        let config = TrainingConfig {
+            epochs: 300,
+            batch_size: 4,
+            ..TrainingConfig::default()
+        };
+        let final_loss = |momentum| {
+            let mut model = MlpModel::new(
+                xor_network(),
+                Loss::MeanSquaredError,
+                Sgd::with_momentum(0.2, momentum),
+            );
+            let history = train(&mut model, (&inputs, &targets), None, &config, |_| {});
+            history.epochs.last().unwrap().training_loss
+        };
+        assert!(final_loss(0.9) < final_loss(0.0));
+    }
+
+    #[test]
+    fn test_early_stopping_and_callback() {
+        let (inputs, targets) = xor();
+        // With a learning rate of zero nothing improves after the first epoch.
+        let mut model = MlpModel::new(xor_network(), Loss::MeanSquaredError, Sgd::new(0.0));
+        let config = TrainingConfig {
+            epochs: 100,
+            early_stopping: Some(EarlyStopping {
+                patience: 3,
+                min_delta: 1e-9,
+            }),
+            ..TrainingConfig::default()
+        };
+        let mut seen = vec![];
+        let history = train(
+            &mut model,
+            (&inputs, &targets),
+            Some((&inputs, &targets)),
+            &config,
+            |metrics| seen.push(metrics.epoch),
+        );
+        assert!(history.stopped_early);
+        assert_eq!(seen, vec![1, 2, 3, 4]);
+        assert_eq!(history.best_epoch().unwrap().epoch, 1);
+        assert!(history.epochs[0].validation_loss.is_some());
+    }
+}
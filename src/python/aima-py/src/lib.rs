@@ -0,0 +1,402 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+//! Python bindings for the Connect Four engine and its Monte Carlo tree search, so the
+//! algorithms can be driven from notebooks.
+//!
+//! Build and install into the current virtualenv with `maturin develop --release`, then:
+//!
+//! ```python
+//! from aima_rs import ConnectFour
+//!
+//! game = ConnectFour()
+//! while not game.is_terminal():
+//!     move = game.best_move(iterations=500, seed=42)
+//!     game.apply_move(move.kind, move.column)
+//! print(game, game.winner())
+//! ```
+//!
+//! Moves are `(kind, column)` pairs where kind is `"insert"` or `"pop"` and columns start at 0.
+//! Players are 1 and 2.
+
+use std::sync::{Arc, Mutex};
+
+use connect_four_logic::{Board, Move, MoveType, Player, TerminalPosition};
+use mcts_connect_four::{Action, MctsConfig, State};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rand::SeedableRng;
+
+/// A game of Connect Four with the pop-out rule: a player may insert a piece at the top of a
+/// column, or pop one of their own pieces from the bottom of a column.
+#[pyclass(module = "aima_rs")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectFour {
+    state: State,
+}
+
+/// The move chosen by a search, and what the search cost.
+#[pyclass(module = "aima_rs", frozen, get_all)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BestMove {
+    /// "insert" or "pop".
+    pub kind: String,
+    pub column: usize,
+    pub iterations: monte_carlo_tree_search::Int,
+    /// Nodes in the search tree.
+    pub nodes: usize,
+    pub approximate_tree_bytes: usize,
+}
+
+#[pymethods]
+impl ConnectFour {
+    /// An empty board. Player 1 moves first.
+    #[new]
+    #[pyo3(signature = (width = 7, height = 6))]
+    fn new(width: usize, height: usize) -> PyResult<Self> {
+        if width == 0 || height == 0 {
+            return Err(PyValueError::new_err("width and height must be positive"));
+        }
+        Ok(ConnectFour {
+            state: State::new(
+                width,
+                height,
+                mcts_connect_four::Player::Player1,
+                mcts_connect_four::Player::Player1,
+            ),
+        })
+    }
+
+    /// A position in the notation of `to_notation`, with `turn` (1 or 2) to move. The position
+    /// must be reachable by inserts alone; finished games are allowed.
+    #[staticmethod]
+    fn from_notation(notation: &str, turn: u8) -> PyResult<Self> {
+        let board = Board::from_notation(notation).map_err(value_error)?;
+        let turn = player(turn)?;
+        match connect_four_logic::validate_position(&board, turn.into()) {
+            Ok(()) | Err(connect_four_logic::ConnectFourError::AlreadyTerminal) => {}
+            Err(err) => return Err(value_error(err)),
+        }
+        Ok(ConnectFour {
+            state: State {
+                board,
+                turn,
+                who_am_i: turn,
+            },
+        })
+    }
+
+    /// Rows top to bottom separated by '/', with '.' for an empty cell and '1' or '2' for a
+    /// player's piece.
+    fn to_notation(&self) -> String {
+        self.state.board.to_notation()
+    }
+
+    #[getter]
+    fn width(&self) -> usize {
+        self.state.board.width
+    }
+
+    #[getter]
+    fn height(&self) -> usize {
+        self.state.board.height
+    }
+
+    /// The player to move, 1 or 2.
+    #[getter]
+    fn turn(&self) -> u8 {
+        player_number(self.state.turn.into())
+    }
+
+    /// Legal moves for the player to move, as (kind, column) pairs. Empty once the game is over.
+    fn legal_moves(&self) -> Vec<(&'static str, usize)> {
+        if self.is_terminal() {
+            return vec![];
+        }
+        connect_four_logic::get_legal_moves(&self.state.board, self.state.turn.into())
+            .into_iter()
+            .map(|legal_move| (kind_name(legal_move.move_type), legal_move.column))
+            .collect()
+    }
+
+    /// Play a move for the player to move. Raises ValueError if the move is not legal.
+    fn apply_move(&mut self, kind: &str, column: usize) -> PyResult<()> {
+        let move_type = match kind {
+            "insert" => MoveType::Insert,
+            "pop" => MoveType::Pop,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "kind must be \"insert\" or \"pop\", not {:?}",
+                    kind
+                )))
+            }
+        };
+        if self.is_terminal() {
+            return Err(value_error(
+                connect_four_logic::ConnectFourError::AlreadyTerminal,
+            ));
+        }
+        if column >= self.state.board.width {
+            return Err(PyValueError::new_err(format!(
+                "column {} is off the board",
+                column
+            )));
+        }
+        let board = &self.state.board;
+        match move_type {
+            MoveType::Insert => board.can_insert(column).map(|_| ()),
+            MoveType::Pop => board.can_pop(column, self.state.turn.into()),
+        }
+        .map_err(value_error)?;
+        self.state.apply_move(&Action(Move { move_type, column }));
+        Ok(())
+    }
+
+    fn is_terminal(&self) -> bool {
+        connect_four_logic::is_terminal_position(&self.state.board)
+            != TerminalPosition::IsNotTerminal
+    }
+
+    /// The winning player, or None while the game goes on or if it was drawn.
+    fn winner(&self) -> Option<u8> {
+        match connect_four_logic::is_terminal_position(&self.state.board) {
+            TerminalPosition::IsTerminalWin(winner) => Some(player_number(winner)),
+            TerminalPosition::IsTerminalDraw | TerminalPosition::IsNotTerminal => None,
+        }
+    }
+
+    /// Search for the best move for the player to move with Monte Carlo tree search. Without a
+    /// seed the random playouts differ from call to call.
+    #[pyo3(signature = (
+        iterations = 300,
+        exploration_constant = std::f64::consts::SQRT_2,
+        playouts_per_simulation = 200,
+        max_depth_per_playout = 50,
+        seed = None,
+    ))]
+    fn best_move(
+        &self,
+        py: Python<'_>,
+        iterations: monte_carlo_tree_search::Int,
+        exploration_constant: monte_carlo_tree_search::Float,
+        playouts_per_simulation: monte_carlo_tree_search::Int,
+        max_depth_per_playout: monte_carlo_tree_search::Int,
+        seed: Option<u64>,
+    ) -> PyResult<BestMove> {
+        if self.is_terminal() {
+            return Err(value_error(
+                connect_four_logic::ConnectFourError::AlreadyTerminal,
+            ));
+        }
+        if iterations <= 0 {
+            return Err(PyValueError::new_err("iterations must be positive"));
+        }
+        let config = MctsConfig {
+            iterations,
+            exploration_constant,
+            playouts_per_simulation,
+            max_depth_per_playout,
+            tree_dump_dir: None,
+            debug_track_trees: monte_carlo_tree_search::DebugTrackTrees::None,
+            transpositions: monte_carlo_tree_search::Transpositions::Separate,
+            reward_perspective: monte_carlo_tree_search::RewardPerspective::Alternating,
+        };
+        let state = State {
+            who_am_i: self.state.turn,
+            ..self.state.clone()
+        };
+        // The search doesn't touch Python objects, so let other Python threads run meanwhile.
+        Ok(py.allow_threads(|| search(&state, &config, seed)))
+    }
+
+    fn copy(&self) -> Self {
+        self.clone()
+    }
+
+    fn __str__(&self) -> String {
+        self.state.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ConnectFour.from_notation({:?}, {})",
+            self.to_notation(),
+            self.turn()
+        )
+    }
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+#[pymethods]
+impl BestMove {
+    fn __repr__(&self) -> String {
+        format!(
+            "BestMove(kind={:?}, column={}, iterations={}, nodes={})",
+            self.kind, self.column, self.iterations, self.nodes
+        )
+    }
+}
+
+fn search(state: &State, config: &MctsConfig, seed: Option<u64>) -> BestMove {
+    let rng = match seed {
+        Some(seed) => rand_pcg::Pcg64::seed_from_u64(seed),
+        None => rand_pcg::Pcg64::from_entropy(),
+    };
+    let best = mcts_connect_four::get_best_mcts_move(state, config, Arc::new(Mutex::new(rng)));
+    BestMove {
+        kind: kind_name(best.actual_move.move_type).to_string(),
+        column: best.actual_move.column,
+        iterations: best.stats.iterations,
+        nodes: best.stats.nodes,
+        approximate_tree_bytes: best.stats.approximate_tree_bytes,
+    }
+}
+
+fn kind_name(move_type: MoveType) -> &'static str {
+    match move_type {
+        MoveType::Insert => "insert",
+        MoveType::Pop => "pop",
+    }
+}
+
+fn player(number: u8) -> PyResult<mcts_connect_four::Player> {
+    match number {
+        1 => Ok(mcts_connect_four::Player::Player1),
+        2 => Ok(mcts_connect_four::Player::Player2),
+        _ => Err(PyValueError::new_err(format!(
+            "player must be 1 or 2, not {}",
+            number
+        ))),
+    }
+}
+
+fn player_number(player: Player) -> u8 {
+    match player {
+        Player::Player1 => 1,
+        Player::Player2 => 2,
+    }
+}
+
+fn value_error(err: connect_four_logic::ConnectFourError) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+/// The `aima_rs` Python module.
+#[pymodule]
+fn aima_rs(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<ConnectFour>()?;
+    module.add_class::<BestMove>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run `test` holding the GIL, which Python exceptions need.
+    fn with_python(test: impl FnOnce()) {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|_| test());
+    }
+
+    #[test]
+    fn test_new_game() {
+        with_python(|| {
+            let game = ConnectFour::new(7, 6).unwrap();
+            assert_eq!((game.width(), game.height(), game.turn()), (7, 6, 1));
+            assert_eq!(game.legal_moves().len(), 7);
+            assert!(!game.is_terminal());
+            assert_eq!(game.winner(), None);
+            assert!(ConnectFour::new(0, 6).is_err());
+        });
+    }
+
+    #[test]
+    fn test_apply_move() {
+        with_python(|| {
+            let mut game = ConnectFour::new(7, 6).unwrap();
+            game.apply_move("insert", 3).unwrap();
+            assert_eq!(game.turn(), 2);
+            assert_eq!(game.to_notation().split('/').next_back(), Some("...1..."));
+            // Player 2 can't pop player 1's piece, nor play off the board or an unknown kind.
+            assert!(game.apply_move("pop", 3).is_err());
+            assert!(game.apply_move("insert", 7).is_err());
+            assert!(game.apply_move("drop", 0).is_err());
+            assert_eq!(game.turn(), 2);
+            game.apply_move("insert", 3).unwrap();
+            assert!(game.legal_moves().contains(&("pop", 3)));
+        });
+    }
+
+    #[test]
+    fn test_from_notation_round_trip() {
+        with_python(|| {
+            let game = ConnectFour::from_notation(".../.../1..", 2).unwrap();
+            assert_eq!(game.to_notation(), ".../.../1..");
+            assert_eq!(game.turn(), 2);
+            assert_eq!(
+                game.__repr__(),
+                "ConnectFour.from_notation(\".../.../1..\", 2)"
+            );
+            assert!(ConnectFour::from_notation(".../.../1..", 3).is_err());
+            // Player 1 has moved, so it can't be their turn again.
+            assert!(ConnectFour::from_notation(".../.../1..", 1).is_err());
+        });
+    }
+
+    #[test]
+    fn test_winner() {
+        with_python(|| {
+            let game =
+                ConnectFour::from_notation("......./......./1....../1..2.../1..2.../1..2...", 2)
+                    .unwrap();
+            assert!(game.is_terminal());
+            assert_eq!(game.winner(), Some(1));
+            assert!(game.legal_moves().is_empty());
+        });
+    }
+
+    // Player 1 has three in a row along the bottom, so player 2 must block at column 3.
+    #[test]
+    fn test_search_blocks_a_win() {
+        with_python(|| {
+            let game =
+                ConnectFour::from_notation("......./......./......./......./2.2..../111....", 2)
+                    .unwrap();
+            let config = MctsConfig {
+                iterations: 300,
+                exploration_constant: std::f64::consts::SQRT_2,
+                playouts_per_simulation: 30,
+                max_depth_per_playout: 50,
+                tree_dump_dir: None,
+                debug_track_trees: monte_carlo_tree_search::DebugTrackTrees::None,
+                transpositions: monte_carlo_tree_search::Transpositions::Separate,
+                reward_perspective: monte_carlo_tree_search::RewardPerspective::Alternating,
+            };
+            let state = State {
+                who_am_i: game.state.turn,
+                ..game.state.clone()
+            };
+            let best = search(&state, &config, Some(42));
+            assert_eq!((best.kind.as_str(), best.column), ("insert", 3));
+            assert_eq!(best.iterations, 300);
+            assert!(best.nodes > 1);
+        });
+    }
+}
@@ -0,0 +1,286 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+//! Connect Four bindings.
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use monte_carlo_tree_search::MctsNodeForSerialization;
+
+use crate::session::{GameSession, SearchConfig, Turn, WasmGame};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MoveType {
+    Insert,
+    Pop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Move {
+    pub move_type: MoveType,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MoveResponse {
+    pub actual_move: Move,
+    pub maybe_insert_row: Option<usize>,
+    pub debug_trees: Vec<
+        monte_carlo_tree_search::MctsNodeForSerialization<
+            mcts_connect_four::State,
+            mcts_connect_four::Action,
+        >,
+    >,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ApplyMoveRequest {
+    pub move_type: MoveType,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LegalMove {
+    pub move_type: MoveType,
+    pub row: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CellValue {
+    Empty,
+    Player1,
+    Player2,
+}
+
+/// PositionSpec is how the front-end describes a position set up in the board editor. Either
+/// the notation accepted by connect_four_logic::Board::from_notation, or rows of cells from top
+/// to bottom.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PositionSpec {
+    Notation(String),
+    Cells(Vec<Vec<CellValue>>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SetPositionRequest {
+    pub position: PositionSpec,
+    pub turn: Turn,
+}
+
+impl PositionSpec {
+    fn to_notation(&self) -> String {
+        match self {
+            PositionSpec::Notation(notation) => notation.clone(),
+            PositionSpec::Cells(rows) => rows
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|cell| match cell {
+                            CellValue::Empty => '.',
+                            CellValue::Player1 => '1',
+                            CellValue::Player2 => '2',
+                        })
+                        .collect::<String>()
+                })
+                .collect::<Vec<String>>()
+                .join("/"),
+        }
+    }
+}
+
+/// ConnectFour presents mcts_connect_four to the front-end. Moves are described by the cell they
+/// affect so the front-end can animate them.
+pub struct ConnectFour;
+
+impl WasmGame for ConnectFour {
+    type Action = mcts_connect_four::Action;
+    type State = mcts_connect_four::State;
+    type MoveRequest = ApplyMoveRequest;
+    type MoveResponse = MoveResponse;
+    type LegalMove = LegalMove;
+
+    fn turn(state: &Self::State) -> Turn {
+        match state.turn {
+            mcts_connect_four::Player::Player1 => Turn::Player1,
+            mcts_connect_four::Player::Player2 => Turn::Player2,
+        }
+    }
+
+    fn is_cpu_turn(state: &Self::State) -> bool {
+        state.turn == state.who_am_i
+    }
+
+    fn action_from_request(request: Self::MoveRequest) -> Self::Action {
+        let move_type = match request.move_type {
+            MoveType::Insert => connect_four_logic::MoveType::Insert,
+            MoveType::Pop => connect_four_logic::MoveType::Pop,
+        };
+        mcts_connect_four::Action(connect_four_logic::Move {
+            move_type,
+            column: request.column,
+        })
+    }
+
+    /// For an insert the cell will be the first empty cell top-down in a column. For a pop it will
+    /// be the bottom of the column.
+    fn legal_move(state: &Self::State, action: &Self::Action) -> Self::LegalMove {
+        let column = action.0.column;
+        match action.0.move_type {
+            connect_four_logic::MoveType::Insert => LegalMove {
+                move_type: MoveType::Insert,
+                row: state.board.can_insert(column).unwrap(),
+                column,
+            },
+            connect_four_logic::MoveType::Pop => LegalMove {
+                move_type: MoveType::Pop,
+                row: state.board.height - 1,
+                column,
+            },
+        }
+    }
+
+    // If this is an insert, then use can_insert to get the row.
+    fn move_response(
+        state: &Self::State,
+        action: &Self::Action,
+        debug_trees: Vec<MctsNodeForSerialization<Self::State, Self::Action>>,
+    ) -> Self::MoveResponse {
+        let column = action.0.column;
+        match action.0.move_type {
+            connect_four_logic::MoveType::Insert => MoveResponse {
+                actual_move: Move {
+                    move_type: MoveType::Insert,
+                    column,
+                },
+                maybe_insert_row: Some(state.board.can_insert(column).unwrap()),
+                debug_trees,
+            },
+            connect_four_logic::MoveType::Pop => MoveResponse {
+                actual_move: Move {
+                    move_type: MoveType::Pop,
+                    column,
+                },
+                maybe_insert_row: None,
+                debug_trees,
+            },
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub struct GameWrapper {
+    session: GameSession<ConnectFour>,
+}
+
+#[wasm_bindgen]
+impl GameWrapper {
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: usize, height: usize, cpu_is_first: bool) -> Self {
+        let (turn, who_am_i) = if cpu_is_first {
+            (
+                mcts_connect_four::Player::Player1,
+                mcts_connect_four::Player::Player1,
+            )
+        } else {
+            (
+                mcts_connect_four::Player::Player1,
+                mcts_connect_four::Player::Player2,
+            )
+        };
+
+        let state = mcts_connect_four::State::new(width, height, turn, who_am_i);
+        let mcts_config = mcts_connect_four::MctsConfig::default();
+        let config = SearchConfig {
+            iterations: mcts_config.iterations,
+            exploration_constant: mcts_config.exploration_constant,
+            playouts_per_simulation: mcts_config.playouts_per_simulation,
+            max_depth_per_playout: mcts_config.max_depth_per_playout,
+        };
+        Self {
+            session: GameSession::new(state, config),
+        }
+    }
+
+    /// turn is the player whose turn it is to make a move and whether they are a human or a CPU.
+    /// e.g. "Player 1 (human).
+    pub fn turn(&self) -> JsValue {
+        self.session.turn()
+    }
+
+    pub fn width(&self) -> usize {
+        self.session.state.board.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.session.state.board.height
+    }
+
+    pub fn get_mcts_best_move(&mut self) -> Result<JsValue, JsValue> {
+        self.session.get_mcts_best_move()
+    }
+
+    pub fn apply_move(&mut self, apply_move_request: JsValue) -> Result<JsValue, JsValue> {
+        self.session.apply_move(apply_move_request)
+    }
+
+    /// search_stats returns statistics for the most recent get_mcts_best_move call, or null if
+    /// there has not been a search yet.
+    pub fn search_stats(&self) -> JsValue {
+        self.session.search_stats()
+    }
+
+    /// set_position replaces the current position with one set up in the board editor, e.g. for
+    /// puzzle mode. The position must have the same dimensions as the current board and must be
+    /// consistent (no floating pieces, piece counts match the turn, not already terminal). On
+    /// success returns the new state, the same as apply_move.
+    pub fn set_position(&mut self, set_position_request: JsValue) -> Result<JsValue, JsValue> {
+        let set_position_request: SetPositionRequest =
+            serde_wasm_bindgen::from_value(set_position_request)?;
+        let board =
+            connect_four_logic::Board::from_notation(&set_position_request.position.to_notation())
+                .map_err(|e| serde_wasm_bindgen::to_value(&e.to_string()).unwrap())?;
+        if board.width != self.width() || board.height != self.height() {
+            return Err(serde_wasm_bindgen::to_value(&format!(
+                "expected a {}x{} board, got {}x{}",
+                self.width(),
+                self.height(),
+                board.width,
+                board.height
+            ))
+            .unwrap());
+        }
+        let turn = match set_position_request.turn {
+            Turn::Player1 => mcts_connect_four::Player::Player1,
+            Turn::Player2 => mcts_connect_four::Player::Player2,
+        };
+        connect_four_logic::validate_position(&board, turn.into())
+            .map_err(|e| serde_wasm_bindgen::to_value(&e.to_string()).unwrap())?;
+
+        self.session.state.board = board;
+        self.session.state.turn = turn;
+        let result = serde_wasm_bindgen::to_value(&self.session.state).unwrap();
+        Ok(result)
+    }
+
+    /// get_legal_moves_cells will return cells on which the current player can move. For
+    /// an insert the cell will be the first empty cell top-down in a column. For a pop it will
+    /// be the bottom of the column.
+    pub fn get_legal_moves_cells(&self) -> JsValue {
+        self.session.get_legal_moves_cells()
+    }
+}
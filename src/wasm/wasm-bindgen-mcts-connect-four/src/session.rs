@@ -0,0 +1,209 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+//! Game-agnostic part of the WASM bindings. wasm_bindgen cannot export generic structs, so each
+//! game has a small #[wasm_bindgen] wrapper that owns a GameSession and forwards to it.
+
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use monte_carlo_tree_search::{shared_rng, ActionStat, MctsNodeForSerialization, SharedRng, State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Turn {
+    Player1,
+    Player2,
+}
+
+/// SearchStatsResponse describes the most recent MCTS search so the web app can adapt its search
+/// budget to the user's device and show diagnostics.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchStatsResponse<_Action> {
+    pub iterations: i32,
+    pub elapsed_ms: f64,
+    pub iterations_per_second: f64,
+    pub nodes: usize,
+    pub approximate_heap_bytes: usize,
+    pub max_depth: usize,
+    pub principal_variation: Vec<_Action>,
+
+    // Every move the CPU considered, most visited first, for showing how good each one looks.
+    pub root_action_stats: Vec<ActionStat<_Action>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LegalMoveResponse<_LegalMove> {
+    pub moves: Vec<_LegalMove>,
+}
+
+/// SearchConfig is the search budget used for every CPU move.
+#[derive(Debug, Clone, Copy)]
+pub struct SearchConfig {
+    pub iterations: monte_carlo_tree_search::Int,
+    pub exploration_constant: monte_carlo_tree_search::Float,
+    pub playouts_per_simulation: monte_carlo_tree_search::Int,
+    pub max_depth_per_playout: monte_carlo_tree_search::Int,
+}
+
+/// WasmGame describes how a game's MCTS State and Action are presented to the front-end. Adding a
+/// game to the website means implementing this and writing a thin #[wasm_bindgen] wrapper around
+/// GameSession.
+pub trait WasmGame {
+    type Action: monte_carlo_tree_search::Action;
+    type State: monte_carlo_tree_search::State<Self::Action>;
+
+    /// What the front-end sends to apply_move.
+    type MoveRequest: DeserializeOwned;
+
+    /// What get_mcts_best_move returns to the front-end.
+    type MoveResponse: Serialize;
+
+    /// One entry in the list returned by get_legal_moves_cells.
+    type LegalMove: Serialize;
+
+    fn turn(state: &Self::State) -> Turn;
+    fn is_cpu_turn(state: &Self::State) -> bool;
+    fn action_from_request(request: Self::MoveRequest) -> Self::Action;
+    fn legal_move(state: &Self::State, action: &Self::Action) -> Self::LegalMove;
+    fn move_response(
+        state: &Self::State,
+        action: &Self::Action,
+        debug_trees: Vec<MctsNodeForSerialization<Self::State, Self::Action>>,
+    ) -> Self::MoveResponse;
+}
+
+/// GameSession holds a game in progress and runs MCTS for the CPU player. The search tree is
+/// kept between moves, so each CPU move starts from the statistics of earlier searches.
+pub struct GameSession<_Game: WasmGame> {
+    pub state: _Game::State,
+    config: SearchConfig,
+    rng: SharedRng,
+    last_search_stats: Option<SearchStatsResponse<_Game::Action>>,
+    mcts: Option<monte_carlo_tree_search::Mcts<_Game::State, _Game::Action>>,
+}
+
+impl<_Game: WasmGame> GameSession<_Game> {
+    pub fn new(state: _Game::State, config: SearchConfig) -> Self {
+        console_error_panic_hook::set_once();
+
+        Self {
+            state,
+            config,
+            rng: shared_rng(42),
+            last_search_stats: None,
+            mcts: None,
+        }
+    }
+
+    pub fn turn(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&_Game::turn(&self.state)).unwrap()
+    }
+
+    pub fn get_mcts_best_move(&mut self) -> Result<JsValue, JsValue> {
+        if !_Game::is_cpu_turn(&self.state) {
+            return Err(serde_wasm_bindgen::to_value("Not CPU's turn").unwrap());
+        }
+
+        // std::time::Instant is not available on wasm32-unknown-unknown, so use the JS clock.
+        let start_ms = js_sys::Date::now();
+        // The state can also be set directly, so only reuse a tree rooted at the current state.
+        let mcts = match self.mcts.take() {
+            Some(mcts) if mcts.root_state() == &self.state => mcts,
+            _ => self.new_mcts(),
+        };
+        let mcts = self.mcts.insert(mcts);
+        mcts.run();
+        let action = mcts
+            .best_action()
+            .ok_or_else(|| serde_wasm_bindgen::to_value("No legal moves").unwrap())?;
+        // SearchInfo can't time the search here, so the rate comes from the JS clock too.
+        let elapsed_ms = js_sys::Date::now() - start_ms;
+        let info = mcts.search_info();
+        self.last_search_stats = Some(SearchStatsResponse {
+            iterations: info.iterations,
+            elapsed_ms,
+            iterations_per_second: if elapsed_ms > 0.0 {
+                f64::from(info.iterations) * 1000.0 / elapsed_ms
+            } else {
+                0.0
+            },
+            nodes: info.nodes,
+            approximate_heap_bytes: mcts.approximate_tree_bytes(),
+            max_depth: info.max_depth,
+            principal_variation: info.principal_variation,
+            root_action_stats: mcts.root_action_stats(),
+        });
+
+        let response =
+            _Game::move_response(&self.state, &action, mcts.debug_trees().unwrap_or_default());
+        Ok(serde_wasm_bindgen::to_value(&response).unwrap())
+    }
+
+    fn new_mcts(&self) -> monte_carlo_tree_search::Mcts<_Game::State, _Game::Action> {
+        monte_carlo_tree_search::Mcts::new(
+            self.state.clone(),
+            monte_carlo_tree_search::MctsArgs {
+                iteration_limit: monte_carlo_tree_search::IterationLimitKind::Iterations(
+                    self.config.iterations,
+                ),
+                exploration_constant: self.config.exploration_constant,
+                playouts_per_simulation: self.config.playouts_per_simulation,
+                max_depth_per_playout: self.config.max_depth_per_playout,
+                rng: Arc::clone(&self.rng),
+                debug_track_trees: monte_carlo_tree_search::DebugTrackTrees::Track,
+                reward_perspective: monte_carlo_tree_search::RewardPerspective::Alternating,
+                solver: monte_carlo_tree_search::Solver::Enabled,
+                ..monte_carlo_tree_search::MctsArgs::default()
+            },
+        )
+    }
+
+    pub fn apply_move(&mut self, apply_move_request: JsValue) -> Result<JsValue, JsValue> {
+        let apply_move_request: _Game::MoveRequest =
+            serde_wasm_bindgen::from_value(apply_move_request)?;
+        let action = _Game::action_from_request(apply_move_request);
+        if !self.state.get_actions().contains(&action) {
+            return Err(serde_wasm_bindgen::to_value("Illegal move").unwrap());
+        }
+        self.state = self.state.get_next_state(&action);
+        match self.mcts.as_mut() {
+            Some(mcts) if mcts.root_state().get_actions().contains(&action) => {
+                mcts.advance_root(action)
+            }
+            _ => self.mcts = None,
+        }
+        let result = serde_wasm_bindgen::to_value(&self.state).unwrap();
+        Ok(result)
+    }
+
+    pub fn get_legal_moves_cells(&self) -> JsValue {
+        let moves = self
+            .state
+            .get_actions()
+            .iter()
+            .map(|action| _Game::legal_move(&self.state, action))
+            .collect();
+        let result = LegalMoveResponse { moves };
+        serde_wasm_bindgen::to_value(&result).unwrap()
+    }
+
+    pub fn search_stats(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.last_search_stats).unwrap()
+    }
+}
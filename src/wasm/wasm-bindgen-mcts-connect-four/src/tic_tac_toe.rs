@@ -0,0 +1,141 @@
+/*
+ * Copyright (C) 2023 Asim Ihsan
+ * SPDX-License-Identifier: AGPL-3.0-only
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU Affero General Public License as published by the Free
+ * Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+ * PARTICULAR PURPOSE. See the GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License along
+ * with this program. If not, see <https://www.gnu.org/licenses/>
+ */
+
+//! Tic-Tac-Toe bindings.
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use monte_carlo_tree_search::MctsNodeForSerialization;
+
+use crate::session::{GameSession, SearchConfig, Turn, WasmGame};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Cell {
+    pub row: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MoveResponse {
+    pub actual_move: Cell,
+    pub debug_trees:
+        Vec<MctsNodeForSerialization<mcts_tic_tac_toe::State, mcts_tic_tac_toe::Action>>,
+}
+
+/// TicTacToe presents mcts_tic_tac_toe to the front-end. Every move is described by the cell it
+/// places a piece in.
+pub struct TicTacToe;
+
+impl WasmGame for TicTacToe {
+    type Action = mcts_tic_tac_toe::Action;
+    type State = mcts_tic_tac_toe::State;
+    type MoveRequest = Cell;
+    type MoveResponse = MoveResponse;
+    type LegalMove = Cell;
+
+    fn turn(state: &Self::State) -> Turn {
+        match state.turn {
+            mcts_tic_tac_toe::Player::Player1 => Turn::Player1,
+            mcts_tic_tac_toe::Player::Player2 => Turn::Player2,
+        }
+    }
+
+    fn is_cpu_turn(state: &Self::State) -> bool {
+        state.turn == state.who_am_i
+    }
+
+    fn action_from_request(request: Self::MoveRequest) -> Self::Action {
+        mcts_tic_tac_toe::Action(tic_tac_toe_logic::Move {
+            column: request.column,
+            row: request.row,
+        })
+    }
+
+    fn legal_move(_state: &Self::State, action: &Self::Action) -> Self::LegalMove {
+        Cell {
+            row: action.0.row,
+            column: action.0.column,
+        }
+    }
+
+    fn move_response(
+        _state: &Self::State,
+        action: &Self::Action,
+        debug_trees: Vec<MctsNodeForSerialization<Self::State, Self::Action>>,
+    ) -> Self::MoveResponse {
+        MoveResponse {
+            actual_move: Cell {
+                row: action.0.row,
+                column: action.0.column,
+            },
+            debug_trees,
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub struct TicTacToeWrapper {
+    session: GameSession<TicTacToe>,
+}
+
+#[wasm_bindgen]
+impl TicTacToeWrapper {
+    #[wasm_bindgen(constructor)]
+    pub fn new(cpu_is_first: bool) -> Self {
+        let who_am_i = if cpu_is_first {
+            mcts_tic_tac_toe::Player::Player1
+        } else {
+            mcts_tic_tac_toe::Player::Player2
+        };
+        let state = mcts_tic_tac_toe::State::new(mcts_tic_tac_toe::Player::Player1, who_am_i);
+
+        // The whole game tree has fewer than 550,000 nodes, so a small budget plays well.
+        let config = SearchConfig {
+            iterations: 1000,
+            exploration_constant: std::f64::consts::SQRT_2,
+            playouts_per_simulation: 20,
+            max_depth_per_playout: 9,
+        };
+        Self {
+            session: GameSession::new(state, config),
+        }
+    }
+
+    /// turn is the player whose turn it is to make a move.
+    pub fn turn(&self) -> JsValue {
+        self.session.turn()
+    }
+
+    pub fn get_mcts_best_move(&mut self) -> Result<JsValue, JsValue> {
+        self.session.get_mcts_best_move()
+    }
+
+    pub fn apply_move(&mut self, apply_move_request: JsValue) -> Result<JsValue, JsValue> {
+        self.session.apply_move(apply_move_request)
+    }
+
+    /// search_stats returns statistics for the most recent get_mcts_best_move call, or null if
+    /// there has not been a search yet.
+    pub fn search_stats(&self) -> JsValue {
+        self.session.search_stats()
+    }
+
+    /// get_legal_moves_cells will return the empty cells the current player can place a piece in.
+    pub fn get_legal_moves_cells(&self) -> JsValue {
+        self.session.get_legal_moves_cells()
+    }
+}
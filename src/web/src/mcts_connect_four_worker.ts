@@ -29,6 +29,16 @@ import('./pkg_mcts_connect_four/mcts_connect_four').then((mctsConnectFour) => {
         } else if (data.type === 'getLegalMovesCells') {
             const legalMoves = gameWrapper.get_legal_moves_cells();
             postMessage(legalMoves);
+        } else if (data.type === 'searchStats') {
+            const stats = gameWrapper.search_stats();
+            postMessage(stats);
+        } else if (data.type === 'setPosition') {
+            try {
+                const result = gameWrapper.set_position(data.request);
+                postMessage(result);
+            } catch (error) {
+                postMessage({ type: 'setPositionError', error });
+            }
         } else if (data.type === 'turn') {
             const turn = gameWrapper.turn();
             postMessage(turn);